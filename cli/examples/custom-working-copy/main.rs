@@ -26,13 +26,13 @@ use jj_lib::git_backend::GitBackend;
 use jj_lib::local_working_copy::LocalWorkingCopy;
 use jj_lib::op_store::{OperationId, WorkspaceId};
 use jj_lib::repo::ReadonlyRepo;
-use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::settings::UserSettings;
 use jj_lib::signing::Signer;
+use jj_lib::sparse::SparsePattern;
 use jj_lib::store::Store;
 use jj_lib::working_copy::{
-    CheckoutError, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError, SnapshotOptions,
-    WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
+    CheckoutError, CheckoutOptions, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError,
+    SnapshotOptions, WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
 };
 use jj_lib::workspace::{default_working_copy_factories, Workspace, WorkspaceInitError};
 
@@ -153,10 +153,14 @@ impl WorkingCopy for ConflictsWorkingCopy {
         self.inner.tree_id()
     }
 
-    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError> {
+    fn sparse_patterns(&self) -> Result<&[SparsePattern], WorkingCopyStateError> {
         self.inner.sparse_patterns()
     }
 
+    fn sparse_profile(&self) -> Result<Option<&str>, WorkingCopyStateError> {
+        self.inner.sparse_profile()
+    }
+
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
         let inner = self.inner.start_mutation()?;
         Ok(Box::new(LockedConflictsWorkingCopy {
@@ -227,14 +231,18 @@ impl LockedWorkingCopy for LockedConflictsWorkingCopy {
         self.inner.snapshot(options)
     }
 
-    fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError> {
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
         let conflicts = commit
             .tree()?
             .conflicts()
             .map(|(path, _value)| format!("{}\n", path.as_internal_file_string()))
             .join("");
         std::fs::write(self.wc_path.join(".conflicts"), conflicts).unwrap();
-        self.inner.check_out(commit)
+        self.inner.check_out(commit, options)
     }
 
     fn reset(&mut self, commit: &Commit) -> Result<(), ResetError> {
@@ -245,15 +253,28 @@ impl LockedWorkingCopy for LockedConflictsWorkingCopy {
         self.inner.recover(commit)
     }
 
-    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError> {
+    fn sparse_patterns(&self) -> Result<&[SparsePattern], WorkingCopyStateError> {
         self.inner.sparse_patterns()
     }
 
+    fn sparse_profile(&self) -> Result<Option<&str>, WorkingCopyStateError> {
+        self.inner.sparse_profile()
+    }
+
     fn set_sparse_patterns(
         &mut self,
-        new_sparse_patterns: Vec<RepoPathBuf>,
+        new_sparse_patterns: Vec<SparsePattern>,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        self.inner.set_sparse_patterns(new_sparse_patterns, options)
+    }
+
+    fn set_sparse_profile(
+        &mut self,
+        name: &str,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
-        self.inner.set_sparse_patterns(new_sparse_patterns)
+        self.inner.set_sparse_profile(name, options)
     }
 
     fn finish(
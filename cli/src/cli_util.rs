@@ -24,8 +24,8 @@ use std::process::ExitCode;
 use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::SystemTime;
-use std::{fs, str};
+use std::time::{Duration, SystemTime};
+use std::{error, fs, iter, str};
 
 use clap::builder::{
     MapValueParser, NonEmptyStringValueParser, TypedValueParser, ValueParserFactory,
@@ -36,12 +36,13 @@ use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use jj_lib::backend::{ChangeId, CommitId, MergedTreeId, TreeValue};
 use jj_lib::commit::Commit;
+use jj_lib::conflicts::ConflictMarkerOptions;
 use jj_lib::fileset::{FilePattern, FilesetExpression, FilesetParseContext};
 use jj_lib::git_backend::GitBackend;
 use jj_lib::gitignore::{GitIgnoreError, GitIgnoreFile};
 use jj_lib::hex_util::to_reverse_hex;
 use jj_lib::id_prefix::IdPrefixContext;
-use jj_lib::matchers::Matcher;
+use jj_lib::matchers::{EverythingMatcher, Matcher};
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::object_id::ObjectId;
@@ -50,7 +51,7 @@ use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::operation::Operation;
 use jj_lib::repo::{
     CheckOutCommitError, EditCommitError, MutableRepo, ReadonlyRepo, Repo, RepoLoader,
-    StoreFactories, StoreLoadError,
+    RepoLoaderError, StoreFactories, StoreLoadError, ViewConflict,
 };
 use jj_lib::repo_path::{FsPathParseError, RepoPath, RepoPathBuf};
 use jj_lib::revset::{
@@ -64,7 +65,8 @@ use jj_lib::str_util::StringPattern;
 use jj_lib::transaction::Transaction;
 use jj_lib::view::View;
 use jj_lib::working_copy::{
-    CheckoutStats, LockedWorkingCopy, SnapshotOptions, WorkingCopy, WorkingCopyFactory,
+    CheckoutOptions, CheckoutStats, LockedWorkingCopy, SnapshotOptions, WorkingCopy,
+    WorkingCopyFactory,
 };
 use jj_lib::workspace::{
     default_working_copy_factories, LockedWorkspace, Workspace, WorkspaceLoadError, WorkspaceLoader,
@@ -76,24 +78,29 @@ use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::prelude::*;
 
 use crate::command_error::{
-    cli_error, config_error_with_message, handle_command_result, internal_error,
+    cli_error, config_error, config_error_with_message, handle_command_result, internal_error,
     internal_error_with_message, user_error, user_error_with_hint, user_error_with_message,
     CommandError,
 };
-use crate::commit_templater::{CommitTemplateLanguage, CommitTemplateLanguageExtension};
+use crate::commit_templater::{
+    CommitTemplateLanguage, CommitTemplateLanguageExtension, DiffEditContext,
+};
 use crate::config::{
-    new_config_path, AnnotatedValue, CommandNameAndArgs, ConfigSource, LayeredConfigs,
+    config_toml_for_value, default_config, new_config_path, parse_config_value,
+    workspace_config_path, AnnotatedValue, CommandNameAndArgs, ConfigSource, LayeredConfigs,
 };
 use crate::formatter::{FormatRecorder, Formatter, PlainTextFormatter};
 use crate::git_util::{
     is_colocated_git_workspace, print_failed_git_export, print_git_import_stats,
 };
-use crate::merge_tools::{DiffEditor, MergeEditor, MergeToolConfigError};
+use crate::hooks::{self, HookKind, TransactionHookInput};
+use crate::merge_tools::{DiffEditor, MergeDriverResolution, MergeEditor, MergeToolConfigError};
 use crate::operation_templater::OperationTemplateLanguageExtension;
 use crate::revset_util::RevsetExpressionEvaluator;
 use crate::template_builder::TemplateLanguage;
 use crate::template_parser::TemplateAliasesMap;
 use crate::templater::{PropertyPlaceholder, TemplateRenderer};
+use crate::text_editor::{edit_file_builtin, BUILTIN_EDITOR_NAME};
 use crate::ui::{ColorChoice, Ui};
 use crate::{revset_util, template_builder, text_util};
 
@@ -195,8 +202,8 @@ pub struct CommandHelper {
     commit_template_extension: Option<Arc<dyn CommitTemplateLanguageExtension>>,
     operation_template_extension: Option<Arc<dyn OperationTemplateLanguageExtension>>,
     maybe_workspace_loader: Result<WorkspaceLoader, CommandError>,
-    store_factories: StoreFactories,
-    working_copy_factories: HashMap<String, Box<dyn WorkingCopyFactory>>,
+    store_factories: Rc<StoreFactories>,
+    working_copy_factories: Rc<HashMap<String, Box<dyn WorkingCopyFactory>>>,
 }
 
 impl CommandHelper {
@@ -276,6 +283,24 @@ impl CommandHelper {
     #[instrument(skip(self, ui))]
     pub fn workspace_helper(&self, ui: &mut Ui) -> Result<WorkspaceCommandHelper, CommandError> {
         let mut workspace_command = self.workspace_helper_no_snapshot(ui)?;
+        let auto_update_stale = self
+            .settings
+            .config()
+            .get_bool("workspace.auto-update-stale")
+            .optional()?
+            .unwrap_or(false);
+        if auto_update_stale
+            && workspace_command.may_update_working_copy
+            && workspace_command.is_working_copy_stale()?
+        {
+            writeln!(
+                ui.status(),
+                "The working copy is stale (not updated since operation {}). Updating it.",
+                short_operation_hash(workspace_command.working_copy().operation_id())
+            )?;
+            crate::commands::update_stale_working_copy(ui, self)?;
+            workspace_command = self.workspace_helper_no_snapshot(ui)?;
+        }
         workspace_command.maybe_snapshot(ui)?;
         Ok(workspace_command)
     }
@@ -336,8 +361,9 @@ impl CommandHelper {
                     // TODO: It may be helpful to print each operation we're merging here
                     let mut tx =
                         start_repo_transaction(&base_repo, &self.settings, &self.string_args);
+                    let mut view_conflicts = vec![];
                     for other_op_head in op_heads.into_iter().skip(1) {
-                        tx.merge_operation(other_op_head)?;
+                        view_conflicts.extend(tx.merge_operation(other_op_head)?);
                         let num_rebased = tx.mut_repo().rebase_descendants(&self.settings)?;
                         if num_rebased > 0 {
                             writeln!(
@@ -347,6 +373,12 @@ impl CommandHelper {
                             )?;
                         }
                     }
+                    view_conflicts.retain(|conflict| conflict.is_present(tx.repo().view()));
+                    print_view_conflicts(
+                        ui,
+                        "Concurrent operations left conflicts in:",
+                        &view_conflicts,
+                    )?;
                     Ok(tx
                         .write("resolve concurrent operations")
                         .leave_unpublished()
@@ -406,8 +438,10 @@ pub struct WorkspaceCommandHelper {
     commit_template_extension: Option<Arc<dyn CommitTemplateLanguageExtension>>,
     revset_aliases_map: RevsetAliasesMap,
     template_aliases_map: TemplateAliasesMap,
+    conflict_marker_options: ConflictMarkerOptions,
     may_update_working_copy: bool,
     working_copy_shared_with_git: bool,
+    git_sync_mode: GitSyncMode,
 }
 
 impl WorkspaceCommandHelper {
@@ -423,9 +457,15 @@ impl WorkspaceCommandHelper {
             settings.config().get_string("templates.commit_summary")?;
         let revset_aliases_map = revset_util::load_revset_aliases(ui, &command.layered_configs)?;
         let template_aliases_map = command.load_template_aliases(ui)?;
+        let conflict_marker_options = settings.conflict_marker_options()?;
         let loaded_at_head = command.global_args.at_operation == "@";
         let may_update_working_copy = loaded_at_head && !command.global_args.ignore_working_copy;
         let working_copy_shared_with_git = is_colocated_git_workspace(&workspace, &repo);
+        let git_sync_mode = if working_copy_shared_with_git {
+            git_sync_mode_setting(settings.config())?
+        } else {
+            GitSyncMode::default()
+        };
         let helper = Self {
             cwd: command.cwd.clone(),
             string_args: command.string_args.clone(),
@@ -437,8 +477,10 @@ impl WorkspaceCommandHelper {
             commit_template_extension: command.commit_template_extension.clone(),
             revset_aliases_map,
             template_aliases_map,
+            conflict_marker_options,
             may_update_working_copy,
             working_copy_shared_with_git,
+            git_sync_mode,
         };
         // Parse commit_summary template (and short-prefixes revset) early to
         // report error before starting mutable operation.
@@ -450,6 +492,24 @@ impl WorkspaceCommandHelper {
         self.user_repo.git_backend()
     }
 
+    /// How conflicts are materialized in and parsed from files.
+    pub fn conflict_marker_options(&self) -> ConflictMarkerOptions {
+        self.conflict_marker_options
+    }
+
+    pub fn checkout_options(&self) -> CheckoutOptions<'static> {
+        CheckoutOptions {
+            conflict_marker_options: self.conflict_marker_options,
+            progress: None,
+            use_reflinks: self.settings.use_reflinks(),
+            reflink_cache_size: self.settings.reflink_cache_size(),
+            backup_dir: self
+                .settings
+                .backup_conflicting_files()
+                .then(|| self.workspace_root().join(".jj").join("backup")),
+        }
+    }
+
     pub fn check_working_copy_writable(&self) -> Result<(), CommandError> {
         if self.may_update_working_copy {
             Ok(())
@@ -480,13 +540,34 @@ impl WorkspaceCommandHelper {
             // failure is okay.
             self.snapshot_working_copy(ui)?;
             // import_git_refs() can rebase the working-copy commit.
-            if self.working_copy_shared_with_git {
+            if self.should_import_git_refs() {
                 self.import_git_refs(ui)?;
             }
         }
         Ok(())
     }
 
+    /// Returns true if the working copy needs `jj workspace update-stale`.
+    fn is_working_copy_stale(&self) -> Result<bool, CommandError> {
+        let repo = self.repo();
+        let Some(wc_commit_id) = repo.view().get_wc_commit_id(self.workspace_id()) else {
+            return Ok(false);
+        };
+        let wc_commit = repo.store().get_commit(wc_commit_id)?;
+        let working_copy = self.working_copy();
+        match working_copy_freshness(
+            working_copy.tree_id()?,
+            working_copy.operation_id(),
+            &wc_commit,
+            repo,
+        ) {
+            Ok(WorkingCopyFreshness::WorkingCopyStale) => Ok(true),
+            Ok(_) => Ok(false),
+            Err(OpStoreError::ObjectNotFound { .. }) => Ok(true),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     /// Imports new HEAD from the colocated Git repo.
     ///
     /// If the Git HEAD has changed, this function abandons our old checkout and
@@ -546,6 +627,39 @@ impl WorkspaceCommandHelper {
         Ok(())
     }
 
+    /// Returns true if the Git refs should be imported before running the
+    /// command, according to `git.colocated-sync`.
+    fn should_import_git_refs(&self) -> bool {
+        if !self.working_copy_shared_with_git {
+            return false;
+        }
+        match self.git_sync_mode {
+            GitSyncMode::Always => true,
+            GitSyncMode::Explicit => false,
+            GitSyncMode::Interval(interval) => {
+                let last_import_time = fs::metadata(self.git_import_stamp_path())
+                    .and_then(|metadata| metadata.modified());
+                match last_import_time.map(|time| time.elapsed()) {
+                    Ok(Ok(elapsed)) => elapsed >= interval,
+                    // Missing stamp file, or the clock went backwards
+                    _ => true,
+                }
+            }
+        }
+    }
+
+    /// Returns true if the changes to the branches and tags should be exported
+    /// to the underlying Git repo, according to `git.colocated-sync`.
+    fn should_export_git_refs(&self) -> bool {
+        self.working_copy_shared_with_git && self.git_sync_mode != GitSyncMode::Explicit
+    }
+
+    /// File whose modification time records the last automatic import of the
+    /// Git refs.
+    fn git_import_stamp_path(&self) -> PathBuf {
+        self.workspace.repo_path().join("git_import_stamp")
+    }
+
     /// Imports branches and tags from the underlying Git repo, abandons old
     /// branches.
     ///
@@ -557,11 +671,18 @@ impl WorkspaceCommandHelper {
     #[instrument(skip_all)]
     fn import_git_refs(&mut self, ui: &mut Ui) -> Result<(), CommandError> {
         let git_settings = self.settings.git_settings();
+        let stamp_path = matches!(self.git_sync_mode, GitSyncMode::Interval(_))
+            .then(|| self.git_import_stamp_path());
         let mut tx = self.start_transaction();
         // Automated import shouldn't fail because of reserved remote name.
         let stats = git::import_some_refs(tx.mut_repo(), &git_settings, |ref_name| {
             !git::is_reserved_git_remote_ref(ref_name)
         })?;
+        if let Some(stamp_path) = stamp_path {
+            fs::write(stamp_path, "").map_err(|err| {
+                internal_error_with_message("Failed to record the Git import time", err)
+            })?;
+        }
         if !tx.mut_repo().has_changes() {
             return Ok(());
         }
@@ -880,15 +1001,16 @@ impl WorkspaceCommandHelper {
     pub fn id_prefix_context(&self) -> Result<&IdPrefixContext, CommandError> {
         self.user_repo.id_prefix_context.get_or_try_init(|| {
             let mut context: IdPrefixContext = IdPrefixContext::default();
-            let revset_string: String = self
+            let (config_name, revset_string) = self
                 .settings
                 .config()
                 .get_string("revsets.short-prefixes")
-                .unwrap_or_else(|_| self.settings.default_revset());
+                .map(|revset_string| ("revsets.short-prefixes", revset_string))
+                .unwrap_or_else(|_| ("revsets.log", self.settings.default_revset()));
             if !revset_string.is_empty() {
                 let disambiguation_revset =
                     revset::parse(&revset_string, &self.revset_parse_context()).map_err(|err| {
-                        config_error_with_message("Invalid `revsets.short-prefixes`", err)
+                        config_error_with_message(format!("Invalid `{config_name}`"), err)
                     })?;
                 context = context.disambiguate_within(revset::optimize(disambiguation_revset));
             }
@@ -936,6 +1058,7 @@ impl WorkspaceCommandHelper {
     pub fn commit_template_language(&self) -> Result<CommitTemplateLanguage<'_>, CommandError> {
         Ok(CommitTemplateLanguage::new(
             self.repo().as_ref(),
+            self.repo(),
             self.workspace_id(),
             self.revset_parse_context(),
             self.id_prefix_context()?,
@@ -972,6 +1095,45 @@ impl WorkspaceCommandHelper {
         self.commit_summary_template().format(commit, formatter)
     }
 
+    /// Renders the diff editor instructions template `templates.<name>` for
+    /// the changes from `left_tree` to `right_tree` matched by `matcher`.
+    ///
+    /// `self` is a `DiffEditContext` in the template.
+    pub fn format_diff_instructions(
+        &self,
+        name: &str,
+        source: &Commit,
+        destination: Option<&Commit>,
+        left_tree: &MergedTree,
+        right_tree: &MergedTree,
+        matcher: &dyn Matcher,
+    ) -> Result<String, CommandError> {
+        let config_key = format!("templates.{name}");
+        let template_text = self.settings.config().get_string(&config_key)?;
+        let language = self.commit_template_language()?;
+        let template = template_builder::parse(
+            &language,
+            &template_text,
+            &self.template_aliases_map,
+            CommitTemplateLanguage::wrap_diff_edit_context,
+        )
+        .map_err(|err| config_error_with_message(format!("Invalid `{config_key}`"), err))?;
+        let selected_paths = left_tree
+            .diff(right_tree, matcher)
+            .map(|(path, _diff)| self.format_file_path(&path))
+            .collect();
+        let context = DiffEditContext {
+            source: source.clone(),
+            destination: destination.cloned(),
+            selected_paths,
+        };
+        let mut output = Vec::new();
+        template
+            .format(&context, &mut PlainTextFormatter::new(&mut output))
+            .expect("write() to PlainTextFormatter should never fail");
+        Ok(String::from_utf8(output).expect("template output should be utf-8 bytes"))
+    }
+
     pub fn check_rewritable<'a>(
         &self,
         commits: impl IntoIterator<Item = &'a CommitId>,
@@ -1024,6 +1186,8 @@ impl WorkspaceCommandHelper {
             return Ok(());
         };
         let base_ignores = self.base_ignores()?;
+        let workspace_root = self.workspace_root().clone();
+        let export_git_refs = self.should_export_git_refs();
 
         // Compare working-copy tree and operation with repo's, and reload as needed.
         let mut locked_ws = self.workspace.start_working_copy_mutation()?;
@@ -1077,6 +1241,8 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
             fsmonitor_kind: self.settings.fsmonitor_kind()?,
             progress: progress.as_ref().map(|x| x as _),
             max_new_file_size: self.settings.max_new_file_size()?,
+            exclude_paths: self.settings.snapshot_exclude_paths()?,
+            conflict_marker_options: self.conflict_marker_options,
         })?;
         drop(progress);
         if new_tree_id != *wc_commit.tree_id() {
@@ -1099,12 +1265,40 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
                 )?;
             }
 
-            if self.working_copy_shared_with_git {
-                let failed_branches = git::export_refs(mut_repo)?;
+            let description = "snapshot working copy";
+            if hooks::is_hook_enabled(&self.settings, HookKind::PreSnapshot)? {
+                let old_heads = tx.base_repo().view().heads().iter().cloned().collect_vec();
+                let (added_commits, removed_commits) =
+                    hooks::added_and_removed_commits(tx.repo(), &old_heads)?;
+                let changed_paths = wc_commit
+                    .tree()?
+                    .diff(&commit.tree()?, &EverythingMatcher)
+                    .map(|(path, _)| path.as_internal_file_string().to_owned())
+                    .collect();
+                let hook_input = TransactionHookInput {
+                    args: self.string_args.clone(),
+                    description: description.to_owned(),
+                    operation_id: None,
+                    added_commits,
+                    removed_commits,
+                    working_copy_commit: Some(commit.id().hex()),
+                    changed_paths: Some(changed_paths),
+                };
+                hooks::run_hook(
+                    ui,
+                    &self.settings,
+                    HookKind::PreSnapshot,
+                    &workspace_root,
+                    &hook_input,
+                )?;
+            }
+
+            if export_git_refs {
+                let failed_branches = git::export_refs(tx.mut_repo())?;
                 print_failed_git_export(ui, &failed_branches)?;
             }
 
-            self.user_repo = ReadonlyUserRepo::new(tx.commit("snapshot working copy"));
+            self.user_repo = ReadonlyUserRepo::new(tx.commit(description));
         }
         locked_ws.finish(self.user_repo.repo.op_id().clone())?;
         Ok(())
@@ -1117,11 +1311,13 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
         new_commit: &Commit,
     ) -> Result<(), CommandError> {
         assert!(self.may_update_working_copy);
+        let checkout_options = self.checkout_options();
         let stats = update_working_copy(
             &self.user_repo.repo,
             &mut self.workspace,
             maybe_old_commit,
             new_commit,
+            &checkout_options,
         )?;
         if Some(new_commit) != maybe_old_commit {
             if let Some(mut formatter) = ui.status_formatter() {
@@ -1138,7 +1334,7 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
             }
         }
         if let Some(stats) = stats {
-            print_checkout_stats(ui, stats, new_commit)?;
+            print_checkout_stats(ui, self, stats, new_commit)?;
         }
         if Some(new_commit) != maybe_old_commit {
             if let Some(mut formatter) = ui.status_formatter() {
@@ -1185,16 +1381,53 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
             .get_wc_commit_id(self.workspace_id())
             .map(|commit_id| tx.repo().store().get_commit(commit_id))
             .transpose()?;
+        let description = match &self.global_args.op_description {
+            Some(description) => description.clone(),
+            None => description.into(),
+        };
+        let hook_args = self.string_args.clone();
+        let old_heads = old_repo.view().heads().iter().cloned().collect_vec();
+        let new_wc_commit_hex = maybe_new_wc_commit.as_ref().map(|commit| commit.id().hex());
+        let transaction_hook_input = |new_repo: &dyn Repo, operation_id: Option<String>| {
+            let (added_commits, removed_commits) =
+                hooks::added_and_removed_commits(new_repo, &old_heads)?;
+            Ok::<_, CommandError>(TransactionHookInput {
+                args: hook_args.clone(),
+                description: description.clone(),
+                operation_id,
+                added_commits,
+                removed_commits,
+                working_copy_commit: new_wc_commit_hex.clone(),
+                changed_paths: None,
+            })
+        };
+        if hooks::is_hook_enabled(&self.settings, HookKind::PreTransaction)? {
+            hooks::run_hook(
+                ui,
+                &self.settings,
+                HookKind::PreTransaction,
+                self.workspace_root(),
+                &transaction_hook_input(tx.repo(), None)?,
+            )?;
+        }
         if self.working_copy_shared_with_git {
             let git_repo = self.git_backend().unwrap().open_git_repo()?;
             if let Some(wc_commit) = &maybe_new_wc_commit {
                 git::reset_head(tx.mut_repo(), &git_repo, wc_commit)?;
             }
-            let failed_branches = git::export_refs(tx.mut_repo())?;
-            print_failed_git_export(ui, &failed_branches)?;
+            if self.should_export_git_refs() {
+                let failed_branches = git::export_refs(tx.mut_repo())?;
+                print_failed_git_export(ui, &failed_branches)?;
+            }
+        }
+        for (key, value) in &self.global_args.op_tag {
+            tx.set_tag(key.clone(), value.clone());
         }
-        self.user_repo = ReadonlyUserRepo::new(tx.commit(description));
+        self.user_repo = ReadonlyUserRepo::new(tx.commit(description.clone()));
         self.report_repo_changes(ui, &old_repo)?;
+        if self.global_args.at_operation != "@" {
+            self.report_divergent_operation(ui)?;
+        }
 
         if self.may_update_working_copy {
             if let Some(new_commit) = &maybe_new_wc_commit {
@@ -1204,6 +1437,16 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
                 // update it.
             }
         }
+        if hooks::is_hook_enabled(&self.settings, HookKind::PostTransaction)? {
+            let new_repo = self.repo().clone();
+            hooks::run_hook(
+                ui,
+                &self.settings,
+                HookKind::PostTransaction,
+                self.workspace_root(),
+                &transaction_hook_input(new_repo.as_ref(), Some(new_repo.op_id().hex()))?,
+            )?;
+        }
         let settings = &self.settings;
         if settings.user_name().is_empty() || settings.user_email().is_empty() {
             writeln!(
@@ -1216,6 +1459,46 @@ See https://github.com/martinvonz/jj/blob/main/docs/working-copy.md#stale-workin
         Ok(())
     }
 
+    /// Inform the user if the operation committed at an earlier operation
+    /// diverged from the latest operation, which will be merged by the next
+    /// command.
+    fn report_divergent_operation(&self, ui: &Ui) -> Result<(), CommandError> {
+        let operation = self.repo().operation();
+        let other_op_heads = self
+            .repo()
+            .op_heads_store()
+            .get_op_heads()
+            .into_iter()
+            .filter(|op_id| op_id != operation.id())
+            .collect_vec();
+        if other_op_heads.is_empty() {
+            return Ok(());
+        }
+        writeln!(
+            ui.status(),
+            "Created divergent operation {} on top of operation {}",
+            short_operation_hash(operation.id()),
+            operation
+                .parent_ids()
+                .iter()
+                .map(short_operation_hash)
+                .join(", ")
+        )?;
+        if let Some(mut writer) = ui.hint_default() {
+            writeln!(
+                writer,
+                "The next command will merge it with the latest {} {}.",
+                if other_op_heads.len() == 1 {
+                    "operation"
+                } else {
+                    "operations"
+                },
+                other_op_heads.iter().map(short_operation_hash).join(", ")
+            )?;
+        }
+        Ok(())
+    }
+
     /// Inform the user about important changes to the repo since the previous
     /// operation (when `old_repo` was loaded).
     fn report_repo_changes(
@@ -1389,6 +1672,15 @@ impl WorkspaceCommandTransaction<'_> {
         self.tx.mut_repo()
     }
 
+    /// Merges the `other_op` into this transaction, and returns the conflicts
+    /// left in the view.
+    pub fn merge_operation(
+        &mut self,
+        other_op: Operation,
+    ) -> Result<Vec<ViewConflict>, RepoLoaderError> {
+        self.tx.merge_operation(other_op)
+    }
+
     pub fn check_out(&mut self, commit: &Commit) -> Result<Commit, CheckOutCommitError> {
         let workspace_id = self.helper.workspace_id().to_owned();
         let settings = &self.helper.settings;
@@ -1416,6 +1708,7 @@ impl WorkspaceCommandTransaction<'_> {
         let id_prefix_context = IdPrefixContext::default();
         let language = CommitTemplateLanguage::new(
             self.tx.repo(),
+            self.tx.base_repo(),
             self.helper.workspace_id(),
             self.helper.revset_parse_context(),
             &id_prefix_context,
@@ -1485,11 +1778,50 @@ jj git init --colocate",
         WorkspaceLoadError::StoreLoadError(StoreLoadError::Signing(
             err @ SignInitError::UnknownBackend(_),
         )) => user_error(err),
+        WorkspaceLoadError::StoreLoadError(StoreLoadError::Config(err)) => err.into(),
         WorkspaceLoadError::StoreLoadError(err) => internal_error(err),
         WorkspaceLoadError::NonUnicodePath | WorkspaceLoadError::Path(_) => user_error(err),
     }
 }
 
+/// When the refs of a colocated Git repo are automatically imported and
+/// exported.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GitSyncMode {
+    /// Import and export on every command.
+    #[default]
+    Always,
+    /// Only import and export on `jj git import` and `jj git export`.
+    Explicit,
+    /// Import at most once per interval, export on every command.
+    Interval(Duration),
+}
+
+fn git_sync_mode_setting(config: &config::Config) -> Result<GitSyncMode, CommandError> {
+    let mode = config
+        .get_string("git.colocated-sync")
+        .optional()?
+        .unwrap_or_else(|| "always".to_owned());
+    match mode.as_str() {
+        "always" => Ok(GitSyncMode::Always),
+        "explicit" => Ok(GitSyncMode::Explicit),
+        "interval" => {
+            let seconds = config
+                .get::<u64>("git.colocated-sync-interval")
+                .optional()
+                .map_err(|err| {
+                    config_error_with_message("Invalid `git.colocated-sync-interval`", err)
+                })?
+                .unwrap_or(300);
+            Ok(GitSyncMode::Interval(Duration::from_secs(seconds)))
+        }
+        _ => Err(config_error(format!(
+            "Invalid `git.colocated-sync`: expected one of `always`, `explicit` or `interval`, \
+             got `{mode}`"
+        ))),
+    }
+}
+
 pub fn start_repo_transaction(
     repo: &Arc<ReadonlyRepo>,
     settings: &UserSettings,
@@ -1543,19 +1875,30 @@ pub fn check_stale_working_copy(
     locked_wc: &dyn LockedWorkingCopy,
     wc_commit: &Commit,
     repo: &ReadonlyRepo,
+) -> Result<WorkingCopyFreshness, OpStoreError> {
+    working_copy_freshness(
+        locked_wc.old_tree_id(),
+        locked_wc.old_operation_id(),
+        wc_commit,
+        repo,
+    )
+}
+
+fn working_copy_freshness(
+    wc_tree_id: &MergedTreeId,
+    wc_operation_id: &OperationId,
+    wc_commit: &Commit,
+    repo: &ReadonlyRepo,
 ) -> Result<WorkingCopyFreshness, OpStoreError> {
     // Check if the working copy's tree matches the repo's view
-    let wc_tree_id = locked_wc.old_tree_id();
     if wc_commit.tree_id() == wc_tree_id {
         // The working copy isn't stale, and no need to reload the repo.
         Ok(WorkingCopyFreshness::Fresh)
     } else {
-        let wc_operation_data = repo
-            .op_store()
-            .read_operation(locked_wc.old_operation_id())?;
+        let wc_operation_data = repo.op_store().read_operation(wc_operation_id)?;
         let wc_operation = Operation::new(
             repo.op_store().clone(),
-            locked_wc.old_operation_id().clone(),
+            wc_operation_id.clone(),
             wc_operation_data,
         );
         let repo_operation = repo.operation();
@@ -1581,6 +1924,48 @@ pub fn check_stale_working_copy(
 }
 
 #[instrument(skip_all)]
+/// Reports the conflicts left by merging operations, under the `heading`
+/// describing what was merged.
+pub fn print_view_conflicts(ui: &Ui, heading: &str, conflicts: &[ViewConflict]) -> io::Result<()> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    writeln!(ui.warning_default(), "{heading}")?;
+    for conflict in conflicts {
+        let mut writer = ui.warning_no_heading();
+        match conflict {
+            ViewConflict::WorkingCopy { workspace_id, .. } => writeln!(
+                writer,
+                "  working-copy commit of workspace {}",
+                workspace_id.as_str()
+            )?,
+            ViewConflict::LocalBranch(name) => writeln!(writer, "  branch {name}")?,
+            ViewConflict::RemoteBranch { name, remote_name } => {
+                writeln!(writer, "  remote branch {name}@{remote_name}")?;
+            }
+            ViewConflict::Tag(name) => writeln!(writer, "  tag {name}")?,
+        }
+    }
+    let has_conflict = |f: fn(&ViewConflict) -> bool| conflicts.iter().any(f);
+    if has_conflict(|conflict| matches!(conflict, ViewConflict::WorkingCopy { .. })) {
+        if let Some(mut writer) = ui.hint_default() {
+            writeln!(
+                writer,
+                "Use `jj edit` to switch the working copy to the commit of the other operation."
+            )?;
+        }
+    }
+    if has_conflict(|conflict| matches!(conflict, ViewConflict::LocalBranch(_))) {
+        if let Some(mut writer) = ui.hint_default() {
+            writeln!(
+                writer,
+                "Use `jj branch set` to resolve the branch conflicts."
+            )?;
+        }
+    }
+    Ok(())
+}
+
 pub fn print_conflicted_paths(
     conflicts: &[(RepoPathBuf, MergedTreeValue)],
     formatter: &mut dyn Formatter,
@@ -1671,8 +2056,28 @@ pub fn print_conflicted_paths(
     Ok(())
 }
 
+/// Warns about the conflicts which the external merge drivers failed to
+/// resolve.
+pub fn print_merge_driver_failures(
+    ui: &Ui,
+    resolution: &MergeDriverResolution,
+    workspace_command: &WorkspaceCommandHelper,
+) -> io::Result<()> {
+    for (path, err) in &resolution.failures {
+        let message =
+            iter::successors(Some(err as &dyn error::Error), |err| err.source()).join(": ");
+        writeln!(
+            ui.warning_default(),
+            "Merge driver failed to resolve the conflict at {}: {message}",
+            workspace_command.format_file_path(path)
+        )?;
+    }
+    Ok(())
+}
+
 pub fn print_checkout_stats(
     ui: &mut Ui,
+    workspace_command: &WorkspaceCommandHelper,
     stats: CheckoutStats,
     new_commit: &Commit,
 ) -> Result<(), std::io::Error> {
@@ -1685,13 +2090,34 @@ pub fn print_checkout_stats(
             stats.removed_files
         )?;
     }
+    if !stats.backed_up_paths.is_empty() {
+        writeln!(
+            ui.warning_default(),
+            "Moved {} files with conflicting changes in the working copy to .jj/backup/:",
+            stats.backed_up_paths.len()
+        )?;
+        for path in &stats.backed_up_paths {
+            writeln!(
+                ui.warning_no_heading(),
+                "  {}",
+                workspace_command.format_file_path(path)
+            )?;
+        }
+    }
     if stats.skipped_files != 0 {
         writeln!(
             ui.warning_default(),
             "{} of those updates were skipped because there were conflicting changes in the \
-             working copy.",
+             working copy:",
             stats.skipped_files
         )?;
+        for path in &stats.skipped_paths {
+            writeln!(
+                ui.warning_no_heading(),
+                "  {}",
+                workspace_command.format_file_path(path)
+            )?;
+        }
         if let Some(mut writer) = ui.hint_default() {
             writeln!(
                 writer,
@@ -1748,13 +2174,19 @@ pub fn update_working_copy(
     workspace: &mut Workspace,
     old_commit: Option<&Commit>,
     new_commit: &Commit,
+    options: &CheckoutOptions,
 ) -> Result<Option<CheckoutStats>, CommandError> {
     let old_tree_id = old_commit.map(|commit| commit.tree_id().clone());
     let stats = if Some(new_commit.tree_id()) != old_tree_id.as_ref() {
         // TODO: CheckoutError::ConcurrentCheckout should probably just result in a
         // warning for most commands (but be an error for the checkout command)
         let stats = workspace
-            .check_out(repo.op_id().clone(), old_tree_id.as_ref(), new_commit)
+            .check_out(
+                repo.op_id().clone(),
+                old_tree_id.as_ref(),
+                new_commit,
+                options,
+            )
             .map_err(|err| {
                 internal_error_with_message(
                     format!("Failed to check out commit {}", new_commit.id().hex()),
@@ -1806,15 +2238,24 @@ fn load_template_aliases(
 pub enum LogContentFormat {
     NoWrap,
     Wrap { term_width: usize },
+    Truncate { term_width: usize },
 }
 
 impl LogContentFormat {
     pub fn new(ui: &Ui, settings: &UserSettings) -> Result<Self, config::ConfigError> {
-        if settings.config().get_bool("ui.log-word-wrap")? {
-            let term_width = usize::from(ui.term_width().unwrap_or(80));
-            Ok(LogContentFormat::Wrap { term_width })
-        } else {
-            Ok(LogContentFormat::NoWrap)
+        let term_width = || usize::from(ui.term_width().unwrap_or(80));
+        // Boolean values are also converted to strings.
+        match settings.config().get_string("ui.log-word-wrap")?.as_str() {
+            "false" => Ok(LogContentFormat::NoWrap),
+            "true" => Ok(LogContentFormat::Wrap {
+                term_width: term_width(),
+            }),
+            "truncate" => Ok(LogContentFormat::Truncate {
+                term_width: term_width(),
+            }),
+            value => Err(config::ConfigError::Message(format!(
+                "Invalid `ui.log-word-wrap`: {value}"
+            ))),
         }
     }
 
@@ -1844,6 +2285,16 @@ impl LogContentFormat {
                 )?;
                 Ok(())
             }
+            LogContentFormat::Truncate { term_width } => {
+                let mut recorder = FormatRecorder::new();
+                content_fn(&mut recorder)?;
+                text_util::write_truncated(
+                    formatter,
+                    &recorder,
+                    term_width.saturating_sub(graph_width_fn()),
+                )?;
+                Ok(())
+            }
         }
     }
 }
@@ -1868,12 +2319,7 @@ pub fn serialize_config_value(value: &config::Value) -> String {
     }
 }
 
-pub fn write_config_value_to_file(
-    key: &str,
-    value_str: &str,
-    path: &Path,
-) -> Result<(), CommandError> {
-    // Read config
+fn read_config_document(path: &Path) -> Result<toml_edit::Document, CommandError> {
     let config_toml = std::fs::read_to_string(path).or_else(|err| {
         match err.kind() {
             // If config doesn't exist yet, read as empty and we'll write one.
@@ -1884,20 +2330,32 @@ pub fn write_config_value_to_file(
             )),
         }
     })?;
-    let mut doc = toml_edit::Document::from_str(&config_toml).map_err(|err| {
+    toml_edit::Document::from_str(&config_toml).map_err(|err| {
         user_error_with_message(
             format!("Failed to parse file {path}", path = path.display()),
             err,
         )
-    })?;
+    })
+}
+
+fn write_config_document(doc: &toml_edit::Document, path: &Path) -> Result<(), CommandError> {
+    std::fs::write(path, doc.to_string()).map_err(|err| {
+        user_error_with_message(
+            format!("Failed to write file {path}", path = path.display()),
+            err,
+        )
+    })
+}
+
+pub fn write_config_value_to_file(
+    key: &str,
+    value_str: &str,
+    path: &Path,
+) -> Result<(), CommandError> {
+    let mut doc = read_config_document(path)?;
 
     // Apply config value
-    // Interpret value as string if it can't be parsed as a TOML value.
-    // TODO(#531): Infer types based on schema (w/ --type arg to override).
-    let item = match toml_edit::Value::from_str(value_str) {
-        Ok(value) => toml_edit::value(value),
-        _ => toml_edit::value(value_str),
-    };
+    let item = toml_edit::value(parse_config_value(key, value_str)?);
     let mut target_table = doc.as_table_mut();
     let mut key_parts_iter = key.split('.');
     // Note: split guarantees at least one item.
@@ -1925,13 +2383,26 @@ pub fn write_config_value_to_file(
     }
     target_table[last_key_part] = item;
 
-    // Write config back
-    std::fs::write(path, doc.to_string()).map_err(|err| {
-        user_error_with_message(
-            format!("Failed to write file {path}", path = path.display()),
-            err,
-        )
-    })
+    write_config_document(&doc, path)
+}
+
+pub fn remove_config_value_from_file(key: &str, path: &Path) -> Result<(), CommandError> {
+    let mut doc = read_config_document(path)?;
+
+    let not_found = || user_error(format!("{key} isn't set in {path}", path = path.display()));
+    let mut target_table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    let mut key_parts_iter = key.split('.');
+    // Note: split guarantees at least one item.
+    let last_key_part = key_parts_iter.next_back().unwrap();
+    for key_part in key_parts_iter {
+        target_table = target_table
+            .get_mut(key_part)
+            .and_then(|item| item.as_table_like_mut())
+            .ok_or_else(not_found)?;
+    }
+    target_table.remove(last_key_part).ok_or_else(not_found)?;
+
+    write_config_document(&doc, path)
 }
 
 pub fn get_new_config_file_path(
@@ -1944,6 +2415,9 @@ pub fn get_new_config_file_path(
             new_config_path()?.ok_or_else(|| user_error("No repo config path found to edit"))?
         }
         ConfigSource::Repo => command.workspace_loader()?.repo_path().join("config.toml"),
+        ConfigSource::Workspace => {
+            workspace_config_path(command.workspace_loader()?.workspace_root())
+        }
         _ => {
             return Err(user_error(format!(
                 "Can't get path for config source {config_source:?}"
@@ -1953,21 +2427,46 @@ pub fn get_new_config_file_path(
     Ok(edit_path)
 }
 
-pub fn run_ui_editor(settings: &UserSettings, edit_path: &PathBuf) -> Result<(), CommandError> {
+pub fn run_ui_editor(
+    ui: &Ui,
+    settings: &UserSettings,
+    edit_path: &PathBuf,
+) -> Result<(), CommandError> {
     let editor: CommandNameAndArgs = settings
         .config()
         .get("ui.editor")
         .map_err(|err| config_error_with_message("Invalid `ui.editor`", err))?;
-    let exit_status = editor.to_command().arg(edit_path).status().map_err(|err| {
-        user_error_with_message(
-            format!(
-                // The executable couldn't be found or run; command-line arguments are not relevant
-                "Failed to run editor '{name}'",
+    if editor == CommandNameAndArgs::String(BUILTIN_EDITOR_NAME.into()) {
+        return edit_file_builtin(ui, edit_path);
+    }
+    // The editor isn't configured if it's still the platform default.
+    let is_default_editor = default_config()
+        .get::<CommandNameAndArgs>("ui.editor")
+        .map_or(false, |default_editor| default_editor == editor);
+    let exit_status = match editor.to_command().arg(edit_path).status() {
+        Ok(status) => status,
+        // Fall back to the built-in editor rather than failing if no editor is
+        // configured and the default one isn't installed.
+        Err(err) if err.kind() == io::ErrorKind::NotFound && is_default_editor => {
+            writeln!(
+                ui.warning_default(),
+                "Editor '{name}' not found, using the built-in editor",
                 name = editor.split_name(),
-            ),
-            err,
-        )
-    })?;
+            )?;
+            return edit_file_builtin(ui, edit_path);
+        }
+        Err(err) => {
+            return Err(user_error_with_message(
+                format!(
+                    // The executable couldn't be found or run; command-line arguments are not
+                    // relevant
+                    "Failed to run editor '{name}'",
+                    name = editor.split_name(),
+                ),
+                err,
+            ));
+        }
+    };
     if !exit_status.success() {
         return Err(user_error(format!(
             "Editor '{editor}' exited with an error"
@@ -1978,6 +2477,7 @@ pub fn run_ui_editor(settings: &UserSettings, edit_path: &PathBuf) -> Result<(),
 }
 
 pub fn edit_temp_file(
+    ui: &Ui,
     error_name: &str,
     tempfile_suffix: &str,
     dir: &Path,
@@ -2004,7 +2504,7 @@ pub fn edit_temp_file(
         )
     })?;
 
-    run_ui_editor(settings, &path)?;
+    run_ui_editor(ui, settings, &path)?;
 
     let edited = fs::read_to_string(&path).map_err(|e| {
         user_error_with_message(
@@ -2174,19 +2674,38 @@ pub struct GlobalArgs {
     /// ignored, as if `--ignore-working-copy` had been specified.
     ///
     /// It is possible to run mutating commands when loading the repo at an
-    /// earlier operation. Doing that is equivalent to having run concurrent
-    /// commands starting at the earlier operation. There's rarely a reason to
-    /// do that, but it is possible.
+    /// earlier operation. The new operation then diverges from the latest
+    /// operation, as if the command had run concurrently with the operations
+    /// since the earlier one, and the next command merges the divergent
+    /// operations.
     #[arg(long, visible_alias = "at-op", global = true, default_value = "@")]
     pub at_operation: String,
     /// Enable debug logging
     #[arg(long, global = true)]
     pub debug: bool,
+    /// Description of the operation created by the command
+    ///
+    /// The description is shown in `jj op log` instead of the one generated
+    /// from the command, which helps to find the operation later.
+    #[arg(long, value_name = "DESCRIPTION", global = true)]
+    pub op_description: Option<String>,
+    /// Tag to add to the operation created by the command (can be repeated)
+    ///
+    /// The tags are shown in `jj op log` along with the `args` tag.
+    #[arg(long, value_name = "KEY=VALUE", global = true, value_parser = parse_op_tag)]
+    pub op_tag: Vec<(String, String)>,
 
     #[command(flatten)]
     pub early_args: EarlyArgs,
 }
 
+fn parse_op_tag(src: &str) -> Result<(String, String), &'static str> {
+    match src.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err("Tag must be in the form KEY=VALUE"),
+    }
+}
+
 #[derive(clap::Args, Clone, Debug)]
 pub struct EarlyArgs {
     /// When to colorize output (always, never, auto)
@@ -2208,10 +2727,14 @@ pub struct EarlyArgs {
     // Option<bool>.
     pub no_pager: Option<bool>,
     /// Additional configuration options (can be repeated)
-    //  TODO: Introduce a `--config` option with simpler syntax for simple
-    //  cases, designed so that `--config ui.color=auto` works
     #[arg(long, value_name = "TOML", global = true)]
     pub config_toml: Vec<String>,
+    /// Set a configuration option (can be repeated)
+    ///
+    /// The value is parsed like with `jj config set`, so `--config
+    /// ui.color=always` works without quoting the string.
+    #[arg(long, value_name = "NAME=VALUE", global = true)]
+    pub config: Vec<String>,
 }
 
 /// Wrapper around revset expression argument.
@@ -2309,8 +2832,8 @@ fn resolve_default_command(
 fn resolve_aliases(
     config: &config::Config,
     app: &Command,
-    mut string_args: Vec<String>,
-) -> Result<Vec<String>, CommandError> {
+    string_args: Vec<String>,
+) -> Result<Vec<Vec<String>>, CommandError> {
     let mut aliases_map = config.get_table("aliases")?;
     if let Ok(alias_map) = config.get_table("alias") {
         for (alias, definition) in alias_map {
@@ -2323,7 +2846,6 @@ fn resolve_aliases(
             }
         }
     }
-    let mut resolved_aliases = HashSet::new();
     let mut real_commands = HashSet::new();
     for command in app.get_subcommands() {
         real_commands.insert(command.get_name().to_string());
@@ -2331,6 +2853,25 @@ fn resolve_aliases(
             real_commands.insert(alias.to_string());
         }
     }
+    resolve_alias_chain(
+        app,
+        &aliases_map,
+        &real_commands,
+        HashSet::new(),
+        string_args,
+    )
+}
+
+/// Resolves the aliases in `string_args` into the command lines to run.
+///
+/// More than one command line is returned if a chained alias is used.
+fn resolve_alias_chain(
+    app: &Command,
+    aliases_map: &HashMap<String, config::Value>,
+    real_commands: &HashSet<String>,
+    mut resolved_aliases: HashSet<String>,
+    mut string_args: Vec<String>,
+) -> Result<Vec<Vec<String>>, CommandError> {
     loop {
         let app_clone = app.clone().allow_external_subcommands(true);
         let matches = app_clone.try_get_matches_from(&string_args).ok();
@@ -2347,27 +2888,161 @@ fn resolve_aliases(
                         r#"Recursive alias definition involving "{alias_name}""#
                     )));
                 }
-                if let Some(value) = aliases_map.remove(&alias_name) {
-                    if let Ok(alias_definition) = value.try_deserialize::<Vec<String>>() {
-                        assert!(string_args.ends_with(&alias_args));
-                        string_args.truncate(string_args.len() - 1 - alias_args.len());
-                        string_args.extend(alias_definition);
-                        string_args.extend_from_slice(&alias_args);
-                        resolved_aliases.insert(alias_name.clone());
-                        continue;
+                if let Some(value) = aliases_map.get(&alias_name) {
+                    let definition = parse_alias_definition(&alias_name, value)?;
+                    let mut expanded = if definition.interpolate {
+                        interpolate_alias_args(&alias_name, &definition.commands, &alias_args)?
                     } else {
-                        return Err(user_error(format!(
-                            r#"Alias definition for "{alias_name}" must be a string list"#
-                        )));
+                        append_alias_args(definition.commands, &alias_args)
+                    };
+                    assert!(string_args.ends_with(&alias_args));
+                    string_args.truncate(string_args.len() - 1 - alias_args.len());
+                    resolved_aliases.insert(alias_name);
+                    if definition.chained {
+                        let mut command_lines = vec![];
+                        for args in expanded {
+                            command_lines.extend(resolve_alias_chain(
+                                app,
+                                aliases_map,
+                                real_commands,
+                                resolved_aliases.clone(),
+                                [string_args.clone(), args].concat(),
+                            )?);
+                        }
+                        return Ok(command_lines);
                     }
+                    string_args.extend(expanded.pop().unwrap());
+                    continue;
                 } else {
                     // Not a real command and not an alias, so return what we've resolved so far
-                    return Ok(string_args);
+                    return Ok(vec![string_args]);
                 }
             }
         }
         // No more alias commands, or hit unknown option
-        return Ok(string_args);
+        return Ok(vec![string_args]);
+    }
+}
+
+/// Parsed alias definition.
+struct AliasDefinition {
+    /// The commands to run, of which there are more than one if the alias is
+    /// chained.
+    commands: Vec<Vec<String>>,
+    chained: bool,
+    /// Whether the commands refer to the alias arguments with `$N` and `$@`.
+    interpolate: bool,
+}
+
+/// Parses the alias definition, which is either a string list, a list of
+/// string lists for a chain of commands, or a table with either of them as
+/// `args` and an optional `interpolate` flag.
+fn parse_alias_definition(
+    alias_name: &str,
+    value: &config::Value,
+) -> Result<AliasDefinition, CommandError> {
+    let invalid_definition = || {
+        user_error(format!(
+            r#"Alias definition for "{alias_name}" must be a string list, a list of string lists, or a table with `args` and `interpolate`"#
+        ))
+    };
+    let (args, interpolate) = match value.clone().into_table() {
+        Ok(mut table) => {
+            let args = table.remove("args").ok_or_else(invalid_definition)?;
+            let interpolate = match table.remove("interpolate") {
+                Some(value) => value.into_bool().map_err(|_| invalid_definition())?,
+                None => false,
+            };
+            if !table.is_empty() {
+                return Err(invalid_definition());
+            }
+            (args, interpolate)
+        }
+        Err(_) => (value.clone(), false),
+    };
+    if let Ok(definition) = args.clone().try_deserialize::<Vec<String>>() {
+        Ok(AliasDefinition {
+            commands: vec![definition],
+            chained: false,
+            interpolate,
+        })
+    } else if let Ok(definitions) = args.try_deserialize::<Vec<Vec<String>>>() {
+        Ok(AliasDefinition {
+            commands: definitions,
+            chained: true,
+            interpolate,
+        })
+    } else {
+        Err(invalid_definition())
+    }
+}
+
+/// Appends the arguments passed to the alias to its last command.
+fn append_alias_args(mut commands: Vec<Vec<String>>, alias_args: &[String]) -> Vec<Vec<String>> {
+    if let Some(args) = commands.last_mut() {
+        args.extend_from_slice(alias_args);
+    }
+    commands
+}
+
+/// Substitutes the arguments passed to the alias into its definitions.
+///
+/// `$N` is replaced by the N-th argument, and an argument `$@` by all of the
+/// arguments. `$$` is replaced by `$`. If the definitions don't refer to the
+/// arguments, they are appended to the last command.
+fn interpolate_alias_args(
+    alias_name: &str,
+    definitions: &[Vec<String>],
+    alias_args: &[String],
+) -> Result<Vec<Vec<String>>, CommandError> {
+    let mut args_referenced = false;
+    let mut expanded = vec![];
+    for definition in definitions {
+        let mut args = vec![];
+        for arg in definition {
+            if arg == "$@" {
+                args.extend_from_slice(alias_args);
+                args_referenced = true;
+                continue;
+            }
+            let mut expanded_arg = String::new();
+            let mut rest = arg.as_str();
+            while let Some(pos) = rest.find('$') {
+                expanded_arg.push_str(&rest[..pos]);
+                rest = &rest[pos + 1..];
+                let num_len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                if num_len > 0 {
+                    let index: usize = rest[..num_len].parse().unwrap_or(usize::MAX);
+                    let value = index
+                        .checked_sub(1)
+                        .and_then(|i| alias_args.get(i))
+                        .ok_or_else(|| {
+                            user_error(format!(
+                                r#"Alias "{alias_name}" refers to "${index}", but got {} arguments"#,
+                                alias_args.len()
+                            ))
+                        })?;
+                    expanded_arg.push_str(value);
+                    args_referenced = true;
+                    rest = &rest[num_len..];
+                } else if let Some(after) = rest.strip_prefix('$') {
+                    expanded_arg.push('$');
+                    rest = after;
+                } else {
+                    expanded_arg.push('$');
+                }
+            }
+            expanded_arg.push_str(rest);
+            args.push(expanded_arg);
+        }
+        expanded.push(args);
+    }
+    if args_referenced {
+        Ok(expanded)
+    } else {
+        Ok(append_alias_args(expanded, alias_args))
     }
 }
 
@@ -2388,6 +3063,26 @@ fn handle_early_args(
         .try_get_matches_from(args)?;
     let mut args: EarlyArgs = EarlyArgs::from_arg_matches(&early_matches).unwrap();
 
+    // --config-toml and --config are applied in the order they're specified.
+    let config_toml_indices = early_matches
+        .indices_of("config_toml")
+        .into_iter()
+        .flatten();
+    let config_indices = early_matches.indices_of("config").into_iter().flatten();
+    let mut config_strs = config_toml_indices.zip(args.config_toml).collect_vec();
+    for (index, arg) in config_indices.zip(&args.config) {
+        let Some((name, value)) = arg.split_once('=') else {
+            return Err(user_error_with_hint(
+                format!("Invalid --config argument: {arg}"),
+                "The argument should be NAME=VALUE, for example `--config ui.color=always`.",
+            ));
+        };
+        let value = parse_config_value(name, value)?;
+        config_strs.push((index, config_toml_for_value(name, &value)));
+    }
+    config_strs.sort_by_key(|(index, _)| *index);
+    args.config_toml = config_strs.into_iter().map(|(_, toml)| toml).collect();
+
     if let Some(choice) = args.color {
         args.config_toml.push(format!(r#"ui.color="{choice}""#));
     }
@@ -2404,12 +3099,14 @@ fn handle_early_args(
     Ok(())
 }
 
+/// Expands the command line arguments into the command lines to run, which are
+/// more than one if a chained alias is used.
 pub fn expand_args(
     ui: &Ui,
     app: &Command,
     args_os: ArgsOs,
     config: &config::Config,
-) -> Result<Vec<String>, CommandError> {
+) -> Result<Vec<Vec<String>>, CommandError> {
     let mut string_args: Vec<String> = vec![];
     for arg_os in args_os {
         if let Some(string_arg) = arg_os.to_str() {
@@ -2469,9 +3166,9 @@ pub struct CliRunner {
     process_global_args_fns: Vec<ProcessGlobalArgsFn>,
 }
 
-type CliDispatchFn = Box<dyn FnOnce(&mut Ui, &CommandHelper) -> Result<(), CommandError>>;
+type CliDispatchFn = Box<dyn Fn(&mut Ui, &CommandHelper) -> Result<(), CommandError>>;
 
-type ProcessGlobalArgsFn = Box<dyn FnOnce(&mut Ui, &ArgMatches) -> Result<(), CommandError>>;
+type ProcessGlobalArgsFn = Box<dyn Fn(&mut Ui, &ArgMatches) -> Result<(), CommandError>>;
 
 impl CliRunner {
     /// Initializes CLI environment and returns a builder. This should be called
@@ -2545,7 +3242,7 @@ impl CliRunner {
     pub fn add_subcommand<C, F>(mut self, custom_dispatch_fn: F) -> Self
     where
         C: clap::Subcommand,
-        F: FnOnce(&mut Ui, &CommandHelper, C) -> Result<(), CommandError> + 'static,
+        F: Fn(&mut Ui, &CommandHelper, C) -> Result<(), CommandError> + 'static,
     {
         let old_dispatch_fn = self.dispatch_fn;
         let new_dispatch_fn =
@@ -2564,7 +3261,7 @@ impl CliRunner {
     pub fn add_global_args<A, F>(mut self, process_before: F) -> Self
     where
         A: clap::Args,
-        F: FnOnce(&mut Ui, A) -> Result<(), CommandError> + 'static,
+        F: Fn(&mut Ui, A) -> Result<(), CommandError> + 'static,
     {
         let process_global_args_fn = move |ui: &mut Ui, matches: &ArgMatches| {
             let custom_args = A::from_arg_matches(matches).unwrap();
@@ -2578,7 +3275,7 @@ impl CliRunner {
 
     #[instrument(skip_all)]
     fn run_internal(
-        self,
+        mut self,
         ui: &mut Ui,
         mut layered_configs: LayeredConfigs,
     ) -> Result<(), CommandError> {
@@ -2597,14 +3294,57 @@ impl CliRunner {
         // than the path resolution.
         let maybe_cwd_workspace_loader = WorkspaceLoader::init(find_workspace_dir(&cwd))
             .map_err(|err| map_workspace_load_error(err, None));
+        layered_configs.read_env_config_vars()?;
         layered_configs.read_user_config()?;
         if let Ok(loader) = &maybe_cwd_workspace_loader {
+            layered_configs.read_git_config(loader.workspace_root(), loader.repo_path())?;
+            layered_configs.read_user_includes(loader.workspace_root())?;
             layered_configs.read_repo_config(loader.repo_path())?;
+            layered_configs.read_workspace_config(loader.workspace_root())?;
         }
         let config = layered_configs.merge();
         ui.reset(&config)?;
 
-        let string_args = expand_args(ui, &self.app, env::args_os(), &config)?;
+        let command_lines = expand_args(ui, &self.app, env::args_os(), &config)?;
+        let store_factories = Rc::new(self.store_factories.take().unwrap_or_default());
+        let working_copy_factories = Rc::new(
+            self.working_copy_factories
+                .take()
+                .unwrap_or_else(default_working_copy_factories),
+        );
+        // The commands of a chained alias run in turn, each with its own
+        // workspace and repo.
+        for string_args in &command_lines {
+            let command_helper = self.prepare_command(
+                ui,
+                &cwd,
+                &command_lines,
+                string_args.clone(),
+                layered_configs.clone(),
+                store_factories.clone(),
+                working_copy_factories.clone(),
+            )?;
+            for start_hook_fn in &self.start_hook_fns {
+                start_hook_fn(ui, &command_helper)?;
+            }
+            (self.dispatch_fn)(ui, &command_helper)?;
+        }
+        Ok(())
+    }
+
+    /// Parses the command line `string_args` of one of the `command_lines`
+    /// expanded from the arguments, and loads the configs it refers to.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_command(
+        &self,
+        ui: &mut Ui,
+        cwd: &Path,
+        command_lines: &[Vec<String>],
+        string_args: Vec<String>,
+        mut layered_configs: LayeredConfigs,
+        store_factories: Rc<StoreFactories>,
+        working_copy_factories: Rc<HashMap<String, Box<dyn WorkingCopyFactory>>>,
+    ) -> Result<CommandHelper, CommandError> {
         let (matches, args) = parse_args(
             ui,
             &self.app,
@@ -2613,7 +3353,7 @@ impl CliRunner {
             &mut layered_configs,
         )
         .map_err(|err| map_clap_cli_error(err, ui, &layered_configs))?;
-        for process_global_args_fn in self.process_global_args_fns {
+        for process_global_args_fn in &self.process_global_args_fns {
             process_global_args_fn(ui, &matches)?;
         }
 
@@ -2621,10 +3361,14 @@ impl CliRunner {
             // Invalid -R path is an error. No need to proceed.
             let loader = WorkspaceLoader::init(&cwd.join(path))
                 .map_err(|err| map_workspace_load_error(err, Some(path)))?;
+            layered_configs.read_git_config(loader.workspace_root(), loader.repo_path())?;
+            layered_configs.read_user_includes(loader.workspace_root())?;
             layered_configs.read_repo_config(loader.repo_path())?;
+            layered_configs.read_workspace_config(loader.workspace_root())?;
             Ok(loader)
         } else {
-            maybe_cwd_workspace_loader
+            WorkspaceLoader::init(find_workspace_dir(cwd))
+                .map_err(|err| map_workspace_load_error(err, None))
         };
 
         // Apply workspace configs and --config-toml arguments.
@@ -2634,8 +3378,8 @@ impl CliRunner {
         // If -R is specified, check if the expanded arguments differ. Aliases
         // can also be injected by --config-toml, but that's obviously wrong.
         if args.global_args.repository.is_some() {
-            let new_string_args = expand_args(ui, &self.app, env::args_os(), &config).ok();
-            if new_string_args.as_ref() != Some(&string_args) {
+            let new_command_lines = expand_args(ui, &self.app, env::args_os(), &config).ok();
+            if new_command_lines.as_deref() != Some(command_lines) {
                 writeln!(
                     ui.warning_default(),
                     "Command aliases cannot be loaded from -R/--repository path"
@@ -2644,27 +3388,20 @@ impl CliRunner {
         }
 
         let settings = UserSettings::from_config(config);
-        let working_copy_factories = self
-            .working_copy_factories
-            .unwrap_or_else(default_working_copy_factories);
-        let command_helper = CommandHelper {
-            app: self.app,
-            cwd,
+        Ok(CommandHelper {
+            app: self.app.clone(),
+            cwd: cwd.to_owned(),
             string_args,
             matches,
             global_args: args.global_args,
             settings,
             layered_configs,
-            commit_template_extension: self.commit_template_extension,
-            operation_template_extension: self.operation_template_extension,
+            commit_template_extension: self.commit_template_extension.clone(),
+            operation_template_extension: self.operation_template_extension.clone(),
             maybe_workspace_loader,
-            store_factories: self.store_factories.unwrap_or_default(),
+            store_factories,
             working_copy_factories,
-        };
-        for start_hook_fn in self.start_hook_fns {
-            start_hook_fn(ui, &command_helper)?;
-        }
-        (self.dispatch_fn)(ui, &command_helper)
+        })
     }
 
     #[must_use]
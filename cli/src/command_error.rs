@@ -19,8 +19,10 @@ use std::{error, io, iter, str};
 
 use itertools::Itertools as _;
 use jj_lib::backend::BackendError;
+use jj_lib::file_util::PathError;
 use jj_lib::git::{GitConfigParseError, GitExportError, GitImportError, GitRemoteManagementError};
 use jj_lib::gitignore::GitIgnoreError;
+use jj_lib::hg::HgImportError;
 use jj_lib::op_heads_store::OpHeadResolutionError;
 use jj_lib::op_store::OpStoreError;
 use jj_lib::op_walk::OpsetEvaluationError;
@@ -31,10 +33,12 @@ use jj_lib::revset::{
 };
 use jj_lib::signing::SignInitError;
 use jj_lib::str_util::StringPatternParseError;
+use jj_lib::sync::SyncError;
 use jj_lib::working_copy::{ResetError, SnapshotError, WorkingCopyStateError};
 use jj_lib::workspace::WorkspaceInitError;
 use thiserror::Error;
 
+use crate::config::ConfigSchemaError;
 use crate::formatter::{FormatRecorder, Formatter};
 use crate::merge_tools::{
     ConflictResolveError, DiffEditError, DiffGenerateError, MergeToolConfigError,
@@ -206,12 +210,33 @@ impl From<io::Error> for CommandError {
     }
 }
 
+impl From<PathError> for CommandError {
+    fn from(err: PathError) -> Self {
+        user_error(err)
+    }
+}
+
 impl From<config::ConfigError> for CommandError {
     fn from(err: config::ConfigError) -> Self {
         config_error(err)
     }
 }
 
+impl From<ConfigSchemaError> for CommandError {
+    fn from(err: ConfigSchemaError) -> Self {
+        let hint = match &err {
+            ConfigSchemaError::UnknownOption {
+                name: _,
+                candidates,
+            } => format_similarity_hint(candidates),
+            ConfigSchemaError::InvalidType { .. } | ConfigSchemaError::InvalidValue { .. } => None,
+        };
+        let mut cmd_err = user_error(err);
+        cmd_err.extend_hints(hint);
+        cmd_err
+    }
+}
+
 impl From<crate::config::ConfigError> for CommandError {
     fn from(err: crate::config::ConfigError) -> Self {
         config_error(err)
@@ -268,6 +293,7 @@ impl From<WorkspaceInitError> for CommandError {
             }
             WorkspaceInitError::SignInit(err @ SignInitError::UnknownBackend(_)) => user_error(err),
             WorkspaceInitError::SignInit(err) => internal_error(err),
+            WorkspaceInitError::Config(err) => err.into(),
         }
     }
 }
@@ -324,6 +350,18 @@ impl From<ResetError> for CommandError {
     }
 }
 
+impl From<SyncError> for CommandError {
+    fn from(err: SyncError) -> Self {
+        match err {
+            // The other side reports why it closed the connection
+            SyncError::Disconnected => CommandError::new(CommandErrorKind::BrokenPipe, err),
+            SyncError::Backend(err) => err.into(),
+            SyncError::OpStore(err) => err.into(),
+            _ => user_error_with_message("Failed to sync with the other repo", err),
+        }
+    }
+}
+
 impl From<DiffEditError> for CommandError {
     fn from(err: DiffEditError) -> Self {
         user_error_with_message("Failed to edit diff", err)
@@ -394,6 +432,15 @@ impl From<GitRemoteManagementError> for CommandError {
     }
 }
 
+impl From<HgImportError> for CommandError {
+    fn from(err: HgImportError) -> Self {
+        match err {
+            HgImportError::Backend(err) => err.into(),
+            _ => user_error_with_message("Failed to import the Mercurial repo", err),
+        }
+    }
+}
+
 impl From<RevsetEvaluationError> for CommandError {
     fn from(err: RevsetEvaluationError) -> Self {
         user_error(err)
@@ -447,6 +494,7 @@ impl From<RevsetResolutionError> for CommandError {
             | RevsetResolutionError::WorkspaceMissingWorkingCopy { .. }
             | RevsetResolutionError::AmbiguousCommitIdPrefix(_)
             | RevsetResolutionError::AmbiguousChangeIdPrefix(_)
+            | RevsetResolutionError::HiddenCommitsUnsupported(_)
             | RevsetResolutionError::StoreError(_) => None,
         };
         let mut cmd_err = user_error(err);
@@ -532,9 +580,9 @@ fn find_source_parse_error_hint(err: &dyn error::Error) -> Option<String> {
 fn string_pattern_parse_error_hint(err: &StringPatternParseError) -> Option<String> {
     match err {
         StringPatternParseError::InvalidKind(_) => {
-            Some("Try prefixing with one of `exact:`, `glob:` or `substring:`".into())
+            Some("Try prefixing with one of `exact:`, `glob:`, `regex:` or `substring:`".into())
         }
-        StringPatternParseError::GlobPattern(_) => None,
+        StringPatternParseError::GlobPattern(_) | StringPatternParseError::Regex(_) => None,
     }
 }
 
@@ -0,0 +1,541 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::hex_util::to_reverse_hex;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_store::OperationId;
+use jj_lib::repo::Repo as _;
+use jj_lib::revset;
+use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::settings::UserSettings;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::instrument;
+
+use super::squash::move_diff;
+use super::status::status_json;
+use crate::cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper};
+use crate::command_error::{
+    config_error_with_message, user_error, user_error_with_message, CommandError, ErrorHint,
+};
+use crate::description_util::join_message_paragraphs;
+use crate::diff_util;
+use crate::formatter::PlainTextFormatter;
+use crate::ui::Ui;
+
+/// Version of the API schema, incremented on incompatible changes.
+const API_VERSION: u32 = 1;
+
+// Error codes defined by JSON-RPC 2.0.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The command run for the request failed.
+const COMMAND_FAILED: i64 = -32000;
+
+/// Serve repository queries and mutations to other programs
+///
+/// Requests are read as JSON-RPC 2.0 messages, one per line, and a response is
+/// written on a single line for each of them. Each request is run against the
+/// latest operation after snapshotting the working copy, and its result reports
+/// the id of the operation it saw (or created).
+///
+/// The methods are `version`, `log`, `evaluate_revset`, `status`, `diff`,
+/// `describe`, `new` and `squash`. See the documentation for their parameters.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct ApiArgs {
+    /// Listen for connections on this Unix socket instead of reading requests
+    /// from stdin
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    socket: Option<PathBuf>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_api(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ApiArgs,
+) -> Result<(), CommandError> {
+    match &args.socket {
+        None => serve(ui, command, io::stdin().lock(), io::stdout().lock()),
+        Some(path) => serve_socket(ui, command, path),
+    }
+}
+
+#[cfg(unix)]
+fn serve_socket(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    path: &std::path::Path,
+) -> Result<(), CommandError> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    use crate::cleanup_guard::CleanupGuard;
+
+    let bind = || UnixListener::bind(path);
+    let listener = match bind() {
+        // The socket of a server which didn't exit cleanly is left behind.
+        // Nothing answers on it, so it can be replaced.
+        Err(err)
+            if err.kind() == io::ErrorKind::AddrInUse
+                && UnixStream::connect(path)
+                    .is_err_and(|err| err.kind() == io::ErrorKind::ConnectionRefused) =>
+        {
+            std::fs::remove_file(path).and_then(|()| bind())
+        }
+        result => result,
+    }
+    .map_err(|err| {
+        user_error_with_message(format!("Failed to listen on {}", path.display()), err)
+    })?;
+    let socket_path = path.to_owned();
+    let _socket_guard = CleanupGuard::new(move || {
+        std::fs::remove_file(&socket_path).ok();
+    });
+    writeln!(ui.status(), "Listening on {}", path.display())?;
+    // Connections are served one at a time so that mutations don't race. A
+    // failing connection doesn't stop the server.
+    for stream in listener.incoming() {
+        let result = stream
+            .and_then(|stream| Ok((BufReader::new(stream.try_clone()?), stream)))
+            .map_err(CommandError::from)
+            .and_then(|(input, output)| serve(ui, command, input, output));
+        if let Err(err) = result {
+            writeln!(
+                ui.warning_default(),
+                "Failed to serve a connection: {}",
+                err.error
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(
+    _ui: &mut Ui,
+    _command: &CommandHelper,
+    _path: &std::path::Path,
+) -> Result<(), CommandError> {
+    Err(user_error("Sockets are only supported on Unix"))
+}
+
+/// Answers the requests read from `input` until the end of the input.
+fn serve(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), CommandError> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_request(ui, command, &line);
+        writeln!(output, "{response}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Error reported in the response to a request.
+#[derive(Debug)]
+struct ApiError {
+    code: i64,
+    message: String,
+    hints: Vec<String>,
+}
+
+impl ApiError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        ApiError {
+            code,
+            message: message.into(),
+            hints: vec![],
+        }
+    }
+}
+
+impl From<CommandError> for ApiError {
+    fn from(err: CommandError) -> Self {
+        let mut message = err.error.to_string();
+        let mut source = err.error.source();
+        while let Some(err) = source {
+            message.push_str(&format!(": {err}"));
+            source = err.source();
+        }
+        let hints = err
+            .hints
+            .iter()
+            .map(|hint| match hint {
+                ErrorHint::PlainText(text) => text.clone(),
+                ErrorHint::Formatted(recorded) => {
+                    String::from_utf8_lossy(recorded.data()).into_owned()
+                }
+            })
+            .collect();
+        ApiError {
+            code: COMMAND_FAILED,
+            message,
+            hints,
+        }
+    }
+}
+
+fn handle_request(ui: &mut Ui, command: &CommandHelper, line: &str) -> Value {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(err) => {
+            return error_response(Value::Null, ApiError::new(PARSE_ERROR, err.to_string()))
+        }
+    };
+    let id = value.get("id").cloned().unwrap_or_default();
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => return error_response(id, ApiError::new(INVALID_REQUEST, err.to_string())),
+    };
+    if request.jsonrpc != "2.0" {
+        return error_response(
+            request.id,
+            ApiError::new(INVALID_REQUEST, "Unsupported JSON-RPC version"),
+        );
+    }
+    match call_method(ui, command, &request.method, request.params) {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": request.id, "result": result}),
+        Err(err) => error_response(request.id, err),
+    }
+}
+
+fn error_response(id: Value, err: ApiError) -> Value {
+    let mut error = json!({"code": err.code, "message": err.message});
+    if !err.hints.is_empty() {
+        error["data"] = json!({"hints": err.hints});
+    }
+    json!({"jsonrpc": "2.0", "id": id, "error": error})
+}
+
+fn call_method(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    method: &str,
+    params: Value,
+) -> Result<Value, ApiError> {
+    match method {
+        "version" => Ok(json!({"api_version": API_VERSION})),
+        "log" => Ok(api_log(ui, command, parse_params(params)?)?),
+        "evaluate_revset" => Ok(api_evaluate_revset(ui, command, parse_params(params)?)?),
+        "status" => Ok(api_status(ui, command)?),
+        "diff" => Ok(api_diff(ui, command, parse_params(params)?)?),
+        "describe" => Ok(api_describe(ui, command, parse_params(params)?)?),
+        "new" => Ok(api_new(ui, command, parse_params(params)?)?),
+        "squash" => Ok(api_squash(ui, command, parse_params(params)?)?),
+        _ => Err(ApiError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method {method:?}"),
+        )),
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, ApiError> {
+    // Omitted parameters are the same as an empty object.
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|err| ApiError::new(INVALID_PARAMS, err.to_string()))
+}
+
+pub(crate) fn commit_json(commit: &Commit) -> Value {
+    json!({
+        "commit_id": commit.id().hex(),
+        "change_id": to_reverse_hex(&commit.change_id().hex()).unwrap(),
+        "description": commit.description(),
+    })
+}
+
+fn operation_json(id: &OperationId) -> Value {
+    id.hex().into()
+}
+
+fn resolve_revset(
+    settings: &UserSettings,
+    workspace_command: &WorkspaceCommandHelper,
+    revset: Option<String>,
+) -> Result<Vec<Commit>, CommandError> {
+    let expression = match revset {
+        Some(revset) => workspace_command.parse_revset(&RevisionArg::from(revset))?,
+        None => {
+            let expression = revset::parse(
+                &settings.default_revset(),
+                &workspace_command.revset_parse_context(),
+            )
+            .map_err(|err| config_error_with_message("Invalid `revsets.log`", err))?;
+            workspace_command.attach_revset_evaluator(expression)?
+        }
+    };
+    Ok(expression.evaluate_to_commits()?.try_collect()?)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LogParams {
+    /// Commits to render (default: `revsets.log`).
+    revset: Option<String>,
+    /// Template to render each commit with (default: `templates.log`).
+    template: Option<String>,
+}
+
+fn api_log(ui: &mut Ui, command: &CommandHelper, params: LogParams) -> Result<Value, CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let template_string = match params.template {
+        Some(template) => template,
+        None => command.settings().config().get_string("templates.log")?,
+    };
+    let template = workspace_command.parse_commit_template(&template_string)?;
+    let commits: Vec<_> = resolve_revset(command.settings(), &workspace_command, params.revset)?
+        .iter()
+        .map(|commit| -> Result<_, CommandError> {
+            let mut output = vec![];
+            template.format(commit, &mut PlainTextFormatter::new(&mut output))?;
+            let mut value = commit_json(commit);
+            value["output"] = String::from_utf8_lossy(&output).into();
+            Ok(value)
+        })
+        .try_collect()?;
+    Ok(json!({
+        "operation_id": operation_json(workspace_command.repo().op_id()),
+        "commits": commits,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EvaluateRevsetParams {
+    /// The revset to evaluate.
+    revset: String,
+}
+
+fn api_evaluate_revset(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    params: EvaluateRevsetParams,
+) -> Result<Value, CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let commits = resolve_revset(command.settings(), &workspace_command, Some(params.revset))?;
+    Ok(json!({
+        "operation_id": operation_json(workspace_command.repo().op_id()),
+        "commits": commits.iter().map(commit_json).collect_vec(),
+    }))
+}
+
+fn api_status(ui: &mut Ui, command: &CommandHelper) -> Result<Value, CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    status_json(&workspace_command, &EverythingMatcher)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiffParams {
+    /// Show the changes in this revision (default: @).
+    revision: Option<String>,
+    /// One of the `ui.diff.format` formats (default: "git").
+    format: Option<String>,
+}
+
+fn api_diff(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    params: DiffParams,
+) -> Result<Value, CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let commit = workspace_command.resolve_single_rev(&revision_arg(params.revision))?;
+    let format = diff_util::diff_format_from_name(
+        command.settings(),
+        params.format.as_deref().unwrap_or("git"),
+    )
+    .map_err(|err| user_error_with_message("Invalid diff format", err))?;
+    let from_tree = merge_commit_trees(workspace_command.repo().as_ref(), &commit.parents())?;
+    let mut output = vec![];
+    diff_util::show_diff(
+        ui,
+        &mut PlainTextFormatter::new(&mut output),
+        command.settings(),
+        &workspace_command,
+        &from_tree,
+        &commit.tree()?,
+        &EverythingMatcher,
+        commit.copies(),
+        &[format],
+    )?;
+    Ok(json!({
+        "operation_id": operation_json(workspace_command.repo().op_id()),
+        "commit": commit_json(&commit),
+        "diff": String::from_utf8_lossy(&output),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DescribeParams {
+    /// The revision to describe (default: @).
+    revision: Option<String>,
+    /// The new description.
+    message: String,
+}
+
+fn api_describe(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    params: DescribeParams,
+) -> Result<Value, CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let commit = workspace_command.resolve_single_rev(&revision_arg(params.revision))?;
+    workspace_command.check_rewritable([commit.id()])?;
+    let description = join_message_paragraphs(&[params.message]);
+    let mut tx = workspace_command.start_transaction();
+    let new_commit = tx
+        .mut_repo()
+        .rewrite_commit(command.settings(), &commit)
+        .set_description(description)
+        .write()?;
+    tx.finish(ui, format!("describe commit {}", commit.id().hex()))?;
+    Ok(json!({
+        "operation_id": operation_json(workspace_command.repo().op_id()),
+        "commit": commit_json(&new_commit),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewParams {
+    /// Parents of the new commit (default: ["@"]).
+    parents: Option<Vec<String>>,
+    /// Description of the new commit.
+    message: Option<String>,
+}
+
+fn api_new(ui: &mut Ui, command: &CommandHelper, params: NewParams) -> Result<Value, CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let parent_args = params
+        .parents
+        .unwrap_or_else(|| vec!["@".to_owned()])
+        .into_iter()
+        .map(RevisionArg::from)
+        .collect_vec();
+    let parents = workspace_command
+        .resolve_some_revsets_default_single(&parent_args)?
+        .into_iter()
+        .collect_vec();
+    let description = params
+        .message
+        .map(|message| join_message_paragraphs(&[message]))
+        .unwrap_or_default();
+    let mut tx = workspace_command.start_transaction();
+    let merged_tree = merge_commit_trees(tx.repo(), &parents)?;
+    let new_commit = tx
+        .mut_repo()
+        .new_commit(
+            command.settings(),
+            parents.iter().map(|parent| parent.id().clone()).collect(),
+            merged_tree.id(),
+        )
+        .set_description(description)
+        .write()?;
+    tx.edit(&new_commit).unwrap();
+    tx.finish(ui, "new empty commit")?;
+    Ok(json!({
+        "operation_id": operation_json(workspace_command.repo().op_id()),
+        "commit": commit_json(&new_commit),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SquashParams {
+    /// The revision to squash into its parent (default: @).
+    revision: Option<String>,
+    /// Description of the squashed commit. The non-empty descriptions of both
+    /// commits are concatenated by default.
+    message: Option<String>,
+}
+
+fn api_squash(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    params: SquashParams,
+) -> Result<Value, CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let source = workspace_command.resolve_single_rev(&revision_arg(params.revision))?;
+    let mut parents = source.parents();
+    if parents.len() != 1 {
+        return Err(user_error("Cannot squash merge commits"));
+    }
+    let destination = parents.pop().unwrap();
+    // The editor can't be used to combine the descriptions.
+    let description = match params.message {
+        Some(message) => join_message_paragraphs(&[message]),
+        None => [&destination, &source]
+            .iter()
+            .map(|commit| commit.description())
+            .filter(|description| !description.is_empty())
+            .join("\n"),
+    };
+    let diff_selector = workspace_command.diff_selector(ui, None, false)?;
+    let mut tx = workspace_command.start_transaction();
+    move_diff(
+        ui,
+        &mut tx,
+        command.settings(),
+        &[source],
+        &destination,
+        &EverythingMatcher,
+        &diff_selector,
+        Some(description),
+        false,
+        &[],
+    )?;
+    tx.finish(
+        ui,
+        format!("squash commits into {}", destination.id().hex()),
+    )?;
+    let repo = workspace_command.repo();
+    let new_commit = match repo.resolve_change_id(destination.change_id()).as_deref() {
+        Some([id]) => repo.store().get_commit(id)?,
+        _ => destination,
+    };
+    Ok(json!({
+        "operation_id": operation_json(repo.op_id()),
+        "commit": commit_json(&new_commit),
+    }))
+}
+
+fn revision_arg(revision: Option<String>) -> RevisionArg {
+    revision.map_or(RevisionArg::AT, RevisionArg::from)
+}
@@ -12,25 +12,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write;
+
+use itertools::Itertools;
 use jj_lib::object_id::ObjectId;
-use jj_lib::rewrite::back_out_commit;
+use jj_lib::rewrite::{back_out_commit, back_out_commits};
 use tracing::instrument;
 
 use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
-/// Apply the reverse of a revision on top of another revision
+/// Apply the reverse of revisions on top of another revision
+///
+/// If more than one revision is given, they're backed out in a chain of
+/// commits, starting from the newest one. With `--combine`, they're backed out
+/// in a single commit instead. The descriptions reference the backed-out
+/// changes.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct BackoutArgs {
-    /// The revision to apply the reverse of
-    #[arg(long, short, default_value = "@")]
-    revision: RevisionArg,
+    /// The revision(s) to apply the reverse of
+    #[arg(long = "revision", short, default_value = "@")]
+    revisions: Vec<RevisionArg>,
     /// The revision to apply the reverse changes on top of
     // TODO: It seems better to default this to `@-`. Maybe the working
     // copy should be rebased on top?
     #[arg(long, short, default_value = "@")]
     destination: Vec<RevisionArg>,
+    /// Back out all of the revisions in a single commit
+    #[arg(long)]
+    combine: bool,
 }
 
 #[instrument(skip_all)]
@@ -40,23 +51,50 @@ pub(crate) fn cmd_backout(
     args: &BackoutArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let commit_to_back_out = workspace_command.resolve_single_rev(&args.revision)?;
+    // The commits are in reverse topological order, so the descendants are
+    // backed out before their ancestors.
+    let commits_to_back_out: Vec<_> = workspace_command
+        .parse_union_revsets(&args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    if commits_to_back_out.is_empty() {
+        writeln!(ui.status(), "No revisions to back out.")?;
+        return Ok(());
+    }
     let mut parents = vec![];
     for revision_str in &args.destination {
         let destination = workspace_command.resolve_single_rev(revision_str)?;
         parents.push(destination);
     }
     let mut tx = workspace_command.start_transaction();
-    back_out_commit(
-        command.settings(),
-        tx.mut_repo(),
-        &commit_to_back_out,
-        &parents,
-    )?;
-    tx.finish(
-        ui,
-        format!("back out commit {}", commit_to_back_out.id().hex()),
-    )?;
+    if args.combine {
+        back_out_commits(
+            command.settings(),
+            tx.mut_repo(),
+            &commits_to_back_out,
+            &parents,
+        )?;
+    } else {
+        for commit_to_back_out in &commits_to_back_out {
+            let new_commit = back_out_commit(
+                command.settings(),
+                tx.mut_repo(),
+                commit_to_back_out,
+                &parents,
+            )?;
+            parents = vec![new_commit];
+        }
+    }
+    let transaction_description = if let [commit] = &commits_to_back_out[..] {
+        format!("back out commit {}", commit.id().hex())
+    } else {
+        format!(
+            "back out commit {} and {} more",
+            commits_to_back_out[0].id().hex(),
+            commits_to_back_out.len() - 1
+        )
+    };
+    tx.finish(ui, transaction_description)?;
 
     Ok(())
 }
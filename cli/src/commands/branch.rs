@@ -18,17 +18,21 @@ use std::io::Write as _;
 
 use clap::builder::NonEmptyStringValueParser;
 use itertools::Itertools;
+use jj_lib::backend::CommitId;
 use jj_lib::git;
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_store::{RefTarget, RemoteRef};
 use jj_lib::repo::Repo;
-use jj_lib::revset::{self, RevsetExpression};
+use jj_lib::revset::RevsetExpression;
 use jj_lib::str_util::StringPattern;
 use jj_lib::view::View;
 
-use crate::cli_util::{CommandHelper, RemoteBranchName, RemoteBranchNamePattern, RevisionArg};
+use crate::cli_util::{
+    CommandHelper, RemoteBranchName, RemoteBranchNamePattern, RevisionArg, WorkspaceCommandHelper,
+};
 use crate::command_error::{user_error, user_error_with_hint, CommandError};
-use crate::formatter::Formatter;
+use crate::commit_templater::{CommitTemplateLanguage, RefName};
+use crate::git_util::find_expired_remote_branches;
 use crate::ui::Ui;
 
 /// Manage branches.
@@ -45,8 +49,11 @@ pub enum BranchCommand {
     Forget(BranchForgetArgs),
     #[command(visible_alias("l"))]
     List(BranchListArgs),
+    #[command(visible_alias("m"))]
+    Move(BranchMoveArgs),
     #[command(visible_alias("r"))]
     Rename(BranchRenameArgs),
+    Resolve(BranchResolveArgs),
     #[command(visible_alias("s"))]
     Set(BranchSetArgs),
     #[command(visible_alias("t"))]
@@ -75,9 +82,22 @@ pub struct BranchDeleteArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(required_unless_present_any(&["glob"]), value_parser = StringPattern::parse)]
+    ///
+    /// With `--revisions`, only the branches matching these names are deleted.
+    #[arg(
+        required_unless_present_any(&["glob", "revisions"]),
+        value_parser = StringPattern::parse,
+    )]
     pub names: Vec<StringPattern>,
 
+    /// Delete the branches whose local targets are in the given revisions
+    #[arg(long, short)]
+    revisions: Vec<RevisionArg>,
+
+    /// Only list the branches which would be deleted
+    #[arg(long)]
+    dry_run: bool,
+
     /// Deprecated. Please prefix the pattern with `glob:` instead.
     #[arg(long, hide = true, value_parser = StringPattern::glob)]
     pub glob: Vec<StringPattern>,
@@ -97,6 +117,9 @@ pub struct BranchDeleteArgs {
 pub struct BranchListArgs {
     /// Show all tracking and non-tracking remote branches including the ones
     /// whose targets are synchronized with the local branches.
+    ///
+    /// Non-tracking remote branches which expired according to the
+    /// `git.remote-branch-expiry` config are shown greyed out.
     #[arg(long, short, alias = "all")]
     all_remotes: bool,
 
@@ -123,6 +146,14 @@ pub struct BranchListArgs {
     /// wouldn't have a local target.
     #[arg(long, short)]
     revisions: Vec<RevisionArg>,
+
+    /// Render each branch using the given template
+    ///
+    /// All 0-argument methods of the `RefName` type are available as keywords.
+    ///
+    /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
+    #[arg(long, short = 'T')]
+    template: Option<String>,
 }
 
 /// Forget everything about a branch, including its local and remote
@@ -137,14 +168,63 @@ pub struct BranchForgetArgs {
     /// By default, the specified name matches exactly. Use `glob:` prefix to
     /// select branches by wildcard pattern. For details, see
     /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
-    #[arg(required_unless_present_any(&["glob"]), value_parser = StringPattern::parse)]
+    ///
+    /// With `--revisions`, only the branches matching these names are
+    /// forgotten.
+    #[arg(
+        required_unless_present_any(&["glob", "revisions"]),
+        value_parser = StringPattern::parse,
+    )]
     pub names: Vec<StringPattern>,
 
+    /// Forget the branches whose local targets are in the given revisions
+    #[arg(long, short)]
+    revisions: Vec<RevisionArg>,
+
+    /// Only list the branches which would be forgotten
+    #[arg(long)]
+    dry_run: bool,
+
     /// Deprecated. Please prefix the pattern with `glob:` instead.
     #[arg(long, hide = true, value_parser = StringPattern::glob)]
     pub glob: Vec<StringPattern>,
 }
 
+/// Move existing branches to a revision
+///
+/// The branches can be selected by name, by the revisions they point to with
+/// `--from`, or both. For example, `jj branch move --from 'heads(::@- &
+/// branches())'` moves the branches closest to the working-copy commit onto
+/// it.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BranchMoveArgs {
+    /// Move the branches whose local targets are in the given revisions
+    #[arg(long, required_unless_present = "names")]
+    from: Vec<RevisionArg>,
+
+    /// The revision to move the branches to
+    #[arg(long, default_value = "@")]
+    to: RevisionArg,
+
+    /// Allow moving the branches backwards or sideways.
+    #[arg(long, short = 'B')]
+    allow_backwards: bool,
+
+    /// Only list the branches which would be moved
+    #[arg(long)]
+    dry_run: bool,
+
+    /// The branches to move
+    ///
+    /// By default, the specified name matches exactly. Use `glob:` prefix to
+    /// select branches by wildcard pattern. For details, see
+    /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#string-patterns.
+    ///
+    /// With `--from`, only the branches matching these names are moved.
+    #[arg(value_parser = StringPattern::parse)]
+    names: Vec<StringPattern>,
+}
+
 /// Rename `old` branch name to `new` branch name.
 ///
 /// The new branch name points at the same commit as the old
@@ -174,6 +254,22 @@ pub struct BranchSetArgs {
     pub names: Vec<String>,
 }
 
+/// Resolve a conflicted branch by keeping one of its targets
+///
+/// A branch becomes conflicted when it is moved both locally and on a remote,
+/// or in concurrent operations. `--keep remote` keeps the target of the
+/// tracked remote branches, `--keep local` keeps the other one. Any other value
+/// is resolved as a revision to point the branch to.
+#[derive(clap::Args, Clone, Debug)]
+pub struct BranchResolveArgs {
+    /// The target to keep: `local`, `remote`, or a revision.
+    #[arg(long, value_name = "local|remote|REV")]
+    keep: RevisionArg,
+
+    /// The conflicted branch to resolve.
+    name: String,
+}
+
 /// Start tracking given remote branches
 ///
 /// A tracking remote branch will be imported as a local branch of the same
@@ -225,11 +321,13 @@ pub fn cmd_branch(
         BranchCommand::Create(sub_args) => cmd_branch_create(ui, command, sub_args),
         BranchCommand::Rename(sub_args) => cmd_branch_rename(ui, command, sub_args),
         BranchCommand::Set(sub_args) => cmd_branch_set(ui, command, sub_args),
+        BranchCommand::Resolve(sub_args) => cmd_branch_resolve(ui, command, sub_args),
         BranchCommand::Delete(sub_args) => cmd_branch_delete(ui, command, sub_args),
         BranchCommand::Forget(sub_args) => cmd_branch_forget(ui, command, sub_args),
         BranchCommand::Track(sub_args) => cmd_branch_track(ui, command, sub_args),
         BranchCommand::Untrack(sub_args) => cmd_branch_untrack(ui, command, sub_args),
         BranchCommand::List(sub_args) => cmd_branch_list(ui, command, sub_args),
+        BranchCommand::Move(sub_args) => cmd_branch_move(ui, command, sub_args),
     }
 }
 
@@ -391,6 +489,155 @@ fn cmd_branch_set(
     Ok(())
 }
 
+fn cmd_branch_resolve(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BranchResolveArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let name = &args.name;
+    let view = workspace_command.repo().view();
+    let local_target = view.get_local_branch(name);
+    if local_target.is_absent() {
+        return Err(user_error(format!("No such branch: {name}")));
+    }
+    if !local_target.has_conflict() {
+        return Err(user_error(format!("Branch {name} is not conflicted")));
+    }
+    let remote_ids: HashSet<_> = view
+        .all_remote_branches()
+        .filter(|&((branch, remote), remote_ref)| {
+            branch == name
+                && remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO
+                && remote_ref.is_tracking()
+        })
+        .flat_map(|(_, remote_ref)| remote_ref.target.added_ids())
+        .collect();
+    let pick_side = |keep_remote: bool| -> Result<CommitId, CommandError> {
+        let side = if keep_remote { "remote" } else { "local" };
+        let candidates = local_target
+            .added_ids()
+            .filter(|id| remote_ids.contains(id) == keep_remote)
+            .unique()
+            .collect_vec();
+        match candidates.as_slice() {
+            [id] => Ok((*id).clone()),
+            [] => Err(user_error_with_hint(
+                format!("Branch {name} has no {side} target"),
+                "Use `--keep REV` to choose the target.",
+            )),
+            _ => Err(user_error_with_hint(
+                format!("Branch {name} has multiple {side} targets"),
+                "Use `--keep REV` to choose the target.",
+            )),
+        }
+    };
+    let target_id = match args.keep.as_ref() {
+        "local" => pick_side(false)?,
+        "remote" => pick_side(true)?,
+        _ => workspace_command
+            .resolve_single_rev(&args.keep)?
+            .id()
+            .clone(),
+    };
+
+    let mut tx = workspace_command.start_transaction();
+    tx.mut_repo()
+        .set_local_branch_target(name, RefTarget::normal(target_id.clone()));
+    tx.finish(
+        ui,
+        format!(
+            "resolve conflicted branch {name} to commit {}",
+            target_id.hex()
+        ),
+    )?;
+    Ok(())
+}
+
+fn cmd_branch_move(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BranchMoveArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let target_commit = workspace_command.resolve_single_rev(&args.to)?;
+    let names = select_branches(
+        &workspace_command,
+        &args.names,
+        &args.from,
+        find_local_branches,
+    )?;
+    let repo = workspace_command.repo().as_ref();
+    if !args.allow_backwards {
+        for name in &names {
+            let old_target = repo.view().get_local_branch(name);
+            let is_fast_forward = old_target
+                .added_ids()
+                .any(|old| repo.index().is_ancestor(old, target_commit.id()));
+            if !is_fast_forward {
+                return Err(user_error_with_hint(
+                    format!("Refusing to move branch backwards or sideways: {name}"),
+                    "Use --allow-backwards to allow it.",
+                ));
+            }
+        }
+    }
+    if args.dry_run {
+        writeln!(ui.status(), "Would move {}.", make_branch_term(&names))?;
+        return Ok(());
+    }
+
+    let mut tx = workspace_command.start_transaction();
+    for name in &names {
+        tx.mut_repo()
+            .set_local_branch_target(name, RefTarget::normal(target_commit.id().clone()));
+    }
+    tx.finish(
+        ui,
+        format!(
+            "point {} to commit {}",
+            make_branch_term(&names),
+            target_commit.id().hex()
+        ),
+    )?;
+    if names.len() > 1 {
+        writeln!(ui.status(), "Moved {} branches.", names.len())?;
+    }
+    Ok(())
+}
+
+/// Finds the branches matching `name_patterns`, or with `revisions`, the
+/// local branches pointing to them, filtered by `name_patterns` if any.
+fn select_branches(
+    workspace_command: &WorkspaceCommandHelper,
+    name_patterns: &[StringPattern],
+    revisions: &[RevisionArg],
+    find_by_name: impl FnOnce(&View, &[StringPattern]) -> Result<Vec<String>, CommandError>,
+) -> Result<Vec<String>, CommandError> {
+    let view = workspace_command.repo().view();
+    if revisions.is_empty() {
+        return find_by_name(view, name_patterns);
+    }
+    let mut expression = workspace_command.parse_union_revsets(revisions)?;
+    // Intersects with the set of local branch targets to minimize the lookup space.
+    expression.intersect_with(&RevsetExpression::branches(StringPattern::everything()));
+    let filtered_targets: HashSet<_> = expression.evaluate_to_commit_ids()?.collect();
+    let names = view
+        .local_branches()
+        .filter(|(_, target)| target.added_ids().any(|id| filtered_targets.contains(id)))
+        .filter(|(name, _)| {
+            name_patterns.is_empty() || name_patterns.iter().any(|pattern| pattern.matches(name))
+        })
+        .map(|(name, _)| name.to_owned())
+        .collect_vec();
+    if names.is_empty() {
+        return Err(user_error(
+            "No matching branches point to the given revisions",
+        ));
+    }
+    Ok(names)
+}
+
 fn find_local_branches(
     view: &View,
     name_patterns: &[StringPattern],
@@ -483,7 +730,6 @@ fn cmd_branch_delete(
     args: &BranchDeleteArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let view = workspace_command.repo().view();
     if !args.glob.is_empty() {
         writeln!(
             ui.warning_default(),
@@ -491,7 +737,16 @@ fn cmd_branch_delete(
         )?;
     }
     let name_patterns = [&args.names[..], &args.glob[..]].concat();
-    let names = find_local_branches(view, &name_patterns)?;
+    let names = select_branches(
+        &workspace_command,
+        &name_patterns,
+        &args.revisions,
+        find_local_branches,
+    )?;
+    if args.dry_run {
+        writeln!(ui.status(), "Would delete {}.", make_branch_term(&names))?;
+        return Ok(());
+    }
     let mut tx = workspace_command.start_transaction();
     for branch_name in names.iter() {
         tx.mut_repo()
@@ -510,7 +765,6 @@ fn cmd_branch_forget(
     args: &BranchForgetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let view = workspace_command.repo().view();
     if !args.glob.is_empty() {
         writeln!(
             ui.warning_default(),
@@ -518,7 +772,16 @@ fn cmd_branch_forget(
         )?;
     }
     let name_patterns = [&args.names[..], &args.glob[..]].concat();
-    let names = find_forgettable_branches(view, &name_patterns)?;
+    let names = select_branches(
+        &workspace_command,
+        &name_patterns,
+        &args.revisions,
+        find_forgettable_branches,
+    )?;
+    if args.dry_run {
+        writeln!(ui.status(), "Would forget {}.", make_branch_term(&names))?;
+        return Ok(());
+    }
     let mut tx = workspace_command.start_transaction();
     for branch_name in names.iter() {
         tx.mut_repo().remove_branch(branch_name);
@@ -642,43 +905,31 @@ fn cmd_branch_list(
         None
     };
 
-    let no_branches_template = workspace_command.parse_commit_template(
-        &command
-            .settings()
-            .config()
-            .get_string("templates.commit_summary_no_branches")?,
-    )?;
-    let print_branch_target =
-        |formatter: &mut dyn Formatter, target: &RefTarget| -> Result<(), CommandError> {
-            if let Some(id) = target.as_normal() {
-                write!(formatter, ": ")?;
-                let commit = repo.store().get_commit(id)?;
-                no_branches_template.format(&commit, formatter)?;
-                writeln!(formatter)?;
-            } else {
-                write!(formatter, " ")?;
-                write!(formatter.labeled("conflict"), "(conflicted)")?;
-                writeln!(formatter, ":")?;
-                for id in target.removed_ids() {
-                    let commit = repo.store().get_commit(id)?;
-                    write!(formatter, "  - ")?;
-                    no_branches_template.format(&commit, formatter)?;
-                    writeln!(formatter)?;
-                }
-                for id in target.added_ids() {
-                    let commit = repo.store().get_commit(id)?;
-                    write!(formatter, "  + ")?;
-                    no_branches_template.format(&commit, formatter)?;
-                    writeln!(formatter)?;
-                }
-            }
-            Ok(())
+    let template = {
+        let language = workspace_command.commit_template_language()?;
+        let text = match &args.template {
+            Some(value) => value.to_owned(),
+            None => command
+                .settings()
+                .config()
+                .get_string("templates.branch_list")?,
         };
+        workspace_command.parse_template(&language, &text, CommitTemplateLanguage::wrap_ref_name)?
+    };
+
+    let expired_remote_branches: HashSet<_> = if args.all_remotes {
+        find_expired_remote_branches(repo.as_ref(), command.settings())?
+            .into_iter()
+            .collect()
+    } else {
+        HashSet::new()
+    };
 
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
-    let formatter = formatter.as_mut();
+    formatter.push_label("branch_list")?;
 
+    let mut found_conflicted = false;
     let branches_to_list = view.branches().filter(|(name, target)| {
         branch_names_to_list
             .as_ref()
@@ -686,9 +937,12 @@ fn cmd_branch_list(
             && (!args.conflicted || target.local_target.has_conflict())
     });
     for (name, branch_target) in branches_to_list {
-        let (mut tracking_remote_refs, untracked_remote_refs) = branch_target
-            .remote_refs
-            .into_iter()
+        let local_target = branch_target.local_target;
+        found_conflicted |= local_target.has_conflict();
+        let remote_refs = branch_target.remote_refs;
+        let (mut tracking_remote_refs, untracked_remote_refs) = remote_refs
+            .iter()
+            .copied()
             .partition::<Vec<_>, _>(|&(_, remote_ref)| remote_ref.is_tracking());
 
         if args.tracked {
@@ -696,63 +950,25 @@ fn cmd_branch_list(
                 .retain(|&(remote, _)| remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO);
         }
 
-        if !args.tracked && branch_target.local_target.is_present()
-            || !tracking_remote_refs.is_empty()
-        {
-            write!(formatter.labeled("branch"), "{name}")?;
-            if branch_target.local_target.is_present() {
-                print_branch_target(formatter, branch_target.local_target)?;
-            } else {
-                writeln!(formatter, " (deleted)")?;
-            }
+        if !args.tracked && local_target.is_present() || !tracking_remote_refs.is_empty() {
+            let ref_name = RefName::local(
+                name,
+                local_target.clone(),
+                remote_refs.iter().map(|&(_, remote_ref)| remote_ref),
+            );
+            template.format(&ref_name, formatter.as_mut())?;
         }
 
         for &(remote, remote_ref) in &tracking_remote_refs {
-            let synced = remote_ref.target == *branch_target.local_target;
-
+            let synced = remote_ref.target == *local_target;
             if !args.all_remotes && !args.tracked && synced {
                 continue;
             }
-            write!(formatter, "  ")?;
-            write!(formatter.labeled("branch"), "@{remote}")?;
-            let local_target = branch_target.local_target;
-            if local_target.is_present() && !synced {
-                let remote_added_ids = remote_ref.target.added_ids().cloned().collect_vec();
-                let local_added_ids = local_target.added_ids().cloned().collect_vec();
-                let (remote_ahead_lower, remote_ahead_upper) =
-                    revset::walk_revs(repo.as_ref(), &remote_added_ids, &local_added_ids)?
-                        .count_estimate();
-                let (local_ahead_lower, local_ahead_upper) =
-                    revset::walk_revs(repo.as_ref(), &local_added_ids, &remote_added_ids)?
-                        .count_estimate();
-                let remote_ahead_message = match remote_ahead_upper {
-                    Some(0) => None,
-                    Some(upper) if upper == remote_ahead_lower => {
-                        Some(format!("ahead by {remote_ahead_lower} commits"))
-                    }
-                    _ => Some(format!("ahead by at least {remote_ahead_lower} commits")),
-                };
-                let local_ahead_message = match local_ahead_upper {
-                    Some(0) => None,
-                    Some(upper) if upper == local_ahead_lower => {
-                        Some(format!("behind by {local_ahead_lower} commits"))
-                    }
-                    _ => Some(format!("behind by at least {local_ahead_lower} commits")),
-                };
-                match (remote_ahead_message, local_ahead_message) {
-                    (Some(rm), Some(lm)) => {
-                        write!(formatter, " ({rm}, {lm})")?;
-                    }
-                    (Some(m), None) | (None, Some(m)) => {
-                        write!(formatter, " ({m})")?;
-                    }
-                    (None, None) => { /* do nothing */ }
-                }
-            }
-            print_branch_target(formatter, &remote_ref.target)?;
+            let ref_name = RefName::remote(name, remote, remote_ref.clone(), local_target);
+            template.format(&ref_name, formatter.as_mut())?;
         }
 
-        if branch_target.local_target.is_absent() && !tracking_remote_refs.is_empty() {
+        if local_target.is_absent() && !tracking_remote_refs.is_empty() {
             let found_non_git_remote = tracking_remote_refs
                 .iter()
                 .any(|&(remote, _)| remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO);
@@ -773,11 +989,28 @@ fn cmd_branch_list(
 
         if args.all_remotes {
             for &(remote, remote_ref) in &untracked_remote_refs {
-                write!(formatter.labeled("branch"), "{name}@{remote}")?;
-                print_branch_target(formatter, &remote_ref.target)?;
+                let ref_name = RefName::remote_only(name, remote, remote_ref.target.clone());
+                if expired_remote_branches.contains(&(name.to_owned(), remote.to_owned())) {
+                    formatter
+                        .with_label("expired", |formatter| template.format(&ref_name, formatter))?;
+                } else {
+                    template.format(&ref_name, formatter.as_mut())?;
+                }
             }
         }
     }
 
+    formatter.pop_label()?;
+    drop(formatter);
+
+    if found_conflicted {
+        if let Some(mut writer) = ui.hint_default() {
+            writeln!(
+                writer,
+                "Some branches have conflicts. Use `jj branch resolve <name> --keep \
+                 local|remote|<rev>` to resolve them."
+            )?;
+        }
+    }
     Ok(())
 }
@@ -12,10 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Write;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
+use itertools::Itertools;
 use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+use jj_lib::matchers::PrefixMatcher;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::{RepoPath, RepoPathBuf};
 use pollster::FutureExt;
 use tracing::instrument;
 
@@ -23,15 +27,26 @@ use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::{user_error, CommandError};
 use crate::ui::Ui;
 
-/// Print contents of a file in a revision
+/// Print contents of files in a revision
+///
+/// If more than one file is given, or a directory which contains more than
+/// one file, each file is preceded by a `==> path <==` header. The target of a
+/// symlink is printed as its contents. Conflicted files are printed with
+/// conflict markers.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct CatArgs {
     /// The revision to get the file contents from
     #[arg(long, short, default_value = "@")]
     revision: RevisionArg,
-    /// The file to print
-    #[arg(value_hint = clap::ValueHint::FilePath)]
-    path: String,
+    /// Write the files into this directory instead of printing them
+    ///
+    /// The files are written at their paths relative to the repository root,
+    /// replacing any existing files.
+    #[arg(long, short, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    output: Option<PathBuf>,
+    /// The files to print. Directories are printed recursively.
+    #[arg(required = true, value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
 }
 
 #[instrument(skip_all)]
@@ -44,27 +59,133 @@ pub(crate) fn cmd_cat(
     let commit = workspace_command.resolve_single_rev(&args.revision)?;
     let tree = commit.tree()?;
     // TODO: migrate to .parse_file_patterns()?.to_matcher()?
-    let path = workspace_command.parse_file_path(&args.path)?;
+    let paths: Vec<RepoPathBuf> = args
+        .paths
+        .iter()
+        .map(|path| workspace_command.parse_file_path(path))
+        .try_collect()?;
+    for (path, repo_path) in args.paths.iter().zip(&paths) {
+        if tree.path_value(repo_path).is_absent() {
+            return Err(user_error(format!("No such path: {path}")));
+        }
+    }
+    let matcher = PrefixMatcher::new(&paths);
+    let entries = tree.entries_matching(&matcher).collect_vec();
     let repo = workspace_command.repo();
-    let value = tree.path_value(&path);
-    let materialized = materialize_tree_value(repo.store(), &path, value).block_on()?;
+    if let Some(output_dir) = &args.output {
+        for (path, value) in &entries {
+            let materialized = materialize_tree_value(
+                repo.store(),
+                path,
+                value.clone(),
+                workspace_command.conflict_marker_options(),
+            )
+            .block_on()?;
+            write_to_dir(output_dir, path, materialized)?;
+        }
+        writeln!(
+            ui.status(),
+            "Wrote {} files to {}",
+            entries.len(),
+            output_dir.display()
+        )?;
+        return Ok(());
+    }
+
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let print_headers = entries.len() > 1;
+    for (path, value) in entries {
+        let materialized = materialize_tree_value(
+            repo.store(),
+            &path,
+            value,
+            workspace_command.conflict_marker_options(),
+        )
+        .block_on()?;
+        if let MaterializedTreeValue::GitSubmodule(_) = materialized {
+            writeln!(
+                ui.warning_default(),
+                "Skipping Git submodule {}",
+                workspace_command.format_file_path(&path)
+            )?;
+            continue;
+        }
+        if print_headers {
+            writeln!(
+                formatter,
+                "==> {} <==",
+                workspace_command.format_file_path(&path)
+            )?;
+        }
+        match materialized {
+            MaterializedTreeValue::File { mut reader, .. } => {
+                io::copy(&mut reader, &mut formatter.as_mut())?;
+            }
+            MaterializedTreeValue::Symlink { target, .. } => {
+                formatter.write_all(target.as_bytes())?;
+            }
+            MaterializedTreeValue::Conflict { contents, .. } => {
+                formatter.write_all(&contents)?;
+            }
+            MaterializedTreeValue::Absent
+            | MaterializedTreeValue::Tree(_)
+            | MaterializedTreeValue::GitSubmodule(_) => {
+                panic!("unexpected tree entry at {path:?}")
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_to_dir(
+    output_dir: &Path,
+    path: &RepoPath,
+    materialized: MaterializedTreeValue,
+) -> io::Result<()> {
+    let disk_path = path.to_fs_path(output_dir);
+    if let Some(parent) = disk_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Don't write through an existing symlink
+    if disk_path.symlink_metadata().is_ok() {
+        std::fs::remove_file(&disk_path)?;
+    }
     match materialized {
-        MaterializedTreeValue::Absent => {
-            return Err(user_error("No such path"));
+        MaterializedTreeValue::File {
+            mut reader,
+            executable,
+            ..
+        } => {
+            let mut file = std::fs::File::create(&disk_path)?;
+            io::copy(&mut reader, &mut file)?;
+            set_executable(&disk_path, executable)?;
         }
-        MaterializedTreeValue::File { mut reader, .. } => {
-            ui.request_pager();
-            std::io::copy(&mut reader, &mut ui.stdout_formatter().as_mut())?;
+        MaterializedTreeValue::Symlink { target, .. } => {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(target, &disk_path)?;
+            #[cfg(not(unix))]
+            std::fs::write(&disk_path, target)?;
         }
         MaterializedTreeValue::Conflict { contents, .. } => {
-            ui.request_pager();
-            ui.stdout_formatter().write_all(&contents)?;
+            std::fs::write(&disk_path, contents)?;
         }
-        MaterializedTreeValue::Symlink { .. }
-        | MaterializedTreeValue::Tree(_)
-        | MaterializedTreeValue::GitSubmodule(_) => {
-            return Err(user_error("Path exists but is not a file"));
+        MaterializedTreeValue::GitSubmodule(_) => {}
+        MaterializedTreeValue::Absent | MaterializedTreeValue::Tree(_) => {
+            panic!("unexpected tree entry at {path:?}")
         }
     }
     Ok(())
 }
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if executable { 0o755 } else { 0o644 };
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> io::Result<()> {
+    Ok(())
+}
@@ -12,38 +12,56 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::io::Read as _;
+use std::slice;
+
 use itertools::Itertools;
 use jj_lib::backend::TreeValue;
+use jj_lib::fileset::{FilePattern, FilesetExpression};
 use jj_lib::merged_tree::MergedTreeBuilder;
 use jj_lib::object_id::ObjectId;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::store::Store;
 use tracing::instrument;
 
-use crate::cli_util::{CommandHelper, RevisionArg};
+use crate::cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper};
 use crate::command_error::{user_error, CommandError};
 use crate::ui::Ui;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
 enum ChmodMode {
-    /// Make a path non-executable (alias: normal)
-    // We use short names for enum values so that errors say that the possible values are `n, x`.
+    /// Make a path a non-executable file (alias: normal)
+    // We use short names for enum values so that errors say that the possible values are `n, x, l`.
     #[value(name = "n", alias("normal"))]
     Normal,
-    /// Make a path executable (alias: executable)
+    /// Make a path an executable file (alias: executable)
     #[value(name = "x", alias("executable"))]
     Executable,
+    /// Make a path a symlink to the path in its contents (alias: symlink)
+    #[value(name = "l", alias("symlink"))]
+    Symlink,
 }
 
 /// Sets or removes the executable bit for paths in the repo
 ///
 /// Unlike the POSIX `chmod`, `jj chmod` also works on Windows, on conflicted
 /// files, and on arbitrary revisions.
+///
+/// The paths can be directories and file patterns, in which case all the
+/// files they match are changed.
+///
+/// The `l` mode turns files into symlinks to the path in their contents. The
+/// other modes turn symlinks into files containing the target of the link.
+/// Files and symlinks are only converted when their path is given explicitly,
+/// not when they are matched through a directory or a pattern.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct ChmodArgs {
     mode: ChmodMode,
     /// The revision to update
     #[arg(long, short, default_value = "@")]
     revision: RevisionArg,
-    /// Paths to change the executable bit for
+    /// Paths to change the mode of
     #[arg(required = true, value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
 }
@@ -54,58 +72,66 @@ pub(crate) fn cmd_chmod(
     command: &CommandHelper,
     args: &ChmodArgs,
 ) -> Result<(), CommandError> {
-    let executable_bit = match args.mode {
-        ChmodMode::Executable => true,
-        ChmodMode::Normal => false,
-    };
-
     let mut workspace_command = command.workspace_helper(ui)?;
-    // TODO: migrate to .parse_file_patterns()?.to_matcher()
-    let repo_paths: Vec<_> = args
-        .paths
-        .iter()
-        .map(|path| workspace_command.parse_file_path(path))
-        .try_collect()?;
     let commit = workspace_command.resolve_single_rev(&args.revision)?;
     workspace_command.check_rewritable([commit.id()])?;
+    let tree = commit.tree()?;
+    // Match the paths one by one to report the ones which don't match anything,
+    // and to tell the explicitly named paths from the ones found under them
+    let mut entries = BTreeMap::new();
+    for path in &args.paths {
+        let expression = workspace_command.parse_file_patterns(slice::from_ref(path))?;
+        let explicit_path = match &expression {
+            FilesetExpression::Pattern(
+                FilePattern::FilePath(pattern_path) | FilePattern::PrefixPath(pattern_path),
+            ) => Some(pattern_path.clone()),
+            _ => None,
+        };
+        let matcher = expression.to_matcher();
+        let matched_entries = tree.entries_matching(matcher.as_ref()).collect_vec();
+        if matched_entries.is_empty() {
+            return Err(user_error(format!("No such path at '{path}'.")));
+        }
+        for (repo_path, tree_value) in matched_entries {
+            let explicit = explicit_path.as_ref() == Some(&repo_path);
+            let (_, entry_explicit) = entries.entry(repo_path).or_insert((tree_value, false));
+            *entry_explicit |= explicit;
+        }
+    }
 
     let mut tx = workspace_command.start_transaction();
-    let tree = commit.tree()?;
     let store = tree.store();
     let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
-    for repo_path in repo_paths {
+    for (repo_path, (tree_value, explicit)) in entries {
         let user_error_with_path = |msg: &str| {
             user_error(format!(
                 "{msg} at '{}'.",
                 tx.base_workspace_helper().format_file_path(&repo_path)
             ))
         };
-        let tree_value = tree.path_value(&repo_path);
-        if tree_value.is_absent() {
-            return Err(user_error_with_path("No such path"));
-        }
         let all_files = tree_value
             .adds()
             .flatten()
-            .all(|tree_value| matches!(tree_value, TreeValue::File { .. }));
+            .all(|tree_value| matches!(tree_value, TreeValue::File { .. } | TreeValue::Symlink(_)));
         if !all_files {
             let message = if tree_value.is_resolved() {
-                "Found neither a file nor a conflict"
+                "Found neither a file, a symlink nor a conflict"
             } else {
                 "Some of the sides of the conflict are not files"
             };
             return Err(user_error_with_path(message));
         }
-        let new_tree_value = tree_value.map(|value| match value {
-            Some(TreeValue::File { id, executable: _ }) => Some(TreeValue::File {
-                id: id.clone(),
-                executable: executable_bit,
-            }),
-            Some(TreeValue::Conflict(_)) => {
-                panic!("Conflict sides must not themselves be conflicts")
-            }
-            value => value.clone(),
-        });
+        let new_tree_value = tree_value.try_map(|value| match value {
+            Some(value) => Ok(Some(change_mode(
+                tx.base_workspace_helper(),
+                store,
+                &repo_path,
+                value,
+                args.mode,
+                explicit,
+            )?)),
+            None => Ok::<_, CommandError>(None),
+        })?;
         tree_builder.set_or_remove(repo_path, new_tree_value);
     }
 
@@ -118,12 +144,56 @@ pub(crate) fn cmd_chmod(
         ui,
         format!(
             "make paths {} in commit {}",
-            if executable_bit {
-                "executable"
-            } else {
-                "non-executable"
+            match args.mode {
+                ChmodMode::Normal => "non-executable",
+                ChmodMode::Executable => "executable",
+                ChmodMode::Symlink => "symlinks",
             },
             commit.id().hex(),
         ),
     )
 }
+
+/// Returns `value` with the given mode, converting files to symlinks and back
+/// if needed and the path was named `explicit`ly.
+fn change_mode(
+    workspace_command: &WorkspaceCommandHelper,
+    store: &Store,
+    path: &RepoPath,
+    value: &TreeValue,
+    mode: ChmodMode,
+    explicit: bool,
+) -> Result<TreeValue, CommandError> {
+    let executable = mode == ChmodMode::Executable;
+    let new_value = match (value, mode) {
+        (TreeValue::File { id, executable: _ }, ChmodMode::Normal | ChmodMode::Executable) => {
+            TreeValue::File {
+                id: id.clone(),
+                executable,
+            }
+        }
+        (TreeValue::File { id, executable: _ }, ChmodMode::Symlink) if explicit => {
+            let mut contents = vec![];
+            store.read_file(path, id)?.read_to_end(&mut contents)?;
+            let target = String::from_utf8(contents).map_err(|_| {
+                user_error(format!(
+                    "Contents of '{}' aren't valid UTF-8, so it can't be made a symlink.",
+                    workspace_command.format_file_path(path)
+                ))
+            })?;
+            TreeValue::Symlink(store.write_symlink(path, &target)?)
+        }
+        (TreeValue::Symlink(id), ChmodMode::Normal | ChmodMode::Executable) if explicit => {
+            let target = store.read_symlink(path, id)?;
+            TreeValue::File {
+                id: store.write_file(path, &mut target.as_bytes())?,
+                executable,
+            }
+        }
+        (TreeValue::Conflict(_), _) => {
+            panic!("Conflict sides must not themselves be conflicts")
+        }
+        (value, _) => value.clone(),
+    };
+    Ok(new_value)
+}
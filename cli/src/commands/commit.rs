@@ -12,15 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use itertools::Itertools as _;
 use jj_lib::object_id::ObjectId;
+use jj_lib::op_store::RefTarget;
 use jj_lib::repo::Repo;
 use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
+use jj_lib::str_util::StringPattern;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
-use crate::command_error::{user_error, CommandError};
+use crate::command_error::{config_error_with_message, user_error, CommandError};
+use crate::commands::describe::AuthorArgs;
 use crate::description_util::{
-    description_template_for_commit, edit_description, join_message_paragraphs,
+    add_trailers, description_template_for_commit, edit_description, join_message_paragraphs,
+    lint_description,
 };
 use crate::ui::Ui;
 
@@ -37,6 +43,19 @@ pub(crate) struct CommitArgs {
     /// The change description to use (don't open editor)
     #[arg(long = "message", short, value_name = "MESSAGE")]
     message_paragraphs: Vec<String>,
+    /// Add a `Signed-off-by` trailer for the configured user
+    #[arg(long)]
+    signoff: bool,
+    #[command(flatten)]
+    author_args: AuthorArgs,
+    /// Move the branches pointing to the parent of the working-copy commit to
+    /// the committed revision
+    ///
+    /// This is done for the branches matching `advance-branches.enabled-branches`
+    /// even without this flag. Branches are only advanced if the working-copy
+    /// commit has a single parent.
+    #[arg(long)]
+    advance_branch: bool,
     /// Put these paths in the first commit
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -99,15 +118,37 @@ new working-copy commit.
     let description = if !args.message_paragraphs.is_empty() {
         join_message_paragraphs(&args.message_paragraphs)
     } else {
-        edit_description(tx.base_repo(), &template, command.settings())?
+        edit_description(ui, tx.base_repo(), &template, command.settings())?
     };
+    let description = add_trailers(
+        command.settings(),
+        commit.change_id(),
+        args.signoff,
+        &description,
+    )?;
+    lint_description(ui, command.settings(), &description)?;
 
-    let new_commit = tx
+    let commit_builder = tx
         .mut_repo()
         .rewrite_commit(command.settings(), &commit)
         .set_tree_id(tree_id)
-        .set_description(description)
-        .write()?;
+        .set_description(description);
+    let new_commit = args.author_args.apply(commit_builder).write()?;
+    if let [parent_id] = commit.parent_ids() {
+        let patterns = advance_branch_patterns(command.settings(), args.advance_branch)?;
+        let branch_names = tx
+            .repo()
+            .view()
+            .local_branches()
+            .filter(|(_, target)| target.as_normal() == Some(parent_id))
+            .filter(|(name, _)| patterns.iter().any(|pattern| pattern.matches(name)))
+            .map(|(name, _)| name.to_owned())
+            .collect_vec();
+        for name in branch_names {
+            tx.mut_repo()
+                .set_local_branch_target(&name, RefTarget::normal(new_commit.id().clone()));
+        }
+    }
     let workspace_ids = tx
         .mut_repo()
         .view()
@@ -128,3 +169,29 @@ new working-copy commit.
     tx.finish(ui, format!("commit {}", commit.id().hex()))?;
     Ok(())
 }
+
+/// Returns the patterns of the branches to advance. With `--advance-branch`,
+/// all branches are advanced.
+fn advance_branch_patterns(
+    settings: &UserSettings,
+    advance_all: bool,
+) -> Result<Vec<StringPattern>, CommandError> {
+    if advance_all {
+        return Ok(vec![StringPattern::everything()]);
+    }
+    settings
+        .config()
+        .get::<Vec<String>>("advance-branches.enabled-branches")
+        .optional()?
+        .unwrap_or_default()
+        .iter()
+        .map(|pattern| {
+            StringPattern::parse(pattern).map_err(|err| {
+                config_error_with_message(
+                    format!("Invalid pattern in advance-branches.enabled-branches: {pattern}"),
+                    err,
+                )
+            })
+        })
+        .collect()
+}
@@ -19,8 +19,8 @@ use itertools::Itertools;
 use tracing::instrument;
 
 use crate::cli_util::{
-    get_new_config_file_path, run_ui_editor, serialize_config_value, write_config_value_to_file,
-    CommandHelper,
+    get_new_config_file_path, remove_config_value_from_file, run_ui_editor, serialize_config_value,
+    write_config_value_to_file, CommandHelper,
 };
 use crate::command_error::{config_error, user_error, CommandError};
 use crate::config::{AnnotatedValue, ConfigSource};
@@ -39,6 +39,10 @@ pub(crate) struct ConfigArgs {
     /// Target the repo-level config
     #[arg(long, group = "config_level")]
     repo: bool,
+
+    /// Target the workspace-level config
+    #[arg(long, group = "config_level")]
+    workspace: bool,
 }
 
 impl ConfigArgs {
@@ -47,6 +51,8 @@ impl ConfigArgs {
             ConfigSource::User
         } else if self.repo {
             ConfigSource::Repo
+        } else if self.workspace {
+            ConfigSource::Workspace
         } else {
             // Shouldn't be reachable unless clap ArgGroup is broken.
             panic!("No config_level provided");
@@ -69,6 +75,8 @@ pub(crate) enum ConfigCommand {
     Get(ConfigGetArgs),
     #[command(visible_alias("s"))]
     Set(ConfigSetArgs),
+    #[command(visible_alias("u"))]
+    Unset(ConfigUnsetArgs),
     #[command(visible_alias("e"))]
     Edit(ConfigEditArgs),
     #[command(visible_alias("p"))]
@@ -77,7 +85,7 @@ pub(crate) enum ConfigCommand {
 
 /// List variables set in config file, along with their values.
 #[derive(clap::Args, Clone, Debug)]
-#[command(group(clap::ArgGroup::new("specific").args(&["repo", "user"])))]
+#[command(group(clap::ArgGroup::new("specific").args(&["repo", "user", "workspace"])))]
 pub(crate) struct ConfigListArgs {
     /// An optional name of a specific config option to look up.
     #[arg(value_parser = NonEmptyStringValueParser::new())]
@@ -94,6 +102,9 @@ pub(crate) struct ConfigListArgs {
     /// Target the repo-level config
     #[arg(long)]
     repo: bool,
+    /// Target the workspace-level config
+    #[arg(long)]
+    workspace: bool,
     // TODO(#1047): Support --show-origin using LayeredConfigs.
     /// Render each variable using the given template
     ///
@@ -114,6 +125,8 @@ impl ConfigListArgs {
             Some(ConfigSource::User)
         } else if self.repo {
             Some(ConfigSource::Repo)
+        } else if self.workspace {
+            Some(ConfigSource::Workspace)
         } else {
             //List all variables
             None
@@ -138,6 +151,10 @@ pub(crate) struct ConfigGetArgs {
 }
 
 /// Update config file to set the given option to a given value.
+///
+/// The value is parsed as a TOML value, or taken as a string if it isn't valid
+/// TOML or if the option only accepts strings. Options known to jj are checked
+/// against the config schema (see `jj util config-schema`).
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct ConfigSetArgs {
     #[arg(required = true)]
@@ -148,6 +165,15 @@ pub(crate) struct ConfigSetArgs {
     config_args: ConfigArgs,
 }
 
+/// Update config file to unset the given option.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct ConfigUnsetArgs {
+    #[arg(required = true)]
+    name: String,
+    #[clap(flatten)]
+    config_args: ConfigArgs,
+}
+
 /// Start an editor on a jj config file.
 ///
 /// Creates the file if it doesn't already exist regardless of what the editor
@@ -179,6 +205,7 @@ pub(crate) fn cmd_config(
         ConfigCommand::List(sub_args) => cmd_config_list(ui, command, sub_args),
         ConfigCommand::Get(sub_args) => cmd_config_get(ui, command, sub_args),
         ConfigCommand::Set(sub_args) => cmd_config_set(ui, command, sub_args),
+        ConfigCommand::Unset(sub_args) => cmd_config_unset(ui, command, sub_args),
         ConfigCommand::Edit(sub_args) => cmd_config_edit(ui, command, sub_args),
         ConfigCommand::Path(sub_args) => cmd_config_path(ui, command, sub_args),
     }
@@ -320,13 +347,29 @@ pub(crate) fn cmd_config_set(
 }
 
 #[instrument(skip_all)]
-pub(crate) fn cmd_config_edit(
+pub(crate) fn cmd_config_unset(
     _ui: &mut Ui,
     command: &CommandHelper,
+    args: &ConfigUnsetArgs,
+) -> Result<(), CommandError> {
+    let config_path = get_new_config_file_path(&args.config_args.get_source_kind(), command)?;
+    if config_path.is_dir() {
+        return Err(user_error(format!(
+            "Can't unset config in path {path} (dirs not supported)",
+            path = config_path.display()
+        )));
+    }
+    remove_config_value_from_file(&args.name, &config_path)
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_config_edit(
+    ui: &mut Ui,
+    command: &CommandHelper,
     args: &ConfigEditArgs,
 ) -> Result<(), CommandError> {
     let config_path = get_new_config_file_path(&args.config_args.get_source_kind(), command)?;
-    run_ui_editor(command.settings(), &config_path)
+    run_ui_editor(ui, command.settings(), &config_path)
 }
 
 #[instrument(skip_all)]
@@ -14,17 +14,21 @@
 
 use std::any::Any;
 use std::fmt::Debug;
-use std::io::Write as _;
+use std::io::{self, Write as _};
+use std::time::{Duration, Instant};
 
-use clap::Subcommand;
-use jj_lib::backend::TreeId;
+use clap::{Subcommand, ValueEnum as _};
+use jj_lib::backend::{CommitId, ConflictId, FileId, SymlinkId, TreeId};
 use jj_lib::default_index::{AsCompositeIndex as _, DefaultIndexStore, DefaultReadonlyIndex};
 use jj_lib::local_working_copy::LocalWorkingCopy;
-use jj_lib::merged_tree::MergedTree;
+use jj_lib::matchers::{EverythingMatcher, Matcher};
+use jj_lib::merge::{Merge, MergedTreeValue};
+use jj_lib::merged_tree::{MergedTree, MergedTreeVal};
 use jj_lib::object_id::ObjectId;
+use jj_lib::op_store::{OperationId, ViewId};
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPathBuf;
-use jj_lib::working_copy::WorkingCopy;
+use jj_lib::working_copy::{SnapshotOptions, WorkingCopy};
 use jj_lib::{op_walk, revset};
 
 use crate::cli_util::{CommandHelper, RevisionArg};
@@ -41,11 +45,15 @@ pub enum DebugCommand {
     WorkingCopy(DebugWorkingCopyArgs),
     Template(DebugTemplateArgs),
     Index(DebugIndexArgs),
+    /// Rebuild commit index (alias for `jj debug index rebuild`)
     #[command(name = "reindex")]
-    ReIndex(DebugReIndexArgs),
+    ReIndex(DebugIndexRebuildArgs),
     #[command(visible_alias = "view")]
     Operation(DebugOperationArgs),
     Tree(DebugTreeArgs),
+    Object(DebugObjectArgs),
+    #[command(name = "revwalk")]
+    RevWalk(DebugRevWalkArgs),
     #[command(subcommand)]
     Watchman(DebugWatchmanSubcommand),
 }
@@ -66,13 +74,47 @@ pub struct DebugTemplateArgs {
     template: String,
 }
 
+/// Show commit index stats, or rebuild the commit index
+///
+/// Without a subcommand, the stats are shown.
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugIndexArgs {
+    #[command(subcommand)]
+    command: Option<DebugIndexCommand>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum DebugIndexCommand {
+    Stats(DebugIndexStatsArgs),
+    Rebuild(DebugIndexRebuildArgs),
+}
+
 /// Show commit index stats
+///
+/// The index is made of a stack of segments (or levels), each segment adding
+/// commits on top of its parent segment. Segments are squashed together as the
+/// index grows, so a long stack of segments, or a large number of commits in
+/// the top segments, can make the index slow to load.
 #[derive(clap::Args, Clone, Debug)]
-pub struct DebugIndexArgs {}
+pub struct DebugIndexStatsArgs {}
 
 /// Rebuild commit index
+///
+/// The existing index segments are discarded, and the index is rebuilt from
+/// the operation log as a single segment.
 #[derive(clap::Args, Clone, Debug)]
-pub struct DebugReIndexArgs {}
+pub struct DebugIndexRebuildArgs {
+    /// Store Bloom filters of the paths changed by each commit in the index
+    ///
+    /// The filters speed up path-limited queries such as `jj log PATH` and the
+    /// `file()` revset, at the cost of diffing each commit when it is indexed.
+    /// The setting is kept when the index is rebuilt or updated.
+    #[arg(long)]
+    changed_path_filters: bool,
+    /// Stop storing changed-path filters in the index
+    #[arg(long, conflicts_with = "changed_path_filters")]
+    no_changed_path_filters: bool,
+}
 
 /// Show information about an operation and its view
 #[derive(clap::Args, Clone, Debug)]
@@ -105,7 +147,63 @@ pub struct DebugTreeArgs {
     #[arg(long, requires = "id")]
     dir: Option<String>,
     paths: Vec<String>,
-    // TODO: Add an option to include trees that are ancestors of the matched paths
+    /// Also list the trees containing the matched paths, with their ids
+    #[arg(long)]
+    trees: bool,
+    /// Only list the conflicts, including the conflicts between trees and
+    /// other values
+    #[arg(long, conflicts_with = "trees")]
+    conflicts: bool,
+}
+
+/// Show the contents of a backend or operation store object
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugObjectArgs {
+    #[arg(value_enum)]
+    kind: DebugObjectKind,
+    /// The id of the object, in hex
+    id: String,
+    /// The path of the object, for the kinds of objects which are read by path
+    #[arg(long)]
+    path: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum DebugObjectKind {
+    Commit,
+    Tree,
+    /// Print the raw contents of the file
+    File,
+    Symlink,
+    Conflict,
+    Operation,
+    View,
+}
+
+/// Time revset evaluation, tree diffs and snapshots on the current repo
+///
+/// Each step is run several times, and the duration of the first run, which
+/// may have to fill caches, is printed with the shortest, median and longest
+/// durations. The report can be attached to performance bug reports, or
+/// compared between versions of jj on the same repo. Unlike `jj bench`, this
+/// command is available in all builds.
+///
+/// The snapshots don't record anything, and are skipped with
+/// `--ignore-working-copy` or `--at-op`.
+#[derive(clap::Args, Clone, Debug)]
+pub struct DebugRevWalkArgs {
+    /// Revsets to evaluate and walk
+    #[arg(long, short, default_values = ["::@", "all()", "heads(all())"])]
+    revisions: Vec<RevisionArg>,
+    /// The revision to diff the tree from
+    #[arg(long, default_value = "root()")]
+    from: RevisionArg,
+    /// The revision to diff the tree to
+    #[arg(long, default_value = "@")]
+    to: RevisionArg,
+    /// Number of runs of each step
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    iterations: u32,
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -125,9 +223,11 @@ pub fn cmd_debug(
         DebugCommand::WorkingCopy(args) => cmd_debug_working_copy(ui, command, args),
         DebugCommand::Template(args) => cmd_debug_template(ui, command, args),
         DebugCommand::Index(args) => cmd_debug_index(ui, command, args),
-        DebugCommand::ReIndex(args) => cmd_debug_reindex(ui, command, args),
+        DebugCommand::ReIndex(args) => cmd_debug_index_rebuild(ui, command, args),
         DebugCommand::Operation(args) => cmd_debug_operation(ui, command, args),
         DebugCommand::Tree(args) => cmd_debug_tree(ui, command, args),
+        DebugCommand::Object(args) => cmd_debug_object(ui, command, args),
+        DebugCommand::RevWalk(args) => cmd_debug_revwalk(ui, command, args),
         DebugCommand::Watchman(args) => cmd_debug_watchman(ui, command, args),
     }
 }
@@ -205,7 +305,19 @@ fn cmd_debug_template(
 fn cmd_debug_index(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &DebugIndexArgs,
+    args: &DebugIndexArgs,
+) -> Result<(), CommandError> {
+    match &args.command {
+        None => cmd_debug_index_stats(ui, command, &DebugIndexStatsArgs {}),
+        Some(DebugIndexCommand::Stats(args)) => cmd_debug_index_stats(ui, command, args),
+        Some(DebugIndexCommand::Rebuild(args)) => cmd_debug_index_rebuild(ui, command, args),
+    }
+}
+
+fn cmd_debug_index_stats(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &DebugIndexStatsArgs,
 ) -> Result<(), CommandError> {
     // Resolve the operation without loading the repo, so this command won't
     // merge concurrent operations and update the index.
@@ -227,11 +339,23 @@ fn cmd_debug_index(
         )?;
         writeln!(ui.stdout(), "Number of heads: {}", stats.num_heads)?;
         writeln!(ui.stdout(), "Number of changes: {}", stats.num_changes)?;
+        writeln!(ui.stdout(), "Number of levels: {}", stats.levels.len())?;
+        let total_size: u64 = stats
+            .levels
+            .iter()
+            .filter_map(|level| level.file_size)
+            .sum();
+        writeln!(ui.stdout(), "Total size: {total_size} bytes")?;
         writeln!(ui.stdout(), "Stats per level:")?;
         for (i, level) in stats.levels.iter().enumerate() {
             writeln!(ui.stdout(), "  Level {i}:")?;
             writeln!(ui.stdout(), "    Number of commits: {}", level.num_commits)?;
-            writeln!(ui.stdout(), "    Name: {}", level.name.as_ref().unwrap())?;
+            if let Some(file_size) = level.file_size {
+                writeln!(ui.stdout(), "    Size: {file_size} bytes")?;
+            }
+            if let Some(name) = &level.name {
+                writeln!(ui.stdout(), "    Name: {name}")?;
+            }
         }
     } else {
         return Err(user_error(format!(
@@ -242,10 +366,10 @@ fn cmd_debug_index(
     Ok(())
 }
 
-fn cmd_debug_reindex(
+fn cmd_debug_index_rebuild(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &DebugReIndexArgs,
+    args: &DebugIndexRebuildArgs,
 ) -> Result<(), CommandError> {
     // Resolve the operation without loading the repo. The index might have to
     // be rebuilt while loading the repo.
@@ -254,6 +378,11 @@ fn cmd_debug_reindex(
     let op = op_walk::resolve_op_for_load(repo_loader, &command.global_args().at_operation)?;
     let index_store = repo_loader.index_store();
     if let Some(default_index_store) = index_store.as_any().downcast_ref::<DefaultIndexStore>() {
+        if args.changed_path_filters || args.no_changed_path_filters {
+            default_index_store
+                .set_changed_path_filters_enabled(args.changed_path_filters)
+                .map_err(internal_error)?;
+        }
         default_index_store.reinit().map_err(internal_error)?;
         let default_index = default_index_store
             .build_index_at_operation(&op, repo_loader.store())
@@ -320,7 +449,18 @@ fn cmd_debug_tree(
     let matcher = workspace_command
         .parse_file_patterns(&args.paths)?
         .to_matcher();
-    for (path, value) in tree.entries_matching(matcher.as_ref()) {
+    let entries = if args.trees {
+        let mut entries = vec![];
+        collect_tree_entries(&tree, matcher.as_ref(), &mut entries);
+        entries
+    } else if args.conflicts {
+        tree.conflicts()
+            .filter(|(path, _)| matcher.matches(path))
+            .collect()
+    } else {
+        tree.entries_matching(matcher.as_ref()).collect()
+    };
+    for (path, value) in entries {
         let ui_path = workspace_command.format_file_path(&path);
         writeln!(ui.stdout(), "{ui_path}: {value:?}")?;
     }
@@ -328,6 +468,195 @@ fn cmd_debug_tree(
     Ok(())
 }
 
+/// Collects the entries of the `tree` matching the `matcher` like
+/// `MergedTree::entries_matching()`, and the subtrees which may contain
+/// matching entries before their entries.
+fn collect_tree_entries(
+    tree: &MergedTree,
+    matcher: &dyn Matcher,
+    entries: &mut Vec<(RepoPathBuf, MergedTreeValue)>,
+) {
+    for name in tree.names() {
+        let path = tree.dir().join(name);
+        let value = match tree.value(name) {
+            MergedTreeVal::Resolved(value) => Merge::resolved(value.cloned()),
+            MergedTreeVal::Conflict(merge) => merge,
+        };
+        if let Some(sub_tree) = tree.sub_tree(name) {
+            if !matcher.visit(&path).is_nothing() {
+                entries.push((path, value));
+                collect_tree_entries(&sub_tree, matcher, entries);
+            }
+        } else if matcher.matches(&path) {
+            entries.push((path, value));
+        }
+    }
+}
+
+fn cmd_debug_object(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &DebugObjectArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo = workspace_command.repo();
+    let store = repo.store();
+    let path = if let Some(path_str) = &args.path {
+        workspace_command.parse_file_path(path_str)?
+    } else {
+        RepoPathBuf::root()
+    };
+    let invalid_id = || {
+        let kind = args.kind.to_possible_value().unwrap();
+        user_error(format!("Invalid {} id", kind.get_name()))
+    };
+    match args.kind {
+        DebugObjectKind::Commit => {
+            let id = CommitId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let commit = store.get_commit(&id)?;
+            writeln!(ui.stdout(), "{:#?}", commit.store_commit())?;
+        }
+        DebugObjectKind::Tree => {
+            let id = TreeId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let tree = store.get_tree(&path, &id)?;
+            writeln!(ui.stdout(), "{:#?}", tree.data())?;
+        }
+        DebugObjectKind::File => {
+            let id = FileId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let mut reader = store.read_file(&path, &id)?;
+            io::copy(&mut reader, &mut ui.stdout())?;
+        }
+        DebugObjectKind::Symlink => {
+            let id = SymlinkId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let target = store.read_symlink(&path, &id)?;
+            writeln!(ui.stdout(), "{target}")?;
+        }
+        DebugObjectKind::Conflict => {
+            let id = ConflictId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let conflict = store.read_conflict(&path, &id)?;
+            writeln!(ui.stdout(), "{conflict:#?}")?;
+        }
+        DebugObjectKind::Operation => {
+            let id = OperationId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let operation = repo.op_store().read_operation(&id)?;
+            writeln!(ui.stdout(), "{operation:#?}")?;
+        }
+        DebugObjectKind::View => {
+            let id = ViewId::try_from_hex(&args.id).map_err(|_| invalid_id())?;
+            let view = repo.op_store().read_view(&id)?;
+            writeln!(ui.stdout(), "{view:#?}")?;
+        }
+    }
+    Ok(())
+}
+
+fn cmd_debug_revwalk(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &DebugRevWalkArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let from_tree = workspace_command.resolve_single_rev(&args.from)?.tree()?;
+    let to_tree = workspace_command.resolve_single_rev(&args.to)?.tree()?;
+
+    write!(ui.stdout(), "{}", command.app().render_version())?;
+    writeln!(ui.stdout(), "Runs per step: {}", args.iterations)?;
+    writeln!(
+        ui.stdout(),
+        "{:<40} {:>10} {:>10} {:>10} {:>10}",
+        "Step",
+        "First",
+        "Min",
+        "Median",
+        "Max"
+    )?;
+    for revset in &args.revisions {
+        let (durations, result) = time_runs(args.iterations, || {
+            let revset = workspace_command.parse_revset(revset)?.evaluate()?;
+            Ok(format!("{} commits", revset.iter().count()))
+        })?;
+        write_timings(ui, &format!("revset {revset}"), &durations, &result)?;
+    }
+
+    let (durations, result) = time_runs(args.iterations, || {
+        let mut num_paths = 0;
+        for (_path, diff) in from_tree.diff(&to_tree, &EverythingMatcher) {
+            diff?;
+            num_paths += 1;
+        }
+        Ok(format!("{num_paths} paths"))
+    })?;
+    write_timings(
+        ui,
+        &format!("diff {} {}", args.from, args.to),
+        &durations,
+        &result,
+    )?;
+
+    if workspace_command.check_working_copy_writable().is_ok()
+        && workspace_command.get_wc_commit_id().is_some()
+    {
+        let base_ignores = workspace_command.base_ignores()?;
+        let conflict_marker_options = workspace_command.conflict_marker_options();
+        let settings = command.settings();
+        let (durations, result) = time_runs(args.iterations, || {
+            // The locked working copy is dropped without being finished, so the
+            // snapshot isn't recorded.
+            let (mut locked_ws, wc_commit) =
+                workspace_command.unchecked_start_working_copy_mutation()?;
+            let tree_id = locked_ws.locked_wc().snapshot(SnapshotOptions {
+                base_ignores: base_ignores.clone(),
+                fsmonitor_kind: settings.fsmonitor_kind()?,
+                progress: None,
+                max_new_file_size: settings.max_new_file_size()?,
+                exclude_paths: settings.snapshot_exclude_paths()?,
+                conflict_marker_options,
+            })?;
+            let result = if tree_id == *wc_commit.tree_id() {
+                "unchanged"
+            } else {
+                "changed"
+            };
+            Ok(result.to_owned())
+        })?;
+        write_timings(ui, "snapshot", &durations, &result)?;
+    }
+    Ok(())
+}
+
+/// Runs the `routine` the given number of times. Returns the duration of each
+/// run and the result of the last one.
+fn time_runs(
+    iterations: u32,
+    mut routine: impl FnMut() -> Result<String, CommandError>,
+) -> Result<(Vec<Duration>, String), CommandError> {
+    let mut durations = vec![];
+    let mut result = String::new();
+    for _ in 0..iterations {
+        let before = Instant::now();
+        result = routine()?;
+        durations.push(before.elapsed());
+    }
+    Ok((durations, result))
+}
+
+fn write_timings(ui: &Ui, name: &str, durations: &[Duration], result: &str) -> io::Result<()> {
+    let first = durations[0];
+    let mut sorted = durations.to_vec();
+    sorted.sort();
+    let [first, min, median, max] = [
+        first,
+        sorted[0],
+        sorted[sorted.len() / 2],
+        sorted[sorted.len() - 1],
+    ]
+    .map(|duration| format!("{duration:.2?}"));
+    writeln!(
+        ui.stdout(),
+        "{name:<40} {first:>10} {min:>10} {median:>10} {max:>10}  {result}"
+    )
+}
+
 #[cfg(feature = "watchman")]
 fn cmd_debug_watchman(
     ui: &mut Ui,
@@ -14,13 +14,19 @@
 
 use std::io::{self, Read, Write};
 
+use itertools::Itertools;
+use jj_lib::backend::Timestamp;
+use jj_lib::commit::CommitIteratorExt;
+use jj_lib::commit_builder::CommitBuilder;
 use jj_lib::object_id::ObjectId;
+use jj_lib::time_util::{parse_datetime, DatePatternParseError};
 use tracing::instrument;
 
 use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::CommandError;
 use crate::description_util::{
-    description_template_for_describe, edit_description, join_message_paragraphs,
+    add_trailers, description_template_for_describe, edit_description, edit_multiple_descriptions,
+    join_message_paragraphs, lint_description, parse_multiple_descriptions,
 };
 use crate::ui::Ui;
 
@@ -28,19 +34,28 @@ use crate::ui::Ui;
 ///
 /// Starts an editor to let you edit the description of a change. The editor
 /// will be $EDITOR, or `pico` if that's not defined (`Notepad` on Windows).
+///
+/// If more than one revision is given, their descriptions are edited in a
+/// single editor. Each description is preceded by a `JJ: describe <commit id>`
+/// line, which tells which commit it belongs to. `--stdin` reads the
+/// descriptions in the same format.
 #[derive(clap::Args, Clone, Debug)]
 #[command(visible_aliases = &["desc"])]
 pub(crate) struct DescribeArgs {
-    /// The revision whose description to edit
+    /// The revision(s) whose description to edit
     #[arg(default_value = "@")]
-    revision: RevisionArg,
+    revisions: Vec<RevisionArg>,
     /// Ignored (but lets you pass `-r` for consistency with other commands)
-    #[arg(short = 'r', hide = true)]
-    unused_revision: bool,
+    #[arg(short = 'r', hide = true, action = clap::ArgAction::Count)]
+    unused_revision: u8,
     /// The change description to use (don't open editor)
     #[arg(long = "message", short, value_name = "MESSAGE")]
     message_paragraphs: Vec<String>,
     /// Read the change description from stdin
+    ///
+    /// If more than one revision is given, each description must be preceded
+    /// by a `JJ: describe <commit id>` line. The commit id may be abbreviated.
+    /// The revisions without a description are left unchanged.
     #[arg(long)]
     stdin: bool,
     /// Don't open an editor
@@ -48,6 +63,16 @@ pub(crate) struct DescribeArgs {
     /// This is mainly useful in combination with e.g. `--reset-author`.
     #[arg(long)]
     no_edit: bool,
+    /// Add a `Signed-off-by` trailer for the configured user
+    #[arg(long)]
+    signoff: bool,
+    #[command(flatten)]
+    author_args: AuthorArgs,
+}
+
+/// Options to change the author of the rewritten commits
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct AuthorArgs {
     /// Reset the author to the configured user
     ///
     /// This resets the author name, email, and timestamp.
@@ -58,6 +83,65 @@ pub(crate) struct DescribeArgs {
     /// $ JJ_USER='Foo Bar' JJ_EMAIL=foo@bar.com jj describe --reset-author
     #[arg(long)]
     reset_author: bool,
+    /// Set the author name and email, e.g. `--author "Foo Bar <foo@bar.com>"`
+    ///
+    /// The author timestamp is kept unless `--reset-author` or `--author-date`
+    /// is also given.
+    #[arg(long, value_name = "AUTHOR", value_parser = parse_author)]
+    author: Option<(String, String)>,
+    /// Set the author timestamp
+    ///
+    /// Accepts the same formats as the `after:`/`before:` date patterns, e.g.
+    /// `2024-02-01T12:00:00+09:00` or `2 days ago`.
+    #[arg(long, value_name = "DATE", value_parser = parse_author_date)]
+    author_date: Option<Timestamp>,
+}
+
+impl AuthorArgs {
+    /// Whether any of the options is given.
+    pub fn is_set(&self) -> bool {
+        self.reset_author || self.author.is_some() || self.author_date.is_some()
+    }
+
+    /// Updates the author of the commit being built as specified by the
+    /// options.
+    pub fn apply<'repo>(&self, commit_builder: CommitBuilder<'repo>) -> CommitBuilder<'repo> {
+        if !self.is_set() {
+            return commit_builder;
+        }
+        let mut author = if self.reset_author {
+            commit_builder.committer().clone()
+        } else {
+            commit_builder.author().clone()
+        };
+        if let Some((name, email)) = &self.author {
+            author.name = name.clone();
+            author.email = email.clone();
+        }
+        if let Some(timestamp) = &self.author_date {
+            author.timestamp = timestamp.clone();
+        }
+        commit_builder.set_author(author)
+    }
+}
+
+fn parse_author(src: &str) -> Result<(String, String), &'static str> {
+    let error = "Author must be in the form `Name <email>`";
+    let (name, email) = src
+        .trim()
+        .strip_suffix('>')
+        .and_then(|src| src.rsplit_once('<'))
+        .ok_or(error)?;
+    let name = name.trim();
+    if name.is_empty() || email.is_empty() {
+        return Err(error);
+    }
+    Ok((name.to_owned(), email.to_owned()))
+}
+
+fn parse_author_date(src: &str) -> Result<Timestamp, DatePatternParseError> {
+    let datetime = parse_datetime(src, chrono::Local::now())?;
+    Ok(Timestamp::from_datetime(datetime))
 }
 
 #[instrument(skip_all)]
@@ -67,35 +151,116 @@ pub(crate) fn cmd_describe(
     args: &DescribeArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let commit = workspace_command.resolve_single_rev(&args.revision)?;
-    workspace_command.check_rewritable([commit.id()])?;
-    let description = if args.stdin {
+    let commits: Vec<_> = workspace_command
+        .parse_union_revsets(&args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    if commits.is_empty() {
+        writeln!(ui.status(), "No revisions to describe.")?;
+        return Ok(());
+    }
+    workspace_command.check_rewritable(commits.iter().ids())?;
+
+    let descriptions = if args.stdin {
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer).unwrap();
-        buffer
+        if let [commit] = &commits[..] {
+            vec![(commit, buffer)]
+        } else {
+            let mut descriptions = parse_multiple_descriptions(&buffer, &commits)?;
+            commits
+                .iter()
+                .filter_map(|commit| Some((commit, descriptions.remove(commit.id())?)))
+                .collect()
+        }
     } else if !args.message_paragraphs.is_empty() {
-        join_message_paragraphs(&args.message_paragraphs)
+        let description = join_message_paragraphs(&args.message_paragraphs);
+        commits
+            .iter()
+            .map(|commit| (commit, description.clone()))
+            .collect()
     } else if args.no_edit {
-        commit.description().to_owned()
+        commits
+            .iter()
+            .map(|commit| (commit, commit.description().to_owned()))
+            .collect()
     } else {
-        let template =
-            description_template_for_describe(ui, command.settings(), &workspace_command, &commit)?;
-        edit_description(workspace_command.repo(), &template, command.settings())?
+        let templates: Vec<_> = commits
+            .iter()
+            .map(|commit| {
+                description_template_for_describe(
+                    ui,
+                    command.settings(),
+                    &workspace_command,
+                    commit,
+                )
+            })
+            .try_collect()?;
+        if let [commit] = &commits[..] {
+            let description = edit_description(
+                ui,
+                workspace_command.repo(),
+                &templates[0],
+                command.settings(),
+            )?;
+            vec![(commit, description)]
+        } else {
+            let mut descriptions = edit_multiple_descriptions(
+                ui,
+                workspace_command.repo(),
+                &commits,
+                &templates,
+                command.settings(),
+            )?;
+            commits
+                .iter()
+                .filter_map(|commit| Some((commit, descriptions.remove(commit.id())?)))
+                .collect()
+        }
     };
-    if description == *commit.description() && !args.reset_author {
+
+    let mut changed = vec![];
+    for (commit, description) in descriptions {
+        let description = add_trailers(
+            command.settings(),
+            commit.change_id(),
+            args.signoff,
+            &description,
+        )?;
+        if description != *commit.description() || args.author_args.is_set() {
+            lint_description(ui, command.settings(), &description)?;
+            changed.push((commit, description));
+        }
+    }
+    if changed.is_empty() {
         writeln!(ui.status(), "Nothing changed.")?;
-    } else {
-        let mut tx = workspace_command.start_transaction();
-        let mut commit_builder = tx
+        return Ok(());
+    }
+
+    let mut tx = workspace_command.start_transaction();
+    // Rewrite the parents first so that the rewritten children can be put on
+    // top of them.
+    for (commit, description) in changed.iter().rev() {
+        let new_parents = tx.mut_repo().new_parents(commit.parent_ids());
+        let commit_builder = tx
             .mut_repo()
-            .rewrite_commit(command.settings(), &commit)
+            .rewrite_commit(command.settings(), commit)
+            .set_parents(new_parents)
             .set_description(description);
-        if args.reset_author {
-            let new_author = commit_builder.committer().clone();
-            commit_builder = commit_builder.set_author(new_author);
-        }
-        commit_builder.write()?;
-        tx.finish(ui, format!("describe commit {}", commit.id().hex()))?;
+        args.author_args.apply(commit_builder).write()?;
+    }
+    if changed.len() > 1 {
+        writeln!(ui.status(), "Updated {} commits", changed.len())?;
     }
+    let transaction_description = if let [(commit, _)] = &changed[..] {
+        format!("describe commit {}", commit.id().hex())
+    } else {
+        format!(
+            "describe commit {} and {} more",
+            changed[0].0.id().hex(),
+            changed.len() - 1
+        )
+    };
+    tx.finish(ui, transaction_description)?;
     Ok(())
 }
@@ -12,12 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fs::File;
+use std::io::{self, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools;
+use jj_lib::commit::Commit;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::rewrite::merge_commit_trees;
+use once_cell::sync::Lazy;
 use tracing::instrument;
 
-use crate::cli_util::{CommandHelper, RevisionArg};
-use crate::command_error::CommandError;
-use crate::diff_util::{diff_formats_for, show_diff, DiffFormatArgs};
+use crate::cli_util::{short_change_hash, CommandHelper, RevisionArg};
+use crate::command_error::{user_error, user_error_with_message, CommandError};
+use crate::diff_util::{
+    diff_formats_for, patch_diff_format, show_diff, show_patch, DiffFormatArgs,
+};
+use crate::formatter::PlainTextFormatter;
+use crate::time_util::{format_absolute_timestamp_with, FormattingItems};
 use crate::ui::Ui;
 
 /// Compare file contents between two revisions
@@ -31,6 +43,10 @@ use crate::ui::Ui;
 /// given revisions. If either is left out, it defaults to the working-copy
 /// commit. For example, `jj diff --from main` shows the changes from "main"
 /// (perhaps a branch name) to the working-copy commit.
+///
+/// With the `--output` or `--output-dir` options, the changes are written to
+/// patch files in Git's format instead, which include the contents of binary
+/// files and can be applied with `git apply`.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct DiffArgs {
     /// Show changes in this revision, compared to its parent(s)
@@ -49,6 +65,32 @@ pub(crate) struct DiffArgs {
     /// Restrict the diff to these paths
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Write the diff to this patch file instead of showing it
+    ///
+    /// The diff is written in Git's format, with the number of context lines
+    /// given by `--context`.
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath,
+        conflicts_with_all = ["short-format", "long-format"],
+    )]
+    output: Option<PathBuf>,
+    /// Write the diff of each revision to a separate patch file in this
+    /// directory
+    ///
+    /// The revisions are given by `-r`, which may be any revset in this case.
+    /// The patches are numbered from the oldest revision, and named after the
+    /// change ids of the revisions, e.g. `0001-kkmpptxzrspx.patch`. Each patch
+    /// starts with an email-style header with the author and description of
+    /// the revision, so it can also be applied with `git am`.
+    #[arg(
+        long,
+        value_name = "DIR",
+        value_hint = clap::ValueHint::DirPath,
+        conflicts_with_all = ["from", "to", "output", "short-format", "long-format"],
+    )]
+    output_dir: Option<PathBuf>,
     #[command(flatten)]
     format: DiffFormatArgs,
 }
@@ -60,8 +102,55 @@ pub(crate) fn cmd_diff(
     args: &DiffArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
+    let matcher = workspace_command
+        .parse_file_patterns(&args.paths)?
+        .to_matcher();
+    if let Some(output_dir) = &args.output_dir {
+        let revision = args.revision.as_ref().unwrap_or(&RevisionArg::AT);
+        let commits: Vec<Commit> = workspace_command
+            .parse_revset(revision)?
+            .evaluate_to_commits()?
+            .try_collect()?;
+        if commits.is_empty() {
+            return Err(user_error("Empty revision set"));
+        }
+        let formats = [patch_diff_format(command.settings(), &args.format)?];
+        std::fs::create_dir_all(output_dir).map_err(|err| {
+            let message = format!("Failed to create directory {}", output_dir.display());
+            user_error_with_message(message, err)
+        })?;
+        for (i, commit) in commits.iter().rev().enumerate() {
+            let file_name = format!(
+                "{:04}-{}.patch",
+                i + 1,
+                short_change_hash(commit.change_id())
+            );
+            let path = output_dir.join(file_name);
+            let mut file = create_patch_file(&path)?;
+            write_patch_header(&mut file, commit)?;
+            show_patch(
+                ui,
+                &mut PlainTextFormatter::new(&mut file),
+                command.settings(),
+                &workspace_command,
+                commit,
+                matcher.as_ref(),
+                &formats,
+            )?;
+            file.flush()?;
+        }
+        writeln!(
+            ui.status(),
+            "Wrote {} patches to {}",
+            commits.len(),
+            output_dir.display()
+        )?;
+        return Ok(());
+    }
+
     let from_tree;
     let to_tree;
+    let mut copies = vec![];
     if args.from.is_some() || args.to.is_some() {
         let from =
             workspace_command.resolve_single_rev(args.from.as_ref().unwrap_or(&RevisionArg::AT))?;
@@ -74,21 +163,70 @@ pub(crate) fn cmd_diff(
             .resolve_single_rev(args.revision.as_ref().unwrap_or(&RevisionArg::AT))?;
         let parents = commit.parents();
         from_tree = merge_commit_trees(workspace_command.repo().as_ref(), &parents)?;
-        to_tree = commit.tree()?
+        to_tree = commit.tree()?;
+        copies = commit.copies().to_vec();
+    }
+    if let Some(path) = &args.output {
+        let formats = [patch_diff_format(command.settings(), &args.format)?];
+        let mut file = create_patch_file(path)?;
+        show_diff(
+            ui,
+            &mut PlainTextFormatter::new(&mut file),
+            command.settings(),
+            &workspace_command,
+            &from_tree,
+            &to_tree,
+            matcher.as_ref(),
+            &copies,
+            &formats,
+        )?;
+        file.flush()?;
+        return Ok(());
     }
-    let matcher = workspace_command
-        .parse_file_patterns(&args.paths)?
-        .to_matcher();
     let diff_formats = diff_formats_for(command.settings(), &args.format)?;
     ui.request_pager();
     show_diff(
         ui,
         ui.stdout_formatter().as_mut(),
+        command.settings(),
         &workspace_command,
         &from_tree,
         &to_tree,
         matcher.as_ref(),
+        &copies,
         &diff_formats,
     )?;
     Ok(())
 }
+
+fn create_patch_file(path: &Path) -> Result<BufWriter<File>, CommandError> {
+    let file = File::create(path).map_err(|err| {
+        user_error_with_message(format!("Failed to create {}", path.display()), err)
+    })?;
+    Ok(BufWriter::new(file))
+}
+
+/// Writes the header of the patch of the `commit`, in the format of `git
+/// format-patch`.
+fn write_patch_header(out: &mut dyn io::Write, commit: &Commit) -> Result<(), CommandError> {
+    static DATE_FORMAT: Lazy<FormattingItems> =
+        Lazy::new(|| FormattingItems::parse("%a, %d %b %Y %H:%M:%S %z").unwrap());
+    let author = commit.author();
+    let date =
+        format_absolute_timestamp_with(&author.timestamp, &DATE_FORMAT).map_err(user_error)?;
+    let (subject, body) = commit
+        .description()
+        .split_once('\n')
+        .unwrap_or((commit.description(), ""));
+    writeln!(out, "From {} Mon Sep 17 00:00:00 2001", commit.id().hex())?;
+    writeln!(out, "From: {} <{}>", author.name, author.email)?;
+    writeln!(out, "Date: {date}")?;
+    writeln!(out, "Subject: [PATCH] {subject}")?;
+    writeln!(out)?;
+    let body = body.trim_matches('\n');
+    if !body.is_empty() {
+        writeln!(out, "{body}")?;
+    }
+    writeln!(out, "---")?;
+    Ok(())
+}
@@ -0,0 +1,185 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::backend::{CopyRecord, TreeValue};
+use jj_lib::merge::Merge;
+use jj_lib::merged_tree::MergedTreeBuilder;
+use jj_lib::object_id::ObjectId;
+use jj_lib::rewrite::merge_commit_trees;
+use tracing::instrument;
+
+use crate::cli_util::{CommandHelper, RevisionArg};
+use crate::command_error::{user_error, CommandError};
+use crate::ui::Ui;
+
+/// Copy or move files and record it in the revision
+///
+/// Unlike copying or moving the file in the working copy, the source of the
+/// file is recorded in the revision, so `jj diff` and `jj rebase` (if
+/// `merge.follow-renames` is enabled) can follow the file precisely even if
+/// its contents change a lot. Git doesn't record copies, so the records aren't
+/// exported; Git detects renames by content similarity instead.
+#[derive(clap::Subcommand, Clone, Debug)]
+pub(crate) enum FileCommand {
+    #[command(visible_alias("cp"))]
+    Copy(FileCopyArgs),
+    #[command(visible_alias("mv"))]
+    Move(FileMoveArgs),
+}
+
+/// Copy a file and record the source of the copy
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct FileCopyArgs {
+    /// The revision to update
+    #[arg(long, short, default_value = "@")]
+    revision: RevisionArg,
+    /// The file to copy
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    source: String,
+    /// The path to copy the file to
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    destination: String,
+}
+
+/// Move a file and record the rename
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct FileMoveArgs {
+    /// The revision to update
+    #[arg(long, short, default_value = "@")]
+    revision: RevisionArg,
+    /// The file to move
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    source: String,
+    /// The path to move the file to
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    destination: String,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_file(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &FileCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        FileCommand::Copy(args) => copy_file(
+            ui,
+            command,
+            &args.revision,
+            &args.source,
+            &args.destination,
+            false,
+        ),
+        FileCommand::Move(args) => copy_file(
+            ui,
+            command,
+            &args.revision,
+            &args.source,
+            &args.destination,
+            true,
+        ),
+    }
+}
+
+fn copy_file(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    revision: &RevisionArg,
+    source: &str,
+    destination: &str,
+    remove_source: bool,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let source_path = workspace_command.parse_file_path(source)?;
+    let destination_path = workspace_command.parse_file_path(destination)?;
+    let commit = workspace_command.resolve_single_rev(revision)?;
+    workspace_command.check_rewritable([commit.id()])?;
+
+    let tree = commit.tree()?;
+    let store = tree.store();
+    let Ok(Some(TreeValue::File { id, executable })) =
+        tree.path_value(&source_path).into_resolved()
+    else {
+        return Err(user_error(format!(
+            "No such file at '{}'.",
+            workspace_command.format_file_path(&source_path)
+        )));
+    };
+    if tree.path_value(&destination_path).is_present() {
+        return Err(user_error(format!(
+            "Destination already exists at '{}'.",
+            workspace_command.format_file_path(&destination_path)
+        )));
+    }
+
+    // The file id may depend on the path, so the content is rewritten at the
+    // new path.
+    let mut content = store.read_file(&source_path, &id)?;
+    let new_id = store.write_file(&destination_path, &mut content)?;
+    let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+    tree_builder.set_or_remove(
+        destination_path.clone(),
+        Merge::normal(TreeValue::File {
+            id: new_id,
+            executable,
+        }),
+    );
+    if remove_source {
+        tree_builder.set_or_remove(source_path.clone(), Merge::absent());
+    }
+    let new_tree_id = tree_builder.write_tree(store)?;
+
+    // If the source was itself copied in this revision, the new file comes from
+    // the original source. A file which was added in this revision isn't copied
+    // from the parents.
+    let mut copies = commit.copies().to_vec();
+    let new_copy = match copies.iter().position(|copy| copy.target == source_path) {
+        Some(i) if remove_source => Some(copies.remove(i)),
+        Some(i) => Some(copies[i].clone()),
+        None => {
+            let parent_tree =
+                merge_commit_trees(workspace_command.repo().as_ref(), &commit.parents())?;
+            match parent_tree.path_value(&source_path).into_resolved() {
+                Ok(Some(TreeValue::File { id, .. })) => Some(CopyRecord {
+                    target: source_path.clone(),
+                    source: source_path.clone(),
+                    source_file: id,
+                }),
+                _ => None,
+            }
+        }
+    };
+    copies.retain(|copy| copy.target != destination_path);
+    copies.extend(new_copy.map(|copy| CopyRecord {
+        target: destination_path.clone(),
+        ..copy
+    }));
+
+    let mut tx = workspace_command.start_transaction();
+    tx.mut_repo()
+        .rewrite_commit(command.settings(), &commit)
+        .set_tree_id(new_tree_id)
+        .set_copies(copies)
+        .write()?;
+    tx.finish(
+        ui,
+        format!(
+            "{} {} to {} in commit {}",
+            if remove_source { "move" } else { "copy" },
+            source_path.as_internal_file_string(),
+            destination_path.as_internal_file_string(),
+            commit.id().hex(),
+        ),
+    )
+}
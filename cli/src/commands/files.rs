@@ -12,12 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Write;
+use std::io::{self, Write};
+use std::sync::Arc;
 
+use jj_lib::backend::TreeValue;
+use jj_lib::conflicts::{materialize_tree_value, ConflictMarkerOptions, MaterializedTreeValue};
+use jj_lib::merge::MergedTreeValue;
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::store::Store;
+use pollster::FutureExt;
 use tracing::instrument;
 
 use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::CommandError;
+use crate::generic_templater::GenericTemplateLanguage;
+use crate::template_builder::TemplateLanguage as _;
+use crate::templater::{TemplatePropertyError, TemplatePropertyExt as _};
 use crate::ui::Ui;
 
 /// List files in a revision
@@ -26,6 +37,26 @@ pub(crate) struct FilesArgs {
     /// The revision to list files in
     #[arg(long, short, default_value = "@")]
     revision: RevisionArg,
+    /// Render each file using the given template
+    ///
+    /// The following keywords are defined:
+    ///
+    /// * `path: String`: Path to the file.
+    /// * `size: Integer`: Size of the file in bytes. For a symlink, this is the
+    ///   length of the target, and for a conflicted file, the size of the
+    ///   contents with conflict markers.
+    /// * `executable: Boolean`: True if the file is executable. For a
+    ///   conflicted file, true if the sides agree that it's executable.
+    /// * `conflict: Boolean`: True if the file is conflicted.
+    ///
+    /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
+    #[arg(long, short = 'T', value_name = "TEMPLATE", verbatim_doc_comment)]
+    template: Option<String>,
+    /// Terminate each path with a NUL character instead of a newline
+    ///
+    /// With `--template`, include `"\0"` in the template instead.
+    #[arg(long = "null", short = '0', conflicts_with = "template")]
+    null_terminated: bool,
     /// Only list files matching these prefixes (instead of all files)
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -43,13 +74,102 @@ pub(crate) fn cmd_files(
     let matcher = workspace_command
         .parse_file_patterns(&args.paths)?
         .to_matcher();
+    if let Some(template_text) = &args.template {
+        let language = file_template_language();
+        let template = command.parse_template(
+            ui,
+            &language,
+            template_text,
+            GenericTemplateLanguage::wrap_self,
+        )?;
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        for (repo_path, value) in tree.entries_matching(matcher.as_ref()) {
+            let entry = FileEntry {
+                path: workspace_command.format_file_path(&repo_path),
+                repo_path,
+                value,
+                store: workspace_command.repo().store().clone(),
+                conflict_marker_options: workspace_command.conflict_marker_options(),
+            };
+            template.format(&entry, formatter.as_mut())?;
+        }
+        return Ok(());
+    }
+
     ui.request_pager();
+    let terminator = if args.null_terminated { "\0" } else { "\n" };
     for (name, _value) in tree.entries_matching(matcher.as_ref()) {
-        writeln!(
+        write!(
             ui.stdout(),
-            "{}",
+            "{}{terminator}",
             &workspace_command.format_file_path(&name)
         )?;
     }
     Ok(())
 }
+
+#[derive(Clone)]
+struct FileEntry {
+    path: String,
+    repo_path: RepoPathBuf,
+    value: MergedTreeValue,
+    store: Arc<Store>,
+    conflict_marker_options: ConflictMarkerOptions,
+}
+
+impl FileEntry {
+    /// Returns the size of the file as `jj cat` would print it.
+    fn size(&self) -> Result<u64, TemplatePropertyError> {
+        let materialized = materialize_tree_value(
+            &self.store,
+            &self.repo_path,
+            self.value.clone(),
+            self.conflict_marker_options,
+        )
+        .block_on()?;
+        let size = match materialized {
+            MaterializedTreeValue::File { mut reader, .. } => {
+                io::copy(&mut reader, &mut io::sink())?
+            }
+            MaterializedTreeValue::Symlink { target, .. } => target.len() as u64,
+            MaterializedTreeValue::Conflict { contents, .. } => contents.len() as u64,
+            MaterializedTreeValue::Absent
+            | MaterializedTreeValue::GitSubmodule(_)
+            | MaterializedTreeValue::Tree(_) => 0,
+        };
+        Ok(size)
+    }
+
+    fn executable(&self) -> bool {
+        let executable = self.value.maybe_map(|term| match term {
+            Some(TreeValue::File { executable, .. }) => Some(*executable),
+            _ => None,
+        });
+        executable.is_some_and(|executable| executable.resolve_trivial() == Some(&true))
+    }
+}
+
+// FileEntry will be cloned internally in the templater. If the cloning cost
+// matters, wrap it with Rc.
+fn file_template_language() -> GenericTemplateLanguage<'static, FileEntry> {
+    type L = GenericTemplateLanguage<'static, FileEntry>;
+    let mut language = L::new();
+    language.add_keyword("path", |self_property| {
+        let out_property = self_property.map(|entry| entry.path);
+        Ok(L::wrap_string(out_property))
+    });
+    language.add_keyword("size", |self_property| {
+        let out_property = self_property.and_then(|entry| Ok(entry.size()?.try_into()?));
+        Ok(L::wrap_integer(out_property))
+    });
+    language.add_keyword("executable", |self_property| {
+        let out_property = self_property.map(|entry| entry.executable());
+        Ok(L::wrap_boolean(out_property))
+    });
+    language.add_keyword("conflict", |self_property| {
+        let out_property = self_property.map(|entry| !entry.value.is_resolved());
+        Ok(L::wrap_boolean(out_property))
+    });
+    language
+}
@@ -24,13 +24,14 @@ use jj_lib::backend::TreeValue;
 use jj_lib::file_util;
 use jj_lib::git::{
     self, parse_gitmodules, GitBranchPushTargets, GitFetchError, GitFetchStats, GitPushError,
+    RefName,
 };
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_store::RefTarget;
 use jj_lib::refs::{
     classify_branch_push_action, BranchPushAction, BranchPushUpdate, LocalAndRemoteRef,
 };
-use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::repo::{MutableRepo, ReadonlyRepo, Repo};
 use jj_lib::repo_path::RepoPath;
 use jj_lib::revset::{self, RevsetExpression, RevsetIteratorExt as _};
 use jj_lib::settings::{ConfigResultExt as _, UserSettings};
@@ -44,12 +45,15 @@ use crate::cli_util::{
     CommandHelper, RevisionArg, WorkspaceCommandHelper, WorkspaceCommandTransaction,
 };
 use crate::command_error::{
-    user_error, user_error_with_hint, user_error_with_message, CommandError,
+    config_error_with_message, user_error, user_error_with_hint, user_error_with_message,
+    CommandError,
 };
 use crate::git_util::{
-    get_git_repo, is_colocated_git_workspace, print_failed_git_export, print_git_import_stats,
-    with_remote_git_callbacks, GitSidebandProgressMessageWriter,
+    find_expired_remote_branches, get_git_repo, is_colocated_git_workspace,
+    print_failed_git_export, print_git_import_stats, with_remote_git_callbacks,
+    GitSidebandProgressMessageWriter,
 };
+use crate::hooks::{self, HookKind, PushHookBranch, PushHookInput};
 use crate::ui::Ui;
 
 /// Commands for working with the underlying Git repo
@@ -66,6 +70,7 @@ pub enum GitCommand {
     Push(GitPushArgs),
     Import(GitImportArgs),
     Export(GitExportArgs),
+    Colocate(GitColocateArgs),
     #[command(subcommand, hide = true)]
     Submodule(GitSubmoduleCommand),
 }
@@ -150,6 +155,10 @@ pub struct GitInitArgs {
 ///
 /// If a working-copy commit gets abandoned, it will be given a new, empty
 /// commit. This is true in general; it is not specific to this command.
+///
+/// If `git.prune-expired-remote-branches` is enabled, the non-tracking remote
+/// branches which expired according to `git.remote-branch-expiry` are pruned
+/// after fetching, and aren't fetched again while pruning is enabled.
 #[derive(clap::Args, Clone, Debug)]
 pub struct GitFetchArgs {
     /// Fetch only some of the branches
@@ -158,6 +167,12 @@ pub struct GitFetchArgs {
     /// expand `*` as a glob. The other wildcard characters aren't supported.
     #[arg(long, short, default_value = "glob:*", value_parser = StringPattern::parse)]
     branch: Vec<StringPattern>,
+    /// Fetch only the branches which are tracked (see `jj branch track`)
+    ///
+    /// Branches which are new on the remote, or not tracked locally, are
+    /// left as they are.
+    #[arg(long, conflicts_with = "branch")]
+    tracked: bool,
     /// The remote to fetch from (only named remotes are supported, can be
     /// repeated)
     #[arg(long = "remote", value_name = "remote")]
@@ -227,6 +242,10 @@ pub struct GitPushArgs {
     /// repeated)
     #[arg(long, short)]
     change: Vec<RevisionArg>,
+    /// Allow moving or deleting branches protected by
+    /// `git.push-protected-branches`
+    #[arg(long)]
+    allow_protected: bool,
     /// Only display what will change on the remote
     #[arg(long)]
     dry_run: bool,
@@ -237,12 +256,30 @@ pub struct GitPushArgs {
 /// If a working-copy commit gets abandoned, it will be given a new, empty
 /// commit. This is true in general; it is not specific to this command.
 #[derive(clap::Args, Clone, Debug)]
-pub struct GitImportArgs {}
+pub struct GitImportArgs {
+    /// Import only some of the branches (can be repeated)
+    ///
+    /// By default, all branches and tags are imported. If this is specified,
+    /// only the local and remote branches matching the pattern are imported.
+    /// By default, the specified name matches exactly. Use `glob:` prefix to
+    /// expand `*` as a glob. The other wildcard characters aren't supported.
+    #[arg(long, short, value_parser = StringPattern::parse)]
+    branch: Vec<StringPattern>,
+}
 
 /// Update the underlying Git repo with changes made in the repo
 #[derive(clap::Args, Clone, Debug)]
 pub struct GitExportArgs {}
 
+/// Turn a repo backed by an internal Git repo into a colocated repo
+///
+/// The Git repo is moved from `.jj/repo/store/git` to the `.git` directory of
+/// the workspace, so the workspace can also be used with `git` commands. The
+/// Git HEAD is set to the parent of the working-copy commit, and the branches
+/// are exported to the Git repo. The files in the workspace aren't modified.
+#[derive(clap::Args, Clone, Debug)]
+pub struct GitColocateArgs {}
+
 /// FOR INTERNAL USE ONLY Interact with git submodules
 #[derive(Subcommand, Clone, Debug)]
 pub enum GitSubmoduleCommand {
@@ -267,7 +304,7 @@ fn make_branch_term(branch_names: &[impl fmt::Display]) -> String {
     }
 }
 
-fn map_git_error(err: git2::Error) -> CommandError {
+pub fn map_git_error(err: git2::Error) -> CommandError {
     if err.class() == git2::ErrorClass::Ssh {
         let hint =
             if err.code() == git2::ErrorCode::Certificate && std::env::var_os("HOME").is_none() {
@@ -531,22 +568,53 @@ fn cmd_git_fetch(
     } else {
         args.remotes.clone()
     };
+    let prune_expired = command
+        .settings()
+        .config()
+        .get_bool("git.prune-expired-remote-branches")
+        .optional()?
+        .unwrap_or(false);
     let mut tx = workspace_command.start_transaction();
+    let mut num_pruned = 0;
     for remote in &remotes {
-        let stats = with_remote_git_callbacks(ui, None, |cb| {
+        let branch_patterns = if args.tracked {
+            let patterns = tx
+                .base_repo()
+                .view()
+                .remote_branches(remote)
+                .filter(|(_, remote_ref)| remote_ref.is_tracking())
+                .map(|(name, _)| StringPattern::exact(name))
+                .collect_vec();
+            if patterns.is_empty() {
+                writeln!(
+                    ui.status(),
+                    "No tracked branches to fetch from remote {remote}"
+                )?;
+                continue;
+            }
+            patterns
+        } else {
+            args.branch.clone()
+        };
+        let excluded_branches = if prune_expired {
+            find_pruned_remote_branches(&git_repo, remote)?
+        } else {
+            vec![]
+        };
+        let mut stats = with_remote_git_callbacks(ui, None, |cb| {
             git::fetch(
                 tx.mut_repo(),
                 &git_repo,
                 remote,
-                &args.branch,
+                &branch_patterns,
+                &excluded_branches,
                 cb,
                 &command.settings().git_settings(),
             )
         })
         .map_err(|err| match err {
             GitFetchError::InvalidBranchPattern => {
-                if args
-                    .branch
+                if branch_patterns
                     .iter()
                     .any(|pattern| pattern.as_exact().map_or(false, |s| s.contains('*')))
                 {
@@ -562,8 +630,24 @@ fn cmd_git_fetch(
             GitFetchError::InternalGitError(err) => map_git_error(err),
             _ => user_error(err),
         })?;
+        if prune_expired {
+            let pruned_refs = prune_expired_remote_branches(
+                tx.mut_repo(),
+                command.settings(),
+                &git_repo,
+                remote,
+            )?;
+            num_pruned += pruned_refs.len();
+            stats
+                .import_stats
+                .changed_remote_refs
+                .retain(|ref_name, _| !pruned_refs.contains(ref_name));
+        }
         print_git_import_stats(ui, tx.repo(), &stats.import_stats, true)?;
     }
+    if num_pruned > 0 {
+        writeln!(ui.status(), "Pruned {num_pruned} expired remote branches")?;
+    }
     tx.finish(
         ui,
         format!("fetch from git remote(s) {}", remotes.iter().join(",")),
@@ -571,6 +655,63 @@ fn cmd_git_fetch(
     Ok(())
 }
 
+/// Namespace of the Git refs remembering the pruned remote branches, which
+/// aren't fetched again. The refs also keep the pruned commits from being
+/// garbage collected, so fetching them again wouldn't download anything.
+const PRUNED_REMOTE_REFS_PREFIX: &str = "refs/jj/pruned/";
+
+/// Returns the names of the branches of the `remote` pruned by previous
+/// fetches.
+fn find_pruned_remote_branches(
+    git_repo: &git2::Repository,
+    remote: &str,
+) -> Result<Vec<String>, CommandError> {
+    let prefix = format!("{PRUNED_REMOTE_REFS_PREFIX}{remote}/");
+    let mut branches = vec![];
+    for git_ref in git_repo.references_glob(&format!("{prefix}*"))? {
+        if let Some(branch) = git_ref?.name().and_then(|name| name.strip_prefix(&prefix)) {
+            branches.push(branch.to_owned());
+        }
+    }
+    Ok(branches)
+}
+
+/// Moves the Git remote-tracking refs of the expired remote branches of the
+/// `remote` to `PRUNED_REMOTE_REFS_PREFIX`, and imports the deletions. Returns
+/// the pruned refs.
+fn prune_expired_remote_branches(
+    mut_repo: &mut MutableRepo,
+    settings: &UserSettings,
+    git_repo: &git2::Repository,
+    remote: &str,
+) -> Result<Vec<RefName>, CommandError> {
+    let expired_branches = find_expired_remote_branches(&*mut_repo, settings)?
+        .into_iter()
+        .filter(|(_, branch_remote)| branch_remote == remote)
+        .collect_vec();
+    if expired_branches.is_empty() {
+        return Ok(vec![]);
+    }
+    for (branch, remote) in &expired_branches {
+        if let Ok(mut git_ref) = git_repo.find_reference(&format!("refs/remotes/{remote}/{branch}"))
+        {
+            git_ref.rename(
+                &format!("{PRUNED_REMOTE_REFS_PREFIX}{remote}/{branch}"),
+                true,
+                "prune expired remote branch",
+            )?;
+        }
+    }
+    let expired_refs = expired_branches
+        .into_iter()
+        .map(|(branch, remote)| RefName::RemoteBranch { branch, remote })
+        .collect_vec();
+    git::import_some_refs(mut_repo, &settings.git_settings(), |ref_name| {
+        expired_refs.contains(ref_name)
+    })?;
+    Ok(expired_refs)
+}
+
 fn get_single_remote(git_repo: &git2::Repository) -> Result<Option<String>, CommandError> {
     let git_remotes = git_repo.remotes()?;
     Ok(match git_remotes.len() {
@@ -581,7 +722,7 @@ fn get_single_remote(git_repo: &git2::Repository) -> Result<Option<String>, Comm
 
 const DEFAULT_REMOTE: &str = "origin";
 
-fn get_default_fetch_remotes(
+pub fn get_default_fetch_remotes(
     ui: &Ui,
     settings: &UserSettings,
     git_repo: &git2::Repository,
@@ -760,6 +901,7 @@ fn do_git_clone(
             &git_repo,
             remote_name,
             &[StringPattern::everything()],
+            &[],
             cb,
             &command.settings().git_settings(),
         )
@@ -898,6 +1040,19 @@ fn cmd_git_push(
         return Ok(());
     }
 
+    if !args.allow_protected {
+        check_protected_branches(command.settings(), &remote, &branch_updates)?;
+    }
+    if command
+        .settings()
+        .config()
+        .get_bool("git.push-requires-fetch")
+        .optional()?
+        .unwrap_or(false)
+    {
+        check_remote_branches_up_to_date(ui, &git_repo, &remote, &branch_updates)?;
+    }
+
     let mut new_heads = vec![];
     let mut force_pushed_branches = hashset! {};
     for (branch_name, update) in &branch_updates {
@@ -1001,6 +1156,26 @@ fn cmd_git_push(
         return Ok(());
     }
 
+    let hook_input = PushHookInput {
+        args: command.string_args().clone(),
+        remote: remote.clone(),
+        branches: branch_updates
+            .iter()
+            .map(|(name, update)| PushHookBranch {
+                name: name.clone(),
+                old_target: update.old_target.as_ref().map(|id| id.hex()),
+                new_target: update.new_target.as_ref().map(|id| id.hex()),
+            })
+            .collect(),
+    };
+    hooks::run_hook(
+        ui,
+        command.settings(),
+        HookKind::PrePush,
+        tx.base_workspace_helper().workspace_root(),
+        &hook_input,
+    )?;
+
     let targets = GitBranchPushTargets {
         branch_updates,
         force_pushed_branches,
@@ -1026,7 +1201,81 @@ fn cmd_git_push(
     Ok(())
 }
 
-fn get_default_push_remote(
+/// Rejects updates which would move or delete remote branches matching
+/// `git.push-protected-branches`. Creating such a branch is allowed.
+fn check_protected_branches(
+    settings: &UserSettings,
+    remote: &str,
+    branch_updates: &[(String, BranchPushUpdate)],
+) -> Result<(), CommandError> {
+    let patterns: Vec<StringPattern> = settings
+        .config()
+        .get::<Vec<String>>("git.push-protected-branches")
+        .optional()?
+        .unwrap_or_default()
+        .iter()
+        .map(|pattern| {
+            StringPattern::glob(pattern).map_err(|err| {
+                config_error_with_message(
+                    format!("Invalid pattern in git.push-protected-branches: {pattern}"),
+                    err,
+                )
+            })
+        })
+        .try_collect()?;
+    let protected_names = branch_updates
+        .iter()
+        .filter(|(_, update)| update.old_target.is_some())
+        .filter(|(branch_name, _)| patterns.iter().any(|pattern| pattern.matches(branch_name)))
+        .map(|(branch_name, _)| format!("{branch_name}@{remote}"))
+        .collect_vec();
+    if protected_names.is_empty() {
+        return Ok(());
+    }
+    Err(user_error_with_hint(
+        format!(
+            "Refusing to move or delete protected {}",
+            make_branch_term(&protected_names)
+        ),
+        "Use --allow-protected to push anyway.",
+    ))
+}
+
+/// Rejects updates of branches which were moved on the remote since the last
+/// fetch, as seen by listing the branches of the remote.
+fn check_remote_branches_up_to_date(
+    ui: &Ui,
+    git_repo: &git2::Repository,
+    remote: &str,
+    branch_updates: &[(String, BranchPushUpdate)],
+) -> Result<(), CommandError> {
+    let remote_branches = with_remote_git_callbacks(ui, None, |cb| {
+        git::list_remote_branches(git_repo, remote, cb)
+    })
+    .map_err(|err| match err {
+        GitPushError::InternalGitError(err) => map_git_error(err),
+        _ => user_error(err),
+    })?;
+    let stale_names = branch_updates
+        .iter()
+        .filter(|(branch_name, update)| {
+            remote_branches.get(branch_name) != update.old_target.as_ref()
+        })
+        .map(|(branch_name, _)| format!("{branch_name}@{remote}"))
+        .collect_vec();
+    if stale_names.is_empty() {
+        return Ok(());
+    }
+    Err(user_error_with_hint(
+        format!(
+            "Refusing to push since {} moved on the remote since the last fetch",
+            make_branch_term(&stale_names)
+        ),
+        "Run `jj git fetch` to update the branches, then push again.",
+    ))
+}
+
+pub fn get_default_push_remote(
     ui: &Ui,
     settings: &UserSettings,
     git_repo: &git2::Repository,
@@ -1226,14 +1475,25 @@ fn find_branches_targeted_by_revisions<'a>(
 fn cmd_git_import(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &GitImportArgs,
+    args: &GitImportArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let mut tx = workspace_command.start_transaction();
     // In non-colocated repo, HEAD@git will never be moved internally by jj.
     // That's why cmd_git_export() doesn't export the HEAD ref.
     git::import_head(tx.mut_repo())?;
-    let stats = git::import_refs(tx.mut_repo(), &command.settings().git_settings())?;
+    let git_settings = command.settings().git_settings();
+    let stats = if args.branch.is_empty() {
+        git::import_refs(tx.mut_repo(), &git_settings)?
+    } else {
+        git::import_some_refs(tx.mut_repo(), &git_settings, |ref_name| {
+            let branch = match ref_name {
+                RefName::LocalBranch(branch) | RefName::RemoteBranch { branch, .. } => branch,
+                RefName::Tag(_) => return false,
+            };
+            args.branch.iter().any(|pattern| pattern.matches(branch))
+        })?
+    };
     print_git_import_stats(ui, tx.repo(), &stats, true)?;
     tx.finish(ui, "import git refs")?;
     Ok(())
@@ -1252,6 +1512,116 @@ fn cmd_git_export(
     Ok(())
 }
 
+fn cmd_git_colocate(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &GitColocateArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    if workspace_command.working_copy_shared_with_git() {
+        return Err(user_error("The repo is already colocated with Git"));
+    }
+    let Some(git_backend) = workspace_command.git_backend() else {
+        return Err(user_error("The repo is not backed by a Git repo"));
+    };
+    let workspace_root = workspace_command.workspace_root().clone();
+    let repo_path = command.workspace_loader()?.repo_path().to_owned();
+    if repo_path != workspace_root.join(".jj").join("repo") {
+        return Err(user_error_with_hint(
+            "The repo can only be colocated in the workspace which contains it",
+            format!(
+                r#"Run this command in the workspace at "{}"."#,
+                repo_path.parent().and_then(Path::parent).unwrap().display()
+            ),
+        ));
+    }
+    let store_path = repo_path.join("store");
+    let internal_git_repo_path = store_path.join("git");
+    if internal_git_repo_path.canonicalize().ok().as_deref() != Some(git_backend.git_repo_path()) {
+        return Err(user_error_with_hint(
+            "The repo isn't backed by an internal Git repo",
+            format!(
+                r#"The Git repo at "{}" can be used directly instead."#,
+                git_backend.git_repo_path().display()
+            ),
+        ));
+    }
+    let colocated_git_repo_path = workspace_root.join(".git");
+    if colocated_git_repo_path.exists() {
+        return Err(user_error(format!(
+            r#"There is already a Git repo at "{}""#,
+            colocated_git_repo_path.display()
+        )));
+    }
+    drop(workspace_command);
+
+    move_git_repo_to_workspace(
+        &store_path,
+        &internal_git_repo_path,
+        &colocated_git_repo_path,
+    )?;
+
+    let mut workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    maybe_add_gitignore(&workspace_command)?;
+    let git_repo = get_git_repo(workspace_command.repo().store())?;
+    let mut tx = workspace_command.start_transaction();
+    if let Some(wc_commit_id) = tx
+        .repo()
+        .view()
+        .get_wc_commit_id(tx.base_workspace_helper().workspace_id())
+    {
+        let wc_commit = tx.repo().store().get_commit(wc_commit_id)?;
+        git::reset_head(tx.mut_repo(), &git_repo, &wc_commit)?;
+    }
+    let failed_branches = git::export_refs(tx.mut_repo())?;
+    if tx.mut_repo().has_changes() {
+        tx.finish(ui, "colocate git repo")?;
+    }
+    print_failed_git_export(ui, &failed_branches)?;
+    writeln!(
+        ui.status(),
+        r#"Moved the Git repo to "{}". The repo is now colocated."#,
+        file_util::relative_path(command.cwd(), &colocated_git_repo_path).display()
+    )?;
+    Ok(())
+}
+
+/// Moves the internal Git repo to `colocated_git_repo_path` and points the
+/// store to it. If any step fails, the previous steps are undone so that the
+/// jj repo remains usable.
+fn move_git_repo_to_workspace(
+    store_path: &Path,
+    internal_git_repo_path: &Path,
+    colocated_git_repo_path: &Path,
+) -> Result<(), CommandError> {
+    let git_target_path = store_path.join("git_target");
+    let old_git_target = fs::read(&git_target_path)
+        .map_err(|err| user_error_with_message("Failed to read the path to the Git repo", err))?;
+    fs::rename(internal_git_repo_path, colocated_git_repo_path)
+        .map_err(|err| user_error_with_message("Failed to move the Git repo", err))?;
+    // The internal Git repo is bare since it doesn't have a worktree.
+    let set_bare = |bare: bool| {
+        git2::Repository::open(colocated_git_repo_path)
+            .and_then(|git_repo| git_repo.config()?.set_bool("core.bare", bare))
+    };
+    let result = set_bare(false)
+        .map_err(|err| user_error_with_message("Failed to configure the Git repo", err))
+        .and_then(|()| {
+            let git_target = file_util::relative_path(store_path, colocated_git_repo_path);
+            let git_target = git_target.to_str().unwrap().replace('\\', "/");
+            fs::write(&git_target_path, git_target).map_err(|err| {
+                user_error_with_message("Failed to update the path to the Git repo", err)
+            })
+        });
+    if result.is_err() {
+        // Best effort: the original error is more useful than the rollback's.
+        fs::write(&git_target_path, old_git_target).ok();
+        set_bare(true).ok();
+        fs::rename(colocated_git_repo_path, internal_git_repo_path).ok();
+    }
+    result
+}
+
 fn cmd_git_submodule_print_gitmodules(
     ui: &mut Ui,
     command: &CommandHelper,
@@ -1306,6 +1676,7 @@ pub fn cmd_git(
         GitCommand::Push(args) => cmd_git_push(ui, command, args),
         GitCommand::Import(args) => cmd_git_import(ui, command, args),
         GitCommand::Export(args) => cmd_git_export(ui, command, args),
+        GitCommand::Colocate(args) => cmd_git_colocate(ui, command, args),
         GitCommand::Submodule(GitSubmoduleCommand::PrintGitmodules(args)) => {
             cmd_git_submodule_print_gitmodules(ui, command, args)
         }
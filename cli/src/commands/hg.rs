@@ -0,0 +1,194 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use jj_lib::hg;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::{user_error, user_error_with_message, CommandError};
+use crate::ui::Ui;
+
+/// Commands for working with Mercurial repos
+#[derive(clap::Subcommand, Clone, Debug)]
+pub(crate) enum HgCommand {
+    Import(HgImportArgs),
+}
+
+/// Import the changesets of a Mercurial repo into this repo
+///
+/// The changesets are converted with Mercurial's `hg fastexport`, which
+/// requires Mercurial 5.3 or later. The obsolete changesets are imported too,
+/// as hidden predecessors of their successors, so `jj obslog` shows how the
+/// changesets evolved. The successors keep the change id of their
+/// predecessor. Bookmarks, tags and phases aren't imported.
+///
+/// Importing the same repo again only adds the new changesets.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct HgImportArgs {
+    /// Path of the Mercurial repo
+    #[arg(
+        required_unless_present = "fast_export",
+        conflicts_with = "fast_export",
+        value_hint = clap::ValueHint::DirPath
+    )]
+    repo: Option<PathBuf>,
+    /// Read the changesets from a stream in `git fast-import` format instead of
+    /// running `hg fastexport`
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    fast_export: Option<PathBuf>,
+    /// File mapping the marks of the stream to Mercurial changeset ids, as
+    /// written by `hg fastexport --export-marks`
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "fast_export",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    marks: Option<PathBuf>,
+    /// Obsolescence markers, as printed by `hg debugobsolete -Tjson`
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "fast_export",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    obs_markers: Option<PathBuf>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_hg(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &HgCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        HgCommand::Import(sub_args) => cmd_hg_import(ui, command, sub_args),
+    }
+}
+
+#[instrument(skip_all)]
+fn cmd_hg_import(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &HgImportArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let export = if let Some(repo_path) = &args.repo {
+        export_hg_repo(&command.cwd().join(repo_path))?
+    } else {
+        let read_file = |path: &Path| {
+            fs::read(path).map_err(|err| {
+                user_error_with_message(format!("Failed to read {}", path.display()), err)
+            })
+        };
+        let stream = read_file(args.fast_export.as_ref().unwrap())?;
+        let marks = match &args.marks {
+            Some(path) => hg::parse_marks(&String::from_utf8_lossy(&read_file(path)?))?,
+            None => HashMap::new(),
+        };
+        let obs_markers = match &args.obs_markers {
+            Some(path) => hg::parse_obs_markers(&read_file(path)?)?,
+            None => vec![],
+        };
+        HgExport {
+            stream,
+            marks,
+            obs_markers,
+        }
+    };
+
+    let mut tx = workspace_command.start_transaction();
+    let stats = hg::import_fast_export(
+        tx.mut_repo(),
+        command.settings(),
+        &export.stream,
+        &export.marks,
+        &export.obs_markers,
+    )?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        writeln!(
+            formatter,
+            "Imported {} commits ({} obsolete)",
+            stats.num_commits, stats.num_obsolete
+        )?;
+    }
+    tx.finish(ui, "import hg repo")?;
+    Ok(())
+}
+
+/// The history of a Mercurial repo, as read by `jj hg import`.
+struct HgExport {
+    /// Output of `hg fastexport`
+    stream: Vec<u8>,
+    /// Marks of the fast-export stream to Mercurial changeset ids
+    marks: HashMap<u64, String>,
+    obs_markers: Vec<hg::ObsMarker>,
+}
+
+/// Runs `hg fastexport` and `hg debugobsolete` in the Mercurial repo, including
+/// the hidden changesets.
+fn export_hg_repo(repo_path: &Path) -> Result<HgExport, CommandError> {
+    let marks_file = tempfile::NamedTempFile::new()?;
+    let marks_path = marks_file.path().to_str().ok_or_else(|| {
+        user_error(format!(
+            "Path of the marks file isn't valid UTF-8: {}",
+            marks_file.path().display()
+        ))
+    })?;
+    let stream = run_hg(
+        repo_path,
+        "fastexport",
+        &[
+            "--config",
+            "extensions.fastexport=",
+            "--export-marks",
+            marks_path,
+        ],
+    )?;
+    let marks = hg::parse_marks(&fs::read_to_string(marks_file.path())?)?;
+    let obs_markers = hg::parse_obs_markers(&run_hg(repo_path, "debugobsolete", &["-Tjson"])?)?;
+    Ok(HgExport {
+        stream,
+        marks,
+        obs_markers,
+    })
+}
+
+fn run_hg(repo_path: &Path, subcommand: &str, args: &[&str]) -> Result<Vec<u8>, CommandError> {
+    let output = Command::new("hg")
+        .arg("--repository")
+        .arg(repo_path)
+        .arg("--hidden")
+        .arg(subcommand)
+        .args(args)
+        // Ignore the user's configuration of the output
+        .env("HGPLAIN", "1")
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| user_error_with_message("Failed to run hg", err))?;
+    if !output.status.success() {
+        return Err(user_error(format!(
+            "hg {subcommand} failed with {}",
+            output.status
+        )));
+    }
+    Ok(output.stdout)
+}
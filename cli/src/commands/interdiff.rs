@@ -13,11 +13,13 @@
 // limitations under the License.
 
 use clap::ArgGroup;
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
 use jj_lib::rewrite::rebase_to_dest_parent;
 use tracing::instrument;
 
-use crate::cli_util::{CommandHelper, RevisionArg};
-use crate::command_error::CommandError;
+use crate::cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper};
+use crate::command_error::{user_error, CommandError};
 use crate::diff_util::{self, DiffFormatArgs};
 use crate::ui::Ui;
 
@@ -26,6 +28,10 @@ use crate::ui::Ui;
 /// This excludes changes from other commits by temporarily rebasing `--from`
 /// onto `--to`'s parents. If you wish to compare the same change across
 /// versions, consider `jj obslog -p` instead.
+///
+/// With `--stack`, `--from` and `--to` are revsets of two versions of a stack
+/// of commits, such as a patch series before and after rebasing. The commits
+/// are paired in topological order, and the changes of each pair are compared.
 #[derive(clap::Args, Clone, Debug)]
 #[command(group(ArgGroup::new("to_diff").args(&["from", "to"]).multiple(true).required(true)))]
 pub(crate) struct InterdiffArgs {
@@ -35,6 +41,9 @@ pub(crate) struct InterdiffArgs {
     /// Show changes to this revision
     #[arg(long)]
     to: Option<RevisionArg>,
+    /// Compare two stacks of the same number of commits commit by commit
+    #[arg(long, requires = "from", requires = "to")]
+    stack: bool,
     /// Restrict the diff to these paths
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -49,6 +58,9 @@ pub(crate) fn cmd_interdiff(
     args: &InterdiffArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
+    if args.stack {
+        return cmd_interdiff_stack(ui, command, &workspace_command, args);
+    }
     let from =
         workspace_command.resolve_single_rev(args.from.as_ref().unwrap_or(&RevisionArg::AT))?;
     let to = workspace_command.resolve_single_rev(args.to.as_ref().unwrap_or(&RevisionArg::AT))?;
@@ -63,10 +75,76 @@ pub(crate) fn cmd_interdiff(
     diff_util::show_diff(
         ui,
         ui.stdout_formatter().as_mut(),
+        command.settings(),
         &workspace_command,
         &from_tree,
         &to_tree,
         matcher.as_ref(),
+        &[],
         &diff_formats,
     )
 }
+
+fn cmd_interdiff_stack(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_command: &WorkspaceCommandHelper,
+    args: &InterdiffArgs,
+) -> Result<(), CommandError> {
+    let resolve_stack = |revision_arg: &RevisionArg| -> Result<Vec<Commit>, CommandError> {
+        let commits: Vec<_> = workspace_command
+            .parse_revset(revision_arg)?
+            .evaluate_to_commits()?
+            .try_collect()?;
+        // Oldest first
+        Ok(commits.into_iter().rev().collect())
+    };
+    let from_commits = resolve_stack(args.from.as_ref().unwrap())?;
+    let to_commits = resolve_stack(args.to.as_ref().unwrap())?;
+    if from_commits.is_empty() || to_commits.is_empty() {
+        return Err(user_error("Empty revision set"));
+    }
+    if from_commits.len() != to_commits.len() {
+        return Err(user_error(format!(
+            "The stacks have different numbers of commits: {} and {}",
+            from_commits.len(),
+            to_commits.len()
+        )));
+    }
+    let matcher = workspace_command
+        .parse_file_patterns(&args.paths)?
+        .to_matcher();
+    let diff_formats = diff_util::diff_formats_for(command.settings(), &args.format)?;
+    let template = workspace_command.commit_summary_template();
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let formatter = formatter.as_mut();
+    for (i, (from, to)) in from_commits.iter().zip(&to_commits).enumerate() {
+        if i > 0 {
+            writeln!(formatter)?;
+        }
+        let from_tree = rebase_to_dest_parent(workspace_command.repo().as_ref(), from, to)?;
+        let to_tree = to.tree()?;
+        write!(formatter, "From: ")?;
+        template.format(from, formatter)?;
+        write!(formatter, "\nTo:   ")?;
+        template.format(to, formatter)?;
+        writeln!(formatter)?;
+        if from_tree.id() == to_tree.id() {
+            writeln!(formatter.labeled("empty"), "(no difference)")?;
+            continue;
+        }
+        diff_util::show_diff(
+            ui,
+            formatter,
+            command.settings(),
+            workspace_command,
+            &from_tree,
+            &to_tree,
+            matcher.as_ref(),
+            &[],
+            &diff_formats,
+        )?;
+    }
+    Ok(())
+}
@@ -12,19 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use jj_lib::backend::CommitId;
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use itertools::Itertools;
+use jj_lib::backend::{BackendResult, CommitId};
+use jj_lib::commit::Commit;
+use jj_lib::fileset::FilesetExpression;
+use jj_lib::matchers::{EverythingMatcher, FilesMatcher};
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::rename_detection::{self, RenameDetectionOptions};
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::revset::{self, RevsetExpression, RevsetFilterPredicate, RevsetIteratorExt};
 use jj_lib::revset_graph::{
-    ReverseRevsetGraphIterator, RevsetGraphEdgeType, TopoGroupedRevsetGraphIterator,
+    ReverseRevsetGraphIterator, RevsetGraphEdge, RevsetGraphEdgeType,
+    TopoGroupedRevsetGraphIterator,
 };
+use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::str_util::{StringPattern, StringPatternParseError};
+use jj_lib::time_util::{DatePattern, DatePatternParseError};
 use tracing::instrument;
 
 use crate::cli_util::{format_template, CommandHelper, LogContentFormat, RevisionArg};
-use crate::command_error::CommandError;
+use crate::command_error::{config_error_with_message, user_error, CommandError};
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::diff_util::{self, DiffFormatArgs};
+use crate::formatter::{Formatter, PlainTextFormatter};
 use crate::graphlog::{get_graphlog, Edge};
+use crate::revset_util::RevsetExpressionEvaluator;
 use crate::ui::Ui;
 
 /// Show revision history
@@ -45,17 +61,68 @@ pub(crate) struct LogArgs {
     /// Show revisions modifying the given paths
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Follow the history of the given file across renames and copies
+    ///
+    /// The revisions modifying the file are shown, and where the file was
+    /// added by renaming or copying another file, the revisions modifying the
+    /// other file in the ancestors are shown. Exactly one path must be given.
+    #[arg(long, requires = "paths")]
+    follow: bool,
+    /// Show revisions whose author name or email matches the given pattern
+    ///
+    /// Equivalent to intersecting with `author(PATTERN)`. The pattern is
+    /// matched as a substring unless prefixed with `exact:`, `glob:`,
+    /// `substring:`, or `regex:`.
+    #[arg(long, value_parser = parse_author_pattern)]
+    author: Vec<StringPattern>,
+    /// Show revisions committed at or after the given date
+    ///
+    /// Equivalent to intersecting with `committer_date(after:DATE)`.
+    #[arg(long, value_parser = parse_date_after)]
+    since: Option<DatePattern>,
+    /// Show revisions committed before the given date
+    ///
+    /// Equivalent to intersecting with `committer_date(before:DATE)`.
+    #[arg(long, value_parser = parse_date_before)]
+    until: Option<DatePattern>,
+    /// Show revisions whose diff adds or removes lines containing the string
+    ///
+    /// Only the diffs of the given paths are searched, if any. Equivalent to
+    /// intersecting with `diff_contains(substring:STRING)`.
+    #[arg(short = 'S', long, value_name = "STRING")]
+    diff_contains: Option<String>,
+    /// Show revisions whose diff adds or removes lines matching the regular
+    /// expression
+    ///
+    /// Only the diffs of the given paths are searched, if any. Equivalent to
+    /// intersecting with `diff_contains(regex:REGEX)`.
+    #[arg(short = 'G', long, value_name = "REGEX", value_parser = StringPattern::regex)]
+    diff_matches: Option<StringPattern>,
+    /// Also show the hidden predecessors of the revisions
+    ///
+    /// The revisions the hidden commits were rewritten into are shown below
+    /// them. The hidden commits can be recovered with `jj unhide`.
+    #[arg(long)]
+    hidden: bool,
     /// Show revisions in the opposite order (older revisions first)
     #[arg(long)]
     reversed: bool,
     /// Limit number of revisions to show
     ///
-    /// Applied after revisions are filtered and reordered.
+    /// Applied after revisions are filtered and reordered topologically, but
+    /// before being reversed.
     #[arg(long, short)]
     limit: Option<usize>,
     /// Don't show the graph, show a flat list of revisions
     #[arg(long)]
     no_graph: bool,
+    /// Print the graph as a description in another language
+    ///
+    /// Each revision is a node labeled with the template, which defaults to
+    /// the `templates.commit_summary` setting. Revisions whose ancestors are
+    /// elided are connected to them by dashed edges.
+    #[arg(long, value_enum, conflicts_with_all = ["no_graph", "reversed", "patch"])]
+    graph_format: Option<GraphFormat>,
     /// Render each revision using the given template
     ///
     /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
@@ -68,6 +135,90 @@ pub(crate) struct LogArgs {
     diff_format: DiffFormatArgs,
 }
 
+/// Returns the ids of the predecessors of the `commits`, recursively.
+fn collect_predecessor_ids(commits: Vec<Commit>) -> BackendResult<Vec<CommitId>> {
+    let mut seen: HashSet<CommitId> = commits.iter().map(|commit| commit.id().clone()).collect();
+    let mut predecessor_ids = vec![];
+    let mut pending = commits;
+    while let Some(commit) = pending.pop() {
+        for id in commit.predecessor_ids() {
+            if seen.insert(id.clone()) {
+                pending.push(commit.store().get_commit(id)?);
+                predecessor_ids.push(id.clone());
+            }
+        }
+    }
+    Ok(predecessor_ids)
+}
+
+/// Walks the revisions of the `expression` from the newest ones, and returns
+/// the ids of the ones modifying the file at `path`, along with all the paths
+/// the file had.
+///
+/// The path is followed separately in each line of history: where the file was
+/// added by renaming or copying another file, the other file is followed in
+/// the parents of the revision.
+fn follow_file_history(
+    repo: &dyn Repo,
+    expression: &RevsetExpressionEvaluator,
+    path: RepoPathBuf,
+) -> Result<(Vec<CommitId>, Vec<RepoPathBuf>), CommandError> {
+    let mut commit_ids = vec![];
+    let mut paths = vec![path.clone()];
+    // Paths to follow in the visited parents, the first child taking
+    // precedence
+    let mut parent_paths: HashMap<CommitId, RepoPathBuf> = HashMap::new();
+    for commit in expression.evaluate_to_commits()? {
+        let commit = commit?;
+        let commit_path = parent_paths
+            .remove(commit.id())
+            .unwrap_or_else(|| path.clone());
+        let parent_tree = merge_commit_trees(repo, &commit.parents())?;
+        let tree = commit.tree()?;
+        let matcher = FilesMatcher::new([&commit_path]);
+        let mut source_path = commit_path.clone();
+        if parent_tree.diff(&tree, &matcher).next().is_some() {
+            commit_ids.push(commit.id().clone());
+            if parent_tree.path_value(&commit_path).is_absent() {
+                // The file was added, maybe as a rename or copy of another file
+                let diff: Vec<_> = parent_tree
+                    .diff(&tree, &EverythingMatcher)
+                    .map(|(path, diff)| diff.map(|(before, after)| (path, before, after)))
+                    .try_collect()?;
+                let options = RenameDetectionOptions {
+                    copies: commit.copies().to_vec(),
+                    ..Default::default()
+                };
+                let renames = rename_detection::detect_renames(repo.store(), &diff, &options)?;
+                if let Some(rename) = renames
+                    .into_iter()
+                    .find(|rename| rename.target == commit_path)
+                {
+                    source_path = rename.source;
+                    if !paths.contains(&source_path) {
+                        paths.push(source_path.clone());
+                    }
+                }
+            }
+        }
+        for parent_id in commit.parent_ids() {
+            parent_paths
+                .entry(parent_id.clone())
+                .or_insert_with(|| source_path.clone());
+        }
+    }
+    Ok((commit_ids, paths))
+}
+
+/// Languages in which the graph can be printed.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
 #[instrument(skip_all)]
 pub(crate) fn cmd_log(
     ui: &mut Ui,
@@ -76,24 +227,75 @@ pub(crate) fn cmd_log(
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
 
-    let fileset_expression = workspace_command.parse_file_patterns(&args.paths)?;
+    let mut fileset_expression = workspace_command.parse_file_patterns(&args.paths)?;
     let revset_expression = {
-        // only use default revset if neither revset nor path are specified
-        let mut expression = if args.revisions.is_empty() && args.paths.is_empty() {
-            workspace_command
-                .parse_revset(&RevisionArg::from(command.settings().default_revset()))?
+        let has_filters = !args.paths.is_empty()
+            || !args.author.is_empty()
+            || args.since.is_some()
+            || args.until.is_some()
+            || args.diff_contains.is_some()
+            || args.diff_matches.is_some();
+        // only use default revset if neither revset, path, nor filter are specified
+        let mut expression = if args.revisions.is_empty() && !has_filters {
+            let expression = revset::parse(
+                &command.settings().default_revset(),
+                &workspace_command.revset_parse_context(),
+            )
+            .map_err(|err| config_error_with_message("Invalid `revsets.log`", err))?;
+            workspace_command.attach_revset_evaluator(expression)?
         } else if !args.revisions.is_empty() {
             workspace_command.parse_union_revsets(&args.revisions)?
         } else {
-            // a path was specified so we use all() and add path filter later
+            // a filter was specified so we use all() and add filters later
             workspace_command.attach_revset_evaluator(RevsetExpression::all())?
         };
-        if !args.paths.is_empty() {
+        if args.follow {
+            let [path] = args.paths.as_slice() else {
+                return Err(user_error("--follow requires exactly one path"));
+            };
+            let path = workspace_command.parse_file_path(path)?;
+            let (commit_ids, paths) =
+                follow_file_history(workspace_command.repo().as_ref(), &expression, path)?;
+            expression.intersect_with(&RevsetExpression::commits(commit_ids));
+            // Show the diffs of the file under all of its names
+            fileset_expression = FilesetExpression::union_all(
+                paths
+                    .into_iter()
+                    .map(FilesetExpression::file_path)
+                    .collect(),
+            );
+        } else if !args.paths.is_empty() {
             // Beware that args.paths = ["root:."] is not identical to []. The
             // former will filter out empty commits.
             let predicate = RevsetFilterPredicate::File(fileset_expression.clone());
             expression.intersect_with(&RevsetExpression::filter(predicate));
         }
+        if !args.author.is_empty() {
+            let authors = args.author.iter().map(|pattern| {
+                RevsetExpression::filter(RevsetFilterPredicate::Author(pattern.clone()))
+            });
+            expression.intersect_with(&RevsetExpression::union_all(&authors.collect_vec()));
+        }
+        for pattern in [args.since, args.until].into_iter().flatten() {
+            let predicate = RevsetFilterPredicate::CommitterDate(pattern);
+            expression.intersect_with(&RevsetExpression::filter(predicate));
+        }
+        let text_patterns = [
+            args.diff_contains.clone().map(StringPattern::Substring),
+            args.diff_matches.clone(),
+        ];
+        for text in text_patterns.into_iter().flatten() {
+            let predicate = RevsetFilterPredicate::DiffContains {
+                text,
+                files: fileset_expression.clone(),
+            };
+            expression.intersect_with(&RevsetExpression::filter(predicate));
+        }
+        if args.hidden {
+            let commits: Vec<_> = expression.evaluate_to_commits()?.try_collect()?;
+            let predecessor_ids = collect_predecessor_ids(commits)?;
+            expression.union_with(&RevsetExpression::commits(predecessor_ids));
+        }
         expression
     };
 
@@ -117,8 +319,17 @@ pub(crate) fn cmd_log(
         let language = workspace_command.commit_template_language()?;
         let template_string = match &args.template {
             Some(value) => value.to_string(),
+            None if args.graph_format.is_some() => command
+                .settings()
+                .config()
+                .get_string("templates.commit_summary")?,
             None => command.settings().config().get_string("templates.log")?,
         };
+        let template_string = if args.hidden {
+            format!("{template_string} ++ builtin_log_hidden_successors")
+        } else {
+            template_string
+        };
         template = workspace_command.parse_template(
             &language,
             &template_string,
@@ -135,16 +346,30 @@ pub(crate) fn cmd_log(
         ui.request_pager();
         let mut formatter = ui.stdout_formatter();
         let formatter = formatter.as_mut();
+        let limit = args.limit.unwrap_or(usize::MAX);
 
-        if !args.no_graph {
+        if let Some(graph_format) = args.graph_format {
+            let iter = TopoGroupedRevsetGraphIterator::new(revset.iter_graph()).take(limit);
+            let mut nodes = vec![];
+            for (commit_id, edges) in iter {
+                let commit = store.get_commit(&commit_id)?;
+                let mut label = vec![];
+                template.format(&commit, &mut PlainTextFormatter::new(&mut label))?;
+                let label = String::from_utf8_lossy(&label).trim_end().to_owned();
+                nodes.push((commit_id, label, edges));
+            }
+            write_graph_description(formatter, graph_format, &nodes)?;
+        } else if !args.no_graph {
             let mut graph = get_graphlog(command.settings(), formatter.raw());
-            let forward_iter = TopoGroupedRevsetGraphIterator::new(revset.iter_graph());
+            // The revset and graph iterators are lazy, so limited queries stop
+            // walking the history once enough revisions are emitted.
+            let forward_iter = TopoGroupedRevsetGraphIterator::new(revset.iter_graph()).take(limit);
             let iter: Box<dyn Iterator<Item = _>> = if args.reversed {
                 Box::new(ReverseRevsetGraphIterator::new(forward_iter))
             } else {
                 Box::new(forward_iter)
             };
-            for (commit_id, edges) in iter.take(args.limit.unwrap_or(usize::MAX)) {
+            for (commit_id, edges) in iter {
                 // The graph is keyed by (CommitId, is_synthetic)
                 let mut graphlog_edges = vec![];
                 // TODO: Should we update revset.iter_graph() to yield this flag instead of all
@@ -189,6 +414,7 @@ pub(crate) fn cmd_log(
                     diff_util::show_patch(
                         ui,
                         formatter.as_mut(),
+                        command.settings(),
                         &workspace_command,
                         &commit,
                         matcher.as_ref(),
@@ -223,12 +449,13 @@ pub(crate) fn cmd_log(
                 }
             }
         } else {
+            let forward_iter = revset.iter().take(limit);
             let iter: Box<dyn Iterator<Item = CommitId>> = if args.reversed {
-                Box::new(revset.iter().reversed())
+                Box::new(forward_iter.collect_vec().into_iter().rev())
             } else {
-                Box::new(revset.iter())
+                Box::new(forward_iter)
             };
-            for commit_or_error in iter.commits(store).take(args.limit.unwrap_or(usize::MAX)) {
+            for commit_or_error in iter.commits(store) {
                 let commit = commit_or_error?;
                 with_content_format
                     .write(formatter, |formatter| template.format(&commit, formatter))?;
@@ -236,6 +463,7 @@ pub(crate) fn cmd_log(
                     diff_util::show_patch(
                         ui,
                         formatter,
+                        command.settings(),
                         &workspace_command,
                         &commit,
                         matcher.as_ref(),
@@ -270,3 +498,85 @@ pub(crate) fn cmd_log(
 
     Ok(())
 }
+
+/// Writes the graph of the `nodes`, which are commits with their label and
+/// edges to their parents, in the given language.
+fn write_graph_description(
+    formatter: &mut dyn Formatter,
+    graph_format: GraphFormat,
+    nodes: &[(CommitId, String, Vec<RevsetGraphEdge>)],
+) -> io::Result<()> {
+    // Edges to missing commits aren't printed since they don't point to any
+    // node.
+    let printed_edges = |edges: &[RevsetGraphEdge]| {
+        edges
+            .iter()
+            .filter(|edge| edge.edge_type != RevsetGraphEdgeType::Missing)
+            .map(|edge| (edge.target.hex(), edge.edge_type))
+            .collect_vec()
+    };
+    match graph_format {
+        GraphFormat::Dot => {
+            writeln!(formatter, "digraph {{")?;
+            for (commit_id, label, _) in nodes {
+                let label = label
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                writeln!(formatter, "  \"{}\" [label=\"{label}\"];", commit_id.hex())?;
+            }
+            for (commit_id, _, edges) in nodes {
+                for (target, edge_type) in printed_edges(edges) {
+                    let style = if edge_type == RevsetGraphEdgeType::Indirect {
+                        " [style=dashed]"
+                    } else {
+                        ""
+                    };
+                    writeln!(
+                        formatter,
+                        "  \"{}\" -> \"{target}\"{style};",
+                        commit_id.hex()
+                    )?;
+                }
+            }
+            writeln!(formatter, "}}")?;
+        }
+        GraphFormat::Mermaid => {
+            // Node ids can't start with a digit.
+            writeln!(formatter, "flowchart TD")?;
+            for (commit_id, label, _) in nodes {
+                let label = label.replace('"', "#quot;").replace('\n', "<br>");
+                writeln!(formatter, "  c{}[\"{label}\"]", commit_id.hex())?;
+            }
+            for (commit_id, _, edges) in nodes {
+                for (target, edge_type) in printed_edges(edges) {
+                    let arrow = if edge_type == RevsetGraphEdgeType::Indirect {
+                        "-.->"
+                    } else {
+                        "-->"
+                    };
+                    writeln!(formatter, "  c{} {arrow} c{target}", commit_id.hex())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_author_pattern(src: &str) -> Result<StringPattern, StringPatternParseError> {
+    // Names and emails may contain ":", so only the known kinds are prefixes
+    match src.split_once(':') {
+        Some((kind @ ("exact" | "glob" | "substring" | "regex"), pat)) => {
+            StringPattern::from_str_kind(pat, kind)
+        }
+        _ => Ok(StringPattern::Substring(src.to_owned())),
+    }
+}
+
+fn parse_date_after(src: &str) -> Result<DatePattern, DatePatternParseError> {
+    DatePattern::from_str_kind(src, "after", chrono::Local::now())
+}
+
+fn parse_date_before(src: &str) -> Result<DatePattern, DatePatternParseError> {
+    DatePattern::from_str_kind(src, "before", chrono::Local::now())
+}
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 mod abandon;
+mod api;
 mod backout;
 #[cfg(feature = "bench")]
 mod bench;
@@ -28,8 +29,10 @@ mod diff;
 mod diffedit;
 mod duplicate;
 mod edit;
+mod file;
 mod files;
 mod git;
+mod hg;
 mod init;
 mod interdiff;
 mod log;
@@ -37,25 +40,31 @@ mod merge;
 mod r#move;
 mod new;
 mod next;
+mod note;
 mod obslog;
 mod operation;
 mod parallelize;
+mod patch;
 mod prev;
 mod rebase;
 mod resolve;
 mod restore;
 mod root;
 mod run;
+mod shelve;
 mod show;
 mod sparse;
 mod split;
 mod squash;
 mod status;
+mod sync;
 mod tag;
+mod unhide;
 mod unsquash;
 mod untrack;
 mod util;
 mod version;
+mod web;
 mod workspace;
 
 use std::fmt::Debug;
@@ -67,9 +76,12 @@ use crate::cli_util::{Args, CommandHelper};
 use crate::command_error::{user_error_with_hint, CommandError};
 use crate::ui::Ui;
 
+pub(crate) use self::workspace::update_stale_working_copy;
+
 #[derive(clap::Parser, Clone, Debug)]
 enum Command {
     Abandon(abandon::AbandonArgs),
+    Api(api::ApiArgs),
     Backout(backout::BackoutArgs),
     #[cfg(feature = "bench")]
     #[command(subcommand)]
@@ -91,9 +103,13 @@ enum Command {
     Diffedit(diffedit::DiffeditArgs),
     Duplicate(duplicate::DuplicateArgs),
     Edit(edit::EditArgs),
+    #[command(subcommand)]
+    File(file::FileCommand),
     Files(files::FilesArgs),
     #[command(subcommand)]
     Git(git::GitCommand),
+    #[command(subcommand)]
+    Hg(hg::HgCommand),
     Init(init::InitArgs),
     Interdiff(interdiff::InterdiffArgs),
     Log(log::LogArgs),
@@ -111,11 +127,15 @@ enum Command {
     Move(r#move::MoveArgs),
     New(new::NewArgs),
     Next(next::NextArgs),
+    #[command(subcommand)]
+    Note(note::NoteCommand),
     Obslog(obslog::ObslogArgs),
     #[command(subcommand)]
     #[command(visible_alias = "op")]
     Operation(operation::OperationCommand),
     Parallelize(parallelize::ParallelizeArgs),
+    #[command(subcommand)]
+    Patch(patch::PatchCommand),
     Prev(prev::PrevArgs),
     Rebase(rebase::RebaseArgs),
     Resolve(resolve::ResolveArgs),
@@ -126,24 +146,28 @@ enum Command {
     )]
     Revert(DummyCommandArgs),
     Root(root::RootArgs),
-    #[command(hide = true)]
-    // TODO: Flesh out.
+    // TODO: Record the changes made by the command in the revisions.
     Run(run::RunArgs),
+    Shelve(shelve::ShelveArgs),
     Show(show::ShowArgs),
     #[command(subcommand)]
     Sparse(sparse::SparseArgs),
     Split(split::SplitArgs),
     Squash(squash::SquashArgs),
     Status(status::StatusArgs),
+    Sync(sync::SyncArgs),
     #[command(subcommand)]
     Tag(tag::TagCommand),
     #[command(subcommand)]
     Util(util::UtilCommand),
     /// Undo an operation (shortcut for `jj op undo`)
     Undo(operation::OperationUndoArgs),
+    Unhide(unhide::UnhideArgs),
+    Unshelve(shelve::UnshelveArgs),
     Unsquash(unsquash::UnsquashArgs),
     Untrack(untrack::UntrackArgs),
     Version(version::VersionArgs),
+    Web(web::WebArgs),
     #[command(subcommand)]
     Workspace(workspace::WorkspaceCommand),
 }
@@ -164,15 +188,20 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
     let derived_subcommands: Command = Command::from_arg_matches(command_helper.matches()).unwrap();
     match &derived_subcommands {
         Command::Version(sub_args) => version::cmd_version(ui, command_helper, sub_args),
+        Command::Web(sub_args) => web::cmd_web(ui, command_helper, sub_args),
         Command::Init(sub_args) => init::cmd_init(ui, command_helper, sub_args),
         Command::Config(sub_args) => config::cmd_config(ui, command_helper, sub_args),
         Command::Checkout(sub_args) => checkout::cmd_checkout(ui, command_helper, sub_args),
         Command::Untrack(sub_args) => untrack::cmd_untrack(ui, command_helper, sub_args),
+        Command::File(sub_args) => file::cmd_file(ui, command_helper, sub_args),
         Command::Files(sub_args) => files::cmd_files(ui, command_helper, sub_args),
         Command::Cat(sub_args) => cat::cmd_cat(ui, command_helper, sub_args),
         Command::Diff(sub_args) => diff::cmd_diff(ui, command_helper, sub_args),
+        Command::Shelve(sub_args) => shelve::cmd_shelve(ui, command_helper, sub_args),
+        Command::Unshelve(sub_args) => shelve::cmd_unshelve(ui, command_helper, sub_args),
         Command::Show(sub_args) => show::cmd_show(ui, command_helper, sub_args),
         Command::Status(sub_args) => status::cmd_status(ui, command_helper, sub_args),
+        Command::Sync(sub_args) => sync::cmd_sync(ui, command_helper, sub_args),
         Command::Log(sub_args) => log::cmd_log(ui, command_helper, sub_args),
         Command::Interdiff(sub_args) => interdiff::cmd_interdiff(ui, command_helper, sub_args),
         Command::Obslog(sub_args) => obslog::cmd_obslog(ui, command_helper, sub_args),
@@ -180,15 +209,19 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Commit(sub_args) => commit::cmd_commit(ui, command_helper, sub_args),
         Command::Duplicate(sub_args) => duplicate::cmd_duplicate(ui, command_helper, sub_args),
         Command::Abandon(sub_args) => abandon::cmd_abandon(ui, command_helper, sub_args),
+        Command::Api(sub_args) => api::cmd_api(ui, command_helper, sub_args),
         Command::Edit(sub_args) => edit::cmd_edit(ui, command_helper, sub_args),
         Command::Next(sub_args) => next::cmd_next(ui, command_helper, sub_args),
+        Command::Note(sub_args) => note::cmd_note(ui, command_helper, sub_args),
         Command::Parallelize(sub_args) => {
             parallelize::cmd_parallelize(ui, command_helper, sub_args)
         }
+        Command::Patch(sub_args) => patch::cmd_patch(ui, command_helper, sub_args),
         Command::Prev(sub_args) => prev::cmd_prev(ui, command_helper, sub_args),
         Command::New(sub_args) => new::cmd_new(ui, command_helper, sub_args),
         Command::Move(sub_args) => r#move::cmd_move(ui, command_helper, sub_args),
         Command::Squash(sub_args) => squash::cmd_squash(ui, command_helper, sub_args),
+        Command::Unhide(sub_args) => unhide::cmd_unhide(ui, command_helper, sub_args),
         Command::Unsquash(sub_args) => unsquash::cmd_unsquash(ui, command_helper, sub_args),
         Command::Restore(sub_args) => restore::cmd_restore(ui, command_helper, sub_args),
         Command::Revert(_args) => revert(),
@@ -208,6 +241,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Tag(sub_args) => tag::cmd_tag(ui, command_helper, sub_args),
         Command::Chmod(sub_args) => chmod::cmd_chmod(ui, command_helper, sub_args),
         Command::Git(sub_args) => git::cmd_git(ui, command_helper, sub_args),
+        Command::Hg(sub_args) => hg::cmd_hg(ui, command_helper, sub_args),
         Command::Util(sub_args) => util::cmd_util(ui, command_helper, sub_args),
         #[cfg(feature = "bench")]
         Command::Bench(sub_args) => bench::cmd_bench(ui, command_helper, sub_args),
@@ -16,7 +16,7 @@ use clap::ArgGroup;
 use jj_lib::object_id::ObjectId;
 use tracing::instrument;
 
-use super::squash::move_diff;
+use super::squash::{move_diff, park_conflicts};
 use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::{user_error, CommandError};
 use crate::ui::Ui;
@@ -51,6 +51,11 @@ pub(crate) struct MoveArgs {
     /// Specify diff editor to be used (implies --interactive)
     #[arg(long, value_name = "NAME")]
     tool: Option<String>,
+    /// Move the changes which would cause new conflicts in the destination or
+    /// in the revisions between it and the source to a new revision on top of
+    /// the source instead
+    #[arg(long)]
+    park_conflicts: bool,
     /// Move only changes to these paths (instead of all paths)
     #[arg(conflicts_with_all = ["interactive", "tool"], value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -89,11 +94,12 @@ pub(crate) fn cmd_move(
         source.id().hex(),
         destination.id().hex()
     );
+    let sources = [source];
     move_diff(
         ui,
         &mut tx,
         command.settings(),
-        &[source],
+        &sources,
         &destination,
         matcher.as_ref(),
         &diff_selector,
@@ -101,6 +107,9 @@ pub(crate) fn cmd_move(
         false,
         &args.paths,
     )?;
+    if args.park_conflicts {
+        park_conflicts(ui, &mut tx, command.settings(), &sources, &destination)?;
+    }
     tx.finish(ui, tx_description)?;
     Ok(())
 }
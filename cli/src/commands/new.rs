@@ -14,7 +14,6 @@
 
 use std::io::Write;
 
-use clap::ArgGroup;
 use itertools::Itertools;
 use jj_lib::commit::{Commit, CommitIteratorExt};
 use jj_lib::repo::Repo;
@@ -39,10 +38,9 @@ use crate::ui::Ui;
 /// For more information, see
 /// https://github.com/martinvonz/jj/blob/main/docs/working-copy.md.
 #[derive(clap::Args, Clone, Debug)]
-#[command(group(ArgGroup::new("order").args(&["insert_after", "insert_before"])))]
 pub(crate) struct NewArgs {
     /// Parent(s) of the new change
-    #[arg(default_value = "@")]
+    #[arg(default_value = "@", conflicts_with_all = ["insert_after", "insert_before"])]
     pub(crate) revisions: Vec<RevisionArg>,
     /// Ignored (but lets you pass `-r` for consistency with other commands)
     #[arg(short = 'r', hide = true, action = clap::ArgAction::Count)]
@@ -60,25 +58,17 @@ pub(crate) struct NewArgs {
     #[arg(long, hide = true)]
     _edit: bool,
     /// Insert the new change between the target commit(s) and their children
-    //
-    // Repeating this flag is allowed, but has no effect.
-    #[arg(
-        long,
-        short = 'A',
-        visible_alias = "after",
-        overrides_with = "insert_after"
-    )]
-    insert_after: bool,
+    ///
+    /// This can be repeated to insert the change after several commits. If
+    /// `--insert-before` is also given, only the commits given there are
+    /// rebased onto the new change.
+    #[arg(long, short = 'A', visible_alias = "after", value_name = "REVISION")]
+    insert_after: Vec<RevisionArg>,
     /// Insert the new change between the target commit(s) and their parents
-    //
-    // Repeating this flag is allowed, but has no effect.
-    #[arg(
-        long,
-        short = 'B',
-        visible_alias = "before",
-        overrides_with = "insert_before"
-    )]
-    insert_before: bool,
+    ///
+    /// This can be repeated to insert the change before several commits.
+    #[arg(long, short = 'B', visible_alias = "before", value_name = "REVISION")]
+    insert_before: Vec<RevisionArg>,
 }
 
 #[instrument(skip_all)]
@@ -94,22 +84,30 @@ Please use `jj new 'all:x|y'` instead of `jj new --allow-large-revsets x y`.",
         ));
     }
     let mut workspace_command = command.workspace_helper(ui)?;
-    let target_commits = workspace_command
-        .resolve_some_revsets_default_single(&args.revisions)?
-        .into_iter()
-        .collect_vec();
-    let target_ids = target_commits.iter().map(|c| c.id().clone()).collect_vec();
-    let mut tx = workspace_command.start_transaction();
+    let mut tx;
     let mut num_rebased;
     let new_commit;
-    if args.insert_before {
+    if !args.insert_before.is_empty() {
         // Instead of having the new commit as a child of the changes given on the
         // command line, add it between the changes' parents and the changes.
-        // The parents of the new commit will be the parents of the target commits
-        // which are not descendants of other target commits.
-        tx.base_workspace_helper().check_rewritable(&target_ids)?;
+        // The parents of the new commit will be the commits given with
+        // --insert-after, or else the parents of the target commits which are
+        // not descendants of other target commits.
+        let target_commits = workspace_command
+            .resolve_some_revsets_default_single(&args.insert_before)?
+            .into_iter()
+            .collect_vec();
+        let target_ids = target_commits.iter().ids().cloned().collect_vec();
+        workspace_command.check_rewritable(&target_ids)?;
         let new_children = RevsetExpression::commits(target_ids.clone());
-        let new_parents = new_children.parents();
+        let new_parents = if args.insert_after.is_empty() {
+            new_children.parents()
+        } else {
+            let after_commits =
+                workspace_command.resolve_some_revsets_default_single(&args.insert_after)?;
+            RevsetExpression::commits(after_commits.iter().ids().cloned().collect())
+        };
+        tx = workspace_command.start_transaction();
         if let Some(commit_id) = new_children
             .dag_range_to(&new_parents)
             .evaluate_programmatic(tx.repo())?
@@ -123,12 +121,13 @@ Please use `jj new 'all:x|y'` instead of `jj new --allow-large-revsets x y`.",
             )));
         }
         let new_parents_commits: Vec<Commit> = new_parents
+            .clone()
             .evaluate_programmatic(tx.repo())?
             .iter()
             .commits(tx.repo().store())
             .try_collect()?;
         let merged_tree = merge_commit_trees(tx.repo(), &new_parents_commits)?;
-        let new_parents_commit_id = new_parents_commits.iter().map(|c| c.id().clone()).collect();
+        let new_parents_commit_id = new_parents_commits.iter().ids().cloned().collect();
         new_commit = tx
             .mut_repo()
             .new_commit(command.settings(), new_parents_commit_id, merged_tree.id())
@@ -136,16 +135,37 @@ Please use `jj new 'all:x|y'` instead of `jj new --allow-large-revsets x y`.",
             .write()?;
         num_rebased = target_ids.len();
         for child_commit in target_commits {
+            // The parents which are ancestors of the new commit are replaced by
+            // it. The other parents are kept.
+            let commit_parents = RevsetExpression::commits(child_commit.parent_ids().to_owned());
+            let mut new_parent_commits: Vec<Commit> = commit_parents
+                .minus(&new_parents.ancestors())
+                .evaluate_programmatic(tx.base_repo().as_ref())?
+                .iter()
+                .commits(tx.base_repo().store())
+                .try_collect()?;
+            new_parent_commits.push(new_commit.clone());
             rebase_commit(
                 command.settings(),
                 tx.mut_repo(),
                 &child_commit,
-                &[new_commit.clone()],
+                &new_parent_commits,
             )?;
         }
     } else {
+        let target_revisions = if args.insert_after.is_empty() {
+            &args.revisions
+        } else {
+            &args.insert_after
+        };
+        let target_commits = workspace_command
+            .resolve_some_revsets_default_single(target_revisions)?
+            .into_iter()
+            .collect_vec();
+        let target_ids = target_commits.iter().ids().cloned().collect_vec();
+        tx = workspace_command.start_transaction();
         let old_parents = RevsetExpression::commits(target_ids.clone());
-        let commits_to_rebase: Vec<Commit> = if args.insert_after {
+        let commits_to_rebase: Vec<Commit> = if !args.insert_after.is_empty() {
             // Each child of the targets will be rebased: its set of parents will be updated
             // so that the targets are replaced by the new commit.
             // Exclude children that are ancestors of the new commit
@@ -17,7 +17,7 @@ use std::io::Write;
 use itertools::Itertools;
 use jj_lib::commit::Commit;
 use jj_lib::repo::Repo;
-use jj_lib::revset::{RevsetExpression, RevsetIteratorExt};
+use jj_lib::revset::{RevsetExpression, RevsetFilterPredicate, RevsetIteratorExt};
 
 use crate::cli_util::{short_commit_hash, CommandHelper, WorkspaceCommandHelper};
 use crate::command_error::{user_error, CommandError};
@@ -58,13 +58,17 @@ use crate::ui::Ui;
 pub(crate) struct NextArgs {
     /// How many revisions to move forward. Advances to the next child by
     /// default.
-    #[arg(default_value = "1")]
+    #[arg(default_value = "1", conflicts_with = "conflict")]
     offset: u64,
     /// Instead of creating a new working-copy commit on top of the target
     /// commit (like `jj new`), edit the target commit directly (like `jj
     /// edit`).
     #[arg(long)]
     edit: bool,
+    /// Jump to the next descendant with conflicts instead of moving by an
+    /// offset
+    #[arg(long)]
+    conflict: bool,
 }
 
 pub fn choose_commit<'a>(
@@ -126,7 +130,17 @@ pub(crate) fn cmd_next(
             _ => return Err(user_error("Cannot run `jj next` on a merge commit")),
         }
     };
-    let descendant_expression = RevsetExpression::commit(start_id.clone()).descendants_at(offset);
+    let descendant_expression = if args.conflict {
+        RevsetExpression::commit(start_id.clone())
+            .children()
+            .descendants()
+            .intersection(&RevsetExpression::filter(
+                RevsetFilterPredicate::HasConflict,
+            ))
+            .roots()
+    } else {
+        RevsetExpression::commit(start_id.clone()).descendants_at(offset)
+    };
     let target_expression = if edit {
         descendant_expression
     } else {
@@ -139,6 +153,9 @@ pub(crate) fn cmd_next(
         .try_collect()?;
     let target = match targets.as_slice() {
         [target] => target,
+        [] if args.conflict => {
+            return Err(user_error("No descendant found with conflicts"));
+        }
         [] => {
             // We found no descendant.
             return Err(user_error(format!(
@@ -0,0 +1,257 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use jj_lib::commit::Commit;
+use jj_lib::git::{self, GitFetchError, GitNoteError, GitPushError, NotesFetchOutcome};
+use jj_lib::repo::Repo;
+
+use super::git::{get_default_fetch_remotes, get_default_push_remote, map_git_error};
+use crate::cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper};
+use crate::command_error::{user_error, user_error_with_hint, CommandError};
+use crate::git_util::{get_git_repo, with_remote_git_callbacks};
+use crate::ui::Ui;
+
+/// Manage notes attached to commits
+///
+/// Notes are stored in the `refs/notes/commits` ref of the Git repo, like `git
+/// notes` does, so they can be shared with Git users. They aren't part of the
+/// commits, so adding or removing a note doesn't rewrite the commit. Notes
+/// aren't recorded in the operation log and can't be undone with `jj undo`.
+///
+/// Since notes are attached to commit ids, notes on a change are left behind
+/// on its previous commit when it's rewritten.
+#[derive(clap::Subcommand, Clone, Debug)]
+pub enum NoteCommand {
+    Add(NoteAddArgs),
+    Show(NoteShowArgs),
+    Remove(NoteRemoveArgs),
+    Fetch(NoteFetchArgs),
+    Push(NotePushArgs),
+}
+
+/// Attach a note to a revision
+#[derive(clap::Args, Clone, Debug)]
+pub struct NoteAddArgs {
+    /// The revision to attach the note to
+    #[arg(long, short, default_value = "@")]
+    revision: RevisionArg,
+    /// The note
+    #[arg(long, short)]
+    message: String,
+    /// Replace the existing note of the revision
+    #[arg(long, short)]
+    force: bool,
+}
+
+/// Print the note attached to a revision
+#[derive(clap::Args, Clone, Debug)]
+pub struct NoteShowArgs {
+    /// The revision whose note to print
+    #[arg(long, short, default_value = "@")]
+    revision: RevisionArg,
+}
+
+/// Remove the note attached to a revision
+#[derive(clap::Args, Clone, Debug)]
+pub struct NoteRemoveArgs {
+    /// The revision whose note to remove
+    #[arg(long, short, default_value = "@")]
+    revision: RevisionArg,
+}
+
+/// Fetch the notes from a Git remote
+///
+/// The local notes are fast-forwarded to the notes of the remote. If both have
+/// changed, the notes of the remote are left in
+/// `refs/notes/remotes/<remote>/commits` to be merged with `git notes merge`.
+#[derive(clap::Args, Clone, Debug)]
+pub struct NoteFetchArgs {
+    /// The remote to fetch from (only named remotes are supported, can be
+    /// repeated)
+    #[arg(long = "remote", value_name = "remote")]
+    remotes: Vec<String>,
+}
+
+/// Push the notes to a Git remote
+///
+/// The notes of the remote must have been fetched first if they changed.
+#[derive(clap::Args, Clone, Debug)]
+pub struct NotePushArgs {
+    /// The remote to push to (only named remotes are supported)
+    #[arg(long)]
+    remote: Option<String>,
+}
+
+pub fn cmd_note(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &NoteCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        NoteCommand::Add(sub_args) => cmd_note_add(ui, command, sub_args),
+        NoteCommand::Show(sub_args) => cmd_note_show(ui, command, sub_args),
+        NoteCommand::Remove(sub_args) => cmd_note_remove(ui, command, sub_args),
+        NoteCommand::Fetch(sub_args) => cmd_note_fetch(ui, command, sub_args),
+        NoteCommand::Push(sub_args) => cmd_note_push(ui, command, sub_args),
+    }
+}
+
+fn resolve_annotated_commit(
+    workspace_command: &WorkspaceCommandHelper,
+    revision: &RevisionArg,
+) -> Result<Commit, CommandError> {
+    let commit = workspace_command.resolve_single_rev(revision)?;
+    if commit.id() == workspace_command.repo().store().root_commit_id() {
+        return Err(user_error("The root commit can't have a note"));
+    }
+    Ok(commit)
+}
+
+fn note_signature(command: &CommandHelper) -> Result<git2::Signature<'static>, CommandError> {
+    let settings = command.settings();
+    git2::Signature::now(&settings.user_name(), &settings.user_email()).map_err(|err| {
+        user_error_with_hint(
+            err,
+            "Set `user.name` and `user.email` with `jj config set --user`.",
+        )
+    })
+}
+
+fn map_note_error(err: GitNoteError) -> CommandError {
+    match err {
+        GitNoteError::AlreadyExists(_) => {
+            user_error_with_hint(err, "Use --force to replace the existing note.")
+        }
+        GitNoteError::NotFound(_) => user_error(err),
+        GitNoteError::InternalGitError(err) => map_git_error(err),
+    }
+}
+
+fn cmd_note_add(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &NoteAddArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let commit = resolve_annotated_commit(&workspace_command, &args.revision)?;
+    let git_repo = get_git_repo(workspace_command.repo().store())?;
+    let signature = note_signature(command)?;
+    git::write_note(
+        &git_repo,
+        commit.id(),
+        &args.message,
+        &signature,
+        args.force,
+    )
+    .map_err(map_note_error)?;
+    Ok(())
+}
+
+fn cmd_note_show(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &NoteShowArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let commit = resolve_annotated_commit(&workspace_command, &args.revision)?;
+    let git_repo = get_git_repo(workspace_command.repo().store())?;
+    let note = git::read_note(&git_repo, commit.id())?
+        .ok_or_else(|| map_note_error(GitNoteError::NotFound(commit.id().clone())))?;
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    formatter.write_all(note.as_bytes())?;
+    if !note.ends_with('\n') {
+        writeln!(formatter)?;
+    }
+    Ok(())
+}
+
+fn cmd_note_remove(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &NoteRemoveArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let commit = resolve_annotated_commit(&workspace_command, &args.revision)?;
+    let git_repo = get_git_repo(workspace_command.repo().store())?;
+    let signature = note_signature(command)?;
+    git::remove_note(&git_repo, commit.id(), &signature).map_err(map_note_error)?;
+    Ok(())
+}
+
+fn cmd_note_fetch(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &NoteFetchArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let git_repo = get_git_repo(workspace_command.repo().store())?;
+    let remotes = if args.remotes.is_empty() {
+        get_default_fetch_remotes(ui, command.settings(), &git_repo)?
+    } else {
+        args.remotes.clone()
+    };
+    for remote in &remotes {
+        let outcome =
+            with_remote_git_callbacks(ui, None, |cb| git::fetch_notes(&git_repo, remote, cb))
+                .map_err(|err| match err {
+                    GitFetchError::InternalGitError(err) => map_git_error(err),
+                    _ => user_error(err),
+                })?;
+        match outcome {
+            NotesFetchOutcome::Unchanged => {}
+            NotesFetchOutcome::Updated => {
+                writeln!(ui.status(), "Updated the notes from {remote}")?;
+            }
+            NotesFetchOutcome::Diverged(remote_ref) => {
+                return Err(user_error_with_hint(
+                    format!("The notes diverged from the notes of {remote}"),
+                    format!("Run `git notes merge {remote_ref}` to merge them."),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_note_push(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &NotePushArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let git_repo = get_git_repo(workspace_command.repo().store())?;
+    let remote = if let Some(remote) = &args.remote {
+        remote.clone()
+    } else {
+        get_default_push_remote(ui, command.settings(), &git_repo)?
+    };
+    let pushed = with_remote_git_callbacks(ui, None, |cb| git::push_notes(&git_repo, &remote, cb))
+        .map_err(|err| match err {
+            GitPushError::InternalGitError(err) => map_git_error(err),
+            GitPushError::NotFastForward => user_error_with_hint(
+                format!("The notes of {remote} changed since they were last fetched"),
+                "Run `jj note fetch` to update the notes, then push again.",
+            ),
+            _ => user_error(err),
+        })?;
+    if pushed {
+        writeln!(ui.status(), "Pushed the notes to {remote}")?;
+    } else {
+        writeln!(ui.status(), "Nothing changed.")?;
+    }
+    Ok(())
+}
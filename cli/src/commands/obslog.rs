@@ -16,6 +16,7 @@ use jj_lib::commit::Commit;
 use jj_lib::dag_walk::topo_order_reverse;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::rewrite::rebase_to_dest_parent;
+use jj_lib::settings::UserSettings;
 use tracing::instrument;
 
 use crate::cli_util::{
@@ -28,6 +29,14 @@ use crate::formatter::Formatter;
 use crate::graphlog::{get_graphlog, Edge};
 use crate::ui::Ui;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+enum ObslogDiffBase {
+    /// The previous version of the change (what each rewrite changed)
+    Predecessor,
+    /// The parents of each version (the change's content at that point)
+    Parent,
+}
+
 /// Show how a change has evolved
 ///
 /// Show how a change has evolved as it's been updated, rebased, etc.
@@ -51,8 +60,16 @@ pub(crate) struct ObslogArgs {
     /// If the previous version has different parents, it will be temporarily
     /// rebased to the parents of the new version, so the diff is not
     /// contaminated by unrelated changes.
+    ///
+    /// Use `--diff-against=parent` to show the patch of each version compared
+    /// to its parents instead.
     #[arg(long, short = 'p')]
     patch: bool,
+    /// What to compare each version against when showing a diff
+    ///
+    /// Implies `--patch` unless another diff format is requested.
+    #[arg(long, value_enum)]
+    diff_against: Option<ObslogDiffBase>,
     #[command(flatten)]
     diff_format: DiffFormatArgs,
 }
@@ -67,8 +84,12 @@ pub(crate) fn cmd_obslog(
 
     let start_commit = workspace_command.resolve_single_rev(&args.revision)?;
 
-    let diff_formats =
+    let mut diff_formats =
         diff_util::diff_formats_for_log(command.settings(), &args.diff_format, args.patch)?;
+    if diff_formats.is_empty() && args.diff_against.is_some() {
+        diff_formats =
+            diff_util::diff_formats_for_log(command.settings(), &args.diff_format, true)?;
+    }
     let with_content_format = LogContentFormat::new(ui, command.settings())?;
 
     let template;
@@ -122,11 +143,13 @@ pub(crate) fn cmd_obslog(
             }
             if !diff_formats.is_empty() {
                 let mut formatter = ui.new_formatter(&mut buffer);
-                show_predecessor_patch(
+                show_obslog_patch(
                     ui,
                     formatter.as_mut(),
+                    command.settings(),
                     &workspace_command,
                     &commit,
+                    args.diff_against.unwrap_or(ObslogDiffBase::Predecessor),
                     &diff_formats,
                 )?;
             }
@@ -143,7 +166,15 @@ pub(crate) fn cmd_obslog(
             with_content_format
                 .write(formatter, |formatter| template.format(&commit, formatter))?;
             if !diff_formats.is_empty() {
-                show_predecessor_patch(ui, formatter, &workspace_command, &commit, &diff_formats)?;
+                show_obslog_patch(
+                    ui,
+                    formatter,
+                    command.settings(),
+                    &workspace_command,
+                    &commit,
+                    args.diff_against.unwrap_or(ObslogDiffBase::Predecessor),
+                    &diff_formats,
+                )?;
             }
         }
     }
@@ -151,9 +182,40 @@ pub(crate) fn cmd_obslog(
     Ok(())
 }
 
+fn show_obslog_patch(
+    ui: &Ui,
+    formatter: &mut dyn Formatter,
+    settings: &UserSettings,
+    workspace_command: &WorkspaceCommandHelper,
+    commit: &Commit,
+    diff_against: ObslogDiffBase,
+    diff_formats: &[DiffFormat],
+) -> Result<(), CommandError> {
+    match diff_against {
+        ObslogDiffBase::Predecessor => show_predecessor_patch(
+            ui,
+            formatter,
+            settings,
+            workspace_command,
+            commit,
+            diff_formats,
+        ),
+        ObslogDiffBase::Parent => diff_util::show_patch(
+            ui,
+            formatter,
+            settings,
+            workspace_command,
+            commit,
+            &EverythingMatcher,
+            diff_formats,
+        ),
+    }
+}
+
 fn show_predecessor_patch(
     ui: &Ui,
     formatter: &mut dyn Formatter,
+    settings: &UserSettings,
     workspace_command: &WorkspaceCommandHelper,
     commit: &Commit,
     diff_formats: &[DiffFormat],
@@ -169,10 +231,12 @@ fn show_predecessor_patch(
     diff_util::show_diff(
         ui,
         formatter,
+        settings,
         workspace_command,
         &predecessor_tree,
         &tree,
         &EverythingMatcher,
+        &[],
         diff_formats,
     )
 }
@@ -12,21 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{HashMap, HashSet};
 use std::io::Write as _;
 use std::slice;
 
 use clap::Subcommand;
 use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
 use jj_lib::object_id::ObjectId;
 use jj_lib::op_store::OperationId;
-use jj_lib::op_walk;
 use jj_lib::operation::Operation;
 use jj_lib::repo::Repo;
+use jj_lib::str_util::{StringPattern, StringPatternParseError};
+use jj_lib::time_util::{DatePattern, DatePatternParseError};
+use jj_lib::view::View;
+use jj_lib::{op_store, op_walk};
 
-use crate::cli_util::{format_template, short_operation_hash, CommandHelper, LogContentFormat};
+use crate::cli_util::{
+    format_template, print_view_conflicts, short_operation_hash, CommandHelper, LogContentFormat,
+    RevisionArg,
+};
 use crate::command_error::{user_error, user_error_with_hint, CommandError};
 use crate::graphlog::{get_graphlog, Edge};
-use crate::operation_templater::OperationTemplateLanguage;
+use crate::operation_templater::{OperationTemplateEnvironment as _, OperationTemplateLanguage};
 use crate::ui::Ui;
 
 /// Commands for working with the operation log
@@ -55,6 +63,204 @@ pub struct OperationLogArgs {
     /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
     #[arg(long, short = 'T')]
     template: Option<String>,
+    /// Show only operations that ended at or after the given date
+    ///
+    /// For the accepted formats, see
+    /// https://github.com/martinvonz/jj/blob/main/docs/revsets.md#date-patterns.
+    #[arg(long, value_parser = parse_since)]
+    since: Option<DatePattern>,
+    /// Show only operations that ended before the given date
+    #[arg(long, value_parser = parse_until)]
+    until: Option<DatePattern>,
+    /// Show only operations run by users matching the given pattern
+    ///
+    /// The pattern is matched against "USERNAME@HOSTNAME". By default, it
+    /// matches a substring. Use `exact:` or `glob:` prefix to match it
+    /// differently.
+    #[arg(long, value_parser = parse_substring_pattern)]
+    user: Option<StringPattern>,
+    /// Show only operations whose description or command line matches the
+    /// given pattern
+    ///
+    /// By default, the pattern matches a substring. Use `exact:` or `glob:`
+    /// prefix to match it differently.
+    #[arg(long, value_parser = parse_substring_pattern)]
+    command: Option<StringPattern>,
+    /// Show only operations that changed the given branches
+    ///
+    /// By default, the specified name matches exactly. Use `glob:` prefix to
+    /// select branches by wildcard pattern.
+    #[arg(long, short, value_parser = StringPattern::parse)]
+    branch: Vec<StringPattern>,
+    /// Show only operations that added, removed, or moved a ref to the given
+    /// revisions
+    #[arg(long, short)]
+    revisions: Vec<RevisionArg>,
+}
+
+fn parse_since(src: &str) -> Result<DatePattern, DatePatternParseError> {
+    DatePattern::from_str_kind(src, "after", chrono::Local::now())
+}
+
+fn parse_until(src: &str) -> Result<DatePattern, DatePatternParseError> {
+    DatePattern::from_str_kind(src, "before", chrono::Local::now())
+}
+
+fn parse_substring_pattern(src: &str) -> Result<StringPattern, StringPatternParseError> {
+    match src.split_once(':') {
+        Some((kind @ ("exact" | "glob" | "substring"), pattern)) => {
+            StringPattern::from_str_kind(pattern, kind)
+        }
+        _ => Ok(StringPattern::Substring(src.to_owned())),
+    }
+}
+
+/// Selection of the operations shown by `jj op log`.
+struct OperationFilter<'a> {
+    args: &'a OperationLogArgs,
+    commit_ids: HashSet<CommitId>,
+}
+
+impl OperationFilter<'_> {
+    fn is_empty(&self) -> bool {
+        let args = self.args;
+        args.since.is_none()
+            && args.until.is_none()
+            && args.user.is_none()
+            && args.command.is_none()
+            && args.branch.is_empty()
+            && args.revisions.is_empty()
+    }
+
+    fn matches(&self, op: &Operation) -> Result<bool, CommandError> {
+        let args = self.args;
+        let metadata = op.metadata();
+        let date_matches = |pattern: &Option<DatePattern>| {
+            pattern
+                .as_ref()
+                .map_or(true, |pattern| pattern.matches(&metadata.end_time))
+        };
+        if !date_matches(&args.since) || !date_matches(&args.until) {
+            return Ok(false);
+        }
+        if let Some(pattern) = &args.user {
+            if !pattern.matches(&format!("{}@{}", metadata.username, metadata.hostname)) {
+                return Ok(false);
+            }
+        }
+        if let Some(pattern) = &args.command {
+            let command_line = metadata.tags.get("args").map_or("", |args| args.as_str());
+            if !pattern.matches(&metadata.description) && !pattern.matches(command_line) {
+                return Ok(false);
+            }
+        }
+        if args.branch.is_empty() && self.commit_ids.is_empty() {
+            return Ok(true);
+        }
+        // The operation must have changed something compared to each of its
+        // parents, so merge operations don't match only because of the changes
+        // merged in from the other side.
+        let view = op.view()?;
+        let parent_views: Vec<_> = op.parents().map_ok(|parent| parent.view()).try_collect()?;
+        let parent_views: Vec<_> = if parent_views.is_empty() {
+            vec![View::new(op_store::View::default())]
+        } else {
+            parent_views.into_iter().try_collect()?
+        };
+        let touches_branches = !args.branch.is_empty()
+            && parent_views
+                .iter()
+                .all(|parent_view| self.changes_branches(parent_view, &view));
+        let touches_commits = !self.commit_ids.is_empty()
+            && parent_views
+                .iter()
+                .all(|parent_view| self.changes_commits(parent_view, &view));
+        Ok(touches_branches || touches_commits)
+    }
+
+    fn changes_branches(&self, old_view: &View, new_view: &View) -> bool {
+        let is_matching =
+            |(name, _): &(&str, _)| self.args.branch.iter().any(|pattern| pattern.matches(name));
+        !old_view
+            .branches()
+            .filter(is_matching)
+            .eq(new_view.branches().filter(is_matching))
+    }
+
+    fn changes_commits(&self, old_view: &View, new_view: &View) -> bool {
+        // Count the references so that e.g. creating a branch pointing to a
+        // visible commit is detected.
+        let referenced_commits = |view: &View| -> Vec<CommitId> {
+            view.all_referenced_commit_ids()
+                .filter(|id| self.commit_ids.contains(id))
+                .cloned()
+                .sorted_unstable()
+                .collect()
+        };
+        referenced_commits(old_view) != referenced_commits(new_view)
+    }
+}
+
+/// Operation and its edges to the parent operations in the graph.
+type OperationGraphNode = (Operation, Vec<Edge<OperationId>>);
+
+/// Returns the operations matching the `filter`, with graph edges to their
+/// closest matching ancestors.
+fn filter_operation_graph(
+    ops: Vec<Operation>,
+    filter: &OperationFilter,
+) -> Result<Vec<OperationGraphNode>, CommandError> {
+    let matching_ids: HashSet<OperationId> = ops
+        .iter()
+        .filter_map(|op| match filter.matches(op) {
+            Ok(true) => Some(Ok(op.id().clone())),
+            Ok(false) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .try_collect()?;
+    // Closest matching ancestors of each operation (including itself), computed
+    // from the root since the operations are sorted children first.
+    let mut closest_matches: HashMap<OperationId, Vec<OperationId>> = HashMap::new();
+    for op in ops.iter().rev() {
+        let ids = if matching_ids.contains(op.id()) {
+            vec![op.id().clone()]
+        } else {
+            op.parent_ids()
+                .iter()
+                .flat_map(|id| &closest_matches[id])
+                .unique()
+                .cloned()
+                .collect()
+        };
+        closest_matches.insert(op.id().clone(), ids);
+    }
+    let graph = ops
+        .into_iter()
+        .filter(|op| matching_ids.contains(op.id()))
+        .map(|op| {
+            let mut edges = vec![];
+            let parent_edges = op.parent_ids().iter().flat_map(|id| {
+                if matching_ids.contains(id) {
+                    vec![Edge::Direct(id.clone())]
+                } else if closest_matches[id].is_empty() {
+                    vec![Edge::Missing]
+                } else {
+                    closest_matches[id]
+                        .iter()
+                        .cloned()
+                        .map(Edge::Indirect)
+                        .collect()
+                }
+            });
+            for edge in parent_edges {
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+            (op, edges)
+        })
+        .collect();
+    Ok(graph)
 }
 
 /// Create a new operation that restores the repo to an earlier state
@@ -77,17 +283,19 @@ pub struct OperationRestoreArgs {
     what: Vec<UndoWhatToRestore>,
 }
 
-/// Create a new operation that undoes an earlier operation
+/// Create a new operation that undoes earlier operations
 ///
-/// This undoes an individual operation by applying the inverse of the
-/// operation.
+/// This undoes individual operations by applying the inverse of the
+/// operations. The later operations are kept.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationUndoArgs {
-    /// The operation to undo
+    /// The operations to undo
     ///
-    /// Use `jj op log` to find an operation to undo.
+    /// Use `jj op log` to find an operation to undo. Use `X..Y` to undo the
+    /// operations after `X` up to and including `Y` at once. `X..` is the same
+    /// as `X..@`.
     #[arg(default_value = "@")]
-    operation: String,
+    operations: Vec<String>,
 
     /// What portions of the local state to restore (can be repeated)
     ///
@@ -184,18 +392,37 @@ fn cmd_op_log(
         )?;
     }
 
+    let filter = OperationFilter {
+        args,
+        commit_ids: if args.revisions.is_empty() {
+            HashSet::new()
+        } else {
+            let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+            let expression = workspace_command.parse_union_revsets(&args.revisions)?;
+            let commit_ids = expression.evaluate_to_commit_ids()?.collect();
+            commit_ids
+        },
+    };
+    let iter: Box<dyn Iterator<Item = Result<OperationGraphNode, CommandError>>> =
+        if filter.is_empty() {
+            Box::new(op_walk::walk_ancestors(&head_ops).map(|op| {
+                let op = op?;
+                let edges = op.parent_ids().iter().cloned().map(Edge::Direct).collect();
+                Ok((op, edges))
+            }))
+        } else {
+            let ops = op_walk::walk_ancestors(&head_ops).try_collect()?;
+            Box::new(filter_operation_graph(ops, &filter)?.into_iter().map(Ok))
+        };
+    let iter = iter.take(args.limit.unwrap_or(usize::MAX));
+
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
     let formatter = formatter.as_mut();
-    let iter = op_walk::walk_ancestors(&head_ops).take(args.limit.unwrap_or(usize::MAX));
     if !args.no_graph {
         let mut graph = get_graphlog(command.settings(), formatter.raw());
-        for op in iter {
-            let op = op?;
-            let mut edges = vec![];
-            for id in op.parent_ids() {
-                edges.push(Edge::Direct(id.clone()));
-            }
+        for op_and_edges in iter {
+            let (op, edges) = op_and_edges?;
             let mut buffer = vec![];
             with_content_format.write_graph_text(
                 ui.new_formatter(&mut buffer).as_mut(),
@@ -216,8 +443,8 @@ fn cmd_op_log(
             )?;
         }
     } else {
-        for op in iter {
-            let op = op?;
+        for op_and_edges in iter {
+            let (op, _edges) = op_and_edges?;
             with_content_format.write(formatter, |formatter| {
                 formatter.with_label("op_log", |formatter| template.format(&op, formatter))
             })?;
@@ -260,27 +487,78 @@ pub fn cmd_op_undo(
     args: &OperationUndoArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let bad_op = workspace_command.resolve_single_op(&args.operation)?;
-    let mut parent_ops = bad_op.parents();
-    let Some(parent_op) = parent_ops.next().transpose()? else {
-        return Err(user_error("Cannot undo repo initialization"));
-    };
-    if parent_ops.next().is_some() {
-        return Err(user_error("Cannot undo a merge operation"));
+    // Pairs of operation to undo and the operation to go back to.
+    let mut undo_ranges = vec![];
+    for op_str in &args.operations {
+        if let Some((root_op_str, head_op_str)) = op_str.split_once("..") {
+            if root_op_str.is_empty() {
+                return Err(user_error_with_hint(
+                    "Cannot undo repo initialization",
+                    "Specify the operation before the first operation to undo",
+                ));
+            }
+            let root_op = workspace_command.resolve_single_op(root_op_str)?;
+            let head_op = if head_op_str.is_empty() {
+                workspace_command.resolve_single_op("@")?
+            } else {
+                workspace_command.resolve_single_op(head_op_str)?
+            };
+            let is_ancestor = op_walk::walk_ancestors(slice::from_ref(&head_op))
+                .skip(1)
+                .process_results(|mut ops| ops.any(|op| op == root_op))?;
+            if !is_ancestor {
+                return Err(user_error(format!(
+                    "Operation {} is not an ancestor of operation {}",
+                    short_operation_hash(root_op.id()),
+                    short_operation_hash(head_op.id())
+                )));
+            }
+            let description = format!("{}..{}", root_op.id().hex(), head_op.id().hex());
+            undo_ranges.push((head_op, root_op, description));
+        } else {
+            let bad_op = workspace_command.resolve_single_op(op_str)?;
+            let parent_op = {
+                let mut parent_ops = bad_op.parents();
+                let Some(parent_op) = parent_ops.next().transpose()? else {
+                    return Err(user_error("Cannot undo repo initialization"));
+                };
+                if parent_ops.next().is_some() {
+                    return Err(user_error("Cannot undo a merge operation"));
+                }
+                parent_op
+            };
+            let description = bad_op.id().hex();
+            undo_ranges.push((bad_op, parent_op, description));
+        }
     }
 
     let mut tx = workspace_command.start_transaction();
     let repo_loader = tx.base_repo().loader();
-    let bad_repo = repo_loader.load_at(&bad_op)?;
-    let parent_repo = repo_loader.load_at(&parent_op)?;
-    tx.mut_repo().merge(&bad_repo, &parent_repo);
+    let mut view_conflicts = vec![];
+    for (bad_op, parent_op, _) in &undo_ranges {
+        let bad_repo = repo_loader.load_at(bad_op)?;
+        let parent_repo = repo_loader.load_at(parent_op)?;
+        view_conflicts.extend(tx.mut_repo().merge(&bad_repo, &parent_repo)?);
+    }
     let new_view = view_with_desired_portions_restored(
         tx.repo().view().store_view(),
         tx.base_repo().view().store_view(),
         &args.what,
     );
     tx.mut_repo().set_view(new_view);
-    tx.finish(ui, format!("undo operation {}", bad_op.id().hex()))?;
+    view_conflicts.retain(|conflict| conflict.is_present(tx.repo().view()));
+    print_view_conflicts(ui, "Undoing left conflicts in:", &view_conflicts)?;
+    let tx_description = if let [(_, _, description)] = &*undo_ranges {
+        if description.contains("..") {
+            format!("undo operations {description}")
+        } else {
+            format!("undo operation {description}")
+        }
+    } else {
+        let descriptions = undo_ranges.iter().map(|(_, _, description)| description);
+        format!("undo operations {}", descriptions.format(", "))
+    };
+    tx.finish(ui, tx_description)?;
 
     Ok(())
 }
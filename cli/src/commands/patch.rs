@@ -0,0 +1,304 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read as _, Write};
+use std::path::PathBuf;
+
+use jj_lib::backend::{CopyRecord, MergedTreeId, TreeValue};
+use jj_lib::commit::Commit;
+use jj_lib::merge::{Merge, MergedTreeValue};
+use jj_lib::merged_tree::MergedTreeBuilder;
+use jj_lib::repo_path::{RepoPath, RepoPathBuf};
+use jj_lib::store::Store;
+use tracing::instrument;
+
+use crate::cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper};
+use crate::command_error::{user_error, user_error_with_message, CommandError};
+use crate::description_util::join_message_paragraphs;
+use crate::patch_util::{parse_patch, FilePatch, Patch};
+use crate::ui::Ui;
+
+/// Work with patch files
+#[derive(clap::Subcommand, Clone, Debug)]
+pub(crate) enum PatchCommand {
+    Apply(PatchApplyArgs),
+}
+
+/// Create commits from patch files
+///
+/// Each patch is applied as a new commit on top of the previous one, starting
+/// from the destination revision. The patches can be unified diffs or patches
+/// in Git's format, such as the ones written by `git format-patch` or `jj diff
+/// --output-dir`. The author, date and description of the commits are taken
+/// from the email-style header of the patches if they have one.
+///
+/// The working-copy commit isn't changed.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct PatchApplyArgs {
+    /// The patch files to apply, in order (default: read a patch from stdin)
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    patches: Vec<PathBuf>,
+    /// The revision to apply the first patch onto
+    #[arg(long, short, default_value = "@")]
+    destination: RevisionArg,
+    /// The change description to use (instead of the one from the patch)
+    #[arg(long = "message", short, value_name = "MESSAGE")]
+    message_paragraphs: Vec<String>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_patch(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &PatchCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        PatchCommand::Apply(sub_args) => cmd_patch_apply(ui, command, sub_args),
+    }
+}
+
+#[instrument(skip_all)]
+fn cmd_patch_apply(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &PatchApplyArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut parent = workspace_command.resolve_single_rev(&args.destination)?;
+    let patches = read_patches(&args.patches)?;
+
+    let mut tx = workspace_command.start_transaction();
+    for (name, patch) in &patches {
+        let (tree_id, copies) = apply_file_patches(tx.base_workspace_helper(), &parent, patch)?;
+        let description = if args.message_paragraphs.is_empty() {
+            patch.header.description.clone().unwrap_or_default()
+        } else {
+            join_message_paragraphs(&args.message_paragraphs)
+        };
+        let mut commit_builder = tx
+            .mut_repo()
+            .new_commit(command.settings(), vec![parent.id().clone()], tree_id)
+            .set_copies(copies)
+            .set_description(description);
+        let mut author = commit_builder.author().clone();
+        if let Some((name, email)) = &patch.header.author {
+            author.name = name.clone();
+            author.email = email.clone();
+        }
+        if let Some(date) = &patch.header.date {
+            author.timestamp = date.clone();
+            let mut committer = commit_builder.committer().clone();
+            committer.timestamp = date.clone();
+            commit_builder = commit_builder.set_committer(committer);
+        }
+        commit_builder = commit_builder.set_author(author);
+        let commit = commit_builder.write()?;
+        if let Some(mut formatter) = ui.status_formatter() {
+            write!(formatter, "Applied {name} as ")?;
+            tx.write_commit_summary(formatter.as_mut(), &commit)?;
+            writeln!(formatter)?;
+        }
+        parent = commit;
+    }
+    let num_patches = patches.len();
+    tx.finish(
+        ui,
+        format!(
+            "apply {num_patches} {}",
+            if num_patches == 1 { "patch" } else { "patches" }
+        ),
+    )?;
+    Ok(())
+}
+
+/// Reads and parses the patch files, or the patch from stdin if no file is
+/// given. Returns the patches with their names.
+fn read_patches(paths: &[PathBuf]) -> Result<Vec<(String, Patch)>, CommandError> {
+    let read_data = |path: Option<&PathBuf>| -> Result<Vec<u8>, CommandError> {
+        match path {
+            Some(path) => fs::read(path).map_err(|err| {
+                user_error_with_message(format!("Failed to read {}", path.display()), err)
+            }),
+            None => {
+                let mut data = vec![];
+                io::stdin()
+                    .read_to_end(&mut data)
+                    .map_err(|err| user_error_with_message("Failed to read stdin", err))?;
+                Ok(data)
+            }
+        }
+    };
+    let sources = if paths.is_empty() {
+        vec![None]
+    } else {
+        paths.iter().map(Some).collect()
+    };
+    sources
+        .into_iter()
+        .map(|path| {
+            let name = path.map_or("stdin".to_owned(), |path| path.display().to_string());
+            let patch = parse_patch(&read_data(path)?)
+                .map_err(|err| user_error_with_message(format!("Failed to parse {name}"), err))?;
+            Ok((name, patch))
+        })
+        .collect()
+}
+
+/// Applies the file changes of the `patch` to the tree of the `parent`, and
+/// returns the new tree and the copies and renames made by the patch. The file
+/// changes are applied in order, so several of them can touch the same path.
+fn apply_file_patches(
+    workspace_command: &WorkspaceCommandHelper,
+    parent: &Commit,
+    patch: &Patch,
+) -> Result<(MergedTreeId, Vec<CopyRecord>), CommandError> {
+    let tree = parent.tree()?;
+    let store = tree.store();
+    // The values changed by the previous file patches
+    let mut new_values: HashMap<RepoPathBuf, MergedTreeValue> = HashMap::new();
+    let current_value = |new_values: &HashMap<RepoPathBuf, MergedTreeValue>, path: &RepoPath| {
+        new_values
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| tree.path_value(path))
+    };
+    let mut copies = vec![];
+    for file in &patch.files {
+        let (old_value, old_content) = match &file.old_path {
+            Some(old_path) => {
+                let value = read_old_value(
+                    workspace_command,
+                    &current_value(&new_values, old_path),
+                    old_path,
+                )?;
+                let content = match &value {
+                    TreeValue::File { id, .. } => {
+                        let mut content = vec![];
+                        store.read_file(old_path, id)?.read_to_end(&mut content)?;
+                        content
+                    }
+                    TreeValue::Symlink(id) => store.read_symlink(old_path, id)?.into_bytes(),
+                    _ => unreachable!(),
+                };
+                (Some(value), content)
+            }
+            None => {
+                let new_path = file.path();
+                if current_value(&new_values, new_path).is_present() {
+                    return Err(user_error(format!(
+                        "File already exists at '{}'",
+                        workspace_command.format_file_path(new_path)
+                    )));
+                }
+                (None, vec![])
+            }
+        };
+        let new_content = file.apply(&old_content).map_err(user_error)?;
+
+        if let Some(new_path) = &file.new_path {
+            let new_value =
+                write_new_value(store, file, new_path, old_value.as_ref(), new_content)?;
+            new_values.insert(new_path.clone(), Merge::normal(new_value));
+        }
+        if let (Some(old_path), Some(new_path)) = (&file.old_path, &file.new_path) {
+            if old_path != new_path {
+                if !file.is_copy {
+                    new_values.insert(old_path.clone(), Merge::absent());
+                }
+                if let Some(TreeValue::File { id, .. }) = &old_value {
+                    copies.push(CopyRecord {
+                        target: new_path.clone(),
+                        source: old_path.clone(),
+                        source_file: id.clone(),
+                    });
+                }
+            }
+        } else if let (Some(old_path), None) = (&file.old_path, &file.new_path) {
+            new_values.insert(old_path.clone(), Merge::absent());
+        }
+    }
+    let mut tree_builder = MergedTreeBuilder::new(parent.tree_id().clone());
+    for (path, value) in new_values {
+        tree_builder.set_or_remove(path, value);
+    }
+    Ok((tree_builder.write_tree(store)?, copies))
+}
+
+fn read_old_value(
+    workspace_command: &WorkspaceCommandHelper,
+    value: &Merge<Option<TreeValue>>,
+    path: &RepoPath,
+) -> Result<TreeValue, CommandError> {
+    match value.as_resolved() {
+        Some(Some(value @ (TreeValue::File { .. } | TreeValue::Symlink(_)))) => Ok(value.clone()),
+        Some(None) => Err(user_error(format!(
+            "No such file at '{}'",
+            workspace_command.format_file_path(path)
+        ))),
+        Some(Some(_)) => Err(user_error(format!(
+            "Can't apply a patch to '{}' because it isn't a file",
+            workspace_command.format_file_path(path)
+        ))),
+        None => Err(user_error(format!(
+            "Can't apply a patch to '{}' because it has conflicts",
+            workspace_command.format_file_path(path)
+        ))),
+    }
+}
+
+/// Writes the new contents of the file, keeping the mode of the old file if
+/// the patch doesn't change it.
+fn write_new_value(
+    store: &Store,
+    file: &FilePatch,
+    path: &RepoPath,
+    old_value: Option<&TreeValue>,
+    content: Vec<u8>,
+) -> Result<TreeValue, CommandError> {
+    let mode = match (&file.new_mode, old_value) {
+        (Some(mode), _) => mode.as_str(),
+        (None, Some(TreeValue::Symlink(_))) => "120000",
+        (
+            None,
+            Some(TreeValue::File {
+                executable: true, ..
+            }),
+        ) => "100755",
+        (None, _) => "100644",
+    };
+    match mode {
+        "100644" | "100755" => {
+            let id = store.write_file(path, &mut content.as_slice())?;
+            Ok(TreeValue::File {
+                id,
+                executable: mode == "100755",
+            })
+        }
+        "120000" => {
+            let target = String::from_utf8(content).map_err(|_| {
+                user_error(format!(
+                    "Target of symlink '{}' isn't valid UTF-8",
+                    path.as_internal_file_string()
+                ))
+            })?;
+            Ok(TreeValue::Symlink(store.write_symlink(path, &target)?))
+        }
+        _ => Err(user_error(format!(
+            "Unsupported mode {mode} of '{}'",
+            path.as_internal_file_string()
+        ))),
+    }
+}
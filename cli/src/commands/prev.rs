@@ -14,7 +14,7 @@
 
 use itertools::Itertools;
 use jj_lib::repo::Repo;
-use jj_lib::revset::{RevsetExpression, RevsetIteratorExt};
+use jj_lib::revset::{RevsetExpression, RevsetFilterPredicate, RevsetIteratorExt};
 
 use crate::cli_util::{short_commit_hash, CommandHelper};
 use crate::command_error::{user_error, CommandError};
@@ -55,11 +55,15 @@ use crate::ui::Ui;
 #[command(verbatim_doc_comment)]
 pub(crate) struct PrevArgs {
     /// How many revisions to move backward. Moves to the parent by default.
-    #[arg(default_value = "1")]
+    #[arg(default_value = "1", conflicts_with = "conflict")]
     offset: u64,
     /// Edit the parent directly, instead of moving the working-copy commit.
     #[arg(long)]
     edit: bool,
+    /// Jump to the previous ancestor with conflicts instead of moving by an
+    /// offset
+    #[arg(long)]
+    conflict: bool,
 }
 
 pub(crate) fn cmd_prev(
@@ -87,7 +91,17 @@ pub(crate) fn cmd_prev(
             _ => return Err(user_error("Cannot run `jj prev` on a merge commit")),
         }
     };
-    let ancestor_expression = RevsetExpression::commit(start_id.clone()).ancestors_at(offset);
+    let ancestor_expression = if args.conflict {
+        RevsetExpression::commit(start_id.clone())
+            .parents()
+            .ancestors()
+            .intersection(&RevsetExpression::filter(
+                RevsetFilterPredicate::HasConflict,
+            ))
+            .heads()
+    } else {
+        RevsetExpression::commit(start_id.clone()).ancestors_at(offset)
+    };
     let target_revset = if edit {
         ancestor_expression
     } else {
@@ -105,6 +119,7 @@ pub(crate) fn cmd_prev(
         .try_collect()?;
     let target = match targets.as_slice() {
         [target] => target,
+        [] if args.conflict => return Err(user_error("No ancestor found with conflicts")),
         [] => {
             return Err(user_error(format!(
                 "No ancestor found {offset} commit{} back",
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::sync::Arc;
 
@@ -22,19 +22,21 @@ use indexmap::IndexSet;
 use itertools::Itertools;
 use jj_lib::backend::CommitId;
 use jj_lib::commit::{Commit, CommitIteratorExt};
+use jj_lib::matchers::EverythingMatcher;
 use jj_lib::object_id::ObjectId;
-use jj_lib::repo::{ReadonlyRepo, Repo};
-use jj_lib::revset::{RevsetExpression, RevsetIteratorExt};
-use jj_lib::rewrite::{rebase_commit, rebase_commit_with_options, EmptyBehaviour, RebaseOptions};
-use jj_lib::settings::UserSettings;
+use jj_lib::repo::{MutableRepo, ReadonlyRepo, Repo};
+use jj_lib::revset::{self, RevsetExpression, RevsetIteratorExt};
+use jj_lib::rewrite::{rebase_commit_with_options, EmptyBehaviour, RebaseOptions, RebasedCommit};
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
 use tracing::instrument;
 
 use crate::cli_util::{
-    short_commit_hash, CommandHelper, RevisionArg, WorkspaceCommandHelper,
-    WorkspaceCommandTransaction,
+    print_merge_driver_failures, short_commit_hash, CommandHelper, RevisionArg,
+    WorkspaceCommandHelper, WorkspaceCommandTransaction,
 };
 use crate::command_error::{user_error, CommandError};
 use crate::formatter::Formatter;
+use crate::merge_tools::resolve_with_merge_drivers;
 use crate::ui::Ui;
 
 /// Move revisions to different parent(s)
@@ -191,6 +193,12 @@ Please use `jj rebase -d 'all:x|y'` instead of `jj rebase --allow-large-revsets
             false => EmptyBehaviour::Keep,
         },
         simplify_ancestor_merge: false,
+        follow_renames: command
+            .settings()
+            .config()
+            .get_bool("merge.follow-renames")
+            .optional()?
+            .unwrap_or(false),
     };
     let mut workspace_command = command.workspace_helper(ui)?;
     let new_parents = workspace_command
@@ -219,6 +227,7 @@ Please use `jj rebase -d 'all:x|y'` instead of `jj rebase --allow-large-revsets
             &mut workspace_command,
             &new_parents,
             rev_arg,
+            &rebase_options,
         )?;
     } else if !args.source.is_empty() {
         let source_commits = workspace_command.resolve_some_revsets_default_single(&args.source)?;
@@ -333,6 +342,7 @@ fn rebase_descendants_transaction(
     let num_rebased =
         rebase_descendants(&mut tx, settings, new_parents, &old_commits, rebase_options)?;
     writeln!(ui.status(), "Rebased {num_rebased} commits")?;
+    resolve_rebased_with_merge_drivers(ui, settings, &mut tx)?;
     let tx_message = if old_commits.len() == 1 {
         format!(
             "rebase commit {} and descendants",
@@ -351,6 +361,7 @@ fn rebase_revision(
     workspace_command: &mut WorkspaceCommandHelper,
     new_parents: &[Commit],
     rev_arg: &RevisionArg,
+    rebase_options: &RebaseOptions,
 ) -> Result<(), CommandError> {
     let old_commit = workspace_command.resolve_single_rev(rev_arg)?;
     workspace_command.check_rewritable([old_commit.id()])?;
@@ -409,12 +420,14 @@ fn rebase_revision(
             .commits(tx.base_repo().store())
             .try_collect()?;
 
-        rebased_commit_ids.insert(
-            child_commit.id().clone(),
-            rebase_commit(settings, tx.mut_repo(), child_commit, &new_child_parents)?
-                .id()
-                .clone(),
-        );
+        let rebased_child = rebase_kept_commit(
+            settings,
+            tx.mut_repo(),
+            child_commit,
+            &new_child_parents,
+            rebase_options,
+        )?;
+        rebased_commit_ids.insert(child_commit.id().clone(), rebased_child.id().clone());
     }
     // Now, rebase the descendants of the children.
     // TODO(ilyagr): Consider making it possible for these descendants to become
@@ -470,7 +483,13 @@ fn rebase_revision(
         }
         true
     } else {
-        rebase_commit(settings, tx.mut_repo(), &old_commit, &new_parents)?;
+        rebase_kept_commit(
+            settings,
+            tx.mut_repo(),
+            &old_commit,
+            &new_parents,
+            rebase_options,
+        )?;
         debug_assert_eq!(tx.mut_repo().rebase_descendants(settings)?, 0);
         false
     };
@@ -489,6 +508,7 @@ fn rebase_revision(
             )?;
         }
     }
+    resolve_rebased_with_merge_drivers(ui, settings, &mut tx)?;
     if tx.mut_repo().has_changes() {
         tx.finish(ui, format!("rebase commit {}", old_commit.id().hex()))
     } else {
@@ -496,6 +516,64 @@ fn rebase_revision(
     }
 }
 
+/// Runs the external merge drivers on the conflicts of the commits created by
+/// the transaction, parents first, and rebases their descendants onto the
+/// results.
+fn resolve_rebased_with_merge_drivers(
+    ui: &Ui,
+    settings: &UserSettings,
+    tx: &mut WorkspaceCommandTransaction,
+) -> Result<(), CommandError> {
+    if !tx.base_repo().store().merge_drivers().has_external() {
+        return Ok(());
+    }
+    let old_heads = tx.base_repo().view().heads().iter().cloned().collect_vec();
+    let mut visited = HashSet::new();
+    'restart: loop {
+        let new_heads = tx.repo().view().heads().iter().cloned().collect_vec();
+        let new_commits: Vec<Commit> = revset::walk_revs(tx.repo(), &new_heads, &old_heads)?
+            .iter()
+            .commits(tx.repo().store())
+            .try_collect()?;
+        for commit in new_commits.iter().rev() {
+            if !visited.insert(commit.id().clone()) || !commit.has_conflict()? {
+                continue;
+            }
+            let resolution = resolve_with_merge_drivers(&commit.tree()?, &EverythingMatcher)?;
+            print_merge_driver_failures(ui, &resolution, tx.base_workspace_helper())?;
+            if resolution.resolved_paths.is_empty() {
+                continue;
+            }
+            let new_commit = tx
+                .mut_repo()
+                .rewrite_commit(settings, commit)
+                .set_tree_id(resolution.tree_id)
+                .write()?;
+            visited.insert(new_commit.id().clone());
+            // The descendants are rebased onto the new commit, so the list of
+            // commits has to be computed again.
+            tx.mut_repo().rebase_descendants(settings)?;
+            continue 'restart;
+        }
+        return Ok(());
+    }
+}
+
+/// Rebases `old_commit` with the `rebase_options`, which must keep the empty
+/// commits.
+fn rebase_kept_commit(
+    settings: &UserSettings,
+    mut_repo: &mut MutableRepo,
+    old_commit: &Commit,
+    new_parents: &[Commit],
+    rebase_options: &RebaseOptions,
+) -> Result<Commit, CommandError> {
+    match rebase_commit_with_options(settings, mut_repo, old_commit, new_parents, rebase_options)? {
+        RebasedCommit::Rewritten(new_commit) => Ok(new_commit),
+        RebasedCommit::Abandoned { parent: _ } => panic!("Commit was unexpectedly abandoned"),
+    }
+}
+
 fn check_rebase_destinations(
     repo: &Arc<ReadonlyRepo>,
     new_parents: &[Commit],
@@ -13,15 +13,57 @@
 // limitations under the License.
 
 use std::io::Write;
+use std::sync::Arc;
 
 use itertools::Itertools;
+use jj_lib::backend::TreeValue;
+use jj_lib::merge::{Merge, MergedTreeValue};
+use jj_lib::merged_tree::MergedTreeBuilder;
 use jj_lib::object_id::ObjectId;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::store::Store;
+use jj_lib::tree::try_resolve_file_conflict;
+use serde_json::json;
 use tracing::instrument;
 
-use crate::cli_util::{print_conflicted_paths, CommandHelper, RevisionArg};
-use crate::command_error::{cli_error, CommandError};
+use crate::cli_util::{
+    print_conflicted_paths, print_merge_driver_failures, CommandHelper, RevisionArg,
+};
+use crate::command_error::{cli_error, user_error, user_error_with_hint, CommandError};
+use crate::generic_templater::GenericTemplateLanguage;
+use crate::merge_tools::resolve_with_merge_drivers;
+use crate::template_builder::TemplateLanguage as _;
+use crate::templater::TemplatePropertyExt as _;
 use crate::ui::Ui;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+enum ResolveSide {
+    /// The first side of the conflict (e.g. the destination of a rebase, or the
+    /// first parent of a merge)
+    Ours,
+    /// The second side of the conflict (e.g. the rebased commit, or the second
+    /// parent of a merge)
+    Theirs,
+    /// The common base of the two sides
+    Base,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum ResolveMode {
+    /// Not executable
+    Normal,
+    /// Executable
+    Executable,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum ResolveShape {
+    /// Keep the file (or symlink), discarding the directory
+    File,
+    /// Keep the directory, discarding the file (or symlink)
+    Directory,
+}
+
 /// Resolve a conflicted file with an external merge tool
 ///
 /// Only conflicts that can be resolved with a 3-way merge are supported. See
@@ -30,6 +72,15 @@ use crate::ui::Ui;
 /// Note that conflicts can also be resolved without using this command. You may
 /// edit the conflict markers in the conflicted file directly with a text
 /// editor.
+///
+/// With `--take`, all the matching conflicts are resolved at once by picking
+/// one side of each, which is handy for lock files and generated files.
+///
+/// With `--mode`, the executable bit is set on all sides of the matching
+/// conflicts, and the conflicts whose contents can be merged are resolved.
+/// With `--shape`, conflicts where some sides have a directory and others have
+/// a file are resolved by keeping one kind. When run in a terminal, `jj
+/// resolve` asks which kind to keep if the first conflict is of this kind.
 //  TODOs:
 //   - `jj resolve --editor` to resolve a conflict in the default text editor. Should work for
 //     conflicts with 3+ adds. Useful to resolve conflicts in a commit other than the current one.
@@ -46,9 +97,47 @@ pub(crate) struct ResolveArgs {
     // `diff --summary`, but should be more verbose.
     #[arg(long, short)]
     list: bool,
+    /// Render each conflict in the `--list` using the given template
+    ///
+    /// The following keywords are defined:
+    ///
+    /// * `path: String`: Path to the conflicted file.
+    /// * `sides: Integer`: Number of sides of the conflict.
+    /// * `deletion: Boolean`: True if any side deletes the path.
+    /// * `executable: Boolean`: True if the sides disagree on the executable
+    ///   bit of the file.
+    /// * `directory: Boolean`: True if any side is a directory.
+    /// * `added_ids: List<String>`: Ids of the added terms.
+    /// * `removed_ids: List<String>`: Ids of the removed terms.
+    ///
+    /// For the syntax, see https://github.com/martinvonz/jj/blob/main/docs/templates.md
+    #[arg(
+        long,
+        short = 'T',
+        requires = "list",
+        value_name = "TEMPLATE",
+        verbatim_doc_comment
+    )]
+    template: Option<String>,
     /// Specify 3-way merge tool to be used
     #[arg(long, conflicts_with = "list", value_name = "NAME")]
     tool: Option<String>,
+    /// Resolve all the matching conflicts by taking one side of each, without
+    /// starting a merge tool
+    #[arg(long, conflicts_with_all = ["list", "tool"], value_name = "SIDE")]
+    take: Option<ResolveSide>,
+    /// Set the executable bit of all sides of the matching conflicts, and
+    /// resolve the conflicts if the contents can be merged
+    #[arg(long, conflicts_with_all = ["list", "tool", "take"], value_name = "MODE")]
+    mode: Option<ResolveMode>,
+    /// Resolve all the matching conflicts between files and directories by
+    /// keeping one kind
+    #[arg(
+        long,
+        conflicts_with_all = ["list", "tool", "take", "mode"],
+        value_name = "KIND"
+    )]
+    shape: Option<ResolveShape>,
     /// Restrict to these paths when searching for a conflict to resolve. We
     /// will attempt to resolve the first conflict we can find. You can use
     /// the `--list` argument to find paths to use here.
@@ -80,6 +169,25 @@ pub(crate) fn cmd_resolve(
             "No conflicts found at the given path(s)"
         }));
     }
+    if let Some(template_text) = &args.template {
+        let language = conflict_template_language();
+        let template = command.parse_template(
+            ui,
+            &language,
+            template_text,
+            GenericTemplateLanguage::wrap_self,
+        )?;
+        ui.request_pager();
+        let mut formatter = ui.stdout_formatter();
+        for (path, conflict) in conflicts {
+            let entry = ConflictEntry {
+                path: workspace_command.format_file_path(&path),
+                conflict,
+            };
+            template.format(&entry, formatter.as_mut())?;
+        }
+        return Ok(());
+    }
     if args.list {
         return print_conflicted_paths(
             &conflicts,
@@ -88,16 +196,108 @@ pub(crate) fn cmd_resolve(
         );
     };
 
-    let (repo_path, _) = conflicts.first().unwrap();
     workspace_command.check_rewritable([commit.id()])?;
-    let merge_editor = workspace_command.merge_editor(ui, args.tool.as_deref())?;
-    writeln!(
-        ui.status(),
-        "Resolving conflicts in: {}",
-        workspace_command.format_file_path(repo_path)
-    )?;
+    let store = tree.store();
+    let shape = if args.take.is_none() && args.mode.is_none() && args.shape.is_none() {
+        let (repo_path, conflict) = conflicts.first().unwrap();
+        let file_path = workspace_command.format_file_path(repo_path);
+        prompt_shape(ui, &file_path, conflict)?
+    } else {
+        args.shape
+    };
+    let new_tree_id = if let Some(mode) = args.mode {
+        let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+        for (repo_path, conflict) in &conflicts {
+            let file_path = workspace_command.format_file_path(repo_path);
+            let executable = mode == ResolveMode::Executable;
+            let Some(new_value) = set_executable(conflict, executable) else {
+                return Err(user_error(format!(
+                    "Cannot set the mode of the conflict at {file_path}; not all sides are files"
+                )));
+            };
+            writeln!(ui.status(), "Resolving conflicts in: {file_path}")?;
+            let new_value = try_resolve_files(store, repo_path, new_value)?;
+            tree_builder.set_or_remove(repo_path.clone(), new_value);
+        }
+        tree_builder.write_tree(store)?
+    } else if let Some(shape) = shape {
+        let conflicts = if args.shape.is_some() {
+            &conflicts[..]
+        } else {
+            &conflicts[..1]
+        };
+        let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+        for (repo_path, conflict) in conflicts {
+            let file_path = workspace_command.format_file_path(repo_path);
+            if !is_file_directory_conflict(conflict) {
+                return Err(user_error(format!(
+                    "The conflict at {file_path} is not between a file and a directory"
+                )));
+            }
+            if conflict.num_sides() > 2 {
+                return Err(user_error(format!(
+                    "Cannot pick the kind of the {}-sided conflict at {file_path}; only 2-sided \
+                     conflicts are supported",
+                    conflict.num_sides()
+                )));
+            }
+            writeln!(ui.status(), "Resolving conflicts in: {file_path}")?;
+            let new_value = try_resolve_files(store, repo_path, keep_shape(conflict, shape))?;
+            tree_builder.set_or_remove(repo_path.clone(), new_value);
+        }
+        tree_builder.write_tree(store)?
+    } else if let Some(side) = args.take {
+        let mut tree_builder = MergedTreeBuilder::new(commit.tree_id().clone());
+        for (repo_path, conflict) in &conflicts {
+            let file_path = workspace_command.format_file_path(repo_path);
+            if conflict.num_sides() > 2 {
+                return Err(user_error(format!(
+                    "Cannot take one side of the {}-sided conflict at {file_path}; only 2-sided \
+                     conflicts are supported",
+                    conflict.num_sides()
+                )));
+            }
+            writeln!(ui.status(), "Resolving conflicts in: {file_path}")?;
+            let value = match side {
+                ResolveSide::Ours => conflict.get_add(0),
+                ResolveSide::Theirs => conflict.get_add(1),
+                ResolveSide::Base => conflict.get_remove(0),
+            };
+            tree_builder.set_or_remove(repo_path.clone(), Merge::resolved(value.unwrap().clone()));
+        }
+        tree_builder.write_tree(store)?
+    } else {
+        // The merge drivers configured for the paths take precedence over the
+        // default merge editor. The editor is used if none of them resolved
+        // anything.
+        let resolution = if args.tool.is_none() && store.merge_drivers().has_external() {
+            let resolution = resolve_with_merge_drivers(&tree, matcher.as_ref())?;
+            print_merge_driver_failures(ui, &resolution, &workspace_command)?;
+            Some(resolution).filter(|resolution| !resolution.resolved_paths.is_empty())
+        } else {
+            None
+        };
+        if let Some(resolution) = resolution {
+            for repo_path in &resolution.resolved_paths {
+                writeln!(
+                    ui.status(),
+                    "Resolving conflicts in: {} (with merge driver)",
+                    workspace_command.format_file_path(repo_path)
+                )?;
+            }
+            resolution.tree_id
+        } else {
+            let (repo_path, _) = conflicts.first().unwrap();
+            let merge_editor = workspace_command.merge_editor(ui, args.tool.as_deref())?;
+            writeln!(
+                ui.status(),
+                "Resolving conflicts in: {}",
+                workspace_command.format_file_path(repo_path)
+            )?;
+            merge_editor.edit_file(&tree, repo_path)?
+        }
+    };
     let mut tx = workspace_command.start_transaction();
-    let new_tree_id = merge_editor.edit_file(&tree, repo_path)?;
     let new_commit = tx
         .mut_repo()
         .rewrite_commit(command.settings(), &commit)
@@ -126,3 +326,188 @@ pub(crate) fn cmd_resolve(
     }
     Ok(())
 }
+
+/// Asks which kind to keep if the conflict is between a file and a directory,
+/// which a merge tool can't resolve.
+fn prompt_shape(
+    ui: &Ui,
+    file_path: &str,
+    conflict: &MergedTreeValue,
+) -> Result<Option<ResolveShape>, CommandError> {
+    if !is_file_directory_conflict(conflict) || conflict.num_sides() > 2 {
+        return Ok(None);
+    }
+    if !Ui::can_prompt() {
+        return Err(user_error_with_hint(
+            format!("The conflict at {file_path} is between a file and a directory"),
+            "Use `jj resolve --shape file` or `jj resolve --shape directory` to keep one.",
+        ));
+    }
+    let choice = ui.prompt_choice(
+        &format!(
+            "The conflict at {file_path} is between a file and a directory. Keep the (f)ile or \
+             the (d)irectory?"
+        ),
+        &["f", "d"],
+        None,
+    )?;
+    if choice == "f" {
+        Ok(Some(ResolveShape::File))
+    } else {
+        Ok(Some(ResolveShape::Directory))
+    }
+}
+
+/// Returns the distinct executable bits of the file terms.
+fn executable_bits(conflict: &MergedTreeValue) -> Vec<bool> {
+    conflict
+        .iter()
+        .flatten()
+        .filter_map(|term| match term {
+            TreeValue::File { executable, .. } => Some(*executable),
+            _ => None,
+        })
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+fn is_file_directory_conflict(conflict: &MergedTreeValue) -> bool {
+    let (trees, others): (Vec<_>, Vec<_>) = conflict
+        .iter()
+        .flatten()
+        .partition(|term| matches!(term, TreeValue::Tree(_)));
+    !trees.is_empty() && !others.is_empty()
+}
+
+/// Sets the executable bit of all terms if they're all files or absent.
+fn set_executable(conflict: &MergedTreeValue, executable: bool) -> Option<MergedTreeValue> {
+    conflict.maybe_map(|term| match term {
+        None => Some(None),
+        Some(TreeValue::File { id, .. }) => Some(Some(TreeValue::File {
+            id: id.clone(),
+            executable,
+        })),
+        Some(_) => None,
+    })
+}
+
+/// Replaces the terms of the other kind with the base if the base is of the
+/// kept kind, or with absent values otherwise. For example, if one side turned
+/// a directory into a file, keeping the directory undoes that change.
+fn keep_shape(conflict: &MergedTreeValue, shape: ResolveShape) -> MergedTreeValue {
+    let keep = |term: &Option<TreeValue>| {
+        let is_tree = matches!(term, Some(TreeValue::Tree(_)));
+        match shape {
+            ResolveShape::File => !is_tree,
+            ResolveShape::Directory => is_tree || term.is_none(),
+        }
+    };
+    let base = conflict.get_remove(0).unwrap();
+    let replacement = if keep(base) { base.clone() } else { None };
+    conflict.map(|term| {
+        if keep(term) {
+            term.clone()
+        } else {
+            replacement.clone()
+        }
+    })
+}
+
+/// Resolves the conflict if it became trivial or the file contents can be
+/// merged.
+fn try_resolve_files(
+    store: &Arc<Store>,
+    repo_path: &RepoPath,
+    value: MergedTreeValue,
+) -> Result<MergedTreeValue, CommandError> {
+    let simplified = value.clone().simplify();
+    if let Some(resolved) = simplified.resolve_trivial() {
+        return Ok(Merge::resolved(resolved.clone()));
+    }
+    if let Some(resolved) = try_resolve_file_conflict(store, repo_path, &simplified)? {
+        return Ok(Merge::normal(resolved));
+    }
+    Ok(value)
+}
+
+#[derive(Clone, Debug)]
+struct ConflictEntry {
+    path: String,
+    conflict: MergedTreeValue,
+}
+
+// ConflictEntry will be cloned internally in the templater. If the cloning
+// cost matters, wrap it with Rc.
+fn conflict_template_language() -> GenericTemplateLanguage<'static, ConflictEntry> {
+    type L = GenericTemplateLanguage<'static, ConflictEntry>;
+    let mut language = L::new();
+    language.add_keyword("path", |self_property| {
+        let out_property = self_property.map(|entry| entry.path);
+        Ok(L::wrap_string(out_property))
+    });
+    language.add_keyword("sides", |self_property| {
+        let out_property = self_property.map(|entry| entry.conflict.num_sides() as i64);
+        Ok(L::wrap_integer(out_property))
+    });
+    language.add_keyword("deletion", |self_property| {
+        let out_property = self_property.map(|entry| has_deletion(&entry.conflict));
+        Ok(L::wrap_boolean(out_property))
+    });
+    language.add_keyword("executable", |self_property| {
+        let out_property = self_property.map(|entry| executable_bits(&entry.conflict).len() > 1);
+        Ok(L::wrap_boolean(out_property))
+    });
+    language.add_keyword("directory", |self_property| {
+        let out_property = self_property.map(|entry| has_directory(&entry.conflict));
+        Ok(L::wrap_boolean(out_property))
+    });
+    language.add_keyword("added_ids", |self_property| {
+        let out_property = self_property.map(|entry| term_ids(entry.conflict.adds()));
+        Ok(L::wrap_string_list(out_property))
+    });
+    language.add_keyword("removed_ids", |self_property| {
+        let out_property = self_property.map(|entry| term_ids(entry.conflict.removes()));
+        Ok(L::wrap_string_list(out_property))
+    });
+    language
+}
+
+/// Describes the conflict at `path` with the same fields as the `--template`
+/// keywords.
+pub(crate) fn conflict_json(path: &str, conflict: &MergedTreeValue) -> serde_json::Value {
+    json!({
+        "path": path,
+        "sides": conflict.num_sides(),
+        "deletion": has_deletion(conflict),
+        "executable": executable_bits(conflict).len() > 1,
+        "directory": has_directory(conflict),
+        "added_ids": term_ids(conflict.adds()),
+        "removed_ids": term_ids(conflict.removes()),
+    })
+}
+
+fn has_deletion(conflict: &MergedTreeValue) -> bool {
+    conflict.adds().any(Option::is_none)
+}
+
+fn has_directory(conflict: &MergedTreeValue) -> bool {
+    conflict
+        .iter()
+        .flatten()
+        .any(|term| matches!(term, TreeValue::Tree(_)))
+}
+
+/// Returns the object ids of the present terms.
+fn term_ids<'a>(terms: impl Iterator<Item = &'a Option<TreeValue>>) -> Vec<String> {
+    terms
+        .flatten()
+        .map(|term| match term {
+            TreeValue::File { id, .. } => id.hex(),
+            TreeValue::Symlink(id) => id.hex(),
+            TreeValue::Tree(id) => id.hex(),
+            TreeValue::GitSubmodule(id) => id.hex(),
+            TreeValue::Conflict(id) => id.hex(),
+        })
+        .collect()
+}
@@ -36,8 +36,9 @@ use crate::ui::Ui;
 /// to `jj abandon`, except that it leaves an empty revision with its
 /// description and other metadata preserved.
 ///
-/// See `jj diffedit` if you'd like to restore portions of files rather than
-/// entire files.
+/// With `--interactive`, you can choose which parts of the changes to restore
+/// in a diff editor. See `jj diffedit` if you'd like to edit the contents of
+/// the files rather than only restoring them.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct RestoreArgs {
     /// Restore only these paths (instead of all paths)
@@ -59,6 +60,12 @@ pub(crate) struct RestoreArgs {
     /// --changes-in @`.
     #[arg(long, short, value_name="REVISION", conflicts_with_all=["to", "from"])]
     changes_in: Option<RevisionArg>,
+    /// Interactively choose which parts to restore
+    #[arg(long, short)]
+    interactive: bool,
+    /// Specify diff editor to be used (implies --interactive)
+    #[arg(long, value_name = "NAME")]
+    tool: Option<String>,
     /// Prints an error. DO NOT USE.
     ///
     /// If we followed the pattern of `jj diff` and `jj diffedit`, we would use
@@ -77,7 +84,7 @@ pub(crate) fn cmd_restore(
     args: &RestoreArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    let (from_tree, to_commit);
+    let (from_tree, from_description, to_commit);
     if args.revision.is_some() {
         return Err(user_error(
             "`jj restore` does not have a `--revision`/`-r` option. If you'd like to modify\nthe \
@@ -88,22 +95,47 @@ pub(crate) fn cmd_restore(
     if args.from.is_some() || args.to.is_some() {
         to_commit =
             workspace_command.resolve_single_rev(args.to.as_ref().unwrap_or(&RevisionArg::AT))?;
-        from_tree = workspace_command
-            .resolve_single_rev(args.from.as_ref().unwrap_or(&RevisionArg::AT))?
-            .tree()?;
+        let from_commit =
+            workspace_command.resolve_single_rev(args.from.as_ref().unwrap_or(&RevisionArg::AT))?;
+        from_tree = from_commit.tree()?;
+        from_description = workspace_command.format_commit_summary(&from_commit);
     } else {
         to_commit = workspace_command
             .resolve_single_rev(args.changes_in.as_ref().unwrap_or(&RevisionArg::AT))?;
         from_tree = merge_commit_trees(workspace_command.repo().as_ref(), &to_commit.parents())?;
+        from_description = "the parents of the commit".to_owned();
     }
     workspace_command.check_rewritable([to_commit.id()])?;
 
     let matcher = workspace_command
         .parse_file_patterns(&args.paths)?
         .to_matcher();
+    let diff_selector =
+        workspace_command.diff_selector(ui, args.tool.as_deref(), args.interactive)?;
     let to_tree = to_commit.tree()?;
-    let new_tree_id = restore_tree(&from_tree, &to_tree, matcher.as_ref())?;
+    let instructions = format!(
+        "\
+You are restoring changes from: {from_description}
+into commit: {}
+
+The left side of the diff shows the contents of the commit. The right side
+initially shows the contents you're restoring from.
+
+Adjust the right side until the diff shows the changes you want to restore
+into the commit. If you don't make any changes, then all the changes will be
+restored.
+",
+        workspace_command.format_commit_summary(&to_commit)
+    );
+    let selected_tree_id =
+        diff_selector.select(&to_tree, &from_tree, matcher.as_ref(), Some(&instructions))?;
+    // The diff editor leaves the unmatched paths as they are in the source
+    let selected_tree = to_tree.store().get_root_tree(&selected_tree_id)?;
+    let new_tree_id = restore_tree(&selected_tree, &to_tree, matcher.as_ref())?;
     if &new_tree_id == to_commit.tree_id() {
+        if diff_selector.is_interactive() {
+            return Err(user_error("No changes selected"));
+        }
         writeln!(ui.status(), "Nothing changed.")?;
     } else {
         let mut tx = workspace_command.start_transaction();
@@ -14,17 +14,32 @@
 
 //! This file contains the internal implementation of `run`.
 
+use std::io::Write as _;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
 use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo as _;
+use jj_lib::scratch::{ScratchPool, DEFAULT_MAX_SCRATCH_WORKING_COPIES};
+use jj_lib::working_copy::CheckoutOptions;
 
 use crate::cli_util::{CommandHelper, RevisionArg};
-use crate::command_error::{user_error, CommandError};
+use crate::command_error::{user_error, user_error_with_message, CommandError};
 use crate::ui::Ui;
 
 /// Run a command across a set of revisions.
 ///
+/// Each revision is checked out in a scratch working copy in the `.jj/scratch`
+/// directory, in which the command is run. The scratch working copies are kept
+/// for the next runs, which only need to update the files which differ, and
+/// keep the untracked files (like build outputs). Changes made by the command
+/// to the tracked files are discarded, and not recorded in the revisions.
 ///
-/// All recorded state will be persisted in the `.jj` directory, so occasionally
-/// a `jj run --clean` is needed to clean up disk space.
+/// Occasionally a `jj run --clean` is needed to clean up disk space.
 ///
 /// # Example
 ///
@@ -36,8 +51,9 @@ use crate::ui::Ui;
 #[command(verbatim_doc_comment)]
 pub struct RunArgs {
     /// The command to run across all selected revisions.
-    shell_command: String,
-    /// The revisions to change.
+    #[arg(required_unless_present = "clean")]
+    shell_command: Option<String>,
+    /// The revisions to run the command on.
     #[arg(long, short, default_value = "@")]
     revisions: Vec<RevisionArg>,
     /// A no-op option to match the interface of `git rebase -x`.
@@ -46,24 +62,126 @@ pub struct RunArgs {
     /// How many processes should run in parallel, uses by default all cores.
     #[arg(long, short)]
     jobs: Option<usize>,
+    /// Remove the scratch working copies which are not in use.
+    #[arg(long, conflicts_with_all = ["shell_command", "revisions", "jobs"])]
+    clean: bool,
 }
 
 pub fn cmd_run(ui: &mut Ui, command: &CommandHelper, args: &RunArgs) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
-    let _resolved_commits: Vec<_> = workspace_command
+    let scratch_pool = ScratchPool::new(
+        workspace_command.repo().store().clone(),
+        workspace_command
+            .workspace_root()
+            .join(".jj")
+            .join("scratch"),
+        DEFAULT_MAX_SCRATCH_WORKING_COPIES,
+    );
+    if args.clean {
+        let num_removed = scratch_pool.clean().map_err(|err| {
+            user_error_with_message("Failed to remove the scratch working copies", err)
+        })?;
+        writeln!(ui.status(), "Removed {num_removed} scratch working copies")?;
+        return Ok(());
+    }
+    let shell_command = args.shell_command.as_deref().unwrap();
+    let mut commits: Vec<Commit> = workspace_command
         .parse_union_revsets(&args.revisions)?
         .evaluate_to_commits()?
         .try_collect()?;
+    // Run on the ancestors first
+    commits.reverse();
     // Jobs are resolved in this order:
     // 1. Commandline argument iff > 0.
     // 2. the amount of cores available.
     // 3. a single job, if all of the above fails.
-    let _jobs = match args.jobs {
+    let jobs = match args.jobs {
         Some(0) => return Err(user_error("must pass at least one job")),
         Some(jobs) => Some(jobs),
         None => std::thread::available_parallelism().map(|t| t.into()).ok(),
     }
     // Fallback to a single user-visible job.
     .unwrap_or(1usize);
-    Err(user_error("This is a stub, do not use"))
+
+    let checkout_options = workspace_command.checkout_options();
+    let next_index = AtomicUsize::new(0);
+    let results = Mutex::new(commits.iter().map(|_| None).collect_vec());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(commits.len()) {
+            scope.spawn(|| loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(commit) = commits.get(index) else {
+                    break;
+                };
+                let result = run_in_scratch_working_copy(
+                    &scratch_pool,
+                    commit,
+                    shell_command,
+                    &checkout_options,
+                );
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    let mut num_failed = 0;
+    for (commit, result) in commits.iter().zip(results.into_inner().unwrap()) {
+        let output = result.unwrap()?;
+        if let Some(mut formatter) = ui.status_formatter() {
+            if output.status.success() {
+                write!(formatter, "Succeeded on ")?;
+                workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+                writeln!(formatter)?;
+            } else {
+                write!(formatter, "Failed on ")?;
+                workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+                writeln!(formatter, " ({})", output.status)?;
+            }
+        }
+        ui.stdout().write_all(&output.stdout)?;
+        ui.stderr().write_all(&output.stderr)?;
+        if !output.status.success() {
+            num_failed += 1;
+        }
+    }
+    if num_failed > 0 {
+        return Err(user_error(format!(
+            "The command failed on {num_failed} of {} revisions",
+            commits.len()
+        )));
+    }
+    Ok(())
+}
+
+fn run_in_scratch_working_copy(
+    scratch_pool: &ScratchPool,
+    commit: &Commit,
+    shell_command: &str,
+    checkout_options: &CheckoutOptions,
+) -> Result<Output, CommandError> {
+    let scratch = scratch_pool
+        .check_out(&commit.tree()?, checkout_options)
+        .map_err(|err| {
+            user_error_with_message(
+                format!(
+                    "Failed to check out {} in a scratch working copy",
+                    commit.id().hex()
+                ),
+                err,
+            )
+        })?;
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell
+        .arg(shell_command)
+        .current_dir(scratch.working_copy_path())
+        .output()
+        .map_err(|err| user_error_with_message(format!("Failed to run `{shell_command}`"), err))
 }
@@ -0,0 +1,265 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use itertools::Itertools as _;
+use jj_lib::commit::Commit;
+use jj_lib::object_id::ObjectId;
+use jj_lib::repo::Repo;
+use jj_lib::revset::{RevsetExpression, RevsetFilterPredicate};
+use jj_lib::rewrite::{merge_commit_trees, restore_tree};
+use jj_lib::str_util::StringPattern;
+use tracing::instrument;
+
+use crate::cli_util::{CommandHelper, RevisionArg, WorkspaceCommandHelper};
+use crate::command_error::{
+    config_error_with_message, user_error, user_error_with_hint, CommandError,
+};
+use crate::revset_util;
+use crate::ui::Ui;
+
+/// Prefix of the descriptions of the shelved changes.
+const SHELF_DESCRIPTION_PREFIX: &str = "shelve: ";
+
+/// Move the changes in the working copy to a new named commit
+///
+/// The changes are moved to a new commit on top of the parents of the
+/// working-copy commit, whose description is `shelve: <name>`, and the
+/// working-copy commit is left without them. The shelved changes can be
+/// restored into any revision with `jj unshelve`.
+///
+/// The shelf is a regular commit, so it can also be seen with `jj log`, and
+/// rebased or edited like any other commit.
+#[derive(clap::Args, Clone, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct ShelveArgs {
+    #[command(subcommand)]
+    command: Option<ShelveCommand>,
+    /// The name of the shelf (default: `shelf-<N>`)
+    #[arg(long)]
+    name: Option<String>,
+    /// Shelve only the changes to these paths (instead of all paths)
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
+}
+
+#[derive(clap::Subcommand, Clone, Debug)]
+enum ShelveCommand {
+    #[command(visible_alias("l"))]
+    List(ShelveListArgs),
+}
+
+/// List the shelved changes
+#[derive(clap::Args, Clone, Debug)]
+struct ShelveListArgs {}
+
+/// Restore shelved changes into a revision
+///
+/// The changes of the shelf are applied to the destination like `jj squash
+/// --from <shelf> --into <destination>` would, and the shelf is abandoned.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct UnshelveArgs {
+    /// The name of the shelf (default: the most recent one)
+    name: Option<String>,
+    /// The revision to restore the changes into
+    #[arg(long, short, default_value = "@")]
+    destination: RevisionArg,
+    /// Keep the shelf after restoring its changes
+    #[arg(long)]
+    keep: bool,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_shelve(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &ShelveArgs,
+) -> Result<(), CommandError> {
+    if let Some(ShelveCommand::List(sub_args)) = &args.command {
+        return cmd_shelve_list(ui, command, sub_args);
+    }
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let wc_commit_id = workspace_command
+        .get_wc_commit_id()
+        .ok_or_else(|| user_error("This command requires a working copy"))?;
+    let wc_commit = workspace_command.repo().store().get_commit(wc_commit_id)?;
+    workspace_command.check_rewritable([wc_commit.id()])?;
+    let matcher = workspace_command
+        .parse_file_patterns(&args.paths)?
+        .to_matcher();
+
+    let shelves = find_shelves(&workspace_command)?;
+    let name = match &args.name {
+        Some(name) => {
+            if name.is_empty() || name.contains('\n') {
+                return Err(user_error(format!("Invalid shelf name: {name:?}")));
+            }
+            if shelves.iter().any(|(shelf_name, _)| shelf_name == name) {
+                return Err(user_error_with_hint(
+                    format!("A shelf named {name} already exists"),
+                    "Use a different name, or run `jj unshelve` to restore the shelf first.",
+                ));
+            }
+            name.clone()
+        }
+        None => (1..)
+            .map(|n| format!("shelf-{n}"))
+            .find(|name| shelves.iter().all(|(shelf_name, _)| shelf_name != name))
+            .unwrap(),
+    };
+
+    let mut tx = workspace_command.start_transaction();
+    let base_tree = merge_commit_trees(tx.repo(), &wc_commit.parents())?;
+    let wc_tree = wc_commit.tree()?;
+    let shelf_tree_id = restore_tree(&wc_tree, &base_tree, matcher.as_ref())?;
+    if shelf_tree_id == base_tree.id() {
+        return Err(user_error("No changes to shelve"));
+    }
+    let shelf = tx
+        .mut_repo()
+        .new_commit(
+            command.settings(),
+            wc_commit.parent_ids().to_vec(),
+            shelf_tree_id,
+        )
+        .set_description(format!("{SHELF_DESCRIPTION_PREFIX}{name}\n"))
+        .write()?;
+    let new_wc_tree_id = restore_tree(&base_tree, &wc_tree, matcher.as_ref())?;
+    tx.mut_repo()
+        .rewrite_commit(command.settings(), &wc_commit)
+        .set_tree_id(new_wc_tree_id)
+        .write()?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Shelved the changes as {name}: ")?;
+        tx.write_commit_summary(formatter.as_mut(), &shelf)?;
+        writeln!(formatter)?;
+    }
+    tx.finish(ui, format!("shelve changes as {name}"))?;
+    Ok(())
+}
+
+fn cmd_shelve_list(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &ShelveListArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let shelves = find_shelves(&workspace_command)?;
+    ui.request_pager();
+    let mut formatter = ui.stdout_formatter();
+    let template = workspace_command.commit_summary_template();
+    for (name, commit) in &shelves {
+        write!(formatter.labeled("shelf"), "{name}")?;
+        write!(formatter, ": ")?;
+        template.format(commit, formatter.as_mut())?;
+        writeln!(formatter)?;
+    }
+    Ok(())
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_unshelve(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &UnshelveArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let shelves = find_shelves(&workspace_command)?;
+    let (name, shelf) = match &args.name {
+        Some(name) => shelves
+            .into_iter()
+            .find(|(shelf_name, _)| shelf_name == name)
+            .ok_or_else(|| {
+                user_error_with_hint(
+                    format!("No shelf named {name}"),
+                    "Run `jj shelve list` to list the shelves.",
+                )
+            })?,
+        None => shelves
+            .into_iter()
+            .next()
+            .ok_or_else(|| user_error("There are no shelved changes"))?,
+    };
+    let destination = workspace_command.resolve_single_rev(&args.destination)?;
+    if destination.id() == shelf.id() {
+        return Err(user_error("Cannot restore a shelf into itself"));
+    }
+    let mut to_rewrite = vec![destination.id()];
+    if !args.keep {
+        to_rewrite.push(shelf.id());
+    }
+    workspace_command.check_rewritable(to_rewrite)?;
+
+    let mut tx = workspace_command.start_transaction();
+    let shelf_base_tree = merge_commit_trees(tx.repo(), &shelf.parents())?;
+    let new_tree = destination
+        .tree()?
+        .merge(&shelf_base_tree, &shelf.tree()?)?;
+    let new_destination = tx
+        .mut_repo()
+        .rewrite_commit(command.settings(), &destination)
+        .set_tree_id(new_tree.id())
+        .write()?;
+    if !args.keep {
+        tx.mut_repo().record_abandoned_commit(shelf.id().clone());
+    }
+    let num_rebased = tx.mut_repo().rebase_descendants(command.settings())?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Restored the changes of {name} into ")?;
+        tx.write_commit_summary(formatter.as_mut(), &new_destination)?;
+        writeln!(formatter)?;
+        if num_rebased > 0 {
+            writeln!(formatter, "Rebased {num_rebased} descendant commits")?;
+        }
+    }
+    tx.finish(
+        ui,
+        format!("unshelve {name} into commit {}", destination.id().hex()),
+    )?;
+    Ok(())
+}
+
+/// Returns the mutable commits which hold shelved changes, most recent first,
+/// along with the names of the shelves.
+fn find_shelves(
+    workspace_command: &WorkspaceCommandHelper,
+) -> Result<Vec<(String, Commit)>, CommandError> {
+    let immutable =
+        revset_util::parse_immutable_expression(&workspace_command.revset_parse_context())
+            .map_err(|err| {
+                config_error_with_message("Invalid `revset-aliases.immutable_heads()`", err)
+            })?;
+    let expression = RevsetExpression::filter(RevsetFilterPredicate::Description(
+        StringPattern::Substring(SHELF_DESCRIPTION_PREFIX.to_owned()),
+    ))
+    .minus(&immutable);
+    let commits: Vec<Commit> = workspace_command
+        .attach_revset_evaluator(expression)?
+        .evaluate_to_commits()?
+        .try_collect()?;
+    let shelves = commits
+        .into_iter()
+        .filter_map(|commit| {
+            let name = commit
+                .description()
+                .strip_prefix(SHELF_DESCRIPTION_PREFIX)?
+                .lines()
+                .next()?
+                .to_owned();
+            Some((name, commit))
+        })
+        .collect();
+    Ok(shelves)
+}
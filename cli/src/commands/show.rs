@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use itertools::Itertools as _;
 use jj_lib::matchers::EverythingMatcher;
 use tracing::instrument;
 
@@ -21,11 +22,14 @@ use crate::diff_util::{self, DiffFormatArgs};
 use crate::ui::Ui;
 
 /// Show commit description and changes in a revision
+///
+/// If multiple revisions are given, they are shown one after the other,
+/// separated by an empty line.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct ShowArgs {
-    /// Show changes in this revision, compared to its parent(s)
+    /// Show changes in these revisions, compared to their parent(s)
     #[arg(default_value = "@")]
-    revision: RevisionArg,
+    revisions: Vec<RevisionArg>,
     /// Ignored (but lets you pass `-r` for consistency with other commands)
     #[arg(short = 'r', hide = true)]
     unused_revision: bool,
@@ -45,7 +49,10 @@ pub(crate) fn cmd_show(
     args: &ShowArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
-    let commit = workspace_command.resolve_single_rev(&args.revision)?;
+    let commits: Vec<_> = workspace_command
+        .parse_union_revsets(&args.revisions)?
+        .evaluate_to_commits()?
+        .try_collect()?;
     let template_string = match &args.template {
         Some(value) => value.to_string(),
         None => command.settings().config().get_string("templates.show")?,
@@ -55,14 +62,20 @@ pub(crate) fn cmd_show(
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
     let formatter = formatter.as_mut();
-    template.format(&commit, formatter)?;
-    diff_util::show_patch(
-        ui,
-        formatter,
-        &workspace_command,
-        &commit,
-        &EverythingMatcher,
-        &diff_formats,
-    )?;
+    for (i, commit) in commits.iter().enumerate() {
+        if i > 0 {
+            writeln!(formatter)?;
+        }
+        template.format(commit, formatter)?;
+        diff_util::show_patch(
+            ui,
+            formatter,
+            command.settings(),
+            &workspace_command,
+            commit,
+            &EverythingMatcher,
+            &diff_formats,
+        )?;
+    }
     Ok(())
 }
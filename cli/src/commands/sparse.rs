@@ -21,13 +21,14 @@ use clap::Subcommand;
 use itertools::Itertools;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::settings::UserSettings;
+use jj_lib::sparse::{normalize_sparse_patterns, read_sparse_profile, SparsePattern};
 use tracing::instrument;
 
 use crate::cli_util::{
     edit_temp_file, print_checkout_stats, CommandHelper, WorkspaceCommandHelper,
 };
 use crate::command_error::{
-    internal_error, internal_error_with_message, user_error_with_message, CommandError,
+    internal_error_with_message, user_error, user_error_with_message, CommandError,
 };
 use crate::ui::Ui;
 
@@ -54,26 +55,40 @@ pub(crate) struct SparseListArgs {}
 /// For example, if all you need is the `README.md` and the `lib/`
 /// directory, use `jj sparse set --clear --add README.md --add lib`.
 /// If you no longer need the `lib` directory, use `jj sparse set --remove lib`.
+///
+/// A pattern is a path relative to the workspace root, which includes the
+/// path and everything under it, or a glob prefixed with `glob:`, which
+/// includes the files whose path matches it (e.g. `glob:**/*.md`). A pattern
+/// prefixed with `!` excludes the paths it matches even if another pattern
+/// includes them (e.g. `!lib/tests`).
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct SparseSetArgs {
     /// Patterns to add to the working copy
     #[arg(
         long,
         value_hint = clap::ValueHint::AnyPath,
-        value_parser = |s: &str| RepoPathBuf::from_relative_path(s),
+        value_parser = |s: &str| SparsePattern::parse(s),
     )]
-    add: Vec<RepoPathBuf>,
+    add: Vec<SparsePattern>,
     /// Patterns to remove from the working copy
     #[arg(
         long,
         conflicts_with = "clear",
         value_hint = clap::ValueHint::AnyPath,
-        value_parser = |s: &str| RepoPathBuf::from_relative_path(s),
+        value_parser = |s: &str| SparsePattern::parse(s),
     )]
-    remove: Vec<RepoPathBuf>,
+    remove: Vec<SparsePattern>,
     /// Include no files in the working copy (combine with --add)
     #[arg(long)]
     clear: bool,
+    /// Use the patterns from a sparse profile in the working-copy commit
+    ///
+    /// Profile `NAME` is read from `.sparse-profiles/NAME.toml`, which lists
+    /// the patterns in a `patterns` array. The patterns are updated whenever
+    /// the profile changes in a checked-out commit, until other patterns are
+    /// set.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["add", "remove", "clear"])]
+    profile: Option<String>,
 }
 
 /// Reset the patterns to include all files in the working copy
@@ -81,6 +96,10 @@ pub(crate) struct SparseSetArgs {
 pub(crate) struct SparseResetArgs {}
 
 /// Start an editor to update the patterns that are present in the working copy
+///
+/// Each line is a pattern as accepted by `jj sparse set`. All the patterns are
+/// checked before the working copy is updated, so the working copy is left
+/// unchanged if any of them is invalid.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct SparseEditArgs {}
 
@@ -105,8 +124,11 @@ fn cmd_sparse_list(
     _args: &SparseListArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
-    for path in workspace_command.working_copy().sparse_patterns()? {
-        writeln!(ui.stdout(), "{}", path.to_fs_path(Path::new("")).display())?;
+    if let Some(name) = workspace_command.working_copy().sparse_profile()? {
+        writeln!(ui.status(), "Using sparse profile {name}")?;
+    }
+    for pattern in workspace_command.working_copy().sparse_patterns()? {
+        writeln!(ui.stdout(), "{pattern}")?;
     }
     Ok(())
 }
@@ -118,18 +140,21 @@ fn cmd_sparse_set(
     args: &SparseSetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
+    if let Some(name) = &args.profile {
+        return set_sparse_profile(ui, &mut workspace_command, name);
+    }
     update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
         let mut new_patterns = HashSet::new();
         if !args.clear {
             new_patterns.extend(old_patterns.iter().cloned());
-            for path in &args.remove {
-                new_patterns.remove(path);
+            for pattern in &args.remove {
+                new_patterns.remove(pattern);
             }
         }
-        for path in &args.add {
-            new_patterns.insert(path.to_owned());
+        for pattern in &args.add {
+            new_patterns.insert(pattern.clone());
         }
-        Ok(new_patterns.into_iter().sorted_unstable().collect())
+        Ok(normalize_sparse_patterns(new_patterns))
     })
 }
 
@@ -141,7 +166,7 @@ fn cmd_sparse_reset(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     update_sparse_patterns_with(ui, &mut workspace_command, |_ui, _old_patterns| {
-        Ok(vec![RepoPathBuf::root()])
+        Ok(vec![SparsePattern::Prefix(RepoPathBuf::root())])
     })
 }
 
@@ -153,32 +178,25 @@ fn cmd_sparse_edit(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let repo_path = workspace_command.repo().repo_path().to_owned();
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
-        let mut new_patterns = edit_sparse(&repo_path, old_patterns, command.settings())?;
-        new_patterns.sort_unstable();
-        new_patterns.dedup();
-        Ok(new_patterns)
+    update_sparse_patterns_with(ui, &mut workspace_command, |ui, old_patterns| {
+        let new_patterns = edit_sparse(ui, &repo_path, old_patterns, command.settings())?;
+        Ok(normalize_sparse_patterns(new_patterns))
     })
 }
 
 fn edit_sparse(
+    ui: &Ui,
     repo_path: &Path,
-    sparse: &[RepoPathBuf],
+    sparse: &[SparsePattern],
     settings: &UserSettings,
-) -> Result<Vec<RepoPathBuf>, CommandError> {
+) -> Result<Vec<SparsePattern>, CommandError> {
     let mut content = String::new();
-    for sparse_path in sparse {
-        let workspace_relative_sparse_path = sparse_path.to_fs_path(Path::new(""));
-        let path_string = workspace_relative_sparse_path.to_str().ok_or_else(|| {
-            internal_error(format!(
-                "Stored sparse path is not valid utf-8: {}",
-                workspace_relative_sparse_path.display()
-            ))
-        })?;
-        writeln!(&mut content, "{}", path_string).unwrap();
+    for pattern in sparse {
+        writeln!(&mut content, "{pattern}").unwrap();
     }
 
     let content = edit_temp_file(
+        ui,
         "sparse patterns",
         ".jjsparse",
         repo_path,
@@ -192,7 +210,7 @@ fn edit_sparse(
         .map(|line| line.trim())
         .filter(|line| !line.is_empty())
         .map(|line| {
-            RepoPathBuf::from_relative_path(line).map_err(|err| {
+            SparsePattern::parse(line).map_err(|err| {
                 user_error_with_message(format!("Failed to parse sparse pattern: {line}"), err)
             })
         })
@@ -202,16 +220,36 @@ fn edit_sparse(
 fn update_sparse_patterns_with(
     ui: &mut Ui,
     workspace_command: &mut WorkspaceCommandHelper,
-    f: impl FnOnce(&mut Ui, &[RepoPathBuf]) -> Result<Vec<RepoPathBuf>, CommandError>,
+    f: impl FnOnce(&mut Ui, &[SparsePattern]) -> Result<Vec<SparsePattern>, CommandError>,
 ) -> Result<(), CommandError> {
+    let checkout_options = workspace_command.checkout_options();
     let (mut locked_ws, wc_commit) = workspace_command.start_working_copy_mutation()?;
     let new_patterns = f(ui, locked_ws.locked_wc().sparse_patterns()?)?;
     let stats = locked_ws
         .locked_wc()
-        .set_sparse_patterns(new_patterns)
+        .set_sparse_patterns(new_patterns, &checkout_options)
+        .map_err(|err| internal_error_with_message("Failed to update working copy paths", err))?;
+    let operation_id = locked_ws.locked_wc().old_operation_id().clone();
+    locked_ws.finish(operation_id)?;
+    print_checkout_stats(ui, workspace_command, stats, &wc_commit)?;
+    Ok(())
+}
+
+fn set_sparse_profile(
+    ui: &mut Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    name: &str,
+) -> Result<(), CommandError> {
+    let checkout_options = workspace_command.checkout_options();
+    let (mut locked_ws, wc_commit) = workspace_command.start_working_copy_mutation()?;
+    // Check the profile first to report problems with it as user errors
+    read_sparse_profile(&wc_commit.tree()?, name).map_err(user_error)?;
+    let stats = locked_ws
+        .locked_wc()
+        .set_sparse_profile(name, &checkout_options)
         .map_err(|err| internal_error_with_message("Failed to update working copy paths", err))?;
     let operation_id = locked_ws.locked_wc().old_operation_id().clone();
     locked_ws.finish(operation_id)?;
-    print_checkout_stats(ui, stats, &wc_commit)?;
+    print_checkout_stats(ui, workspace_command, stats, &wc_commit)?;
     Ok(())
 }
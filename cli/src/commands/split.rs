@@ -24,7 +24,9 @@ use tracing::instrument;
 use crate::cli_util::{CommandHelper, RevisionArg};
 use crate::command_error::CommandError;
 use crate::commands::rebase::rebase_descendants;
-use crate::description_util::{description_template_for_commit, edit_description};
+use crate::description_util::{
+    add_trailers, description_template_for_commit, edit_description, lint_description,
+};
 use crate::ui::Ui;
 
 /// Split a revision in two
@@ -54,6 +56,9 @@ pub(crate) struct SplitArgs {
     /// Split the revision into two siblings instead of a parent and child.
     #[arg(long, short)]
     siblings: bool,
+    /// Add a `Signed-off-by` trailer for the configured user
+    #[arg(long)]
+    signoff: bool,
     /// Put these paths in the first commit
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -79,18 +84,14 @@ pub(crate) fn cmd_split(
     let mut tx = workspace_command.start_transaction();
     let end_tree = commit.tree()?;
     let base_tree = merge_commit_trees(tx.repo(), &commit.parents())?;
-    let instructions = format!(
-        "\
-You are splitting a commit into two: {}
-
-The diff initially shows the changes in the commit you're splitting.
-
-Adjust the right side until it shows the contents you want for the first commit.
-The remainder will be in the second commit. If you don't make any changes, then
-the operation will be aborted.
-",
-        tx.format_commit_summary(&commit)
-    );
+    let instructions = tx.base_workspace_helper().format_diff_instructions(
+        "split_instructions",
+        &commit,
+        None,
+        &base_tree,
+        &end_tree,
+        matcher.as_ref(),
+    )?;
 
     // Prompt the user to select the changes they want for the first commit.
     let selected_tree_id =
@@ -120,7 +121,15 @@ the operation will be aborted.
         &base_tree,
         &selected_tree,
     )?;
-    let first_description = edit_description(tx.base_repo(), &first_template, command.settings())?;
+    let first_description =
+        edit_description(ui, tx.base_repo(), &first_template, command.settings())?;
+    let first_description = add_trailers(
+        command.settings(),
+        commit.change_id(),
+        args.signoff,
+        &first_description,
+    )?;
+    lint_description(ui, command.settings(), &first_description)?;
     let first_commit = tx
         .mut_repo()
         .rewrite_commit(command.settings(), &commit)
@@ -156,16 +165,24 @@ the operation will be aborted.
             second_base_tree,
             &second_tree,
         )?;
-        edit_description(tx.base_repo(), &second_template, command.settings())?
+        edit_description(ui, tx.base_repo(), &second_template, command.settings())?
     };
-    let second_commit = tx
+    let second_commit_builder = tx
         .mut_repo()
         .rewrite_commit(command.settings(), &commit)
         .set_parents(second_commit_parents)
         .set_tree_id(second_tree.id())
         // Generate a new change id so that the commit being split doesn't
         // become divergent.
-        .generate_new_change_id()
+        .generate_new_change_id();
+    let second_description = add_trailers(
+        command.settings(),
+        second_commit_builder.change_id(),
+        args.signoff,
+        &second_description,
+    )?;
+    lint_description(ui, command.settings(), &second_description)?;
+    let second_commit = second_commit_builder
         .set_description(second_description)
         .write()?;
 
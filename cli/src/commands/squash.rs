@@ -12,17 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write;
+
 use itertools::Itertools as _;
+use jj_lib::backend::ChangeId;
 use jj_lib::commit::{Commit, CommitIteratorExt};
-use jj_lib::matchers::Matcher;
+use jj_lib::matchers::{FilesMatcher, Matcher};
 use jj_lib::object_id::ObjectId;
 use jj_lib::repo::Repo;
-use jj_lib::revset;
-use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::revset::{self, RevsetExpression, RevsetIteratorExt};
+use jj_lib::rewrite::{merge_commit_trees, rebase_commit, restore_tree};
 use jj_lib::settings::UserSettings;
 use tracing::instrument;
 
-use crate::cli_util::{CommandHelper, DiffSelector, RevisionArg, WorkspaceCommandTransaction};
+use crate::cli_util::{
+    short_change_hash, CommandHelper, DiffSelector, RevisionArg, WorkspaceCommandTransaction,
+};
 use crate::command_error::{user_error, CommandError};
 use crate::description_util::{combine_messages, join_message_paragraphs};
 use crate::ui::Ui;
@@ -46,6 +51,10 @@ use crate::ui::Ui;
 /// non-empty description, you will be asked for the combined description. If
 /// either was empty, then the other one will be used.
 ///
+/// With `--park-conflicts`, the changes which would create new conflicts in the
+/// destination or in the revisions between it and the sources are moved to a
+/// new revision on top of the sources instead, where they can be resolved.
+///
 /// If a working-copy commit gets abandoned, it will be given a new, empty
 /// commit. This is true in general; it is not specific to this command.
 #[derive(clap::Args, Clone, Debug)]
@@ -68,6 +77,11 @@ pub(crate) struct SquashArgs {
     /// Specify diff editor to be used (implies --interactive)
     #[arg(long, value_name = "NAME")]
     tool: Option<String>,
+    /// Move the changes which would cause new conflicts in the destination or
+    /// in the revisions between it and the sources to a new revision on top
+    /// of the sources instead
+    #[arg(long)]
+    park_conflicts: bool,
     /// Move only changes to these paths (instead of all paths)
     #[arg(conflicts_with_all = ["interactive", "tool"], value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
@@ -132,6 +146,9 @@ pub(crate) fn cmd_squash(
         args.revision.is_none() && args.from.is_empty() && args.into.is_none(),
         &args.paths,
     )?;
+    if args.park_conflicts {
+        park_conflicts(ui, &mut tx, command.settings(), &sources, &destination)?;
+    }
     tx.finish(ui, tx_description)?;
     Ok(())
 }
@@ -157,22 +174,14 @@ pub fn move_diff(
     for source in sources {
         let parent_tree = merge_commit_trees(tx.repo(), &source.parents())?;
         let source_tree = source.tree()?;
-        let instructions = format!(
-            "\
-You are moving changes from: {}
-into commit: {}
-
-The left side of the diff shows the contents of the parent commit. The
-right side initially shows the contents of the commit you're moving
-changes from.
-
-Adjust the right side until the diff shows the changes you want to move
-to the destination. If you don't make any changes, then all the changes
-from the source will be moved into the destination.
-",
-            tx.format_commit_summary(source),
-            tx.format_commit_summary(destination)
-        );
+        let instructions = tx.base_workspace_helper().format_diff_instructions(
+            "squash_instructions",
+            source,
+            Some(destination),
+            &parent_tree,
+            &source_tree,
+            matcher,
+        )?;
         let new_parent_tree_id =
             diff_selector.select(&parent_tree, &source_tree, matcher, Some(&instructions))?;
         let new_parent_tree = tx.repo().store().get_root_tree(&new_parent_tree_id)?;
@@ -236,7 +245,13 @@ from the source will be moved into the destination.
     }
     let description = match description {
         Some(description) => description,
-        None => combine_messages(tx.base_repo(), &abandoned_commits, destination, settings)?,
+        None => combine_messages(
+            ui,
+            tx.base_repo(),
+            &abandoned_commits,
+            destination,
+            settings,
+        )?,
     };
     let mut predecessors = vec![destination.id().clone()];
     predecessors.extend(sources.iter().map(|source| source.id().clone()));
@@ -248,3 +263,147 @@ from the source will be moved into the destination.
         .write()?;
     Ok(())
 }
+
+/// Undoes the changes which created new conflicts in the destination or in the
+/// commits between it and the sources after `move_diff()`, and moves them to a
+/// new commit on top of the sources instead.
+///
+/// The conflicting paths of the destination keep their original contents, and
+/// the ones of the commits in between get the contents of their parents.
+pub fn park_conflicts(
+    ui: &mut Ui,
+    tx: &mut WorkspaceCommandTransaction,
+    settings: &UserSettings,
+    sources: &[Commit],
+    destination: &Commit,
+) -> Result<(), CommandError> {
+    if sources.iter().any(|source| {
+        tx.base_repo()
+            .index()
+            .is_ancestor(source.id(), destination.id())
+    }) {
+        return Err(user_error(
+            "Cannot park conflicts when moving changes to a descendant",
+        ));
+    }
+    tx.mut_repo().rebase_descendants(settings)?;
+    let source_ids = sources.iter().ids().cloned().collect_vec();
+    let intermediate_commits: Vec<Commit> = RevsetExpression::commit(destination.id().clone())
+        .range(&RevsetExpression::commits(source_ids.clone()))
+        .minus(&RevsetExpression::commits(source_ids))
+        .evaluate_programmatic(tx.base_repo().as_ref())?
+        .iter()
+        .commits(tx.base_repo().store())
+        .try_collect()?;
+
+    let mut parked_diffs = vec![];
+    let mut parked_paths = vec![];
+    let mut stack_ids = vec![];
+    // Start from the destination so the conflicts we undo don't propagate to the
+    // descendants
+    let old_commits = std::iter::once(destination).chain(intermediate_commits.iter().rev());
+    for (index, old_commit) in old_commits.enumerate() {
+        let Some(commit) = find_visible_commit(tx.repo(), old_commit.change_id())? else {
+            continue;
+        };
+        let old_tree = old_commit.tree()?;
+        let tree = commit.tree()?;
+        let conflicted_paths = tree
+            .conflicts()
+            .map(|(path, _)| path)
+            .filter(|path| old_tree.path_value(path).is_resolved())
+            .collect_vec();
+        if conflicted_paths.is_empty() {
+            stack_ids.push(commit.id().clone());
+            continue;
+        }
+        let matcher = FilesMatcher::new(&conflicted_paths);
+        let restored_tree = if index == 0 {
+            old_tree
+        } else {
+            merge_commit_trees(tx.repo(), &commit.parents())?
+        };
+        let new_tree_id = restore_tree(&restored_tree, &tree, &matcher)?;
+        let new_commit = tx
+            .mut_repo()
+            .rewrite_commit(settings, &commit)
+            .set_tree_id(new_tree_id)
+            .write()?;
+        tx.mut_repo().rebase_descendants(settings)?;
+        stack_ids.push(new_commit.id().clone());
+        parked_diffs.push((new_commit.tree()?, tree));
+        parked_paths.push((commit.change_id().clone(), conflicted_paths));
+    }
+    if parked_diffs.is_empty() {
+        return Ok(());
+    }
+
+    for source in sources {
+        if let Some(commit) = find_visible_commit(tx.repo(), source.change_id())? {
+            stack_ids.push(commit.id().clone());
+        }
+    }
+    let store = tx.repo().store().clone();
+    let head_ids = tx.repo().index().heads(&mut stack_ids.iter());
+    let heads: Vec<Commit> = head_ids
+        .iter()
+        .map(|id| store.get_commit(id))
+        .try_collect()?;
+    let children: Vec<Commit> = RevsetExpression::commits(head_ids.clone())
+        .children()
+        .evaluate_programmatic(tx.repo())?
+        .iter()
+        .commits(&store)
+        .try_collect()?;
+    let mut parked_tree = merge_commit_trees(tx.repo(), &heads)?;
+    for (tree1, tree2) in &parked_diffs {
+        parked_tree = parked_tree.merge(tree1, tree2)?;
+    }
+    let mut description = "Parked conflicting changes\n".to_owned();
+    for (change_id, paths) in &parked_paths {
+        description.push_str(&format!("\nFrom {}:\n", short_change_hash(change_id)));
+        for path in paths {
+            description.push_str(&format!("  {}\n", path.as_internal_file_string()));
+        }
+    }
+    let parked_commit = tx
+        .mut_repo()
+        .new_commit(settings, head_ids.clone(), parked_tree.id())
+        .set_description(description)
+        .write()?;
+    // Keep the working copy and the other children of the stack on top
+    for child in children {
+        let new_parents = child
+            .parents()
+            .into_iter()
+            .map(|parent| {
+                if head_ids.contains(parent.id()) {
+                    parked_commit.clone()
+                } else {
+                    parent
+                }
+            })
+            .unique_by(|parent| parent.id().clone())
+            .collect_vec();
+        rebase_commit(settings, tx.mut_repo(), &child, &new_parents)?;
+    }
+    tx.mut_repo().rebase_descendants(settings)?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(formatter, "Parked conflicting changes in ")?;
+        tx.write_commit_summary(formatter.as_mut(), &parked_commit)?;
+        writeln!(formatter)?;
+    }
+    Ok(())
+}
+
+/// Returns the visible commit with the given change id, or `None` if it was
+/// abandoned or is divergent.
+fn find_visible_commit(
+    repo: &dyn Repo,
+    change_id: &ChangeId,
+) -> Result<Option<Commit>, CommandError> {
+    match repo.resolve_change_id(change_id).as_deref() {
+        Some([commit_id]) => Ok(Some(repo.store().get_commit(commit_id)?)),
+        _ => Ok(None),
+    }
+}
@@ -12,14 +12,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write as _;
+use std::thread;
+use std::time::{Duration, Instant};
+
 use itertools::Itertools;
+use jj_lib::backend::CommitId;
+#[cfg(feature = "watchman")]
+use jj_lib::fsmonitor::FsmonitorKind;
+use jj_lib::git;
+use jj_lib::matchers::Matcher;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::Repo;
+use jj_lib::revset;
 use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::settings::UserSettings;
+use serde_json::json;
 use tracing::instrument;
 
-use crate::cli_util::{print_conflicted_paths, CommandHelper};
+use super::api::commit_json;
+use super::resolve::conflict_json;
+use crate::cli_util::{print_conflicted_paths, CommandHelper, WorkspaceCommandHelper};
+#[cfg(feature = "watchman")]
+use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
 use crate::diff_util;
+use crate::formatter::Formatter;
 use crate::ui::Ui;
 
 /// Show high-level repo status
@@ -29,6 +47,9 @@ use crate::ui::Ui;
 ///  * The working copy commit and its (first) parent, and a summary of the
 ///    changes between them
 ///
+///  * The branches pointing to the working copy commit and its parents, and how
+///    far they are ahead of or behind their tracked remote branches
+///
 ///  * Conflicted branches (see https://github.com/martinvonz/jj/blob/main/docs/branches.md)
 #[derive(clap::Args, Clone, Debug)]
 #[command(visible_alias = "st")]
@@ -36,6 +57,31 @@ pub(crate) struct StatusArgs {
     /// Restrict the status display to these paths
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Print the status as a JSON object on a single line
+    ///
+    /// The object has the same fields as the result of the `status` method of
+    /// `jj api`.
+    #[arg(long)]
+    json: bool,
+    /// Keep running and print the status again whenever it changes
+    ///
+    /// If `core.fsmonitor` is set to `"watchman"`, the working copy is
+    /// snapshotted again whenever the filesystem monitor reports changed files.
+    /// Otherwise, it's snapshotted at every interval.
+    #[arg(long, short)]
+    watch: bool,
+    /// Number of seconds between the snapshots in watch mode
+    ///
+    /// With a filesystem monitor, this is the minimum time between the
+    /// snapshots.
+    #[arg(
+        long,
+        default_value_t = 1,
+        requires = "watch",
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    interval: u64,
 }
 
 #[instrument(skip_all)]
@@ -44,18 +90,121 @@ pub(crate) fn cmd_status(
     command: &CommandHelper,
     args: &StatusArgs,
 ) -> Result<(), CommandError> {
+    if args.watch {
+        return watch_status(ui, command, args);
+    }
     let workspace_command = command.workspace_helper(ui)?;
-    let repo = workspace_command.repo();
-    let maybe_wc_commit = workspace_command
-        .get_wc_commit_id()
-        .map(|id| repo.store().get_commit(id))
-        .transpose()?;
     let matcher = workspace_command
         .parse_file_patterns(&args.paths)?
         .to_matcher();
+    if args.json {
+        let status = status_json(&workspace_command, matcher.as_ref())?;
+        writeln!(ui.stdout(), "{status}")?;
+        return Ok(());
+    }
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
-    let formatter = formatter.as_mut();
+    write_status(
+        formatter.as_mut(),
+        command.settings(),
+        &workspace_command,
+        matcher.as_ref(),
+    )
+}
+
+/// Prints the status whenever it changes, until the command is interrupted.
+fn watch_status(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &StatusArgs,
+) -> Result<(), CommandError> {
+    let interval = Duration::from_secs(args.interval);
+    // Subscribe before the first snapshot so that no change is missed
+    let mut watcher = ChangeWatcher::new(command)?;
+    let mut last_output = None;
+    loop {
+        let snapshot_time = Instant::now();
+        let workspace_command = command.workspace_helper(ui)?;
+        let matcher = workspace_command
+            .parse_file_patterns(&args.paths)?
+            .to_matcher();
+        let mut output = vec![];
+        if args.json {
+            let status = status_json(&workspace_command, matcher.as_ref())?;
+            writeln!(output, "{status}")?;
+        } else {
+            let mut formatter = ui.new_formatter(&mut output);
+            write_status(
+                formatter.as_mut(),
+                command.settings(),
+                &workspace_command,
+                matcher.as_ref(),
+            )?;
+        }
+        if last_output.as_ref() != Some(&output) {
+            let mut stdout = ui.stdout();
+            if last_output.is_some() && !args.json {
+                writeln!(stdout)?;
+            }
+            stdout.write_all(&output)?;
+            stdout.flush()?;
+            last_output = Some(output);
+        }
+        watcher.wait_for_changes()?;
+        // Let the changes settle instead of snapshotting after every write
+        if let Some(remaining) = interval.checked_sub(snapshot_time.elapsed()) {
+            thread::sleep(remaining);
+        }
+    }
+}
+
+/// Tells when the working copy may have changed in watch mode.
+enum ChangeWatcher {
+    /// Snapshots the working copy again at every interval.
+    Poll,
+    /// Waits for the filesystem monitor to report changed files.
+    #[cfg(feature = "watchman")]
+    Watchman(Box<jj_lib::fsmonitor::watchman::ChangeWatcher>),
+}
+
+impl ChangeWatcher {
+    fn new(command: &CommandHelper) -> Result<Self, CommandError> {
+        match command.settings().fsmonitor_kind()? {
+            #[cfg(feature = "watchman")]
+            FsmonitorKind::Watchman => {
+                let workspace_root = command.workspace_loader()?.workspace_root();
+                let watcher = jj_lib::fsmonitor::watchman::ChangeWatcher::new(workspace_root)
+                    .map_err(|err| {
+                        user_error_with_message("Failed to watch the working copy", err)
+                    })?;
+                Ok(ChangeWatcher::Watchman(Box::new(watcher)))
+            }
+            _ => Ok(ChangeWatcher::Poll),
+        }
+    }
+
+    fn wait_for_changes(&mut self) -> Result<(), CommandError> {
+        match self {
+            ChangeWatcher::Poll => Ok(()),
+            #[cfg(feature = "watchman")]
+            ChangeWatcher::Watchman(watcher) => watcher
+                .wait_for_changes()
+                .map_err(|err| user_error_with_message("Failed to watch the working copy", err)),
+        }
+    }
+}
+
+fn write_status(
+    formatter: &mut dyn Formatter,
+    settings: &UserSettings,
+    workspace_command: &WorkspaceCommandHelper,
+    matcher: &dyn Matcher,
+) -> Result<(), CommandError> {
+    let repo = workspace_command.repo();
+    let maybe_wc_commit = workspace_command
+        .get_wc_commit_id()
+        .map(|id| repo.store().get_commit(id))
+        .transpose()?;
 
     if let Some(wc_commit) = &maybe_wc_commit {
         let parent_tree = merge_commit_trees(repo.as_ref(), &wc_commit.parents())?;
@@ -64,10 +213,12 @@ pub(crate) fn cmd_status(
             writeln!(formatter, "The working copy is clean")?;
         } else {
             writeln!(formatter, "Working copy changes:")?;
+            let rename_options = diff_util::rename_detection_options(settings, wc_commit.copies())?;
             diff_util::show_diff_summary(
                 formatter,
-                &workspace_command,
-                parent_tree.diff_stream(&tree, matcher.as_ref()),
+                workspace_command,
+                parent_tree.diff_stream(&tree, matcher),
+                &rename_options,
             )?;
         }
 
@@ -79,7 +230,7 @@ pub(crate) fn cmd_status(
                 formatter.labeled("conflict"),
                 "There are unresolved conflicts at these paths:"
             )?;
-            print_conflicted_paths(&conflicts, formatter, &workspace_command)?
+            print_conflicted_paths(&conflicts, formatter, workspace_command)?
         }
 
         let template = workspace_command.commit_summary_template();
@@ -91,6 +242,12 @@ pub(crate) fn cmd_status(
             template.format(&parent, formatter)?;
             writeln!(formatter)?;
         }
+
+        let commit_ids = [wc_commit.id()]
+            .into_iter()
+            .chain(wc_commit.parent_ids())
+            .collect_vec();
+        print_branch_tracking(formatter, repo.as_ref(), &commit_ids)?;
     } else {
         writeln!(formatter, "No working copy")?;
     }
@@ -141,3 +298,130 @@ pub(crate) fn cmd_status(
 
     Ok(())
 }
+
+/// Returns the status of the working copy as a JSON object, with the changes
+/// restricted to the paths matched by `matcher`.
+pub(crate) fn status_json(
+    workspace_command: &WorkspaceCommandHelper,
+    matcher: &dyn Matcher,
+) -> Result<serde_json::Value, CommandError> {
+    let repo = workspace_command.repo();
+    let mut result = json!({
+        "operation_id": repo.op_id().hex(),
+        "working_copy": null,
+    });
+    let Some(wc_commit_id) = workspace_command.get_wc_commit_id() else {
+        return Ok(result);
+    };
+    let wc_commit = repo.store().get_commit(wc_commit_id)?;
+    let parent_tree = merge_commit_trees(repo.as_ref(), &wc_commit.parents())?;
+    let tree = wc_commit.tree()?;
+    let mut changes = vec![];
+    for (path, diff) in parent_tree.diff(&tree, matcher) {
+        let (before, after) = diff?;
+        let status = if before.is_absent() {
+            "added"
+        } else if after.is_absent() {
+            "removed"
+        } else {
+            "modified"
+        };
+        changes.push(json!({"path": path.as_internal_file_string(), "status": status}));
+    }
+    let conflicts = tree
+        .conflicts()
+        .map(|(path, conflict)| conflict_json(path.as_internal_file_string(), &conflict))
+        .collect_vec();
+    result["working_copy"] = commit_json(&wc_commit);
+    result["parents"] = wc_commit.parents().iter().map(commit_json).collect();
+    result["changes"] = changes.into();
+    result["conflicts"] = conflicts.into();
+    Ok(result)
+}
+
+/// Number of commits up to which a local branch is counted as ahead of or
+/// behind its remote branch.
+const MAX_TRACKING_COUNT: usize = 100;
+
+/// Prints how far the local branches pointing to the `commit_ids` are ahead of
+/// or behind their tracked remote branches.
+fn print_branch_tracking(
+    formatter: &mut dyn Formatter,
+    repo: &dyn Repo,
+    commit_ids: &[&CommitId],
+) -> Result<(), CommandError> {
+    let branches = repo
+        .view()
+        .branches()
+        .filter(|(_, branch_target)| {
+            let local_target = branch_target.local_target;
+            !local_target.has_conflict()
+                && local_target.added_ids().any(|id| commit_ids.contains(&id))
+        })
+        .collect_vec();
+    if branches.is_empty() {
+        return Ok(());
+    }
+    writeln!(formatter, "Branches:")?;
+    for (name, branch_target) in branches {
+        let local_ids = branch_target
+            .local_target
+            .added_ids()
+            .cloned()
+            .collect_vec();
+        let tracking_remote_refs = branch_target
+            .remote_refs
+            .iter()
+            .filter(|&&(remote, remote_ref)| {
+                remote != git::REMOTE_NAME_FOR_LOCAL_GIT_REPO && remote_ref.is_tracking()
+            })
+            .collect_vec();
+        if tracking_remote_refs.is_empty() {
+            write!(formatter, "  ")?;
+            write!(formatter.labeled("branch"), "{name}")?;
+            writeln!(formatter, ": not tracking any remote branch")?;
+        }
+        for &(remote, remote_ref) in tracking_remote_refs {
+            // There's nothing to compare with if the remote branch was deleted,
+            // and walking all the ancestors of the local branch would be slow.
+            if remote_ref.target.is_absent() {
+                continue;
+            }
+            let remote_ids = remote_ref.target.added_ids().cloned().collect_vec();
+            let ahead = revset::walk_revs(repo, &local_ids, &remote_ids)?
+                .iter()
+                .take(MAX_TRACKING_COUNT + 1)
+                .count();
+            let behind = revset::walk_revs(repo, &remote_ids, &local_ids)?
+                .iter()
+                .take(MAX_TRACKING_COUNT + 1)
+                .count();
+            let status = match (ahead, behind) {
+                (0, 0) => "up to date with".to_owned(),
+                (ahead, 0) => format!("{} ahead of", commits_count(ahead)),
+                (0, behind) => format!("{} behind", commits_count(behind)),
+                (ahead, behind) => format!(
+                    "{} ahead and {} behind",
+                    commits_count(ahead),
+                    commits_count(behind)
+                ),
+            };
+            write!(formatter, "  ")?;
+            write!(formatter.labeled("branch"), "{name}")?;
+            write!(formatter, ": {status} ")?;
+            write!(formatter.labeled("branch"), "{name}@{remote}")?;
+            writeln!(formatter)?;
+        }
+    }
+    Ok(())
+}
+
+fn commits_count(count: usize) -> String {
+    if count == 1 {
+        "1 commit".to_owned()
+    } else if count > MAX_TRACKING_COUNT {
+        format!("more than {MAX_TRACKING_COUNT} commits")
+    } else {
+        format!("{count} commits")
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::env;
+use std::io::Write as _;
+use std::process::{Command, Stdio};
+
+use jj_lib::dag_walk;
+use jj_lib::operation::Operation;
+use jj_lib::repo::{Repo as _, ViewConflict};
+use jj_lib::sync::{
+    add_remote_heads, keep_local_view_state, sync_as_client, RemoteHeads, SyncConnection,
+    SyncError, SyncStats,
+};
+use tracing::instrument;
+
+use crate::cli_util::{print_view_conflicts, CommandHelper, WorkspaceCommandHelper};
+use crate::command_error::{user_error, user_error_with_message, CommandError};
+use crate::ui::Ui;
+
+/// Exchange commits with another jj repo
+///
+/// The commits of each repo are copied to the other one, keeping their change
+/// ids and their predecessors, and the visible heads of each repo become
+/// visible in the other one. Unlike `jj git push` and `jj git fetch`, this
+/// requires both repos to use the same commit backend. Branches aren't
+/// exchanged unless `--op-log` is given.
+///
+/// The other repo can be a local path, a `file://` URL, or an
+/// `ssh://[user@]host[:port]/path` URL. For SSH URLs, `jj` must be installed on
+/// the remote host. The `sync.remote-jj` config option can be used to set its
+/// path there.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct SyncArgs {
+    /// Path or URL of the other repo
+    #[arg(value_hint = clap::ValueHint::Url)]
+    url: String,
+    /// Also exchange the operation logs
+    ///
+    /// The latest operation of each repo is merged into the other repo's
+    /// operation log, like concurrent operations. This syncs branches, tags
+    /// and abandoned commits too. The working-copy commits of each repo stay as
+    /// they were.
+    #[arg(long)]
+    op_log: bool,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_sync(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &SyncArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut server = server_command(command, &args.url)?;
+    let mut child = server
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            user_error_with_message(format!("Failed to start jj for {}", args.url), err)
+        })?;
+    let mut connection =
+        SyncConnection::new(child.stdout.take().unwrap(), child.stdin.take().unwrap());
+    let result = sync_as_client(&mut connection, workspace_command.repo(), args.op_log);
+    // Closing the connection lets the server finish
+    drop(connection);
+    let status = child.wait()?;
+    let result = match result {
+        Ok(result) => result,
+        Err(err @ SyncError::Disconnected) => return Err(user_error(err)),
+        Err(err) => return Err(err.into()),
+    };
+    if !status.success() {
+        return Err(user_error(format!("Failed to sync with {}", args.url)));
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        let received = format_stats(&result.received, args.op_log);
+        let sent = format_stats(&result.sent, args.op_log);
+        writeln!(formatter, "Received {received}, sent {sent}")?;
+    }
+    apply_remote_heads(
+        ui,
+        command,
+        &mut workspace_command,
+        result.remote_heads,
+        format!("sync with {}", args.url),
+    )
+}
+
+/// Builds the command which runs `jj util sync-server` in the other repo.
+fn server_command(command: &CommandHelper, url: &str) -> Result<Command, CommandError> {
+    let server_args = ["--ignore-working-copy", "--quiet", "util", "sync-server"];
+    if let Some(rest) = url.strip_prefix("ssh://") {
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        };
+        // "ssh://host/~/repo" is relative to the home directory
+        let path = path.strip_prefix("/~/").map_or_else(
+            || shell_quote(path),
+            |path| format!("~/{}", shell_quote(path)),
+        );
+        // A host starting with "-" would be parsed as an option by ssh
+        if host.is_empty()
+            || host.starts_with('-')
+            || port.is_some_and(|port| port.parse::<u16>().is_err())
+            || path.is_empty()
+        {
+            return Err(user_error(format!("Invalid SSH URL: {url}")));
+        }
+        let remote_jj = command
+            .settings()
+            .config()
+            .get_string("sync.remote-jj")
+            .unwrap_or_else(|_| "jj".to_owned());
+        let mut ssh = Command::new("ssh");
+        if let Some(port) = port {
+            ssh.args(["-p", port]);
+        }
+        ssh.arg("--").arg(host);
+        ssh.arg(format!(
+            "{remote_jj} --repository {path} {}",
+            server_args.join(" ")
+        ));
+        Ok(ssh)
+    } else {
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        let path = command.cwd().join(path);
+        let exe = env::current_exe()
+            .map_err(|err| user_error_with_message("Failed to find the jj executable", err))?;
+        let mut jj = Command::new(exe);
+        jj.arg("--repository").arg(path).args(server_args);
+        Ok(jj)
+    }
+}
+
+fn shell_quote(s: &str) -> String {
+    if s.is_empty() {
+        return String::new();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn format_stats(stats: &SyncStats, op_log: bool) -> String {
+    let commits = format!(
+        "{} {}",
+        stats.num_commits,
+        if stats.num_commits == 1 {
+            "commit"
+        } else {
+            "commits"
+        }
+    );
+    if op_log {
+        let operations = format!(
+            "{} {}",
+            stats.num_operations,
+            if stats.num_operations == 1 {
+                "operation"
+            } else {
+                "operations"
+            }
+        );
+        format!("{commits} and {operations}")
+    } else {
+        commits
+    }
+}
+
+/// Makes the heads of the other repo visible in the repo of the
+/// `workspace_command`, merging the other repo's operation into this repo's
+/// if the operation logs were exchanged.
+pub(crate) fn apply_remote_heads(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_command: &mut WorkspaceCommandHelper,
+    remote_heads: RemoteHeads,
+    description: String,
+) -> Result<(), CommandError> {
+    match remote_heads {
+        RemoteHeads::Commits(head_ids) => {
+            let mut tx = workspace_command.start_transaction();
+            add_remote_heads(tx.mut_repo(), &head_ids)?;
+            tx.finish(ui, description)
+        }
+        RemoteHeads::Operation(remote_op) => {
+            if is_ancestor_op(&remote_op, workspace_command.repo().operation())? {
+                return Ok(());
+            }
+            let mut tx = workspace_command.start_transaction();
+            let local_view = tx.base_repo().view().clone();
+            let remote_view = remote_op.view()?;
+            let mut view_conflicts = tx.merge_operation(remote_op)?;
+            keep_local_view_state(tx.mut_repo(), &local_view, &remote_view)?;
+            tx.mut_repo().rebase_descendants(command.settings())?;
+            // The working-copy commits of this repo were kept
+            view_conflicts.retain(|conflict| {
+                !matches!(conflict, ViewConflict::WorkingCopy { .. })
+                    && conflict.is_present(tx.repo().view())
+            });
+            print_view_conflicts(
+                ui,
+                "Concurrent operations left conflicts in:",
+                &view_conflicts,
+            )?;
+            tx.finish(ui, description)
+        }
+    }
+}
+
+fn is_ancestor_op(ancestor: &Operation, op: &Operation) -> Result<bool, CommandError> {
+    let common_op = dag_walk::closest_common_node_ok(
+        [Ok(ancestor.clone())],
+        [Ok(op.clone())],
+        |op: &Operation| op.id().clone(),
+        |op: &Operation| op.parents().collect::<Vec<_>>(),
+    )?;
+    Ok(common_op.map_or(false, |common_op| common_op.id() == ancestor.id()))
+}
@@ -0,0 +1,90 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use itertools::Itertools as _;
+use jj_lib::object_id::ObjectId;
+use jj_lib::repo::Repo;
+use jj_lib::revset::RevsetExpression;
+use tracing::instrument;
+
+use crate::cli_util::{short_commit_hash, CommandHelper, RevisionArg};
+use crate::command_error::{user_error_with_hint, CommandError};
+use crate::ui::Ui;
+
+/// Recover a hidden commit as a new visible change
+///
+/// Hidden commits are the previous versions of rewritten changes and the
+/// abandoned changes. They can be found with `jj obslog`, `jj log --hidden` or
+/// the `hidden()` revset, and referred to by their commit id.
+///
+/// The recovered commit has the same parents, content and description as the
+/// hidden commit, but a new change id, so it doesn't become divergent with
+/// the current version of the change. If its parents are hidden too, the commit
+/// is recovered on top of their closest visible ancestors instead, so the
+/// hidden parents aren't made visible again.
+#[derive(clap::Args, Clone, Debug)]
+#[command(visible_alias = "recover")]
+pub(crate) struct UnhideArgs {
+    /// The hidden commit to recover
+    revision: RevisionArg,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_unhide(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &UnhideArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let hidden_commit = workspace_command.resolve_single_rev(&args.revision)?;
+    let repo = workspace_command.repo();
+    if repo
+        .resolve_change_id(hidden_commit.change_id())
+        .is_some_and(|visible_ids| visible_ids.contains(hidden_commit.id()))
+    {
+        return Err(user_error_with_hint(
+            format!("Commit {} is not hidden", hidden_commit.id().hex()),
+            "Use `jj duplicate` to copy a visible commit.",
+        ));
+    }
+    // Committing on top of hidden parents would make them visible again with
+    // their original change ids, so they would be divergent.
+    let new_parent_ids = RevsetExpression::commits(hidden_commit.parent_ids().to_vec())
+        .ancestors()
+        .intersection(&RevsetExpression::visible_heads().ancestors())
+        .heads()
+        .evaluate_programmatic(repo.as_ref())?
+        .iter()
+        .collect_vec();
+    let mut tx = workspace_command.start_transaction();
+    let new_commit = tx
+        .mut_repo()
+        .rewrite_commit(command.settings(), &hidden_commit)
+        .set_parents(new_parent_ids)
+        .generate_new_change_id()
+        .write()?;
+    if let Some(mut formatter) = ui.status_formatter() {
+        write!(
+            formatter,
+            "Recovered {} as ",
+            short_commit_hash(hidden_commit.id())
+        )?;
+        tx.write_commit_summary(formatter.as_mut(), &new_commit)?;
+        writeln!(formatter)?;
+    }
+    tx.finish(ui, format!("unhide commit {}", hidden_commit.id().hex()))?;
+    Ok(())
+}
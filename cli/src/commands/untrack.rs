@@ -50,6 +50,7 @@ pub(crate) fn cmd_untrack(
 
     let mut tx = workspace_command.start_transaction().into_inner();
     let base_ignores = workspace_command.base_ignores()?;
+    let conflict_marker_options = workspace_command.conflict_marker_options();
     let (mut locked_ws, wc_commit) = workspace_command.start_working_copy_mutation()?;
     // Create a new tree without the unwanted files
     let mut tree_builder = MergedTreeBuilder::new(wc_commit.tree_id().clone());
@@ -72,6 +73,8 @@ pub(crate) fn cmd_untrack(
         fsmonitor_kind: command.settings().fsmonitor_kind()?,
         progress: None,
         max_new_file_size: command.settings().max_new_file_size()?,
+        exclude_paths: command.settings().snapshot_exclude_paths()?,
+        conflict_marker_options,
     })?;
     if wc_tree_id != *new_commit.tree_id() {
         let wc_tree = store.get_root_tree(&wc_tree_id)?;
@@ -12,16 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Write;
+use std::io::{self, Write};
 use std::slice;
 use std::time::{Duration, SystemTime};
 
 use clap::{Command, Subcommand};
 use jj_lib::repo::Repo;
+use jj_lib::sync::{sync_as_server, SyncConnection};
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::command_error::{user_error, CommandError};
+use crate::commands::sync::apply_remote_heads;
+use crate::config::CONFIG_SCHEMA;
 use crate::ui::Ui;
 
 /// Infrequently used commands such as for generating shell completions
@@ -32,6 +35,8 @@ pub(crate) enum UtilCommand {
     Mangen(UtilMangenArgs),
     MarkdownHelp(UtilMarkdownHelp),
     ConfigSchema(UtilConfigSchemaArgs),
+    #[command(hide = true)]
+    SyncServer(UtilSyncServerArgs),
 }
 
 // Using an explicit `doc` attribute prevents rustfmt from mangling the list
@@ -96,6 +101,10 @@ pub(crate) struct UtilMarkdownHelp {}
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct UtilConfigSchemaArgs {}
 
+/// Serve `jj sync` over stdin and stdout
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct UtilSyncServerArgs {}
+
 /// Available shell completions
 #[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, Hash, PartialEq)]
 enum ShellCompletion {
@@ -119,6 +128,7 @@ pub(crate) fn cmd_util(
         UtilCommand::Mangen(args) => cmd_util_mangen(ui, command, args),
         UtilCommand::MarkdownHelp(args) => cmd_util_markdownhelp(ui, command, args),
         UtilCommand::ConfigSchema(args) => cmd_util_config_schema(ui, command, args),
+        UtilCommand::SyncServer(args) => cmd_util_sync_server(ui, command, args),
     }
 }
 
@@ -221,11 +231,27 @@ fn cmd_util_config_schema(
     _args: &UtilConfigSchemaArgs,
 ) -> Result<(), CommandError> {
     // TODO(#879): Consider generating entire schema dynamically vs. static file.
-    let buf = include_bytes!("../config-schema.json");
-    ui.stdout_formatter().write_all(buf)?;
+    ui.stdout_formatter().write_all(CONFIG_SCHEMA.as_bytes())?;
     Ok(())
 }
 
+fn cmd_util_sync_server(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    _args: &UtilSyncServerArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut connection = SyncConnection::new(io::stdin(), io::stdout());
+    let result = sync_as_server(&mut connection, workspace_command.repo())?;
+    apply_remote_heads(
+        ui,
+        command,
+        &mut workspace_command,
+        result.remote_heads,
+        "sync with a remote repo".to_owned(),
+    )
+}
+
 impl ShellCompletion {
     fn generate(&self, cmd: &mut Command) -> Vec<u8> {
         use clap_complete::{generate, Shell};
@@ -0,0 +1,475 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::slice;
+
+use jj_lib::backend::{CommitId, Timestamp};
+use jj_lib::commit::Commit;
+use jj_lib::dag_walk::topo_order_reverse;
+use jj_lib::hex_util::to_reverse_hex;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_walk;
+use jj_lib::repo::Repo as _;
+use jj_lib::revset;
+use jj_lib::revset_graph::{RevsetGraphEdgeType, TopoGroupedRevsetGraphIterator};
+use tracing::instrument;
+
+use crate::cli_util::{short_operation_hash, CommandHelper, RevisionArg, WorkspaceCommandHelper};
+use crate::command_error::{config_error_with_message, user_error_with_message, CommandError};
+use crate::commit_templater::CommitTemplateLanguage;
+use crate::diff_util;
+use crate::formatter::PlainTextFormatter;
+use crate::graphlog::{get_graphlog, Edge};
+use crate::templater::TemplateRenderer;
+use crate::time_util::format_absolute_timestamp;
+use crate::ui::Ui;
+
+/// Serve a web interface for browsing the repo
+///
+/// The interface shows the commit graph, the operation log, and the changes
+/// and evolution of each commit. Pages are rendered from the latest operation
+/// when they're requested. The working copy isn't snapshotted, so run another
+/// command such as `jj status` to see its latest changes.
+///
+/// The server only listens on the loopback interface.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct WebArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8000)]
+    port: u16,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_web(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &WebArgs,
+) -> Result<(), CommandError> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, args.port)).map_err(|err| {
+        user_error_with_message(format!("Failed to listen on port {}", args.port), err)
+    })?;
+    writeln!(ui.status(), "Serving on http://{}/", listener.local_addr()?)?;
+    for stream in listener.incoming() {
+        // A failing connection shouldn't stop the server.
+        if let Err(err) = stream.and_then(|stream| serve_connection(ui, command, stream)) {
+            writeln!(ui.warning_default(), "Failed to serve request: {err}")?;
+        }
+    }
+    Ok(())
+}
+
+/// A rendered page.
+struct Response {
+    status: &'static str,
+    title: String,
+    body: String,
+}
+
+fn serve_connection(ui: &mut Ui, command: &CommandHelper, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers aren't needed.
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    let response = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["GET", target, _] => route(ui, command, target),
+        _ => Response {
+            status: "400 Bad Request",
+            title: "Bad request".to_owned(),
+            body: "<p>Only GET requests are supported.</p>".to_owned(),
+        },
+    };
+    let html = render_html(&response);
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: \
+         {}\r\nConnection: close\r\n\r\n{html}",
+        response.status,
+        html.len()
+    )?;
+    stream.flush()
+}
+
+fn route(ui: &mut Ui, command: &CommandHelper, target: &str) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = percent_decode(path);
+    let result = if path == "/" {
+        let revset = query_param(query, "revset").filter(|revset| !revset.is_empty());
+        render_log(ui, command, revset)
+    } else if path == "/operations" {
+        render_operations(ui, command, query_param(query, "skip"))
+    } else if let Some(revision) = path.strip_prefix("/commit/") {
+        render_commit(ui, command, revision)
+    } else if let Some(revision) = path.strip_prefix("/obslog/") {
+        render_obslog(ui, command, revision)
+    } else {
+        return Response {
+            status: "404 Not Found",
+            title: "Not found".to_owned(),
+            body: format!("<p>No page at {}.</p>", escape_html(&path)),
+        };
+    };
+    result.unwrap_or_else(|err| {
+        let mut message = err.error.to_string();
+        let mut source = err.error.source();
+        while let Some(err) = source {
+            write!(message, ": {err}").unwrap();
+            source = err.source();
+        }
+        Response {
+            status: "400 Bad Request",
+            title: "Error".to_owned(),
+            body: format!("<p class=\"error\">{}</p>", escape_html(&message)),
+        }
+    })
+}
+
+fn render_html(response: &Response) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title} - jj</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em 2em; }}
+pre {{ font-family: monospace; line-height: 1.2; }}
+nav a {{ margin-right: 1em; }}
+.error {{ color: red; }}
+</style>
+</head>
+<body>
+<nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+<h1>{title}</h1>
+{body}
+</body>
+</html>
+"#,
+        title = escape_html(&response.title),
+        body = response.body,
+    )
+}
+
+/// Renders `commits` as a graph in which each commit links to its page.
+///
+/// The commits are in reverse topological order, with the edges to their
+/// displayed parents.
+fn render_graph(
+    command: &CommandHelper,
+    workspace_command: &WorkspaceCommandHelper,
+    commits: impl IntoIterator<Item = (Commit, Vec<Edge<CommitId>>)>,
+) -> Result<String, CommandError> {
+    let language = workspace_command.commit_template_language()?;
+    let node_template = workspace_command.parse_template(
+        &language,
+        &command.settings().commit_node_template(),
+        CommitTemplateLanguage::wrap_commit_opt,
+    )?;
+    let summary_template = workspace_command.commit_summary_template();
+    let mut output = vec![];
+    {
+        let mut graph = get_graphlog(command.settings(), &mut output);
+        for (commit, edges) in commits {
+            let text = format!(
+                "<a href=\"/commit/{}\">{}</a>\n",
+                commit.id().hex(),
+                escape_html(&format_plain(&summary_template, &commit)?)
+            );
+            let node_symbol = format_plain(&node_template, &Some(commit.clone()))?;
+            graph.add_node(commit.id(), &edges, &escape_html(&node_symbol), &text)?;
+        }
+    }
+    Ok(format!("<pre>{}</pre>", String::from_utf8_lossy(&output)))
+}
+
+fn render_log(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    revset: Option<String>,
+) -> Result<Response, CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let (revset_text, expression) = match revset {
+        Some(revset) => {
+            let expression = workspace_command.parse_revset(&RevisionArg::from(revset.clone()))?;
+            (revset, expression)
+        }
+        None => {
+            let revset = command.settings().default_revset();
+            let expression = revset::parse(&revset, &workspace_command.revset_parse_context())
+                .map_err(|err| config_error_with_message("Invalid `revsets.log`", err))?;
+            let expression = workspace_command.attach_revset_evaluator(expression)?;
+            (revset, expression)
+        }
+    };
+    let revset = expression.evaluate()?;
+    let store = workspace_command.repo().store();
+    let mut commits = vec![];
+    for (commit_id, edges) in TopoGroupedRevsetGraphIterator::new(revset.iter_graph()) {
+        let edges = edges
+            .into_iter()
+            .map(|edge| match edge.edge_type {
+                RevsetGraphEdgeType::Missing => Edge::Missing,
+                RevsetGraphEdgeType::Direct => Edge::Direct(edge.target),
+                RevsetGraphEdgeType::Indirect => Edge::Indirect(edge.target),
+            })
+            .collect();
+        commits.push((store.get_commit(&commit_id)?, edges));
+    }
+    let body = format!(
+        "<form action=\"/\"><input name=\"revset\" size=\"60\" value=\"{}\"> <input \
+         type=\"submit\" value=\"Show\"></form>\n{}",
+        escape_html(&revset_text),
+        render_graph(command, &workspace_command, commits)?
+    );
+    Ok(Response {
+        status: "200 OK",
+        title: "Log".to_owned(),
+        body,
+    })
+}
+
+fn render_commit(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    revision: &str,
+) -> Result<Response, CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let commit = workspace_command.resolve_single_rev(&RevisionArg::from(revision.to_owned()))?;
+    let summary = workspace_command.format_commit_summary(&commit);
+    let mut body = String::new();
+    writeln!(
+        body,
+        "<p>Commit ID: {}<br>\nChange ID: <a href=\"/?revset={change_id}\">{change_id}</a><br>",
+        commit.id().hex(),
+        change_id = to_reverse_hex(&commit.change_id().hex()).unwrap(),
+    )
+    .unwrap();
+    writeln!(
+        body,
+        "Author: {} &lt;{}&gt; ({})<br>",
+        escape_html(&commit.author().name),
+        escape_html(&commit.author().email),
+        escape_html(&format_timestamp(&commit.author().timestamp)),
+    )
+    .unwrap();
+    for parent in commit.parents() {
+        writeln!(
+            body,
+            "Parent: <a href=\"/commit/{}\">{}</a><br>",
+            parent.id().hex(),
+            escape_html(&workspace_command.format_commit_summary(&parent))
+        )
+        .unwrap();
+    }
+    writeln!(
+        body,
+        "<a href=\"/obslog/{}\">Evolution of this change</a></p>",
+        commit.id().hex()
+    )
+    .unwrap();
+    let description = if commit.description().is_empty() {
+        "(no description set)"
+    } else {
+        commit.description()
+    };
+    writeln!(body, "<pre>{}</pre>", escape_html(description)).unwrap();
+    let format = diff_util::diff_format_from_name(command.settings(), "git")?;
+    let mut diff = vec![];
+    diff_util::show_patch(
+        ui,
+        &mut PlainTextFormatter::new(&mut diff),
+        command.settings(),
+        &workspace_command,
+        &commit,
+        &EverythingMatcher,
+        &[format],
+    )?;
+    writeln!(
+        body,
+        "<pre>{}</pre>",
+        escape_html(&String::from_utf8_lossy(&diff))
+    )
+    .unwrap();
+    Ok(Response {
+        status: "200 OK",
+        title: summary,
+        body,
+    })
+}
+
+fn render_obslog(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    revision: &str,
+) -> Result<Response, CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let start_commit =
+        workspace_command.resolve_single_rev(&RevisionArg::from(revision.to_owned()))?;
+    let title = format!(
+        "Evolution of {}",
+        workspace_command.format_commit_summary(&start_commit)
+    );
+    let commits = topo_order_reverse(
+        vec![start_commit],
+        |commit: &Commit| commit.id().clone(),
+        |commit: &Commit| commit.predecessors(),
+    )
+    .into_iter()
+    .map(|commit| {
+        let edges = commit
+            .predecessor_ids()
+            .iter()
+            .map(|id| Edge::Direct(id.clone()))
+            .collect();
+        (commit, edges)
+    });
+    Ok(Response {
+        status: "200 OK",
+        title,
+        body: render_graph(command, &workspace_command, commits)?,
+    })
+}
+
+/// Number of operations shown on each page of the operation log.
+const OPERATIONS_PER_PAGE: usize = 50;
+
+fn render_operations(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    skip: Option<String>,
+) -> Result<Response, CommandError> {
+    let skip = match skip {
+        Some(skip) => skip
+            .parse()
+            .map_err(|err| user_error_with_message(format!("Invalid skip count: {skip}"), err))?,
+        None => 0,
+    };
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let mut body = "<pre>".to_owned();
+    let mut ops = op_walk::walk_ancestors(slice::from_ref(workspace_command.repo().operation()))
+        .skip(skip)
+        .peekable();
+    for op in ops.by_ref().take(OPERATIONS_PER_PAGE) {
+        let op = op?;
+        let metadata = op.metadata();
+        writeln!(
+            body,
+            "{} {}@{} {}",
+            short_operation_hash(op.id()),
+            escape_html(&metadata.username),
+            escape_html(&metadata.hostname),
+            escape_html(&format_timestamp(&metadata.end_time)),
+        )
+        .unwrap();
+        if !metadata.description.is_empty() {
+            writeln!(body, "    {}", escape_html(&metadata.description)).unwrap();
+        }
+    }
+    body.push_str("</pre>");
+    if skip > 0 {
+        write!(
+            body,
+            "\n<p><a href=\"/operations?skip={}\">Newer operations</a></p>",
+            skip.saturating_sub(OPERATIONS_PER_PAGE)
+        )
+        .unwrap();
+    }
+    if ops.peek().is_some() {
+        write!(
+            body,
+            "\n<p><a href=\"/operations?skip={}\">Older operations</a></p>",
+            skip + OPERATIONS_PER_PAGE
+        )
+        .unwrap();
+    }
+    Ok(Response {
+        status: "200 OK",
+        title: "Operations".to_owned(),
+        body,
+    })
+}
+
+fn format_plain<C: Clone>(
+    template: &TemplateRenderer<'_, C>,
+    context: &C,
+) -> Result<String, CommandError> {
+    let mut output = vec![];
+    template.format(context, &mut PlainTextFormatter::new(&mut output))?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn format_timestamp(timestamp: &Timestamp) -> String {
+    format_absolute_timestamp(timestamp).unwrap_or_else(|err| err.to_string())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns the decoded value of the parameter `name` in the query string.
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query.split('&').find_map(|param| {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        (percent_decode(key) == name).then(|| percent_decode(value))
+    })
+}
+
+/// Decodes a URL component, in which spaces may be encoded as "+".
+fn percent_decode(text: &str) -> String {
+    let mut bytes = vec![];
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [iter.next(), iter.next()];
+                match hex
+                    .iter()
+                    .map(|digit| digit.and_then(|digit| (digit as char).to_digit(16)))
+                    .collect::<Option<Vec<_>>>()
+                    .as_deref()
+                {
+                    Some(&[high, low]) => bytes.push((high * 16 + low) as u8),
+                    _ => {
+                        bytes.push(b'%');
+                        bytes.extend(hex.into_iter().flatten());
+                    }
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -25,7 +25,8 @@ use jj_lib::op_store::{OpStoreError, WorkspaceId};
 use jj_lib::operation::Operation;
 use jj_lib::repo::{ReadonlyRepo, Repo};
 use jj_lib::rewrite::merge_commit_trees;
-use jj_lib::workspace::Workspace;
+use jj_lib::sparse::SparsePattern;
+use jj_lib::workspace::{forget_workspace_root, recorded_workspace_root, Workspace};
 use tracing::instrument;
 
 use crate::cli_util::{
@@ -82,6 +83,20 @@ pub(crate) struct WorkspaceAddArgs {
     /// new r1 r2 r3 ...`.
     #[arg(long, short)]
     revision: Vec<RevisionArg>,
+    /// Only check out the paths matching these patterns in the new workspace
+    /// (can be repeated)
+    ///
+    /// The patterns are relative to the workspace root, and use the same
+    /// syntax as `jj sparse set`. By default, the sparse patterns of the
+    /// current workspace are copied. They can be changed later with `jj
+    /// sparse`.
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_hint = clap::ValueHint::AnyPath,
+        value_parser = |s: &str| SparsePattern::parse(s),
+    )]
+    sparse: Vec<SparsePattern>,
 }
 
 /// Stop tracking a workspace's working-copy commit in the repo
@@ -92,12 +107,26 @@ pub(crate) struct WorkspaceAddArgs {
 pub(crate) struct WorkspaceForgetArgs {
     /// Names of the workspaces to forget. By default, forgets only the current
     /// workspace.
+    #[arg(conflicts_with = "clean")]
     workspaces: Vec<String>,
+    /// Forget the workspaces whose directories no longer exist
+    #[arg(long)]
+    clean: bool,
+    /// Also abandon the working-copy commits of the forgotten workspaces
+    #[arg(long)]
+    abandon: bool,
 }
 
 /// List workspaces
 #[derive(clap::Args, Clone, Debug)]
-pub(crate) struct WorkspaceListArgs {}
+pub(crate) struct WorkspaceListArgs {
+    /// Only list the workspaces whose directories no longer exist
+    ///
+    /// Workspaces created by older versions of jj aren't known to be stale
+    /// since their directories weren't recorded.
+    #[arg(long)]
+    stale: bool,
+}
 
 /// Show the current workspace root directory
 #[derive(clap::Args, Clone, Debug)]
@@ -171,16 +200,22 @@ fn cmd_workspace_add(
             .display()
     )?;
 
-    // Copy sparse patterns from workspace where the command was run
+    // Copy sparse patterns from workspace where the command was run, unless
+    // they were specified
     let mut new_workspace_command = WorkspaceCommandHelper::new(ui, command, new_workspace, repo)?;
+    let checkout_options = new_workspace_command.checkout_options();
     let (mut locked_ws, _wc_commit) = new_workspace_command.start_working_copy_mutation()?;
-    let sparse_patterns = old_workspace_command
-        .working_copy()
-        .sparse_patterns()?
-        .to_vec();
+    let sparse_patterns = if args.sparse.is_empty() {
+        old_workspace_command
+            .working_copy()
+            .sparse_patterns()?
+            .to_vec()
+    } else {
+        args.sparse.clone()
+    };
     locked_ws
         .locked_wc()
-        .set_sparse_patterns(sparse_patterns)
+        .set_sparse_patterns(sparse_patterns, &checkout_options)
         .map_err(|err| internal_error_with_message("Failed to set sparse patterns", err))?;
     let operation_id = locked_ws.locked_wc().old_operation_id().clone();
     locked_ws.finish(operation_id)?;
@@ -231,7 +266,14 @@ fn cmd_workspace_forget(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
 
-    let wss: Vec<WorkspaceId> = if args.workspaces.is_empty() {
+    let wss: Vec<WorkspaceId> = if args.clean {
+        let wss = find_stale_workspaces(workspace_command.repo())?;
+        if wss.is_empty() {
+            writeln!(ui.status(), "No workspaces with missing directories.")?;
+            return Ok(());
+        }
+        wss
+    } else if args.workspaces.is_empty() {
         vec![workspace_command.workspace_id().clone()]
     } else {
         args.workspaces
@@ -254,7 +296,17 @@ fn cmd_workspace_forget(
     // bundle every workspace forget into a single transaction, so that e.g.
     // undo correctly restores all of them at once.
     let mut tx = workspace_command.start_transaction();
-    wss.iter().for_each(|ws| tx.mut_repo().remove_wc_commit(ws));
+    for ws in &wss {
+        let wc_commit_id = tx.repo().view().get_wc_commit_id(ws).unwrap().clone();
+        tx.mut_repo().remove_wc_commit(ws);
+        if args.abandon {
+            tx.mut_repo().record_abandoned_commit(wc_commit_id);
+        }
+    }
+    let num_rebased = tx.mut_repo().rebase_descendants(command.settings())?;
+    if num_rebased > 0 {
+        writeln!(ui.status(), "Rebased {num_rebased} descendant commits")?;
+    }
     let description = if let [ws] = wss.as_slice() {
         format!("forget workspace {}", ws.as_str())
     } else {
@@ -264,21 +316,49 @@ fn cmd_workspace_forget(
         )
     };
 
+    let repo_path = tx.base_repo().repo_path().to_owned();
     tx.finish(ui, description)?;
+    for ws in &wss {
+        forget_workspace_root(&repo_path, ws)?;
+    }
     Ok(())
 }
 
+/// Returns the workspaces whose recorded root directories no longer exist.
+fn find_stale_workspaces(repo: &ReadonlyRepo) -> Result<Vec<WorkspaceId>, CommandError> {
+    let mut stale_workspaces = vec![];
+    for workspace_id in repo.view().wc_commit_ids().keys().sorted() {
+        if let Some(root) = recorded_workspace_root(repo.repo_path(), workspace_id)? {
+            if !root.exists() {
+                stale_workspaces.push(workspace_id.clone());
+            }
+        }
+    }
+    Ok(stale_workspaces)
+}
+
 #[instrument(skip_all)]
 fn cmd_workspace_list(
     ui: &mut Ui,
     command: &CommandHelper,
-    _args: &WorkspaceListArgs,
+    args: &WorkspaceListArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
     let repo = workspace_command.repo();
+    let stale_workspaces = if args.stale {
+        Some(find_stale_workspaces(repo)?)
+    } else {
+        None
+    };
     let mut formatter = ui.stdout_formatter();
     let template = workspace_command.commit_summary_template();
     for (workspace_id, wc_commit_id) in repo.view().wc_commit_ids().iter().sorted() {
+        if stale_workspaces
+            .as_ref()
+            .is_some_and(|stale| !stale.contains(workspace_id))
+        {
+            continue;
+        }
         write!(formatter, "{}: ", workspace_id.as_str())?;
         let commit = repo.store().get_commit(wc_commit_id)?;
         template.format(&commit, formatter.as_mut())?;
@@ -374,6 +454,15 @@ fn cmd_workspace_update_stale(
     ui: &mut Ui,
     command: &CommandHelper,
     _args: &WorkspaceUpdateStaleArgs,
+) -> Result<(), CommandError> {
+    update_stale_working_copy(ui, command)
+}
+
+/// Updates the working copy to the working-copy commit of the current
+/// operation, keeping any changes made since it became stale.
+pub(crate) fn update_stale_working_copy(
+    ui: &mut Ui,
+    command: &CommandHelper,
 ) -> Result<(), CommandError> {
     // Snapshot the current working copy on top of the last known working-copy
     // operation, then merge the concurrent operations. The wc_commit_id of the
@@ -396,6 +485,7 @@ fn cmd_workspace_update_stale(
     let mut workspace_command = command.workspace_helper_no_snapshot(ui)?;
 
     let repo = workspace_command.repo().clone();
+    let checkout_options = workspace_command.checkout_options();
     let (mut locked_ws, desired_wc_commit) =
         workspace_command.unchecked_start_working_copy_mutation()?;
     match check_stale_working_copy(locked_ws.locked_wc(), &desired_wc_commit, &repo)? {
@@ -413,7 +503,7 @@ fn cmd_workspace_update_stale(
             }
             let stats = locked_ws
                 .locked_wc()
-                .check_out(&desired_wc_commit)
+                .check_out(&desired_wc_commit, &checkout_options)
                 .map_err(|err| {
                     internal_error_with_message(
                         format!(
@@ -431,7 +521,7 @@ fn cmd_workspace_update_stale(
                 })?;
                 writeln!(formatter)?;
             }
-            print_checkout_stats(ui, stats, &desired_wc_commit)?;
+            print_checkout_stats(ui, &workspace_command, stats, &desired_wc_commit)?;
         }
     }
     Ok(())
@@ -13,32 +13,37 @@
 // limitations under the License.
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::cmp::max;
-use std::collections::HashMap;
-use std::io;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::{fmt, io};
 
 use itertools::Itertools as _;
 use jj_lib::backend::{ChangeId, CommitId};
 use jj_lib::commit::Commit;
 use jj_lib::extensions_map::ExtensionsMap;
+use jj_lib::git_backend::GitBackend;
 use jj_lib::hex_util::to_reverse_hex;
 use jj_lib::id_prefix::IdPrefixContext;
+use jj_lib::index::ReadonlyIndex;
 use jj_lib::object_id::ObjectId as _;
-use jj_lib::op_store::{RefTarget, WorkspaceId};
-use jj_lib::repo::Repo;
-use jj_lib::revset::{Revset, RevsetParseContext};
+use jj_lib::op_store::{OperationId, RefTarget, RemoteRef, WorkspaceId};
+use jj_lib::operation::Operation;
+use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::revset::{self, Revset, RevsetParseContext};
 use jj_lib::{git, rewrite};
 use once_cell::unsync::OnceCell;
 
+use crate::operation_templater::{self, OperationTemplateEnvironment};
 use crate::template_builder::{
     self, merge_fn_map, BuildContext, CoreTemplateBuildFnTable, CoreTemplatePropertyKind,
     IntoTemplateProperty, TemplateBuildMethodFnMap, TemplateLanguage,
 };
 use crate::template_parser::{self, FunctionCallNode, TemplateParseError, TemplateParseResult};
 use crate::templater::{
-    self, IntoTemplate, PlainTextFormattedProperty, Template, TemplateFormatter, TemplateProperty,
-    TemplatePropertyError, TemplatePropertyExt as _,
+    self, IntoTemplate, PlainTextFormattedProperty, SizeHint, Template, TemplateFormatter,
+    TemplateProperty, TemplatePropertyError, TemplatePropertyExt as _,
 };
 use crate::{revset_util, text_util};
 
@@ -50,6 +55,9 @@ pub trait CommitTemplateLanguageExtension {
 
 pub struct CommitTemplateLanguage<'repo> {
     repo: &'repo dyn Repo,
+    // Repo whose operation history is searched to find the operation that
+    // created a commit. This is the base repo if `repo` is a mutable repo.
+    base_repo: &'repo ReadonlyRepo,
     workspace_id: WorkspaceId,
     // RevsetParseContext doesn't borrow a repo, but we'll need 'repo lifetime
     // anyway to capture it to evaluate dynamically-constructed user expression
@@ -68,6 +76,7 @@ impl<'repo> CommitTemplateLanguage<'repo> {
     /// evaluation tree.
     pub fn new(
         repo: &'repo dyn Repo,
+        base_repo: &'repo ReadonlyRepo,
         workspace_id: &WorkspaceId,
         revset_parse_context: RevsetParseContext<'repo>,
         id_prefix_context: &'repo IdPrefixContext,
@@ -85,6 +94,7 @@ impl<'repo> CommitTemplateLanguage<'repo> {
 
         CommitTemplateLanguage {
             repo,
+            base_repo,
             workspace_id: workspace_id.clone(),
             revset_parse_context,
             id_prefix_context,
@@ -166,6 +176,11 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                     Self::wrap_ref_name,
                 )
             }
+            CommitTemplatePropertyKind::DiffEditContext(property) => {
+                let table = &self.build_fn_table.diff_edit_context_methods;
+                let build = template_parser::lookup_method("DiffEditContext", table, function)?;
+                build(self, build_ctx, property, function)
+            }
             CommitTemplatePropertyKind::CommitOrChangeId(property) => {
                 let table = &self.build_fn_table.commit_or_change_id_methods;
                 let build = template_parser::lookup_method("CommitOrChangeId", table, function)?;
@@ -176,6 +191,16 @@ impl<'repo> TemplateLanguage<'repo> for CommitTemplateLanguage<'repo> {
                 let build = template_parser::lookup_method("ShortestIdPrefix", table, function)?;
                 build(self, build_ctx, property, function)
             }
+            CommitTemplatePropertyKind::Operation(property) => {
+                let table = &self.build_fn_table.operation_methods;
+                let build = template_parser::lookup_method("Operation", table, function)?;
+                build(self, build_ctx, property, function)
+            }
+            CommitTemplatePropertyKind::OperationId(property) => {
+                let table = &self.build_fn_table.operation_id_methods;
+                let build = template_parser::lookup_method("OperationId", table, function)?;
+                build(self, build_ctx, property, function)
+            }
         }
     }
 }
@@ -235,6 +260,12 @@ impl<'repo> CommitTemplateLanguage<'repo> {
         CommitTemplatePropertyKind::RefNameList(Box::new(property))
     }
 
+    pub fn wrap_diff_edit_context(
+        property: impl TemplateProperty<Output = DiffEditContext> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::DiffEditContext(Box::new(property))
+    }
+
     pub fn wrap_commit_or_change_id(
         property: impl TemplateProperty<Output = CommitOrChangeId> + 'repo,
     ) -> CommitTemplatePropertyKind<'repo> {
@@ -248,6 +279,28 @@ impl<'repo> CommitTemplateLanguage<'repo> {
     }
 }
 
+impl<'repo> OperationTemplateEnvironment<'repo> for CommitTemplateLanguage<'repo> {
+    fn root_op_id(&self) -> &OperationId {
+        self.base_repo.op_store().root_operation_id()
+    }
+
+    fn current_op_id(&self) -> Option<&OperationId> {
+        Some(self.base_repo.op_id())
+    }
+
+    fn wrap_operation(
+        property: impl TemplateProperty<Output = Operation> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::Operation(Box::new(property))
+    }
+
+    fn wrap_operation_id(
+        property: impl TemplateProperty<Output = OperationId> + 'repo,
+    ) -> CommitTemplatePropertyKind<'repo> {
+        CommitTemplatePropertyKind::OperationId(Box::new(property))
+    }
+}
+
 pub enum CommitTemplatePropertyKind<'repo> {
     Core(CoreTemplatePropertyKind<'repo>),
     Commit(Box<dyn TemplateProperty<Output = Commit> + 'repo>),
@@ -256,8 +309,11 @@ pub enum CommitTemplatePropertyKind<'repo> {
     RefName(Box<dyn TemplateProperty<Output = RefName> + 'repo>),
     RefNameOpt(Box<dyn TemplateProperty<Output = Option<RefName>> + 'repo>),
     RefNameList(Box<dyn TemplateProperty<Output = Vec<RefName>> + 'repo>),
+    DiffEditContext(Box<dyn TemplateProperty<Output = DiffEditContext> + 'repo>),
     CommitOrChangeId(Box<dyn TemplateProperty<Output = CommitOrChangeId> + 'repo>),
     ShortestIdPrefix(Box<dyn TemplateProperty<Output = ShortestIdPrefix> + 'repo>),
+    Operation(Box<dyn TemplateProperty<Output = Operation> + 'repo>),
+    OperationId(Box<dyn TemplateProperty<Output = OperationId> + 'repo>),
 }
 
 impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
@@ -278,8 +334,11 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::RefNameList(property) => {
                 Some(Box::new(property.map(|l| !l.is_empty())))
             }
+            CommitTemplatePropertyKind::DiffEditContext(_) => None,
             CommitTemplatePropertyKind::CommitOrChangeId(_) => None,
             CommitTemplatePropertyKind::ShortestIdPrefix(_) => None,
+            CommitTemplatePropertyKind::Operation(_) => None,
+            CommitTemplatePropertyKind::OperationId(_) => None,
         }
     }
 
@@ -309,12 +368,15 @@ impl<'repo> IntoTemplateProperty<'repo> for CommitTemplatePropertyKind<'repo> {
             CommitTemplatePropertyKind::RefName(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::RefNameOpt(property) => Some(property.into_template()),
             CommitTemplatePropertyKind::RefNameList(property) => Some(property.into_template()),
+            CommitTemplatePropertyKind::DiffEditContext(_) => None,
             CommitTemplatePropertyKind::CommitOrChangeId(property) => {
                 Some(property.into_template())
             }
             CommitTemplatePropertyKind::ShortestIdPrefix(property) => {
                 Some(property.into_template())
             }
+            CommitTemplatePropertyKind::Operation(_) => None,
+            CommitTemplatePropertyKind::OperationId(property) => Some(property.into_template()),
         }
     }
 }
@@ -328,8 +390,11 @@ pub struct CommitTemplateBuildFnTable<'repo> {
     pub core: CoreTemplateBuildFnTable<'repo, CommitTemplateLanguage<'repo>>,
     pub commit_methods: CommitTemplateBuildMethodFnMap<'repo, Commit>,
     pub ref_name_methods: CommitTemplateBuildMethodFnMap<'repo, RefName>,
+    pub diff_edit_context_methods: CommitTemplateBuildMethodFnMap<'repo, DiffEditContext>,
     pub commit_or_change_id_methods: CommitTemplateBuildMethodFnMap<'repo, CommitOrChangeId>,
     pub shortest_id_prefix_methods: CommitTemplateBuildMethodFnMap<'repo, ShortestIdPrefix>,
+    pub operation_methods: CommitTemplateBuildMethodFnMap<'repo, Operation>,
+    pub operation_id_methods: CommitTemplateBuildMethodFnMap<'repo, OperationId>,
 }
 
 impl<'repo> CommitTemplateBuildFnTable<'repo> {
@@ -339,8 +404,11 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             core: CoreTemplateBuildFnTable::builtin(),
             commit_methods: builtin_commit_methods(),
             ref_name_methods: builtin_ref_name_methods(),
+            diff_edit_context_methods: builtin_diff_edit_context_methods(),
             commit_or_change_id_methods: builtin_commit_or_change_id_methods(),
             shortest_id_prefix_methods: builtin_shortest_id_prefix_methods(),
+            operation_methods: operation_templater::builtin_operation_methods(),
+            operation_id_methods: operation_templater::builtin_operation_id_methods(),
         }
     }
 
@@ -349,8 +417,11 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             core: CoreTemplateBuildFnTable::empty(),
             commit_methods: HashMap::new(),
             ref_name_methods: HashMap::new(),
+            diff_edit_context_methods: HashMap::new(),
             commit_or_change_id_methods: HashMap::new(),
             shortest_id_prefix_methods: HashMap::new(),
+            operation_methods: HashMap::new(),
+            operation_id_methods: HashMap::new(),
         }
     }
 
@@ -359,13 +430,20 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             core,
             commit_methods,
             ref_name_methods,
+            diff_edit_context_methods,
             commit_or_change_id_methods,
             shortest_id_prefix_methods,
+            operation_methods,
+            operation_id_methods,
         } = extension;
 
         self.core.merge(core);
         merge_fn_map(&mut self.commit_methods, commit_methods);
         merge_fn_map(&mut self.ref_name_methods, ref_name_methods);
+        merge_fn_map(
+            &mut self.diff_edit_context_methods,
+            diff_edit_context_methods,
+        );
         merge_fn_map(
             &mut self.commit_or_change_id_methods,
             commit_or_change_id_methods,
@@ -374,6 +452,8 @@ impl<'repo> CommitTemplateBuildFnTable<'repo> {
             &mut self.shortest_id_prefix_methods,
             shortest_id_prefix_methods,
         );
+        merge_fn_map(&mut self.operation_methods, operation_methods);
+        merge_fn_map(&mut self.operation_id_methods, operation_id_methods);
     }
 }
 
@@ -383,6 +463,8 @@ pub struct CommitKeywordCache {
     branches_index: OnceCell<Rc<RefNamesIndex>>,
     tags_index: OnceCell<Rc<RefNamesIndex>>,
     git_refs_index: OnceCell<Rc<RefNamesIndex>>,
+    notes_index: OnceCell<Rc<HashMap<CommitId, String>>>,
+    creating_operations: Rc<CreatingOperationsIndex>,
 }
 
 impl CommitKeywordCache {
@@ -400,6 +482,15 @@ impl CommitKeywordCache {
         self.git_refs_index
             .get_or_init(|| Rc::new(build_ref_names_index(repo.view().git_refs())))
     }
+
+    pub fn notes_index(&self, repo: &dyn Repo) -> &Rc<HashMap<CommitId, String>> {
+        self.notes_index
+            .get_or_init(|| Rc::new(build_notes_index(repo)))
+    }
+
+    pub fn creating_operations(&self) -> &Rc<CreatingOperationsIndex> {
+        &self.creating_operations
+    }
 }
 
 fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Commit> {
@@ -548,6 +639,16 @@ fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Comm
             Ok(L::wrap_ref_name_list(out_property))
         },
     );
+    map.insert("note", |language, _build_ctx, self_property, function| {
+        template_parser::expect_no_arguments(function)?;
+        let index = language.keyword_cache.notes_index(language.repo).clone();
+        let out_property = self_property.map(move |commit| {
+            index
+                .get(commit.id())
+                .map_or_else(String::new, text_util::complete_newline)
+        });
+        Ok(L::wrap_string(out_property))
+    });
     map.insert(
         "git_head",
         |language, _build_ctx, self_property, function| {
@@ -579,6 +680,27 @@ fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Comm
         });
         Ok(L::wrap_boolean(out_property))
     });
+    map.insert(
+        "successors",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.repo;
+            let out_property = self_property.and_then(|commit| {
+                let visible_ids = repo
+                    .resolve_change_id(commit.change_id())
+                    .unwrap_or_default();
+                if visible_ids.contains(commit.id()) {
+                    return Ok(vec![]);
+                }
+                let commits: Vec<_> = visible_ids
+                    .iter()
+                    .map(|id| repo.store().get_commit(id))
+                    .try_collect()?;
+                Ok(commits)
+            });
+            Ok(L::wrap_commit_list(out_property))
+        },
+    );
     map.insert(
         "immutable",
         |language, _build_ctx, self_property, function| {
@@ -615,6 +737,20 @@ fn builtin_commit_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Comm
         let out_property = self_property.map(|commit| commit.id() == repo.store().root_commit_id());
         Ok(L::wrap_boolean(out_property))
     });
+    map.insert(
+        "operation",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.base_repo;
+            let index = language.keyword_cache.creating_operations().clone();
+            let out_property = self_property.and_then(move |commit| {
+                index.find(repo, commit.id())?.ok_or_else(|| {
+                    TemplatePropertyError("No operation found for the commit".into())
+                })
+            });
+            Ok(L::wrap_operation(out_property))
+        },
+    );
     map
 }
 
@@ -659,14 +795,85 @@ pub struct RefName {
     name: String,
     /// Remote name if this is a remote or Git-tracking ref.
     remote: Option<String>,
-    /// Ref target has conflicts.
-    conflict: bool,
+    /// Target commit ids.
+    target: RefTarget,
+    /// Local ref metadata which tracks this remote ref.
+    tracking_ref: Option<TrackingRef>,
     /// Local ref is synchronized with all tracking remotes, or tracking remote
     /// ref is synchronized with the local.
     synced: bool,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TrackingRef {
+    /// Local ref target which tracks the other remote ref.
+    target: RefTarget,
+}
+
 impl RefName {
+    // RefName is generic over local/remote branches and tags. Functions below
+    // are defined to help construct representations of branches.
+
+    /// Creates local ref representation which might track some of the
+    /// `remote_refs`.
+    pub fn local<'a>(
+        name: impl Into<String>,
+        target: RefTarget,
+        remote_refs: impl IntoIterator<Item = &'a RemoteRef>,
+    ) -> Self {
+        let synced = remote_refs
+            .into_iter()
+            .all(|remote_ref| !remote_ref.is_tracking() || remote_ref.target == target);
+        RefName {
+            name: name.into(),
+            remote: None,
+            target,
+            tracking_ref: None,
+            synced,
+        }
+    }
+
+    /// Creates local ref representation which doesn't track any remote refs.
+    pub fn local_only(name: impl Into<String>, target: RefTarget) -> Self {
+        Self::local(name, target, [])
+    }
+
+    /// Creates remote ref representation which might be tracked by a local
+    /// ref pointing to the `local_target`.
+    pub fn remote(
+        name: impl Into<String>,
+        remote_name: impl Into<String>,
+        remote_ref: RemoteRef,
+        local_target: &RefTarget,
+    ) -> Self {
+        let synced = remote_ref.is_tracking() && remote_ref.target == *local_target;
+        let tracking_ref = remote_ref.is_tracking().then(|| TrackingRef {
+            target: local_target.clone(),
+        });
+        RefName {
+            name: name.into(),
+            remote: Some(remote_name.into()),
+            target: remote_ref.target,
+            tracking_ref,
+            synced,
+        }
+    }
+
+    /// Creates remote ref representation which isn't tracked by a local ref.
+    pub fn remote_only(
+        name: impl Into<String>,
+        remote_name: impl Into<String>,
+        target: RefTarget,
+    ) -> Self {
+        RefName {
+            name: name.into(),
+            remote: Some(remote_name.into()),
+            target,
+            tracking_ref: None,
+            synced: false, // has no local counterpart
+        }
+    }
+
     fn is_local(&self) -> bool {
         self.remote.is_none()
     }
@@ -674,6 +881,49 @@ impl RefName {
     fn is_remote(&self) -> bool {
         self.remote.is_some()
     }
+
+    fn is_present(&self) -> bool {
+        self.target.is_present()
+    }
+
+    /// Whether the ref target has conflicts.
+    fn has_conflict(&self) -> bool {
+        self.target.has_conflict()
+    }
+
+    /// Returns true if this ref is tracked by a local ref. The local ref might
+    /// have been deleted (but not pushed yet.)
+    fn is_tracked(&self) -> bool {
+        self.tracking_ref.is_some()
+    }
+
+    /// Returns true if this ref is tracked by a local ref, and if the local
+    /// ref is present.
+    fn is_tracking_present(&self) -> bool {
+        self.tracking_ref
+            .as_ref()
+            .is_some_and(|tracking| tracking.target.is_present())
+    }
+
+    /// Number of commits ahead of the tracking local ref.
+    fn tracking_ahead_count(&self, repo: &dyn Repo) -> Result<SizeHint, TemplatePropertyError> {
+        let Some(tracking) = &self.tracking_ref else {
+            return Err(TemplatePropertyError("Not a tracked remote ref".into()));
+        };
+        let self_ids = self.target.added_ids().cloned().collect_vec();
+        let other_ids = tracking.target.added_ids().cloned().collect_vec();
+        Ok(revset::walk_revs(repo, &self_ids, &other_ids)?.count_estimate())
+    }
+
+    /// Number of commits behind of the tracking local ref.
+    fn tracking_behind_count(&self, repo: &dyn Repo) -> Result<SizeHint, TemplatePropertyError> {
+        let Some(tracking) = &self.tracking_ref else {
+            return Err(TemplatePropertyError("Not a tracked remote ref".into()));
+        };
+        let self_ids = self.target.added_ids().cloned().collect_vec();
+        let other_ids = tracking.target.added_ids().cloned().collect_vec();
+        Ok(revset::walk_revs(repo, &other_ids, &self_ids)?.count_estimate())
+    }
 }
 
 impl Template for RefName {
@@ -685,7 +935,7 @@ impl Template for RefName {
         }
         // Don't show both conflict and unsynced sigils as conflicted ref wouldn't
         // be pushed.
-        if self.conflict {
+        if self.has_conflict() {
             write!(formatter, "??")?;
         } else if self.is_local() && !self.synced {
             write!(formatter, "*")?;
@@ -718,6 +968,139 @@ fn builtin_ref_name_methods<'repo>() -> CommitTemplateBuildMethodFnMap<'repo, Re
             Ok(L::wrap_string(out_property))
         },
     );
+    map.insert(
+        "present",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|ref_name| ref_name.is_present());
+            Ok(L::wrap_boolean(out_property))
+        },
+    );
+    map.insert(
+        "conflict",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|ref_name| ref_name.has_conflict());
+            Ok(L::wrap_boolean(out_property))
+        },
+    );
+    map.insert(
+        "normal_target",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.repo;
+            let out_property = self_property.and_then(|ref_name| {
+                let maybe_id = ref_name.target.as_normal();
+                Ok(maybe_id.map(|id| repo.store().get_commit(id)).transpose()?)
+            });
+            Ok(L::wrap_commit_opt(out_property))
+        },
+    );
+    map.insert(
+        "removed_targets",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.repo;
+            let out_property = self_property.and_then(|ref_name| {
+                let ids = ref_name.target.removed_ids();
+                Ok(ids.map(|id| repo.store().get_commit(id)).try_collect()?)
+            });
+            Ok(L::wrap_commit_list(out_property))
+        },
+    );
+    map.insert(
+        "added_targets",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.repo;
+            let out_property = self_property.and_then(|ref_name| {
+                let ids = ref_name.target.added_ids();
+                Ok(ids.map(|id| repo.store().get_commit(id)).try_collect()?)
+            });
+            Ok(L::wrap_commit_list(out_property))
+        },
+    );
+    map.insert(
+        "tracked",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|ref_name| ref_name.is_tracked());
+            Ok(L::wrap_boolean(out_property))
+        },
+    );
+    map.insert(
+        "tracking_present",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|ref_name| ref_name.is_tracking_present());
+            Ok(L::wrap_boolean(out_property))
+        },
+    );
+    map.insert(
+        "tracking_ahead_count",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.repo;
+            let out_property =
+                self_property.and_then(|ref_name| ref_name.tracking_ahead_count(repo));
+            Ok(L::wrap_size_hint(out_property))
+        },
+    );
+    map.insert(
+        "tracking_behind_count",
+        |language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let repo = language.repo;
+            let out_property =
+                self_property.and_then(|ref_name| ref_name.tracking_behind_count(repo));
+            Ok(L::wrap_size_hint(out_property))
+        },
+    );
+    map
+}
+
+/// Changes being edited in a diff editor, such as by `jj split` or `jj squash`.
+#[derive(Clone, Debug)]
+pub struct DiffEditContext {
+    /// Commit whose changes are edited.
+    pub source: Commit,
+    /// Commit the selected changes are moved into, if any.
+    pub destination: Option<Commit>,
+    /// Paths of the changes shown in the diff editor, relative to the current
+    /// directory.
+    pub selected_paths: Vec<String>,
+}
+
+fn builtin_diff_edit_context_methods<'repo>(
+) -> CommitTemplateBuildMethodFnMap<'repo, DiffEditContext> {
+    type L<'repo> = CommitTemplateLanguage<'repo>;
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = CommitTemplateBuildMethodFnMap::<DiffEditContext>::new();
+    map.insert(
+        "source",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|context| context.source);
+            Ok(L::wrap_commit(out_property))
+        },
+    );
+    map.insert(
+        "destination",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|context| context.destination);
+            Ok(L::wrap_commit_opt(out_property))
+        },
+    );
+    map.insert(
+        "selected_paths",
+        |_language, _build_ctx, self_property, function| {
+            template_parser::expect_no_arguments(function)?;
+            let out_property = self_property.map(|context| context.selected_paths);
+            Ok(L::wrap_string_list(out_property))
+        },
+    );
     map
 }
 
@@ -746,29 +1129,34 @@ impl RefNamesIndex {
     }
 }
 
+/// Reads the Git notes of the commits. The notes of repos which aren't backed
+/// by Git, or whose notes can't be read, are empty.
+fn build_notes_index(repo: &dyn Repo) -> HashMap<CommitId, String> {
+    let Some(git_backend) = repo.store().backend_impl().downcast_ref::<GitBackend>() else {
+        return HashMap::new();
+    };
+    git_backend
+        .open_git_repo()
+        .and_then(|git_repo| git::read_all_notes(&git_repo))
+        .unwrap_or_default()
+}
+
 fn build_branches_index(repo: &dyn Repo) -> RefNamesIndex {
     let mut index = RefNamesIndex::default();
     for (branch_name, branch_target) in repo.view().branches() {
         let local_target = branch_target.local_target;
         let remote_refs = branch_target.remote_refs;
         if local_target.is_present() {
-            let ref_name = RefName {
-                name: branch_name.to_owned(),
-                remote: None,
-                conflict: local_target.has_conflict(),
-                synced: remote_refs.iter().all(|&(_, remote_ref)| {
-                    !remote_ref.is_tracking() || remote_ref.target == *local_target
-                }),
-            };
+            let ref_name = RefName::local(
+                branch_name,
+                local_target.clone(),
+                remote_refs.iter().map(|&(_, remote_ref)| remote_ref),
+            );
             index.insert(local_target.added_ids(), ref_name);
         }
         for &(remote_name, remote_ref) in &remote_refs {
-            let ref_name = RefName {
-                name: branch_name.to_owned(),
-                remote: Some(remote_name.to_owned()),
-                conflict: remote_ref.target.has_conflict(),
-                synced: remote_ref.is_tracking() && remote_ref.target == *local_target,
-            };
+            let ref_name =
+                RefName::remote(branch_name, remote_name, remote_ref.clone(), local_target);
             index.insert(remote_ref.target.added_ids(), ref_name);
         }
     }
@@ -780,12 +1168,7 @@ fn build_ref_names_index<'a>(
 ) -> RefNamesIndex {
     let mut index = RefNamesIndex::default();
     for (name, target) in ref_pairs {
-        let ref_name = RefName {
-            name: name.to_owned(),
-            remote: None,
-            conflict: target.has_conflict(),
-            synced: true, // has no tracking remotes
-        };
+        let ref_name = RefName::local_only(name, target.clone());
         index.insert(target.added_ids(), ref_name);
     }
     index
@@ -793,14 +1176,10 @@ fn build_ref_names_index<'a>(
 
 fn extract_git_head(repo: &dyn Repo, commit: &Commit) -> Option<RefName> {
     let target = repo.view().git_head();
-    target.added_ids().contains(commit.id()).then(|| {
-        RefName {
-            name: "HEAD".to_owned(),
-            remote: Some(git::REMOTE_NAME_FOR_LOCAL_GIT_REPO.to_owned()),
-            conflict: target.has_conflict(),
-            synced: false, // has no local counterpart
-        }
-    })
+    target
+        .added_ids()
+        .contains(commit.id())
+        .then(|| RefName::remote_only("HEAD", git::REMOTE_NAME_FOR_LOCAL_GIT_REPO, target.clone()))
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -942,3 +1321,102 @@ fn builtin_shortest_id_prefix_methods<'repo>(
     });
     map
 }
+
+/// Maximum number of operations walked back from the current operation when
+/// looking up the operation that created a commit. Each walked operation needs
+/// its index loaded, so commits created earlier are reported as not found.
+const MAX_CREATING_OPERATION_SEARCH_DEPTH: usize = 100;
+
+/// Cache of operations that created commits.
+#[derive(Default)]
+pub struct CreatingOperationsIndex {
+    operations: RefCell<HashMap<CommitId, Option<Operation>>>,
+    // Parents and index of the visited operations, loaded once and shared by
+    // all the lookups.
+    op_entries: RefCell<HashMap<OperationId, Rc<OperationEntry>>>,
+}
+
+struct OperationEntry {
+    parents: Vec<Operation>,
+    index: Box<dyn ReadonlyIndex>,
+}
+
+impl fmt::Debug for CreatingOperationsIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreatingOperationsIndex")
+            .field("operations", &self.operations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CreatingOperationsIndex {
+    /// Looks up the operation which first added the `commit_id` to the index.
+    ///
+    /// The operation history is walked back from the `repo` operation, only
+    /// following the operations whose index contains the commit. At most
+    /// `MAX_CREATING_OPERATION_SEARCH_DEPTH` operations are visited.
+    pub fn find(
+        &self,
+        repo: &ReadonlyRepo,
+        commit_id: &CommitId,
+    ) -> Result<Option<Operation>, TemplatePropertyError> {
+        if let Some(op) = self.operations.borrow().get(commit_id) {
+            return Ok(op.clone());
+        }
+        let op = self.find_creating_operation(repo, commit_id)?;
+        self.operations
+            .borrow_mut()
+            .insert(commit_id.clone(), op.clone());
+        Ok(op)
+    }
+
+    fn find_creating_operation(
+        &self,
+        repo: &ReadonlyRepo,
+        commit_id: &CommitId,
+    ) -> Result<Option<Operation>, TemplatePropertyError> {
+        let head_entry = self.op_entry(repo, repo.operation())?;
+        if !head_entry.index.as_index().has_id(commit_id) {
+            return Ok(None);
+        }
+        let mut visited = HashSet::new();
+        let mut pending = vec![(repo.operation().clone(), head_entry)];
+        while let Some((op, entry)) = pending.pop() {
+            if visited.len() > MAX_CREATING_OPERATION_SEARCH_DEPTH {
+                return Ok(None);
+            }
+            let mut found_in_parent = false;
+            for parent in &entry.parents {
+                let parent_entry = self.op_entry(repo, parent)?;
+                if parent_entry.index.as_index().has_id(commit_id) {
+                    found_in_parent = true;
+                    if visited.insert(parent.id().clone()) {
+                        pending.push((parent.clone(), parent_entry));
+                    }
+                }
+            }
+            if !found_in_parent {
+                return Ok(Some(op));
+            }
+        }
+        Ok(None)
+    }
+
+    fn op_entry(
+        &self,
+        repo: &ReadonlyRepo,
+        op: &Operation,
+    ) -> Result<Rc<OperationEntry>, TemplatePropertyError> {
+        if let Some(entry) = self.op_entries.borrow().get(op.id()) {
+            return Ok(entry.clone());
+        }
+        let entry = Rc::new(OperationEntry {
+            parents: op.parents().try_collect()?,
+            index: repo.index_store().get_index_at_op(op, repo.store())?,
+        });
+        self.op_entries
+            .borrow_mut()
+            .insert(op.id().clone(), entry.clone());
+        Ok(entry)
+    }
+}
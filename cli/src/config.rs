@@ -20,7 +20,9 @@ use std::{env, fmt};
 
 use config::Source;
 use itertools::Itertools;
+use jj_lib::git_backend::canonicalize_git_repo_path;
 use jj_lib::settings::ConfigResultExt as _;
+use once_cell::sync::Lazy;
 use thiserror::Error;
 use tracing::instrument;
 
@@ -32,15 +34,28 @@ pub enum ConfigError {
     AmbiguousSource(PathBuf, PathBuf),
     #[error(transparent)]
     ConfigCreateError(#[from] std::io::Error),
+    #[error("Invalid config option in environment variable {name}")]
+    EnvVar {
+        name: String,
+        source: ConfigSchemaError,
+    },
+    #[error("Invalid path pattern {pattern:?} in config include")]
+    IncludePattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ConfigSource {
     Default,
     Env,
+    /// Options translated from the config of a colocated Git repo.
+    Git,
     // TODO: Track explicit file paths, especially for when user config is a dir.
     User,
     Repo,
+    Workspace,
     CommandArg,
 }
 
@@ -57,18 +72,25 @@ pub struct AnnotatedValue {
 /// Sources from the lowest precedence:
 /// 1. Default
 /// 2. Base environment variables
-/// 3. [User config](https://github.com/martinvonz/jj/blob/main/docs/config.md#configuration)
-/// 4. Repo config `.jj/repo/config.toml`
-/// 5. TODO: Workspace config `.jj/config.toml`
-/// 6. Override environment variables
-/// 7. Command-line arguments `--config-toml`
+/// 3. Git config of a colocated repo, for the options `jj` understands
+/// 4. [User config](https://github.com/martinvonz/jj/blob/main/docs/config.md#configuration)
+/// 5. Files included by the user config for the current workspace
+/// 6. Repo config `.jj/repo/config.toml`
+/// 7. Workspace config `.jj/workspace-config.toml`
+/// 8. Override environment variables, including `JJ_CONFIG_<NAME>`
+/// 9. Command-line arguments `--config-toml` and `--config`
 #[derive(Clone, Debug)]
 pub struct LayeredConfigs {
     default: config::Config,
     env_base: config::Config,
+    git: Option<config::Config>,
     user: Option<config::Config>,
+    user_includes: Vec<ConditionalInclude>,
+    user_included: Option<config::Config>,
     repo: Option<config::Config>,
+    workspace: Option<config::Config>,
     env_overrides: config::Config,
+    env_config_vars: Option<config::Config>,
     arg_overrides: Option<config::Config>,
 }
 
@@ -78,18 +100,81 @@ impl LayeredConfigs {
         LayeredConfigs {
             default,
             env_base: env_base(),
+            git: None,
             user: None,
+            user_includes: vec![],
+            user_included: None,
             repo: None,
+            workspace: None,
             env_overrides: env_overrides(),
+            env_config_vars: None,
             arg_overrides: None,
         }
     }
 
     #[instrument]
     pub fn read_user_config(&mut self) -> Result<(), ConfigError> {
-        self.user = existing_config_path()?
-            .map(|path| read_config_path(&path))
-            .transpose()?;
+        let Some(path) = existing_config_path()? else {
+            self.user = None;
+            self.user_includes = vec![];
+            return Ok(());
+        };
+        let config = read_config_path(&path)?;
+        self.user_includes = read_conditional_includes(&config, &path)?;
+        self.user = Some(config);
+        Ok(())
+    }
+
+    /// Reads the files which the user config includes for the workspace at
+    /// `workspace_root`.
+    #[instrument]
+    pub fn read_user_includes(&mut self, workspace_root: &Path) -> Result<(), ConfigError> {
+        // The root may be given relative to the current directory by -R.
+        let workspace_root = workspace_root.canonicalize()?;
+        let paths = self
+            .user_includes
+            .iter()
+            .filter(|include| include.matches(&workspace_root))
+            .map(|include| include.path.as_path())
+            .collect_vec();
+        self.user_included = Some(read_config_files(&paths)?);
+        Ok(())
+    }
+
+    /// Reads the options which have a `jj` equivalent from the local Git config
+    /// if the workspace at `workspace_root` is colocated with the Git repo
+    /// backing the repo at `repo_path`.
+    #[instrument]
+    pub fn read_git_config(
+        &mut self,
+        workspace_root: &Path,
+        repo_path: &Path,
+    ) -> Result<(), ConfigError> {
+        self.git = None;
+        // Check the target of the Git backend without loading the repo, so
+        // that non-colocated repos don't pay for opening the Git repo.
+        let store_path = repo_path.join("store");
+        let Ok(git_target) = std::fs::read_to_string(store_path.join("git_target")) else {
+            return Ok(());
+        };
+        let (Ok(git_target), Ok(git_dir)) = (
+            canonicalize_git_repo_path(&store_path.join(git_target)),
+            canonicalize_git_repo_path(&workspace_root.join(".git")),
+        ) else {
+            return Ok(());
+        };
+        if git_target != git_dir {
+            return Ok(());
+        }
+        // An unreadable Git repo will be reported when the repo is loaded.
+        // Only the repo's own config is read, not the global or system config.
+        let Ok(git_config) = git2::Repository::open(git_dir)
+            .and_then(|repo| repo.config())
+            .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+        else {
+            return Ok(());
+        };
+        self.git = Some(config_from_git_config(&git_config)?);
         Ok(())
     }
 
@@ -99,6 +184,36 @@ impl LayeredConfigs {
         Ok(())
     }
 
+    #[instrument]
+    pub fn read_workspace_config(&mut self, workspace_root: &Path) -> Result<(), ConfigError> {
+        self.workspace = Some(read_config_file(&workspace_config_path(workspace_root))?);
+        Ok(())
+    }
+
+    /// Reads the config options set by `JJ_CONFIG_<NAME>` environment
+    /// variables.
+    pub fn read_env_config_vars(&mut self) -> Result<(), ConfigError> {
+        let mut builder = config::Config::builder();
+        for (var, value) in env::vars_os() {
+            let (Some(var), Some(value)) = (var.to_str(), value.to_str()) else {
+                continue;
+            };
+            let Some(name) = env_var_config_name(var) else {
+                continue;
+            };
+            let value = parse_config_value(&name, value).map_err(|source| ConfigError::EnvVar {
+                name: var.to_owned(),
+                source,
+            })?;
+            builder = builder.add_source(config::File::from_str(
+                &config_toml_for_value(&name, &value),
+                config::FileFormat::Toml,
+            ));
+        }
+        self.env_config_vars = Some(builder.build()?);
+        Ok(())
+    }
+
     pub fn parse_config_args(&mut self, toml_strs: &[String]) -> Result<(), ConfigError> {
         let config = toml_strs
             .iter()
@@ -126,9 +241,13 @@ impl LayeredConfigs {
         let config_sources = [
             (ConfigSource::Default, Some(&self.default)),
             (ConfigSource::Env, Some(&self.env_base)),
+            (ConfigSource::Git, self.git.as_ref()),
             (ConfigSource::User, self.user.as_ref()),
+            (ConfigSource::User, self.user_included.as_ref()),
             (ConfigSource::Repo, self.repo.as_ref()),
+            (ConfigSource::Workspace, self.workspace.as_ref()),
             (ConfigSource::Env, Some(&self.env_overrides)),
+            (ConfigSource::Env, self.env_config_vars.as_ref()),
             (ConfigSource::CommandArg, self.arg_overrides.as_ref()),
         ];
         config_sources
@@ -335,6 +454,41 @@ fn env_base() -> config::Config {
     builder.build().unwrap()
 }
 
+/// Translates the Git config options which have a `jj` equivalent.
+fn config_from_git_config(git_config: &git2::Config) -> Result<config::Config, ConfigError> {
+    let get_string = |name: &str| git_config.get_string(name).ok();
+    let mut builder = config::Config::builder();
+    for (git_name, name) in [
+        ("user.name", "user.name"),
+        ("user.email", "user.email"),
+        ("user.signingKey", "signing.key"),
+        ("gpg.program", "signing.backends.gpg.program"),
+        ("gpg.ssh.program", "signing.backends.ssh.program"),
+        (
+            "gpg.ssh.allowedSignersFile",
+            "signing.backends.ssh.allowed-signers",
+        ),
+    ] {
+        if let Some(value) = get_string(git_name) {
+            builder = builder.set_override(name, value)?;
+        }
+    }
+    let sign_all = git_config.get_bool("commit.gpgSign").unwrap_or(false);
+    if sign_all || get_string("user.signingKey").is_some() {
+        // Git defaults to OpenPGP. X.509 isn't supported by jj.
+        let backend = match get_string("gpg.format").as_deref() {
+            None | Some("openpgp") => Some("gpg"),
+            Some("ssh") => Some("ssh"),
+            Some(_) => None,
+        };
+        if let Some(backend) = backend {
+            builder = builder.set_override("signing.backend", backend)?;
+            builder = builder.set_override("signing.sign-all", sign_all)?;
+        }
+    }
+    Ok(builder.build()?)
+}
+
 pub fn default_config() -> config::Config {
     // Syntax error in default config isn't a user error. That's why defaults are
     // loaded by separate builder.
@@ -372,6 +526,11 @@ fn env_overrides() -> config::Config {
             .set_override("debug.commit-timestamp", value)
             .unwrap();
     }
+    if let Ok(value) = env::var("JJ_COMMIT_TIMESTAMP") {
+        builder = builder
+            .set_override("debug.commit-timestamp", value)
+            .unwrap();
+    }
     if let Ok(value) = env::var("JJ_RANDOMNESS_SEED") {
         builder = builder
             .set_override("debug.randomness-seed", value)
@@ -394,6 +553,11 @@ fn env_overrides() -> config::Config {
     builder.build().unwrap()
 }
 
+/// Returns the path to the config file of the workspace at `workspace_root`.
+pub fn workspace_config_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".jj").join("workspace-config.toml")
+}
+
 fn read_config_file(path: &Path) -> Result<config::Config, config::ConfigError> {
     config::Config::builder()
         .add_source(
@@ -420,7 +584,10 @@ fn read_config_path(config_path: &Path) -> Result<config::Config, config::Config
     } else {
         files.push(config_path.to_owned());
     }
+    read_config_files(&files)
+}
 
+fn read_config_files(files: &[impl AsRef<Path>]) -> Result<config::Config, config::ConfigError> {
     files
         .iter()
         .fold(config::Config::builder(), |builder, path| {
@@ -434,6 +601,281 @@ fn read_config_path(config_path: &Path) -> Result<config::Config, config::Config
         .build()
 }
 
+/// Returns the name of the config option set by the environment variable
+/// `var`, if any.
+///
+/// The name after the `JJ_CONFIG_` prefix is lowercased, and double and single
+/// underscores separate tables and words respectively. For example,
+/// `JJ_CONFIG_UI__DIFF_EDITOR` sets `ui.diff-editor`.
+fn env_var_config_name(var: &str) -> Option<String> {
+    let name = var.strip_prefix("JJ_CONFIG_")?;
+    if name.is_empty() {
+        return None;
+    }
+    let name = name
+        .to_ascii_lowercase()
+        .split("__")
+        .map(|part| part.replace('_', "-"))
+        .join(".");
+    Some(name)
+}
+
+/// Returns a TOML document setting the option `name` to `value`.
+pub fn config_toml_for_value(name: &str, value: &toml_edit::Value) -> String {
+    let key = name
+        .split('.')
+        .map(|part| toml_edit::Key::new(part).display_repr().into_owned())
+        .join(".");
+    format!("{key} = {value}")
+}
+
+/// Config file included by the user config in workspaces at matching paths.
+///
+/// ```toml
+/// [[include]]
+/// if-path = "~/work/**"
+/// path = "work.toml"
+/// ```
+#[derive(Clone, Debug)]
+struct ConditionalInclude {
+    path: PathBuf,
+    if_path: glob::Pattern,
+}
+
+impl ConditionalInclude {
+    fn matches(&self, workspace_root: &Path) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.if_path.matches_path_with(workspace_root, options)
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ConditionalIncludeConfig {
+    path: String,
+    if_path: String,
+}
+
+/// Reads the `include` tables of the user config read from `config_path`.
+///
+/// Relative include paths are resolved from the directory of the user config,
+/// and a leading `~/` in paths and patterns refers to the home directory.
+fn read_conditional_includes(
+    config: &config::Config,
+    config_path: &Path,
+) -> Result<Vec<ConditionalInclude>, ConfigError> {
+    let Some(includes) = config
+        .get::<Vec<ConditionalIncludeConfig>>("include")
+        .optional()?
+    else {
+        return Ok(vec![]);
+    };
+    let base_dir = if config_path.is_dir() {
+        config_path
+    } else {
+        config_path.parent().unwrap_or(Path::new(""))
+    };
+    includes
+        .into_iter()
+        .map(|include| {
+            // Like Git's "gitdir:" conditions, a pattern ending with a slash
+            // matches everything under the directory.
+            let mut pattern = expand_home_dir(&include.if_path)
+                .to_string_lossy()
+                .into_owned();
+            if pattern.ends_with(std::path::is_separator) {
+                pattern.push_str("**");
+            }
+            let if_path =
+                glob::Pattern::new(&pattern).map_err(|source| ConfigError::IncludePattern {
+                    pattern: include.if_path,
+                    source,
+                })?;
+            Ok(ConditionalInclude {
+                path: base_dir.join(expand_home_dir(&include.path)),
+                if_path,
+            })
+        })
+        .try_collect()
+}
+
+fn expand_home_dir(path: &str) -> PathBuf {
+    if let Some(remainder) = path.strip_prefix("~/") {
+        if let Some(home_dir) = dirs::home_dir() {
+            return home_dir.join(remainder);
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// JSON schema of the config options known to jj.
+pub const CONFIG_SCHEMA: &str = include_str!("config-schema.json");
+
+static PARSED_CONFIG_SCHEMA: Lazy<serde_json::Value> =
+    Lazy::new(|| serde_json::from_str(CONFIG_SCHEMA).expect("config schema should be valid JSON"));
+
+/// Error from checking a config value against the config schema.
+#[derive(Debug, Error)]
+pub enum ConfigSchemaError {
+    #[error("Unknown config option {name}")]
+    UnknownOption {
+        name: String,
+        candidates: Vec<String>,
+    },
+    #[error("Invalid value for {name}: expected {}", .expected.join(" or "))]
+    InvalidType { name: String, expected: Vec<String> },
+    #[error("Invalid value for {name}: expected one of {}", .allowed.join(", "))]
+    InvalidValue { name: String, allowed: Vec<String> },
+}
+
+/// Parses `value_str` as the value of the config option `name`.
+///
+/// A value that isn't valid TOML is interpreted as a string, as is any value of
+/// an option that the schema only allows to be a string. Options that the
+/// schema describes are checked against it, but options in tables unknown to
+/// the schema are accepted as is.
+pub fn parse_config_value(
+    name: &str,
+    value_str: &str,
+) -> Result<toml_edit::Value, ConfigSchemaError> {
+    let parsed = value_str.parse::<toml_edit::Value>().ok();
+    let Some(schema) = find_option_schema(&PARSED_CONFIG_SCHEMA, name)? else {
+        return Ok(parsed.unwrap_or_else(|| value_str.into()));
+    };
+    let types = schema_types(schema);
+    let accepts = |ty: &str| types.as_ref().map_or(true, |types| types.contains(&ty));
+    let value = match parsed {
+        Some(value) if accepts(toml_type_name(&value)) => value,
+        Some(value @ toml_edit::Value::Integer(_)) if accepts("number") => value,
+        _ if accepts("string") => value_str.into(),
+        _ => {
+            return Err(ConfigSchemaError::InvalidType {
+                name: name.to_owned(),
+                expected: types.unwrap().iter().map(|ty| ty.to_string()).collect(),
+            });
+        }
+    };
+    if let Some(allowed) = schema.get("enum").and_then(|allowed| allowed.as_array()) {
+        if !allowed
+            .iter()
+            .any(|allowed| toml_value_eq_json(&value, allowed))
+        {
+            return Err(ConfigSchemaError::InvalidValue {
+                name: name.to_owned(),
+                allowed: allowed.iter().map(|allowed| allowed.to_string()).collect(),
+            });
+        }
+    }
+    Ok(value)
+}
+
+/// Looks up the schema of the option `name`, or returns `None` if the schema
+/// doesn't describe it.
+fn find_option_schema<'a>(
+    root: &'a serde_json::Value,
+    name: &str,
+) -> Result<Option<&'a serde_json::Value>, ConfigSchemaError> {
+    let mut schema = root;
+    for (depth, key) in name.split('.').enumerate() {
+        let properties = schema
+            .get("properties")
+            .and_then(|properties| properties.as_object());
+        if let Some(property) = properties.and_then(|properties| properties.get(key)) {
+            schema = property;
+            continue;
+        }
+        match schema.get("additionalProperties") {
+            Some(serde_json::Value::Bool(true)) => return Ok(None),
+            Some(property @ serde_json::Value::Object(_)) => {
+                schema = property;
+                continue;
+            }
+            Some(_) | None => {}
+        }
+        // Values whose structure isn't described are accepted as is.
+        let Some(properties) = properties else {
+            return Ok(None);
+        };
+        // Custom top-level tables are allowed unless they look like a typo of
+        // a known one.
+        let threshold = if depth == 0 { 0.85 } else { 0.7 };
+        let candidates = properties
+            .keys()
+            .filter(|candidate| strsim::jaro(key, candidate) > threshold)
+            .cloned()
+            .collect_vec();
+        if depth == 0 && candidates.is_empty() {
+            return Ok(None);
+        }
+        return Err(ConfigSchemaError::UnknownOption {
+            name: name.to_owned(),
+            candidates,
+        });
+    }
+    Ok(Some(schema))
+}
+
+/// Returns the JSON types allowed by the `schema`, or `None` if it doesn't
+/// restrict the type.
+fn schema_types(schema: &serde_json::Value) -> Option<Vec<&str>> {
+    let alternatives = schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(|alternatives| alternatives.as_array());
+    let mut types = match (schema.get("type"), alternatives) {
+        (Some(ty), _) => schema_type_names(ty),
+        (None, Some(_)) => vec![],
+        (None, None) => return None,
+    };
+    for alternative in alternatives.into_iter().flatten() {
+        types.extend(schema_type_names(alternative.get("type")?));
+    }
+    Some(types)
+}
+
+fn schema_type_names(ty: &serde_json::Value) -> Vec<&str> {
+    match ty {
+        serde_json::Value::String(ty) => vec![ty],
+        serde_json::Value::Array(types) => types.iter().filter_map(|ty| ty.as_str()).collect(),
+        _ => vec![],
+    }
+}
+
+/// Returns true if the TOML `value` is of the same type as and equal to the
+/// JSON `json_value`.
+fn toml_value_eq_json(value: &toml_edit::Value, json_value: &serde_json::Value) -> bool {
+    match (value, json_value) {
+        (toml_edit::Value::String(value), serde_json::Value::String(json_value)) => {
+            value.value() == json_value
+        }
+        (toml_edit::Value::Integer(value), serde_json::Value::Number(json_value)) => {
+            json_value.as_i64() == Some(*value.value())
+        }
+        (toml_edit::Value::Float(value), serde_json::Value::Number(json_value)) => {
+            json_value.as_f64() == Some(*value.value())
+        }
+        (toml_edit::Value::Boolean(value), serde_json::Value::Bool(json_value)) => {
+            value.value() == json_value
+        }
+        _ => false,
+    }
+}
+
+fn toml_type_name(value: &toml_edit::Value) -> &'static str {
+    match value {
+        toml_edit::Value::String(_) => "string",
+        toml_edit::Value::Integer(_) => "integer",
+        toml_edit::Value::Float(_) => "number",
+        toml_edit::Value::Boolean(_) => "boolean",
+        toml_edit::Value::Datetime(_) => "datetime",
+        toml_edit::Value::Array(_) => "array",
+        toml_edit::Value::InlineTable(_) => "object",
+    }
+}
+
 /// Command name and arguments specified by config.
 #[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize)]
 #[serde(untagged)]
@@ -527,6 +969,7 @@ impl TryFrom<Vec<String>> for NonEmptyCommandArgsVec {
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
     use maplit::hashmap;
 
     use super::*;
@@ -595,15 +1038,115 @@ mod tests {
         assert_eq!(args, ["-nw"].as_ref());
     }
 
+    #[test]
+    fn test_env_var_config_name() {
+        assert_eq!(env_var_config_name("JJ_CONFIG"), None);
+        assert_eq!(env_var_config_name("JJ_CONFIG_"), None);
+        assert_eq!(env_var_config_name("JJ_EDITOR"), None);
+        assert_eq!(
+            env_var_config_name("JJ_CONFIG_UI__DIFF_EDITOR").as_deref(),
+            Some("ui.diff-editor")
+        );
+        assert_eq!(
+            env_var_config_name("JJ_CONFIG_SIGNING__BACKENDS__GPG__PROGRAM").as_deref(),
+            Some("signing.backends.gpg.program")
+        );
+    }
+
+    #[test]
+    fn test_config_toml_for_value() {
+        assert_eq!(
+            config_toml_for_value("ui.color", &"always".into()),
+            r#"ui.color = "always""#
+        );
+        assert_eq!(
+            config_toml_for_value("revset-aliases.foo()", &"@".into()),
+            r#"revset-aliases."foo()" = "@""#
+        );
+    }
+
+    #[test]
+    fn test_parse_config_value() {
+        let parse = |name, value| parse_config_value(name, value).map(|value| value.to_string());
+
+        // Options unknown to the schema
+        assert_eq!(parse("test-key", "42").unwrap(), "42");
+        assert_eq!(parse("test-key", "a + b").unwrap(), r#""a + b""#);
+        assert_eq!(parse("test-table.foo", "true").unwrap(), "true");
+        assert_eq!(
+            parse("ui.pager.command", r#"["less"]"#).unwrap(),
+            r#"["less"]"#
+        );
+
+        // String options
+        assert_eq!(parse("user.name", "42").unwrap(), r#""42""#);
+        assert_eq!(parse("user.name", r#""Foo""#).unwrap(), r#""Foo""#);
+        assert_eq!(
+            parse("ui.editor", r#"["vim", "-n"]"#).unwrap(),
+            r#"["vim", "-n"]"#
+        );
+        assert_eq!(
+            parse("templates.log", "commit_id").unwrap(),
+            r#""commit_id""#
+        );
+        assert_eq!(parse("revset-aliases.foo", "true").unwrap(), r#""true""#);
+
+        // Other types
+        assert_eq!(parse("ui.quiet", "true").unwrap(), "true");
+        assert_matches!(
+            parse_config_value("ui.quiet", "yes"),
+            Err(ConfigSchemaError::InvalidType { .. })
+        );
+        assert_eq!(parse("snapshot.max-new-file-size", "1").unwrap(), "1");
+        assert_eq!(
+            parse("snapshot.max-new-file-size", "1MiB").unwrap(),
+            r#""1MiB""#
+        );
+
+        // Enums
+        assert_eq!(parse("ui.diff.format", "git").unwrap(), r#""git""#);
+        assert_matches!(
+            parse_config_value("ui.diff.format", "gti"),
+            Err(ConfigSchemaError::InvalidValue { .. })
+        );
+        assert_matches!(
+            parse_config_value("core.fsmonitor", "true"),
+            Err(ConfigSchemaError::InvalidValue { .. })
+        );
+        assert_matches!(
+            parse_config_value("ui.color", "42"),
+            Err(ConfigSchemaError::InvalidValue { .. })
+        );
+
+        // Unknown options
+        assert_matches!(
+            parse_config_value("ui.qiet", "true"),
+            Err(ConfigSchemaError::UnknownOption { candidates, .. }) if candidates == ["quiet"]
+        );
+        assert_matches!(
+            parse_config_value("usr.name", "Foo"),
+            Err(ConfigSchemaError::UnknownOption { candidates, .. }) if candidates == ["user"]
+        );
+        assert_matches!(
+            parse_config_value("ui.no-such-option", "true"),
+            Err(ConfigSchemaError::UnknownOption { candidates, .. }) if candidates.is_empty()
+        );
+    }
+
     #[test]
     fn test_layered_configs_resolved_config_values_empty() {
         let empty_config = config::Config::default();
         let layered_configs = LayeredConfigs {
             default: empty_config.to_owned(),
             env_base: empty_config.to_owned(),
+            git: None,
             user: None,
+            user_includes: vec![],
+            user_included: None,
             repo: None,
+            workspace: None,
             env_overrides: empty_config,
+            env_config_vars: None,
             arg_overrides: None,
         };
         assert_eq!(layered_configs.resolved_config_values(&[]).unwrap(), []);
@@ -627,9 +1170,14 @@ mod tests {
         let layered_configs = LayeredConfigs {
             default: empty_config.to_owned(),
             env_base: env_base_config,
+            git: None,
             user: None,
+            user_includes: vec![],
+            user_included: None,
             repo: Some(repo_config),
+            workspace: None,
             env_overrides: empty_config,
+            env_config_vars: None,
             arg_overrides: None,
         };
         // Note: "email" is alphabetized, before "name" from same layer.
@@ -702,9 +1250,14 @@ mod tests {
         let layered_configs = LayeredConfigs {
             default: empty_config.to_owned(),
             env_base: empty_config.to_owned(),
+            git: None,
             user: Some(user_config),
+            user_includes: vec![],
+            user_included: None,
             repo: Some(repo_config),
+            workspace: None,
             env_overrides: empty_config,
+            env_config_vars: None,
             arg_overrides: None,
         };
         insta::assert_debug_snapshot!(
@@ -1,18 +1,28 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
 use itertools::Itertools;
+use jj_lib::backend::{ChangeId, CommitId};
 use jj_lib::commit::Commit;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::merged_tree::MergedTree;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::ReadonlyRepo;
-use jj_lib::settings::UserSettings;
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
+use regex::Regex;
 
-use crate::cli_util::{edit_temp_file, WorkspaceCommandHelper};
-use crate::command_error::CommandError;
+use crate::cli_util::{edit_temp_file, short_commit_hash, WorkspaceCommandHelper};
+use crate::command_error::{
+    config_error, config_error_with_message, user_error, user_error_with_hint, CommandError,
+};
 use crate::diff_util::{self, DiffFormat};
 use crate::formatter::PlainTextFormatter;
 use crate::text_util;
 use crate::ui::Ui;
 
 pub fn edit_description(
+    ui: &Ui,
     repo: &ReadonlyRepo,
     description: &str,
     settings: &UserSettings,
@@ -25,6 +35,7 @@ JJ: Lines starting with "JJ: " (like this one) will be removed.
     );
 
     let description = edit_temp_file(
+        ui,
         "description",
         ".jjdescription",
         repo.repo_path(),
@@ -40,10 +51,95 @@ JJ: Lines starting with "JJ: " (like this one) will be removed.
     Ok(text_util::complete_newline(description.trim_matches('\n')))
 }
 
+/// Edits the descriptions of several commits in a single editor. Each template
+/// is preceded by a `JJ: describe <commit id>` header, which tells which commit
+/// the description below it belongs to.
+pub fn edit_multiple_descriptions(
+    ui: &Ui,
+    repo: &ReadonlyRepo,
+    commits: &[Commit],
+    templates: &[String],
+    settings: &UserSettings,
+) -> Result<HashMap<CommitId, String>, CommandError> {
+    let mut content = String::new();
+    for (commit, template) in commits.iter().zip(templates) {
+        let commit_hash = short_commit_hash(commit.id());
+        content.push_str(&format!("JJ: describe {commit_hash} -------\n"));
+        content.push_str(&text_util::complete_newline(template.as_str()));
+        content.push('\n');
+    }
+    content.push_str(
+        r#"JJ: Lines starting with "JJ: " (like this one) will be removed.
+JJ: Lines starting with "JJ: describe" tell which commit the description below
+JJ: belongs to. Don't change them.
+"#,
+    );
+
+    let content = edit_temp_file(
+        ui,
+        "description",
+        ".jjdescription",
+        repo.repo_path(),
+        &content,
+        settings,
+    )?;
+    parse_multiple_descriptions(&content, commits)
+}
+
+/// Parses the descriptions of several commits, each preceded by a
+/// `JJ: describe <commit id>` header. The commit id may be abbreviated. Commits
+/// without a header are omitted from the result.
+pub fn parse_multiple_descriptions(
+    content: &str,
+    commits: &[Commit],
+) -> Result<HashMap<CommitId, String>, CommandError> {
+    let mut blocks: Vec<(&CommitId, Vec<&str>)> = vec![];
+    for line in content.lines() {
+        if let Some(header) = line.strip_prefix("JJ: describe ") {
+            let prefix = header.split_whitespace().next().unwrap_or("");
+            let matches = commits
+                .iter()
+                .filter(|commit| !prefix.is_empty() && commit.id().hex().starts_with(prefix))
+                .collect_vec();
+            let [commit] = matches[..] else {
+                return Err(user_error(format!(
+                    "The description header `{line}` doesn't match exactly one of the revisions \
+                     being described"
+                )));
+            };
+            if blocks.iter().any(|(id, _)| *id == commit.id()) {
+                return Err(user_error(format!(
+                    "More than one description for commit {}",
+                    short_commit_hash(commit.id())
+                )));
+            }
+            blocks.push((commit.id(), vec![]));
+        } else if line.starts_with("JJ: ") {
+            continue;
+        } else if let Some((_, lines)) = blocks.last_mut() {
+            lines.push(line);
+        } else if !line.trim().is_empty() {
+            return Err(user_error_with_hint(
+                "Found a description without a `JJ: describe <commit id>` header",
+                "Precede each description with a header naming the commit it belongs to.",
+            ));
+        }
+    }
+    Ok(blocks
+        .into_iter()
+        .map(|(id, lines)| {
+            let description = lines.join("\n");
+            let description = text_util::complete_newline(description.trim_matches('\n'));
+            (id.clone(), description)
+        })
+        .collect())
+}
+
 /// Combines the descriptions from the input commits. If only one is non-empty,
 /// then that one is used. Otherwise we concatenate the messages and ask the
 /// user to edit the result in their editor.
 pub fn combine_messages(
+    ui: &Ui,
     repo: &ReadonlyRepo,
     sources: &[&Commit],
     destination: &Commit,
@@ -74,7 +170,7 @@ pub fn combine_messages(
         combined.push_str("\nJJ: Description from source commit:\n");
         combined.push_str(commit.description());
     }
-    edit_description(repo, &combined, settings)
+    edit_description(ui, repo, &combined, settings)
 }
 
 /// Create a description from a list of paragraphs.
@@ -100,13 +196,14 @@ pub fn description_template_for_describe(
     diff_util::show_patch(
         ui,
         &mut PlainTextFormatter::new(&mut diff_summary_bytes),
+        settings,
         workspace_command,
         commit,
         &EverythingMatcher,
         &[DiffFormat::Summary],
     )?;
     let description = if commit.description().is_empty() {
-        settings.default_description()
+        default_description(settings, workspace_command)?
     } else {
         commit.description().to_owned()
     };
@@ -130,10 +227,12 @@ pub fn description_template_for_commit(
     diff_util::show_diff(
         ui,
         &mut PlainTextFormatter::new(&mut diff_summary_bytes),
+        settings,
         workspace_command,
         from_tree,
         to_tree,
         &EverythingMatcher,
+        &[],
         &[DiffFormat::Summary],
     )?;
     let mut template_chunks = Vec::new();
@@ -141,7 +240,7 @@ pub fn description_template_for_commit(
         template_chunks.push(format!("JJ: {intro}\n"));
     }
     template_chunks.push(if overall_commit_description.is_empty() {
-        settings.default_description()
+        default_description(settings, workspace_command)?
     } else {
         overall_commit_description.to_owned()
     });
@@ -160,3 +259,190 @@ pub fn diff_summary_to_description(bytes: &[u8]) -> String {
     "JJ: This commit contains the following changes:\n".to_owned()
         + &textwrap::indent(text, "JJ:     ")
 }
+
+/// Returns the description to prepopulate the editor with for a change without
+/// a description. `ui.description-template-file` takes precedence over
+/// `ui.default-description`.
+fn default_description(
+    settings: &UserSettings,
+    workspace_command: &WorkspaceCommandHelper,
+) -> Result<String, CommandError> {
+    let Some(path) = settings
+        .config()
+        .get_string("ui.description-template-file")
+        .optional()?
+    else {
+        return Ok(settings.default_description());
+    };
+    let path = match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(relative_path), Some(home_dir)) => home_dir.join(relative_path),
+        _ => workspace_command.workspace_root().join(PathBuf::from(path)),
+    };
+    fs::read_to_string(&path).map_err(|err| {
+        config_error_with_message(
+            format!(
+                "Failed to read the description template file {}",
+                path.display()
+            ),
+            err,
+        )
+    })
+}
+
+/// Checks of the description subject configured in `description-lint`.
+struct DescriptionLint {
+    max_subject_length: Option<usize>,
+    subject_pattern: Option<Regex>,
+    /// Whether the commands should fail instead of warning.
+    blocking: bool,
+}
+
+impl DescriptionLint {
+    fn from_settings(settings: &UserSettings) -> Result<Self, CommandError> {
+        let config = settings.config();
+        let max_subject_length = config
+            .get::<usize>("description-lint.max-subject-length")
+            .optional()?;
+        let subject_pattern = config
+            .get_string("description-lint.subject-pattern")
+            .optional()?
+            .map(|pattern| Regex::new(&pattern))
+            .transpose()
+            .map_err(|err| {
+                config_error_with_message("Invalid `description-lint.subject-pattern`", err)
+            })?;
+        let blocking = match config
+            .get_string("description-lint.level")
+            .optional()?
+            .as_deref()
+        {
+            None | Some("warn") => false,
+            Some("error") => true,
+            Some(level) => {
+                return Err(config_error(format!(
+                    "Invalid `description-lint.level`: {level}; expected \"warn\" or \"error\""
+                )))
+            }
+        };
+        Ok(DescriptionLint {
+            max_subject_length,
+            subject_pattern,
+            blocking,
+        })
+    }
+
+    /// Returns the problems with the `description`. Empty descriptions are not
+    /// checked.
+    fn check(&self, description: &str) -> Vec<String> {
+        let Some(subject) = description.lines().next() else {
+            return vec![];
+        };
+        let mut problems = vec![];
+        if let Some(max_length) = self.max_subject_length {
+            let length = subject.chars().count();
+            if length > max_length {
+                problems.push(format!(
+                    "The description subject is {length} characters long, more than \
+                     {max_length}"
+                ));
+            }
+        }
+        if let Some(pattern) = &self.subject_pattern {
+            if !pattern.is_match(subject) {
+                problems.push(format!(
+                    "The description subject doesn't match the pattern `{pattern}`"
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// Checks the `description` with the `description-lint` settings. The problems
+/// are reported as warnings, or as an error if the lint is blocking.
+pub fn lint_description(
+    ui: &Ui,
+    settings: &UserSettings,
+    description: &str,
+) -> Result<(), CommandError> {
+    let lint = DescriptionLint::from_settings(settings)?;
+    let problems = lint.check(description);
+    for problem in &problems {
+        writeln!(ui.warning_default(), "{problem}")?;
+    }
+    if lint.blocking && !problems.is_empty() {
+        return Err(user_error_with_hint(
+            "The description doesn't pass the lint checks",
+            "Fix the description, or set `description-lint.level = \"warn\"` to only warn.",
+        ));
+    }
+    Ok(())
+}
+
+/// Adds the trailers requested by `--signoff` or configured in `trailers` to
+/// the `description` of the commit with `change_id`. Trailers which are
+/// already present aren't added again. Empty descriptions are left as is.
+pub fn add_trailers(
+    settings: &UserSettings,
+    change_id: &ChangeId,
+    signoff: bool,
+    description: &str,
+) -> Result<String, CommandError> {
+    if description.trim().is_empty() {
+        return Ok(description.to_owned());
+    }
+    let config = settings.config();
+    let mut description = description.to_owned();
+    if signoff || config.get_bool("trailers.signoff").optional()? == Some(true) {
+        let value = format!("{} <{}>", settings.user_name(), settings.user_email());
+        description = append_trailer(&description, "Signed-off-by", &value, false);
+    }
+    let co_authors = config
+        .get::<Vec<String>>("trailers.co-authors")
+        .optional()?
+        .unwrap_or_default();
+    for co_author in &co_authors {
+        description = append_trailer(&description, "Co-authored-by", co_author, false);
+    }
+    if config.get_bool("trailers.change-id").optional()? == Some(true) {
+        // Gerrit expects "I" followed by 40 hex digits, so the change id is
+        // padded with the hex of "jjid".
+        let value = format!("I6a6a6964{}", change_id.hex());
+        description = append_trailer(&description, "Change-Id", &value, true);
+    }
+    Ok(description)
+}
+
+/// Appends the `key: value` trailer to the trailers in the last paragraph of
+/// the `description`, or to a new paragraph if there are no trailers yet. If
+/// `unique`, other trailers with the same key are removed.
+fn append_trailer(description: &str, key: &str, value: &str, unique: bool) -> String {
+    let trailer = format!("{key}: {value}");
+    let text = description.trim_end_matches('\n');
+    let (body, trailers) = match text.rfind("\n\n") {
+        Some(pos) if is_trailer_block(&text[pos + 2..]) => (&text[..pos], &text[pos + 2..]),
+        _ => (text, ""),
+    };
+    let mut lines = trailers.lines().collect_vec();
+    if unique {
+        lines.retain(|line| *line == trailer || !is_trailer_with_key(line, key));
+    }
+    if !lines.contains(&trailer.as_str()) {
+        lines.push(&trailer);
+    }
+    format!("{body}\n\n{}\n", lines.iter().join("\n"))
+}
+
+/// Whether every line of the `paragraph` looks like a `Key: value` trailer.
+fn is_trailer_block(paragraph: &str) -> bool {
+    paragraph.lines().all(|line| {
+        line.split_once(": ").is_some_and(|(key, _)| {
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+    })
+}
+
+fn is_trailer_with_key(line: &str, key: &str) -> bool {
+    line.split_once(": ")
+        .is_some_and(|(line_key, _)| line_key.eq_ignore_ascii_case(key))
+}
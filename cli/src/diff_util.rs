@@ -13,21 +13,23 @@
 // limitations under the License.
 
 use std::cmp::max;
-use std::collections::VecDeque;
-use std::io;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Write as _};
 use std::ops::Range;
+use std::process::Stdio;
 
 use futures::{try_join, Stream, StreamExt};
 use itertools::Itertools;
-use jj_lib::backend::{BackendResult, TreeValue};
+use jj_lib::backend::{BackendResult, CopyRecord, TreeValue};
 use jj_lib::commit::Commit;
-use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+use jj_lib::conflicts::{materialize_tree_value, ConflictMarkerOptions, MaterializedTreeValue};
 use jj_lib::diff::{Diff, DiffHunk};
 use jj_lib::files::DiffLine;
 use jj_lib::matchers::Matcher;
-use jj_lib::merge::MergedTreeValue;
+use jj_lib::merge::{Merge, MergedTreeValue};
 use jj_lib::merged_tree::{MergedTree, TreeDiffStream};
 use jj_lib::object_id::ObjectId;
+use jj_lib::rename_detection::{self, DetectedRename, RenameDetectionOptions, RenameKind};
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
 use jj_lib::settings::{ConfigResultExt as _, UserSettings};
@@ -38,10 +40,12 @@ use tracing::instrument;
 use unicode_width::UnicodeWidthStr as _;
 
 use crate::cli_util::WorkspaceCommandHelper;
-use crate::command_error::CommandError;
+use crate::command_error::{user_error_with_message, CommandError};
 use crate::config::CommandNameAndArgs;
 use crate::formatter::Formatter;
 use crate::merge_tools::{self, ExternalMergeTool};
+use crate::patch_util;
+use crate::syntax_highlight::{self, Language, TokenKind};
 use crate::text_util;
 use crate::ui::Ui;
 
@@ -49,7 +53,7 @@ const DEFAULT_CONTEXT_LINES: usize = 3;
 
 #[derive(clap::Args, Clone, Debug)]
 #[command(next_help_heading = "Diff Formatting Options")]
-#[command(group(clap::ArgGroup::new("short-format").args(&["summary", "stat", "types"])))]
+#[command(group(clap::ArgGroup::new("short-format").args(&["summary", "stat", "numstat", "types"])))]
 #[command(group(clap::ArgGroup::new("long-format").args(&["git", "color_words", "tool"])))]
 pub struct DiffFormatArgs {
     /// For each path, show only whether it was modified, added, or deleted
@@ -58,6 +62,12 @@ pub struct DiffFormatArgs {
     /// Show a histogram of the changes
     #[arg(long)]
     pub stat: bool,
+    /// Show the number of added and removed lines in machine-readable form
+    ///
+    /// Each path is printed as `<added>\t<removed>\t<path>`. Binary files are
+    /// printed with `-` in place of the line counts.
+    #[arg(long)]
+    pub numstat: bool,
     /// For each path, show only its type before and after
     ///
     /// The diff is shown as two letters. The first letter indicates the type
@@ -74,20 +84,40 @@ pub struct DiffFormatArgs {
     #[arg(long)]
     pub color_words: bool,
     /// Generate diff by external command
+    ///
+    /// The command is looked up by name in the `merge-tools` table, and its
+    /// `diff-args` are used. If the tool isn't configured, the name is run as
+    /// a program with the `$left` and `$right` directories as arguments.
     #[arg(long)]
     pub tool: Option<String>,
-    /// Number of lines of context to show
+    /// Number of lines of context to show (overrides the `diff.context`
+    /// config)
     #[arg(long)]
     context: Option<usize>,
+    /// Show the contents of binary files as text
+    ///
+    /// By default, only the sizes of changed binary files are shown unless a
+    /// renderer is configured for the file type in `diff.binary-renderers`.
+    #[arg(long)]
+    binary: bool,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DiffFormat {
     Summary,
     Stat,
+    NumStat,
     Types,
-    Git { context: usize },
-    ColorWords { context: usize },
+    Git {
+        context: usize,
+        highlight_inline: bool,
+        syntax_highlight: bool,
+        binary: BinaryDiffOptions,
+    },
+    ColorWords {
+        context: usize,
+        binary: BinaryDiffOptions,
+    },
     Tool(Box<ExternalMergeTool>),
 }
 
@@ -98,7 +128,7 @@ pub fn diff_formats_for(
 ) -> Result<Vec<DiffFormat>, config::ConfigError> {
     let formats = diff_formats_from_args(settings, args)?;
     if formats.is_empty() {
-        Ok(vec![default_diff_format(settings, args.context)?])
+        Ok(vec![default_diff_format(settings, args)?])
     } else {
         Ok(formats)
     }
@@ -114,7 +144,7 @@ pub fn diff_formats_for_log(
     let mut formats = diff_formats_from_args(settings, args)?;
     // --patch implies default if no format other than --summary is specified
     if patch && matches!(formats.as_slice(), [] | [DiffFormat::Summary]) {
-        formats.push(default_diff_format(settings, args.context)?);
+        formats.push(default_diff_format(settings, args)?);
         formats.dedup();
     }
     Ok(formats)
@@ -124,22 +154,31 @@ fn diff_formats_from_args(
     settings: &UserSettings,
     args: &DiffFormatArgs,
 ) -> Result<Vec<DiffFormat>, config::ConfigError> {
+    let highlight_inline = highlight_inline_from_settings(settings)?;
+    let syntax_highlight = syntax_highlight_from_settings(settings)?;
+    let binary = BinaryDiffOptions::from_settings(settings, args.binary)?;
+    let context = num_context_lines(settings, args.context)?;
     let mut formats = [
         (args.summary, DiffFormat::Summary),
         (args.types, DiffFormat::Types),
         (
             args.git,
             DiffFormat::Git {
-                context: args.context.unwrap_or(DEFAULT_CONTEXT_LINES),
+                context,
+                highlight_inline,
+                syntax_highlight,
+                binary: binary.clone(),
             },
         ),
         (
             args.color_words,
             DiffFormat::ColorWords {
-                context: args.context.unwrap_or(DEFAULT_CONTEXT_LINES),
+                context,
+                binary: binary.clone(),
             },
         ),
         (args.stat, DiffFormat::Stat),
+        (args.numstat, DiffFormat::NumStat),
     ]
     .into_iter()
     .filter_map(|(arg, format)| arg.then_some(format))
@@ -154,7 +193,7 @@ fn diff_formats_from_args(
 
 fn default_diff_format(
     settings: &UserSettings,
-    num_context_lines: Option<usize>,
+    args: &DiffFormatArgs,
 ) -> Result<DiffFormat, config::ConfigError> {
     let config = settings.config();
     if let Some(args) = config.get("ui.diff.tool").optional()? {
@@ -174,55 +213,178 @@ fn default_diff_format(
     } else {
         "color-words".to_owned()
     };
-    match name.as_ref() {
+    parse_diff_format(settings, &name, args.context, args.binary)
+}
+
+/// Returns the diff format named `name`, as in the `ui.diff.format` config.
+pub fn diff_format_from_name(
+    settings: &UserSettings,
+    name: &str,
+) -> Result<DiffFormat, config::ConfigError> {
+    parse_diff_format(settings, name, None, false)
+}
+
+/// Returns the Git diff format used to write patch files, which includes the
+/// contents of binary files.
+pub fn patch_diff_format(
+    settings: &UserSettings,
+    args: &DiffFormatArgs,
+) -> Result<DiffFormat, config::ConfigError> {
+    Ok(DiffFormat::Git {
+        context: num_context_lines(settings, args.context)?,
+        highlight_inline: false,
+        syntax_highlight: false,
+        binary: BinaryDiffOptions::for_patch(),
+    })
+}
+
+fn parse_diff_format(
+    settings: &UserSettings,
+    name: &str,
+    context_arg: Option<usize>,
+    binary_arg: bool,
+) -> Result<DiffFormat, config::ConfigError> {
+    match name {
         "summary" => Ok(DiffFormat::Summary),
         "types" => Ok(DiffFormat::Types),
         "git" => Ok(DiffFormat::Git {
-            context: num_context_lines.unwrap_or(DEFAULT_CONTEXT_LINES),
+            context: num_context_lines(settings, context_arg)?,
+            highlight_inline: highlight_inline_from_settings(settings)?,
+            syntax_highlight: syntax_highlight_from_settings(settings)?,
+            binary: BinaryDiffOptions::from_settings(settings, binary_arg)?,
         }),
         "color-words" => Ok(DiffFormat::ColorWords {
-            context: num_context_lines.unwrap_or(DEFAULT_CONTEXT_LINES),
+            context: num_context_lines(settings, context_arg)?,
+            binary: BinaryDiffOptions::from_settings(settings, binary_arg)?,
         }),
         "stat" => Ok(DiffFormat::Stat),
+        "numstat" => Ok(DiffFormat::NumStat),
         _ => Err(config::ConfigError::Message(format!(
             "invalid diff format: {name}"
         ))),
     }
 }
 
+/// Returns the number of context lines specified by the command argument or
+/// the `diff.context` config.
+fn num_context_lines(
+    settings: &UserSettings,
+    context_arg: Option<usize>,
+) -> Result<usize, config::ConfigError> {
+    if let Some(context) = context_arg {
+        return Ok(context);
+    }
+    let context = settings.config().get::<usize>("diff.context").optional()?;
+    Ok(context.unwrap_or(DEFAULT_CONTEXT_LINES))
+}
+
+fn highlight_inline_from_settings(settings: &UserSettings) -> Result<bool, config::ConfigError> {
+    Ok(settings
+        .config()
+        .get_bool("diff.highlight-inline")
+        .optional()?
+        .unwrap_or(false))
+}
+
+fn syntax_highlight_from_settings(settings: &UserSettings) -> Result<bool, config::ConfigError> {
+    Ok(settings
+        .config()
+        .get_bool("diff.syntax-highlight")
+        .optional()?
+        .unwrap_or(false))
+}
+
+/// Returns the options to pair the renamed and copied files of a diff. The
+/// recorded `copies` are always paired.
+pub fn rename_detection_options(
+    settings: &UserSettings,
+    copies: &[CopyRecord],
+) -> Result<RenameDetectionOptions, config::ConfigError> {
+    let detect_similar = settings
+        .config()
+        .get_bool("diff.detect-renames")
+        .optional()?
+        .unwrap_or(false);
+    Ok(RenameDetectionOptions {
+        detect_similar,
+        copies: copies.to_vec(),
+        ..Default::default()
+    })
+}
+
+/// Shows the diff between the trees in the given formats. The `copies` recorded
+/// in the commit of `to_tree` are reported as renames and copies, in addition
+/// to the detected ones if `diff.detect-renames` is enabled.
+#[allow(clippy::too_many_arguments)]
 pub fn show_diff(
     ui: &Ui,
     formatter: &mut dyn Formatter,
+    settings: &UserSettings,
     workspace_command: &WorkspaceCommandHelper,
     from_tree: &MergedTree,
     to_tree: &MergedTree,
     matcher: &dyn Matcher,
+    copies: &[CopyRecord],
     formats: &[DiffFormat],
 ) -> Result<(), CommandError> {
+    let rename_options = rename_detection_options(settings, copies)?;
     for format in formats {
         match format {
             DiffFormat::Summary => {
                 let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_diff_summary(formatter, workspace_command, tree_diff)?;
+                show_diff_summary(formatter, workspace_command, tree_diff, &rename_options)?;
             }
             DiffFormat::Stat => {
                 let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_diff_stat(ui, formatter, workspace_command, tree_diff)?;
+                show_diff_stat(ui, formatter, workspace_command, tree_diff, &rename_options)?;
+            }
+            DiffFormat::NumStat => {
+                let tree_diff = from_tree.diff_stream(to_tree, matcher);
+                show_diff_numstat(formatter, workspace_command, tree_diff, &rename_options)?;
             }
             DiffFormat::Types => {
                 let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_types(formatter, workspace_command, tree_diff)?;
+                show_types(formatter, workspace_command, tree_diff, &rename_options)?;
             }
-            DiffFormat::Git { context } => {
+            DiffFormat::Git {
+                context,
+                highlight_inline,
+                syntax_highlight,
+                binary,
+            } => {
                 let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_git_diff(formatter, workspace_command, *context, tree_diff)?;
+                show_git_diff(
+                    formatter,
+                    workspace_command,
+                    *context,
+                    *highlight_inline,
+                    *syntax_highlight,
+                    binary,
+                    tree_diff,
+                    &rename_options,
+                )?;
             }
-            DiffFormat::ColorWords { context } => {
+            DiffFormat::ColorWords { context, binary } => {
                 let tree_diff = from_tree.diff_stream(to_tree, matcher);
-                show_color_words_diff(formatter, workspace_command, *context, tree_diff)?;
+                show_color_words_diff(
+                    formatter,
+                    workspace_command,
+                    *context,
+                    binary,
+                    tree_diff,
+                    &rename_options,
+                )?;
             }
             DiffFormat::Tool(tool) => {
-                merge_tools::generate_diff(ui, formatter.raw(), from_tree, to_tree, matcher, tool)?;
+                merge_tools::generate_diff(
+                    ui,
+                    formatter.raw(),
+                    from_tree,
+                    to_tree,
+                    matcher,
+                    tool,
+                    workspace_command.conflict_marker_options(),
+                )?;
             }
         }
     }
@@ -232,6 +394,7 @@ pub fn show_diff(
 pub fn show_patch(
     ui: &Ui,
     formatter: &mut dyn Formatter,
+    settings: &UserSettings,
     workspace_command: &WorkspaceCommandHelper,
     commit: &Commit,
     matcher: &dyn Matcher,
@@ -243,10 +406,12 @@ pub fn show_patch(
     show_diff(
         ui,
         formatter,
+        settings,
         workspace_command,
         &from_tree,
         &to_tree,
         matcher,
+        commit.copies(),
         formats,
     )
 }
@@ -401,6 +566,173 @@ fn file_content_for_diff(reader: &mut dyn io::Read) -> io::Result<FileContent> {
     })
 }
 
+/// Options to show the contents of binary files.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BinaryDiffOptions {
+    /// Whether to show binary contents as text.
+    as_text: bool,
+    /// Commands to convert binary contents to text, keyed by MIME type
+    /// patterns such as `image/png`, `image/*`, or `*`.
+    renderers: HashMap<String, CommandNameAndArgs>,
+    /// Whether to include the contents of binary files as Git binary patches,
+    /// so the diff can be applied.
+    patch: bool,
+}
+
+impl BinaryDiffOptions {
+    pub fn from_settings(
+        settings: &UserSettings,
+        as_text: bool,
+    ) -> Result<Self, config::ConfigError> {
+        let renderers = settings
+            .config()
+            .get("diff.binary-renderers")
+            .optional()?
+            .unwrap_or_default();
+        Ok(BinaryDiffOptions {
+            as_text,
+            renderers,
+            patch: false,
+        })
+    }
+
+    /// Options for diffs written to patch files, which keep the contents of
+    /// binary files as is.
+    pub fn for_patch() -> Self {
+        BinaryDiffOptions {
+            patch: true,
+            ..Default::default()
+        }
+    }
+
+    fn find_renderer(&self, mime_type: &str) -> Option<&CommandNameAndArgs> {
+        let (top_level, _) = mime_type.split_once('/').unwrap_or((mime_type, ""));
+        [
+            mime_type.to_owned(),
+            format!("{top_level}/*"),
+            "*".to_owned(),
+        ]
+        .iter()
+        .find_map(|pattern| self.renderers.get(pattern))
+    }
+
+    /// Converts binary `content` to text by the renderer configured for its
+    /// MIME type. The content is passed to the renderer through stdin, and is
+    /// replaced by its output.
+    ///
+    /// The returned content is still marked as binary if it should be
+    /// summarized instead of shown.
+    fn render(&self, path: &RepoPath, content: FileContent) -> Result<FileContent, CommandError> {
+        if !content.is_binary || self.patch {
+            return Ok(content);
+        }
+        if self.as_text {
+            return Ok(FileContent {
+                is_binary: false,
+                contents: content.contents,
+            });
+        }
+        let Some(renderer) = self.find_renderer(detect_mime_type(&content.contents)) else {
+            return Ok(content);
+        };
+        let message = format!(
+            "Failed to render binary file {}",
+            path.as_internal_file_string()
+        );
+        let render_error = |err: io::Error| user_error_with_message(&message, err);
+        let mut child = renderer
+            .to_command()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(render_error)?;
+        let mut stdin = child.stdin.take().unwrap();
+        // Write in a separate thread so the renderer can't block on a full
+        // stdout pipe while we're still writing the input.
+        let output = std::thread::scope(|scope| {
+            scope.spawn(move || {
+                // The renderer may exit without reading the whole input.
+                stdin.write_all(&content.contents).ok();
+            });
+            child.wait_with_output()
+        })
+        .map_err(render_error)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let err = format!(
+                "Renderer exited with {}: {}",
+                output.status,
+                stderr.trim_end()
+            );
+            return Err(user_error_with_message(message, err));
+        }
+        Ok(FileContent {
+            is_binary: false,
+            contents: output.stdout,
+        })
+    }
+}
+
+/// Guesses the MIME type of binary `content` by its leading bytes.
+fn detect_mime_type(content: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x7fELF", "application/x-executable"),
+    ];
+    if content.starts_with(b"RIFF") && content.get(8..12) == Some(b"WEBP") {
+        return "image/webp";
+    }
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| content.starts_with(signature))
+        .map_or("application/octet-stream", |(_, mime_type)| mime_type)
+}
+
+/// Formats the file size in human-readable binary units.
+fn format_file_size(len: usize) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if len < 1024 {
+        return format!("{len} B");
+    }
+    let mut size = len as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next_unit in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next_unit;
+    }
+    format!("{size:.1} {unit}")
+}
+
+fn show_binary_summary(
+    formatter: &mut dyn Formatter,
+    left: Option<&FileContent>,
+    right: Option<&FileContent>,
+) -> io::Result<()> {
+    let output = match (left, right) {
+        (Some(left), Some(right)) => format!(
+            "    (binary file changed, {} -> {})",
+            format_file_size(left.contents.len()),
+            format_file_size(right.contents.len())
+        ),
+        (Some(content), None) | (None, Some(content)) => format!(
+            "    (binary file, {})",
+            format_file_size(content.contents.len())
+        ),
+        (None, None) => panic!("binary summary requires file contents"),
+    };
+    writeln!(formatter.labeled("binary"), "{output}")
+}
+
 fn diff_content(
     path: &RepoPath,
     value: MaterializedTreeValue,
@@ -453,13 +785,22 @@ pub fn show_color_words_diff(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
     num_context_lines: usize,
+    binary_options: &BinaryDiffOptions,
     tree_diff: TreeDiffStream,
+    rename_options: &RenameDetectionOptions,
 ) -> Result<(), CommandError> {
     formatter.push_label("diff")?;
-    let mut diff_stream = materialized_diff_stream(workspace_command.repo().store(), tree_diff);
+    let store = workspace_command.repo().store();
     async {
-        while let Some((path, diff)) = diff_stream.next().await {
-            let ui_path = workspace_command.format_file_path(&path);
+        let entries = collect_diff_entries(store, tree_diff, rename_options).await?;
+        let mut diff_stream = materialized_diff_entries_stream(
+            store,
+            entries,
+            workspace_command.conflict_marker_options(),
+        );
+        while let Some((entry, diff)) = diff_stream.next().await {
+            let ui_path = format_diff_entry_path(workspace_command, &entry);
+            let (source, path) = (&entry.source, &entry.target);
             let (left_value, right_value) = diff?;
             if left_value.is_absent() {
                 let description = basic_diff_file_type(&right_value);
@@ -467,11 +808,12 @@ pub fn show_color_words_diff(
                     formatter.labeled("header"),
                     "Added {description} {ui_path}:"
                 )?;
-                let right_content = diff_content(&path, right_value)?;
+                let right_content =
+                    binary_options.render(path, diff_content(path, right_value)?)?;
                 if right_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else if right_content.is_binary {
-                    writeln!(formatter.labeled("binary"), "    (binary)")?;
+                    show_binary_summary(formatter, None, Some(&right_content))?;
                 } else {
                     show_color_words_diff_hunks(
                         &[],
@@ -528,11 +870,13 @@ pub fn show_color_words_diff(
                         )
                     }
                 };
-                let left_content = diff_content(&path, left_value)?;
-                let right_content = diff_content(&path, right_value)?;
+                let left_content =
+                    binary_options.render(source, diff_content(source, left_value)?)?;
+                let right_content =
+                    binary_options.render(path, diff_content(path, right_value)?)?;
                 writeln!(formatter.labeled("header"), "{description} {ui_path}:")?;
                 if left_content.is_binary || right_content.is_binary {
-                    writeln!(formatter.labeled("binary"), "    (binary)")?;
+                    show_binary_summary(formatter, Some(&left_content), Some(&right_content))?;
                 } else {
                     show_color_words_diff_hunks(
                         &left_content.contents,
@@ -547,11 +891,12 @@ pub fn show_color_words_diff(
                     formatter.labeled("header"),
                     "Removed {description} {ui_path}:"
                 )?;
-                let left_content = diff_content(&path, left_value)?;
+                let left_content =
+                    binary_options.render(source, diff_content(source, left_value)?)?;
                 if left_content.is_empty() {
                     writeln!(formatter.labeled("empty"), "    (empty)")?;
                 } else if left_content.is_binary {
-                    writeln!(formatter.labeled("binary"), "    (binary)")?;
+                    show_binary_summary(formatter, Some(&left_content), None)?;
                 } else {
                     show_color_words_diff_hunks(
                         &left_content.contents,
@@ -573,15 +918,18 @@ struct GitDiffPart {
     mode: String,
     hash: String,
     content: Vec<u8>,
+    is_binary: bool,
 }
 
 fn git_diff_part(
     path: &RepoPath,
     value: MaterializedTreeValue,
+    binary_options: &BinaryDiffOptions,
 ) -> Result<GitDiffPart, CommandError> {
     let mode;
     let hash;
-    let mut contents: Vec<u8>;
+    let contents: Vec<u8>;
+    let mut is_binary = false;
     match value {
         MaterializedTreeValue::Absent => {
             panic!("Absent path {path:?} in diff should have been handled by caller");
@@ -597,9 +945,9 @@ fn git_diff_part(
                 "100644".to_string()
             };
             hash = id.hex();
-            // TODO: use `file_content_for_diff` instead of showing binary
-            contents = vec![];
-            reader.read_to_end(&mut contents)?;
+            let content = binary_options.render(path, file_content_for_diff(&mut reader)?)?;
+            is_binary = content.is_binary;
+            contents = content.contents;
         }
         MaterializedTreeValue::Symlink { id, target } => {
             mode = "120000".to_string();
@@ -624,11 +972,20 @@ fn git_diff_part(
             panic!("Unexpected tree in diff at path {path:?}");
         }
     }
-    let hash = hash[0..10].to_string();
+    // Binary patches can only be applied with the full Git hashes of the blobs,
+    // which may differ from the ids in the backend.
+    let hash = if !binary_options.patch {
+        hash[0..10].to_string()
+    } else if mode == "040000" {
+        hash
+    } else {
+        patch_util::git_blob_hash(&contents)
+    };
     Ok(GitDiffPart {
         mode,
         hash,
         content: contents,
+        is_binary,
     })
 }
 
@@ -720,47 +1077,184 @@ fn unified_diff_hunks<'content>(
         .iter()
         .all(|(diff_type, _line)| *diff_type == DiffLineType::Context)
     {
+        // The context lines at the end of the file may have been added as
+        // leading context of a hunk that doesn't follow.
+        let num_trailing_lines = current_hunk
+            .lines
+            .iter()
+            .rev()
+            .take_while(|(diff_type, _line)| *diff_type == DiffLineType::Context)
+            .count();
+        let num_excess_lines = num_trailing_lines.saturating_sub(num_context_lines);
+        current_hunk
+            .lines
+            .truncate(current_hunk.lines.len() - num_excess_lines);
+        current_hunk.left_line_range.end -= num_excess_lines;
+        current_hunk.right_line_range.end -= num_excess_lines;
         hunks.push(current_hunk);
     }
     hunks
 }
 
+/// Finds the byte ranges of the removed and added lines which differ at word
+/// level. Returns a list of changed ranges for each line.
+fn inline_changed_ranges(lines: &[(DiffLineType, &[u8])]) -> Vec<Vec<Range<usize>>> {
+    let mut line_ranges = vec![vec![]; lines.len()];
+    let mut start = 0;
+    while start < lines.len() {
+        // Pair up a block of removed lines with the block of added lines
+        // following it. Lines which are only removed or added are left as is.
+        let removed_end = start
+            + lines[start..]
+                .iter()
+                .take_while(|(line_type, _)| *line_type == DiffLineType::Removed)
+                .count();
+        let added_end = removed_end
+            + lines[removed_end..]
+                .iter()
+                .take_while(|(line_type, _)| *line_type == DiffLineType::Added)
+                .count();
+        if removed_end == start || added_end == removed_end {
+            start = added_end.max(start + 1);
+            continue;
+        }
+        let left = lines[start..removed_end]
+            .iter()
+            .flat_map(|(_, content)| content.iter().copied())
+            .collect_vec();
+        let right = lines[removed_end..added_end]
+            .iter()
+            .flat_map(|(_, content)| content.iter().copied())
+            .collect_vec();
+        let mut left_ranges = vec![];
+        let mut right_ranges = vec![];
+        let (mut left_pos, mut right_pos) = (0, 0);
+        for hunk in diff::diff(&left, &right) {
+            match hunk {
+                DiffHunk::Matching(content) => {
+                    left_pos += content.len();
+                    right_pos += content.len();
+                }
+                DiffHunk::Different(content) => {
+                    left_ranges.push(left_pos..left_pos + content[0].len());
+                    right_ranges.push(right_pos..right_pos + content[1].len());
+                    left_pos += content[0].len();
+                    right_pos += content[1].len();
+                }
+            }
+        }
+        for (block, block_ranges) in [
+            (start..removed_end, left_ranges),
+            (removed_end..added_end, right_ranges),
+        ] {
+            let mut line_start = 0;
+            for i in block {
+                let line_end = line_start + lines[i].1.len();
+                line_ranges[i] = block_ranges
+                    .iter()
+                    .map(|range| range.start.max(line_start)..range.end.min(line_end))
+                    .filter(|range| !range.is_empty())
+                    .map(|range| range.start - line_start..range.end - line_start)
+                    .collect();
+                line_start = line_end;
+            }
+        }
+        start = added_end;
+    }
+    line_ranges
+}
+
+fn show_unified_diff_line(
+    formatter: &mut dyn Formatter,
+    content: &[u8],
+    changed_ranges: &[Range<usize>],
+    syntax_tokens: &[(Range<usize>, TokenKind)],
+) -> io::Result<()> {
+    // Don't highlight the line terminator.
+    let text_len = content.strip_suffix(b"\n").unwrap_or(content).len();
+    let changed_ranges = changed_ranges
+        .iter()
+        .map(|range| range.start..range.end.min(text_len))
+        .filter(|range| !range.is_empty())
+        .collect_vec();
+    if changed_ranges.is_empty() && syntax_tokens.is_empty() {
+        return formatter.write_all(content);
+    }
+    // Split the line at the boundaries of both kinds of ranges so each segment
+    // can be labeled independently.
+    let boundaries = changed_ranges
+        .iter()
+        .chain(syntax_tokens.iter().map(|(range, _)| range))
+        .flat_map(|range| [range.start, range.end])
+        .chain([0, content.len()])
+        .sorted()
+        .dedup()
+        .collect_vec();
+    for (&start, &end) in boundaries.iter().tuple_windows() {
+        let segment = &content[start..end];
+        let changed = changed_ranges.iter().any(|range| range.contains(&start));
+        let kind = syntax_tokens
+            .iter()
+            .find(|(range, _)| range.contains(&start))
+            .map(|(_, kind)| *kind);
+        let write_syntax = |formatter: &mut dyn Formatter| match kind {
+            Some(kind) => formatter.with_label("syntax", |formatter| {
+                formatter.with_label(kind.label(), |formatter| formatter.write_all(segment))
+            }),
+            None => formatter.write_all(segment),
+        };
+        if changed {
+            formatter.with_label("token", write_syntax)?;
+        } else {
+            write_syntax(formatter)?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats the 1-based line `range` of a hunk header. As in Git, an empty range
+/// is numbered after the line preceding it.
+fn format_hunk_range(range: &Range<usize>) -> String {
+    if range.is_empty() {
+        format!("{},0", range.start - 1)
+    } else {
+        format!("{},{}", range.start, range.len())
+    }
+}
+
 fn show_unified_diff_hunks(
     formatter: &mut dyn Formatter,
     left_content: &[u8],
     right_content: &[u8],
     num_context_lines: usize,
+    highlight_inline: bool,
+    language: Option<&Language>,
 ) -> Result<(), CommandError> {
     for hunk in unified_diff_hunks(left_content, right_content, num_context_lines) {
         writeln!(
             formatter.labeled("hunk_header"),
-            "@@ -{},{} +{},{} @@",
-            hunk.left_line_range.start,
-            hunk.left_line_range.len(),
-            hunk.right_line_range.start,
-            hunk.right_line_range.len()
+            "@@ -{} +{} @@",
+            format_hunk_range(&hunk.left_line_range),
+            format_hunk_range(&hunk.right_line_range),
         )?;
-        for (line_type, content) in hunk.lines {
-            match line_type {
-                DiffLineType::Context => {
-                    formatter.with_label("context", |formatter| {
-                        write!(formatter, " ")?;
-                        formatter.write_all(content)
-                    })?;
-                }
-                DiffLineType::Removed => {
-                    formatter.with_label("removed", |formatter| {
-                        write!(formatter, "-")?;
-                        formatter.write_all(content)
-                    })?;
-                }
-                DiffLineType::Added => {
-                    formatter.with_label("added", |formatter| {
-                        write!(formatter, "+")?;
-                        formatter.write_all(content)
-                    })?;
-                }
-            }
+        let changed_ranges = if highlight_inline {
+            inline_changed_ranges(&hunk.lines)
+        } else {
+            vec![vec![]; hunk.lines.len()]
+        };
+        for ((line_type, content), changed_ranges) in hunk.lines.iter().zip(&changed_ranges) {
+            let syntax_tokens = language
+                .map(|language| syntax_highlight::highlight_line(language, content))
+                .unwrap_or_default();
+            let (label, prefix) = match line_type {
+                DiffLineType::Context => ("context", " "),
+                DiffLineType::Removed => ("removed", "-"),
+                DiffLineType::Added => ("added", "+"),
+            };
+            formatter.with_label(label, |formatter| {
+                write!(formatter, "{prefix}")?;
+                show_unified_diff_line(formatter, content, changed_ranges, &syntax_tokens)
+            })?;
             if !content.ends_with(b"\n") {
                 write!(formatter, "\n\\ No newline at end of file\n")?;
             }
@@ -769,93 +1263,303 @@ fn show_unified_diff_hunks(
     Ok(())
 }
 
-fn materialized_diff_stream<'a>(
-    store: &'a Store,
-    tree_diff: TreeDiffStream<'a>,
+/// An entry of a tree diff. The `source` and `target` paths differ if the file
+/// was renamed or copied.
+struct DiffEntry {
+    source: RepoPathBuf,
+    target: RepoPathBuf,
+    before: MergedTreeValue,
+    after: MergedTreeValue,
+    rename: Option<DetectedRename>,
+}
+
+/// Collects the `tree_diff` entries, and pairs the renamed and copied files
+/// according to the `options`.
+///
+/// A renamed file is reported as a single entry at the target path.
+async fn collect_diff_entries(
+    store: &Store,
+    mut tree_diff: TreeDiffStream<'_>,
+    options: &RenameDetectionOptions,
+) -> Result<Vec<DiffEntry>, CommandError> {
+    let mut entries = vec![];
+    while let Some((path, diff)) = tree_diff.next().await {
+        let (before, after) = diff?;
+        entries.push((path, before, after));
+    }
+    let renames = rename_detection::detect_renames(store, &entries, options)?;
+    let renamed_sources: HashSet<&RepoPath> = renames
+        .iter()
+        .filter(|rename| rename.kind == RenameKind::Rename)
+        .map(|rename| rename.source.as_ref())
+        .collect();
+    let mut renames_by_target: HashMap<&RepoPath, &DetectedRename> = renames
+        .iter()
+        .map(|rename| (rename.target.as_ref(), rename))
+        .collect();
+    let before_values: HashMap<&RepoPath, &MergedTreeValue> = entries
+        .iter()
+        .map(|(path, before, _)| (path.as_ref(), before))
+        .collect();
+    let mut diff_entries = vec![];
+    for (path, before, after) in &entries {
+        if renamed_sources.contains(path.as_ref()) {
+            continue;
+        }
+        let entry = if let Some(rename) = renames_by_target.remove(path.as_ref()) {
+            let before = match before_values.get(rename.source.as_ref()) {
+                Some(&before) => before.clone(),
+                // A recorded copy of an unchanged file
+                None => unchanged_copy_source(&options.copies, rename, after),
+            };
+            DiffEntry {
+                source: rename.source.clone(),
+                target: path.clone(),
+                before,
+                after: after.clone(),
+                rename: Some(rename.clone()),
+            }
+        } else {
+            DiffEntry {
+                source: path.clone(),
+                target: path.clone(),
+                before: before.clone(),
+                after: after.clone(),
+                rename: None,
+            }
+        };
+        diff_entries.push(entry);
+    }
+    Ok(diff_entries)
+}
+
+/// Returns the value of the source of a recorded copy which isn't in the diff.
+fn unchanged_copy_source(
+    copies: &[CopyRecord],
+    rename: &DetectedRename,
+    after: &MergedTreeValue,
+) -> MergedTreeValue {
+    let copy = copies
+        .iter()
+        .find(|copy| copy.target == rename.target)
+        .expect("source of a detected copy should be in the diff");
+    let executable = matches!(
+        after.as_resolved(),
+        Some(Some(TreeValue::File {
+            executable: true,
+            ..
+        }))
+    );
+    Merge::normal(TreeValue::File {
+        id: copy.source_file.clone(),
+        executable,
+    })
+}
+
+/// Formats the path of the `entry`, or its source and target paths if the file
+/// was renamed or copied.
+fn format_diff_entry_path(workspace_command: &WorkspaceCommandHelper, entry: &DiffEntry) -> String {
+    if entry.rename.is_some() {
+        format!(
+            "{} => {}",
+            workspace_command.format_file_path(&entry.source),
+            workspace_command.format_file_path(&entry.target)
+        )
+    } else {
+        workspace_command.format_file_path(&entry.target)
+    }
+}
+
+fn materialized_diff_entries_stream(
+    store: &Store,
+    entries: Vec<DiffEntry>,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> impl Stream<
     Item = (
-        RepoPathBuf,
+        DiffEntry,
         BackendResult<(MaterializedTreeValue, MaterializedTreeValue)>,
     ),
-> + 'a {
-    tree_diff
-        .map(|(path, diff)| async {
-            match diff {
-                Err(err) => (path, Err(err)),
-                Ok((before, after)) => {
-                    let before_future = materialize_tree_value(store, &path, before);
-                    let after_future = materialize_tree_value(store, &path, after);
-                    let values = try_join!(before_future, after_future);
-                    (path, values)
-                }
-            }
+> + '_ {
+    futures::stream::iter(entries)
+        .map(move |entry| async move {
+            let before_future = materialize_tree_value(
+                store,
+                &entry.source,
+                entry.before.clone(),
+                conflict_marker_options,
+            );
+            let after_future = materialize_tree_value(
+                store,
+                &entry.target,
+                entry.after.clone(),
+                conflict_marker_options,
+            );
+            let values = try_join!(before_future, after_future);
+            (entry, values)
         })
         .buffered((store.concurrency() / 2).max(1))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn show_git_diff(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
     num_context_lines: usize,
+    highlight_inline: bool,
+    syntax_highlight: bool,
+    binary_options: &BinaryDiffOptions,
     tree_diff: TreeDiffStream,
+    rename_options: &RenameDetectionOptions,
 ) -> Result<(), CommandError> {
     formatter.push_label("diff")?;
 
-    let mut diff_stream = materialized_diff_stream(workspace_command.repo().store(), tree_diff);
+    let store = workspace_command.repo().store();
     async {
-        while let Some((path, diff)) = diff_stream.next().await {
-            let path_string = path.as_internal_file_string();
+        let entries = collect_diff_entries(store, tree_diff, rename_options).await?;
+        let mut diff_stream = materialized_diff_entries_stream(
+            store,
+            entries,
+            workspace_command.conflict_marker_options(),
+        );
+        while let Some((entry, diff)) = diff_stream.next().await {
+            let left_path_string = entry.source.as_internal_file_string();
+            let right_path_string = entry.target.as_internal_file_string();
             let (left_value, right_value) = diff?;
+            let language = if syntax_highlight {
+                syntax_highlight::language_for_path(&entry.target)
+            } else {
+                None
+            };
             if left_value.is_absent() {
-                let right_part = git_diff_part(&path, right_value)?;
+                let right_part = git_diff_part(&entry.target, right_value, binary_options)?;
                 formatter.with_label("file_header", |formatter| {
-                    writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
+                    writeln!(
+                        formatter,
+                        "diff --git a/{right_path_string} b/{right_path_string}"
+                    )?;
                     writeln!(formatter, "new file mode {}", &right_part.mode)?;
-                    writeln!(formatter, "index 0000000000..{}", &right_part.hash)?;
-                    writeln!(formatter, "--- /dev/null")?;
-                    writeln!(formatter, "+++ b/{path_string}")
+                    let null_hash = "0".repeat(right_part.hash.len());
+                    writeln!(formatter, "index {null_hash}..{}", &right_part.hash)?;
+                    if !right_part.is_binary {
+                        writeln!(formatter, "--- /dev/null")?;
+                        writeln!(formatter, "+++ b/{right_path_string}")?;
+                    }
+                    Ok(())
                 })?;
-                show_unified_diff_hunks(formatter, &[], &right_part.content, num_context_lines)?;
+                if right_part.is_binary && binary_options.patch {
+                    patch_util::write_binary_patch(formatter, &[], &right_part.content)?;
+                } else if right_part.is_binary {
+                    writeln!(
+                        formatter,
+                        "Binary files /dev/null and b/{right_path_string} differ"
+                    )?;
+                } else {
+                    show_unified_diff_hunks(
+                        formatter,
+                        &[],
+                        &right_part.content,
+                        num_context_lines,
+                        highlight_inline,
+                        language,
+                    )?;
+                }
             } else if right_value.is_present() {
-                let left_part = git_diff_part(&path, left_value)?;
-                let right_part = git_diff_part(&path, right_value)?;
+                let left_part = git_diff_part(&entry.source, left_value, binary_options)?;
+                let right_part = git_diff_part(&entry.target, right_value, binary_options)?;
                 formatter.with_label("file_header", |formatter| {
-                    writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
+                    writeln!(
+                        formatter,
+                        "diff --git a/{left_path_string} b/{right_path_string}"
+                    )?;
                     if left_part.mode != right_part.mode {
                         writeln!(formatter, "old mode {}", &left_part.mode)?;
                         writeln!(formatter, "new mode {}", &right_part.mode)?;
-                        if left_part.hash != right_part.hash {
-                            writeln!(formatter, "index {}...{}", &left_part.hash, right_part.hash)?;
+                    }
+                    if let Some(rename) = &entry.rename {
+                        let verb = match rename.kind {
+                            RenameKind::Rename => "rename",
+                            RenameKind::Copy => "copy",
+                        };
+                        writeln!(formatter, "similarity index {}%", rename.similarity)?;
+                        writeln!(formatter, "{verb} from {left_path_string}")?;
+                        writeln!(formatter, "{verb} to {right_path_string}")?;
+                    }
+                    if left_part.hash != right_part.hash {
+                        if left_part.mode != right_part.mode {
+                            writeln!(formatter, "index {}..{}", &left_part.hash, right_part.hash)?;
+                        } else {
+                            writeln!(
+                                formatter,
+                                "index {}..{} {}",
+                                &left_part.hash, right_part.hash, left_part.mode
+                            )?;
                         }
-                    } else if left_part.hash != right_part.hash {
-                        writeln!(
-                            formatter,
-                            "index {}...{} {}",
-                            &left_part.hash, right_part.hash, left_part.mode
-                        )?;
                     }
-                    if left_part.content != right_part.content {
-                        writeln!(formatter, "--- a/{path_string}")?;
-                        writeln!(formatter, "+++ b/{path_string}")?;
+                    let is_binary = left_part.is_binary || right_part.is_binary;
+                    if left_part.content != right_part.content && !is_binary {
+                        writeln!(formatter, "--- a/{left_path_string}")?;
+                        writeln!(formatter, "+++ b/{right_path_string}")?;
                     }
                     Ok(())
                 })?;
-                show_unified_diff_hunks(
-                    formatter,
-                    &left_part.content,
-                    &right_part.content,
-                    num_context_lines,
-                )?;
+                if left_part.is_binary || right_part.is_binary {
+                    if left_part.content == right_part.content {
+                        // Only the mode changed
+                    } else if binary_options.patch {
+                        patch_util::write_binary_patch(
+                            formatter,
+                            &left_part.content,
+                            &right_part.content,
+                        )?;
+                    } else {
+                        writeln!(
+                            formatter,
+                            "Binary files a/{left_path_string} and b/{right_path_string} differ"
+                        )?;
+                    }
+                } else {
+                    show_unified_diff_hunks(
+                        formatter,
+                        &left_part.content,
+                        &right_part.content,
+                        num_context_lines,
+                        highlight_inline,
+                        language,
+                    )?;
+                }
             } else {
-                let left_part = git_diff_part(&path, left_value)?;
+                let left_part = git_diff_part(&entry.source, left_value, binary_options)?;
                 formatter.with_label("file_header", |formatter| {
-                    writeln!(formatter, "diff --git a/{path_string} b/{path_string}")?;
+                    writeln!(
+                        formatter,
+                        "diff --git a/{left_path_string} b/{left_path_string}"
+                    )?;
                     writeln!(formatter, "deleted file mode {}", &left_part.mode)?;
-                    writeln!(formatter, "index {}..0000000000", &left_part.hash)?;
-                    writeln!(formatter, "--- a/{path_string}")?;
-                    writeln!(formatter, "+++ /dev/null")
+                    let null_hash = "0".repeat(left_part.hash.len());
+                    writeln!(formatter, "index {}..{null_hash}", &left_part.hash)?;
+                    if !left_part.is_binary {
+                        writeln!(formatter, "--- a/{left_path_string}")?;
+                        writeln!(formatter, "+++ /dev/null")?;
+                    }
+                    Ok(())
                 })?;
-                show_unified_diff_hunks(formatter, &left_part.content, &[], num_context_lines)?;
+                if left_part.is_binary && binary_options.patch {
+                    patch_util::write_binary_patch(formatter, &left_part.content, &[])?;
+                } else if left_part.is_binary {
+                    writeln!(
+                        formatter,
+                        "Binary files a/{left_path_string} and /dev/null differ"
+                    )?;
+                } else {
+                    show_unified_diff_hunks(
+                        formatter,
+                        &left_part.content,
+                        &[],
+                        num_context_lines,
+                        highlight_inline,
+                        language,
+                    )?;
+                }
             }
         }
         Ok::<(), CommandError>(())
@@ -869,36 +1573,47 @@ pub fn show_git_diff(
 pub fn show_diff_summary(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    mut tree_diff: TreeDiffStream,
-) -> io::Result<()> {
+    tree_diff: TreeDiffStream,
+    rename_options: &RenameDetectionOptions,
+) -> Result<(), CommandError> {
+    let store = workspace_command.repo().store();
+    let entries = collect_diff_entries(store, tree_diff, rename_options).block_on()?;
     formatter.with_label("diff", |formatter| -> io::Result<()> {
-        async {
-            while let Some((repo_path, diff)) = tree_diff.next().await {
-                let (before, after) = diff.unwrap();
-                if before.is_present() && after.is_present() {
-                    writeln!(
-                        formatter.labeled("modified"),
-                        "M {}",
-                        workspace_command.format_file_path(&repo_path)
-                    )?;
-                } else if before.is_absent() {
-                    writeln!(
-                        formatter.labeled("added"),
-                        "A {}",
-                        workspace_command.format_file_path(&repo_path)
-                    )?;
-                } else {
-                    writeln!(
-                        formatter.labeled("removed"),
-                        "D {}", // `R` could be interpreted as "renamed"
-                        workspace_command.format_file_path(&repo_path)
-                    )?;
-                }
+        for entry in entries {
+            if let Some(rename) = &entry.rename {
+                let (label, status) = match rename.kind {
+                    RenameKind::Rename => ("renamed", "R"),
+                    RenameKind::Copy => ("copied", "C"),
+                };
+                writeln!(
+                    formatter.labeled(label),
+                    "{status} {} -> {}",
+                    workspace_command.format_file_path(&entry.source),
+                    workspace_command.format_file_path(&entry.target)
+                )?;
+            } else if entry.before.is_present() && entry.after.is_present() {
+                writeln!(
+                    formatter.labeled("modified"),
+                    "M {}",
+                    workspace_command.format_file_path(&entry.target)
+                )?;
+            } else if entry.before.is_absent() {
+                writeln!(
+                    formatter.labeled("added"),
+                    "A {}",
+                    workspace_command.format_file_path(&entry.target)
+                )?;
+            } else {
+                writeln!(
+                    formatter.labeled("removed"),
+                    "D {}", // `R` could be interpreted as "renamed"
+                    workspace_command.format_file_path(&entry.target)
+                )?;
             }
-            Ok(())
         }
-        .block_on()
-    })
+        Ok(())
+    })?;
+    Ok(())
 }
 
 struct DiffStat {
@@ -939,18 +1654,25 @@ pub fn show_diff_stat(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
     tree_diff: TreeDiffStream,
+    rename_options: &RenameDetectionOptions,
 ) -> Result<(), CommandError> {
     let mut stats: Vec<DiffStat> = vec![];
     let mut max_path_width = 0;
     let mut max_diffs = 0;
 
-    let mut diff_stream = materialized_diff_stream(workspace_command.repo().store(), tree_diff);
+    let store = workspace_command.repo().store();
     async {
-        while let Some((repo_path, diff)) = diff_stream.next().await {
+        let entries = collect_diff_entries(store, tree_diff, rename_options).await?;
+        let mut diff_stream = materialized_diff_entries_stream(
+            store,
+            entries,
+            workspace_command.conflict_marker_options(),
+        );
+        while let Some((entry, diff)) = diff_stream.next().await {
             let (left, right) = diff?;
-            let path = workspace_command.format_file_path(&repo_path);
-            let left_content = diff_content(&repo_path, left)?;
-            let right_content = diff_content(&repo_path, right)?;
+            let path = format_diff_entry_path(workspace_command, &entry);
+            let left_content = diff_content(&entry.source, left)?;
+            let right_content = diff_content(&entry.target, right)?;
             max_path_width = max(max_path_width, path.width());
             let stat = get_diff_stat(path, &left_content, &right_content);
             max_diffs = max(max_diffs, stat.added + stat.removed);
@@ -1011,27 +1733,61 @@ pub fn show_diff_stat(
     Ok(())
 }
 
-pub fn show_types(
+pub fn show_diff_numstat(
     formatter: &mut dyn Formatter,
     workspace_command: &WorkspaceCommandHelper,
-    mut tree_diff: TreeDiffStream,
-) -> io::Result<()> {
-    formatter.with_label("diff", |formatter| {
-        async {
-            while let Some((repo_path, diff)) = tree_diff.next().await {
-                let (before, after) = diff.unwrap();
-                writeln!(
-                    formatter.labeled("modified"),
-                    "{}{} {}",
-                    diff_summary_char(&before),
-                    diff_summary_char(&after),
-                    workspace_command.format_file_path(&repo_path)
-                )?;
+    tree_diff: TreeDiffStream,
+    rename_options: &RenameDetectionOptions,
+) -> Result<(), CommandError> {
+    formatter.push_label("diff")?;
+    let store = workspace_command.repo().store();
+    async {
+        let entries = collect_diff_entries(store, tree_diff, rename_options).await?;
+        let mut diff_stream = materialized_diff_entries_stream(
+            store,
+            entries,
+            workspace_command.conflict_marker_options(),
+        );
+        while let Some((entry, diff)) = diff_stream.next().await {
+            let (left, right) = diff?;
+            let path = format_diff_entry_path(workspace_command, &entry);
+            let left_content = diff_content(&entry.source, left)?;
+            let right_content = diff_content(&entry.target, right)?;
+            if left_content.is_binary || right_content.is_binary {
+                writeln!(formatter, "-\t-\t{path}")?;
+            } else {
+                let stat = get_diff_stat(path, &left_content, &right_content);
+                writeln!(formatter, "{}\t{}\t{}", stat.added, stat.removed, stat.path)?;
             }
-            Ok(())
         }
-        .block_on()
-    })
+        Ok::<(), CommandError>(())
+    }
+    .block_on()?;
+    formatter.pop_label()?;
+    Ok(())
+}
+
+pub fn show_types(
+    formatter: &mut dyn Formatter,
+    workspace_command: &WorkspaceCommandHelper,
+    tree_diff: TreeDiffStream,
+    rename_options: &RenameDetectionOptions,
+) -> Result<(), CommandError> {
+    let store = workspace_command.repo().store();
+    let entries = collect_diff_entries(store, tree_diff, rename_options).block_on()?;
+    formatter.with_label("diff", |formatter| -> io::Result<()> {
+        for entry in &entries {
+            writeln!(
+                formatter.labeled("modified"),
+                "{}{} {}",
+                diff_summary_char(&entry.before),
+                diff_summary_char(&entry.after),
+                format_diff_entry_path(workspace_command, entry)
+            )?;
+        }
+        Ok(())
+    })?;
+    Ok(())
 }
 
 fn diff_summary_char(value: &MergedTreeValue) -> char {
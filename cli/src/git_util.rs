@@ -21,15 +21,18 @@ use std::time::Instant;
 use std::{error, iter};
 
 use itertools::Itertools;
+use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::git::{self, FailedRefExport, FailedRefExportReason, GitImportStats, RefName};
 use jj_lib::git_backend::GitBackend;
 use jj_lib::op_store::{RefTarget, RemoteRef};
 use jj_lib::repo::{ReadonlyRepo, Repo};
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
 use jj_lib::store::Store;
+use jj_lib::time_util::parse_datetime;
 use jj_lib::workspace::Workspace;
 use unicode_width::UnicodeWidthStr;
 
-use crate::command_error::{user_error, CommandError};
+use crate::command_error::{config_error_with_message, user_error, CommandError};
 use crate::formatter::Formatter;
 use crate::progress::Progress;
 use crate::ui::Ui;
@@ -59,6 +62,39 @@ pub fn is_colocated_git_workspace(workspace: &Workspace, repo: &ReadonlyRepo) ->
     git_workdir.canonicalize().ok().as_deref() == dot_git_path.parent()
 }
 
+/// Returns the `(branch, remote)` names of the untracked remote branches whose
+/// targets were committed before the `git.remote-branch-expiry` date. Nothing
+/// expires if the date isn't set.
+///
+/// The committer date is used since the time a remote branch last moved isn't
+/// recorded, so a branch recently moved to an old commit is expired too.
+pub fn find_expired_remote_branches(
+    repo: &dyn Repo,
+    settings: &UserSettings,
+) -> Result<Vec<(String, String)>, CommandError> {
+    const KEY: &str = "git.remote-branch-expiry";
+    let Some(expiry) = settings.config().get_string(KEY).optional()? else {
+        return Ok(vec![]);
+    };
+    let expiry = parse_datetime(&expiry, chrono::Local::now())
+        .map_err(|err| config_error_with_message(format!("Invalid `{KEY}`"), err))?;
+    let expiry = MillisSinceEpoch(expiry.timestamp_millis());
+    let mut expired_branches = vec![];
+    for ((branch, remote), remote_ref) in repo.view().all_remote_branches() {
+        if remote == git::REMOTE_NAME_FOR_LOCAL_GIT_REPO || remote_ref.is_tracking() {
+            continue;
+        }
+        let Some(Some(commit_id)) = remote_ref.target.as_resolved() else {
+            continue;
+        };
+        let commit = repo.store().get_commit(commit_id)?;
+        if commit.committer().timestamp.timestamp < expiry {
+            expired_branches.push((branch.to_owned(), remote.to_owned()));
+        }
+    }
+    Ok(expired_branches)
+}
+
 fn terminal_get_username(ui: &Ui, url: &str) -> Option<String> {
     ui.prompt(&format!("Username for {url}")).ok()
 }
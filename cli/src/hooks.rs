@@ -0,0 +1,191 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! User commands run before and after operations, configured in `[hooks]`.
+//!
+//! A hook receives a JSON object describing the operation on stdin. A hook
+//! run before an operation can reject it by exiting with a non-zero status.
+
+use std::env;
+use std::io::Write as _;
+use std::path::Path;
+use std::process::Stdio;
+use std::rc::Rc;
+
+use jj_lib::backend::CommitId;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo;
+use jj_lib::revset::RevsetExpression;
+use jj_lib::settings::{ConfigResultExt as _, UserSettings};
+use serde::Serialize;
+
+use crate::command_error::{user_error, user_error_with_message, CommandError};
+use crate::config::CommandNameAndArgs;
+use crate::ui::Ui;
+
+/// Environment variable set for the hook processes. Hooks aren't run by `jj`
+/// commands run from a hook.
+const HOOK_ENV_VAR: &str = "JJ_HOOK";
+
+/// The points at which hooks can be run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HookKind {
+    /// Before a snapshot of the working copy is recorded.
+    PreSnapshot,
+    /// Before the operation of a command is recorded.
+    PreTransaction,
+    /// After the operation of a command is recorded.
+    PostTransaction,
+    /// Before branches are pushed to a Git remote.
+    PrePush,
+}
+
+impl HookKind {
+    /// Name of the hook in the `[hooks]` table.
+    pub fn name(self) -> &'static str {
+        match self {
+            HookKind::PreSnapshot => "pre-snapshot",
+            HookKind::PreTransaction => "pre-transaction",
+            HookKind::PostTransaction => "post-transaction",
+            HookKind::PrePush => "pre-push",
+        }
+    }
+
+    /// Whether the hook can reject the operation.
+    fn can_reject(self) -> bool {
+        self != HookKind::PostTransaction
+    }
+}
+
+/// Description of an operation passed to the transaction and snapshot hooks.
+#[derive(Debug, Serialize)]
+pub struct TransactionHookInput {
+    /// The command line of the command.
+    pub args: Vec<String>,
+    /// Description of the operation.
+    pub description: String,
+    /// Id of the recorded operation. Only set for the `post-transaction` hook.
+    pub operation_id: Option<String>,
+    /// Commits that became visible.
+    pub added_commits: Vec<String>,
+    /// Commits that were abandoned or rewritten.
+    pub removed_commits: Vec<String>,
+    /// Working-copy commit of the workspace after the operation.
+    pub working_copy_commit: Option<String>,
+    /// Paths changed in the working copy. Only set for the `pre-snapshot` hook.
+    pub changed_paths: Option<Vec<String>>,
+}
+
+/// Description of a push passed to the `pre-push` hook.
+#[derive(Debug, Serialize)]
+pub struct PushHookInput {
+    /// The command line of the command.
+    pub args: Vec<String>,
+    /// Name of the remote to push to.
+    pub remote: String,
+    /// Branches to update on the remote.
+    pub branches: Vec<PushHookBranch>,
+}
+
+/// Update of a branch passed to the `pre-push` hook.
+#[derive(Debug, Serialize)]
+pub struct PushHookBranch {
+    /// Name of the branch.
+    pub name: String,
+    /// Commit the branch currently points to on the remote, if any.
+    pub old_target: Option<String>,
+    /// Commit the branch will point to, or none if it's deleted.
+    pub new_target: Option<String>,
+}
+
+/// Returns the commits that became visible and those that became hidden
+/// between the `old_heads` and the heads of `new_repo`.
+pub fn added_and_removed_commits(
+    new_repo: &dyn Repo,
+    old_heads: &[CommitId],
+) -> Result<(Vec<String>, Vec<String>), CommandError> {
+    let old_heads = RevsetExpression::commits(old_heads.to_vec());
+    let new_heads = RevsetExpression::commits(new_repo.view().heads().iter().cloned().collect());
+    let hex_ids = |expr: Rc<RevsetExpression>| -> Result<Vec<String>, CommandError> {
+        let revset = expr.evaluate_programmatic(new_repo)?;
+        let ids = revset.iter().map(|id| id.hex()).collect();
+        Ok(ids)
+    };
+    let added = hex_ids(old_heads.range(&new_heads))?;
+    let removed = hex_ids(new_heads.range(&old_heads))?;
+    Ok((added, removed))
+}
+
+/// Returns true if a hook is configured for `kind` and will be run, so that
+/// its input is worth computing.
+pub fn is_hook_enabled(settings: &UserSettings, kind: HookKind) -> Result<bool, CommandError> {
+    Ok(hook_command(settings, kind)?.is_some())
+}
+
+fn hook_command(
+    settings: &UserSettings,
+    kind: HookKind,
+) -> Result<Option<CommandNameAndArgs>, CommandError> {
+    if env::var_os(HOOK_ENV_VAR).is_some() {
+        return Ok(None);
+    }
+    let command = settings
+        .config()
+        .get::<CommandNameAndArgs>(&format!("hooks.{}", kind.name()))
+        .optional()?;
+    Ok(command)
+}
+
+/// Runs the hook configured for `kind`, if any, in `cwd` with the JSON
+/// serialized `input` on stdin.
+///
+/// Returns an error if the hook rejects the operation. A failing
+/// `post-transaction` hook is reported as a warning.
+pub fn run_hook(
+    ui: &Ui,
+    settings: &UserSettings,
+    kind: HookKind,
+    cwd: &Path,
+    input: &impl Serialize,
+) -> Result<(), CommandError> {
+    let name = kind.name();
+    let Some(command) = hook_command(settings, kind)? else {
+        return Ok(());
+    };
+    let input = serde_json::to_vec(input).unwrap();
+    let run = || {
+        let mut child = command
+            .to_command()
+            .current_dir(cwd)
+            .env(HOOK_ENV_VAR, name)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        // The hook may exit without reading its input.
+        _ = child.stdin.take().unwrap().write_all(&input);
+        child.wait()
+    };
+    let status = run().map_err(|err| {
+        user_error_with_message(format!("Failed to run the {name} hook `{command}`"), err)
+    })?;
+    if status.success() {
+        Ok(())
+    } else if kind.can_reject() {
+        Err(user_error(format!(
+            "The {name} hook rejected the operation ({status})"
+        )))
+    } else {
+        writeln!(ui.warning_default(), "The {name} hook failed ({status})")?;
+        Ok(())
+    }
+}
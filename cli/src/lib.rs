@@ -26,13 +26,17 @@ pub mod formatter;
 pub mod generic_templater;
 pub mod git_util;
 pub mod graphlog;
+pub mod hooks;
 pub mod merge_tools;
 pub mod operation_templater;
+pub mod patch_util;
 mod progress;
 pub mod revset_util;
+pub mod syntax_highlight;
 pub mod template_builder;
 pub mod template_parser;
 pub mod templater;
+pub mod text_editor;
 pub mod text_util;
 pub mod time_util;
 pub mod ui;
@@ -5,7 +5,7 @@ use std::sync::Arc;
 use futures::{StreamExt, TryFutureExt, TryStreamExt};
 use itertools::Itertools;
 use jj_lib::backend::{BackendError, FileId, MergedTreeId, TreeValue};
-use jj_lib::conflicts::{materialize_tree_value, MaterializedTreeValue};
+use jj_lib::conflicts::{materialize_tree_value, ConflictMarkerOptions, MaterializedTreeValue};
 use jj_lib::diff::{find_line_ranges, Diff, DiffHunk};
 use jj_lib::files::{self, ContentHunk, MergeResult};
 use jj_lib::matchers::Matcher;
@@ -106,9 +106,10 @@ fn read_file_contents(
     store: &Store,
     tree: &MergedTree,
     path: &RepoPath,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<FileInfo, BuiltinToolError> {
     let value = tree.path_value(path);
-    let materialized_value = materialize_tree_value(store, path, value)
+    let materialized_value = materialize_tree_value(store, path, value, conflict_marker_options)
         .map_err(BuiltinToolError::BackendError)
         .block_on()?;
     match materialized_value {
@@ -242,17 +243,18 @@ pub fn make_diff_files(
     left_tree: &MergedTree,
     right_tree: &MergedTree,
     changed_files: &[RepoPathBuf],
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<Vec<scm_record::File<'static>>, BuiltinToolError> {
     let mut files = Vec::new();
     for changed_path in changed_files {
         let FileInfo {
             file_mode: left_file_mode,
             contents: left_contents,
-        } = read_file_contents(store, left_tree, changed_path)?;
+        } = read_file_contents(store, left_tree, changed_path, conflict_marker_options)?;
         let FileInfo {
             file_mode: right_file_mode,
             contents: right_contents,
-        } = read_file_contents(store, right_tree, changed_path)?;
+        } = read_file_contents(store, right_tree, changed_path, conflict_marker_options)?;
 
         let mut sections = Vec::new();
         if left_file_mode != right_file_mode
@@ -414,6 +416,7 @@ pub fn edit_diff_builtin(
     left_tree: &MergedTree,
     right_tree: &MergedTree,
     matcher: &dyn Matcher,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<MergedTreeId, BuiltinToolError> {
     let store = left_tree.store().clone();
     let changed_files: Vec<_> = left_tree
@@ -421,7 +424,13 @@ pub fn edit_diff_builtin(
         .map(|(path, diff)| diff.map(|_| path))
         .try_collect()
         .block_on()?;
-    let files = make_diff_files(&store, left_tree, right_tree, &changed_files)?;
+    let files = make_diff_files(
+        &store,
+        left_tree,
+        right_tree,
+        &changed_files,
+        conflict_marker_options,
+    )?;
     let mut input = scm_record::helpers::CrosstermInput;
     let recorder = scm_record::Recorder::new(
         scm_record::RecordState {
@@ -594,7 +603,14 @@ mod tests {
             changed_path.to_owned(),
             added_path.to_owned(),
         ];
-        let files = make_diff_files(store, &left_tree, &right_tree, &changed_files).unwrap();
+        let files = make_diff_files(
+            store,
+            &left_tree,
+            &right_tree,
+            &changed_files,
+            ConflictMarkerOptions::default(),
+        )
+        .unwrap();
         insta::assert_debug_snapshot!(files, @r###"
         [
             File {
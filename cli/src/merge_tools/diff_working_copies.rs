@@ -6,14 +6,16 @@ use std::sync::Arc;
 
 use futures::StreamExt;
 use jj_lib::backend::MergedTreeId;
+use jj_lib::conflicts::ConflictMarkerOptions;
 use jj_lib::fsmonitor::FsmonitorKind;
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::local_working_copy::{TreeState, TreeStateError};
 use jj_lib::matchers::Matcher;
 use jj_lib::merged_tree::MergedTree;
 use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::sparse::SparsePattern;
 use jj_lib::store::Store;
-use jj_lib::working_copy::{CheckoutError, SnapshotOptions};
+use jj_lib::working_copy::{CheckoutError, CheckoutOptions, SnapshotOptions};
 use pollster::FutureExt;
 use tempfile::TempDir;
 use thiserror::Error;
@@ -78,12 +80,17 @@ fn check_out(
     state_dir: PathBuf,
     tree: &MergedTree,
     sparse_patterns: Vec<RepoPathBuf>,
+    options: &CheckoutOptions,
 ) -> Result<TreeState, DiffCheckoutError> {
     std::fs::create_dir(&wc_dir).map_err(DiffCheckoutError::SetUpDir)?;
     std::fs::create_dir(&state_dir).map_err(DiffCheckoutError::SetUpDir)?;
     let mut tree_state = TreeState::init(store, wc_dir, state_dir)?;
-    tree_state.set_sparse_patterns(sparse_patterns)?;
-    tree_state.check_out(tree)?;
+    let sparse_patterns = sparse_patterns
+        .into_iter()
+        .map(SparsePattern::Prefix)
+        .collect();
+    tree_state.set_sparse_patterns(sparse_patterns, options)?;
+    tree_state.check_out(tree, options)?;
     Ok(tree_state)
 }
 
@@ -115,9 +122,13 @@ pub(crate) fn set_readonly_recursively(path: &Path) -> Result<(), std::io::Error
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum DiffSide {
-    // Left,
+/// Side of a diff, used to pick the initial contents of the `$output`
+/// directory of a diff editor.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffSide {
+    Left,
+    #[default]
     Right,
 }
 
@@ -129,7 +140,15 @@ pub(crate) fn check_out_trees(
     right_tree: &MergedTree,
     matcher: &dyn Matcher,
     output_is: Option<DiffSide>,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<DiffWorkingCopies, DiffCheckoutError> {
+    let options = CheckoutOptions {
+        conflict_marker_options,
+        progress: None,
+        use_reflinks: false,
+        reflink_cache_size: 0,
+        backup_dir: None,
+    };
     let changed_files: Vec<_> = left_tree
         .diff_stream(right_tree, matcher)
         .map(|(path, _diff)| path)
@@ -147,6 +166,7 @@ pub(crate) fn check_out_trees(
         left_state_dir,
         left_tree,
         changed_files.clone(),
+        &options,
     )?;
     let right_tree_state = check_out(
         store.clone(),
@@ -154,6 +174,7 @@ pub(crate) fn check_out_trees(
         right_state_dir,
         right_tree,
         changed_files.clone(),
+        &options,
     )?;
     let output_tree_state = output_is
         .map(|output_side| {
@@ -164,10 +185,11 @@ pub(crate) fn check_out_trees(
                 output_wc_dir,
                 output_state_dir,
                 match output_side {
-                    // DiffSide::Left => left_tree,
+                    DiffSide::Left => left_tree,
                     DiffSide::Right => right_tree,
                 },
                 changed_files,
+                &options,
             )
         })
         .transpose()?;
@@ -194,8 +216,16 @@ impl DiffEditWorkingCopies {
         matcher: &dyn Matcher,
         output_is: Option<DiffSide>,
         instructions: Option<&str>,
+        conflict_marker_options: ConflictMarkerOptions,
     ) -> Result<Self, DiffEditError> {
-        let diff_wc = check_out_trees(store, left_tree, right_tree, matcher, output_is)?;
+        let diff_wc = check_out_trees(
+            store,
+            left_tree,
+            right_tree,
+            matcher,
+            output_is,
+            conflict_marker_options,
+        )?;
         let got_output_field = output_is.is_some();
 
         set_readonly_recursively(diff_wc.left_working_copy_path())
@@ -267,6 +297,7 @@ diff editing in mind and be a little inaccurate.
     pub fn snapshot_results(
         self,
         base_ignores: Arc<GitIgnoreFile>,
+        conflict_marker_options: ConflictMarkerOptions,
     ) -> Result<MergedTreeId, DiffEditError> {
         if let Some(path) = self.instructions_path_to_cleanup {
             std::fs::remove_file(path).ok();
@@ -282,6 +313,8 @@ diff editing in mind and be a little inaccurate.
             fsmonitor_kind: FsmonitorKind::None,
             progress: None,
             max_new_file_size: u64::MAX,
+            exclude_paths: vec![],
+            conflict_marker_options,
         })?;
         Ok(output_tree_state.current_tree_id().clone())
     }
@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 use itertools::Itertools;
 use jj_lib::backend::{FileId, MergedTreeId, TreeValue};
-use jj_lib::conflicts::{self, materialize_merge_result};
+use jj_lib::conflicts::{self, materialize_merge_result, ConflictMarkerOptions};
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::matchers::Matcher;
 use jj_lib::merge::{Merge, MergedTreeValue};
@@ -35,6 +35,15 @@ pub struct ExternalMergeTool {
     /// Arguments to pass to the program when editing diffs.
     /// `$left` and `$right` are replaced with the corresponding directories.
     pub edit_args: Vec<String>,
+    /// Side of the diff that the `$output` directory starts out with when
+    /// `edit_args` refer to it. Defaults to the right side, so that the
+    /// changes not to keep have to be removed. With the left side, the changes
+    /// to keep have to be copied over instead.
+    pub edit_output_from: DiffSide,
+    /// Whether to add the `JJ-INSTRUCTIONS` file when editing diffs. Tools
+    /// which can't ignore the file, or show their own instructions, can turn
+    /// it off even if `ui.diff-instructions` is enabled.
+    pub edit_instructions: bool,
     /// Arguments to pass to the program when resolving 3-way conflicts.
     /// `$left`, `$right`, `$base`, and `$output` are replaced with
     /// paths to the corresponding files.
@@ -57,6 +66,8 @@ impl Default for ExternalMergeTool {
             program: String::new(),
             diff_args: ["$left", "$right"].map(ToOwned::to_owned).to_vec(),
             edit_args: ["$left", "$right"].map(ToOwned::to_owned).to_vec(),
+            edit_output_from: DiffSide::Right,
+            edit_instructions: true,
             merge_args: vec![],
             merge_tool_edits_conflict_markers: false,
         }
@@ -124,11 +135,16 @@ pub fn run_mergetool_external(
     repo_path: &RepoPath,
     conflict: MergedTreeValue,
     tree: &MergedTree,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<MergedTreeId, ConflictResolveError> {
     let initial_output_content: Vec<u8> = if editor.merge_tool_edits_conflict_markers {
         let mut materialized_conflict = vec![];
-        materialize_merge_result(&content, &mut materialized_conflict)
-            .expect("Writing to an in-memory buffer should never fail");
+        materialize_merge_result(
+            &content,
+            conflict_marker_options,
+            &mut materialized_conflict,
+        )
+        .expect("Writing to an in-memory buffer should never fail");
         materialized_conflict
     } else {
         vec![]
@@ -194,6 +210,7 @@ pub fn run_mergetool_external(
             tree.store(),
             repo_path,
             output_file_contents.as_slice(),
+            conflict_marker_options,
         )
         .block_on()?
     } else {
@@ -219,6 +236,54 @@ pub fn run_mergetool_external(
 static VARIABLE_REGEX: once_cell::sync::Lazy<Regex> =
     once_cell::sync::Lazy::new(|| Regex::new(r"\$([a-z0-9_]+)\b").unwrap());
 
+/// Runs the external merge driver `program` with the `merge_args` on the
+/// 2-sided file conflict `content` at `repo_path`, and returns the contents of
+/// its `$output` file.
+pub fn run_merge_driver_external(
+    program: &str,
+    merge_args: &[String],
+    repo_path: &RepoPath,
+    content: &Merge<jj_lib::files::ContentHunk>,
+) -> Result<Vec<u8>, ExternalToolError> {
+    assert_eq!(content.num_sides(), 2);
+    let files: HashMap<&str, &[u8]> = maplit::hashmap! {
+        "base" => content.get_remove(0).unwrap().0.as_slice(),
+        "left" => content.get_add(0).unwrap().0.as_slice(),
+        "right" => content.get_add(1).unwrap().0.as_slice(),
+        "output" => b"".as_slice(),
+    };
+    let temp_dir = new_utf8_temp_dir("jj-merge-").map_err(ExternalToolError::SetUpDir)?;
+    let mut paths: HashMap<&str, String> = files
+        .iter()
+        .map(|(role, contents)| -> Result<_, ExternalToolError> {
+            let path = temp_dir.path().join(role);
+            std::fs::write(&path, contents).map_err(ExternalToolError::SetUpDir)?;
+            let path = path.into_os_string().into_string().map_err(|path| {
+                let message = format!("path {path:?} is not valid UTF-8");
+                ExternalToolError::SetUpDir(io::Error::new(io::ErrorKind::InvalidData, message))
+            })?;
+            Ok((*role, path))
+        })
+        .try_collect()?;
+    paths.insert("path", repo_path.as_internal_file_string().to_owned());
+
+    let mut cmd = Command::new(program);
+    cmd.args(interpolate_variables(merge_args, &paths))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null());
+    tracing::info!(?cmd, "Invoking the external merge driver:");
+    let exit_status = cmd
+        .status()
+        .map_err(|e| ExternalToolError::FailedToExecute {
+            tool_binary: program.to_owned(),
+            source: e,
+        })?;
+    if !exit_status.success() {
+        return Err(ExternalToolError::ToolAborted { exit_status });
+    }
+    std::fs::read(&paths["output"]).map_err(ExternalToolError::Io)
+}
+
 fn interpolate_variables<V: AsRef<str>>(
     args: &[String],
     variables: &HashMap<&str, V>,
@@ -257,6 +322,7 @@ pub fn edit_diff_external(
     matcher: &dyn Matcher,
     instructions: Option<&str>,
     base_ignores: Arc<GitIgnoreFile>,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<MergedTreeId, DiffEditError> {
     let got_output_field = find_all_variables(&editor.edit_args).contains(&"output");
     let store = left_tree.store();
@@ -265,8 +331,9 @@ pub fn edit_diff_external(
         left_tree,
         right_tree,
         matcher,
-        got_output_field.then_some(DiffSide::Right),
+        got_output_field.then_some(editor.edit_output_from),
         instructions,
+        conflict_marker_options,
     )?;
 
     let patterns = diffedit_wc.working_copies.to_command_variables();
@@ -285,7 +352,7 @@ pub fn edit_diff_external(
         }));
     }
 
-    diffedit_wc.snapshot_results(base_ignores)
+    diffedit_wc.snapshot_results(base_ignores, conflict_marker_options)
 }
 
 /// Generates textual diff by the specified `tool`, and writes into `writer`.
@@ -296,9 +363,17 @@ pub fn generate_diff(
     right_tree: &MergedTree,
     matcher: &dyn Matcher,
     tool: &ExternalMergeTool,
+    conflict_marker_options: ConflictMarkerOptions,
 ) -> Result<(), DiffGenerateError> {
     let store = left_tree.store();
-    let diff_wc = check_out_trees(store, left_tree, right_tree, matcher, None)?;
+    let diff_wc = check_out_trees(
+        store,
+        left_tree,
+        right_tree,
+        matcher,
+        None,
+        conflict_marker_options,
+    )?;
     set_readonly_recursively(diff_wc.left_working_copy_path())
         .map_err(ExternalToolError::SetUpDir)?;
     set_readonly_recursively(diff_wc.right_working_copy_path())
@@ -19,11 +19,13 @@ mod external;
 use std::sync::Arc;
 
 use config::ConfigError;
-use jj_lib::backend::MergedTreeId;
-use jj_lib::conflicts::extract_as_single_hunk;
+use jj_lib::backend::{BackendError, MergedTreeId, TreeValue};
+use jj_lib::conflicts::{extract_as_single_hunk, ConflictMarkerOptions};
 use jj_lib::gitignore::GitIgnoreFile;
 use jj_lib::matchers::Matcher;
-use jj_lib::merged_tree::MergedTree;
+use jj_lib::merge::Merge;
+use jj_lib::merge_driver::MergeDriverKind;
+use jj_lib::merged_tree::{MergedTree, MergedTreeBuilder};
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
 use jj_lib::settings::{ConfigResultExt as _, UserSettings};
 use jj_lib::working_copy::SnapshotError;
@@ -32,6 +34,7 @@ use thiserror::Error;
 
 use self::builtin::{edit_diff_builtin, edit_merge_builtin, BuiltinToolError};
 use self::diff_working_copies::DiffCheckoutError;
+pub use self::diff_working_copies::DiffSide;
 use self::external::{edit_diff_external, ExternalToolError};
 pub use self::external::{generate_diff, ExternalMergeTool};
 use crate::config::CommandNameAndArgs;
@@ -174,6 +177,7 @@ pub struct DiffEditor {
     tool: MergeTool,
     base_ignores: Arc<GitIgnoreFile>,
     use_instructions: bool,
+    conflict_marker_options: ConflictMarkerOptions,
 }
 
 impl DiffEditor {
@@ -214,6 +218,7 @@ impl DiffEditor {
             tool,
             base_ignores,
             use_instructions: settings.config().get_bool("ui.diff-instructions")?,
+            conflict_marker_options: settings.conflict_marker_options()?,
         })
     }
 
@@ -227,10 +232,15 @@ impl DiffEditor {
     ) -> Result<MergedTreeId, DiffEditError> {
         match &self.tool {
             MergeTool::Builtin => {
-                Ok(edit_diff_builtin(left_tree, right_tree, matcher).map_err(Box::new)?)
+                Ok(
+                    edit_diff_builtin(left_tree, right_tree, matcher, self.conflict_marker_options)
+                        .map_err(Box::new)?,
+                )
             }
             MergeTool::External(editor) => {
-                let instructions = self.use_instructions.then_some(instructions).flatten();
+                let instructions = (self.use_instructions && editor.edit_instructions)
+                    .then_some(instructions)
+                    .flatten();
                 edit_diff_external(
                     editor,
                     left_tree,
@@ -238,6 +248,7 @@ impl DiffEditor {
                     matcher,
                     instructions,
                     self.base_ignores.clone(),
+                    self.conflict_marker_options,
                 )
             }
         }
@@ -248,6 +259,7 @@ impl DiffEditor {
 #[derive(Clone, Debug)]
 pub struct MergeEditor {
     tool: MergeTool,
+    conflict_marker_options: ConflictMarkerOptions,
 }
 
 impl MergeEditor {
@@ -256,7 +268,7 @@ impl MergeEditor {
     pub fn with_name(name: &str, settings: &UserSettings) -> Result<Self, MergeToolConfigError> {
         let tool = get_tool_config(settings, name)?
             .unwrap_or_else(|| MergeTool::external(ExternalMergeTool::with_program(name)));
-        Self::new_inner(name, tool)
+        Self::new_inner(name, tool, settings)
     }
 
     /// Loads the default 3-way merge editor from the settings.
@@ -268,16 +280,23 @@ impl MergeEditor {
             None
         }
         .unwrap_or_else(|| MergeTool::external(ExternalMergeTool::with_merge_args(&args)));
-        Self::new_inner(&args, tool)
+        Self::new_inner(&args, tool, settings)
     }
 
-    fn new_inner(name: impl ToString, tool: MergeTool) -> Result<Self, MergeToolConfigError> {
+    fn new_inner(
+        name: impl ToString,
+        tool: MergeTool,
+        settings: &UserSettings,
+    ) -> Result<Self, MergeToolConfigError> {
         if matches!(&tool, MergeTool::External(mergetool) if mergetool.merge_args.is_empty()) {
             return Err(MergeToolConfigError::MergeArgsNotConfigured {
                 tool_name: name.to_string(),
             });
         }
-        Ok(MergeEditor { tool })
+        Ok(MergeEditor {
+            tool,
+            conflict_marker_options: settings.conflict_marker_options()?,
+        })
     }
 
     /// Starts a merge editor for the specified file.
@@ -316,12 +335,93 @@ impl MergeEditor {
                 Ok(tree_id)
             }
             MergeTool::External(editor) => external::run_mergetool_external(
-                editor, file_merge, content, repo_path, conflict, tree,
+                editor,
+                file_merge,
+                content,
+                repo_path,
+                conflict,
+                tree,
+                self.conflict_marker_options,
             ),
         }
     }
 }
 
+/// Result of running the external merge drivers on the conflicts of a tree.
+#[derive(Debug)]
+pub struct MergeDriverResolution {
+    /// Id of the tree with the conflicts resolved by the drivers.
+    pub tree_id: MergedTreeId,
+    /// Paths of the conflicts resolved by the drivers.
+    pub resolved_paths: Vec<RepoPathBuf>,
+    /// Paths of the conflicts which the drivers failed to resolve, with the
+    /// errors.
+    pub failures: Vec<(RepoPathBuf, ExternalToolError)>,
+}
+
+/// Runs the external merge drivers configured for the 2-sided file conflicts
+/// in `tree` at the paths matched by `matcher`.
+pub fn resolve_with_merge_drivers(
+    tree: &MergedTree,
+    matcher: &dyn Matcher,
+) -> Result<MergeDriverResolution, BackendError> {
+    let store = tree.store();
+    let mut tree_builder = MergedTreeBuilder::new(tree.id());
+    let mut resolved_paths = vec![];
+    let mut failures = vec![];
+    for (repo_path, conflict) in tree.conflicts() {
+        if !matcher.matches(&repo_path) {
+            continue;
+        }
+        let Some(MergeDriverKind::External {
+            program,
+            merge_args,
+        }) = store
+            .merge_drivers()
+            .find(&repo_path)
+            .and_then(|driver| driver.kind.as_ref())
+        else {
+            continue;
+        };
+        let Some(file_merge) = conflict.to_file_merge() else {
+            continue;
+        };
+        let file_merge = file_merge.simplify();
+        if file_merge.num_sides() != 2 || file_merge.iter().any(Option::is_none) {
+            continue;
+        }
+        let Some(executable_merge) = conflict.maybe_map(|term| match term {
+            Some(TreeValue::File { id: _, executable }) => Some(*executable),
+            _ => None,
+        }) else {
+            continue;
+        };
+        let Some(&executable) = executable_merge.resolve_trivial() else {
+            continue;
+        };
+        let content = extract_as_single_hunk(&file_merge, store, &repo_path).block_on();
+        match external::run_merge_driver_external(program, merge_args, &repo_path, &content) {
+            Ok(merged_content) => {
+                let id = store.write_file(&repo_path, &mut merged_content.as_slice())?;
+                let value = Merge::normal(TreeValue::File { id, executable });
+                tree_builder.set_or_remove(repo_path.clone(), value);
+                resolved_paths.push(repo_path);
+            }
+            Err(err) => failures.push((repo_path, err)),
+        }
+    }
+    let tree_id = if resolved_paths.is_empty() {
+        tree.id()
+    } else {
+        tree_builder.write_tree(store)?
+    };
+    Ok(MergeDriverResolution {
+        tree_id,
+        resolved_paths,
+        failures,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -358,6 +458,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -384,6 +486,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -417,6 +521,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -439,6 +545,8 @@ mod tests {
                     "-r",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -460,6 +568,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -487,6 +597,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -512,6 +624,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -531,6 +645,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [],
                 merge_tool_edits_conflict_markers: false,
             },
@@ -576,6 +692,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [
                     "$base",
                     "$left",
@@ -621,6 +739,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [
                     "$left",
                     "$base",
@@ -648,6 +768,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [
                     "$left",
                     "$base",
@@ -678,6 +800,8 @@ mod tests {
                     "$left",
                     "$right",
                 ],
+                edit_output_from: Right,
+                edit_instructions: true,
                 merge_args: [
                     "$base",
                     "$left",
@@ -32,6 +32,20 @@ use crate::templater::{
     TemplatePropertyExt as _, TimestampRange,
 };
 
+/// Template environment that can build operation properties.
+///
+/// This is implemented by the languages which share the builtin `Operation`
+/// and `OperationId` methods.
+pub trait OperationTemplateEnvironment<'a>: TemplateLanguage<'a> {
+    fn root_op_id(&self) -> &OperationId;
+    fn current_op_id(&self) -> Option<&OperationId>;
+
+    fn wrap_operation(property: impl TemplateProperty<Output = Operation> + 'a) -> Self::Property;
+    fn wrap_operation_id(
+        property: impl TemplateProperty<Output = OperationId> + 'a,
+    ) -> Self::Property;
+}
+
 pub trait OperationTemplateLanguageExtension {
     fn build_fn_table(&self) -> OperationTemplateBuildFnTable;
 
@@ -109,24 +123,34 @@ impl TemplateLanguage<'static> for OperationTemplateLanguage {
     }
 }
 
-impl OperationTemplateLanguage {
-    pub fn cache_extension<T: Any>(&self) -> Option<&T> {
-        self.cache_extensions.get::<T>()
+impl OperationTemplateEnvironment<'static> for OperationTemplateLanguage {
+    fn root_op_id(&self) -> &OperationId {
+        &self.root_op_id
+    }
+
+    fn current_op_id(&self) -> Option<&OperationId> {
+        self.current_op_id.as_ref()
     }
 
-    pub fn wrap_operation(
+    fn wrap_operation(
         property: impl TemplateProperty<Output = Operation> + 'static,
     ) -> OperationTemplatePropertyKind {
         OperationTemplatePropertyKind::Operation(Box::new(property))
     }
 
-    pub fn wrap_operation_id(
+    fn wrap_operation_id(
         property: impl TemplateProperty<Output = OperationId> + 'static,
     ) -> OperationTemplatePropertyKind {
         OperationTemplatePropertyKind::OperationId(Box::new(property))
     }
 }
 
+impl OperationTemplateLanguage {
+    pub fn cache_extension<T: Any>(&self) -> Option<&T> {
+        self.cache_extensions.get::<T>()
+    }
+}
+
 pub enum OperationTemplatePropertyKind {
     Core(CoreTemplatePropertyKind<'static>),
     Operation(Box<dyn TemplateProperty<Output = Operation>>),
@@ -210,16 +234,17 @@ impl OperationTemplateBuildFnTable {
     }
 }
 
-fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
-    type L = OperationTemplateLanguage;
+/// Creates the builtin `Operation` methods for the language `L`.
+pub fn builtin_operation_methods<'a, L: OperationTemplateEnvironment<'a>>(
+) -> TemplateBuildMethodFnMap<'a, L, Operation> {
     // Not using maplit::hashmap!{} or custom declarative macro here because
     // code completion inside macro is quite restricted.
-    let mut map = OperationTemplateBuildMethodFnMap::<Operation>::new();
+    let mut map = TemplateBuildMethodFnMap::<L, Operation>::new();
     map.insert(
         "current_operation",
         |language, _build_ctx, self_property, function| {
             template_parser::expect_no_arguments(function)?;
-            let current_op_id = language.current_op_id.clone();
+            let current_op_id = language.current_op_id().cloned();
             let out_property = self_property.map(move |op| Some(op.id()) == current_op_id.as_ref());
             Ok(L::wrap_boolean(out_property))
         },
@@ -244,6 +269,7 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
             op.metadata()
                 .tags
                 .iter()
+                .sorted_unstable()
                 .map(|(key, value)| format!("{key}: {value}"))
                 .join("\n")
         });
@@ -275,7 +301,7 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
     });
     map.insert("root", |language, _build_ctx, self_property, function| {
         template_parser::expect_no_arguments(function)?;
-        let root_op_id = language.root_op_id.clone();
+        let root_op_id = language.root_op_id().clone();
         let out_property = self_property.map(move |op| op.id() == &root_op_id);
         Ok(L::wrap_boolean(out_property))
     });
@@ -288,11 +314,12 @@ impl Template for OperationId {
     }
 }
 
-fn builtin_operation_id_methods() -> OperationTemplateBuildMethodFnMap<OperationId> {
-    type L = OperationTemplateLanguage;
+/// Creates the builtin `OperationId` methods for the language `L`.
+pub fn builtin_operation_id_methods<'a, L: OperationTemplateEnvironment<'a>>(
+) -> TemplateBuildMethodFnMap<'a, L, OperationId> {
     // Not using maplit::hashmap!{} or custom declarative macro here because
     // code completion inside macro is quite restricted.
-    let mut map = OperationTemplateBuildMethodFnMap::<OperationId>::new();
+    let mut map = TemplateBuildMethodFnMap::<L, OperationId>::new();
     map.insert("short", |language, build_ctx, self_property, function| {
         let ([], [len_node]) = template_parser::expect_arguments(function)?;
         let len_property = len_node
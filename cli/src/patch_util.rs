@@ -0,0 +1,1003 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for patch files in Git's format.
+
+use std::io::{self, Read as _, Write};
+
+use chrono::DateTime;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use itertools::Itertools as _;
+use jj_lib::backend::Timestamp;
+use jj_lib::repo_path::{RepoPath, RepoPathBuf};
+use thiserror::Error;
+
+/// Alphabet of the base85 encoding used in Git binary patches.
+const BASE85_CHARS: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// Maximum number of bytes encoded on each line of a binary patch.
+const BINARY_LINE_LEN: usize = 52;
+
+/// Returns the hex hash of the Git blob with the given `content`, as used in
+/// the `index` line of binary patches.
+pub fn git_blob_hash(content: &[u8]) -> String {
+    // Hashing an object doesn't need a repository, so this can't fail.
+    git2::Oid::hash_object(git2::ObjectType::Blob, content)
+        .unwrap()
+        .to_string()
+}
+
+/// Writes the `GIT binary patch` section replacing the `old` content with the
+/// `new` content. The reverse hunk is included so the patch can be reverted.
+pub fn write_binary_patch<W: Write + ?Sized>(
+    out: &mut W,
+    old: &[u8],
+    new: &[u8],
+) -> io::Result<()> {
+    writeln!(out, "GIT binary patch")?;
+    write_literal_hunk(out, new)?;
+    write_literal_hunk(out, old)
+}
+
+/// Writes a `literal` hunk containing the whole `content`, compressed with
+/// zlib and encoded in base85.
+fn write_literal_hunk<W: Write + ?Sized>(out: &mut W, content: &[u8]) -> io::Result<()> {
+    writeln!(out, "literal {}", content.len())?;
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(content)?;
+    for chunk in encoder.finish()?.chunks(BINARY_LINE_LEN) {
+        out.write_all(&encode_binary_line(chunk))?;
+    }
+    writeln!(out)
+}
+
+/// Encodes a line of binary patch data, prefixed by its length.
+fn encode_binary_line(data: &[u8]) -> Vec<u8> {
+    assert!(!data.is_empty() && data.len() <= BINARY_LINE_LEN);
+    let len = data.len() as u8;
+    let len_char = if len <= 26 {
+        b'A' + len - 1
+    } else {
+        b'a' + len - 27
+    };
+    let mut line = vec![len_char];
+    for group in data.chunks(4) {
+        let mut bytes = [0; 4];
+        bytes[..group.len()].copy_from_slice(group);
+        let mut value = u32::from_be_bytes(bytes);
+        let mut encoded = [0; 5];
+        for c in encoded.iter_mut().rev() {
+            *c = BASE85_CHARS[(value % 85) as usize];
+            value /= 85;
+        }
+        line.extend_from_slice(&encoded);
+    }
+    line.push(b'\n');
+    line
+}
+
+/// Error while parsing or applying a patch.
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("Invalid patch at line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Failed to apply patch to {path}: {message}")]
+    Apply { path: String, message: String },
+    #[error("No changes found in the patch")]
+    Empty,
+}
+
+/// Patch parsed from a file.
+#[derive(Clone, Debug)]
+pub struct Patch {
+    pub header: PatchHeader,
+    pub files: Vec<FilePatch>,
+}
+
+/// Metadata of the change, parsed from the email-style header of patches
+/// written by `git format-patch` or `jj diff --output-dir`.
+#[derive(Clone, Debug, Default)]
+pub struct PatchHeader {
+    /// Name and email of the author.
+    pub author: Option<(String, String)>,
+    pub date: Option<Timestamp>,
+    pub description: Option<String>,
+}
+
+/// Changes to a single file.
+#[derive(Clone, Debug)]
+pub struct FilePatch {
+    /// Path of the file before the change, or `None` if the file is added.
+    pub old_path: Option<RepoPathBuf>,
+    /// Path of the file after the change, or `None` if the file is deleted.
+    pub new_path: Option<RepoPathBuf>,
+    /// Whether the file at the old path is kept, i.e. the new file is a copy.
+    pub is_copy: bool,
+    /// Git mode of the file after the change if it's given, e.g. `100755`.
+    pub new_mode: Option<String>,
+    pub content: FilePatchContent,
+}
+
+#[derive(Clone, Debug)]
+pub enum FilePatchContent {
+    /// Hunks of a text file, which are empty if only the path or the mode of
+    /// the file changed.
+    Text(Vec<Hunk>),
+    /// Full contents of a binary file.
+    BinaryLiteral(Vec<u8>),
+    /// Contents of a binary file as a Git delta from the old contents.
+    BinaryDelta(Vec<u8>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Hunk {
+    /// 1-based number of the first old line of the hunk, or of the line
+    /// preceding the hunk if it has no old lines.
+    pub old_start: usize,
+    /// Lines including their newlines, if any.
+    pub lines: Vec<(HunkLineKind, Vec<u8>)>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HunkLineKind {
+    Context,
+    Removed,
+    Added,
+}
+
+impl FilePatch {
+    /// Returns the path of the file after the change, or before it if the file
+    /// is deleted.
+    pub fn path(&self) -> &RepoPath {
+        self.new_path
+            .as_ref()
+            .or(self.old_path.as_ref())
+            .expect("file patch should have a path")
+    }
+
+    /// Applies the changes to the `old` contents of the file.
+    pub fn apply(&self, old: &[u8]) -> Result<Vec<u8>, PatchError> {
+        let result = match &self.content {
+            FilePatchContent::Text(hunks) => apply_hunks(old, hunks),
+            FilePatchContent::BinaryLiteral(content) => Ok(content.clone()),
+            FilePatchContent::BinaryDelta(delta) => apply_git_delta(old, delta),
+        };
+        result.map_err(|message| PatchError::Apply {
+            path: self.path().as_internal_file_string().to_owned(),
+            message,
+        })
+    }
+}
+
+/// Lines of the patch being parsed.
+struct PatchLines<'a> {
+    lines: Vec<&'a [u8]>,
+    pos: usize,
+}
+
+impl<'a> PatchLines<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        PatchLines {
+            lines: data.split_inclusive(|&b| b == b'\n').collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.lines.get(self.pos).copied()
+    }
+
+    fn peek_str(&self) -> Option<&'a str> {
+        self.peek().map(line_to_str)
+    }
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let line = self.peek()?;
+        self.pos += 1;
+        Some(line)
+    }
+
+    fn error(&self, message: impl Into<String>) -> PatchError {
+        PatchError::Parse {
+            line: self.pos + 1,
+            message: message.into(),
+        }
+    }
+
+    /// Returns true if the next lines start the patch of a file.
+    fn at_file_patch(&self) -> bool {
+        let Some(line) = self.peek() else {
+            return false;
+        };
+        line.starts_with(b"diff --git ")
+            || (line.starts_with(b"--- ")
+                && self
+                    .lines
+                    .get(self.pos + 1)
+                    .is_some_and(|next| next.starts_with(b"+++ ")))
+    }
+}
+
+/// Returns the line without its newline, replacing invalid UTF-8 sequences.
+fn line_to_str(line: &[u8]) -> &str {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    std::str::from_utf8(line).unwrap_or("\u{fffd}")
+}
+
+/// Parses a unified diff or a Git-format patch.
+///
+/// Text before the first file patch is ignored, except for an email-style
+/// header. Paths are stripped of their first component, like `git apply`
+/// does.
+pub fn parse_patch(data: &[u8]) -> Result<Patch, PatchError> {
+    let mut lines = PatchLines::new(data);
+    let header = parse_patch_header(&mut lines);
+    let mut files = vec![];
+    while let Some(line) = lines.peek() {
+        if line.starts_with(b"diff --git ") {
+            files.push(parse_git_file_patch(&mut lines)?);
+        } else if lines.at_file_patch() {
+            files.push(parse_unified_file_patch(&mut lines)?);
+        } else {
+            // Skip the text between the file patches, such as a diffstat
+            lines.next();
+        }
+    }
+    if files.is_empty() && header.description.is_none() {
+        return Err(PatchError::Empty);
+    }
+    Ok(Patch { header, files })
+}
+
+fn parse_patch_header(lines: &mut PatchLines) -> PatchHeader {
+    let start = lines.pos;
+    // Separator line of mbox files
+    if lines.peek().is_some_and(|line| line.starts_with(b"From ")) {
+        lines.next();
+    }
+    let mut fields: Vec<(String, String)> = vec![];
+    while let Some(line) = lines.peek_str() {
+        if line.starts_with([' ', '\t']) && !fields.is_empty() {
+            // Folded header field
+            let value = &mut fields.last_mut().unwrap().1;
+            value.push(' ');
+            value.push_str(line.trim());
+        } else if let Some((name, value)) = line.split_once(':').filter(|(name, _)| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }) {
+            fields.push((name.to_ascii_lowercase(), value.trim().to_owned()));
+        } else {
+            break;
+        }
+        lines.next();
+    }
+    let is_email = fields
+        .iter()
+        .any(|(name, _)| matches!(name.as_str(), "from" | "subject"));
+    if !is_email {
+        lines.pos = start;
+        return PatchHeader::default();
+    }
+
+    let mut header = PatchHeader::default();
+    let mut subject = String::new();
+    for (name, value) in fields {
+        match name.as_str() {
+            "from" => header.author = Some(parse_author(&value)),
+            "date" => {
+                header.date = DateTime::parse_from_rfc2822(&value)
+                    .ok()
+                    .map(Timestamp::from_datetime);
+            }
+            "subject" => subject = strip_subject_prefix(&value).to_owned(),
+            _ => {}
+        }
+    }
+    // The body ends at the `---` line preceding the diffstat
+    let mut body = String::new();
+    while !lines.at_file_patch() {
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line_to_str(line);
+        if line == "---" {
+            break;
+        }
+        body.push_str(line);
+        body.push('\n');
+    }
+    let body = body.trim_matches('\n');
+    header.description = Some(if body.is_empty() {
+        format!("{subject}\n")
+    } else {
+        format!("{subject}\n\n{body}\n")
+    });
+    header
+}
+
+/// Parses `Name <email>` into its name and email.
+fn parse_author(value: &str) -> (String, String) {
+    match value.rsplit_once('<') {
+        Some((name, email)) => (
+            name.trim().trim_matches('"').to_owned(),
+            email.trim_end_matches('>').trim().to_owned(),
+        ),
+        None => (String::new(), value.to_owned()),
+    }
+}
+
+/// Strips the `[PATCH ...]` prefix added by `git format-patch`.
+fn strip_subject_prefix(subject: &str) -> &str {
+    subject
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .filter(|(tag, _)| tag.starts_with("PATCH"))
+        .map_or(subject, |(_, rest)| rest.trim_start())
+}
+
+fn parse_git_file_patch(lines: &mut PatchLines) -> Result<FilePatch, PatchError> {
+    let line = line_to_str(lines.next().unwrap());
+    let names = line.strip_prefix("diff --git ").unwrap_or_default();
+    let (mut old_path, mut new_path) = match split_git_diff_names(names) {
+        Some((old_name, new_name)) => (
+            Some(parse_patch_path(old_name).map_err(|err| lines.error(err))?),
+            Some(parse_patch_path(new_name).map_err(|err| lines.error(err))?),
+        ),
+        None => (None, None),
+    };
+    let mut is_new = false;
+    let mut is_deleted = false;
+    let mut is_copy = false;
+    let mut new_mode = None;
+    let mut is_binary = false;
+    while let Some(line) = lines.peek_str() {
+        if let Some(mode) = line.strip_prefix("new mode ") {
+            new_mode = Some(mode.to_owned());
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            is_new = true;
+            new_mode = Some(mode.to_owned());
+        } else if line.starts_with("deleted file mode ") {
+            is_deleted = true;
+        } else if let Some(path) = line
+            .strip_prefix("rename from ")
+            .or_else(|| line.strip_prefix("copy from "))
+        {
+            is_copy = line.starts_with("copy");
+            old_path = Some(parse_repo_path(path).map_err(|err| lines.error(err))?);
+        } else if let Some(path) = line
+            .strip_prefix("rename to ")
+            .or_else(|| line.strip_prefix("copy to "))
+        {
+            new_path = Some(parse_repo_path(path).map_err(|err| lines.error(err))?);
+        } else if line.starts_with("--- ") {
+            let (old, new) = parse_unified_file_header(lines)?;
+            old_path = old.or(old_path);
+            new_path = new.or(new_path);
+            break;
+        } else if line == "GIT binary patch" {
+            is_binary = true;
+            lines.next();
+            break;
+        } else if line.starts_with("Binary files ") {
+            return Err(lines.error("Binary patch without the contents of the files"));
+        } else if !(line.starts_with("old mode ")
+            || line.starts_with("similarity index ")
+            || line.starts_with("dissimilarity index ")
+            || line.starts_with("index "))
+        {
+            break;
+        }
+        lines.next();
+    }
+    if old_path.is_none() && new_path.is_none() {
+        return Err(lines.error("Can't determine the path of the file"));
+    }
+    if is_new {
+        old_path = None;
+    } else if old_path.is_none() {
+        old_path = new_path.clone();
+    }
+    if is_deleted {
+        new_path = None;
+    } else if new_path.is_none() {
+        new_path = old_path.clone();
+    }
+    let content = if is_binary {
+        parse_binary_patch(lines)?
+    } else {
+        FilePatchContent::Text(parse_hunks(lines)?)
+    };
+    Ok(FilePatch {
+        old_path,
+        new_path,
+        is_copy,
+        new_mode,
+        content,
+    })
+}
+
+/// Splits the names of a `diff --git a/<path> b/<path>` line. Since the names
+/// may contain spaces, they can only be split if they're the same.
+fn split_git_diff_names(names: &str) -> Option<(&str, &str)> {
+    if names.len() % 2 == 0 {
+        return None;
+    }
+    let (old_name, new_name) = names.split_at(names.len() / 2);
+    let new_name = new_name.strip_prefix(' ')?;
+    (old_name.get(2..) == new_name.get(2..)).then_some((old_name, new_name))
+}
+
+fn parse_unified_file_patch(lines: &mut PatchLines) -> Result<FilePatch, PatchError> {
+    let (old_path, new_path) = parse_unified_file_header(lines)?;
+    // Renames can't be expressed without Git's extended headers, so the old
+    // path is usually a backup of the new file, e.g. `file.orig`.
+    let (old_path, new_path) = match (old_path, new_path) {
+        (Some(_), Some(new_path)) => (Some(new_path.clone()), Some(new_path)),
+        (None, None) => return Err(lines.error("Both paths are /dev/null")),
+        paths => paths,
+    };
+    Ok(FilePatch {
+        old_path,
+        new_path,
+        is_copy: false,
+        new_mode: None,
+        content: FilePatchContent::Text(parse_hunks(lines)?),
+    })
+}
+
+/// Parses the `---` and `+++` lines of a file patch.
+fn parse_unified_file_header(
+    lines: &mut PatchLines,
+) -> Result<(Option<RepoPathBuf>, Option<RepoPathBuf>), PatchError> {
+    let mut parse_line = |prefix: &str| -> Result<Option<RepoPathBuf>, PatchError> {
+        let Some(name) = lines.peek_str().and_then(|line| line.strip_prefix(prefix)) else {
+            return Err(lines.error(format!("Expected a line starting with {prefix:?}")));
+        };
+        // Strip the timestamp added by `diff -u`
+        let name = name.split('\t').next().unwrap();
+        let path = if name == "/dev/null" {
+            None
+        } else {
+            Some(parse_patch_path(name).map_err(|err| lines.error(err))?)
+        };
+        lines.next();
+        Ok(path)
+    };
+    let old_path = parse_line("--- ")?;
+    let new_path = parse_line("+++ ")?;
+    Ok((old_path, new_path))
+}
+
+/// Parses a path of the patch, stripping its first component, which is `a/`
+/// or `b/` in Git diffs.
+fn parse_patch_path(name: &str) -> Result<RepoPathBuf, String> {
+    let (_, path) = name
+        .split_once('/')
+        .ok_or_else(|| format!("Path {name:?} has no leading directory to strip"))?;
+    parse_repo_path(path)
+}
+
+fn parse_repo_path(path: &str) -> Result<RepoPathBuf, String> {
+    if path.starts_with('"') {
+        return Err(format!("Quoted path {path} isn't supported"));
+    }
+    let is_valid = !path.is_empty()
+        && path
+            .split('/')
+            .all(|component| !matches!(component, "" | "." | ".."));
+    if !is_valid {
+        return Err(format!("Invalid path {path:?}"));
+    }
+    Ok(RepoPathBuf::from_internal_string(path))
+}
+
+fn parse_hunks(lines: &mut PatchLines) -> Result<Vec<Hunk>, PatchError> {
+    let mut hunks = vec![];
+    while let Some(header) = lines.peek_str().filter(|line| line.starts_with("@@ ")) {
+        let parse_error = || lines.error(format!("Invalid hunk header {header:?}"));
+        let ranges = header
+            .strip_prefix("@@ -")
+            .and_then(|rest| rest.split_once(" @@"))
+            .and_then(|(ranges, _)| ranges.split_once(" +"))
+            .ok_or_else(parse_error)?;
+        let (old_start, mut old_len) = parse_hunk_range(ranges.0).ok_or_else(parse_error)?;
+        let (_, mut new_len) = parse_hunk_range(ranges.1).ok_or_else(parse_error)?;
+        lines.next();
+        let mut hunk_lines: Vec<(HunkLineKind, Vec<u8>)> = vec![];
+        while old_len > 0 || new_len > 0 {
+            let Some(line) = lines.peek() else {
+                return Err(lines.error("Hunk is truncated"));
+            };
+            let (kind, text) = match line[0] {
+                b' ' => (HunkLineKind::Context, &line[1..]),
+                b'-' => (HunkLineKind::Removed, &line[1..]),
+                b'+' => (HunkLineKind::Added, &line[1..]),
+                // Context line whose space was stripped, e.g. by an editor
+                b'\n' | b'\r' => (HunkLineKind::Context, line),
+                b'\\' => {
+                    strip_last_newline(&mut hunk_lines);
+                    lines.next();
+                    continue;
+                }
+                _ => return Err(lines.error("Hunk is shorter than its header says")),
+            };
+            let (old_count, new_count) = match kind {
+                HunkLineKind::Context => (1, 1),
+                HunkLineKind::Removed => (1, 0),
+                HunkLineKind::Added => (0, 1),
+            };
+            (old_len, new_len) = old_len
+                .checked_sub(old_count)
+                .zip(new_len.checked_sub(new_count))
+                .ok_or_else(|| lines.error("Hunk is longer than its header says"))?;
+            hunk_lines.push((kind, text.to_vec()));
+            lines.next();
+        }
+        // "\ No newline at end of file" after the last line
+        if lines.peek().is_some_and(|line| line.starts_with(b"\\")) {
+            strip_last_newline(&mut hunk_lines);
+            lines.next();
+        }
+        hunks.push(Hunk {
+            old_start,
+            lines: hunk_lines,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Parses a `<start>[,<len>]` range of a hunk header.
+fn parse_hunk_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn strip_last_newline(hunk_lines: &mut [(HunkLineKind, Vec<u8>)]) {
+    if let Some((_, text)) = hunk_lines.last_mut() {
+        if text.ends_with(b"\n") {
+            text.pop();
+        }
+    }
+}
+
+fn parse_binary_patch(lines: &mut PatchLines) -> Result<FilePatchContent, PatchError> {
+    let (content, is_delta) = parse_binary_hunk(lines)?;
+    // Skip the reverse hunk
+    if lines
+        .peek_str()
+        .is_some_and(|line| line.starts_with("literal ") || line.starts_with("delta "))
+    {
+        parse_binary_hunk(lines)?;
+    }
+    Ok(if is_delta {
+        FilePatchContent::BinaryDelta(content)
+    } else {
+        FilePatchContent::BinaryLiteral(content)
+    })
+}
+
+/// Parses a `literal` or `delta` hunk of a binary patch, and returns its
+/// decoded data.
+fn parse_binary_hunk(lines: &mut PatchLines) -> Result<(Vec<u8>, bool), PatchError> {
+    let line = lines.peek_str().unwrap_or_default();
+    let (is_delta, size) = if let Some(size) = line.strip_prefix("literal ") {
+        (false, size)
+    } else if let Some(size) = line.strip_prefix("delta ") {
+        (true, size)
+    } else {
+        return Err(lines.error("Expected a literal or delta binary hunk"));
+    };
+    let size: usize = size
+        .parse()
+        .map_err(|_| lines.error(format!("Invalid size {size:?}")))?;
+    lines.next();
+    let mut compressed = vec![];
+    while let Some(line) = lines.peek_str() {
+        if line.is_empty() {
+            lines.next();
+            break;
+        }
+        decode_binary_line(line, &mut compressed).map_err(|err| lines.error(err))?;
+        lines.next();
+    }
+    let mut data = Vec::with_capacity(size);
+    ZlibDecoder::new(compressed.as_slice())
+        .read_to_end(&mut data)
+        .map_err(|err| lines.error(format!("Invalid compressed data: {err}")))?;
+    if data.len() != size {
+        return Err(lines.error(format!(
+            "Binary hunk has {} bytes instead of {size}",
+            data.len()
+        )));
+    }
+    Ok((data, is_delta))
+}
+
+/// Decodes a line of binary patch data, and appends the bytes to `out`.
+fn decode_binary_line(line: &str, out: &mut Vec<u8>) -> Result<(), String> {
+    let (len, encoded) = match line.as_bytes().split_first() {
+        Some((&c @ b'A'..=b'Z', encoded)) => (usize::from(c - b'A') + 1, encoded),
+        Some((&c @ b'a'..=b'z', encoded)) => (usize::from(c - b'a') + 27, encoded),
+        _ => return Err("Invalid length of binary data".to_owned()),
+    };
+    if encoded.len() != len.div_ceil(4) * 5 {
+        return Err("Binary data doesn't match its length".to_owned());
+    }
+    let mut decoded = Vec::with_capacity(encoded.len() / 5 * 4);
+    for group in encoded.chunks(5) {
+        let mut value: u32 = 0;
+        for &c in group {
+            let digit = BASE85_CHARS
+                .iter()
+                .position(|&b| b == c)
+                .ok_or_else(|| format!("Invalid character {:?} in binary data", char::from(c)))?;
+            value = value
+                .checked_mul(85)
+                .and_then(|value| value.checked_add(digit as u32))
+                .ok_or_else(|| "Invalid binary data".to_owned())?;
+        }
+        decoded.extend_from_slice(&value.to_be_bytes());
+    }
+    out.extend_from_slice(&decoded[..len]);
+    Ok(())
+}
+
+/// Applies the text `hunks` to the `old` contents.
+///
+/// The old lines of each hunk must match exactly, but they're searched around
+/// the position given in the hunk header if they moved.
+fn apply_hunks(old: &[u8], hunks: &[Hunk]) -> Result<Vec<u8>, String> {
+    let old_lines: Vec<&[u8]> = old.split_inclusive(|&b| b == b'\n').collect();
+    let mut new = vec![];
+    // Index of the first old line which isn't copied yet
+    let mut pos = 0;
+    // Number of lines the previous hunk was moved by
+    let mut offset: isize = 0;
+    for (i, hunk) in hunks.iter().enumerate() {
+        let old_hunk_lines = hunk
+            .lines
+            .iter()
+            .filter(|(kind, _)| *kind != HunkLineKind::Added)
+            .map(|(_, text)| text.as_slice())
+            .collect_vec();
+        let expected = if old_hunk_lines.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let start = expected
+            .saturating_add_signed(offset)
+            .clamp(pos, old_lines.len());
+        let found = find_lines(&old_lines, &old_hunk_lines, pos, start)
+            .ok_or_else(|| format!("hunk #{} doesn't match the file", i + 1))?;
+        offset = found as isize - expected as isize;
+        new.extend(old_lines[pos..found].concat());
+        for (kind, text) in &hunk.lines {
+            if *kind != HunkLineKind::Removed {
+                new.extend_from_slice(text);
+            }
+        }
+        pos = found + old_hunk_lines.len();
+    }
+    new.extend(old_lines[pos..].concat());
+    Ok(new)
+}
+
+/// Finds the `needle` lines in the `lines` at or after `min`, starting from
+/// the `start` position and going both ways.
+fn find_lines(lines: &[&[u8]], needle: &[&[u8]], min: usize, start: usize) -> Option<usize> {
+    let matches_at = |i: usize| lines.get(i..i + needle.len()) == Some(needle);
+    for distance in 0..=lines.len() {
+        let after = start + distance;
+        let before = start.checked_sub(distance).filter(|&i| i >= min);
+        if after <= lines.len() && matches_at(after) {
+            return Some(after);
+        }
+        if let Some(before) = before.filter(|&i| matches_at(i)) {
+            return Some(before);
+        }
+        if after > lines.len() && before.is_none() {
+            break;
+        }
+    }
+    None
+}
+
+/// Applies a binary delta in Git's format to the `base` contents.
+fn apply_git_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, String> {
+    let invalid = || "invalid binary delta".to_owned();
+    let mut delta = delta;
+    let base_size = read_delta_size(&mut delta).ok_or_else(invalid)?;
+    if base_size != base.len() {
+        return Err(format!(
+            "binary delta applies to {base_size} bytes, but the file has {} bytes",
+            base.len()
+        ));
+    }
+    let result_size = read_delta_size(&mut delta).ok_or_else(invalid)?;
+    let mut result = Vec::with_capacity(result_size);
+    while let Some((&op, rest)) = delta.split_first() {
+        delta = rest;
+        if op & 0x80 != 0 {
+            // Copy from the base
+            let mut read_bytes = |flags: u8, count: usize| -> Option<usize> {
+                let mut value = 0;
+                for i in 0..count {
+                    if flags & (1 << i) != 0 {
+                        let (&byte, rest) = delta.split_first()?;
+                        delta = rest;
+                        value |= usize::from(byte) << (8 * i);
+                    }
+                }
+                Some(value)
+            };
+            let offset = read_bytes(op, 4).ok_or_else(invalid)?;
+            let size = match read_bytes(op >> 4, 3).ok_or_else(invalid)? {
+                0 => 0x10000,
+                size => size,
+            };
+            let chunk = offset
+                .checked_add(size)
+                .and_then(|end| base.get(offset..end))
+                .ok_or_else(invalid)?;
+            result.extend_from_slice(chunk);
+        } else if op != 0 {
+            // Insert the following bytes
+            let size = usize::from(op);
+            let data = delta.get(..size).ok_or_else(invalid)?;
+            result.extend_from_slice(data);
+            delta = &delta[size..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    if result.len() != result_size {
+        return Err(invalid());
+    }
+    Ok(result)
+}
+
+fn read_delta_size(delta: &mut &[u8]) -> Option<usize> {
+    let mut value = 0;
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&byte, rest) = delta.split_first()?;
+        *delta = rest;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_binary_line() {
+        // Git's encoding of the empty content
+        let line = encode_binary_line(&[0x78, 0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(line, b"HcmV?d00001\n");
+        // Partial group and length characters
+        assert_eq!(encode_binary_line(&[0xff]), b"A{{R30\n");
+        assert_eq!(encode_binary_line(&[0; 27])[0], b'a');
+        assert_eq!(encode_binary_line(&[0; 52])[0], b'z');
+    }
+
+    #[test]
+    fn test_write_binary_patch() {
+        let mut out = vec![];
+        write_binary_patch(&mut out, b"", b"\0\x01binary\n").unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect_vec();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(
+            [lines[0], lines[1], lines[3], lines[4], lines[6]],
+            ["GIT binary patch", "literal 9", "", "literal 0", ""]
+        );
+        let decompress = |line: &str| {
+            let mut compressed = vec![];
+            decode_binary_line(line, &mut compressed).unwrap();
+            let mut data = vec![];
+            ZlibDecoder::new(compressed.as_slice())
+                .read_to_end(&mut data)
+                .unwrap();
+            data
+        };
+        assert_eq!(decompress(lines[2]), b"\0\x01binary\n");
+        assert_eq!(decompress(lines[5]), b"");
+    }
+
+    #[test]
+    fn test_git_blob_hash() {
+        assert_eq!(
+            git_blob_hash(b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+    }
+
+    fn repo_path_buf(value: &str) -> RepoPathBuf {
+        RepoPathBuf::from_internal_string(value)
+    }
+
+    #[test]
+    fn test_parse_patch_header() {
+        let patch = parse_patch(
+            indoc! {"
+                From 0123456789abcdef0123456789abcdef01234567 Mon Sep 17 00:00:00 2001
+                From: Some One <some.one@example.com>
+                Date: Tue, 12 Mar 2024 10:11:12 +0100
+                Subject: [PATCH 2/3] Fix the
+                 frobnicator
+
+                It was broken.
+                ---
+                 file | 2 +-
+
+                diff --git a/file b/file
+                index 257cc56..3bd1f0e 100644
+                --- a/file
+                +++ b/file
+                @@ -1 +1,2 @@
+                 foo
+                +bar
+            "}
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            patch.header.author,
+            Some(("Some One".to_owned(), "some.one@example.com".to_owned()))
+        );
+        assert_eq!(patch.header.date.unwrap().timestamp.0, 1_710_234_672_000);
+        assert_eq!(
+            patch.header.description.as_deref(),
+            Some("Fix the frobnicator\n\nIt was broken.\n")
+        );
+        assert_eq!(patch.files.len(), 1);
+        assert_eq!(patch.files[0].path(), repo_path_buf("file").as_ref());
+        assert_eq!(patch.files[0].apply(b"foo\n").unwrap(), b"foo\nbar\n");
+    }
+
+    #[test]
+    fn test_parse_patch_git_headers() {
+        let patch = parse_patch(
+            indoc! {"
+                diff --git a/old b/new
+                similarity index 100%
+                rename from old
+                rename to new
+                diff --git a/script b/script
+                old mode 100644
+                new mode 100755
+                diff --git a/added b/added
+                new file mode 100644
+                index 0000000..257cc56
+                --- /dev/null
+                +++ b/added
+                @@ -0,0 +1 @@
+                +foo
+                diff --git a/deleted b/deleted
+                deleted file mode 100644
+                index 257cc56..0000000
+                --- a/deleted
+                +++ /dev/null
+                @@ -1 +0,0 @@
+                -foo
+            "}
+            .as_bytes(),
+        )
+        .unwrap();
+        assert!(patch.header.description.is_none());
+        let paths = patch
+            .files
+            .iter()
+            .map(|file| (file.old_path.clone(), file.new_path.clone()))
+            .collect_vec();
+        assert_eq!(
+            paths,
+            [
+                (Some(repo_path_buf("old")), Some(repo_path_buf("new"))),
+                (Some(repo_path_buf("script")), Some(repo_path_buf("script"))),
+                (None, Some(repo_path_buf("added"))),
+                (Some(repo_path_buf("deleted")), None),
+            ]
+        );
+        assert_eq!(patch.files[1].new_mode.as_deref(), Some("100755"));
+        assert_eq!(patch.files[2].apply(b"").unwrap(), b"foo\n");
+        assert_eq!(patch.files[3].apply(b"foo\n").unwrap(), b"");
+    }
+
+    #[test]
+    fn test_parse_patch_no_newline_at_end() {
+        let patch = parse_patch(
+            indoc! {r"
+                --- a/file
+                +++ b/file
+                @@ -1,2 +1,2 @@
+                 foo
+                -bar
+                \ No newline at end of file
+                +baz
+            "}
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(patch.files[0].apply(b"foo\nbar").unwrap(), b"foo\nbaz\n");
+    }
+
+    #[test]
+    fn test_parse_patch_invalid() {
+        assert_matches!(parse_patch(b"just some text\n"), Err(PatchError::Empty));
+        assert_matches!(
+            parse_patch(b"--- a/file\n+++ b/file\n@@ -1,2 +1,2 @@\n foo\n"),
+            Err(PatchError::Parse { line: 5, .. })
+        );
+        assert_matches!(
+            parse_patch(b"--- a/../file\n+++ b/../file\n"),
+            Err(PatchError::Parse { line: 1, .. })
+        );
+    }
+
+    #[test]
+    fn test_apply_hunks_with_offset() {
+        let hunks = vec![Hunk {
+            old_start: 2,
+            lines: vec![
+                (HunkLineKind::Context, b"b\n".to_vec()),
+                (HunkLineKind::Removed, b"c\n".to_vec()),
+                (HunkLineKind::Added, b"C\n".to_vec()),
+            ],
+        }];
+        assert_eq!(apply_hunks(b"a\nb\nc\n", &hunks).unwrap(), b"a\nb\nC\n");
+        assert_eq!(
+            apply_hunks(b"x\ny\na\nb\nc\n", &hunks).unwrap(),
+            b"x\ny\na\nb\nC\n"
+        );
+        assert!(apply_hunks(b"a\nb\nd\n", &hunks).is_err());
+    }
+
+    #[test]
+    fn test_binary_patch_round_trip() {
+        let old = b"\0\x01old contents\n";
+        let new = b"\0\x02new contents\n";
+        let mut data = b"diff --git a/file b/file\nindex 1..2 100644\n".to_vec();
+        write_binary_patch(&mut data, old, new).unwrap();
+        let patch = parse_patch(&data).unwrap();
+        assert_matches!(
+            &patch.files[0].content,
+            FilePatchContent::BinaryLiteral(content) if content == new
+        );
+        assert_eq!(patch.files[0].apply(old).unwrap(), new);
+    }
+
+    #[test]
+    fn test_apply_git_delta() {
+        // Sizes 6 and 7, copy 3 bytes at offset 3, insert "ab", copy 2 bytes
+        let delta = [6, 7, 0x91, 3, 3, 2, b'a', b'b', 0x90, 2];
+        assert_eq!(apply_git_delta(b"foobar", &delta).unwrap(), b"barabfo");
+        assert!(apply_git_delta(b"foo", &delta).is_err());
+        assert!(apply_git_delta(b"foobar", &delta[..6]).is_err());
+    }
+}
@@ -75,6 +75,11 @@ impl<'repo> RevsetExpressionEvaluator<'repo> {
         self.expression = self.expression.intersection(other);
     }
 
+    /// Unites the underlying expression with the `other` expression.
+    pub fn union_with(&mut self, other: &Rc<RevsetExpression>) {
+        self.expression = self.expression.union(other);
+    }
+
     /// Evaluates the expression.
     pub fn evaluate(&self) -> Result<Box<dyn Revset + 'repo>, UserRevsetEvaluationError> {
         let symbol_resolver = default_symbol_resolver(self.repo, self.id_prefix_context);
@@ -0,0 +1,459 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lightweight syntax highlighting of source lines in diffs.
+//!
+//! This is a small lexer recognizing the keywords, strings, comments, and
+//! numbers of a few common languages. It isn't a grammar-based highlighter
+//! like syntect, which would add large dependencies and syntax definitions.
+//! Users wanting full highlighting can use an external diff tool instead.
+//!
+//! Each line is tokenized on its own since diff hunks don't necessarily start
+//! at a token boundary. Multi-line strings and comments are therefore only
+//! highlighted on the line where they start.
+
+use std::ops::Range;
+
+use jj_lib::repo_path::RepoPath;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+impl TokenKind {
+    /// Formatter label of this kind of token.
+    pub fn label(self) -> &'static str {
+        match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::String => "string",
+            TokenKind::Comment => "comment",
+            TokenKind::Number => "number",
+        }
+    }
+}
+
+/// Lexical rules of a language.
+#[derive(Debug)]
+pub struct Language {
+    pub name: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [u8],
+    keywords: &'static [&'static str],
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "class",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "nullptr",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "true",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "rust",
+        extensions: &["rs"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        // Single quotes are also used by lifetimes.
+        quotes: b"\"",
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ],
+    },
+    Language {
+        name: "c",
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: b"\"'",
+        keywords: C_KEYWORDS,
+    },
+    Language {
+        name: "go",
+        extensions: &["go"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: b"\"'`",
+        keywords: &[
+            "break",
+            "case",
+            "chan",
+            "const",
+            "continue",
+            "default",
+            "defer",
+            "else",
+            "false",
+            "fallthrough",
+            "for",
+            "func",
+            "go",
+            "goto",
+            "if",
+            "import",
+            "interface",
+            "map",
+            "nil",
+            "package",
+            "range",
+            "return",
+            "select",
+            "struct",
+            "switch",
+            "true",
+            "type",
+            "var",
+        ],
+    },
+    Language {
+        name: "java",
+        extensions: &["java", "kt", "scala"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: b"\"'",
+        keywords: &[
+            "abstract",
+            "boolean",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "continue",
+            "default",
+            "do",
+            "double",
+            "else",
+            "enum",
+            "extends",
+            "false",
+            "final",
+            "finally",
+            "float",
+            "for",
+            "fun",
+            "if",
+            "implements",
+            "import",
+            "instanceof",
+            "int",
+            "interface",
+            "long",
+            "new",
+            "null",
+            "object",
+            "package",
+            "private",
+            "protected",
+            "public",
+            "return",
+            "static",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "throws",
+            "true",
+            "try",
+            "val",
+            "var",
+            "void",
+            "while",
+        ],
+    },
+    Language {
+        name: "javascript",
+        extensions: &["js", "jsx", "mjs", "cjs", "ts", "tsx"],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: b"\"'`",
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "from",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "interface",
+            "let",
+            "new",
+            "null",
+            "of",
+            "return",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "type",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+    },
+    Language {
+        name: "python",
+        extensions: &["py", "pyi"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: b"\"'",
+        keywords: &[
+            "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+            "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+            "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+            "return", "try", "while", "with", "yield",
+        ],
+    },
+    Language {
+        name: "shell",
+        extensions: &["sh", "bash", "zsh"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: b"\"'",
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+            "in", "local", "return", "then", "until", "while",
+        ],
+    },
+    Language {
+        name: "toml",
+        extensions: &["toml"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: b"\"'",
+        keywords: &["false", "true"],
+    },
+];
+
+/// Looks up the language by the file extension of the `path`.
+pub fn language_for_path(path: &RepoPath) -> Option<&'static Language> {
+    let (_, file_name) = path.split()?;
+    let (_, extension) = file_name.as_str().rsplit_once('.')?;
+    LANGUAGES
+        .iter()
+        .find(|language| language.extensions.contains(&extension))
+}
+
+/// Splits the `line` into highlighted tokens. Returns the byte ranges of the
+/// tokens in order. The line terminator is never included.
+pub fn highlight_line(language: &Language, line: &[u8]) -> Vec<(Range<usize>, TokenKind)> {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < line.len() {
+        let rest = &line[pos..];
+        let is_line_comment = language.line_comments.iter().any(|prefix| {
+            // "#" is also used in e.g. "$#" and "${#var}" of shell scripts.
+            rest.starts_with(prefix.as_bytes())
+                && (*prefix != "#" || pos == 0 || line[pos - 1].is_ascii_whitespace())
+        });
+        if is_line_comment {
+            tokens.push((pos..line.len(), TokenKind::Comment));
+            break;
+        }
+        if let Some((start, end)) = language.block_comment {
+            if rest.starts_with(start.as_bytes()) {
+                let len = find_subslice(&rest[start.len()..], end.as_bytes())
+                    .map_or(rest.len(), |i| start.len() + i + end.len());
+                tokens.push((pos..pos + len, TokenKind::Comment));
+                pos += len;
+                continue;
+            }
+        }
+        let c = rest[0];
+        let len = if language.quotes.contains(&c) {
+            let len = string_literal_len(rest);
+            tokens.push((pos..pos + len, TokenKind::String));
+            len
+        } else if is_identifier_start(c) {
+            let len = rest.iter().take_while(|&&c| is_identifier_char(c)).count();
+            let word = &rest[..len];
+            if language.keywords.iter().any(|k| k.as_bytes() == word) {
+                tokens.push((pos..pos + len, TokenKind::Keyword));
+            }
+            len
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .iter()
+                .take_while(|&&c| is_identifier_char(c) || c == b'.')
+                .count();
+            tokens.push((pos..pos + len, TokenKind::Number));
+            len
+        } else {
+            1
+        };
+        pos += len;
+    }
+    tokens
+}
+
+/// Returns the length of the string literal starting with a quote. An
+/// unterminated literal extends to the end of the line.
+fn string_literal_len(s: &[u8]) -> usize {
+    let quote = s[0];
+    let mut i = 1;
+    while i < s.len() {
+        match s[i] {
+            b'\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    s.len()
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn is_identifier_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || !c.is_ascii()
+}
+
+fn is_identifier_char(c: u8) -> bool {
+    is_identifier_start(c) || c.is_ascii_digit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight<'a>(file_name: &str, line: &'a str) -> Vec<(&'a str, TokenKind)> {
+        let language = language_for_path(RepoPath::from_internal_string(file_name)).unwrap();
+        highlight_line(language, line.as_bytes())
+            .into_iter()
+            .map(|(range, kind)| (&line[range], kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_language_for_path() {
+        let name = |path| language_for_path(RepoPath::from_internal_string(path)).map(|l| l.name);
+        assert_eq!(name("src/lib.rs"), Some("rust"));
+        assert_eq!(name("dir.py/foo.h"), Some("c"));
+        assert_eq!(name("foo.tar.gz"), None);
+        assert_eq!(name("Makefile"), None);
+        assert_eq!(name("rs"), None);
+    }
+
+    #[test]
+    fn test_highlight_line() {
+        use TokenKind::*;
+        assert_eq!(
+            highlight("a.rs", "pub fn f() -> u32 { 42 } // answer\n"),
+            vec![
+                ("pub", Keyword),
+                ("fn", Keyword),
+                ("42", Number),
+                ("// answer", Comment),
+            ]
+        );
+        assert_eq!(
+            highlight("a.rs", r#"let s = "a \" // b"; /* c */ x1"#),
+            vec![
+                ("let", Keyword),
+                (r#""a \" // b""#, String),
+                ("/* c */", Comment),
+            ]
+        );
+        assert_eq!(
+            highlight("a.py", "if x: 'unterminated"),
+            vec![("if", Keyword), ("'unterminated", String)]
+        );
+        assert_eq!(
+            highlight("a.sh", "echo $# # count"),
+            vec![("# count", Comment)]
+        );
+        assert_eq!(
+            highlight("a.c", "/* unterminated"),
+            vec![("/* unterminated", Comment)]
+        );
+    }
+}
@@ -24,8 +24,8 @@ use crate::template_parser::{
 use crate::templater::{
     CoalesceTemplate, ConcatTemplate, ConditionalTemplate, IntoTemplate, LabelTemplate,
     ListPropertyTemplate, ListTemplate, Literal, PlainTextFormattedProperty, PropertyPlaceholder,
-    ReformatTemplate, SeparateTemplate, Template, TemplateProperty, TemplatePropertyError,
-    TemplatePropertyExt as _, TemplateRenderer, TimestampRange,
+    ReformatTemplate, SeparateTemplate, SizeHint, Template, TemplateProperty,
+    TemplatePropertyError, TemplatePropertyExt as _, TemplateRenderer, TimestampRange,
 };
 use crate::{text_util, time_util};
 
@@ -39,11 +39,15 @@ pub trait TemplateLanguage<'a> {
     ) -> Self::Property;
     fn wrap_boolean(property: impl TemplateProperty<Output = bool> + 'a) -> Self::Property;
     fn wrap_integer(property: impl TemplateProperty<Output = i64> + 'a) -> Self::Property;
+    fn wrap_integer_opt(
+        property: impl TemplateProperty<Output = Option<i64>> + 'a,
+    ) -> Self::Property;
     fn wrap_signature(property: impl TemplateProperty<Output = Signature> + 'a) -> Self::Property;
     fn wrap_timestamp(property: impl TemplateProperty<Output = Timestamp> + 'a) -> Self::Property;
     fn wrap_timestamp_range(
         property: impl TemplateProperty<Output = TimestampRange> + 'a,
     ) -> Self::Property;
+    fn wrap_size_hint(property: impl TemplateProperty<Output = SizeHint> + 'a) -> Self::Property;
 
     fn wrap_template(template: Box<dyn Template + 'a>) -> Self::Property;
     fn wrap_list_template(template: Box<dyn ListTemplate + 'a>) -> Self::Property;
@@ -81,9 +85,11 @@ macro_rules! impl_core_wrap_property_fns {
                 wrap_string_list(Vec<String>) => StringList,
                 wrap_boolean(bool) => Boolean,
                 wrap_integer(i64) => Integer,
+                wrap_integer_opt(Option<i64>) => IntegerOpt,
                 wrap_signature(jj_lib::backend::Signature) => Signature,
                 wrap_timestamp(jj_lib::backend::Timestamp) => Timestamp,
                 wrap_timestamp_range($crate::templater::TimestampRange) => TimestampRange,
+                wrap_size_hint($crate::templater::SizeHint) => SizeHint,
             }
         );
         fn wrap_template(
@@ -130,9 +136,11 @@ pub enum CoreTemplatePropertyKind<'a> {
     StringList(Box<dyn TemplateProperty<Output = Vec<String>> + 'a>),
     Boolean(Box<dyn TemplateProperty<Output = bool> + 'a>),
     Integer(Box<dyn TemplateProperty<Output = i64> + 'a>),
+    IntegerOpt(Box<dyn TemplateProperty<Output = Option<i64>> + 'a>),
     Signature(Box<dyn TemplateProperty<Output = Signature> + 'a>),
     Timestamp(Box<dyn TemplateProperty<Output = Timestamp> + 'a>),
     TimestampRange(Box<dyn TemplateProperty<Output = TimestampRange> + 'a>),
+    SizeHint(Box<dyn TemplateProperty<Output = SizeHint> + 'a>),
 
     // Both TemplateProperty and Template can represent a value to be evaluated
     // dynamically, which suggests that `Box<dyn Template + 'a>` could be
@@ -159,9 +167,13 @@ impl<'a> IntoTemplateProperty<'a> for CoreTemplatePropertyKind<'a> {
             }
             CoreTemplatePropertyKind::Boolean(property) => Some(property),
             CoreTemplatePropertyKind::Integer(_) => None,
+            CoreTemplatePropertyKind::IntegerOpt(property) => {
+                Some(Box::new(property.map(|opt| opt.is_some())))
+            }
             CoreTemplatePropertyKind::Signature(_) => None,
             CoreTemplatePropertyKind::Timestamp(_) => None,
             CoreTemplatePropertyKind::TimestampRange(_) => None,
+            CoreTemplatePropertyKind::SizeHint(_) => None,
             // Template types could also be evaluated to boolean, but it's less likely
             // to apply label() or .map() and use the result as conditional. It's also
             // unclear whether ListTemplate should behave as a "list" or a "template".
@@ -173,6 +185,11 @@ impl<'a> IntoTemplateProperty<'a> for CoreTemplatePropertyKind<'a> {
     fn try_into_integer(self) -> Option<Box<dyn TemplateProperty<Output = i64> + 'a>> {
         match self {
             CoreTemplatePropertyKind::Integer(property) => Some(property),
+            CoreTemplatePropertyKind::IntegerOpt(property) => {
+                Some(Box::new(property.and_then(|opt| {
+                    opt.ok_or_else(|| TemplatePropertyError("No Integer available".into()))
+                })))
+            }
             _ => None,
         }
     }
@@ -193,9 +210,11 @@ impl<'a> IntoTemplateProperty<'a> for CoreTemplatePropertyKind<'a> {
             CoreTemplatePropertyKind::StringList(property) => Some(property.into_template()),
             CoreTemplatePropertyKind::Boolean(property) => Some(property.into_template()),
             CoreTemplatePropertyKind::Integer(property) => Some(property.into_template()),
+            CoreTemplatePropertyKind::IntegerOpt(property) => Some(property.into_template()),
             CoreTemplatePropertyKind::Signature(property) => Some(property.into_template()),
             CoreTemplatePropertyKind::Timestamp(property) => Some(property.into_template()),
             CoreTemplatePropertyKind::TimestampRange(property) => Some(property.into_template()),
+            CoreTemplatePropertyKind::SizeHint(_) => None,
             CoreTemplatePropertyKind::Template(template) => Some(template),
             CoreTemplatePropertyKind::ListTemplate(template) => Some(template.into_template()),
         }
@@ -240,6 +259,7 @@ pub struct CoreTemplateBuildFnTable<'a, L: TemplateLanguage<'a> + ?Sized> {
     pub signature_methods: TemplateBuildMethodFnMap<'a, L, Signature>,
     pub timestamp_methods: TemplateBuildMethodFnMap<'a, L, Timestamp>,
     pub timestamp_range_methods: TemplateBuildMethodFnMap<'a, L, TimestampRange>,
+    pub size_hint_methods: TemplateBuildMethodFnMap<'a, L, SizeHint>,
 }
 
 pub fn merge_fn_map<'s, F>(base: &mut HashMap<&'s str, F>, extension: HashMap<&'s str, F>) {
@@ -261,6 +281,7 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
             signature_methods: builtin_signature_methods(),
             timestamp_methods: builtin_timestamp_methods(),
             timestamp_range_methods: builtin_timestamp_range_methods(),
+            size_hint_methods: builtin_size_hint_methods(),
         }
     }
 
@@ -273,6 +294,7 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
             signature_methods: HashMap::new(),
             timestamp_methods: HashMap::new(),
             timestamp_range_methods: HashMap::new(),
+            size_hint_methods: HashMap::new(),
         }
     }
 
@@ -285,6 +307,7 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
             signature_methods,
             timestamp_methods,
             timestamp_range_methods,
+            size_hint_methods,
         } = extension;
 
         merge_fn_map(&mut self.functions, functions);
@@ -294,6 +317,7 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
         merge_fn_map(&mut self.signature_methods, signature_methods);
         merge_fn_map(&mut self.timestamp_methods, timestamp_methods);
         merge_fn_map(&mut self.timestamp_range_methods, timestamp_range_methods);
+        merge_fn_map(&mut self.size_hint_methods, size_hint_methods);
     }
 
     /// Translates the function call node `function` by using this symbol table.
@@ -339,6 +363,14 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
                 let build = template_parser::lookup_method("Integer", table, function)?;
                 build(language, build_ctx, property, function)
             }
+            CoreTemplatePropertyKind::IntegerOpt(property) => {
+                let table = &self.integer_methods;
+                let build = template_parser::lookup_method("Integer", table, function)?;
+                let inner_property = property.and_then(|opt| {
+                    opt.ok_or_else(|| TemplatePropertyError("No Integer available".into()))
+                });
+                build(language, build_ctx, Box::new(inner_property), function)
+            }
             CoreTemplatePropertyKind::Signature(property) => {
                 let table = &self.signature_methods;
                 let build = template_parser::lookup_method("Signature", table, function)?;
@@ -354,6 +386,11 @@ impl<'a, L: TemplateLanguage<'a> + ?Sized> CoreTemplateBuildFnTable<'a, L> {
                 let build = template_parser::lookup_method("TimestampRange", table, function)?;
                 build(language, build_ctx, property, function)
             }
+            CoreTemplatePropertyKind::SizeHint(property) => {
+                let table = &self.size_hint_methods;
+                let build = template_parser::lookup_method("SizeHint", table, function)?;
+                build(language, build_ctx, property, function)
+            }
             CoreTemplatePropertyKind::Template(_) => {
                 // TODO: migrate to table?
                 Err(TemplateParseError::no_such_method("Template", function))
@@ -743,6 +780,38 @@ fn builtin_timestamp_range_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
     map
 }
 
+fn builtin_size_hint_methods<'a, L: TemplateLanguage<'a> + ?Sized>(
+) -> TemplateBuildMethodFnMap<'a, L, SizeHint> {
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map = TemplateBuildMethodFnMap::<L, SizeHint>::new();
+    map.insert("lower", |_language, _build_ctx, self_property, function| {
+        template_parser::expect_no_arguments(function)?;
+        let out_property = self_property.and_then(|(lower, _)| Ok(i64::try_from(lower)?));
+        Ok(L::wrap_integer(out_property))
+    });
+    map.insert("upper", |_language, _build_ctx, self_property, function| {
+        template_parser::expect_no_arguments(function)?;
+        let out_property =
+            self_property.and_then(|(_, upper)| Ok(upper.map(i64::try_from).transpose()?));
+        Ok(L::wrap_integer_opt(out_property))
+    });
+    map.insert("exact", |_language, _build_ctx, self_property, function| {
+        template_parser::expect_no_arguments(function)?;
+        let out_property = self_property.and_then(|(lower, upper)| {
+            let exact = (Some(lower) == upper).then_some(lower);
+            Ok(exact.map(i64::try_from).transpose()?)
+        });
+        Ok(L::wrap_integer_opt(out_property))
+    });
+    map.insert("zero", |_language, _build_ctx, self_property, function| {
+        template_parser::expect_no_arguments(function)?;
+        let out_property = self_property.map(|(_, upper)| upper == Some(0));
+        Ok(L::wrap_boolean(out_property))
+    });
+    map
+}
+
 fn build_list_template_method<'a, L: TemplateLanguage<'a> + ?Sized>(
     language: &L,
     build_ctx: &BuildContext<L::Property>,
@@ -882,6 +951,18 @@ fn builtin_functions<'a, L: TemplateLanguage<'a> + ?Sized>() -> TemplateBuildFun
             });
         Ok(L::wrap_template(Box::new(template)))
     });
+    map.insert("wrap", map["fill"]);
+    map.insert("truncate", |language, build_ctx, function| {
+        let [width_node, content_node] = template_parser::expect_exact_arguments(function)?;
+        let width = expect_usize_expression(language, build_ctx, width_node)?;
+        let content = expect_template_expression(language, build_ctx, content_node)?;
+        let template =
+            ReformatTemplate::new(content, move |formatter, recorded| match width.extract() {
+                Ok(width) => text_util::write_truncated(formatter.as_mut(), recorded, width),
+                Err(err) => formatter.handle_error(err),
+            });
+        Ok(L::wrap_template(Box::new(template)))
+    });
     map.insert("indent", |language, build_ctx, function| {
         let [prefix_node, content_node] = template_parser::expect_exact_arguments(function)?;
         let prefix = expect_template_expression(language, build_ctx, prefix_node)?;
@@ -1932,6 +2013,53 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_wrap_function() {
+        let env = TestTemplateEnv::new();
+        insta::assert_snapshot!(
+            env.render_ok(r#"wrap(20, "The quick fox jumps over the lazy dog\n")"#),
+            @r###"
+        The quick fox jumps
+        over the lazy dog
+        "###);
+    }
+
+    #[test]
+    fn test_truncate_function() {
+        let mut env = TestTemplateEnv::new();
+        env.add_color("error", crossterm::style::Color::DarkRed);
+
+        insta::assert_snapshot!(
+            env.render_ok(r#"truncate(31, "The quick fox jumps over the " ++
+                                      label("error", "lazy") ++ " dog\n")"#),
+            @"The quick fox jumps over the [38;5;1mla[39m");
+
+        // Each line is truncated, and a label can be chopped
+        insta::assert_snapshot!(
+            env.render_ok(r#"truncate(7, "The quick\nfox " ++
+                                     label("error", "jumps over") ++ "\nthe lazy dog\n")"#),
+            @r###"
+        The qui
+        fox [38;5;1mjum[39m
+        the laz
+        "###);
+
+        // Truncating to 0 leaves empty lines
+        insta::assert_snapshot!(
+            env.render_ok(r#"truncate(0, "The quick\nfox\n") ++ "end""#),
+            @r###"
+
+
+        end
+        "###);
+
+        // Truncating to negative width is an error
+        insta::assert_snapshot!(
+            env.render_ok(r#"truncate(-10, "The quick fox jumps over the " ++
+                                      label("error", "lazy") ++ " dog\n")"#),
+            @"[38;5;1m<Error: out of range integral type conversion attempted>[39m");
+    }
+
     #[test]
     fn test_indent_function() {
         let mut env = TestTemplateEnv::new();
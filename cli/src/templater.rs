@@ -120,6 +120,10 @@ impl TimestampRange {
     }
 }
 
+/// Lower and upper bounds of the size of a collection, like
+/// `Iterator::size_hint()`.
+pub type SizeHint = (usize, Option<usize>);
+
 impl Template for TimestampRange {
     fn format(&self, formatter: &mut TemplateFormatter) -> io::Result<()> {
         self.start.format(formatter)?;
@@ -0,0 +1,139 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal line-based text editor used when `ui.editor` is `:builtin`.
+//!
+//! The text is split into blocks separated by "JJ: " comment lines, which are
+//! kept as they are. Each block is printed and can be kept or replaced by
+//! typing new lines, so that the comments which identify the blocks (like the
+//! `JJ: describe` headers) are preserved.
+//!
+//! The lines are read from stdin even if it isn't a terminal, so that the text
+//! can be piped in. If the input ends before a line with only ".", the lines
+//! read so far replace the block. Empty input is refused if stdin isn't a
+//! terminal, since it would silently clear the text.
+
+use std::io::{self, BufRead as _, Write as _};
+use std::path::Path;
+use std::{fs, mem};
+
+use crate::command_error::{user_error, user_error_with_message, CommandError};
+use crate::ui::Ui;
+
+/// Value of `ui.editor` selecting the built-in editor.
+pub const BUILTIN_EDITOR_NAME: &str = ":builtin";
+
+/// Edits the file at `path` in the terminal.
+pub fn edit_file_builtin(ui: &Ui, path: &Path) -> Result<(), CommandError> {
+    let content = fs::read_to_string(path).map_err(|err| {
+        user_error_with_message(format!(r#"Failed to read file "{}""#, path.display()), err)
+    })?;
+    let mut input = io::stdin().lock();
+    // Only check piped input, since reading from a terminal would block before
+    // the text is printed
+    if !Ui::can_prompt() {
+        let input_is_empty = input
+            .fill_buf()
+            .map_err(|err| user_error_with_message("Failed to run the built-in editor", err))?
+            .is_empty();
+        if input_is_empty {
+            return Err(user_error(
+                "Cannot run the built-in editor with empty input since the output is not \
+                 connected to a terminal",
+            ));
+        }
+    }
+    let edited = edit_text(ui, &content, &mut input)
+        .map_err(|err| user_error_with_message("Failed to run the built-in editor", err))?;
+    fs::write(path, edited).map_err(|err| {
+        user_error_with_message(format!(r#"Failed to write file "{}""#, path.display()), err)
+    })?;
+    Ok(())
+}
+
+fn edit_text(ui: &Ui, content: &str, input: &mut dyn io::BufRead) -> io::Result<String> {
+    let mut edited = String::new();
+    let mut comments = vec![];
+    let mut block = vec![];
+    for line in content.lines() {
+        if line.starts_with("JJ: ") {
+            if !block.is_empty() {
+                edit_block(ui, &mem::take(&mut comments), &block, input, &mut edited)?;
+                block.clear();
+            }
+            comments.push(line);
+            edited.push_str(line);
+            edited.push('\n');
+        } else {
+            block.push(line);
+        }
+    }
+    if !block.is_empty() || edited.is_empty() {
+        edit_block(ui, &comments, &block, input, &mut edited)?;
+    }
+    Ok(edited)
+}
+
+/// Prints the `comments` preceding the `block` and the lines of the block, and
+/// appends the lines entered by the user to `edited`, or the block if only "."
+/// was entered.
+fn edit_block(
+    ui: &Ui,
+    comments: &[&str],
+    block: &[&str],
+    input: &mut dyn io::BufRead,
+    edited: &mut String,
+) -> io::Result<()> {
+    let mut stdout = ui.stdout();
+    for comment in comments {
+        writeln!(stdout, "{comment}")?;
+    }
+    for line in block {
+        if line.is_empty() {
+            writeln!(stdout, "|")?;
+        } else {
+            writeln!(stdout, "| {line}")?;
+        }
+    }
+    writeln!(
+        stdout,
+        r#"Enter the new text followed by a line with only ".", or only "." to keep it:"#
+    )?;
+    stdout.flush()?;
+    let mut new_lines = vec![];
+    let mut terminated = false;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line == "." {
+            terminated = true;
+            break;
+        }
+        new_lines.push(line.to_owned());
+    }
+    let lines = if new_lines.is_empty() && terminated {
+        block.iter().map(|line| line.to_string()).collect()
+    } else {
+        new_lines
+    };
+    for line in lines {
+        edited.push_str(&line);
+        edited.push('\n');
+    }
+    Ok(())
+}
@@ -259,6 +259,79 @@ pub fn write_wrapped(
     })
 }
 
+/// Returns the end offset and display width of the longest prefix of `line`
+/// that fits in `max_width`.
+///
+/// Invalid UTF-8 sequences are counted as 1-width characters.
+fn truncate_end_pos_bytes(line: &[u8], max_width: usize) -> (usize, usize) {
+    let mut acc_width = 0;
+    let mut offset = 0;
+    while offset < line.len() {
+        let (valid, invalid_len) = match std::str::from_utf8(&line[offset..]) {
+            Ok(valid) => (valid, 0),
+            Err(err) => {
+                let valid = std::str::from_utf8(&line[offset..][..err.valid_up_to()]).unwrap();
+                let rest_len = line.len() - offset - valid.len();
+                (valid, err.error_len().unwrap_or(rest_len))
+            }
+        };
+        for (i, c) in valid.char_indices() {
+            let new_width = acc_width + c.width().unwrap_or(0);
+            if new_width > max_width {
+                return (offset + i, acc_width);
+            }
+            acc_width = new_width;
+        }
+        offset += valid.len();
+        if invalid_len > 0 {
+            if acc_width + 1 > max_width {
+                return (offset, acc_width);
+            }
+            acc_width += 1;
+            offset += invalid_len;
+        }
+    }
+    (line.len(), acc_width)
+}
+
+/// Truncates lines at the given width preserving labels.
+///
+/// Unlike [`write_wrapped()`], the overflowing part of each line is dropped.
+pub fn write_truncated(
+    formatter: &mut dyn Formatter,
+    recorded_content: &FormatRecorder,
+    width: usize,
+) -> io::Result<()> {
+    let data = recorded_content.data();
+    // Ranges of bytes to be kept, including the newline characters.
+    let mut kept_ranges = Vec::new();
+    let mut line_start = 0;
+    for line in data.split_inclusive(|&c| c == b'\n') {
+        let content_len = line.strip_suffix(b"\n").unwrap_or(line).len();
+        let (end, _) = truncate_end_pos_bytes(&line[..content_len], width);
+        kept_ranges.push(line_start..line_start + end);
+        if content_len < line.len() {
+            kept_ranges.push(line_start + content_len..line_start + line.len());
+        }
+        line_start += line.len();
+    }
+    let mut kept_ranges = kept_ranges.into_iter().peekable();
+    recorded_content.replay_with(formatter, |formatter, data_range| {
+        while let Some(kept_range) = kept_ranges.peek() {
+            let start = cmp::max(data_range.start, kept_range.start);
+            let end = cmp::min(data_range.end, kept_range.end);
+            if start < end {
+                formatter.write_all(&data[start..end])?;
+            }
+            if data_range.end <= kept_range.end {
+                break; // No more kept bytes in this data range
+            }
+            kept_ranges.next().unwrap();
+        }
+        Ok(())
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write as _;
@@ -578,6 +651,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_truncate_end_pos_bytes() {
+        assert_eq!(truncate_end_pos_bytes(b"", 0), (0, 0));
+        assert_eq!(truncate_end_pos_bytes(b"abc", 3), (3, 3));
+        assert_eq!(truncate_end_pos_bytes(b"abc", 2), (2, 2));
+        assert_eq!(truncate_end_pos_bytes(b"abc", 0), (0, 0));
+        // East Asian characters (char.width() == 2)
+        let text = "一二三".as_bytes();
+        assert_eq!(truncate_end_pos_bytes(text, 6), (9, 6));
+        assert_eq!(truncate_end_pos_bytes(text, 5), (6, 4));
+        assert_eq!(truncate_end_pos_bytes(text, 1), (0, 0));
+        // Invalid UTF-8 byte is counted as 1-width character
+        assert_eq!(truncate_end_pos_bytes(b"a\xffbc", 4), (4, 4));
+        assert_eq!(truncate_end_pos_bytes(b"a\xffbc", 2), (2, 2));
+        assert_eq!(truncate_end_pos_bytes(b"a\xffbc", 1), (1, 1));
+    }
+
+    #[test]
+    fn test_write_truncated() {
+        // Truncate each line of single label chunk
+        let mut recorder = FormatRecorder::new();
+        recorder.push_label("red").unwrap();
+        write!(recorder, "foo bar baz\nqux quux\n").unwrap();
+        recorder.pop_label().unwrap();
+        insta::assert_snapshot!(
+            format_colored(|formatter| write_truncated(formatter, &recorder, 7)),
+            @r###"
+        [38;5;1mfoo bar[39m
+        [38;5;1mqux quu[39m
+        "###
+        );
+
+        // Truncate at label boundary
+        let mut recorder = FormatRecorder::new();
+        for (i, word) in ["foo ", "bar ", "baz\n", "qux ", "quux"].iter().enumerate() {
+            recorder.push_label(["red", "cyan"][i & 1]).unwrap();
+            write!(recorder, "{word}").unwrap();
+            recorder.pop_label().unwrap();
+        }
+        insta::assert_snapshot!(
+            format_colored(|formatter| write_truncated(formatter, &recorder, 4)),
+            @r###"
+        [38;5;1mfoo [39m[38;5;1m[39m
+        [38;5;6mqux [39m
+        "###
+        );
+
+        // Empty lines and zero width should not cause panic
+        let mut recorder = FormatRecorder::new();
+        writeln!(recorder, "\nfoo\n").unwrap();
+        assert_eq!(
+            format_plain_text(|formatter| write_truncated(formatter, &recorder, 0)),
+            "\n\n\n"
+        );
+    }
+
     #[test]
     fn test_write_wrapped_leading_labeled_whitespace() {
         let mut recorder = FormatRecorder::new();
@@ -10,6 +10,8 @@ fn test_no_forgotten_test_files() {
 
 mod test_abandon_command;
 mod test_alias;
+mod test_api_command;
+mod test_backout_command;
 mod test_branch_command;
 mod test_builtin_aliases;
 mod test_cat_command;
@@ -25,6 +27,8 @@ mod test_diff_command;
 mod test_diffedit_command;
 mod test_duplicate_command;
 mod test_edit_command;
+mod test_file_command;
+mod test_files_command;
 mod test_generate_md_cli_help;
 mod test_git_clone;
 mod test_git_colocated;
@@ -36,34 +40,44 @@ mod test_git_remotes;
 mod test_git_submodule;
 mod test_gitignores;
 mod test_global_opts;
+mod test_hg_command;
+mod test_hooks;
 mod test_immutable_commits;
 mod test_init_command;
 mod test_interdiff_command;
 mod test_log_command;
+mod test_merge_drivers;
 mod test_move_command;
 mod test_new_command;
 mod test_next_prev_commands;
+mod test_note_command;
 mod test_obslog_command;
 mod test_operations;
 mod test_parallelize_command;
+mod test_patch_command;
 mod test_rebase_command;
 mod test_repo_change_report;
 mod test_resolve_command;
 mod test_restore_command;
 mod test_revset_output;
 mod test_root;
+mod test_run_command;
 mod test_shell_completion;
+mod test_shelve_command;
 mod test_show_command;
 mod test_sparse_command;
 mod test_split_command;
 mod test_squash_command;
 mod test_status_command;
+mod test_sync_command;
 mod test_tag_command;
 mod test_templater;
 mod test_tree_level_conflicts;
 mod test_undo;
+mod test_unhide_command;
 mod test_unsquash_command;
 mod test_untrack_command;
 mod test_util_command;
+mod test_web_command;
 mod test_working_copy;
 mod test_workspaces;
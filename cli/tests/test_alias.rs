@@ -254,16 +254,136 @@ fn test_alias_invalid_definition() {
     test_env.add_config(
         r#"[aliases]
     non-list = 5
-    non-string-list = [[]]
+    non-string-list = [[[]]]
+    unknown-key = { args = ["log"], interpolated = true }
     "#,
     );
     let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["non-list"]);
     insta::assert_snapshot!(stderr, @r###"
-    Error: Alias definition for "non-list" must be a string list
+    Error: Alias definition for "non-list" must be a string list, a list of string lists, or a table with `args` and `interpolate`
     "###);
     let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["non-string-list"]);
     insta::assert_snapshot!(stderr, @r###"
-    Error: Alias definition for "non-string-list" must be a string list
+    Error: Alias definition for "non-string-list" must be a string list, a list of string lists, or a table with `args` and `interpolate`
+    "###);
+    let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["unknown-key"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Alias definition for "unknown-key" must be a string list, a list of string lists, or a table with `args` and `interpolate`
+    "###);
+}
+
+#[test]
+fn test_alias_interpolation() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+
+    test_env.add_config(
+        r#"[aliases]
+    show-desc = { args = ["log", "--no-graph", "-r", "$1", "-T", 'description'], interpolate = true }
+    range = { args = ["log", "--no-graph", "-r", "$1::$2", "-T", 'description'], interpolate = true }
+    all-args = { args = ["log", "--no-graph", "$@", "-T", 'description'], interpolate = true }
+    dollar = { args = ["log", "--no-graph", "-r", "@", "-T", '"$$1 $x\n"'], interpolate = true }
+    literal = ["log", "--no-graph", "-r", "@", "-T", '"$1 $$\n"']
+    "#,
+    );
+    // Positional arguments are substituted, and not appended
+    let stdout = test_env.jj_cmd_success(&repo_path, &["show-desc", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    first
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["range", "@-", "@"]);
+    insta::assert_snapshot!(stdout, @r###"
+    second
+    first
+    "###);
+    // Flags are arguments too
+    let stdout = test_env.jj_cmd_success(&repo_path, &["all-args", "-r", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    first
+    "###);
+    // `$$` is an escaped `$`
+    let stdout = test_env.jj_cmd_success(&repo_path, &["dollar"]);
+    insta::assert_snapshot!(stdout, @r###"
+    $1 $x
+    "###);
+    // Referring to a missing argument is an error
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["range", "@-"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Alias "range" refers to "$2", but got 1 arguments
+    "###);
+    // Aliases which don't opt in are left as they are
+    let stdout = test_env.jj_cmd_success(&repo_path, &["literal"]);
+    insta::assert_snapshot!(stdout, @r###"
+    $1 $$
+    "###);
+}
+
+#[test]
+fn test_alias_chain() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.add_config(
+        r#"[aliases]
+    desc-new = { args = [["describe", "-m", "$1"], ["new"], ["descs"]], interpolate = true }
+    descs = ["log", "--no-graph", "-r", "..@-", "-T", 'description.first_line() ++ "\n"']
+    fail-first = [["log", "-r", "nonexistent"], ["new"]]
+    recursive = [["log", "-r", "@"], ["recursive"]]
+    "#,
+    );
+    // Each command of the chain runs in turn, and may itself be an alias
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["desc-new", "first"]);
+    insta::assert_snapshot!(stdout, @r###"
+    first
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm 69542c19 (empty) first
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Working copy now at: rlvkpnrz b88fb4e5 (empty) (no description set)
+    Parent commit      : qpvuntsm 69542c19 (empty) first
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["desc-new", "second"]);
+    insta::assert_snapshot!(stdout, @r###"
+    second
+    first
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz 5c52832c (empty) second
+    Parent commit      : qpvuntsm 69542c19 (empty) first
+    Working copy now at: kkmpptxz 885e101c (empty) (no description set)
+    Parent commit      : rlvkpnrz 5c52832c (empty) second
+    "###);
+
+    // Global args before the alias apply to all of the commands
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &["-R", "repo", "--quiet", "desc-new", "third"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    third
+    second
+    first
+    "###);
+
+    // The chain stops at the first failing command
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["fail-first"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Revision "nonexistent" doesn't exist
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["descs"]);
+    insta::assert_snapshot!(stdout, @r###"
+    third
+    second
+    first
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["recursive"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Recursive alias definition involving "recursive"
     "###);
 }
 
@@ -0,0 +1,302 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use itertools::Itertools as _;
+
+use crate::common::TestEnvironment;
+
+/// Sends the `requests` to `jj api` and returns the responses, pretty-printed.
+fn call_api(test_env: &TestEnvironment, repo_path: &Path, requests: &[&str]) -> String {
+    let (stdout, _stderr) = test_env.jj_cmd_stdin_ok(repo_path, &["api"], &requests.join("\n"));
+    stdout
+        .lines()
+        .map(|line| {
+            let mut response: serde_json::Value = serde_json::from_str(line).unwrap();
+            // Operation ids are checked separately
+            if let Some(result) = response.get_mut("result") {
+                if let Some(op_id) = result.get_mut("operation_id") {
+                    *op_id = op_id.as_str().unwrap()[..12].into();
+                }
+            }
+            serde_json::to_string_pretty(&response).unwrap()
+        })
+        .join("\n")
+}
+
+#[test]
+fn test_api_queries() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    std::fs::write(repo_path.join("file"), "b\n").unwrap();
+
+    let output = call_api(
+        &test_env,
+        &repo_path,
+        &[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "version"}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "log", "params": {"revset": "::@", "template": "description.first_line()"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "evaluate_revset", "params": {"revset": "@-"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "status"}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "diff", "params": {"revision": "@", "format": "summary"}}"#,
+        ],
+    );
+    insta::assert_snapshot!(output, @r###"
+    {
+      "id": 1,
+      "jsonrpc": "2.0",
+      "result": {
+        "api_version": 1
+      }
+    }
+    {
+      "id": 2,
+      "jsonrpc": "2.0",
+      "result": {
+        "commits": [
+          {
+            "change_id": "rlvkpnrzqnoowoytxnquwvuryrwnrmlp",
+            "commit_id": "e62ece7c6dc1b3a0832f57567c6e1afefe2cb581",
+            "description": "",
+            "output": ""
+          },
+          {
+            "change_id": "qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu",
+            "commit_id": "8b7541789330b742549955794e96be0e6862138d",
+            "description": "first\n",
+            "output": "first"
+          },
+          {
+            "change_id": "zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz",
+            "commit_id": "0000000000000000000000000000000000000000",
+            "description": "",
+            "output": ""
+          }
+        ],
+        "operation_id": "b5ccf1aaf7ee"
+      }
+    }
+    {
+      "id": 3,
+      "jsonrpc": "2.0",
+      "result": {
+        "commits": [
+          {
+            "change_id": "qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu",
+            "commit_id": "8b7541789330b742549955794e96be0e6862138d",
+            "description": "first\n"
+          }
+        ],
+        "operation_id": "b5ccf1aaf7ee"
+      }
+    }
+    {
+      "id": 4,
+      "jsonrpc": "2.0",
+      "result": {
+        "changes": [
+          {
+            "path": "file",
+            "status": "modified"
+          }
+        ],
+        "conflicts": [],
+        "operation_id": "b5ccf1aaf7ee",
+        "parents": [
+          {
+            "change_id": "qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu",
+            "commit_id": "8b7541789330b742549955794e96be0e6862138d",
+            "description": "first\n"
+          }
+        ],
+        "working_copy": {
+          "change_id": "rlvkpnrzqnoowoytxnquwvuryrwnrmlp",
+          "commit_id": "e62ece7c6dc1b3a0832f57567c6e1afefe2cb581",
+          "description": ""
+        }
+      }
+    }
+    {
+      "id": 5,
+      "jsonrpc": "2.0",
+      "result": {
+        "commit": {
+          "change_id": "rlvkpnrzqnoowoytxnquwvuryrwnrmlp",
+          "commit_id": "e62ece7c6dc1b3a0832f57567c6e1afefe2cb581",
+          "description": ""
+        },
+        "diff": "M file\n",
+        "operation_id": "b5ccf1aaf7ee"
+      }
+    }
+    "###);
+    let op_id = test_env.current_operation_id(&repo_path);
+    insta::assert_snapshot!(&op_id[..12], @"b5ccf1aaf7ee");
+}
+
+#[test]
+fn test_api_mutations() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+
+    let output = call_api(
+        &test_env,
+        &repo_path,
+        &[
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "describe", "params": {"message": "first"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "new", "params": {"message": "second"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "squash"}"#,
+        ],
+    );
+    insta::assert_snapshot!(output, @r###"
+    {
+      "id": 1,
+      "jsonrpc": "2.0",
+      "result": {
+        "commit": {
+          "change_id": "qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu",
+          "commit_id": "8b7541789330b742549955794e96be0e6862138d",
+          "description": "first\n"
+        },
+        "operation_id": "2043de5e5da9"
+      }
+    }
+    {
+      "id": 2,
+      "jsonrpc": "2.0",
+      "result": {
+        "commit": {
+          "change_id": "rlvkpnrzqnoowoytxnquwvuryrwnrmlp",
+          "commit_id": "fa6298528ee8dd6bd6aae1a9f340ad2fdb841566",
+          "description": "second\n"
+        },
+        "operation_id": "8f4e05878a69"
+      }
+    }
+    {
+      "id": 3,
+      "jsonrpc": "2.0",
+      "result": {
+        "commit": {
+          "change_id": "qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu",
+          "commit_id": "d50bb1c5ed02b6e3844825d8006a7190fcfb549a",
+          "description": "first\n\nsecond\n"
+        },
+        "operation_id": "5a1563fb7cd0"
+      }
+    }
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @
+    ◉  first
+    │
+    │  second
+    ◉
+    "###);
+}
+
+#[test]
+fn test_api_errors() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // The server keeps running after an error
+    let output = call_api(
+        &test_env,
+        &repo_path,
+        &[
+            r#"{"jsonrpc": "2.0", "id": 1, "method""#,
+            r#"{"id": 2}"#,
+            r#"{"jsonrpc": "1.0", "id": 3, "method": "version"}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "unknown"}"#,
+            r#"{"jsonrpc": "2.0", "id": 5, "method": "diff", "params": {"revisions": "@"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 6, "method": "evaluate_revset", "params": {"revset": "nonexistent"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 7, "method": "describe", "params": {"revision": "root()", "message": "root"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "version"}"#,
+        ],
+    );
+    insta::assert_snapshot!(output, @r###"
+    {
+      "error": {
+        "code": -32700,
+        "message": "EOF while parsing an object at line 1 column 36"
+      },
+      "id": null,
+      "jsonrpc": "2.0"
+    }
+    {
+      "error": {
+        "code": -32600,
+        "message": "missing field `jsonrpc`"
+      },
+      "id": 2,
+      "jsonrpc": "2.0"
+    }
+    {
+      "error": {
+        "code": -32600,
+        "message": "Unsupported JSON-RPC version"
+      },
+      "id": 3,
+      "jsonrpc": "2.0"
+    }
+    {
+      "error": {
+        "code": -32601,
+        "message": "Unknown method \"unknown\""
+      },
+      "id": 4,
+      "jsonrpc": "2.0"
+    }
+    {
+      "error": {
+        "code": -32602,
+        "message": "unknown field `revisions`, expected `revision` or `format`"
+      },
+      "id": 5,
+      "jsonrpc": "2.0"
+    }
+    {
+      "error": {
+        "code": -32000,
+        "message": "Revision \"nonexistent\" doesn't exist"
+      },
+      "id": 6,
+      "jsonrpc": "2.0"
+    }
+    {
+      "error": {
+        "code": -32000,
+        "message": "The root commit 000000000000 is immutable"
+      },
+      "id": 7,
+      "jsonrpc": "2.0"
+    }
+    {
+      "id": 8,
+      "jsonrpc": "2.0",
+      "result": {
+        "api_version": 1
+      }
+    }
+    "###);
+}
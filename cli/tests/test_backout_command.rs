@@ -0,0 +1,132 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+fn create_commit(test_env: &TestEnvironment, repo_path: &Path, name: &str, parents: &[&str]) {
+    if parents.is_empty() {
+        test_env.jj_cmd_ok(repo_path, &["new", "root()", "-m", name]);
+    } else {
+        let mut args = vec!["new", "-m", name];
+        args.extend(parents);
+        test_env.jj_cmd_ok(repo_path, &args);
+    }
+    std::fs::write(repo_path.join(name), format!("{name}\n")).unwrap();
+    test_env.jj_cmd_ok(repo_path, &["branch", "create", name]);
+}
+
+#[test]
+fn test_backout() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "a", &[]);
+    create_commit(&test_env, &repo_path, "b", &["a"]);
+    // Test the setup
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  [zsu] b b
+    ◉  [rlv] a a
+    ◉  [zzz]
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["backout", "-r", "a"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    ◉  [yqo] Back out "a"
+    @  [zsu] b b
+    ◉  [rlv] a a
+    ◉  [zzz]
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "@+", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    Back out "a"
+
+    This backs out change rlvkpnrzqnoowoytxnquwvuryrwnrmlp (commit 2443ea76b0b1c531326908326aab7020abab8e6c).
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary", "-r", "@+"]);
+    insta::assert_snapshot!(stdout, @"D a");
+}
+
+#[test]
+fn test_backout_multiple() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "a", &[]);
+    create_commit(&test_env, &repo_path, "b", &["a"]);
+    create_commit(&test_env, &repo_path, "c", &["b"]);
+    create_commit(&test_env, &repo_path, "d", &["c"]);
+
+    // The revisions are backed out in a chain, starting from the newest one
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["backout", "-r", "b::c", "-r", "a", "-d", "d"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    ◉  [lpn] Back out "a"
+    ◉  [uuz] Back out "b"
+    ◉  [znk] Back out "c"
+    @  [vru] d d
+    ◉  [roy] c c
+    ◉  [zsu] b b
+    ◉  [rlv] a a
+    ◉  [zzz]
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary", "-r", "d+"]);
+    insta::assert_snapshot!(stdout, @"D c");
+
+    // With --combine, they're backed out in a single commit
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["backout", "-r", "b::c", "-d", "d", "--combine"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "d+", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    Back out 2 changes
+
+    This backs out the following changes:
+    * royxmykxtrkr c
+    * zsuskulnrvyr b
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary", "-r", "d+"]);
+    insta::assert_snapshot!(stdout, @r###"
+    D b
+    D c
+    "###);
+}
+
+fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    test_env.jj_cmd_success(
+        repo_path,
+        &[
+            "log",
+            "-T",
+            r#"separate(" ", "[" ++ change_id.short(3) ++ "]", branches, description.first_line())"#,
+        ],
+    )
+}
@@ -187,6 +187,56 @@ fn test_branch_move_conflicting() {
     "###);
 }
 
+#[test]
+fn test_branch_move_from() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "foo", "bar-1"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "bar-2"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "all:branches()"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @     bd86f892044f
+    ├─╮
+    │ ◉  bar-1 foo 230dd059e1b0
+    ◉ │  bar-2 fcdbbd731496
+    ├─╯
+    ◉   000000000000
+    "###);
+
+    // Branches are selected by the revisions they point to, filtered by name
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "move", "--from=@-", "glob:bar-*", "--dry-run"],
+    );
+    insta::assert_snapshot!(stderr, @"Would move branches bar-1, bar-2.");
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["branch", "move", "--from=@-", "glob:bar-*"]);
+    insta::assert_snapshot!(stderr, @"Moved 2 branches.");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @    bar-1 bar-2 bd86f892044f
+    ├─╮
+    │ ◉  foo 230dd059e1b0
+    ◉ │   fcdbbd731496
+    ├─╯
+    ◉   000000000000
+    "###);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "move", "foo"]);
+    insta::assert_snapshot!(stderr, @"");
+
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["branch", "move", "--to=230dd059e1b0", "foo"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Refusing to move branch backwards or sideways: foo
+    Hint: Use --allow-backwards to allow it.
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["branch", "move", "--from=root()"]);
+    insta::assert_snapshot!(stderr, @"Error: No matching branches point to the given revisions");
+}
+
 #[test]
 fn test_branch_rename() {
     let test_env = TestEnvironment::default();
@@ -399,10 +449,63 @@ fn test_branch_delete_glob() {
     error: invalid value 'whatever:branch' for '[NAMES]...': Invalid string pattern kind "whatever:"
 
     For more information, try '--help'.
-    Hint: Try prefixing with one of `exact:`, `glob:` or `substring:`
+    Hint: Try prefixing with one of `exact:`, `glob:`, `regex:` or `substring:`
     "###);
 }
 
+#[test]
+fn test_branch_delete_forget_revisions() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=main"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main", "push-1"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m=side"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "push-2"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(main)", "-m=child"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "push-3"]);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  push-3 466841c18c05
+    ◉  main push-1 49b77ab9f7c1
+    │ ◉  push-2 0b95112c3f7f
+    ├─╯
+    ◉   000000000000
+    "###);
+
+    // Delete the push-* branches which were merged into main
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "delete", "-r=::main", "glob:push-*", "--dry-run"],
+    );
+    insta::assert_snapshot!(stderr, @"Would delete branch push-1.");
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "delete", "-r=::main", "glob:push-*"],
+    );
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  push-3 466841c18c05
+    ◉  main 49b77ab9f7c1
+    │ ◉  push-2 0b95112c3f7f
+    ├─╯
+    ◉   000000000000
+    "###);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "forget", "-r=~::main"]);
+    insta::assert_snapshot!(stderr, @"Forgot 2 branches.");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @   466841c18c05
+    ◉  main 49b77ab9f7c1
+    │ ◉   0b95112c3f7f
+    ├─╯
+    ◉   000000000000
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["branch", "delete", "-r=root()"]);
+    insta::assert_snapshot!(stderr, @"Error: No matching branches point to the given revisions");
+}
+
 #[test]
 fn test_branch_delete_export() {
     let test_env = TestEnvironment::default();
@@ -1032,6 +1135,57 @@ fn test_branch_list() {
       @origin (ahead by 1 commits, behind by 1 commits): qpsqxpyq 38ef8af7 (empty) remote-unsync
     remote-untrack@origin: vmortlor 71a16b05 (empty) remote-untrack
     "###);
+
+    let template = r#"
+    concat(
+      "[" ++ name ++ if(remote, "@" ++ remote) ++ "]\n",
+      separate(" ", "present:", present) ++ "\n",
+      separate(" ", "conflict:", conflict) ++ "\n",
+      separate(" ", "normal_target:", normal_target.description().first_line()) ++ "\n",
+      separate(" ", "removed_targets:", removed_targets.map(|c| c.description().first_line())) ++ "\n",
+      separate(" ", "added_targets:", added_targets.map(|c| c.description().first_line())) ++ "\n",
+      separate(" ", "tracked:", tracked) ++ "\n",
+      separate(" ", "tracking_present:", tracking_present) ++ "\n",
+      separate(" ", "tracking_ahead_count:", tracking_ahead_count.lower()) ++ "\n",
+      separate(" ", "tracking_behind_count:", tracking_behind_count.lower()) ++ "\n",
+    )
+    "#;
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(
+            &local_path,
+            &["branch", "list", "--all-remotes", "-T", template, "remote-unsync", "remote-untrack"]),
+        @r###"
+    [remote-unsync]
+    present: true
+    conflict: false
+    normal_target: local-only
+    removed_targets:
+    added_targets: local-only
+    tracked: false
+    tracking_present: false
+    tracking_ahead_count: <Error: Not a tracked remote ref>
+    tracking_behind_count: <Error: Not a tracked remote ref>
+    [remote-unsync@origin]
+    present: true
+    conflict: false
+    normal_target: remote-unsync
+    removed_targets:
+    added_targets: remote-unsync
+    tracked: true
+    tracking_present: true
+    tracking_ahead_count: 1
+    tracking_behind_count: 1
+    [remote-untrack@origin]
+    present: true
+    conflict: false
+    normal_target: remote-untrack
+    removed_targets:
+    added_targets: remote-untrack
+    tracked: false
+    tracking_present: false
+    tracking_ahead_count: <Error: Not a tracked remote ref>
+    tracking_behind_count: <Error: Not a tracked remote ref>
+    "###);
 }
 
 #[test]
@@ -1394,11 +1548,183 @@ fn test_branch_list_conflicted() {
       + rlvkpnrz d8d5f980 (empty) a
       + kkmpptxz 06a973bc (empty) b
     "###);
-    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["branch", "list", "--conflicted"]), @r###"
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["branch", "list", "--conflicted"]);
+    insta::assert_snapshot!(stdout, @r###"
     foo (conflicted):
       + rlvkpnrz d8d5f980 (empty) a
       + kkmpptxz 06a973bc (empty) b
     "###);
+    insta::assert_snapshot!(stderr, @"Hint: Some branches have conflicts. Use `jj branch resolve <name> --keep local|remote|<rev>` to resolve them.");
+}
+
+#[test]
+fn test_branch_resolve() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    let git_repo_path = test_env.env_root().join("git-repo");
+    git2::Repository::init_bare(git_repo_path).unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["git", "remote", "add", "origin", "../git-repo"],
+    );
+
+    // Move the branch on the remote, but forget about it locally, then move it
+    // locally as well
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "base"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "foo"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "push", "--all"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "remote"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "foo"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "push"]);
+    test_env.jj_cmd_ok(&repo_path, &["op", "restore", "@---"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "local"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "foo"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo (conflicted):
+      - qpvuntsm 7597521e (empty) base
+      + znkkpsqq b743576d (empty) local
+      + royxmykx 2978a607 (empty) remote
+      @origin (behind by 1 commits): royxmykx 2978a607 (empty) remote
+    "###);
+
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["branch", "resolve", "bar", "--keep", "local"]);
+    insta::assert_snapshot!(stderr, @"Error: No such branch: bar");
+
+    // Keep either side
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["branch", "resolve", "foo", "--keep", "local"]);
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo: znkkpsqq b743576d (empty) local
+      @origin (ahead by 1 commits, behind by 1 commits): royxmykx 2978a607 (empty) remote
+    "###);
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "resolve", "foo", "--keep", "remote"],
+    );
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo: royxmykx 2978a607 (empty) remote
+      @origin: royxmykx 2978a607 (empty) remote
+    "###);
+
+    // The branch is no longer conflicted
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["branch", "resolve", "foo", "--keep", "local"]);
+    insta::assert_snapshot!(stderr, @"Error: Branch foo is not conflicted");
+
+    // Any revision can be kept
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "resolve", "foo", "--keep", "description(base)"],
+    );
+    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    foo: qpvuntsm 7597521e (empty) base
+      @origin (ahead by 1 commits): royxmykx 2978a607 (empty) remote
+    "###);
+}
+
+#[test]
+fn test_branch_list_template_remote_distances() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "a"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "export"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "b"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "c"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "main"]);
+
+    let template = r#"
+    if(remote,
+      name ++ "@" ++ remote ++ ": " ++ if(tracking_present,
+        "+" ++ tracking_ahead_count.exact() ++ " -" ++ tracking_behind_count.exact(),
+        "untracked"),
+      name ++ if(present, "", " (deleted)"),
+    ) ++ "\n"
+    "#;
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["branch", "list", "--all-remotes", "-T", template]),
+        @r###"
+    main
+    main@git: +0 -2
+    "###);
+}
+
+#[test]
+fn test_branch_list_expired_remote_branches() {
+    let test_env = TestEnvironment::default();
+
+    // Initialize remote refs
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "remote", "--git"]);
+    let remote_path = test_env.env_root().join("remote");
+    for branch in ["remote-track", "remote-untrack"] {
+        test_env.jj_cmd_ok(&remote_path, &["new", "root()", "-m", branch]);
+        test_env.jj_cmd_ok(&remote_path, &["branch", "create", branch]);
+    }
+    test_env.jj_cmd_ok(&remote_path, &["git", "export"]);
+
+    let mut remote_git_path = remote_path;
+    remote_git_path.extend([".jj", "repo", "store", "git"]);
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", remote_git_path.to_str().unwrap(), "local"],
+    );
+    let local_path = test_env.env_root().join("local");
+    test_env.jj_cmd_ok(&local_path, &["branch", "track", "remote-track@origin"]);
+
+    let template = r#"name ++ if(remote, "@" ++ remote) ++ "\n""#;
+    let query = |expiry: &str| {
+        test_env.jj_cmd_success(
+            &local_path,
+            &[
+                "branch",
+                "list",
+                "--all-remotes",
+                "--color=always",
+                "-T",
+                template,
+                &format!("--config-toml=git.remote-branch-expiry = '{expiry}'"),
+            ],
+        )
+    };
+
+    // Nothing is expired before the commits were made
+    insta::assert_snapshot!(query("2001-01-01"), @r###"
+    remote-track
+    remote-track@origin
+    remote-untrack@origin
+    "###);
+
+    // Only the non-tracking remote branches are expired
+    insta::assert_snapshot!(query("2001-03-01"), @r###"
+    remote-track
+    remote-track@origin
+    [38;5;8mremote-untrack@origin[39m
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(
+        &local_path,
+        &[
+            "branch",
+            "list",
+            "--all-remotes",
+            "--config-toml=git.remote-branch-expiry = 'invalid'",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid `git.remote-branch-expiry`
+    Caused by: Invalid date "invalid"
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
 }
 
 fn get_log_output(test_env: &TestEnvironment, cwd: &Path) -> String {
@@ -1407,5 +1733,6 @@ fn get_log_output(test_env: &TestEnvironment, cwd: &Path) -> String {
 }
 
 fn get_branch_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
-    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes"])
+    // --quiet to suppress the hint about conflicted branches
+    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes", "--quiet"])
 }
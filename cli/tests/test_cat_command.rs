@@ -57,14 +57,21 @@ fn test_cat() {
 
     // Error if the path doesn't exist
     let stderr = test_env.jj_cmd_failure(&repo_path, &["cat", "nonexistent"]);
-    insta::assert_snapshot!(stderr, @r###"
-    Error: No such path
-    "###);
+    insta::assert_snapshot!(stderr, @"Error: No such path: nonexistent");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["cat", "file1", "nonexistent"]);
+    insta::assert_snapshot!(stderr, @"Error: No such path: nonexistent");
+
+    // Can print the files in a directory
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "dir"]);
+    insta::assert_snapshot!(stdout, @"c");
 
-    // Error if the path is not a file
-    let stderr = test_env.jj_cmd_failure(&repo_path, &["cat", "dir"]);
-    insta::assert_snapshot!(stderr, @r###"
-    Error: Path exists but is not a file
+    // Can print several files, with headers
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "file1", "dir"]);
+    insta::assert_snapshot!(stdout, @r###"
+    ==> dir/file2 <==
+    c
+    ==> file1 <==
+    b
     "###);
 
     // Can print a conflict
@@ -82,3 +89,53 @@ fn test_cat() {
     >>>>>>>
     "###);
 }
+
+#[cfg(unix)]
+#[test]
+fn test_cat_symlink() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::os::unix::fs::symlink("file1", repo_path.join("symlink1")).unwrap();
+
+    // The target of a symlink is printed
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "symlink1"]);
+    insta::assert_snapshot!(stdout, @"file1");
+}
+
+#[test]
+fn test_cat_output() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("dir").join("file2"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("dir").join("file2"), "c\n").unwrap();
+
+    // Can write a subtree to a directory
+    let output_dir = test_env.env_root().join("output");
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["cat", "-R", "repo", "-r", "@-", "-o", "output", "repo"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Wrote 2 files to output");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(output_dir.join("file1")).unwrap(), @"a");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(output_dir.join("dir").join("file2")).unwrap(), @"b");
+
+    // Existing files are replaced
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["cat", "-R", "repo", "-o", "output", "repo/dir"],
+    );
+    insta::assert_snapshot!(stderr, @"Wrote 1 files to output");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(output_dir.join("dir").join("file2")).unwrap(), @"c");
+}
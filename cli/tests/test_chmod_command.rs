@@ -253,3 +253,71 @@ fn test_chmod_file_dir_deletion_conflicts() {
     >>>>>>>
     "###);
 }
+
+#[test]
+fn test_chmod_patterns_and_symlinks() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("dir").join("a"), "a\n").unwrap();
+    std::fs::write(repo_path.join("dir").join("b"), "b\n").unwrap();
+    std::fs::write(repo_path.join("link"), "target").unwrap();
+    std::fs::write(repo_path.join("binary"), b"\xff").unwrap();
+
+    // All the files in a directory are changed
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "x", "dir"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree"]);
+    insta::assert_snapshot!(stdout, @r###"
+    binary: Resolved(Some(File { id: FileId("ce542efaa5124a0437f0c4db329d7ec4b7ba70a7"), executable: false }))
+    dir/a: Resolved(Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: true }))
+    dir/b: Resolved(Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: true }))
+    link: Resolved(Some(File { id: FileId("1de565933b05f74c75ff9a6520af5f9f8a5a2f1d"), executable: false }))
+    "###);
+
+    // And the files matching a pattern
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "n", "file:dir/a"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree", "dir"]);
+    insta::assert_snapshot!(stdout, @r###"
+    dir/a: Resolved(Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: false }))
+    dir/b: Resolved(Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: true }))
+    "###);
+
+    // A file can be turned into a symlink to the path in its contents, and back
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "l", "link"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree", "link"]);
+    insta::assert_snapshot!(stdout, @r###"
+    link: Resolved(Some(Symlink(SymlinkId("1de565933b05f74c75ff9a6520af5f9f8a5a2f1d"))))
+    "###);
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "x", "link"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree", "link"]);
+    insta::assert_snapshot!(stdout, @r###"
+    link: Resolved(Some(File { id: FileId("1de565933b05f74c75ff9a6520af5f9f8a5a2f1d"), executable: true }))
+    "###);
+
+    // Symlinks are left alone when they are matched through a directory, and
+    // so are files in `l` mode
+    std::fs::write(repo_path.join("dir").join("link"), "target").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "l", "dir/link"]);
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "n", "dir"]);
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "l", "dir"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["debug", "tree", "dir"]);
+    insta::assert_snapshot!(stdout, @r###"
+    dir/a: Resolved(Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: false }))
+    dir/b: Resolved(Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: false }))
+    dir/link: Resolved(Some(Symlink(SymlinkId("1de565933b05f74c75ff9a6520af5f9f8a5a2f1d"))))
+    "###);
+
+    // The contents of a symlink must be a valid path
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["chmod", "l", "binary"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Contents of 'binary' aren't valid UTF-8, so it can't be made a symlink.
+    "###);
+
+    // A pattern which doesn't match anything is an error
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["chmod", "x", "root:nonexistent"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No such path at 'root:nonexistent'.
+    "###);
+}
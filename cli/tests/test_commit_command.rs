@@ -31,6 +31,58 @@ fn test_commit_with_description_from_cli() {
     "###);
 }
 
+#[test]
+fn test_commit_signoff() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m=first", "--signoff"]);
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-r@-", "-Tdescription"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    first
+
+    Signed-off-by: Test User <test.user@example.com>
+    "###);
+}
+
+#[test]
+fn test_commit_author() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &[
+            "commit",
+            "-m=first",
+            "--author",
+            "Foo Bar <foo@bar.com>",
+            "--author-date",
+            "2020-01-02T03:04:05+06:00",
+        ],
+    );
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &[
+            "log",
+            "--no-graph",
+            "-r@- | @",
+            "-T",
+            r#"author.name() ++ " " ++ author.email() ++ " " ++ author.timestamp() ++ "\n""#,
+        ],
+    );
+    // The new working-copy commit has the configured author
+    insta::assert_snapshot!(stdout, @r###"
+    Test User test.user@example.com 2001-02-03 04:05:08.000 +07:00
+    Foo Bar foo@bar.com 2020-01-02 03:04:05.000 +06:00
+    "###);
+}
+
 #[test]
 fn test_commit_with_editor() {
     let mut test_env = TestEnvironment::default();
@@ -101,6 +153,12 @@ fn test_commit_interactive() {
     JJ: Lines starting with "JJ: " (like this one) will be removed.
     "###);
 
+    // The unselected changes are left in the new working-copy commit
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["diff", "--summary", "-r@-"]);
+    insta::assert_snapshot!(stdout, @"A file1");
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @"A file2");
+
     // Try again with --tool=<name>, which implies --interactive
     test_env.jj_cmd_ok(&workspace_path, &["undo"]);
     test_env.jj_cmd_ok(
@@ -136,11 +194,11 @@ fn test_commit_with_default_description() {
     std::fs::write(edit_script, ["dump editor"].join("\0")).unwrap();
     test_env.jj_cmd_ok(&workspace_path, &["commit"]);
 
-    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r#"
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_path), @r###"
     @  8dc0591d00f7
     ◉  7e780ba80aeb TESTED=TODO
     ◉  000000000000
-    "#);
+    "###);
     assert_eq!(
         std::fs::read_to_string(test_env.env_root().join("editor")).unwrap(),
         r#"
@@ -185,10 +243,10 @@ fn test_commit_paths() {
     "###);
 
     let stdout = test_env.jj_cmd_success(&workspace_path, &["diff"]);
-    insta::assert_snapshot!(stdout, @"
+    insta::assert_snapshot!(stdout, @r###"
     Added regular file file2:
             1: bar
-    ");
+    "###);
 }
 
 #[test]
@@ -217,6 +275,44 @@ fn test_commit_paths_warning() {
     "###);
 }
 
+#[test]
+fn test_commit_advance_branch() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+    let get_log_output = || {
+        let template = r#"branches ++ " " ++ description"#;
+        test_env.jj_cmd_success(&workspace_path, &["log", "-T", template])
+    };
+
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m=first"]);
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["branch", "create", "-r@-", "main", "other"],
+    );
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["commit", "-m=second", "--advance-branch"],
+    );
+    insta::assert_snapshot!(get_log_output(), @r###"
+    @
+    ◉  main other second
+    ◉   first
+    ◉
+    "###);
+
+    // Only the configured branches are advanced by default
+    test_env.add_config(r#"advance-branches.enabled-branches = ["main"]"#);
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m=third"]);
+    insta::assert_snapshot!(get_log_output(), @r###"
+    @
+    ◉  main third
+    ◉  other second
+    ◉   first
+    ◉
+    "###);
+}
+
 fn get_log_output(test_env: &TestEnvironment, cwd: &Path) -> String {
     let template = r#"commit_id.short() ++ " " ++ description"#;
     test_env.jj_cmd_success(cwd, &["log", "-T", template])
@@ -391,6 +391,9 @@ fn test_log_obslog_divergence() {
     "###);
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace default
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     "###);
 
     // Color
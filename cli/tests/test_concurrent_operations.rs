@@ -25,10 +25,16 @@ fn test_concurrent_operation_divergence() {
     let repo_path = test_env.env_root().join("repo");
 
     test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "message 1"]);
-    test_env.jj_cmd_ok(
+    let (stdout, stderr) = test_env.jj_cmd_ok(
         &repo_path,
         &["describe", "-m", "message 2", "--at-op", "@-"],
     );
+    insta::assert_snapshot!(stdout, @"");
+    // We should be informed that the operation diverged
+    insta::assert_snapshot!(stderr, @r###"
+    Created divergent operation bd72b691e2a8 on top of operation b51416386f26
+    Hint: The next command will merge it with the latest operation aff16879e85e.
+    "###);
 
     // "op log" doesn't merge the concurrent operations
     let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log"]);
@@ -56,6 +62,9 @@ fn test_concurrent_operation_divergence() {
     "###);
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace default
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     "###);
 }
 
@@ -99,6 +108,9 @@ fn test_concurrent_operations_auto_rebase() {
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
     Rebased 1 descendant commits onto commits rewritten by other operation
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace default
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     "###);
 }
 
@@ -134,11 +146,14 @@ fn test_concurrent_operations_wc_modified() {
     "###);
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace default
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     "###);
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
     insta::assert_snapshot!(stdout, @r###"
     diff --git a/file b/file
-    index 12f00e90b6...2e0996000b 100644
+    index 12f00e90b6..2e0996000b 100644
     --- a/file
     +++ b/file
     @@ -1,1 +1,1 @@
@@ -163,6 +178,74 @@ fn test_concurrent_operations_wc_modified() {
     "###);
 }
 
+#[test]
+fn test_concurrent_operations_wc_discardable() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "initial"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "child"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "--at-op", "@-"]);
+
+    // The empty working-copy commit created by the other operation is dropped
+    let (stdout, stderr) = get_log_output_with_stderr(&test_env, &repo_path);
+    insta::assert_snapshot!(stdout, @r###"
+    @  227612ef4f38e0f54cdddaeafee9909f29e324ed child
+    ◉  e343cd4a2ce7fc2af6ff0a959a79da4bc6cfdb11 initial
+    ◉  0000000000000000000000000000000000000000
+    "###);
+    insta::assert_snapshot!(stderr, @"Concurrent modification detected, resolving automatically.");
+}
+
+#[test]
+fn test_concurrent_operations_view_conflicts() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "initial"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "child1"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "child2", "main"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["branch", "set", "main", "-r", "description(child1)"],
+    );
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "branch",
+            "set",
+            "main",
+            "-r",
+            "description(child2)",
+            "--at-op",
+            "@-",
+        ],
+    );
+
+    // The conflicts are reported
+    let (stdout, stderr) = get_log_output_with_stderr(&test_env, &repo_path);
+    insta::assert_snapshot!(stdout, @r###"
+    @  a8ce2d181ad4dc345de48b3c9ab6d5aa001188f3 child2
+    │ ◉  04971f263b8b1a40022df4005333ebd25c2efdea child1
+    ├─╯
+    ◉  e343cd4a2ce7fc2af6ff0a959a79da4bc6cfdb11 initial
+    ◉  0000000000000000000000000000000000000000
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Concurrent modification detected, resolving automatically.
+    Warning: Concurrent operations left conflicts in:
+      branch main
+    Hint: Use `jj branch set` to resolve the branch conflicts.
+    "###);
+
+    // They aren't reported again
+    let (_stdout, stderr) = get_log_output_with_stderr(&test_env, &repo_path);
+    insta::assert_snapshot!(stderr, @"");
+}
+
 #[test]
 fn test_concurrent_snapshot_wc_reloadable() {
     let test_env = TestEnvironment::default();
@@ -375,6 +375,163 @@ fn test_config_layer_workspace() {
     insta::assert_snapshot!(stdout, @r###"
     ui.editor="main-repo"
     "###);
+
+    // Workspace config overrides the repo config in that workspace only
+    test_env.jj_cmd_ok(
+        &secondary_path,
+        &[
+            "config",
+            "set",
+            "--workspace",
+            config_key,
+            "second-workspace",
+        ],
+    );
+    let workspace_config_toml =
+        std::fs::read_to_string(secondary_path.join(".jj/workspace-config.toml")).unwrap();
+    insta::assert_snapshot!(workspace_config_toml, @r###"
+    [ui]
+    editor = "second-workspace"
+    "###);
+    let stdout = test_env.jj_cmd_success(&main_path, &["config", "list", config_key]);
+    insta::assert_snapshot!(stdout, @r###"
+    ui.editor="main-repo"
+    "###);
+    let stdout = test_env.jj_cmd_success(&secondary_path, &["config", "list", config_key]);
+    insta::assert_snapshot!(stdout, @r###"
+    ui.editor="second-workspace"
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &secondary_path,
+        &["config", "list", "--include-overridden", config_key],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    # ui.editor="main-repo"
+    ui.editor="second-workspace"
+    "###);
+}
+
+#[test]
+fn test_config_layer_git_colocated() {
+    let test_env = TestEnvironment::default();
+    let repo_path = test_env.env_root().join("repo");
+    let git_repo = git2::Repository::init(&repo_path).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["git", "init", "--git-repo=."]);
+    let mut git_config = git_repo.config().unwrap();
+    git_config.set_str("user.signingKey", "my-key").unwrap();
+    git_config.set_bool("commit.gpgSign", true).unwrap();
+    git_config.set_str("gpg.format", "ssh").unwrap();
+    git_config
+        .set_str("gpg.ssh.program", "my-ssh-keygen")
+        .unwrap();
+
+    // The Git config options are translated to their jj equivalents
+    let stdout = test_env.jj_cmd_success(&repo_path, &["config", "list", "signing"]);
+    insta::assert_snapshot!(stdout, @r###"
+    signing.backend="ssh"
+    signing.backends.ssh.program="my-ssh-keygen"
+    signing.key="my-key"
+    signing.sign-all=true
+    "###);
+
+    // The global Git config isn't read
+    std::fs::write(
+        test_env.home_dir().join(".gitconfig"),
+        "[gpg \"ssh\"]\n\tallowedSignersFile = global-signers\n",
+    )
+    .unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["config", "list", "signing"]);
+    insta::assert_snapshot!(stdout, @r###"
+    signing.backend="ssh"
+    signing.backends.ssh.program="my-ssh-keygen"
+    signing.key="my-key"
+    signing.sign-all=true
+    "###);
+
+    // The jj config takes precedence over the Git config
+    test_env.add_config(r#"signing.sign-all = false"#);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["config", "list", "--include-overridden", "signing.sign-all"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    # signing.sign-all=true
+    signing.sign-all=false
+    "###);
+
+    // The Git config of a non-colocated repo isn't read
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "internal"]);
+    let internal_path = test_env.env_root().join("internal");
+    let mut git_config = git2::Repository::open(internal_path.join(".jj/repo/store/git"))
+        .unwrap()
+        .config()
+        .unwrap();
+    git_config.set_str("user.signingKey", "my-key").unwrap();
+    let stdout = test_env.jj_cmd_success(&internal_path, &["config", "list", "signing"]);
+    insta::assert_snapshot!(stdout, @"signing.sign-all=false");
+}
+
+#[test]
+fn test_config_conditional_include() {
+    let test_env = TestEnvironment::default();
+    let home_dir = test_env.home_dir();
+    std::fs::create_dir_all(home_dir.join("work")).unwrap();
+    test_env.jj_cmd_ok(home_dir, &["git", "init", "personal"]);
+    test_env.jj_cmd_ok(&home_dir.join("work"), &["git", "init", "repo"]);
+    std::fs::write(home_dir.join("work.toml"), r#"signing.key = "work-key""#).unwrap();
+    test_env.add_config(
+        r#"
+        signing.key = "personal-key"
+
+        [[include]]
+        if-path = "~/work/"
+        path = "~/work.toml"
+        "#,
+    );
+
+    let stdout = test_env.jj_cmd_success(
+        &home_dir.join("personal"),
+        &["config", "get", "signing.key"],
+    );
+    insta::assert_snapshot!(stdout, @"personal-key");
+    let stdout = test_env.jj_cmd_success(
+        &home_dir.join("work/repo"),
+        &["config", "get", "signing.key"],
+    );
+    insta::assert_snapshot!(stdout, @"work-key");
+    // The workspace specified by -R decides which files are included
+    let stdout = test_env.jj_cmd_success(
+        &home_dir.join("personal"),
+        &["config", "get", "signing.key", "-R", "../work/repo"],
+    );
+    insta::assert_snapshot!(stdout, @"work-key");
+    // Included files override the user config, but not the repo config
+    test_env.jj_cmd_ok(
+        &home_dir.join("work/repo"),
+        &["config", "set", "--repo", "signing.key", "repo-key"],
+    );
+    let stdout = test_env.jj_cmd_success(
+        &home_dir.join("work/repo"),
+        &["config", "get", "signing.key"],
+    );
+    insta::assert_snapshot!(stdout, @"repo-key");
+    // Included files are only read in workspaces
+    let stdout = test_env.jj_cmd_success(home_dir, &["config", "get", "signing.key"]);
+    insta::assert_snapshot!(stdout, @"personal-key");
+
+    test_env.add_config(
+        r#"
+        [[include]]
+        if-path = "[work/"
+        path = "~/work.toml"
+        "#,
+    );
+    let stderr = test_env.jj_cmd_failure(home_dir, &["config", "get", "signing.key"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid path pattern "[work/" in config include
+    Caused by: Pattern syntax error near position 0: invalid range pattern
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
 }
 
 #[test]
@@ -383,11 +540,11 @@ fn test_config_set_missing_opts() {
     let stderr = test_env.jj_cmd_cli_error(test_env.env_root(), &["config", "set"]);
     insta::assert_snapshot!(stderr, @r###"
     error: the following required arguments were not provided:
-      <--user|--repo>
+      <--user|--repo|--workspace>
       <NAME>
       <VALUE>
 
-    Usage: jj config set <--user|--repo> <NAME> <VALUE>
+    Usage: jj config set <--user|--repo|--workspace> <NAME> <VALUE>
 
     For more information, try '--help'.
     "###);
@@ -540,15 +697,106 @@ fn test_config_set_nontable_parent() {
     "###);
 }
 
+#[test]
+fn test_config_set_schema() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let user_config_path = test_env.config_path().join("config.toml");
+    test_env.set_config_path(user_config_path.clone());
+    let repo_path = test_env.env_root().join("repo");
+
+    // Values of string options aren't parsed as other types
+    test_env.jj_cmd_ok(&repo_path, &["config", "set", "--user", "user.name", "42"]);
+    test_env.jj_cmd_ok(&repo_path, &["config", "set", "--user", "ui.quiet", "true"]);
+    insta::assert_snapshot!(std::fs::read_to_string(&user_config_path).unwrap(), @r###"
+    [user]
+    name = "42"
+
+    [ui]
+    quiet = true
+    "###);
+
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["config", "set", "--user", "ui.quiet", "yes"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid value for ui.quiet: expected boolean
+    "###);
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["config", "set", "--user", "ui.diff.format", "gti"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid value for ui.diff.format: expected one of "color-words", "git", "summary"
+    "###);
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["config", "set", "--user", "ui.qiet", "true"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Unknown config option ui.qiet
+    Hint: Did you mean "quiet"?
+    "###);
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["config", "set", "--user", "usr.email", "foo@example.com"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Unknown config option usr.email
+    Hint: Did you mean "user"?
+    "###);
+}
+
+#[test]
+fn test_config_unset() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let user_config_path = test_env.config_path().join("config.toml");
+    test_env.set_config_path(user_config_path.clone());
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["config", "set", "--user", "test-key", "test-val"],
+    );
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["config", "set", "--user", "test-table.foo", "true"],
+    );
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["config", "set", "--user", "test-table.bar", "false"],
+    );
+
+    test_env.jj_cmd_ok(&repo_path, &["config", "unset", "--user", "test-key"]);
+    test_env.jj_cmd_ok(&repo_path, &["config", "unset", "--user", "test-table.foo"]);
+    insta::assert_snapshot!(std::fs::read_to_string(&user_config_path).unwrap(), @r###"
+
+    [test-table]
+    bar = false
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["config", "unset", "--user", "test-key"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: test-key isn't set in $TEST_ENV/config/config.toml
+    "###);
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["config", "unset", "--repo", "test-table.bar"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: test-table.bar isn't set in $TEST_ENV/repo/.jj/repo/config.toml
+    "###);
+
+    // A whole table can be unset
+    test_env.jj_cmd_ok(&repo_path, &["config", "unset", "--user", "test-table"]);
+    insta::assert_snapshot!(std::fs::read_to_string(&user_config_path).unwrap(), @"");
+}
+
 #[test]
 fn test_config_edit_missing_opt() {
     let test_env = TestEnvironment::default();
     let stderr = test_env.jj_cmd_cli_error(test_env.env_root(), &["config", "edit"]);
     insta::assert_snapshot!(stderr, @r###"
     error: the following required arguments were not provided:
-      <--user|--repo>
+      <--user|--repo|--workspace>
 
-    Usage: jj config edit <--user|--repo>
+    Usage: jj config edit <--user|--repo|--workspace>
 
     For more information, try '--help'.
     "###);
@@ -605,6 +853,12 @@ fn test_config_path() {
       $TEST_ENV/repo/.jj/repo/config.toml
       "###
     );
+    assert_snapshot!(
+      test_env.jj_cmd_success(&repo_path, &["config", "path", "--workspace"]),
+      @r###"
+      $TEST_ENV/repo/.jj/workspace-config.toml
+      "###
+    );
 }
 
 #[test]
@@ -66,9 +66,12 @@ fn test_debug_index() {
     Max generation number: 1
     Number of heads: 1
     Number of changes: 2
+    Number of levels: 1
+    Total size: 152 bytes
     Stats per level:
       Level 0:
         Number of commits: 2
+        Size: 152 bytes
         Name: [hash]
     "###
     );
@@ -88,12 +91,16 @@ fn test_debug_reindex() {
     Max generation number: 3
     Number of heads: 1
     Number of changes: 4
+    Number of levels: 2
+    Total size: 432 bytes
     Stats per level:
       Level 0:
         Number of commits: 3
+        Size: 212 bytes
         Name: [hash]
       Level 1:
         Number of commits: 1
+        Size: 220 bytes
         Name: [hash]
     "###
     );
@@ -109,14 +116,138 @@ fn test_debug_reindex() {
     Max generation number: 3
     Number of heads: 1
     Number of changes: 4
+    Number of levels: 1
+    Total size: 272 bytes
     Stats per level:
       Level 0:
         Number of commits: 4
+        Size: 272 bytes
         Name: [hash]
     "###
     );
 }
 
+#[test]
+fn test_debug_index_subcommands() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&workspace_path, &["new"]);
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "index", "stats"]);
+    assert_eq!(
+        stdout,
+        test_env.jj_cmd_success(&workspace_path, &["debug", "index"])
+    );
+    assert_snapshot!(filter_index_stats(&stdout), @r###"
+    Number of commits: 3
+    Number of merges: 0
+    Max generation number: 2
+    Number of heads: 1
+    Number of changes: 3
+    Number of levels: 1
+    Total size: 212 bytes
+    Stats per level:
+      Level 0:
+        Number of commits: 3
+        Size: 212 bytes
+        Name: [hash]
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_path, &["debug", "index", "rebuild"]);
+    assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Finished indexing 3 commits.
+    "###);
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "index", "stats"]);
+    assert_snapshot!(filter_index_stats(&stdout), @r###"
+    Number of commits: 3
+    Number of merges: 0
+    Max generation number: 2
+    Number of heads: 1
+    Number of changes: 3
+    Number of levels: 1
+    Total size: 212 bytes
+    Stats per level:
+      Level 0:
+        Number of commits: 3
+        Size: 212 bytes
+        Name: [hash]
+    "###);
+}
+
+#[test]
+fn test_debug_index_rebuild_changed_path_filters() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+    std::fs::create_dir(workspace_path.join("dir")).unwrap();
+    std::fs::write(workspace_path.join("dir").join("file1"), "1").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m", "add file1"]);
+    std::fs::write(workspace_path.join("file2"), "2").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m", "add file2"]);
+
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["debug", "index", "rebuild", "--changed-path-filters"],
+    );
+
+    // Commits indexed after the rebuild also get filters
+    std::fs::write(workspace_path.join("dir").join("file1"), "1 modified").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m", "modify file1"]);
+
+    let template = r#"description.first_line() ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-T", template, "-r", "file(dir)"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    modify file1
+    add file1
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-T", template, "-r", "file(file2)"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    add file2
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &[
+            "log",
+            "--no-graph",
+            "-T",
+            template,
+            "-r",
+            "file(nonexistent)",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+
+    // The filters are kept when the index is rebuilt without the flag
+    test_env.jj_cmd_ok(&workspace_path, &["debug", "index", "rebuild"]);
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-T", template, "-r", "file(dir/file1)"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    modify file1
+    add file1
+    "###);
+
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &["debug", "index", "rebuild", "--no-changed-path-filters"],
+    );
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-T", template, "-r", "file(file2)"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    add file2
+    "###);
+}
+
 #[test]
 fn test_debug_tree() {
     let test_env = TestEnvironment::default();
@@ -196,6 +327,112 @@ fn test_debug_tree() {
     dir/subdir/file2: Resolved(Some(File { id: FileId("b2496eaffe394cd50a9db4de5787f45f09fd9722"), executable: false }))
     "###
     );
+
+    // Can include the trees
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["debug", "tree", "--trees", "dir/subdir/file2"],
+    );
+    assert_snapshot!(stdout.replace('\\',"/"), @r###"
+    dir: Resolved(Some(Tree(TreeId("6ac232efa713535ae518a1a898b77e76c0478184"))))
+    dir/subdir: Resolved(Some(Tree(TreeId("6b87c8ebe329e0aff9244cb127234c11455b9c2a"))))
+    dir/subdir/file2: Resolved(Some(File { id: FileId("b2496eaffe394cd50a9db4de5787f45f09fd9722"), executable: false }))
+    "###);
+}
+
+#[test]
+fn test_debug_tree_conflicts() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_path = test_env.env_root().join("repo");
+    std::fs::write(workspace_path.join("file"), "base\n").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["commit", "-m", "base"]);
+    std::fs::write(workspace_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["new", "description(base)"]);
+    std::fs::write(workspace_path.join("file"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["new", "all:@-+"]);
+
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "tree", "--conflicts"]);
+    assert_snapshot!(stdout, @r#"file: Conflicted([Some(File { id: FileId("61780798228d17af2d34fce4cfbdf35556832472"), executable: false }), Some(File { id: FileId("df967b96a579e45a18b8251732d16804b2e56a55"), executable: false }), Some(File { id: FileId("78981922613b2afb6025042ff6bd878ac1994e85"), executable: false })])"#);
+}
+
+#[test]
+fn test_debug_object() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_path = test_env.env_root().join("repo");
+    std::fs::write(workspace_path.join("file"), "contents\n").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", "first"]);
+
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "tree"]);
+    assert_snapshot!(stdout, @r#"file: Resolved(Some(File { id: FileId("12f00e90b6ef79117ce6e650416b8cf517099b78"), executable: false }))"#);
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &[
+            "debug",
+            "object",
+            "file",
+            "12f00e90b6ef79117ce6e650416b8cf517099b78",
+        ],
+    );
+    assert_snapshot!(stdout, @"contents");
+
+    let commit_id = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-r@", "-T", "commit_id"],
+    );
+    let stdout =
+        test_env.jj_cmd_success(&workspace_path, &["debug", "object", "commit", &commit_id]);
+    assert_snapshot!(stdout, @r###"
+    Commit {
+        parents: [
+            CommitId(
+                "0000000000000000000000000000000000000000",
+            ),
+        ],
+        predecessors: [
+            CommitId(
+                "cf911c223d3e24e001fc8264d6dbf0610804fc40",
+            ),
+        ],
+        root_tree: Merge(
+            Resolved(
+                TreeId(
+                    "8add0d07efc6ba027407c82740a001cfcbc7b772",
+                ),
+            ),
+        ),
+        change_id: ChangeId(
+            "9a45c67d3e96a7e5007c110ede34dec5",
+        ),
+        description: "first\n",
+        author: Signature {
+            name: "Test User",
+            email: "test.user@example.com",
+            timestamp: Timestamp {
+                timestamp: MillisSinceEpoch(
+                    981147907000,
+                ),
+                tz_offset: 420,
+            },
+        },
+        committer: Signature {
+            name: "Test User",
+            email: "test.user@example.com",
+            timestamp: Timestamp {
+                timestamp: MillisSinceEpoch(
+                    981147908000,
+                ),
+                tz_offset: 420,
+            },
+        },
+        secure_sig: None,
+        copies: [],
+    }
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["debug", "object", "tree", "xyz"]);
+    assert_snapshot!(stderr, @"Error: Invalid tree id");
 }
 
 #[test]
@@ -211,6 +448,56 @@ fn test_debug_operation_id() {
     );
 }
 
+#[test]
+fn test_debug_revwalk() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+    std::fs::write(workspace_path.join("file"), "contents\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["debug", "revwalk", "-r=::@", "-r=none()", "--iterations=3"],
+    );
+    assert_snapshot!(filter_timings(&stdout), @r###"
+    jj [VERSION]
+    Runs per step: 3
+    Step                                          First        Min     Median        Max
+    revset ::@                               [TIMINGS]  2 commits
+    revset none()                            [TIMINGS]  0 commits
+    diff root() @                            [TIMINGS]  1 paths
+    snapshot                                 [TIMINGS]  unchanged
+    "###);
+
+    // The snapshots are skipped if the working copy can't be updated
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &[
+            "debug",
+            "revwalk",
+            "-r=@",
+            "--from=@-",
+            "--iterations=1",
+            "--ignore-working-copy",
+        ],
+    );
+    assert_snapshot!(filter_timings(&stdout), @r###"
+    jj [VERSION]
+    Runs per step: 1
+    Step                                          First        Min     Median        Max
+    revset @                                 [TIMINGS]  1 commits
+    diff @- @                                [TIMINGS]  1 paths
+    "###);
+}
+
+fn filter_timings(text: &str) -> String {
+    let version_regex = Regex::new(r"(?m)^jj .*$").unwrap();
+    // The step names are padded to 40 characters, which are kept
+    let timings_regex = Regex::new(r"(?m)^(.{40})( +[0-9.]+(ns|µs|ms|s)){4}").unwrap();
+    let text = version_regex.replace_all(text, "jj [VERSION]");
+    timings_regex.replace_all(&text, "$1 [TIMINGS]").to_string()
+}
+
 fn filter_index_stats(text: &str) -> String {
     let regex = Regex::new(r"    Name: [0-9a-z]+").unwrap();
     regex.replace_all(text, "    Name: [hash]").to_string()
@@ -111,10 +111,10 @@ fn test_describe() {
     "###);
     let stdout =
         test_env.jj_cmd_success(&repo_path, &["log", "--no-graph", "-r@", "-Tdescription"]);
-    insta::assert_snapshot!(stdout, @r#"
+    insta::assert_snapshot!(stdout, @r###"
     line1
     line2
-    "#);
+    "###);
 
     // Clear description
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["describe", "-m", ""]);
@@ -171,6 +171,125 @@ fn test_describe() {
     assert!(get_stderr_string(&assert).contains("bad-jj-editor-from-jj-editor-env"));
 }
 
+#[test]
+fn test_describe_multiple_commits() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&workspace_path, &["new"]);
+    test_env.jj_cmd_ok(&workspace_path, &["new"]);
+    let edit_script = test_env.set_up_fake_editor();
+    let get_log_output = || {
+        test_env.jj_cmd_success(
+            &workspace_path,
+            &["log", "-T", r#"commit_id.short() ++ " " ++ description"#],
+        )
+    };
+    let commit_id = |revision: &str| {
+        test_env.jj_cmd_success(
+            &workspace_path,
+            &["log", "--no-graph", "-r", revision, "-T", "commit_id"],
+        )
+    };
+
+    // The same description is set on all of the revisions
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &workspace_path,
+        &["describe", "-r", "@-", "-r", "@--", "-m", "a"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Updated 2 commits
+    Rebased 1 descendant commits
+    Working copy now at: kkmpptxz c045e040 (empty) (no description set)
+    Parent commit      : rlvkpnrz 6f603cb9 (empty) a
+    "###);
+    insta::assert_snapshot!(get_log_output(), @r###"
+    @  c045e04031a7
+    ◉  6f603cb9c49c a
+    ◉  4bfd80cd6e74 a
+    ◉  000000000000
+    "###);
+
+    // The descriptions are edited in a single editor
+    std::fs::write(
+        &edit_script,
+        [
+            "dump editor",
+            &format!(
+                "write\nJJ: describe {} -------\nb\n\nJJ: describe {} -------\nc\n",
+                &commit_id("@-")[..4],
+                commit_id("@"),
+            ),
+        ]
+        .join("\0"),
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_path, &["describe", "@", "@-"]);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("editor")).unwrap(), @r###"
+    JJ: describe c045e04031a7 -------
+
+    JJ: describe 6f603cb9c49c -------
+    a
+
+    JJ: Lines starting with "JJ: " (like this one) will be removed.
+    JJ: Lines starting with "JJ: describe" tell which commit the description below
+    JJ: belongs to. Don't change them.
+    "###);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Updated 2 commits
+    Working copy now at: kkmpptxz 6c57d233 (empty) c
+    Parent commit      : rlvkpnrz 0ef7c175 (empty) b
+    "###);
+    insta::assert_snapshot!(get_log_output(), @r###"
+    @  6c57d233d876 c
+    ◉  0ef7c17581fe b
+    ◉  4bfd80cd6e74 a
+    ◉  000000000000
+    "###);
+
+    // Only the revisions with a header are described, and the others are
+    // rebased
+    let stdin = format!("JJ: describe {} -------\nd\n", commit_id("@--"));
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(
+        &workspace_path,
+        &["describe", "@", "@--", "--stdin"],
+        &stdin,
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Rebased 2 descendant commits
+    Working copy now at: kkmpptxz 45c890e7 (empty) c
+    Parent commit      : rlvkpnrz d8772d7c (empty) b
+    "###);
+    insta::assert_snapshot!(get_log_output(), @r###"
+    @  45c890e7059f c
+    ◉  d8772d7cacb8 b
+    ◉  ab5bd60d1a24 d
+    ◉  000000000000
+    "###);
+
+    // The descriptions must be preceded by a header naming one of the revisions
+    let assert = test_env
+        .jj_cmd_stdin(&workspace_path, &["describe", "@", "@-", "--stdin"], "e\n")
+        .assert()
+        .failure();
+    insta::assert_snapshot!(get_stderr_string(&assert), @r###"
+    Error: Found a description without a `JJ: describe <commit id>` header
+    Hint: Precede each description with a header naming the commit it belongs to.
+    "###);
+    let stdin = format!("JJ: describe {} -------\ne\n", &commit_id("@--")[..12]);
+    let assert = test_env
+        .jj_cmd_stdin(&workspace_path, &["describe", "@", "@-", "--stdin"], &stdin)
+        .assert()
+        .failure();
+    insta::assert_snapshot!(get_stderr_string(&assert), @r###"
+    Error: The description header `JJ: describe ab5bd60d1a24 -------` doesn't match exactly one of the revisions being described
+    "###);
+}
+
 #[test]
 fn test_multiple_message_args() {
     let test_env = TestEnvironment::default();
@@ -278,6 +397,156 @@ JJ: Lines starting with "JJ: " (like this one) will be removed.
     );
 }
 
+#[test]
+fn test_describe_description_template_file() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    test_env.add_config(r#"ui.default-description = "unused""#);
+    test_env.add_config(r#"ui.description-template-file = "../template.txt""#);
+    let workspace_path = test_env.env_root().join("repo");
+
+    std::fs::write(test_env.env_root().join("template.txt"), "\n\nBUG=\n").unwrap();
+    let edit_script = test_env.set_up_fake_editor();
+    std::fs::write(edit_script, ["dump editor"].join("\0")).unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["describe"]);
+    assert_eq!(
+        std::fs::read_to_string(test_env.env_root().join("editor")).unwrap(),
+        r#"
+
+BUG=
+
+JJ: Lines starting with "JJ: " (like this one) will be removed.
+"#
+    );
+
+    std::fs::remove_file(test_env.env_root().join("template.txt")).unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["new"]);
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["describe"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Failed to read the description template file $TEST_ENV/repo/../template.txt
+    Caused by: No such file or directory (os error 2)
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
+}
+
+#[test]
+fn test_describe_lint() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    test_env.add_config(
+        r#"
+        [description-lint]
+        max-subject-length = 10
+        subject-pattern = '^(feat|fix): '
+        "#,
+    );
+    let workspace_path = test_env.env_root().join("repo");
+
+    // The problems are reported as warnings by default
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", "a long subject"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: The description subject is 14 characters long, more than 10
+    Warning: The description subject doesn't match the pattern `^(feat|fix): `
+    Working copy now at: qpvuntsm dd193d3b (empty) a long subject
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    "###);
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", "fix: a\n\nbody"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm 132d0f15 (empty) fix: a
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    "###);
+
+    // Empty descriptions aren't checked
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", ""]);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm 0103ba47 (empty) (no description set)
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    "###);
+
+    // The description is rejected if the lint is blocking
+    test_env.add_config(r#"description-lint.level = "error""#);
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["describe", "-m", "a long subject"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: The description subject is 14 characters long, more than 10
+    Warning: The description subject doesn't match the pattern `^(feat|fix): `
+    Error: The description doesn't pass the lint checks
+    Hint: Fix the description, or set `description-lint.level = "warn"` to only warn.
+    "###);
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["commit", "-m", "feat: too long"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: The description subject is 14 characters long, more than 10
+    Error: The description doesn't pass the lint checks
+    Hint: Fix the description, or set `description-lint.level = "warn"` to only warn.
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &["log", "--no-graph", "-r@", "-Tdescription"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+
+    test_env.add_config(r#"description-lint.level = "block""#);
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["describe", "-m", "fix: a"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid `description-lint.level`: block; expected "warn" or "error"
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
+}
+
+#[test]
+fn test_describe_trailers() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let workspace_path = test_env.env_root().join("repo");
+    let get_description = || {
+        test_env.jj_cmd_success(
+            &workspace_path,
+            &["log", "--no-graph", "-r@", "-Tdescription"],
+        )
+    };
+
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", "subject", "--signoff"]);
+    insta::assert_snapshot!(get_description(), @r###"
+    subject
+
+    Signed-off-by: Test User <test.user@example.com>
+    "###);
+
+    // The trailer isn't added again
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&workspace_path, &["describe", "--no-edit", "--signoff"]);
+    insta::assert_snapshot!(stderr, @"Nothing changed.");
+
+    // Configured trailers are added to the existing trailers
+    test_env.add_config(
+        r#"
+        trailers.co-authors = ["Alice <alice@example.com>"]
+        trailers.change-id = true
+        "#,
+    );
+    test_env.jj_cmd_ok(
+        &workspace_path,
+        &[
+            "describe",
+            "-m",
+            "subject\n\nbody\n\nCo-authored-by: Alice <alice@example.com>",
+        ],
+    );
+    insta::assert_snapshot!(get_description(), @r###"
+    subject
+
+    body
+
+    Co-authored-by: Alice <alice@example.com>
+    Change-Id: I6a6a69649a45c67d3e96a7e5007c110ede34dec5
+    "###);
+
+    // Empty descriptions are left empty
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", "", "--signoff"]);
+    insta::assert_snapshot!(get_description(), @"");
+}
+
 #[test]
 fn test_describe_author() {
     let test_env = TestEnvironment::default();
@@ -322,4 +591,163 @@ fn test_describe_author() {
     │  Ove Ridder ove.ridder@example.com 2001-02-03 04:05:09.000 +07:00
     ~
     "###);
+
+    // Set the author name and email, keeping the timestamp
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["describe", "--no-edit", "--author", "Foo Bar <foo@bar.com>"],
+    );
+    insta::assert_snapshot!(get_signatures(), @r###"
+    @  Foo Bar foo@bar.com 2001-02-03 04:05:09.000 +07:00
+    │  Test User test.user@example.com 2001-02-03 04:05:11.000 +07:00
+    ~
+    "###);
+
+    // Set the author timestamp
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "--no-edit",
+            "--author-date",
+            "2020-01-02T03:04:05+06:00",
+        ],
+    );
+    insta::assert_snapshot!(get_signatures(), @r###"
+    @  Foo Bar foo@bar.com 2020-01-02 03:04:05.000 +06:00
+    │  Test User test.user@example.com 2001-02-03 04:05:13.000 +07:00
+    ~
+    "###);
+
+    // Reset the author, but with a different name and email
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "--no-edit",
+            "--reset-author",
+            "--author",
+            "Baz Qux <baz@qux.com>",
+        ],
+    );
+    insta::assert_snapshot!(get_signatures(), @r###"
+    @  Baz Qux baz@qux.com 2001-02-03 04:05:15.000 +07:00
+    │  Test User test.user@example.com 2001-02-03 04:05:15.000 +07:00
+    ~
+    "###);
+
+    // Invalid author and timestamp
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["describe", "--author", "Foo Bar"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'Foo Bar' for '--author <AUTHOR>': Author must be in the form `Name <email>`
+
+    For more information, try '--help'.
+    "###);
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["describe", "--author-date", "foo"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'foo' for '--author-date <DATE>': Invalid date "foo"
+
+    For more information, try '--help'.
+    "###);
+}
+
+#[test]
+fn test_describe_builtin_editor() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_path = test_env.env_root().join("repo");
+    test_env.add_config(r#"ui.editor = ":builtin""#);
+    test_env.jj_cmd_ok(&workspace_path, &["new"]);
+
+    // The lines entered replace the description
+    let (stdout, stderr) =
+        test_env.jj_cmd_stdin_ok(&workspace_path, &["describe"], "first\n\nbody\n.\n");
+    insta::assert_snapshot!(stdout, @r###"
+    |
+    Enter the new text followed by a line with only ".", or only "." to keep it:
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz da48cd70 (empty) first
+    Parent commit      : qpvuntsm 230dd059 (empty) (no description set)
+    "###);
+
+    // Entering only "." keeps the description
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(&workspace_path, &["describe"], ".\n");
+    insta::assert_snapshot!(stdout, @r###"
+    | first
+    |
+    | body
+    |
+    Enter the new text followed by a line with only ".", or only "." to keep it:
+    "###);
+    insta::assert_snapshot!(stderr, @"Nothing changed.");
+
+    // Each description is edited separately, keeping the comments
+    let (stdout, stderr) =
+        test_env.jj_cmd_stdin_ok(&workspace_path, &["describe", "@", "@-"], "second\n.\n.\n");
+    insta::assert_snapshot!(stdout, @r###"
+    JJ: describe da48cd70b0fd -------
+    | first
+    |
+    | body
+    |
+    Enter the new text followed by a line with only ".", or only "." to keep it:
+    JJ: describe 230dd059e1b0 -------
+    |
+    Enter the new text followed by a line with only ".", or only "." to keep it:
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz 71d103bc (empty) second
+    Parent commit      : qpvuntsm 230dd059 (empty) (no description set)
+    "###);
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  second
+    ◉
+    ◉
+    "###);
+
+    // Empty input is refused if stdin isn't a terminal
+    let mut cmd = test_env.jj_cmd(&workspace_path, &["describe"]);
+    cmd.write_stdin("");
+    let stderr = get_stderr_string(&cmd.assert().code(1));
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot run the built-in editor with empty input since the output is not connected to a terminal
+    "###);
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  second
+    ◉
+    ◉
+    "###);
+}
+
+// Notepad, the default editor on Windows, is always installed
+#[cfg(unix)]
+#[test]
+fn test_describe_builtin_editor_fallback() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_path = test_env.env_root().join("repo");
+
+    // The built-in editor is used if no editor is configured and the default
+    // one isn't installed
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(&workspace_path, &["describe"], "first");
+    insta::assert_snapshot!(stdout, @r###"
+    |
+    Enter the new text followed by a line with only ".", or only "." to keep it:
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: Editor 'pico' not found, using the built-in editor
+    Working copy now at: qpvuntsm 69542c19 (empty) first
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    "###);
+
+    // A configured editor isn't replaced by the built-in one
+    test_env.add_config(r#"ui.editor = "nonexistent-editor""#);
+    let stderr = test_env.jj_cmd_failure(&workspace_path, &["describe"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to run editor 'nonexistent-editor'
+    Caused by: No such file or directory (os error 2)
+    "###);
 }
@@ -14,7 +14,7 @@
 
 use itertools::Itertools;
 
-use crate::common::{escaped_fake_diff_editor_path, strip_last_line, TestEnvironment};
+use crate::common::{strip_last_line, TestEnvironment};
 
 #[test]
 fn test_diff_basic() {
@@ -72,10 +72,10 @@ fn test_diff_basic() {
     index 257cc5642c..0000000000
     --- a/file1
     +++ /dev/null
-    @@ -1,1 +1,0 @@
+    @@ -1,1 +0,0 @@
     -foo
     diff --git a/file2 b/file2
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file2
     +++ b/file2
     @@ -1,1 +1,2 @@
@@ -86,7 +86,7 @@ fn test_diff_basic() {
     index 0000000000..257cc5642c
     --- /dev/null
     +++ b/file3
-    @@ -1,0 +1,1 @@
+    @@ -0,0 +1,1 @@
     +foo
     "###);
 
@@ -97,20 +97,20 @@ fn test_diff_basic() {
     index 257cc5642c..0000000000
     --- a/file1
     +++ /dev/null
-    @@ -1,1 +1,0 @@
+    @@ -1,1 +0,0 @@
     -foo
     diff --git a/file2 b/file2
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file2
     +++ b/file2
-    @@ -2,0 +2,1 @@
+    @@ -1,0 +2,1 @@
     +bar
     diff --git a/file3 b/file3
     new file mode 100644
     index 0000000000..257cc5642c
     --- /dev/null
     +++ b/file3
-    @@ -1,0 +1,1 @@
+    @@ -0,0 +1,1 @@
     +foo
     "###);
 
@@ -124,10 +124,10 @@ fn test_diff_basic() {
     index 257cc5642c..0000000000
     --- a/file1
     +++ /dev/null
-    @@ -1,1 +1,0 @@
+    @@ -1,1 +0,0 @@
     -foo
     diff --git a/file2 b/file2
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file2
     +++ b/file2
     @@ -1,1 +1,2 @@
@@ -138,7 +138,7 @@ fn test_diff_basic() {
     index 0000000000..257cc5642c
     --- /dev/null
     +++ b/file3
-    @@ -1,0 +1,1 @@
+    @@ -0,0 +1,1 @@
     +foo
     "###);
 
@@ -151,6 +151,159 @@ fn test_diff_basic() {
     "###);
 }
 
+#[test]
+fn test_diff_renamed_and_copied_files() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("diff.detect-renames = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "1\n2\n3\n4\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a\nb\nc\n").unwrap();
+    std::fs::write(repo_path.join("file3"), "x\ny\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::remove_file(repo_path.join("file1")).unwrap();
+    std::fs::write(repo_path.join("dir/renamed"), "1\n2\n3\nfour\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a\nb\nc\nd\n").unwrap();
+    std::fs::write(repo_path.join("copied"), "a\nb\nc\n").unwrap();
+    // Not similar enough to file3
+    std::fs::remove_file(repo_path.join("file3")).unwrap();
+    std::fs::write(repo_path.join("file4"), "x\nz\nw\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @r###"
+    C file2 -> copied
+    R file1 -> dir/renamed
+    M file2
+    D file3
+    A file4
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Working copy changes:
+    C file2 -> copied
+    R file1 -> dir/renamed
+    M file2
+    D file3
+    A file4
+    Working copy : rlvkpnrz afba14d1 (no description set)
+    Parent commit: qpvuntsm a44fd9f1 (no description set)
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file2 b/copied
+    similarity index 100%
+    copy from file2
+    copy to copied
+    diff --git a/file1 b/dir/renamed
+    similarity index 54%
+    rename from file1
+    rename to dir/renamed
+    index 94ebaf9001..c33d4989a2 100644
+    --- a/file1
+    +++ b/dir/renamed
+    @@ -1,4 +1,4 @@
+     1
+     2
+     3
+    -4
+    +four
+    diff --git a/file2 b/file2
+    index de980441c3..d68dd4031d 100644
+    --- a/file2
+    +++ b/file2
+    @@ -1,3 +1,4 @@
+     a
+     b
+     c
+    +d
+    diff --git a/file3 b/file3
+    deleted file mode 100644
+    index b77b4eb1d9..0000000000
+    --- a/file3
+    +++ /dev/null
+    @@ -1,2 +0,0 @@
+    -x
+    -y
+    diff --git a/file4 b/file4
+    new file mode 100644
+    index 0000000000..df06f7a359
+    --- /dev/null
+    +++ b/file4
+    @@ -0,0 +1,3 @@
+    +x
+    +z
+    +w
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Modified regular file file2 => copied:
+    Modified regular file file1 => dir/renamed:
+       1    1: 1
+       2    2: 2
+       3    3: 3
+       4    4: 4four
+    Modified regular file file2:
+       1    1: a
+       2    2: b
+       3    3: c
+            4: d
+    Removed regular file file3:
+       1     : x
+       2     : y
+    Added regular file file4:
+            1: x
+            2: z
+            3: w
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--types"]);
+    insta::assert_snapshot!(stdout, @r###"
+    FF file2 => copied
+    FF file1 => dir/renamed
+    FF file2
+    F- file3
+    -F file4
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--stat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file2 => copied      | 0
+    file1 => dir/renamed | 2 +-
+    file2                | 1 +
+    file3                | 2 --
+    file4                | 3 +++
+    5 files changed, 5 insertions(+), 3 deletions(-)
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--numstat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    0	0	file2 => copied
+    1	1	file1 => dir/renamed
+    1	0	file2
+    0	2	file3
+    3	0	file4
+    "###);
+
+    // Renamed files aren't detected unless enabled
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["diff", "-s", "--config-toml=diff.detect-renames=false"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    A copied
+    A dir/renamed
+    D file1
+    M file2
+    D file3
+    A file4
+    "###);
+}
+
 #[test]
 fn test_diff_empty() {
     let test_env = TestEnvironment::default();
@@ -358,28 +511,28 @@ fn test_diff_relative_paths() {
     let stdout = test_env.jj_cmd_success(&repo_path.join("dir1"), &["diff", "--git"]);
     insta::assert_snapshot!(stdout, @r###"
     diff --git a/dir1/file2 b/dir1/file2
-    index 54b060eee9...1fe912cdd8 100644
+    index 54b060eee9..1fe912cdd8 100644
     --- a/dir1/file2
     +++ b/dir1/file2
     @@ -1,1 +1,1 @@
     -foo2
     +bar2
     diff --git a/dir1/subdir1/file3 b/dir1/subdir1/file3
-    index c1ec6c6f12...f3c8b75ec6 100644
+    index c1ec6c6f12..f3c8b75ec6 100644
     --- a/dir1/subdir1/file3
     +++ b/dir1/subdir1/file3
     @@ -1,1 +1,1 @@
     -foo3
     +bar3
     diff --git a/dir2/file4 b/dir2/file4
-    index a0016dbc4c...17375f7a12 100644
+    index a0016dbc4c..17375f7a12 100644
     --- a/dir2/file4
     +++ b/dir2/file4
     @@ -1,1 +1,1 @@
     -foo4
     +bar4
     diff --git a/file1 b/file1
-    index 1715acd6a5...05c4fe6772 100644
+    index 1715acd6a5..05c4fe6772 100644
     --- a/file1
     +++ b/file1
     @@ -1,1 +1,1 @@
@@ -431,7 +584,7 @@ fn test_diff_missing_newline() {
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
     insta::assert_snapshot!(stdout, @r###"
     diff --git a/file1 b/file1
-    index 1910281566...a907ec3f43 100644
+    index 1910281566..a907ec3f43 100644
     --- a/file1
     +++ b/file1
     @@ -1,1 +1,2 @@
@@ -441,7 +594,7 @@ fn test_diff_missing_newline() {
     +bar
     \ No newline at end of file
     diff --git a/file2 b/file2
-    index a907ec3f43...1910281566 100644
+    index a907ec3f43..1910281566 100644
     --- a/file2
     +++ b/file2
     @@ -1,2 +1,1 @@
@@ -561,6 +714,167 @@ fn test_color_words_diff_missing_newline() {
     "###);
 }
 
+#[test]
+fn test_diff_git_highlight_inline() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nfoo bar baz\nb\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "a\nfoo qux baz\nb\nadded\n").unwrap();
+
+    // Disabled by default
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git", "--color=always"]);
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file1 b/file1[0m
+    [1mindex 758a321eb0..10bf6b6dc6 100644[0m
+    [1m--- a/file1[0m
+    [1m+++ b/file1[0m
+    [38;5;6m@@ -1,3 +1,4 @@[39m
+     a
+    [38;5;1m-foo bar baz[39m
+    [38;5;2m+foo qux baz[39m
+     b
+    [38;5;2m+added[39m
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--color=always",
+            "--config-toml=diff.highlight-inline=true",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file1 b/file1[0m
+    [1mindex 758a321eb0..10bf6b6dc6 100644[0m
+    [1m--- a/file1[0m
+    [1m+++ b/file1[0m
+    [38;5;6m@@ -1,3 +1,4 @@[39m
+     a
+    [38;5;1m-foo [4mbar[24m baz[39m
+    [38;5;2m+foo [4mqux[24m baz[39m
+     b
+    [38;5;2m+added[39m
+    "###);
+
+    // Plain-text output is unchanged
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["diff", "--git", "--config-toml=diff.highlight-inline=true"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file1 b/file1
+    index 758a321eb0..10bf6b6dc6 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,3 +1,4 @@
+     a
+    -foo bar baz
+    +foo qux baz
+     b
+    +added
+    "###);
+}
+
+#[test]
+fn test_diff_git_syntax_highlight() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("main.rs"), "fn main() {\n    f(1);\n}\n").unwrap();
+    std::fs::write(repo_path.join("file"), "fn main() {\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(
+        repo_path.join("main.rs"),
+        "fn main() {\n    f(\"2\"); // comment\n    return;\n}\n",
+    )
+    .unwrap();
+    std::fs::write(repo_path.join("file"), "fn main() { }\n").unwrap();
+
+    // Files of unknown languages aren't highlighted
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--color=always",
+            "--config-toml=diff.syntax-highlight=true",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/file b/file[0m
+    [1mindex defa9c6a3c..45590d86ba 100644[0m
+    [1m--- a/file[0m
+    [1m+++ b/file[0m
+    [38;5;6m@@ -1,1 +1,1 @@[39m
+    [38;5;1m-fn main() {[39m
+    [38;5;2m+fn main() { }[39m
+    [1mdiff --git a/main.rs b/main.rs[0m
+    [1mindex 823f7a72ce..5af18e1188 100644[0m
+    [1m--- a/main.rs[0m
+    [1m+++ b/main.rs[0m
+    [38;5;6m@@ -1,3 +1,4 @@[39m
+     [1m[38;5;5mfn[0m main() {
+    [38;5;1m-    f(1);[39m
+    [38;5;2m+    f("2"); // comment[39m
+    [38;5;2m+    [1mreturn[0m[38;5;2m;[39m
+     }
+    "###);
+
+    // Combined with inline highlighting
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--color=always",
+            "--config-toml=diff.syntax-highlight=true",
+            "--config-toml=diff.highlight-inline=true",
+            "main.rs",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    [1mdiff --git a/main.rs b/main.rs[0m
+    [1mindex 823f7a72ce..5af18e1188 100644[0m
+    [1m--- a/main.rs[0m
+    [1m+++ b/main.rs[0m
+    [38;5;6m@@ -1,3 +1,4 @@[39m
+     [1m[38;5;5mfn[0m main() {
+    [38;5;1m-    f([4m1[24m);[39m
+    [38;5;2m+    f([4m"2"[24m);[4m // comment[24m[39m
+    [38;5;2m+[4m    [1mreturn[0m[4m[38;5;2m;[24m[39m
+     }
+    "###);
+
+    // Plain-text output is unchanged
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--config-toml=diff.syntax-highlight=true",
+            "main.rs",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/main.rs b/main.rs
+    index 823f7a72ce..5af18e1188 100644
+    --- a/main.rs
+    +++ b/main.rs
+    @@ -1,3 +1,4 @@
+     fn main() {
+    -    f(1);
+    +    f("2"); // comment
+    +    return;
+     }
+    "###);
+}
+
 #[test]
 fn test_diff_skipped_context() {
     let test_env = TestEnvironment::default();
@@ -740,6 +1054,64 @@ fn test_diff_skipped_context_nondefault() {
     "###);
 }
 
+#[test]
+fn test_diff_context_config() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\nd\ne\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "a\nb\nC\nd\ne\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["diff", "--git", "--config-toml=diff.context=1"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file1 b/file1
+    index 9405325339..d7ec9f78e2 100644
+    --- a/file1
+    +++ b/file1
+    @@ -2,3 +2,3 @@
+     b
+    -c
+    +C
+     d
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["show", "-T", r#""""#, "--config-toml=diff.context=0"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    Modified regular file file1:
+        ...
+       3    3: cC
+        ...
+    "###);
+
+    // The command argument overrides the config
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "diff",
+            "--git",
+            "--context=0",
+            "--config-toml=diff.context=1",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file1 b/file1
+    index 9405325339..d7ec9f78e2 100644
+    --- a/file1
+    +++ b/file1
+    @@ -3,1 +3,1 @@
+    -c
+    +C
+    "###);
+}
+
 #[test]
 fn test_diff_external_tool() {
     let mut test_env = TestEnvironment::default();
@@ -822,7 +1194,7 @@ fn test_diff_external_tool() {
     "###);
 
     // Inlined command arguments
-    let command = escaped_fake_diff_editor_path();
+    let command = test_env.escaped_fake_diff_editor_path();
     let config = format!(r#"--config-toml=ui.diff.tool=["{command}", "$right", "$left"]"#);
     insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["diff", &config]), @r###"
     file2
@@ -938,6 +1310,41 @@ fn test_diff_stat() {
     "###);
 }
 
+#[test]
+fn test_diff_numstat() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("file1"), "foo\nbar\n").unwrap();
+    std::fs::write(repo_path.join("dir").join("file2"), "foo\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--numstat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    1	0	dir/file2
+    2	0	file1
+    "###);
+
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "foo\nbaz\nqux\n").unwrap();
+    std::fs::remove_file(repo_path.join("dir").join("file2")).unwrap();
+    std::fs::write(repo_path.join("binary"), b"\0\x01").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--numstat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    -	-	binary
+    0	1	dir/file2
+    2	1	file1
+    "###);
+
+    // Paths are relative to the current directory
+    let stdout = test_env.jj_cmd_success(&repo_path.join("dir"), &["diff", "--numstat", "-r@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    1	0	file2
+    2	0	../file1
+    "###);
+}
+
 #[test]
 fn test_diff_stat_long_name_or_stat() {
     let mut test_env = TestEnvironment::default();
@@ -1070,13 +1477,13 @@ fn test_diff_binary() {
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff"]);
     insta::assert_snapshot!(stdout, @r###"
     Removed regular file file1.png:
-        (binary)
+        (binary file, 16 B)
     Modified regular file file2.png:
-        (binary)
+        (binary file changed, 16 B -> 8 B)
     Added regular file file3.png:
-        (binary)
+        (binary file, 12 B)
     Added regular file file4.png:
-        (binary)
+        (binary file, 3 B)
     "###);
 
     let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--stat"]);
@@ -1087,4 +1494,249 @@ fn test_diff_binary() {
     file4.png | 1 +
     4 files changed, 6 insertions(+), 6 deletions(-)
     "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["diff", "--git", "--config-toml=diff.detect-renames=true"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file2.png b/file2.png
+    index 7f036ce788..3bd1f0e297 100644
+    Binary files a/file2.png and b/file2.png differ
+    diff --git a/file1.png b/file3.png
+    similarity index 50%
+    rename from file1.png
+    rename to file3.png
+    index 2b65b23c22..deacfbc286 100644
+    Binary files a/file1.png and b/file3.png differ
+    diff --git a/file4.png b/file4.png
+    new file mode 100644
+    index 0000000000..4227ca4e87
+    Binary files /dev/null and b/file4.png differ
+    "###);
+
+    // --binary shows the contents as text
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file4.png"), b"\0\0\0\nfoo\n").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--binary"]);
+    insta::assert_snapshot!(stdout.replace('\0', "<NUL>"), @r###"
+    Modified regular file file4.png:
+       1    1: <NUL><NUL><NUL>
+            2: foo
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git", "--binary"]);
+    insta::assert_snapshot!(stdout.replace('\0', "<NUL>"), @r###"
+    diff --git a/file4.png b/file4.png
+    index 4227ca4e87..90f2a69fb3 100644
+    --- a/file4.png
+    +++ b/file4.png
+    @@ -1,1 +1,2 @@
+    -<NUL><NUL><NUL>
+    \ No newline at end of file
+    +<NUL><NUL><NUL>
+    +foo
+    "###);
+}
+
+#[test]
+fn test_diff_output_patch_files() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(repo_path.join("file2.png"), b"\x89PNG\r\n\x1a\n\0").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first\n\nbody"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+    std::fs::write(repo_path.join("file1"), "foo\nbar\n").unwrap();
+    std::fs::write(repo_path.join("file2.png"), b"\0\0\0").unwrap();
+
+    // The patch includes the contents of binary files, and the full hashes
+    let output_path = test_env.env_root().join("out.patch");
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "diff",
+            "--from",
+            "root()",
+            "--output",
+            output_path.to_str().unwrap(),
+            "file2.png",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    let patch = std::fs::read_to_string(&output_path).unwrap();
+    insta::assert_snapshot!(hide_binary_patch_data(&patch), @r###"
+    diff --git a/file2.png b/file2.png
+    new file mode 100644
+    index 0000000000000000000000000000000000000000..4227ca4e8736af63036e7457e2db376ddf7e5795
+    GIT binary patch
+    literal 3
+    <compressed data>
+
+    literal 0
+    <compressed data>
+    "###);
+
+    // The diff formatting options can't be combined with an output file
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["diff", "--output", output_path.to_str().unwrap(), "--stat"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--output <FILE>' cannot be used with:
+      --summary
+      --stat
+      --numstat
+      --types
+
+    Usage: jj diff --output <FILE> --stat [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+
+    // One patch is written for each revision, with a header for `git am`
+    let output_dir = test_env.env_root().join("patches");
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "diff",
+            "-r",
+            "root()..@",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Wrote 2 patches to $TEST_ENV/patches
+    "###);
+    let file_names = std::fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .sorted()
+        .collect_vec();
+    insta::assert_debug_snapshot!(file_names, @r###"
+    [
+        "0001-qpvuntsmwlqt.patch",
+        "0002-kkmpptxzrspx.patch",
+    ]
+    "###);
+    // The commit ids and dates are left out
+    let read_patch = |name: &str| {
+        let patch = std::fs::read_to_string(output_dir.join(name)).unwrap();
+        hide_binary_patch_data(&patch)
+            .lines()
+            .filter(|line| !line.starts_with("From ") && !line.starts_with("Date: "))
+            .join("\n")
+    };
+    insta::assert_snapshot!(read_patch(&file_names[0]), @r###"
+    From: Test User <test.user@example.com>
+    Subject: [PATCH] first
+
+    body
+    ---
+    diff --git a/file1 b/file1
+    new file mode 100644
+    index 0000000000000000000000000000000000000000..257cc5642cb1a054f08cc83f2d943e56fd3ebe99
+    --- /dev/null
+    +++ b/file1
+    @@ -0,0 +1,1 @@
+    +foo
+    diff --git a/file2.png b/file2.png
+    new file mode 100644
+    index 0000000000000000000000000000000000000000..aa860abb474c16b64e6a58a44d70206090fc2c56
+    GIT binary patch
+    literal 9
+    <compressed data>
+
+    literal 0
+    <compressed data>
+    "###);
+    insta::assert_snapshot!(read_patch(&file_names[1]), @r###"
+    From: Test User <test.user@example.com>
+    Subject: [PATCH] second
+
+    ---
+    diff --git a/file1 b/file1
+    index 257cc5642cb1a054f08cc83f2d943e56fd3ebe99..3bd1f0e29744a1f32b08d5650e62e2e62afb177c 100644
+    --- a/file1
+    +++ b/file1
+    @@ -1,1 +1,2 @@
+     foo
+    +bar
+    diff --git a/file2.png b/file2.png
+    index aa860abb474c16b64e6a58a44d70206090fc2c56..4227ca4e8736af63036e7457e2db376ddf7e5795 100644
+    GIT binary patch
+    literal 3
+    <compressed data>
+
+    literal 9
+    <compressed data>
+    "###);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_diff_binary_renderer() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("image.png"), b"\x89PNG\r\n\x1a\nabc\0").unwrap();
+    std::fs::write(repo_path.join("other"), b"\0abc").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("image.png"), b"\x89PNG\r\n\x1a\nabcdef\0").unwrap();
+    std::fs::write(repo_path.join("other"), b"\0abcdef").unwrap();
+    test_env.add_config(
+        r#"
+        [diff.binary-renderers]
+        "image/*" = ["sh", "-c", "echo \"image of $(wc -c | tr -d ' ') bytes\""]
+        "#,
+    );
+
+    // Only the files of the matching type are rendered
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Modified regular file image.png:
+       1    1: image of 1215 bytes
+    Modified regular file other:
+        (binary file changed, 4 B -> 7 B)
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git", "image.png"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/image.png b/image.png
+    index 87e70af223..9cf1703d0a 100644
+    --- a/image.png
+    +++ b/image.png
+    @@ -1,1 +1,1 @@
+    -image of 12 bytes
+    +image of 15 bytes
+    "###);
+
+    // Failing renderer
+    test_env
+        .add_config(r#"diff.binary-renderers."image/png" = ["sh", "-c", "echo oops >&2; false"]"#);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["diff"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to render binary file image.png
+    Caused by: Renderer exited with exit status: 1: oops
+    "###);
+}
+
+/// Replaces the data lines of the binary patches, which depend on the zlib
+/// implementation.
+fn hide_binary_patch_data(patch: &str) -> String {
+    let mut in_binary_hunk = false;
+    patch
+        .lines()
+        .map(|line| {
+            if in_binary_hunk && !line.is_empty() {
+                return "<compressed data>";
+            }
+            in_binary_hunk = line.starts_with("literal ") || line.starts_with("delta ");
+            line
+        })
+        .join("\n")
 }
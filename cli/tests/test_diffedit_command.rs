@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::{escaped_fake_diff_editor_path, TestEnvironment};
+use crate::common::TestEnvironment;
 
 #[test]
 fn test_diffedit() {
@@ -237,11 +237,11 @@ fn test_diffedit_3pane() {
     // fake diff editor considers the "after" state.
     let config_with_right_as_after = format!(
         r#"ui.diff-editor=["{}", "$left", "$right", "--ignore=$output"]"#,
-        escaped_fake_diff_editor_path()
+        test_env.escaped_fake_diff_editor_path()
     );
     let config_with_output_as_after = format!(
         r#"ui.diff-editor=["{}", "$left", "$output", "--ignore=$right"]"#,
-        escaped_fake_diff_editor_path()
+        test_env.escaped_fake_diff_editor_path()
     );
     let edit_script = test_env.env_root().join("diff_edit_script");
     std::fs::write(&edit_script, "").unwrap();
@@ -340,6 +340,48 @@ fn test_diffedit_3pane() {
     // since the file is readonly.
 }
 
+#[test]
+fn test_diffedit_3pane_output_from_left() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::remove_file(repo_path.join("file1")).unwrap();
+    std::fs::write(repo_path.join("file2"), "a\n").unwrap();
+
+    test_env.add_config(&format!(
+        r#"
+        [merge-tools.fake-3pane]
+        program = "{}"
+        edit-args = ["$left", "$output", "--ignore=$right"]
+        edit-output-from = "left"
+        "#,
+        test_env.escaped_fake_diff_editor_path()
+    ));
+    let edit_script = test_env.env_root().join("diff_edit_script");
+    test_env.add_env_var("DIFF_EDIT_SCRIPT", edit_script.to_str().unwrap());
+
+    // The output starts out with the left side, so the changes to keep have to be
+    // copied over
+    std::fs::write(
+        &edit_script,
+        "files-before file1\0files-after JJ-INSTRUCTIONS file1\0write file2\na\n",
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["diffedit", "--tool", "fake-3pane"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Created rlvkpnrz a493c482 (no description set)
+    Working copy now at: rlvkpnrz a493c482 (no description set)
+    Parent commit      : qpvuntsm 414e1614 (no description set)
+    Added 1 files, modified 0 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"A file2");
+}
+
 #[test]
 fn test_diffedit_merge() {
     let mut test_env = TestEnvironment::default();
@@ -479,10 +521,10 @@ fn test_diffedit_old_restore_interactive_tests() {
     index 7898192261..0000000000
     --- a/file1
     +++ /dev/null
-    @@ -1,1 +1,0 @@
+    @@ -1,1 +0,0 @@
     -a
     diff --git a/file2 b/file2
-    index 7898192261...6178079822 100644
+    index 7898192261..6178079822 100644
     --- a/file2
     +++ b/file2
     @@ -1,1 +1,1 @@
@@ -493,7 +535,7 @@ fn test_diffedit_old_restore_interactive_tests() {
     index 0000000000..c21c9352f7
     --- /dev/null
     +++ b/file3
-    @@ -1,0 +1,1 @@
+    @@ -0,0 +1,1 @@
     +unrelated
     "###);
 }
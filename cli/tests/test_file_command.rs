@@ -0,0 +1,148 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_file_move() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "x\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["file", "move", "file1", "renamed"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: rlvkpnrz 3a773020 (no description set)
+    Parent commit      : qpvuntsm 0a0ea4e2 (no description set)
+    Added 1 files, modified 0 files, removed 1 files
+    "###);
+    assert!(!repo_path.join("file1").exists());
+    assert_eq!(
+        std::fs::read_to_string(repo_path.join("renamed")).unwrap(),
+        "a\nb\nc\n"
+    );
+
+    // The rename is reported even if the contents change completely
+    std::fs::write(repo_path.join("renamed"), "d\ne\nf\n").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    Working copy changes:
+    R file1 -> renamed
+    Working copy : rlvkpnrz bb5951c3 (no description set)
+    Parent commit: qpvuntsm 0a0ea4e2 (no description set)
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r###"
+    diff --git a/file1 b/renamed
+    similarity index 0%
+    rename from file1
+    rename to renamed
+    index de980441c3..2bb9b90bce 100644
+    --- a/file1
+    +++ b/renamed
+    @@ -1,3 +1,3 @@
+    -a
+    -b
+    -c
+    +d
+    +e
+    +f
+    "###);
+
+    // Moving the file again keeps the original source
+    test_env.jj_cmd_ok(&repo_path, &["file", "mv", "renamed", "renamed2"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @"R file1 -> renamed2");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "move", "nonexistent", "file3"]);
+    insta::assert_snapshot!(stderr, @"Error: No such file at 'nonexistent'.");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "move", "file2", "renamed2"]);
+    insta::assert_snapshot!(stderr, @"Error: Destination already exists at 'renamed2'.");
+}
+
+#[test]
+fn test_file_copy() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+
+    test_env.jj_cmd_ok(&repo_path, &["file", "copy", "file1", "file2"]);
+    std::fs::write(repo_path.join("file2"), "d\ne\nf\n").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @"C file1 -> file2");
+
+    // A copy of a file added in the revision isn't recorded
+    std::fs::write(repo_path.join("new-file"), "g\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["file", "copy", "new-file", "file3"]);
+    std::fs::write(repo_path.join("file3"), "h\n").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @r###"
+    C file1 -> file2
+    A file3
+    A new-file
+    "###);
+}
+
+#[test]
+fn test_file_move_rebase() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "move"]);
+    test_env.jj_cmd_ok(&repo_path, &["file", "move", "file", "renamed"]);
+    // Too different to be detected as a rename
+    std::fs::write(repo_path.join("renamed"), "a\nb\nc\nd\ne\nf\n7\n8\n9\n10\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "modify", "@-"]);
+    std::fs::write(repo_path.join("file"), "1\n2\n3\n4\n5\n6\n7\n8\n9\nten\n").unwrap();
+
+    // The modification follows the recorded rename
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "rebase",
+            "-r",
+            "@",
+            "-d",
+            "description(move)",
+            "--config-toml=merge.follow-renames=true",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: zsuskuln c4de0972 modify
+    Parent commit      : rlvkpnrz 7abbcf4f move
+    Added 1 files, modified 0 files, removed 1 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "renamed"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    b
+    c
+    d
+    e
+    f
+    7
+    8
+    9
+    ten
+    "###);
+}
@@ -0,0 +1,90 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_files() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("dir").join("file 2"), "bb\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files"]);
+    insta::assert_snapshot!(stdout, @r###"
+    dir/file 2
+    file1
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files", "dir"]);
+    insta::assert_snapshot!(stdout, @r###"
+    dir/file 2
+    "###);
+
+    // Paths can be terminated with NUL
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files", "-0"]);
+    assert_eq!(stdout, "dir/file 2\0file1\0");
+
+    // -0 and -T can't be combined
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["files", "-0", "-T", "path"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--null' cannot be used with '--template <TEMPLATE>'
+
+    Usage: jj files --null [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+}
+
+#[test]
+fn test_files_template() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "base\n").unwrap();
+    std::fs::write(repo_path.join("script"), "#!/bin/sh\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "x", "script"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "a"]);
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "b", "@-"]);
+    std::fs::write(repo_path.join("file"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(a)", "description(b)"]);
+
+    let template = r#"path ++ " " ++ size ++ if(executable, " x") ++ if(conflict, " C") ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    file 43 C
+    script 10 x
+    "###);
+
+    // The template can terminate each path with NUL
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files", "-T", r#"path ++ "\0""#]);
+    assert_eq!(stdout, "file\0script\0");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["files", "-T", "name"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse template: Keyword "name" doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | name
+      | ^--^
+      |
+      = Keyword "name" doesn't exist
+    Hint: Did you mean "name_placeholder"?
+    "###);
+}
@@ -363,6 +363,106 @@ fn test_git_colocated_branches() {
     "###);
 }
 
+#[test]
+fn test_git_colocated_sync_explicit() {
+    let test_env = TestEnvironment::default();
+    let workspace_root = test_env.env_root().join("repo");
+    let git_repo = git2::Repository::init(&workspace_root).unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["init", "--git-repo", "."]);
+    test_env.jj_cmd_ok(&workspace_root, &["new"]);
+    test_env.add_config(r#"git.colocated-sync = "explicit""#);
+
+    // Branches created in jj are only exported by `jj git export`
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "foo", "-r@-"]);
+    assert!(git_repo.find_reference("refs/heads/foo").is_err());
+    test_env.jj_cmd_ok(&workspace_root, &["git", "export"]);
+    insta::assert_snapshot!(
+        git_repo.find_reference("refs/heads/foo").unwrap().target().unwrap().to_string(),
+        @"230dd059e1b059aefc0da06a2e5a7dbf22362f22"
+    );
+
+    // Branches created in Git are only imported by `jj git import`
+    let head_id = git_repo.head().unwrap().target().unwrap();
+    git_repo
+        .reference("refs/heads/bar", head_id, false, "test")
+        .unwrap();
+    git_repo
+        .reference("refs/heads/baz", head_id, false, "test")
+        .unwrap();
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_root), @r###"
+    @  65b6b74e08973b88d38404430f119c8c79465250
+    ◉  230dd059e1b059aefc0da06a2e5a7dbf22362f22 foo HEAD@git
+    ◉  0000000000000000000000000000000000000000
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "import", "--branch=bar"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"branch: bar [new] tracked");
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_root), @r###"
+    @  65b6b74e08973b88d38404430f119c8c79465250
+    ◉  230dd059e1b059aefc0da06a2e5a7dbf22362f22 bar foo HEAD@git
+    ◉  0000000000000000000000000000000000000000
+    "###);
+}
+
+#[test]
+fn test_git_colocated_sync_interval() {
+    let test_env = TestEnvironment::default();
+    let workspace_root = test_env.env_root().join("repo");
+    let git_repo = git2::Repository::init(&workspace_root).unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["init", "--git-repo", "."]);
+    test_env.jj_cmd_ok(&workspace_root, &["new"]);
+    test_env.add_config(
+        r#"
+        git.colocated-sync = "interval"
+        git.colocated-sync-interval = 3600
+        "#,
+    );
+    let head_id = git_repo.head().unwrap().target().unwrap();
+
+    // The refs are imported by the first command
+    git_repo
+        .reference("refs/heads/foo", head_id, false, "test")
+        .unwrap();
+    let (stdout, stderr) = get_log_output_with_stderr(&test_env, &workspace_root);
+    insta::assert_snapshot!(stdout, @r###"
+    @  65b6b74e08973b88d38404430f119c8c79465250
+    ◉  230dd059e1b059aefc0da06a2e5a7dbf22362f22 foo HEAD@git
+    ◉  0000000000000000000000000000000000000000
+    "###);
+    insta::assert_snapshot!(stderr, @"Done importing changes from the underlying Git repo.");
+
+    // But not by the following ones until the interval has elapsed
+    git_repo
+        .reference("refs/heads/bar", head_id, false, "test")
+        .unwrap();
+    insta::assert_snapshot!(get_log_output(&test_env, &workspace_root), @r###"
+    @  65b6b74e08973b88d38404430f119c8c79465250
+    ◉  230dd059e1b059aefc0da06a2e5a7dbf22362f22 foo HEAD@git
+    ◉  0000000000000000000000000000000000000000
+    "###);
+
+    // Changes are still exported on every command
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "baz", "-r@-"]);
+    insta::assert_snapshot!(
+        git_repo.find_reference("refs/heads/baz").unwrap().target().unwrap().to_string(),
+        @"230dd059e1b059aefc0da06a2e5a7dbf22362f22"
+    );
+}
+
+#[test]
+fn test_git_colocated_sync_invalid() {
+    let test_env = TestEnvironment::default();
+    let workspace_root = test_env.env_root().join("repo");
+    git2::Repository::init(&workspace_root).unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["init", "--git-repo", "."]);
+    test_env.add_config(r#"git.colocated-sync = "sometimes""#);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["log"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid `git.colocated-sync`: expected one of `always`, `explicit` or `interval`, got `sometimes`
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
+}
+
 #[test]
 fn test_git_colocated_branch_forget() {
     let test_env = TestEnvironment::default();
@@ -50,7 +50,8 @@ fn add_git_remote(test_env: &TestEnvironment, repo_path: &Path, remote: &str) {
 }
 
 fn get_branch_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
-    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes"])
+    // --quiet to suppress the hint about conflicted branches
+    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes", "--quiet"])
 }
 
 fn create_commit(test_env: &TestEnvironment, repo_path: &Path, name: &str, parents: &[&str]) {
@@ -307,6 +308,69 @@ fn test_git_fetch_from_remote_named_git() {
     "###);
 }
 
+#[test]
+fn test_git_fetch_prune_expired_remote_branches() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    add_git_remote(&test_env, &repo_path, "origin");
+    add_git_remote(&test_env, &repo_path, "tracked");
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "track", "tracked@tracked"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    origin@origin: oputwtnw ffecd2d6 message
+    tracked: kvmwppmv 910c405e message
+      @tracked: kvmwppmv 910c405e message
+    "###);
+
+    // The remote commits were made in 1970, but pruning isn't enabled
+    test_env.add_config(r#"git.remote-branch-expiry = "2000-01-01""#);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+
+    // Only the non-tracking remote branch is pruned
+    test_env.add_config("git.prune-expired-remote-branches = true");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Pruned 1 expired remote branches
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    tracked: kvmwppmv 910c405e message
+      @tracked: kvmwppmv 910c405e message
+    "###);
+
+    // The pruned branch isn't fetched again
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Nothing changed.
+    "###);
+    let git_repo = git2::Repository::open(repo_path.join(".jj/repo/store/git")).unwrap();
+    assert!(git_repo
+        .find_reference("refs/remotes/origin/origin")
+        .is_err());
+    assert!(git_repo
+        .find_reference("refs/jj/pruned/origin/origin")
+        .is_ok());
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    tracked: kvmwppmv 910c405e message
+      @tracked: kvmwppmv 910c405e message
+    "###);
+
+    // Unless pruning is disabled
+    test_env.add_config("git.prune-expired-remote-branches = false");
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--all-remotes"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    origin@origin: oputwtnw ffecd2d6 message
+    tracked: kvmwppmv 910c405e message
+      @tracked: kvmwppmv 910c405e message
+    "###);
+}
+
 #[test]
 fn test_git_fetch_prune_before_updating_tips() {
     let test_env = TestEnvironment::default();
@@ -1287,3 +1351,46 @@ fn test_git_fetch_remote_only_branch() {
     feature2@origin: mzyxwzks 9f01a0e0 message
     "###);
 }
+
+#[test]
+fn test_git_fetch_tracked() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    add_git_remote(&test_env, &repo_path, "origin");
+
+    // Nothing is tracked yet, so nothing is fetched
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--tracked"]);
+    insta::assert_snapshot!(stderr, @r###"
+    No tracked branches to fetch from remote origin
+    Nothing changed.
+    "###);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @"");
+
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "track", "origin@origin"]);
+
+    // A new branch on the remote isn't fetched
+    let git_repo = git2::Repository::open(test_env.env_root().join("origin")).unwrap();
+    let head = git_repo.find_reference("refs/heads/origin").unwrap();
+    git_repo
+        .reference("refs/heads/new", head.target().unwrap(), false, "")
+        .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["git", "fetch", "--tracked"]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    origin: oputwtnw ffecd2d6 message
+      @origin: oputwtnw ffecd2d6 message
+    "###);
+
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["git", "fetch", "--tracked", "--branch", "origin"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--tracked' cannot be used with '--branch <BRANCH>'
+
+    Usage: jj git fetch --tracked
+
+    For more information, try '--help'.
+    "###);
+}
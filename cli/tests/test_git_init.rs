@@ -58,7 +58,8 @@ fn init_git_repo_with_opts(
 }
 
 fn get_branch_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
-    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes"])
+    // --quiet to suppress the hint about conflicted branches
+    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes", "--quiet"])
 }
 
 fn read_git_target(workspace_root: &Path) -> String {
@@ -581,6 +582,89 @@ fn test_git_init_colocated_via_flag_git_dir_not_exists() {
     "###);
 }
 
+#[test]
+fn test_git_colocate() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_root = test_env.env_root().join("repo");
+    std::fs::write(workspace_root.join("file"), "contents").unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["commit", "-m=first"]);
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "main", "-r@-"]);
+    std::fs::write(workspace_root.join("file"), "modified").unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "colocate"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r#"Moved the Git repo to ".git". The repo is now colocated."#);
+    assert!(!workspace_root.join(".jj/repo/store/git").exists());
+    assert!(read_git_target(&workspace_root)
+        .replace('\\', "/")
+        .ends_with("../../../.git"));
+
+    // The Git HEAD is the working-copy parent, and the branches are exported
+    let git_repo = git2::Repository::open(workspace_root.join(".git")).unwrap();
+    assert!(!git_repo.is_bare());
+    insta::assert_snapshot!(
+        git_repo.head().unwrap().peel_to_commit().unwrap().message().unwrap(),
+        @"first"
+    );
+    insta::assert_snapshot!(
+        git_repo.find_reference("refs/heads/main").unwrap().peel_to_commit().unwrap().message().unwrap(),
+        @"first"
+    );
+    let stdout = test_env.jj_cmd_success(&workspace_root, &["log", "-s"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  rlvkpnrz test.user@example.com 2001-02-03 08:05:10 a9441e2c
+    │  (no description set)
+    │  M file
+    ◉  qpvuntsm test.user@example.com 2001-02-03 08:05:08 main HEAD@git c8198942
+    │  first
+    │  A file
+    ◉  zzzzzzzz root() 00000000
+    "###);
+
+    // The Git index matches the working-copy parent
+    let mut statuses = String::new();
+    for entry in git_repo.statuses(None).unwrap().iter() {
+        writeln!(statuses, "{:?} {}", entry.status(), entry.path().unwrap()).unwrap();
+    }
+    insta::assert_snapshot!(statuses, @r###"
+    Status(IGNORED) .jj/.gitignore
+    Status(IGNORED) .jj/repo/
+    Status(IGNORED) .jj/working_copy/
+    Status(WT_MODIFIED) file
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "colocate"]);
+    insta::assert_snapshot!(stderr, @"Error: The repo is already colocated with Git");
+}
+
+#[test]
+fn test_git_colocate_external() {
+    let test_env = TestEnvironment::default();
+    let git_repo_path = test_env.env_root().join("git-repo");
+    init_git_repo(&git_repo_path, true);
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "init", "repo", "--git-repo=git-repo"],
+    );
+    let workspace_root = test_env.env_root().join("repo");
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "colocate"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: The repo isn't backed by an internal Git repo
+    Hint: The Git repo at "$TEST_ENV/git-repo" can be used directly instead.
+    "###);
+
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "internal"]);
+    let internal_path = test_env.env_root().join("internal");
+    test_env.jj_cmd_ok(&internal_path, &["workspace", "add", "../secondary"]);
+    let stderr =
+        test_env.jj_cmd_failure(&test_env.env_root().join("secondary"), &["git", "colocate"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: The repo can only be colocated in the workspace which contains it
+    Hint: Run this command in the workspace at "$TEST_ENV/internal".
+    "###);
+}
+
 #[test]
 fn test_git_init_bad_wc_path() {
     let test_env = TestEnvironment::default();
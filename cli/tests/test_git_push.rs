@@ -452,6 +452,9 @@ fn test_git_push_changes() {
     M file
     Working copy : yostqsxw 3e2ce808 bar
     Parent commit: yqosqzyt fa16a141 push-yostqsxwqrlt* push-yqosqzytrlsw | foo
+    Branches:
+      push-yostqsxwqrlt: 1 commit behind push-yostqsxwqrlt@origin
+      push-yqosqzytrlsw: up to date with push-yqosqzytrlsw@origin
     "###);
     let (stdout, stderr) = test_env.jj_cmd_ok(
         &workspace_root,
@@ -468,6 +471,9 @@ fn test_git_push_changes() {
     M file
     Working copy : yostqsxw 3e2ce808 push-yostqsxwqrlt | bar
     Parent commit: yqosqzyt fa16a141 push-yqosqzytrlsw | foo
+    Branches:
+      push-yostqsxwqrlt: up to date with push-yostqsxwqrlt@origin
+      push-yqosqzytrlsw: up to date with push-yqosqzytrlsw@origin
     "###);
 
     // Test changing `git.push-branch-prefix`. It causes us to push again.
@@ -736,6 +742,95 @@ fn test_git_push_deleted() {
     "###);
 }
 
+#[test]
+fn test_git_push_requires_fetch() {
+    let (test_env, workspace_root) = set_up();
+    test_env.add_config("git.push-requires-fetch = true");
+    let origin_path = test_env.env_root().join("origin");
+
+    // Someone else moves branch1 on the remote
+    test_env.jj_cmd_ok(&origin_path, &["new", "branch1", "-m=remote change"]);
+    test_env.jj_cmd_ok(&origin_path, &["branch", "set", "branch1"]);
+    test_env.jj_cmd_ok(&origin_path, &["git", "export"]);
+
+    // Force-pushing branch1 would clobber the remote change, so it's refused
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "branch1", "-m=modified"]);
+    test_env.jj_cmd_ok(&workspace_root, &["describe", "branch2", "-m=modified"]);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "push", "--all"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Refusing to push since branch branch1@origin moved on the remote since the last fetch
+    Hint: Run `jj git fetch` to update the branches, then push again.
+    "###);
+
+    // Branches which didn't move on the remote can still be pushed
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "push", "-b=branch2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Force branch branch2 from 8476341eb395 to 8dbd420c22b4
+    "###);
+
+    // After a fetch, the branch can be pushed once the conflict is resolved
+    test_env.jj_cmd_ok(&workspace_root, &["git", "fetch"]);
+    test_env.jj_cmd_ok(
+        &workspace_root,
+        &["branch", "resolve", "branch1", "--keep", "local"],
+    );
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "push", "-b=branch1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Force branch branch1 from d6340aa25373 to fc5e78925a49
+    "###);
+}
+
+#[test]
+fn test_git_push_protected_branches() {
+    let (test_env, workspace_root) = set_up();
+    test_env.add_config(r#"git.push-protected-branches = ["branch1", "release/*"]"#);
+
+    // Moving or deleting a protected branch is refused
+    test_env.jj_cmd_ok(&workspace_root, &["new", "branch1", "-m=foo"]);
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "set", "branch1"]);
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "delete", "branch2"]);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "push", "--all"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Refusing to move or delete protected branch branch1@origin
+    Hint: Use --allow-protected to push anyway.
+    "###);
+    let stderr = test_env.jj_cmd_failure(&workspace_root, &["git", "push", "-b=branch1"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Refusing to move or delete protected branch branch1@origin
+    Hint: Use --allow-protected to push anyway.
+    "###);
+
+    // Other branches can still be pushed, and new protected branches can be
+    // created
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "release/1"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "push", "--deleted"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Delete branch branch2 from 8476341eb395
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["git", "push", "-b=release/1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Add branch release/1 to 09017e8d1a7d
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &workspace_root,
+        &["git", "push", "--all", "--allow-protected"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Move branch branch1 from 45a3aa29e907 to 09017e8d1a7d
+    "###);
+}
+
 #[test]
 fn test_git_push_conflicting_branches() {
     let (test_env, workspace_root) = set_up();
@@ -757,7 +852,7 @@ fn test_git_push_conflicting_branches() {
     test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "branch2"]);
     test_env.jj_cmd_ok(&workspace_root, &["git", "fetch"]);
     insta::assert_snapshot!(
-        test_env.jj_cmd_success(&workspace_root, &["branch", "list", "--all-remotes"]), @r###"
+        test_env.jj_cmd_success(&workspace_root, &["branch", "list", "--all-remotes", "--quiet"]), @r###"
     branch1: lzmmnrxq 45a3aa29 (empty) description 1
       @origin: lzmmnrxq 45a3aa29 (empty) description 1
     branch2 (conflicted):
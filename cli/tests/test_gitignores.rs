@@ -91,7 +91,8 @@ fn test_gitignores_ignored_file_in_target_commit() {
     Working copy now at: qpvuntsm 4a703628 with-file | (no description set)
     Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
     Added 1 files, modified 0 files, removed 0 files
-    Warning: 1 of those updates were skipped because there were conflicting changes in the working copy.
+    Warning: 1 of those updates were skipped because there were conflicting changes in the working copy:
+      ignored
     Hint: Inspect the changes compared to the intended target with `jj diff --from 4a703628bcb2`.
     Discard the conflicting changes with `jj restore --from 4a703628bcb2`.
     "###);
@@ -101,7 +102,7 @@ fn test_gitignores_ignored_file_in_target_commit() {
     );
     insta::assert_snapshot!(stdout, @r###"
     diff --git a/ignored b/ignored
-    index 8a69467466...4d9be5127b 100644
+    index 8a69467466..4d9be5127b 100644
     --- a/ignored
     +++ b/ignored
     @@ -1,1 +1,1 @@
@@ -109,3 +110,40 @@ fn test_gitignores_ignored_file_in_target_commit() {
     +contents in working copy
     "###);
 }
+
+#[test]
+fn test_gitignores_ignored_file_backed_up() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("checkout.backup-conflicting-files = true");
+    let workspace_root = test_env.env_root().join("repo");
+    git2::Repository::init(&workspace_root).unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["init", "--git-repo", "."]);
+
+    // Create a commit with file "ignored" in it
+    std::fs::write(workspace_root.join("ignored"), "committed contents\n").unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["branch", "create", "with-file"]);
+
+    // Create another commit where we ignore that path
+    test_env.jj_cmd_ok(&workspace_root, &["new", "root()"]);
+    std::fs::write(workspace_root.join("ignored"), "contents in working copy\n").unwrap();
+    std::fs::write(workspace_root.join(".gitignore"), ".gitignore\nignored\n").unwrap();
+
+    // The ignored file is moved out of the way
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_root, &["edit", "with-file"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm 4a703628 with-file | (no description set)
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 1 files, modified 0 files, removed 0 files
+    Warning: Moved 1 files with conflicting changes in the working copy to .jj/backup/:
+      ignored
+    "###);
+    assert_eq!(
+        std::fs::read_to_string(workspace_root.join("ignored")).unwrap(),
+        "committed contents\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(workspace_root.join(".jj/backup/ignored")).unwrap(),
+        "contents in working copy\n"
+    );
+}
@@ -477,6 +477,116 @@ fn test_early_args() {
     );
 }
 
+#[test]
+fn test_config_arg() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"ui.editor = "user-editor""#);
+
+    // Values are parsed like with `jj config set`
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &[
+            "config",
+            "list",
+            "-T",
+            r#"if(name.starts_with("ui.") || name.starts_with("test-table."), name ++ "=" ++ value ++ "\n")"#,
+            "--config",
+            "ui.editor=vim",
+            "--config",
+            "ui.quiet=true",
+            "--config",
+            "test-table.list=[1, 2]",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    test-table.list=[1, 2]
+    ui.editor="vim"
+    ui.quiet=true
+    "###);
+
+    // --config and --config-toml are applied in order
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &[
+            "config",
+            "get",
+            "ui.editor",
+            "--config",
+            "ui.editor=first",
+            "--config-toml",
+            "ui.editor='second'",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"second");
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &[
+            "config",
+            "get",
+            "ui.editor",
+            "--config-toml",
+            "ui.editor='first'",
+            "--config",
+            "ui.editor=second",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"second");
+
+    let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["log", "--config", "ui.editor"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid --config argument: ui.editor
+    Hint: The argument should be NAME=VALUE, for example `--config ui.color=always`.
+    "###);
+    let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["log", "--config", "ui.edtor=vim"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Unknown config option ui.edtor
+    Hint: Did you mean "editor"?
+    "###);
+}
+
+#[test]
+fn test_config_env_var() {
+    let mut test_env = TestEnvironment::default();
+    test_env.add_config(r#"ui.diff-editor = "user-editor""#);
+
+    test_env.add_env_var("JJ_CONFIG_UI__DIFF_EDITOR", "env-editor");
+    test_env.add_env_var("JJ_CONFIG_SNAPSHOT__MAX_NEW_FILE_SIZE", "42");
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &[
+            "config",
+            "list",
+            "-T",
+            r#"if(name.starts_with("ui.") || name.starts_with("snapshot."), name ++ "=" ++ value ++ "\n")"#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    snapshot.max-new-file-size=42
+    ui.diff-editor="env-editor"
+    "###);
+
+    // Command-line arguments override the environment
+    let stdout = test_env.jj_cmd_success(
+        test_env.env_root(),
+        &[
+            "config",
+            "get",
+            "ui.diff-editor",
+            "--config",
+            "ui.diff-editor=arg-editor",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"arg-editor");
+
+    test_env.add_env_var("JJ_CONFIG_UI__QUIET", "yes");
+    let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["config", "list"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid config option in environment variable JJ_CONFIG_UI__QUIET
+    Caused by: Invalid value for ui.quiet: expected boolean
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
+}
+
 #[test]
 fn test_invalid_config() {
     // Test that we get a reasonable error if the config is invalid (#55)
@@ -543,14 +653,18 @@ fn test_help() {
       -h, --help                 Print help (see more with '--help')
 
     Global Options:
-      -R, --repository <REPOSITORY>      Path to repository to operate on
-          --ignore-working-copy          Don't snapshot the working copy, and don't update it
-          --at-operation <AT_OPERATION>  Operation to load the repo at [default: @] [aliases: at-op]
-          --debug                        Enable debug logging
-          --color <WHEN>                 When to colorize output (always, never, auto)
-          --quiet                        Silence non-primary command output
-          --no-pager                     Disable the pager
-          --config-toml <TOML>           Additional configuration options (can be repeated)
+      -R, --repository <REPOSITORY>       Path to repository to operate on
+          --ignore-working-copy           Don't snapshot the working copy, and don't update it
+          --at-operation <AT_OPERATION>   Operation to load the repo at [default: @] [aliases: at-op]
+          --debug                         Enable debug logging
+          --op-description <DESCRIPTION>  Description of the operation created by the command
+          --op-tag <KEY=VALUE>            Tag to add to the operation created by the command (can be
+                                          repeated)
+          --color <WHEN>                  When to colorize output (always, never, auto)
+          --quiet                         Silence non-primary command output
+          --no-pager                      Disable the pager
+          --config-toml <TOML>            Additional configuration options (can be repeated)
+          --config <NAME=VALUE>           Set a configuration option (can be repeated)
     "###);
 }
 
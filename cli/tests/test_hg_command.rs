@@ -0,0 +1,196 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+// Stream written by `hg fastexport` for a repo where "draft" was amended into
+// "amended", and "pruned" was pruned.
+const FAST_EXPORT: &str = r#"blob
+mark :1
+data 4
+foo
+
+commit refs/heads/default
+mark :2
+author Test User <test.user@example.com> 1700000000 +0000
+committer Test User <test.user@example.com> 1700000000 +0000
+data 7
+initial
+M 100644 :1 file
+
+blob
+mark :3
+data 4
+bar
+
+commit refs/heads/default
+mark :4
+author Test User <test.user@example.com> 1700000100 +0000
+committer Test User <test.user@example.com> 1700000100 +0000
+data 5
+draft
+from :2
+M 100644 :3 file
+
+commit refs/heads/default
+mark :5
+author Test User <test.user@example.com> 1700000100 +0000
+committer Test User <test.user@example.com> 1700000200 +0100
+data 7
+amended
+from :2
+M 100644 :3 file
+M 100755 inline dir/script
+data 8
+echo hi
+
+commit refs/heads/default
+mark :6
+author Test User <test.user@example.com> 1700000300 +0000
+committer Test User <test.user@example.com> 1700000300 +0000
+data 6
+pruned
+from :5
+D file
+
+"#;
+
+const MARKS: &str = "\
+:2 0123456789abcdef0123456789abcdef01234567
+:4 89abcdef0123456789abcdef0123456789abcdef
+:5 fedcba9876543210fedcba9876543210fedcba98
+:6 00112233445566778899aabbccddeeff00112233
+";
+
+const OBS_MARKERS: &str = r#"[
+ {
+  "date": [1700000200, -3600],
+  "flag": 0,
+  "index": 0,
+  "metadata": {"operation": "amend", "user": "Test User <test.user@example.com>"},
+  "prednode": "89abcdef0123456789abcdef0123456789abcdef",
+  "succnodes": ["fedcba9876543210fedcba9876543210fedcba98"]
+ },
+ {
+  "date": [1700000300, 0],
+  "flag": 0,
+  "index": 1,
+  "metadata": {"operation": "prune", "user": "Test User <test.user@example.com>"},
+  "parentnodes": ["fedcba9876543210fedcba9876543210fedcba98"],
+  "prednode": "00112233445566778899aabbccddeeff00112233",
+  "succnodes": []
+ }
+]
+"#;
+
+fn get_described_log(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    test_env.jj_cmd_success(
+        repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            r#"~description(exact:"")"#,
+            "-T",
+            r#"description.first_line() ++ "\n""#,
+        ],
+    )
+}
+
+#[test]
+fn test_hg_import_fast_export() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(test_env.env_root().join("export"), FAST_EXPORT).unwrap();
+    std::fs::write(test_env.env_root().join("marks"), MARKS).unwrap();
+    std::fs::write(test_env.env_root().join("obsmarkers.json"), OBS_MARKERS).unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "hg",
+            "import",
+            "--fast-export=../export",
+            "--marks=../marks",
+            "--obs-markers=../obsmarkers.json",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Imported 4 commits (2 obsolete)
+    "###);
+
+    // The obsolete changesets are hidden
+    insta::assert_snapshot!(get_described_log(&test_env, &repo_path), @r###"
+    amended
+    initial
+    "###);
+
+    // The amended changeset is a successor of the draft one, with the same
+    // change id
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "obslog",
+            "--no-graph",
+            "-r",
+            "description(amended)",
+            "-T",
+            r#"description.first_line() ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    amended
+    draft
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "obslog",
+            "--no-graph",
+            "-r",
+            "description(amended)",
+            "-T",
+            r#"change_id ++ "\n""#,
+        ],
+    );
+    let change_ids = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(change_ids.len(), 2);
+    assert_eq!(change_ids[0], change_ids[1]);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["cat", "-r", "description(amended)", "dir/script"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    echo hi
+    "###);
+}
+
+#[test]
+fn test_hg_import_invalid_stream() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(test_env.env_root().join("export"), "blob\nmark :1\nbogus\n").unwrap();
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["hg", "import", "--fast-export=../export"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to import the Mercurial repo
+    Caused by: Invalid fast-import stream at line 2: expected data
+    "###);
+}
@@ -0,0 +1,273 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg(unix)]
+
+use std::fs;
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+/// Configures the hook `name` to save its input to `input_path` and to exit
+/// with `exit_code`.
+fn set_up_hook(test_env: &TestEnvironment, name: &str, input_path: &Path, exit_code: i32) {
+    test_env.add_config(&format!(
+        r#"hooks.{name} = ["/bin/sh", "-c", 'cat > "{}"; exit {exit_code}']"#,
+        input_path.display()
+    ));
+}
+
+/// Reads the input saved by the hook, with the path to the `jj` binary replaced
+/// by "jj".
+fn read_hook_input(input_path: &Path) -> String {
+    let mut input: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(input_path).unwrap()).unwrap();
+    input["args"][0] = "jj".into();
+    serde_json::to_string_pretty(&input).unwrap()
+}
+
+#[test]
+fn test_hook_pre_and_post_transaction() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let pre_input_path = test_env.env_root().join("pre.json");
+    let post_input_path = test_env.env_root().join("post.json");
+    set_up_hook(&test_env, "pre-transaction", &pre_input_path, 0);
+    set_up_hook(&test_env, "post-transaction", &post_input_path, 0);
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    insta::assert_snapshot!(read_hook_input(&pre_input_path), @r###"
+    {
+      "added_commits": [
+        "69542c1984c1f9d91f7c6c9c9e6941782c944bd9"
+      ],
+      "args": [
+        "jj",
+        "describe",
+        "-m",
+        "first"
+      ],
+      "changed_paths": null,
+      "description": "describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22",
+      "operation_id": null,
+      "removed_commits": [
+        "230dd059e1b059aefc0da06a2e5a7dbf22362f22"
+      ],
+      "working_copy_commit": "69542c1984c1f9d91f7c6c9c9e6941782c944bd9"
+    }
+    "###);
+    insta::assert_snapshot!(read_hook_input(&post_input_path), @r###"
+    {
+      "added_commits": [
+        "69542c1984c1f9d91f7c6c9c9e6941782c944bd9"
+      ],
+      "args": [
+        "jj",
+        "describe",
+        "-m",
+        "first"
+      ],
+      "changed_paths": null,
+      "description": "describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22",
+      "operation_id": "9b6ccf4460e7555e9f8b9302fa00eeebaca14ebeade4b4cefc94281f90ef1371d36fe4ba7ac0eff5269ef94f8496f112a791e653fbbebafc4c64fb0ee1ac0f17",
+      "removed_commits": [
+        "230dd059e1b059aefc0da06a2e5a7dbf22362f22"
+      ],
+      "working_copy_commit": "69542c1984c1f9d91f7c6c9c9e6941782c944bd9"
+    }
+    "###);
+
+    // A failing post-transaction hook is only a warning
+    set_up_hook(&test_env, "post-transaction", &post_input_path, 1);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm f4239511 (empty) second
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Warning: The post-transaction hook failed (exit status: 1)
+    "###);
+
+    // A failing pre-transaction hook rejects the operation
+    set_up_hook(&test_env, "pre-transaction", &pre_input_path, 1);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["describe", "-m", "third"]);
+    insta::assert_snapshot!(stderr, @"Error: The pre-transaction hook rejected the operation (exit status: 1)");
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["log", "--no-graph", "-r@", "-Tdescription"]);
+    insta::assert_snapshot!(stdout, @"second");
+
+    // The hook output isn't captured, and the hook name is passed in JJ_HOOK
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "--config-toml",
+            r#"hooks.pre-transaction = ["/bin/sh", "-c", 'echo "running $JJ_HOOK"']"#,
+            "describe",
+            "-m",
+            "fourth",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"running pre-transaction");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm 6fbbeb91 (empty) fourth
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Warning: The post-transaction hook failed (exit status: 1)
+    "###);
+}
+
+#[test]
+fn test_hook_transaction_colocated() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "--colocate", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let git_repo = git2::Repository::open(&repo_path).unwrap();
+
+    // A rejected operation isn't exported to Git
+    test_env.add_config(r#"hooks.pre-transaction = ["/bin/sh", "-c", "exit 1"]"#);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["branch", "create", "main"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: The pre-transaction hook rejected the operation (exit status: 1)
+    "###);
+    assert!(git_repo.find_reference("refs/heads/main").is_err());
+
+    // The post-transaction hook runs once the working copy is updated
+    test_env.add_config(
+        r#"
+        hooks.pre-transaction = ["/bin/sh", "-c", "true"]
+        hooks.post-transaction = ["/bin/sh", "-c", "cat file"]
+        "#,
+    );
+    std::fs::write(repo_path.join("file"), "contents\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main", "-r", "@-"]);
+    assert!(git_repo.find_reference("refs/heads/main").is_ok());
+    std::fs::remove_file(repo_path.join("file")).unwrap();
+    let (stdout, _stderr) = test_env.jj_cmd_ok(&repo_path, &["restore"]);
+    insta::assert_snapshot!(stdout, @"contents");
+}
+
+#[test]
+fn test_hook_pre_snapshot() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let input_path = test_env.env_root().join("pre-snapshot.json");
+    set_up_hook(&test_env, "pre-snapshot", &input_path, 0);
+
+    // The hook isn't run if nothing changed
+    test_env.jj_cmd_ok(&repo_path, &["status"]);
+    assert!(!input_path.exists());
+
+    fs::write(repo_path.join("file1"), "contents").unwrap();
+    fs::create_dir(repo_path.join("dir")).unwrap();
+    fs::write(repo_path.join("dir").join("file2"), "contents").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["status"]);
+    insta::assert_snapshot!(read_hook_input(&input_path), @r###"
+    {
+      "added_commits": [
+        "c2d8a7b098ebf42c783c82a36aa7915c9b37ed57"
+      ],
+      "args": [
+        "jj",
+        "status"
+      ],
+      "changed_paths": [
+        "dir/file2",
+        "file1"
+      ],
+      "description": "snapshot working copy",
+      "operation_id": null,
+      "removed_commits": [
+        "230dd059e1b059aefc0da06a2e5a7dbf22362f22"
+      ],
+      "working_copy_commit": "c2d8a7b098ebf42c783c82a36aa7915c9b37ed57"
+    }
+    "###);
+
+    // A failing hook rejects the snapshot
+    set_up_hook(&test_env, "pre-snapshot", &input_path, 1);
+    fs::write(repo_path.join("file1"), "new contents").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["status"]);
+    insta::assert_snapshot!(stderr, @"Error: The pre-snapshot hook rejected the operation (exit status: 1)");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--ignore-working-copy", "-s"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A dir/file2
+    A file1
+    "###);
+}
+
+#[test]
+fn test_hook_pre_push() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "origin"]);
+    let origin_git_repo_path = test_env
+        .env_root()
+        .join("origin")
+        .join(".jj")
+        .join("repo")
+        .join("store")
+        .join("git");
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "git",
+            "remote",
+            "add",
+            "origin",
+            origin_git_repo_path.to_str().unwrap(),
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+    let input_path = test_env.env_root().join("pre-push.json");
+
+    // A failing hook rejects the push
+    set_up_hook(&test_env, "pre-push", &input_path, 1);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["git", "push", "--branch", "main"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Branch changes to push to origin:
+      Add branch main to 9654d263fcb5
+    Error: The pre-push hook rejected the operation (exit status: 1)
+    "###);
+    insta::assert_snapshot!(read_hook_input(&input_path), @r###"
+    {
+      "args": [
+        "jj",
+        "git",
+        "push",
+        "--branch",
+        "main"
+      ],
+      "branches": [
+        {
+          "name": "main",
+          "new_target": "9654d263fcb54b9d30d9682f784529a917c18488",
+          "old_target": null
+        }
+      ],
+      "remote": "origin"
+    }
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["branch", "list", "--all-remotes"]);
+    insta::assert_snapshot!(stdout, @"main: rlvkpnrz 9654d263 (empty) first");
+
+    set_up_hook(&test_env, "pre-push", &input_path, 0);
+    test_env.jj_cmd_ok(&repo_path, &["git", "push", "--branch", "main"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["branch", "list", "--all-remotes"]);
+    insta::assert_snapshot!(stdout, @r###"
+    main: rlvkpnrz 9654d263 (empty) first
+      @origin: rlvkpnrz 9654d263 (empty) first
+    "###);
+}
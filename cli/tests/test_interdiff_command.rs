@@ -67,7 +67,7 @@ fn test_interdiff_basic() {
     );
     insta::assert_snapshot!(stdout, @r###"
     diff --git a/file2 b/file2
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file2
     +++ b/file2
     @@ -1,1 +1,2 @@
@@ -149,7 +149,7 @@ fn test_interdiff_conflicting() {
     );
     insta::assert_snapshot!(stdout, @r###"
     diff --git a/file b/file
-    index 0000000000...24c5735c3e 100644
+    index 0000000000..24c5735c3e 100644
     --- a/file
     +++ b/file
     @@ -1,7 +1,1 @@
@@ -163,3 +163,69 @@ fn test_interdiff_conflicting() {
     +def
     "###);
 }
+
+#[test]
+fn test_interdiff_stack() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "base"]);
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    std::fs::write(repo_path.join("file3"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "old"]);
+
+    // Rewrite the stack on top of a new base, and amend the second commit
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(base)", "-m", "new base"]);
+    std::fs::write(repo_path.join("file1"), "bar\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "first"]);
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+    std::fs::write(repo_path.join("file3"), "foo\nbar\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "new"]);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "interdiff",
+            "--stack",
+            "--from=description(base)..old",
+            "--to=description(\"new base\")..new",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    From: rlvkpnrz 5b5ed823 first
+    To:   yqosqzyt 10772087 first
+    (no difference)
+
+    From: kkmpptxz be7e7608 old | second
+    To:   vruxwmqv 1d3824a2 new | second
+    Modified regular file file3:
+       1    1: foo
+            2: bar
+    "###);
+
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &[
+            "interdiff",
+            "--stack",
+            "--from=old",
+            "--to=description(\"new base\")..new",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @"Error: The stacks have different numbers of commits: 1 and 2");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["interdiff", "--stack", "--from=old"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the following required arguments were not provided:
+      --to <TO>
+
+    Usage: jj interdiff --stack <--from <FROM>|--to <TO>> [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+}
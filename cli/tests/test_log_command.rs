@@ -44,6 +44,7 @@ fn test_log_with_no_template() {
     - builtin_log_comfortable
     - builtin_log_compact
     - builtin_log_detailed
+    - builtin_log_hidden_successors
     - builtin_log_oneline
     - builtin_op_log_comfortable
     - builtin_op_log_compact
@@ -111,7 +112,7 @@ fn test_log_with_or_without_diff() {
     @  a new commit
     │  M file1
     │  diff --git a/file1 b/file1
-    │  index 257cc5642c...3bd1f0e297 100644
+    │  index 257cc5642c..3bd1f0e297 100644
     │  --- a/file1
     │  +++ b/file1
     │  @@ -1,1 +1,2 @@
@@ -124,7 +125,7 @@ fn test_log_with_or_without_diff() {
     │  index 0000000000..257cc5642c
     │  --- /dev/null
     │  +++ b/file1
-    │  @@ -1,0 +1,1 @@
+    │  @@ -0,0 +1,1 @@
     │  +foo
     ◉
     "###);
@@ -173,7 +174,7 @@ fn test_log_with_or_without_diff() {
     insta::assert_snapshot!(stdout, @r###"
     a new commit
     diff --git a/file1 b/file1
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file1
     +++ b/file1
     @@ -1,1 +1,2 @@
@@ -185,7 +186,7 @@ fn test_log_with_or_without_diff() {
     index 0000000000..257cc5642c
     --- /dev/null
     +++ b/file1
-    @@ -1,0 +1,1 @@
+    @@ -0,0 +1,1 @@
     +foo
     "###);
 
@@ -238,7 +239,7 @@ fn test_log_with_or_without_diff() {
     insta::assert_snapshot!(stdout, @r###"
     @  a new commit
     │  diff --git a/file1 b/file1
-    ~  index 257cc5642c...3bd1f0e297 100644
+    ~  index 257cc5642c..3bd1f0e297 100644
        --- a/file1
        +++ b/file1
        @@ -1,1 +1,2 @@
@@ -252,7 +253,7 @@ fn test_log_with_or_without_diff() {
     insta::assert_snapshot!(stdout, @r###"
     a new commit
     diff --git a/file1 b/file1
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file1
     +++ b/file1
     @@ -1,1 +1,2 @@
@@ -327,6 +328,64 @@ fn test_log_null_terminate_multiline_descriptions() {
     )
 }
 
+#[test]
+fn test_log_with_stat() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(repo_path.join("binary"), b"\0").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "add files"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "a new commit"]);
+    std::fs::write(repo_path.join("file1"), "bar\nbaz\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description", "--stat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  a new commit
+    │  file1 | 3 ++-
+    │  1 file changed, 2 insertions(+), 1 deletion(-)
+    ◉  add files
+    │  binary | 1 +
+    │  file1  | 1 +
+    │  2 files changed, 2 insertions(+), 0 deletions(-)
+    ◉
+       0 files changed, 0 insertions(+), 0 deletions(-)
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description", "--numstat"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  a new commit
+    │  2	1	file1
+    ◉  add files
+    │  -	-	binary
+    │  1	0	file1
+    ◉
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "-T", "description", "--no-graph", "--numstat", "-r@"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    a new commit
+    2	1	file1
+    "###);
+
+    // `--stat` and `--numstat` are both short formats
+    let stderr = test_env.jj_cmd_cli_error(
+        &repo_path,
+        &["log", "-T", "description", "--stat", "--numstat"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--stat' cannot be used with '--numstat'
+
+    Usage: jj log --template <TEMPLATE> --stat [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_log_shortest_accessors() {
     let test_env = TestEnvironment::default();
@@ -748,6 +807,9 @@ fn test_log_divergence() {
     "###);
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace default
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     "###);
 }
 
@@ -775,6 +837,61 @@ fn test_log_reversed() {
     first
     second
     "###);
+
+    // Forks and merges are drawn from the oldest revision down
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "third", "description(first)"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "new",
+            "-m",
+            "merge",
+            "description(second)",
+            "description(third)",
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "fourth", "description(first)"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  fourth
+    │ ◉    merge
+    │ ├─╮
+    │ │ ◉  third
+    ├───╯
+    │ ◉  second
+    ├─╯
+    ◉  first
+    ◉
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description", "--reversed"]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉
+    ◉      first
+    ├─┬─╮
+    │ │ ◉  second
+    │ ◉ │  third
+    │ ├─╯
+    │ ◉  merge
+    @  fourth
+    "###);
+
+    // Elided revisions are placed between their neighbors
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-T",
+            "description",
+            "--reversed",
+            "-r",
+            "description(first) | description(merge)",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  first
+    ◌  (elided revisions)
+    ◉  merge
+    "###);
 }
 
 #[test]
@@ -861,6 +978,233 @@ fn test_log_filtered_by_path() {
     "###);
 }
 
+#[test]
+fn test_log_follow_renames() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\nd\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "add file1"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "modify file1"]);
+    std::fs::write(repo_path.join("file1"), "a\nb\nc\nd\ne\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "rename file1 to file2"]);
+    std::fs::rename(repo_path.join("file1"), repo_path.join("file2")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "add unrelated file1"]);
+    std::fs::write(repo_path.join("file1"), "unrelated\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "modify file2"]);
+    std::fs::write(repo_path.join("file2"), "a\nb\nc\nd\ne\nf\n").unwrap();
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "-T", "description", "--no-graph", "file2"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    modify file2
+    rename file1 to file2
+    "###);
+
+    // The history of file1 before the rename is shown, but not the history of
+    // the unrelated file1 added afterwards.
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-T",
+            "description",
+            "--no-graph",
+            "--follow",
+            "file2",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    modify file2
+    rename file1 to file2
+    modify file1
+    add file1
+    "###);
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["log", "--follow"]);
+    insta::assert_snapshot!(stderr.lines().next().unwrap(), @"error: the following required arguments were not provided:");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["log", "--follow", "file1", "file2"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: --follow requires exactly one path
+    "###);
+}
+
+#[test]
+fn test_log_filtered_by_author_and_date() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "new",
+            "-m",
+            "second",
+            "--config-toml",
+            r#"user.name="Alice"
+               user.email="alice@example.com""#,
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "third"]);
+
+    let template = r#"description.first_line() ++ " " ++ author.name() ++ " " ++ committer.timestamp() ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "--no-graph", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    third Test User 2001-02-03 04:05:10.000 +07:00
+    second Alice 2001-02-03 04:05:09.000 +07:00
+    first Test User 2001-02-03 04:05:08.000 +07:00
+      1970-01-01 00:00:00.000 +00:00
+    "###);
+
+    // --author matches substring of name or email, and implies all() if no
+    // revisions are specified
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-T", template, "--author", "alice@"],
+    );
+    insta::assert_snapshot!(stdout, @"second Alice 2001-02-03 04:05:09.000 +07:00");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-T",
+            template,
+            "--author",
+            "exact:Test User",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    third Test User 2001-02-03 04:05:10.000 +07:00
+    first Test User 2001-02-03 04:05:08.000 +07:00
+    "###);
+
+    // Patterns without a known kind prefix are substrings, even if they
+    // contain ":"
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-T", template, "--author", "Alice:"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+
+    // Multiple --author patterns are unioned
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-T",
+            template,
+            "--author=exact:Alice",
+            "--author=exact:Test User",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    third Test User 2001-02-03 04:05:10.000 +07:00
+    second Alice 2001-02-03 04:05:09.000 +07:00
+    first Test User 2001-02-03 04:05:08.000 +07:00
+    "###);
+
+    // --since is inclusive, --until is exclusive
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-T",
+            template,
+            "--since=2001-02-03T04:05:09+07:00",
+            "--until=2001-02-03T04:05:10+07:00",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"second Alice 2001-02-03 04:05:09.000 +07:00");
+
+    // Filters are intersected with the specified revisions
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-T",
+            template,
+            "-r@- | @--",
+            "--since",
+            "2001-02-02T21:05:09Z",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"second Alice 2001-02-03 04:05:09.000 +07:00");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["log", "--since", "a while ago"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'a while ago' for '--since <SINCE>': Invalid date "a while ago"
+
+    For more information, try '--help'.
+    "###);
+}
+
+#[test]
+fn test_log_filtered_by_diff_contents() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "fn foo() {}\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "add foo"]);
+    std::fs::write(repo_path.join("file1"), "fn foo() {}\nfn bar() {}\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "foo();\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "add bar and call foo"]);
+    std::fs::write(repo_path.join("file1"), "fn bar() {}\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "remove foo"]);
+
+    let template = r#"description.first_line() ++ "\n""#;
+    // -S matches substrings of the added and removed lines
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-T", template, "-S", "fn foo"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    remove foo
+    add foo
+    "###);
+
+    // -G matches the lines with a regular expression
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-T", template, "-G", r"^foo\(\)"],
+    );
+    insta::assert_snapshot!(stdout, @"add bar and call foo");
+
+    // Only the diffs of the given paths are searched
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-T", template, "-S", "foo", "file2"],
+    );
+    insta::assert_snapshot!(stdout, @"add bar and call foo");
+
+    // The search is restricted to the given revisions
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-T", template, "-S", "foo", "-r", "@-"],
+    );
+    insta::assert_snapshot!(stdout, @"add bar and call foo");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["log", "-G", "foo("]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'foo(' for '--diff-matches <REGEX>': regex parse error:
+        foo(
+           ^
+    error: unclosed group
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_log_limit() {
     let test_env = TestEnvironment::default();
@@ -904,16 +1248,17 @@ fn test_log_limit() {
     c
     "###);
 
-    // Applied on reversed DAG
+    // Applied before reversing the DAG, so the most recent revisions are shown
+    // oldest-first
     let stdout = test_env.jj_cmd_success(
         &repo_path,
         &["log", "-T", "description", "--limit=3", "--reversed"],
     );
     insta::assert_snapshot!(stdout, @r###"
-    ◉
-    ◉    a
-    ├─╮
-    │ ◉  c
+    ◉  c
+    │ ◉  b
+    ├─╯
+    @  d
     "###);
     let stdout = test_env.jj_cmd_success(
         &repo_path,
@@ -927,8 +1272,9 @@ fn test_log_limit() {
         ],
     );
     insta::assert_snapshot!(stdout, @r###"
-    a
     b
+    c
+    d
     "###);
 
     // Applied on filtered commits
@@ -1057,6 +1403,39 @@ fn test_default_revset_per_repo() {
             .lines()
             .count()
     );
+
+    // The default revset can refer to aliases defined in the repo config
+    std::fs::write(
+        repo_path.join(".jj/repo/config.toml"),
+        r#"
+        revsets.log = "work()"
+        revset-aliases."work()" = "@ | @-"
+        "#,
+    )
+    .unwrap();
+    insta::assert_snapshot!(
+        test_env.jj_cmd_success(&repo_path, &["log", "-T", "description"]),
+        @r###"
+    @  add a file
+    ◉
+    "###);
+
+    std::fs::write(
+        repo_path.join(".jj/repo/config.toml"),
+        r#"revsets.log = "work(""#,
+    )
+    .unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["log"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid `revsets.log`
+    Caused by:  --> 1:6
+      |
+    1 | work(
+      |      ^---
+      |
+      = expected <identifier> or <expression>
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
 }
 
 #[test]
@@ -1243,6 +1622,82 @@ fn test_graph_styles() {
     "###);
 }
 
+#[test]
+fn test_log_graph_format() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "initial"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "main branch 1"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "main \"branch\" 2"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "side branch\nwith description"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["new", "-m", "merge", r#"description("main branch 1")"#, "@"],
+    );
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-r",
+            "~description(initial)",
+            "--graph-format",
+            "dot",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    digraph {
+      "a6e23ae95da093d0847f0129d62b09b37fc564a2" [label="royxmykx a6e23ae9 (empty) merge"];
+      "bdcaa20a7aae5368a5175e82425aafdab8e9f095" [label="mzvwutvl bdcaa20a (empty) side branch"];
+      "b158b6155a1b44671e17239406dfdba3ee81475e" [label="kkmpptxz b158b615 (empty) main \"branch\" 2"];
+      "26f206fbf1aee482800223965b13849187baea2a" [label="rlvkpnrz 26f206fb (empty) main branch 1"];
+      "0000000000000000000000000000000000000000" [label="zzzzzzzz 00000000 (empty) (no description set)"];
+      "a6e23ae95da093d0847f0129d62b09b37fc564a2" -> "26f206fbf1aee482800223965b13849187baea2a";
+      "a6e23ae95da093d0847f0129d62b09b37fc564a2" -> "bdcaa20a7aae5368a5175e82425aafdab8e9f095";
+      "bdcaa20a7aae5368a5175e82425aafdab8e9f095" -> "b158b6155a1b44671e17239406dfdba3ee81475e";
+      "b158b6155a1b44671e17239406dfdba3ee81475e" -> "26f206fbf1aee482800223965b13849187baea2a";
+      "26f206fbf1aee482800223965b13849187baea2a" -> "0000000000000000000000000000000000000000" [style=dashed];
+    }
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-r",
+            "~description(initial)",
+            "--graph-format=mermaid",
+            "-T",
+            "description",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    flowchart TD
+      ca6e23ae95da093d0847f0129d62b09b37fc564a2["merge"]
+      cbdcaa20a7aae5368a5175e82425aafdab8e9f095["side branch<br>with description"]
+      cb158b6155a1b44671e17239406dfdba3ee81475e["main #quot;branch#quot; 2"]
+      c26f206fbf1aee482800223965b13849187baea2a["main branch 1"]
+      c0000000000000000000000000000000000000000[""]
+      ca6e23ae95da093d0847f0129d62b09b37fc564a2 --> c26f206fbf1aee482800223965b13849187baea2a
+      ca6e23ae95da093d0847f0129d62b09b37fc564a2 --> cbdcaa20a7aae5368a5175e82425aafdab8e9f095
+      cbdcaa20a7aae5368a5175e82425aafdab8e9f095 --> cb158b6155a1b44671e17239406dfdba3ee81475e
+      cb158b6155a1b44671e17239406dfdba3ee81475e --> c26f206fbf1aee482800223965b13849187baea2a
+      c26f206fbf1aee482800223965b13849187baea2a -.-> c0000000000000000000000000000000000000000
+    "###);
+
+    let stderr =
+        test_env.jj_cmd_cli_error(&repo_path, &["log", "--graph-format=dot", "--no-graph"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--graph-format <GRAPH_FORMAT>' cannot be used with '--no-graph'
+
+    Usage: jj log --graph-format <GRAPH_FORMAT> [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_log_word_wrap() {
     let test_env = TestEnvironment::default();
@@ -1339,6 +1794,73 @@ fn test_log_word_wrap() {
     "###);
 }
 
+#[test]
+fn test_log_word_wrap_truncate() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    let render = |args: &[&str], columns: u32| {
+        let mut args = args.to_vec();
+        args.push("--config-toml=ui.log-word-wrap='truncate'");
+        let assert = test_env
+            .jj_cmd(&repo_path, &args)
+            .env("COLUMNS", columns.to_string())
+            .assert()
+            .success()
+            .stderr("");
+        get_stdout_string(&assert)
+    };
+
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "main branch 1"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "main branch 2"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "side"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "merge", "@--", "@"]);
+
+    insta::assert_snapshot!(render(&["log", "-r@"], 40), @r###"
+    @  mzvwutvl test.user@example.com 2001-0
+    │  (empty) merge
+    ~
+    "###);
+    insta::assert_snapshot!(render(&["log", "--no-graph", "-r@"], 40), @r###"
+    mzvwutvl test.user@example.com 2001-02-0
+    (empty) merge
+    "###);
+
+    // Color labels should be preserved
+    insta::assert_snapshot!(render(&["log", "-r@", "--color=always"], 40), @r###"
+    @  [1m[38;5;13mm[38;5;8mzvwutvl[39m [38;5;3mtest.user@example.com[39m [38;5;14m2001-0[39m[0m
+    │  [1m[38;5;10m(empty)[39m merge[0m
+    ~
+    "###);
+
+    // Graph width should be subtracted from the term width
+    let template = r#""0 1 2 3 4 5 6 7 8 9""#;
+    insta::assert_snapshot!(render(&["log", "-T", template], 10), @r###"
+    @    0 1 2
+    ├─╮
+    │ ◉  0 1 2
+    │ ◉  0 1 2
+    ├─╯
+    ◉  0 1 2 3
+    ◉  0 1 2 3
+    "###);
+
+    // Shouldn't panic with $COLUMNS < graph_width
+    insta::assert_snapshot!(render(&["log", "-r@"], 1), @r###"
+    @
+    │
+    ~
+    "###);
+
+    // Invalid value is reported
+    let stderr =
+        test_env.jj_cmd_failure(&repo_path, &["log", "--config-toml=ui.log-word-wrap='bad'"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Config error: Invalid `ui.log-word-wrap`: bad
+    For help, see https://github.com/martinvonz/jj/blob/main/docs/config.md.
+    "###);
+}
+
 #[test]
 fn test_elided() {
     // Test that elided commits are shown as synthetic nodes.
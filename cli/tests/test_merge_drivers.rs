@@ -0,0 +1,136 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+fn set_up_repo(test_env: &TestEnvironment) -> std::path::PathBuf {
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    for (name, parents) in [
+        ("base", None),
+        ("left", Some("base")),
+        ("right", Some("base")),
+    ] {
+        if let Some(parent) = parents {
+            test_env.jj_cmd_ok(&repo_path, &["new", parent, "-m", name]);
+        } else {
+            test_env.jj_cmd_ok(&repo_path, &["describe", "-m", name]);
+        }
+        std::fs::write(repo_path.join("dir/Cargo.lock"), format!("{name}\n")).unwrap();
+        std::fs::write(repo_path.join("other"), format!("{name}\n")).unwrap();
+        test_env.jj_cmd_ok(&repo_path, &["branch", "create", name]);
+    }
+    repo_path
+}
+
+#[test]
+fn test_external_merge_driver_resolve() {
+    let mut test_env = TestEnvironment::default();
+    let editor_script = test_env.set_up_fake_editor();
+    let editor_path = assert_cmd::cargo::cargo_bin("fake-editor");
+    // Simplified TOML escaping, hoping that there are no '"' or control characters
+    // in it
+    let escaped_editor_path = editor_path.to_str().unwrap().replace('\\', r"\\");
+    test_env.add_config(&format!(
+        r#"
+        merge-drivers.lock.paths = ["**/*.lock"]
+        merge-drivers.lock.program = "{escaped_editor_path}"
+        merge-drivers.lock.merge-args = ["$output"]
+        ui.merge-editor = "fake-editor"
+        merge-tools.fake-editor.program = "{escaped_editor_path}"
+        merge-tools.fake-editor.merge-args = ["$output"]
+        "#
+    ));
+    let repo_path = set_up_repo(&test_env);
+
+    // Drivers aren't run when merging the parents of a new commit
+    test_env.jj_cmd_ok(&repo_path, &["new", "left", "right"]);
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list"]), @r###"
+    dir/Cargo.lock    2-sided conflict
+    other             2-sided conflict
+    "###);
+
+    // A failing driver is reported, and the merge editor is started instead
+    std::fs::write(&editor_script, "fail").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["resolve"]);
+    assert!(
+        stderr.contains("Merge driver failed to resolve the conflict at dir/Cargo.lock"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("Resolving conflicts in: dir/Cargo.lock\n"),
+        "{stderr}"
+    );
+
+    // `jj resolve` runs the driver instead of the merge editor
+    std::fs::write(&editor_script, "write\nmerged\n").unwrap();
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["resolve"]);
+    assert!(
+        stderr.contains("Resolving conflicts in: dir/Cargo.lock (with merge driver)"),
+        "{stderr}"
+    );
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("dir/Cargo.lock")).unwrap(), @"merged");
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list"]), @r###"
+    other    2-sided conflict
+    "###);
+}
+
+#[test]
+fn test_external_merge_driver_rebase() {
+    let mut test_env = TestEnvironment::default();
+    let editor_script = test_env.set_up_fake_editor();
+    let editor_path = assert_cmd::cargo::cargo_bin("fake-editor");
+    // Simplified TOML escaping, hoping that there are no '"' or control characters
+    // in it
+    let escaped_editor_path = editor_path.to_str().unwrap().replace('\\', r"\\");
+    test_env.add_config(&format!(
+        r#"
+        merge-drivers.lock.paths = ["**/*.lock"]
+        merge-drivers.lock.program = "{escaped_editor_path}"
+        merge-drivers.lock.merge-args = ["$output"]
+        "#
+    ));
+    let repo_path = set_up_repo(&test_env);
+    test_env.jj_cmd_ok(&repo_path, &["new", "right"]);
+
+    // The conflict is recorded and reported if the driver fails
+    std::fs::write(&editor_script, "fail").unwrap();
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["rebase", "-s", "right", "-d", "left"]);
+    assert!(
+        stderr.contains("Merge driver failed to resolve the conflict at dir/Cargo.lock"),
+        "{stderr}"
+    );
+    let stdout = test_env.jj_cmd_success(&repo_path, &["resolve", "--list", "-r", "right"]);
+    insta::assert_snapshot!(stdout, @r###"
+    dir/Cargo.lock    2-sided conflict
+    other             2-sided conflict
+    "###);
+
+    // The driver resolves the conflict in the rebased commit, and the
+    // descendants are rebased onto the result
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(&editor_script, "write\nmerged\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["rebase", "-s", "right", "-d", "left"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "dir/Cargo.lock", "-r", "right"]);
+    insta::assert_snapshot!(stdout, @"merged");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["resolve", "--list", "-r", "right"]);
+    insta::assert_snapshot!(stdout, @r###"
+    other    2-sided conflict
+    "###);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("dir/Cargo.lock")).unwrap(), @"merged");
+}
@@ -322,3 +322,81 @@ fn test_next_editing() {
     Parent commit      : kkmpptxz 3fa8931e (empty) third
     "###);
 }
+
+#[test]
+fn test_next_prev_conflict() {
+    // Jump between the conflicted merge and the other commits.
+    // fourth
+    // |
+    // resolved
+    // |
+    // merge (conflicted)
+    // |  \
+    // left right
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "left"]);
+    std::fs::write(repo_path.join("file"), "left\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()", "-m", "right"]);
+    std::fs::write(repo_path.join("file"), "right\n").unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "new",
+            "description(left)",
+            "description(right)",
+            "-m",
+            "merge",
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "resolved"]);
+    std::fs::write(repo_path.join("file"), "resolved\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "fourth"]);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["prev", "--conflict"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: yqosqzyt b7bbd4c0 (conflict) (empty) (no description set)
+    Parent commit      : zsuskuln 39f0a54f (conflict) (empty) merge
+    Added 0 files, modified 1 files, removed 0 files
+    There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["prev", "--conflict"]);
+    insta::assert_snapshot!(stderr, @"Error: No ancestor found with conflicts");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["next", "--conflict"]);
+    insta::assert_snapshot!(stderr, @"Error: No descendant found with conflicts");
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "root()"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["next", "--conflict"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: kpqxywon 0a830973 (conflict) (empty) (no description set)
+    Parent commit      : zsuskuln 39f0a54f (conflict) (empty) merge
+    Added 1 files, modified 0 files, removed 0 files
+    There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    "###);
+
+    test_env.jj_cmd_ok(&repo_path, &["edit", "description(left)"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["next", "--conflict", "--edit"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: zsuskuln 39f0a54f (conflict) (empty) merge
+    Parent commit      : rlvkpnrz 4a24045f left
+    Parent commit      : kkmpptxz c3ee4b0e right
+    Added 0 files, modified 1 files, removed 0 files
+    There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    "###);
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["next", "2", "--conflict"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '[OFFSET]' cannot be used with '--conflict'
+
+    Usage: jj next <OFFSET>
+
+    For more information, try '--help'.
+    "###);
+}
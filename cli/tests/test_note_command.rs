@@ -0,0 +1,108 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_note_add_show_remove() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["note", "show", "-r", "@-"]);
+    insta::assert_snapshot!(stderr, @"Error: Commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9 has no note");
+
+    test_env.jj_cmd_success(&repo_path, &["note", "add", "-r", "@-", "-m", "a note"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["note", "show", "-r", "@-"]);
+    insta::assert_snapshot!(stdout, @"a note");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["note", "add", "-r", "@-", "-m", "another"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9 already has a note
+    Hint: Use --force to replace the existing note.
+    "###);
+    test_env.jj_cmd_success(
+        &repo_path,
+        &["note", "add", "-r", "@-", "-m", "another", "--force"],
+    );
+
+    // The note is available in templates
+    let template = r#"description.first_line() ++ ": " ++ note"#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    @  :
+    ◉  first: another
+    ◉  :
+    "###);
+
+    test_env.jj_cmd_success(&repo_path, &["note", "remove", "-r", "@-"]);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["note", "remove", "-r", "@-"]);
+    insta::assert_snapshot!(stderr, @"Error: Commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9 has no note");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["note", "add", "-r", "root()", "-m", "x"]);
+    insta::assert_snapshot!(stderr, @"Error: The root commit can't have a note");
+}
+
+#[test]
+fn test_note_push_fetch() {
+    let test_env = TestEnvironment::default();
+    let remote_path = test_env.env_root().join("remote");
+    git2::Repository::init_bare(&remote_path).unwrap();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["git", "remote", "add", "origin", "../remote"]);
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main", "-r", "@-"]);
+    test_env.jj_cmd_ok(&repo_path, &["git", "push", "--branch", "main"]);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["note", "push"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Nothing changed.");
+
+    test_env.jj_cmd_ok(&repo_path, &["note", "add", "-r", "main", "-m", "a note"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["note", "push"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Pushed the notes to origin");
+
+    // Another clone sees the note after fetching it
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "clone", "remote", "clone"]);
+    let clone_path = test_env.env_root().join("clone");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&clone_path, &["note", "fetch"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Updated the notes from origin");
+    let stdout = test_env.jj_cmd_success(&clone_path, &["note", "show", "-r", "main@origin"]);
+    insta::assert_snapshot!(stdout, @"a note");
+
+    // Both sides change the note
+    test_env.jj_cmd_ok(
+        &clone_path,
+        &["note", "add", "-r", "main@origin", "-m", "clone", "--force"],
+    );
+    test_env.jj_cmd_ok(&clone_path, &["note", "push"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["note", "add", "-r", "main", "-m", "repo", "--force"],
+    );
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["note", "push"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: The notes of origin changed since they were last fetched
+    Hint: Run `jj note fetch` to update the notes, then push again.
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["note", "fetch"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: The notes diverged from the notes of origin
+    Hint: Run `git notes merge refs/notes/remotes/origin/commits` to merge them.
+    "###);
+}
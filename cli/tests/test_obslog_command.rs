@@ -107,7 +107,7 @@ fn test_obslog_with_or_without_diff() {
     rlvkpnrz test.user@example.com 2001-02-03 08:05:10 66b42ad3
     my description
     diff --git a/file1 b/file1
-    index 0000000000...2ab19ae607 100644
+    index 0000000000..2ab19ae607 100644
     --- a/file1
     +++ b/file1
     @@ -1,7 +1,1 @@
@@ -124,7 +124,7 @@ fn test_obslog_with_or_without_diff() {
     rlvkpnrz hidden test.user@example.com 2001-02-03 08:05:09 6fbba7bc
     my description
     diff --git a/file1 b/file1
-    index 257cc5642c...3bd1f0e297 100644
+    index 257cc5642c..3bd1f0e297 100644
     --- a/file1
     +++ b/file1
     @@ -1,1 +1,2 @@
@@ -135,11 +135,48 @@ fn test_obslog_with_or_without_diff() {
     index 0000000000..257cc5642c
     --- /dev/null
     +++ b/file2
-    @@ -1,0 +1,1 @@
+    @@ -0,0 +1,1 @@
     +foo
     rlvkpnrz hidden test.user@example.com 2001-02-03 08:05:08 eac0d0da
     (empty) my description
     "###);
+
+    // Test `--diff-against=parent`, which shows the diff of each version against
+    // its parents
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "--no-graph", "-s", "--diff-against=parent"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    rlvkpnrz test.user@example.com 2001-02-03 08:05:10 66b42ad3
+    my description
+    A file1
+    A file2
+    rlvkpnrz hidden test.user@example.com 2001-02-03 08:05:09 ebc23d4b conflict
+    my description
+    A file1
+    A file2
+    rlvkpnrz hidden test.user@example.com 2001-02-03 08:05:09 6fbba7bc
+    my description
+    M file1
+    A file2
+    rlvkpnrz hidden test.user@example.com 2001-02-03 08:05:08 eac0d0da
+    (empty) my description
+    "###);
+
+    // `--diff-against` implies `--patch`
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "--no-graph", "--limit=1", "--diff-against=parent"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    rlvkpnrz test.user@example.com 2001-02-03 08:05:10 66b42ad3
+    my description
+    Added regular file file1:
+            1: resolved
+    Added regular file file2:
+            1: foo
+    "###);
 }
 
 #[test]
@@ -263,6 +300,45 @@ fn test_obslog_squash() {
     "###);
 }
 
+#[test]
+fn test_obslog_operation() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+    std::fs::write(repo_path.join("file1"), "foo\nbar\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["squash", "-m", "squashed"]);
+
+    let template = r#"
+    commit_id.short() ++ " " ++ operation.id().short(8) ++ " " ++ operation.description()
+    ++ if(operation.snapshot(), " (snapshot)") ++ "\n"
+    "#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["obslog", "-r", "description(squashed)", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    ◉    27e721a5ba72 2b17ec20 squash commits into 9764e503e1a906ded70a6f2f1d81c6b74be89796
+    ├─╮
+    ◉ │  9764e503e1a9 ca8fce74 snapshot working copy (snapshot)
+    ◉ │  69542c1984c1 9b6ccf44 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ◉ │  230dd059e1b0 b5141638 add workspace 'default'
+      ◉  f09a38899f2b 59ec9432 snapshot working copy (snapshot)
+      ◉  579965369703 421ec43c new empty commit
+    "###);
+
+    // The new working-copy commit was created by the squash operation
+    let template = r#"operation.description() ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "@", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @"squash commits into 9764e503e1a906ded70a6f2f1d81c6b74be89796");
+}
+
 #[test]
 fn test_obslog_with_no_template() {
     let test_env = TestEnvironment::default();
@@ -279,6 +355,7 @@ fn test_obslog_with_no_template() {
     - builtin_log_comfortable
     - builtin_log_compact
     - builtin_log_detailed
+    - builtin_log_hidden_successors
     - builtin_log_oneline
     - builtin_op_log_comfortable
     - builtin_op_log_compact
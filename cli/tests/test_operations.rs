@@ -93,6 +93,67 @@ fn test_op_log() {
     "###);
 }
 
+#[test]
+fn test_op_log_op_description_and_tags() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "-m=description 0",
+            "--op-description=before refactor",
+            "--op-tag=ticket=123",
+            "--op-tag=milestone=v1",
+        ],
+    );
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "--limit=1",
+            "-T",
+            "description ++ \"\n\" ++ tags",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    before refactor
+    args: jj describe '-m=description 0' '--op-description=before refactor' '--op-tag=ticket=123' '--op-tag=milestone=v1'
+    milestone: v1
+    ticket: 123
+    "###);
+
+    // The snapshot operation isn't affected
+    std::fs::write(repo_path.join("file"), "contents").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "--op-description=new change"]);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "--limit=2",
+            "-T",
+            "description ++ \"\n\"",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    new change
+    snapshot working copy
+    "###);
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["new", "--op-tag=foo"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'foo' for '--op-tag <KEY=VALUE>': Tag must be in the form KEY=VALUE
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_op_log_with_custom_symbols() {
     let test_env = TestEnvironment::default();
@@ -140,6 +201,7 @@ fn test_op_log_with_no_template() {
     - builtin_log_comfortable
     - builtin_log_compact
     - builtin_log_detailed
+    - builtin_log_hidden_successors
     - builtin_log_oneline
     - builtin_op_log_comfortable
     - builtin_op_log_compact
@@ -162,6 +224,94 @@ fn test_op_log_limit() {
     "###);
 }
 
+#[test]
+fn test_op_log_filter() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "--config-toml",
+            "operation.username='other'",
+            "new",
+            "-m",
+            "second",
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "main"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "third"]);
+    let op_log = |args: &[&str]| {
+        let args = [&["op", "log", "-Tdescription"], args].concat();
+        test_env.jj_cmd_success(&repo_path, &args)
+    };
+
+    insta::assert_snapshot!(op_log(&[]), @r###"
+    @  describe commit 8348d73c39c86445646b39de3ec67511e0ad0cee
+    ◉  point branch main to commit 8348d73c39c86445646b39de3ec67511e0ad0cee
+    ◉  new empty commit
+    ◉  create branch main pointing to commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9
+    ◉  describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ◉  add workspace 'default'
+    ◉  initialize repo
+    ◉
+    "###);
+    insta::assert_snapshot!(op_log(&["--command", "describe"]), @r###"
+    @  describe commit 8348d73c39c86445646b39de3ec67511e0ad0cee
+    ◉  describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(op_log(&["--command", "glob:jj * new *"]), @r###"
+    ◉  new empty commit
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(op_log(&["--user", "other@"]), @r###"
+    ◉  new empty commit
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(op_log(&["--user", "glob:other@*"]), @r###"
+    ◉  new empty commit
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(
+        op_log(&["--since", "2001-02-03T04:05:09+07:00", "--until", "2001-02-03T04:05:11+07:00"]),
+        @r###"
+    ◉  new empty commit
+    ◉  create branch main pointing to commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(op_log(&["--branch", "main"]), @r###"
+    @  describe commit 8348d73c39c86445646b39de3ec67511e0ad0cee
+    ◉  point branch main to commit 8348d73c39c86445646b39de3ec67511e0ad0cee
+    ◉  create branch main pointing to commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(op_log(&["-r", "description(first)"]), @r###"
+    ◉  point branch main to commit 8348d73c39c86445646b39de3ec67511e0ad0cee
+    ◉  new empty commit
+    ◉  create branch main pointing to commit 69542c1984c1f9d91f7c6c9c9e6941782c944bd9
+    ◉  describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    │
+    ~
+    "###);
+    insta::assert_snapshot!(op_log(&["--branch", "main", "--no-graph", "--limit", "1"]), @"describe commit 8348d73c39c86445646b39de3ec67511e0ad0cee");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["op", "log", "--since", "soon"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'soon' for '--since <SINCE>': Invalid date "soon"
+
+    For more information, try '--help'.
+    "###);
+}
+
 #[test]
 fn test_op_log_no_graph() {
     let test_env = TestEnvironment::default();
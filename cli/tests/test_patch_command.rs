@@ -0,0 +1,222 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use indoc::indoc;
+use itertools::Itertools as _;
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_patch_apply_round_trip() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a\n").unwrap();
+    std::fs::write(repo_path.join("image.png"), b"\x89PNG\r\n\x1a\n\0").unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "-m",
+            "first\n\nbody",
+            "--author",
+            "Some One <some.one@example.com>",
+            "--author-date",
+            "2024-03-12T10:11:12+01:00",
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "foo\nbar\n").unwrap();
+    std::fs::remove_file(repo_path.join("file2")).unwrap();
+    std::fs::write(repo_path.join("file3"), "a\n").unwrap();
+    std::fs::write(repo_path.join("image.png"), b"\0\0\0").unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "describe",
+            "-m",
+            "second",
+            "--author-date",
+            "2024-03-13T10:11:12+01:00",
+        ],
+    );
+    let output_dir = test_env.env_root().join("patches");
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "diff",
+            "-r",
+            "root()..@",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ],
+    );
+
+    // The patches are applied in order, keeping their authors and descriptions
+    let patch_paths = std::fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_str().unwrap().to_owned())
+        .sorted()
+        .collect_vec();
+    let mut args = vec!["patch", "apply", "-d", "root()"];
+    args.extend(patch_paths.iter().map(String::as_str));
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &args);
+    insta::assert_snapshot!(stdout, @"");
+    let stderr = stderr
+        .lines()
+        .map(|line| line.split_once(" as ").unwrap().0)
+        .collect::<Vec<_>>()
+        .join("\n");
+    insta::assert_snapshot!(stderr, @r###"
+    Applied $TEST_ENV/patches/0001-qpvuntsmwlqt.patch
+    Applied $TEST_ENV/patches/0002-kkmpptxzrspx.patch
+    "###);
+    let template = r#"description ++ author.name() ++ " <" ++ author.email() ++ "> " ++ author.timestamp() ++ " " ++ committer.timestamp() ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "~::@", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    second
+    Test User <test.user@example.com> 2024-03-13 10:11:12.000 +01:00 2024-03-13 10:11:12.000 +01:00
+    first
+
+    body
+    Some One <some.one@example.com> 2024-03-12 10:11:12.000 +01:00 2024-03-12 10:11:12.000 +01:00
+    "###);
+
+    // The new commits have the same contents as the original ones
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["diff", "--from", "@-", "--to", "roots(~::@)"]);
+    insta::assert_snapshot!(stdout, @"");
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["diff", "--from", "@", "--to", "heads(~::@)"]);
+    insta::assert_snapshot!(stdout, @"");
+}
+
+#[test]
+fn test_patch_apply_same_path() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+
+    // The file patches are applied on top of each other
+    let patch_path = test_env.env_root().join("same-path.patch");
+    std::fs::write(
+        &patch_path,
+        indoc! {"
+            diff --git a/file1 b/file1
+            deleted file mode 100644
+            --- a/file1
+            +++ /dev/null
+            @@ -1 +0,0 @@
+            -a
+            diff --git a/file2 b/file1
+            rename from file2
+            rename to file1
+            diff --git a/file1 b/file1
+            --- a/file1
+            +++ b/file1
+            @@ -1 +1,2 @@
+             b
+            +c
+        "},
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["patch", "apply", "-m", "edit", patch_path.to_str().unwrap()],
+    );
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files", "-r", "@+"]);
+    insta::assert_snapshot!(stdout, @r###"
+    file1
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "@+", "file1"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    c
+    "###);
+}
+
+#[test]
+fn test_patch_apply_unified_diff() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "x\na\nb\nc\n").unwrap();
+
+    // The hunk is applied even if the lines moved
+    let patch_path = test_env.env_root().join("edit.patch");
+    std::fs::write(
+        &patch_path,
+        "--- a/file.orig\t2024-03-12 10:11:12\n+++ b/file\t2024-03-12 10:11:13\n@@ -1,3 +1,3 @@\n \
+         a\n-b\n+B\n c\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["patch", "apply", "-m", "edit", patch_path.to_str().unwrap()],
+    );
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "@+", "file"]);
+    insta::assert_snapshot!(stdout, @r###"
+    x
+    a
+    B
+    c
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "@+", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    edit
+    "###);
+
+    // The context doesn't match
+    std::fs::write(repo_path.join("file"), "a\nd\nc\n").unwrap();
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["patch", "apply", patch_path.to_str().unwrap()],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Rebased 1 descendant commits onto updated working copy
+    Error: Failed to apply patch to file: hunk #1 doesn't match the file
+    "###);
+
+    // The patched file doesn't exist
+    std::fs::remove_file(repo_path.join("file")).unwrap();
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["patch", "apply", patch_path.to_str().unwrap()],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Rebased 1 descendant commits onto updated working copy
+    Error: No such file at 'file'
+    "###);
+
+    // Invalid patch
+    std::fs::write(&patch_path, "--- a/file\n+++ b/file\n@@ -1,2 +1,2 @@\n a\n").unwrap();
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["patch", "apply", patch_path.to_str().unwrap()],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse $TEST_ENV/edit.patch
+    Caused by: Invalid patch at line 5: Hunk is truncated
+    "###);
+}
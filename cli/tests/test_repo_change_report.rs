@@ -122,6 +122,9 @@ fn test_report_conflicts_with_divergent_commits() {
     insta::assert_snapshot!(stdout, @"");
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace default
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     Rebased 3 commits
     New conflicts appeared in these commits:
       zsuskuln?? 94be9a4c (conflict) C3
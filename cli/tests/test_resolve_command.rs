@@ -525,16 +525,39 @@ fn test_file_vs_dir() {
     "###);
     let error = test_env.jj_cmd_failure(&repo_path, &["resolve"]);
     insta::assert_snapshot!(error, @r###"
-    Hint: Using default editor ':builtin'; run `jj config set --user ui.merge-editor :builtin` to disable this message.
+    Error: The conflict at file is between a file and a directory
+    Hint: Use `jj resolve --shape file` or `jj resolve --shape directory` to keep one.
+    "###);
+
+    // Asks which kind to keep when run interactively
+    let (stdout, stderr) = test_env.jj_cmd_stdin_ok(&repo_path, &["resolve"], "d\n");
+    insta::assert_snapshot!(stdout, @"The conflict at file is between a file and a directory. Keep the (f)ile or the (d)irectory?:");
+    insta::assert_snapshot!(stderr, @r###"
     Resolving conflicts in: file
-    Error: Failed to resolve conflicts
-    Caused by: Only conflicts that involve normal files (not symlinks, not executable, etc.) are supported. Conflict summary for "file":
-    Conflict:
-      Removing file with id df967b96a579e45a18b8251732d16804b2e56a55
-      Adding file with id 78981922613b2afb6025042ff6bd878ac1994e85
-      Adding tree with id 133bb38fc4e4bf6b551f1f04db7e48f04cac2877
+    Working copy now at: vruxwmqv 0a1dc4b2 conflict | conflict
+    Parent commit      : zsuskuln aa493daf a | a
+    Parent commit      : royxmykx 8602fc65 b | b
+    Added 1 files, modified 0 files, removed 1 files
+    "###);
+    assert!(repo_path.join("file").is_dir());
+    test_env.jj_cmd_ok(&repo_path, &["op", "undo"]);
 
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["resolve", "--shape", "file"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Resolving conflicts in: file
+    Working copy now at: vruxwmqv 0aa6993f conflict | conflict
+    Parent commit      : zsuskuln aa493daf a | a
+    Parent commit      : royxmykx 8602fc65 b | b
+    Added 0 files, modified 1 files, removed 0 files
     "###);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("file")).unwrap(), @"a");
+    test_env.jj_cmd_ok(&repo_path, &["op", "undo"]);
+
+    // The mode can't be set on a directory
+    let error = test_env.jj_cmd_failure(&repo_path, &["resolve", "--mode", "normal"]);
+    insta::assert_snapshot!(error, @"Error: Cannot set the mode of the conflict at file; not all sides are files");
 }
 
 #[test]
@@ -580,6 +603,14 @@ fn test_description_with_dir_and_deletion() {
     @r###"
     file    [38;5;1m3-sided[38;5;3m conflict including 1 deletion and [38;5;1ma directory[39m
     "###);
+    // Machine-readable output
+    let template = r#"separate(" ", path, sides, deletion, executable, directory) ++ "\n"
+        ++ "  added: " ++ added_ids ++ "\n" ++ "  removed: " ++ removed_ids ++ "\n""#;
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list", "-T", template]), @r###"
+    file 3 true false true
+      added: 61780798228d17af2d34fce4cfbdf35556832472 133bb38fc4e4bf6b551f1f04db7e48f04cac2877
+      removed: df967b96a579e45a18b8251732d16804b2e56a55 df967b96a579e45a18b8251732d16804b2e56a55
+    "###);
     let error = test_env.jj_cmd_failure(&repo_path, &["resolve"]);
     insta::assert_snapshot!(error, @r###"
     Hint: Using default editor ':builtin'; run `jj config set --user ui.merge-editor :builtin` to disable this message.
@@ -792,3 +823,215 @@ fn test_multiple_conflicts() {
     Error: No conflicts found at this revision
     "###);
 }
+
+#[test]
+fn test_resolve_take() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(
+        &test_env,
+        &repo_path,
+        "base",
+        &[],
+        &[
+            ("file1", "base1\n"),
+            ("file2", "base2\n"),
+            ("file3", "base3\n"),
+        ],
+    );
+    create_commit(
+        &test_env,
+        &repo_path,
+        "a",
+        &["base"],
+        &[("file1", "a1\n"), ("file2", "a2\n"), ("file3", "a3\n")],
+    );
+    create_commit(
+        &test_env,
+        &repo_path,
+        "b",
+        &["base"],
+        &[("file1", "b1\n"), ("file2", "b2\n"), ("file3", "b3\n")],
+    );
+    create_commit(&test_env, &repo_path, "conflict", &["a", "b"], &[]);
+
+    // Take a side of the given paths only
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["resolve", "--take", "ours", "file1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Resolving conflicts in: file1
+    New conflicts appeared in these commits:
+      vruxwmqv 67acc733 conflict | (conflict) conflict
+    To resolve the conflicts, start by updating to it:
+      jj new vruxwmqvtpmx
+    Then use `jj resolve`, or edit the conflict markers in the file directly.
+    Once the conflicts are resolved, you may want inspect the result with `jj diff`.
+    Then run `jj squash` to move the resolution into the conflicted commit.
+    Working copy now at: vruxwmqv 67acc733 conflict | (conflict) conflict
+    Parent commit      : zsuskuln 5c141e9b a | a
+    Parent commit      : royxmykx 78cec328 b | b
+    Added 0 files, modified 1 files, removed 0 files
+    There are unresolved conflicts at these paths:
+    file2    2-sided conflict
+    file3    2-sided conflict
+    "###);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("file1")).unwrap(), @"a1");
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list"]), @r###"
+    file2    2-sided conflict
+    file3    2-sided conflict
+    "###);
+
+    // Take a side of all the remaining conflicts
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["resolve", "--take", "theirs"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Resolving conflicts in: file2
+    Resolving conflicts in: file3
+    Existing conflicts were resolved or abandoned from these commits:
+      vruxwmqv hidden 67acc733 (conflict) conflict
+    Working copy now at: vruxwmqv 2bc47bc5 conflict | conflict
+    Parent commit      : zsuskuln 5c141e9b a | a
+    Parent commit      : royxmykx 78cec328 b | b
+    Added 0 files, modified 2 files, removed 0 files
+    "###);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("file2")).unwrap(), @"b2");
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("file3")).unwrap(), @"b3");
+
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    test_env.jj_cmd_ok(&repo_path, &["resolve", "--take", "base", "file2"]);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(repo_path.join("file2")).unwrap(), @"base2");
+
+    // A deleted side removes the file
+    create_commit(&test_env, &repo_path, "c", &["base"], &[("file1", "c1\n")]);
+    std::fs::remove_file(repo_path.join("file2")).unwrap();
+    create_commit(&test_env, &repo_path, "conflict2", &["a", "c"], &[]);
+    test_env.jj_cmd_ok(&repo_path, &["resolve", "--take", "theirs", "file2"]);
+    assert!(!repo_path.join("file2").exists());
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["resolve", "--take", "ours", "--list"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--take <SIDE>' cannot be used with '--list'
+
+    Usage: jj resolve --take <SIDE> [PATHS]...
+
+    For more information, try '--help'.
+    "###);
+}
+
+#[test]
+fn test_resolve_mode() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(&test_env, &repo_path, "base", &[], &[("file", "base\n")]);
+    create_commit(&test_env, &repo_path, "a", &["base"], &[("file", "a\n")]);
+    test_env.jj_cmd_ok(&repo_path, &["chmod", "x", "file"]);
+    create_commit(&test_env, &repo_path, "b", &["base"], &[("file", "b\n")]);
+    create_commit(&test_env, &repo_path, "conflict", &["a", "b"], &[]);
+    let template = r#"separate(" ", path, executable) ++ "\n""#;
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list", "-T", template]), @"file true");
+
+    // The contents still conflict, but the sides agree on the executable bit
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["resolve", "--mode", "executable"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Resolving conflicts in: file
+    New conflicts appeared in these commits:
+      yostqsxw d557bc36 conflict | (conflict) conflict
+    To resolve the conflicts, start by updating to it:
+      jj new yostqsxwqrlt
+    Then use `jj resolve`, or edit the conflict markers in the file directly.
+    Once the conflicts are resolved, you may want inspect the result with `jj diff`.
+    Then run `jj squash` to move the resolution into the conflicted commit.
+    Working copy now at: yostqsxw d557bc36 conflict | (conflict) conflict
+    Parent commit      : zsuskuln 1397e829 a | a
+    Parent commit      : yqosqzyt e7223784 b | b
+    Added 0 files, modified 1 files, removed 0 files
+    There are unresolved conflicts at these paths:
+    file    2-sided conflict including an executable
+    "###);
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list", "-T", template]), @"file false");
+}
+
+#[test]
+fn test_git_style_conflict_markers() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(
+        r#"
+        ui.conflict-marker-style = "zdiff3"
+        ui.conflict-marker-length = 9
+        "#,
+    );
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    create_commit(
+        &test_env,
+        &repo_path,
+        "base",
+        &[],
+        &[("file", "first\nbase\nlast\n")],
+    );
+    create_commit(
+        &test_env,
+        &repo_path,
+        "a",
+        &["base"],
+        &[("file", "first\na\nlast\n")],
+    );
+    create_commit(
+        &test_env,
+        &repo_path,
+        "b",
+        &["base"],
+        &[("file", "first\nb\nlast\n")],
+    );
+    create_commit(&test_env, &repo_path, "conflict", &["a", "b"], &[]);
+    insta::assert_snapshot!(std::fs::read_to_string(repo_path.join("file")).unwrap(), @r###"
+    first
+    <<<<<<<<<
+    a
+    |||||||||
+    base
+    =========
+    b
+    >>>>>>>>>
+    last
+    "###);
+
+    // Edit one side of the conflict, keeping the markers
+    std::fs::write(
+        repo_path.join("file"),
+        "first\n<<<<<<<<<\na\n|||||||||\nbase\n=========\nb2\n>>>>>>>>>\nlast\n",
+    )
+    .unwrap();
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["resolve", "--list"]), @"file    2-sided conflict");
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["diff", "--git"]), @r###"
+    diff --git a/file b/file
+    --- a/file
+    +++ b/file
+    @@ -4,6 +4,6 @@
+     |||||||||
+     base
+     =========
+    -b
+    +b2
+     >>>>>>>>>
+     last
+    "###);
+
+    // Markers of the wrong length are not parsed
+    std::fs::write(
+        repo_path.join("file"),
+        "first\n<<<<<<<\na\n|||||||\nbase\n=======\nb2\n>>>>>>>\nlast\n",
+    )
+    .unwrap();
+    insta::assert_snapshot!(test_env.jj_cmd_cli_error(&repo_path, &["resolve", "--list"]), @"Error: No conflicts found at this revision");
+}
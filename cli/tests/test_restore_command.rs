@@ -147,6 +147,57 @@ fn test_restore() {
     "###);
 }
 
+#[test]
+fn test_restore_interactive() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "b\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+
+    // Can restore only some changes
+    let edit_script = test_env.set_up_fake_diff_editor();
+    std::fs::write(&edit_script, "reset file1").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["restore", "-i"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Created rlvkpnrz 63569a8a (no description set)
+    Working copy now at: rlvkpnrz 63569a8a (no description set)
+    Parent commit      : qpvuntsm 2375fa16 (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"M file1");
+
+    // Can restore from another revision, restricted to some paths
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(
+        &edit_script,
+        "files-before file2\0files-after JJ-INSTRUCTIONS file2",
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["restore", "-i", "--from=@-", "file2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Created rlvkpnrz ecd0e281 (no description set)
+    Working copy now at: rlvkpnrz ecd0e281 (no description set)
+    Parent commit      : qpvuntsm 2375fa16 (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"M file1");
+
+    // Selecting nothing is an error
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(&edit_script, "reset file1\0reset file2").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["restore", "-i"]);
+    insta::assert_snapshot!(stderr, @"Error: No changes selected");
+}
+
 // Much of this test is copied from test_resolve_command
 #[test]
 fn test_restore_conflicted_merge() {
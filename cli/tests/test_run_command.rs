@@ -0,0 +1,67 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[cfg(unix)]
+#[test]
+fn test_run() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "first\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    std::fs::write(repo_path.join("file"), "second\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "second"]);
+
+    // The command runs in a checkout of each revision, from the ancestors. A
+    // single job reuses the same scratch working copy.
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "run",
+            "cat file; rm file",
+            "-r",
+            "description(first)::@-",
+            "-j",
+            "1",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    first
+    second
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Succeeded on qpvuntsm 58d73475 first
+    Succeeded on rlvkpnrz 87acca01 second
+    "###);
+    // The working copy isn't modified
+    assert!(repo_path.join("file").exists());
+
+    // Failures are reported after running on all revisions
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["run", "grep -q first file", "-r", "root()..@-", "-j", "1"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Succeeded on qpvuntsm 58d73475 first
+    Failed on rlvkpnrz 87acca01 second (exit status: 1)
+    Error: The command failed on 1 of 2 revisions
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["run", "--clean"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Removed 1 scratch working copies");
+    assert!(!repo_path.join(".jj").join("scratch").join("0").exists());
+}
@@ -0,0 +1,105 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    let template = r#"change_id.short() ++ " " ++ description.first_line()"#;
+    test_env.jj_cmd_success(repo_path, &["log", "-T", template])
+}
+
+#[test]
+fn test_shelve_unshelve() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "base"]);
+    std::fs::write(repo_path.join("file1"), "b\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+
+    // Shelve only some paths
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["shelve", "file1"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Shelved the changes as shelf-1: kkmpptxz cf7af83f shelve: shelf-1
+    Working copy now at: rlvkpnrz 78009689 (no description set)
+    Parent commit      : qpvuntsm 4869f6b8 base
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"A file2");
+
+    // Shelve the rest with a name
+    test_env.jj_cmd_ok(&repo_path, &["shelve", "--name", "wip"]);
+    std::fs::write(repo_path.join("file3"), "c\n").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["shelve", "--name", "wip"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: A shelf named wip already exists
+    Hint: Use a different name, or run `jj unshelve` to restore the shelf first.
+    "###);
+    std::fs::remove_file(repo_path.join("file3")).unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["shelve"]);
+    insta::assert_snapshot!(stderr, @"Error: No changes to shelve");
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["shelve", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    wip: mzvwutvl 5e30a1e6 shelve: wip
+    shelf-1: kkmpptxz cf7af83f shelve: shelf-1
+    "###);
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path), @r###"
+    @  rlvkpnrzqnoo
+    │ ◉  mzvwutvlkqwt shelve: wip
+    ├─╯
+    │ ◉  kkmpptxzrspx shelve: shelf-1
+    ├─╯
+    ◉  qpvuntsmwlqt base
+    ◉  zzzzzzzzzzzz
+    "###);
+
+    // Restore a shelf into another revision
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["unshelve", "shelf-1", "-d", "@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Restored the changes of shelf-1 into qpvuntsm 9fad6515 base
+    Rebased 2 descendant commits
+    Working copy now at: rlvkpnrz 61ef9833 (empty) (no description set)
+    Parent commit      : qpvuntsm 9fad6515 base
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "@-", "file1"]);
+    insta::assert_snapshot!(stdout, @"b");
+
+    // Restore the most recent shelf into the working copy, and keep it
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["unshelve", "--keep"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Restored the changes of wip into rlvkpnrz 9ffa72b5 (no description set)
+    Working copy now at: rlvkpnrz 9ffa72b5 (no description set)
+    Parent commit      : qpvuntsm 9fad6515 base
+    Added 1 files, modified 0 files, removed 0 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["shelve", "list"]);
+    insta::assert_snapshot!(stdout, @"wip: mzvwutvl 414dfe4f shelve: wip");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"A file2");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["unshelve", "nonexistent"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No shelf named nonexistent
+    Hint: Run `jj shelve list` to list the shelves.
+    "###);
+}
@@ -64,6 +64,7 @@ fn test_show_with_no_template() {
     - builtin_log_comfortable
     - builtin_log_compact
     - builtin_log_detailed
+    - builtin_log_hidden_successors
     - builtin_log_oneline
     - builtin_op_log_comfortable
     - builtin_op_log_compact
@@ -102,3 +103,47 @@ fn test_show_relative_timestamps() {
         (no description set)
     "###);
 }
+
+#[test]
+fn test_show_multiple_revisions() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    std::fs::write(repo_path.join("file1"), "b\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "c\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["show", "-T", "description", "--summary", "::@ & ~root()"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    second
+    M file1
+    A file2
+
+    first
+    A file1
+    "###);
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["show", "-T", "description", "--stat", "@-", "@"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    second
+    file1 | 2 +-
+    file2 | 1 +
+    2 files changed, 2 insertions(+), 1 deletion(-)
+
+    first
+    file1 | 1 +
+    1 file changed, 1 insertion(+), 0 deletions(-)
+    "###);
+
+    // An empty revset shows nothing
+    let stdout = test_env.jj_cmd_success(&repo_path, &["show", "none()"]);
+    insta::assert_snapshot!(stdout, @"");
+}
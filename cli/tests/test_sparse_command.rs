@@ -174,3 +174,192 @@ fn test_sparse_manage_patterns() {
     file3
     "###);
 }
+
+#[test]
+fn test_sparse_globs_and_exclusions() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let edit_script = test_env.set_up_fake_editor();
+
+    std::fs::create_dir_all(repo_path.join("lib/tests")).unwrap();
+    std::fs::write(repo_path.join("README.md"), "contents").unwrap();
+    std::fs::write(repo_path.join("file1"), "contents").unwrap();
+    std::fs::write(repo_path.join("lib/README.md"), "contents").unwrap();
+    std::fs::write(repo_path.join("lib/file1"), "contents").unwrap();
+    std::fs::write(repo_path.join("lib/tests/file1"), "contents").unwrap();
+
+    // Can include files by glob, and exclude paths
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "sparse",
+            "set",
+            "--clear",
+            "--add=glob:**/*.md",
+            "--add=lib",
+            "--add=!lib/tests",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Added 0 files, modified 0 files, removed 2 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    lib
+    glob:**/*.md
+    !lib/tests
+    "###);
+    assert!(repo_path.join("README.md").exists());
+    assert!(!repo_path.join("file1").exists());
+    assert!(repo_path.join("lib/README.md").exists());
+    assert!(repo_path.join("lib/file1").exists());
+    assert!(!repo_path.join("lib/tests/file1").exists());
+
+    // Can remove an exclusion
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["sparse", "set", "--remove=!lib/tests"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Added 1 files, modified 0 files, removed 0 files
+    "###);
+    assert!(repo_path.join("lib/tests/file1").exists());
+
+    // Invalid glob
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["sparse", "set", "--add=glob:a**"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value 'glob:a**' for '--add <ADD>': Pattern syntax error near position 0: recursive wildcards must form a single path component
+
+    For more information, try '--help'.
+    "###);
+
+    // An invalid pattern in the editor leaves the working copy unchanged
+    std::fs::write(&edit_script, "write\nglob:**/*.md\n!glob:[\n").unwrap();
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sparse", "edit"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to parse sparse pattern: !glob:[
+    Caused by: Pattern syntax error near position 0: invalid range pattern
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    lib
+    glob:**/*.md
+    "###);
+    assert!(repo_path.join("lib/file1").exists());
+
+    // Can edit globs and exclusions
+    std::fs::write(&edit_script, "write\n.\n!glob:**/*.md\n").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "edit"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Added 1 files, modified 0 files, removed 2 files
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    .
+    !glob:**/*.md
+    "###);
+    assert!(!repo_path.join("README.md").exists());
+    assert!(repo_path.join("file1").exists());
+}
+
+#[test]
+fn test_sparse_profile() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::create_dir(repo_path.join(".sparse-profiles")).unwrap();
+    std::fs::create_dir(repo_path.join("frontend")).unwrap();
+    std::fs::create_dir(repo_path.join("backend")).unwrap();
+    std::fs::write(repo_path.join("frontend/file1"), "contents").unwrap();
+    std::fs::write(repo_path.join("backend/file1"), "contents").unwrap();
+    std::fs::write(
+        repo_path.join(".sparse-profiles/frontend.toml"),
+        "patterns = ['.sparse-profiles', 'frontend']\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+
+    // The profile must exist in the working-copy commit
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sparse", "set", "--profile", "missing"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: No sparse profile named "missing"
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sparse", "set", "--profile", "../x"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid sparse profile name "../x"
+    "###);
+
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["sparse", "set", "--profile", "frontend"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Added 0 files, modified 0 files, removed 1 files
+    "###);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    .sparse-profiles
+    frontend
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    Using sparse profile frontend
+    "###);
+    assert!(repo_path.join("frontend/file1").exists());
+    assert!(!repo_path.join("backend/file1").exists());
+
+    // Changing the profile doesn't update the patterns until a checkout
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+    std::fs::write(
+        repo_path.join(".sparse-profiles/frontend.toml"),
+        "patterns = ['.sparse-profiles', 'frontend', 'backend']\n",
+    )
+    .unwrap();
+    let (stdout, _stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    .sparse-profiles
+    frontend
+    "###);
+    assert!(!repo_path.join("backend/file1").exists());
+
+    // The patterns follow the profile in the checked-out commit
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["edit", "description(first)"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: qpvuntsm b692b09b first
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    assert!(!repo_path.join("backend/file1").exists());
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["edit", "description(second)"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Working copy now at: yqosqzyt 23d68dbb second
+    Parent commit      : qpvuntsm b692b09b first
+    Added 1 files, modified 1 files, removed 0 files
+    "###);
+    let (stdout, _stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    .sparse-profiles
+    backend
+    frontend
+    "###);
+    assert!(repo_path.join("backend/file1").exists());
+
+    // Setting other patterns stops using the profile
+    test_env.jj_cmd_ok(&repo_path, &["sparse", "set", "--remove", "backend"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    .sparse-profiles
+    frontend
+    "###);
+    insta::assert_snapshot!(stderr, @"");
+    test_env.jj_cmd_ok(&repo_path, &["edit", "description(first)"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    .sparse-profiles
+    frontend
+    "###);
+}
@@ -204,6 +204,43 @@ JJ: Lines starting with "JJ: " (like this one) will be removed.
     "###);
 }
 
+#[test]
+fn test_split_with_change_id_trailer() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo", "--git"]);
+    test_env.add_config("trailers.change-id = true");
+    let workspace_path = test_env.env_root().join("repo");
+
+    std::fs::write(workspace_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(workspace_path.join("file2"), "bar\n").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["describe", "-m", "test"]);
+    let edit_script = test_env.set_up_fake_editor();
+    std::fs::write(edit_script, ["next invocation\n"].join("\0")).unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["split", "file1"]);
+
+    // The second commit gets a new change id, so its trailer is replaced
+    let stdout = test_env.jj_cmd_success(
+        &workspace_path,
+        &[
+            "log",
+            "--no-graph",
+            "-r@ | @-",
+            "-T",
+            r#"change_id.short() ++ "\n" ++ description"#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    kkmpptxzrspx
+    test
+
+    Change-Id: I6a6a6964ffdaa62087a280bddc5e3d3ff933b8ae
+    qpvuntsmwlqt
+    test
+
+    Change-Id: I6a6a69649a45c67d3e96a7e5007c110ede34dec5
+    "###);
+}
+
 #[test]
 fn test_split_with_default_description() {
     let mut test_env = TestEnvironment::default();
@@ -377,3 +414,58 @@ JJ: Lines starting with "JJ: " (like this one) will be removed.
     ◉  zzzzzzzzzzzz true
     "###);
 }
+
+#[test]
+fn test_split_interactive_instructions() {
+    let mut test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "add files"]);
+    test_env.set_up_fake_editor();
+    let diff_script = test_env.set_up_fake_diff_editor();
+
+    // The instructions are rendered from the configured template
+    std::fs::write(
+        &diff_script,
+        ["dump JJ-INSTRUCTIONS instrs", "reset file2"].join("\0"),
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "split",
+            "-i",
+            "file1",
+            "file2",
+            "--config-toml",
+            r#"templates.split_instructions = '"Splitting " ++ source.description().first_line() ++ " with " ++ selected_paths.join(" ") ++ "\n"'"#,
+        ],
+    );
+    insta::assert_snapshot!(
+        std::fs::read_to_string(test_env.env_root().join("instrs")).unwrap(), @r###"
+    Splitting add files with file1 file2
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A file1
+    "###);
+
+    // The diff editor can opt out of the instructions file
+    test_env.jj_cmd_ok(&repo_path, &["undo"]);
+    std::fs::write(
+        &diff_script,
+        ["files-after file1 file2", "reset file2"].join("\0"),
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "split",
+            "-i",
+            "--config-toml",
+            "merge-tools.fake-diff-editor.edit-instructions = false",
+        ],
+    );
+}
@@ -859,6 +859,69 @@ fn test_squash_from_multiple_partial() {
     "###);
 }
 
+#[test]
+fn test_squash_park_conflicts() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "A"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "B"]);
+    std::fs::write(repo_path.join("file1"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file1"), "c\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "c\n").unwrap();
+
+    // Conflicts can't be parked when moving changes to a descendant
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["squash", "--from", "@--", "--into", "@", "--park-conflicts"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot park conflicts when moving changes to a descendant
+    "###);
+
+    // The change to file1 would conflict with A, so it's moved to a new commit on
+    // top instead
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["squash", "--from", "@", "--into", "@--", "--park-conflicts"],
+    );
+    let template = r#"description.first_line() ++ if(conflict, " conflict")"#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    @
+    ◉  Parked conflicting changes
+    ◉  B
+    ◉  A
+    ◉
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-r", "@---", "--summary"]);
+    insta::assert_snapshot!(stdout, @r###"
+    A file1
+    A file2
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "@---", "file1"]);
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["cat", "-r", "@-", "file1"]);
+    insta::assert_snapshot!(stdout, @r###"
+    c
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "@-", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    Parked conflicting changes
+
+    From qpvuntsmwlqt:
+      file1
+    "###);
+}
+
 fn get_log_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
     let template = r#"commit_id.short() ++ " " ++ branches"#;
     test_env.jj_cmd_success(repo_path, &["log", "-T", template])
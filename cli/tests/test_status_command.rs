@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
+use itertools::Itertools as _;
+
 use crate::common::TestEnvironment;
 
 #[test]
@@ -35,6 +39,8 @@ fn test_status_merge() {
     Working copy : mzvwutvl c965365c (empty) (no description set)
     Parent commit: rlvkpnrz 9ae48ddb left | (empty) left
     Parent commit: zsuskuln 29b991e9 right
+    Branches:
+      left: not tracking any remote branch
     "###);
 }
 
@@ -81,3 +87,186 @@ fn test_status_filtered() {
     Parent commit: zzzzzzzz 00000000 (empty) (no description set)
     "###);
 }
+
+#[test]
+fn test_status_branch_tracking() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("git.auto-local-branch = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "remote", "--git"]);
+    let remote_path = test_env.env_root().join("remote");
+    test_env.jj_cmd_ok(&remote_path, &["describe", "-m=base"]);
+    test_env.jj_cmd_ok(&remote_path, &["new", "-m=main"]);
+    test_env.jj_cmd_ok(&remote_path, &["branch", "create", "main"]);
+    test_env.jj_cmd_ok(&remote_path, &["new", "description(base)", "-m=feature"]);
+    test_env.jj_cmd_ok(&remote_path, &["branch", "create", "feature"]);
+    test_env.jj_cmd_ok(&remote_path, &["git", "export"]);
+    let mut remote_git_path = remote_path.clone();
+    remote_git_path.extend([".jj", "repo", "store", "git"]);
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["git", "clone", remote_git_path.to_str().unwrap(), "local"],
+    );
+    let repo_path = test_env.env_root().join("local");
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "main"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    The working copy is clean
+    Working copy : yostqsxw 82c20a85 (empty) (no description set)
+    Parent commit: rtkrqzko 1c9f2273 main | (empty) main
+    Branches:
+      main: up to date with main@origin
+    "###);
+
+    // The branches at the working-copy commit are shown too
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=local"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "main"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "topic"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    The working copy is clean
+    Working copy : yostqsxw 2d566a21 main* topic | (empty) local
+    Parent commit: rtkrqzko 1c9f2273 main@origin | (empty) main
+    Branches:
+      main: 1 commit ahead of main@origin
+      topic: not tracking any remote branch
+    "###);
+
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "branch",
+            "set",
+            "feature",
+            "-r=description(base)",
+            "--allow-backwards",
+        ],
+    );
+    test_env.jj_cmd_ok(&repo_path, &["new", "feature"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status"]);
+    insta::assert_snapshot!(stdout, @r###"
+    The working copy is clean
+    Working copy : nkmrtpmo e58422cb (empty) (no description set)
+    Parent commit: umkwvvtv de9c740f feature* | (empty) base
+    Branches:
+      feature: 1 commit behind feature@origin
+    "###);
+}
+
+#[test]
+fn test_status_json() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "contents").unwrap();
+    std::fs::write(repo_path.join("file2"), "contents").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status", "--json", "file1"]);
+    let status: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    insta::assert_snapshot!(serde_json::to_string_pretty(&status).unwrap(), @r###"
+    {
+      "changes": [
+        {
+          "path": "file1",
+          "status": "added"
+        }
+      ],
+      "conflicts": [],
+      "operation_id": "d70d7f6966b6c1a9c8c0259fb132513f00f038fad4c459a8cc893d847917832c6b8392edbd313d89c9ff0b9f3fcc23faa3d434285e1be2190ac2ae99748d26d0",
+      "parents": [
+        {
+          "change_id": "zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz",
+          "commit_id": "0000000000000000000000000000000000000000",
+          "description": ""
+        }
+      ],
+      "working_copy": {
+        "change_id": "qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu",
+        "commit_id": "b6f1b1819749ec84687ec31d40c7031572018d42",
+        "description": ""
+      }
+    }
+    "###);
+}
+
+#[test]
+fn test_status_json_conflicts() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "base\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=left"]);
+    std::fs::write(repo_path.join("file"), "left\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "left"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "@-", "-m=right"]);
+    std::fs::write(repo_path.join("file"), "right\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "left", "@"]);
+
+    // Each conflict is described like by the `jj resolve --list -T` keywords
+    let stdout = test_env.jj_cmd_success(&repo_path, &["status", "--json"]);
+    let status: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    insta::assert_snapshot!(serde_json::to_string_pretty(&status["conflicts"]).unwrap(), @r###"
+    [
+      {
+        "added_ids": [
+          "45cf141ba67d59203f02a54f03162f3fcef57830",
+          "c376d892e8b105bd712d06ec5162b5f31ce949c3"
+        ],
+        "deletion": false,
+        "directory": false,
+        "executable": false,
+        "path": "file",
+        "removed_ids": [
+          "df967b96a579e45a18b8251732d16804b2e56a55"
+        ],
+        "sides": 2
+      }
+    ]
+    "###);
+}
+
+#[test]
+fn test_status_watch() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Modify the working copy while `jj status --watch` is running, which is
+    // then killed
+    let writer = std::thread::spawn({
+        let path = repo_path.join("file");
+        move || {
+            std::thread::sleep(Duration::from_millis(1500));
+            std::fs::write(path, "contents").unwrap();
+        }
+    });
+    let output = test_env
+        .jj_cmd(&repo_path, &["status", "--watch", "--json"])
+        .timeout(Duration::from_secs(4))
+        .output()
+        .unwrap();
+    writer.join().unwrap();
+    assert!(!output.status.success());
+    // The status is printed only once before and once after the change
+    let changes = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let status: serde_json::Value = serde_json::from_str(line).unwrap();
+            status["changes"].to_string()
+        })
+        .join("\n");
+    insta::assert_snapshot!(changes, @r###"
+    []
+    [{"path":"file","status":"added"}]
+    "###);
+
+    // The interval can't be zero
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["status", "--watch", "--interval", "0"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: invalid value '0' for '--interval <SECONDS>': 0 is not in 1..18446744073709551615
+
+    For more information, try '--help'.
+    "###);
+}
@@ -0,0 +1,217 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+fn get_described_log(test_env: &TestEnvironment, repo_path: &Path) -> String {
+    test_env.jj_cmd_success(
+        repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            r#"~description(exact:"")"#,
+            "-T",
+            r#"description.first_line() ++ "\n""#,
+        ],
+    )
+}
+
+fn get_change_id(test_env: &TestEnvironment, repo_path: &Path, revision: &str) -> String {
+    test_env.jj_cmd_success(
+        repo_path,
+        &["log", "--no-graph", "-r", revision, "-T", "change_id"],
+    )
+}
+
+#[test]
+fn test_sync_commits() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"ui.allow-init-native = true"#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo1"]);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo2"]);
+    let repo1_path = test_env.env_root().join("repo1");
+    let repo2_path = test_env.env_root().join("repo2");
+
+    test_env.jj_cmd_ok(&repo1_path, &["describe", "-m", "a"]);
+    test_env.jj_cmd_ok(&repo1_path, &["new"]);
+    std::fs::create_dir(repo2_path.join("dir")).unwrap();
+    std::fs::write(repo2_path.join("dir").join("file"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo2_path, &["describe", "-m", "b"]);
+    test_env.jj_cmd_ok(&repo2_path, &["new"]);
+
+    // The commits are copied both ways, including the predecessors
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo1_path, &["sync", "../repo2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Received 4 commits, sent 3 commits
+    "###);
+    insta::assert_snapshot!(get_described_log(&test_env, &repo1_path), @r###"
+    b
+    a
+    "###);
+    insta::assert_snapshot!(get_described_log(&test_env, &repo2_path), @r###"
+    a
+    b
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo1_path, &["cat", "-r", "description(b)", "dir/file"]);
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    "###);
+
+    // The commits keep their change ids
+    assert_eq!(
+        get_change_id(&test_env, &repo1_path, "description(a)"),
+        get_change_id(&test_env, &repo2_path, "description(a)")
+    );
+    assert_eq!(
+        get_change_id(&test_env, &repo1_path, "description(b)"),
+        get_change_id(&test_env, &repo2_path, "description(b)")
+    );
+
+    // The working copies weren't changed
+    let stdout = test_env.jj_cmd_success(
+        &repo1_path,
+        &["log", "--no-graph", "-r", "@-", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    "###);
+
+    // Nothing is exchanged the second time
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo1_path, &["sync", "../repo2"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Received 0 commits, sent 0 commits
+    Nothing changed.
+    "###);
+}
+
+#[test]
+fn test_sync_op_log() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"ui.allow-init-native = true"#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo1"]);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo2"]);
+    let repo1_path = test_env.env_root().join("repo1");
+    let repo2_path = test_env.env_root().join("repo2");
+
+    test_env.jj_cmd_ok(&repo1_path, &["describe", "-m", "a"]);
+    test_env.jj_cmd_ok(&repo2_path, &["describe", "-m", "b"]);
+    test_env.jj_cmd_ok(&repo2_path, &["branch", "create", "main"]);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo1_path, &["sync", "--op-log", "../repo2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r###"
+    Received 2 commits and 4 operations, sent 2 commits and 3 operations
+    "###);
+
+    // The branches are synced, and each repo keeps its working-copy commit
+    for repo_path in [&repo1_path, &repo2_path] {
+        let stdout = test_env.jj_cmd_success(
+            repo_path,
+            &["log", "--no-graph", "-r", "main", "-T", "description"],
+        );
+        insta::allow_duplicates! {
+            insta::assert_snapshot!(stdout, @r###"
+            b
+            "###);
+        }
+    }
+    let stdout = test_env.jj_cmd_success(
+        &repo1_path,
+        &["log", "--no-graph", "-r", "@", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    a
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo2_path,
+        &["log", "--no-graph", "-r", "@", "-T", "description"],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    b
+    "###);
+    assert_eq!(
+        get_change_id(&test_env, &repo1_path, "main"),
+        get_change_id(&test_env, &repo2_path, "main")
+    );
+
+    // The operations of the other repo are merged into the operation log
+    let stdout = test_env.jj_cmd_success(
+        &repo1_path,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "-l1",
+            "-T",
+            r#"description ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    sync with ../repo2
+    "###);
+    let stdout = test_env.jj_cmd_success(
+        &repo2_path,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "-l1",
+            "-T",
+            r#"description ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r###"
+    sync with a remote repo
+    "###);
+}
+
+#[test]
+fn test_sync_incompatible_backends() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"ui.allow-init-native = true"#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo1"]);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo2"]);
+    let repo1_path = test_env.env_root().join("repo1");
+
+    let stderr = test_env.jj_cmd_failure(&repo1_path, &["sync", "../repo2"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Failed to sync with the other repo
+    Caused by: The other repo uses the "git" commit backend, but this repo uses the "local" commit backend
+    "###);
+}
+
+#[test]
+fn test_sync_invalid_ssh_url() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"ui.allow-init-native = true"#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Hosts which ssh would parse as options are rejected
+    let stderr = test_env.jj_cmd_failure(
+        &repo_path,
+        &["sync", "ssh://-oProxyCommand=touch${IFS}pwned/repo"],
+    );
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid SSH URL: ssh://-oProxyCommand=touch${IFS}pwned/repo
+    "###);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sync", "ssh://host:-1/repo"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Invalid SSH URL: ssh://host:-1/repo
+    "###);
+}
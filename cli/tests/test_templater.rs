@@ -71,7 +71,7 @@ fn test_templater_parse_error() {
       | ^---^
       |
       = Function "oncat" doesn't exist
-    Hint: Did you mean "concat", "socat"?
+    Hint: Did you mean "concat", "socat", "truncate"?
     "###);
     insta::assert_snapshot!(render_err(r#""".lines().map(|s| se)"#), @r###"
     Error: Failed to parse template: Keyword "se" doesn't exist
@@ -81,7 +81,7 @@ fn test_templater_parse_error() {
       |                    ^^
       |
       = Keyword "se" doesn't exist
-    Hint: Did you mean "s", "self"?
+    Hint: Did you mean "s", "self", "successors"?
     "###);
     insta::assert_snapshot!(render_err(r#"format_id(commit_id)"#), @r###"
     Error: Failed to parse template: Alias "format_id()" cannot be expanded
@@ -111,7 +111,7 @@ fn test_templater_parse_error() {
       | ^-----^
       |
       = Keyword "builtin" doesn't exist
-    Hint: Did you mean "builtin_change_id_with_hidden_and_divergent_info", "builtin_log_comfortable", "builtin_log_compact", "builtin_log_detailed", "builtin_log_oneline", "builtin_op_log_comfortable", "builtin_op_log_compact"?
+    Hint: Did you mean "builtin_change_id_with_hidden_and_divergent_info", "builtin_log_comfortable", "builtin_log_compact", "builtin_log_detailed", "builtin_log_hidden_successors", "builtin_log_oneline", "builtin_op_log_comfortable", "builtin_op_log_compact"?
     "###);
 }
 
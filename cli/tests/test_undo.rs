@@ -46,6 +46,69 @@ fn test_undo_rewrite_with_child() {
     "###);
 }
 
+#[test]
+fn test_undo_range_and_multiple() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let base_op_id = test_env.current_operation_id(&repo_path);
+    let mut op_ids = vec![];
+    for name in ["b1", "b2", "b3", "b4"] {
+        test_env.jj_cmd_ok(&repo_path, &["branch", "create", name]);
+        op_ids.push(test_env.current_operation_id(&repo_path));
+    }
+    let restore_op_id = test_env.current_operation_id(&repo_path);
+
+    // Undo a range of operations, keeping the later ones
+    let range = format!("{}..{}", base_op_id, op_ids[1]);
+    test_env.jj_cmd_ok(&repo_path, &["undo", &range]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    b3: qpvuntsm 230dd059 (empty) (no description set)
+    b4: qpvuntsm 230dd059 (empty) (no description set)
+    "###);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "-Tdescription", "-l1"]);
+    insta::assert_snapshot!(stdout.replace(&base_op_id, "BASE").replace(&op_ids[1], "B2"), @"@  undo operations BASE..B2");
+
+    // Undo several operations
+    test_env.jj_cmd_ok(&repo_path, &["op", "restore", &restore_op_id]);
+    test_env.jj_cmd_ok(&repo_path, &["undo", &op_ids[0], &op_ids[2]]);
+    insta::assert_snapshot!(get_branch_output(&test_env, &repo_path), @r###"
+    b2: qpvuntsm 230dd059 (empty) (no description set)
+    b4: qpvuntsm 230dd059 (empty) (no description set)
+    "###);
+
+    // The range must be in ancestor order
+    let range = format!("{}..{}", op_ids[1], op_ids[0]);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["undo", &range]);
+    insta::assert_snapshot!(stderr, @"Error: Operation 954a4e86c3ca is not an ancestor of operation 376fa63b8d77");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["undo", &format!("..{}", op_ids[0])]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Cannot undo repo initialization
+    Hint: Specify the operation before the first operation to undo
+    "###);
+}
+
+#[test]
+fn test_undo_reports_conflicts() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    test_env.jj_cmd_ok(&repo_path, &["branch", "create", "b"]);
+    let create_op_id = test_env.current_operation_id(&repo_path);
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    test_env.jj_cmd_ok(&repo_path, &["branch", "set", "b"]);
+
+    // Undoing the creation of the branch conflicts with the later move
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["undo", &create_op_id]);
+    insta::assert_snapshot!(stderr, @r###"
+    Warning: Undoing left conflicts in:
+      branch b
+    Hint: Use `jj branch set` to resolve the branch conflicts.
+    "###);
+}
+
 #[test]
 fn test_git_push_undo() {
     let test_env = TestEnvironment::default();
@@ -388,5 +451,6 @@ fn test_branch_track_untrack_undo() {
 }
 
 fn get_branch_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
-    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes"])
+    // --quiet to suppress the hint about conflicted branches
+    test_env.jj_cmd_success(repo_path, &["branch", "list", "--all-remotes", "--quiet"])
 }
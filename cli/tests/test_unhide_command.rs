@@ -0,0 +1,136 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::TestEnvironment;
+
+#[test]
+fn test_unhide() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    std::fs::write(repo_path.join("file"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+
+    let template = r#"commit_id.short() ++ " " ++ description"#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-r", "hidden()", "-T", template]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  13d4a2c2dd4f first
+    │
+    ~
+
+    ◉  8b7541789330 first
+    │
+    ~
+
+    ◉  4ebf85a935e3
+    │
+    ~
+
+    ◉  230dd059e1b0
+    │
+    ~
+    "###);
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "--hidden", "-r", "@-"]);
+    insta::assert_snapshot!(stdout, @r###"
+    ◉  qpvuntsm test.user@example.com 2001-02-03 08:05:09 db872a08
+    │  second
+    ~
+
+    ◉  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:09 13d4a2c2
+    │  first
+    ~  rewritten into db872a08
+
+    ◉  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:08 8b754178
+    │  first
+    ~  rewritten into db872a08
+
+    ◉  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:08 4ebf85a9
+    │  (no description set)
+    ~  rewritten into db872a08
+
+    ◉  qpvuntsm hidden test.user@example.com 2001-02-03 08:05:07 230dd059
+    │  (empty) (no description set)
+    ~  rewritten into db872a08
+    "###);
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["unhide", "8b754178"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Recovered 8b7541789330 as yqosqzyt 0ab07c3e first");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["cat", "-r", "description(first) & ~hidden()", "file"],
+    );
+    insta::assert_snapshot!(stdout, @"a");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["unhide", "@-"]);
+    insta::assert_snapshot!(stderr, @r###"
+    Error: Commit db872a08260912c854598b3acb06905641efad17 is not hidden
+    Hint: Use `jj duplicate` to copy a visible commit.
+    "###);
+}
+
+#[test]
+fn test_unhide_hidden_parent() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "parent"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "child"]);
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    // The child has been rewritten by the snapshot, so pick its latest version
+    let child_id = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--no-graph", "-r", "@-", "-T", "commit_id"],
+    );
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["abandon", "description(parent)::description(child)"],
+    );
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["unhide", &child_id]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Recovered c313188dc568 as yqosqzyt 03621fd8 child");
+    // The hidden parent isn't made visible again, so it doesn't become
+    // divergent. The recovered commit is on top of its visible ancestor instead.
+    let template = r#"if(root, "root", description.first_line()) ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            "description(parent) | (description(child) & ~hidden())-",
+            "-T",
+            template,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"root");
+    // The recovered commit still has the content of the hidden commit
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["cat", "-r", "description(child) & ~hidden()", "file1"],
+    );
+    insta::assert_snapshot!(stdout, @"a");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["cat", "-r", "description(child) & ~hidden()", "file2"],
+    );
+    insta::assert_snapshot!(stdout, @"b");
+}
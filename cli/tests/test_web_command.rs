@@ -0,0 +1,161 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{Read as _, Write as _};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::common::TestEnvironment;
+
+/// Requests `path` from the server on `port`, retrying until the server is up.
+/// Returns the status line and the contents of the `<body>`.
+fn fetch(port: u16, path: &str) -> String {
+    let start = Instant::now();
+    let mut stream = loop {
+        match TcpStream::connect((Ipv4Addr::LOCALHOST, port)) {
+            Ok(stream) => break stream,
+            Err(_) if start.elapsed() < Duration::from_secs(5) => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(err) => panic!("failed to connect to the server: {err}"),
+        }
+    };
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status_line = response.lines().next().unwrap();
+    let body_start = response.find("<body>\n").unwrap() + "<body>\n".len();
+    let body_end = response.find("</body>").unwrap();
+    format!("{status_line}\n{}", &response[body_start..body_end])
+}
+
+#[test]
+fn test_web() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "a <b>\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "@-", "-m", "first & only"]);
+
+    let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut cmd = test_env.jj_cmd(&repo_path, &["web", "--port", &port.to_string()]);
+    // The server is killed once the pages are fetched
+    cmd.timeout(Duration::from_secs(5));
+    let server = thread::spawn(move || cmd.output().unwrap());
+
+    insta::assert_snapshot!(fetch(port, "/"), @r###"
+    HTTP/1.1 200 OK
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Log</h1>
+    <form action="/"><input name="revset" size="60" value="@ | ancestors(immutable_heads().., 2) | trunk()"> <input type="submit" value="Show"></form>
+    <pre>@  <a href="/commit/263d2abeb90a343b8b7d219397b296994d6cb701">rlvkpnrz 263d2abe (empty) (no description set)</a>
+    ◉  <a href="/commit/d5f2feca89b1e3057fa4ac67e45659e7d0bd45b9">qpvuntsm d5f2feca first &amp; only</a>
+    ◉  <a href="/commit/0000000000000000000000000000000000000000">zzzzzzzz 00000000 (empty) (no description set)</a>
+    </pre>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/?revset=description(first)"), @r###"
+    HTTP/1.1 200 OK
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Log</h1>
+    <form action="/"><input name="revset" size="60" value="description(first)"> <input type="submit" value="Show"></form>
+    <pre>◉  <a href="/commit/d5f2feca89b1e3057fa4ac67e45659e7d0bd45b9">qpvuntsm d5f2feca first &amp; only</a>
+    │
+    ~
+    </pre>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/commit/@-"), @r###"
+    HTTP/1.1 200 OK
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>qpvuntsm d5f2feca first &amp; only</h1>
+    <p>Commit ID: d5f2feca89b1e3057fa4ac67e45659e7d0bd45b9<br>
+    Change ID: <a href="/?revset=qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu">qpvuntsmwlqtpsluzzsnyyzlmlwvmlnu</a><br>
+    Author: Test User &lt;test.user@example.com&gt; (2001-02-03 04:05:07.000 +07:00)<br>
+    Parent: <a href="/commit/0000000000000000000000000000000000000000">zzzzzzzz 00000000 (empty) (no description set)</a><br>
+    <a href="/obslog/d5f2feca89b1e3057fa4ac67e45659e7d0bd45b9">Evolution of this change</a></p>
+    <pre>first &amp; only
+    </pre>
+    <pre>diff --git a/file b/file
+    new file mode 100644
+    index 0000000000..4ab867adff
+    --- /dev/null
+    +++ b/file
+    @@ -0,0 +1,1 @@
+    +a &lt;b&gt;
+    </pre>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/obslog/@-"), @r###"
+    HTTP/1.1 200 OK
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Evolution of qpvuntsm d5f2feca first &amp; only</h1>
+    <pre>◉  <a href="/commit/d5f2feca89b1e3057fa4ac67e45659e7d0bd45b9">qpvuntsm d5f2feca first &amp; only</a>
+    ◉  <a href="/commit/8643bcfb30f6c9a54c55a8f0b0482bff9963bbe3">qpvuntsm hidden 8643bcfb first</a>
+    ◉  <a href="/commit/f14a4f5dab23a0ea2b1d0e1c827d5a1cd49d9640">qpvuntsm hidden f14a4f5d (no description set)</a>
+    ◉  <a href="/commit/230dd059e1b059aefc0da06a2e5a7dbf22362f22">qpvuntsm hidden 230dd059 (empty) (no description set)</a>
+    </pre>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/operations"), @r###"
+    HTTP/1.1 200 OK
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Operations</h1>
+    <pre>6559649c5f11 test-username@host.example.com 2001-02-03 04:05:09.000 +07:00
+        describe commit 8643bcfb30f6c9a54c55a8f0b0482bff9963bbe3
+    f52aefe8883b test-username@host.example.com 2001-02-03 04:05:08.000 +07:00
+        commit f14a4f5dab23a0ea2b1d0e1c827d5a1cd49d9640
+    86f2f5dd9007 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00
+        snapshot working copy
+    b51416386f26 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00
+        add workspace &#39;default&#39;
+    9a7d829846af test-username@host.example.com 2001-02-03 04:05:07.000 +07:00
+        initialize repo
+    000000000000 @ 1970-01-01 00:00:00.000 +00:00
+    </pre>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/operations?skip=4"), @r###"
+    HTTP/1.1 200 OK
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Operations</h1>
+    <pre>9a7d829846af test-username@host.example.com 2001-02-03 04:05:07.000 +07:00
+        initialize repo
+    000000000000 @ 1970-01-01 00:00:00.000 +00:00
+    </pre>
+    <p><a href="/operations?skip=0">Newer operations</a></p>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/operations?skip=x"), @r###"
+    HTTP/1.1 400 Bad Request
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Error</h1>
+    <p class="error">Invalid skip count: x: invalid digit found in string</p>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/commit/nonexistent"), @r###"
+    HTTP/1.1 400 Bad Request
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Error</h1>
+    <p class="error">Revision &quot;nonexistent&quot; doesn&#39;t exist</p>
+    "###);
+    insta::assert_snapshot!(fetch(port, "/nonexistent"), @r###"
+    HTTP/1.1 404 Not Found
+    <nav><a href="/">Log</a><a href="/operations">Operations</a></nav>
+    <h1>Not found</h1>
+    <p>No page at /nonexistent.</p>
+    "###);
+    let server = server.join().unwrap();
+    let stderr = String::from_utf8(server.stderr).unwrap();
+    assert_eq!(stderr, format!("Serving on http://127.0.0.1:{port}/\n"));
+}
@@ -30,3 +30,27 @@ fn test_snapshot_large_file() {
     want this file to be snapshotted. Otherwise add it to your `.gitignore` file.
     "###);
 }
+
+#[test]
+fn test_snapshot_exclude_paths() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(
+        repo_path.join(".jj/repo/config.toml"),
+        r#"snapshot.exclude-paths = ["out", "dir/generated"]"#,
+    )
+    .unwrap();
+    std::fs::create_dir_all(repo_path.join("out/nested")).unwrap();
+    std::fs::create_dir(repo_path.join("dir")).unwrap();
+    std::fs::write(repo_path.join("out/nested/file"), "").unwrap();
+    std::fs::write(repo_path.join("dir/generated"), "").unwrap();
+    std::fs::write(repo_path.join("dir/file"), "").unwrap();
+    std::fs::write(repo_path.join("outside"), "").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["files"]);
+    insta::assert_snapshot!(stdout, @r###"
+    dir/file
+    outside
+    "###);
+}
@@ -77,6 +77,7 @@ fn test_workspaces_sparse_patterns() {
     let ws1_path = test_env.env_root().join("ws1");
     let ws2_path = test_env.env_root().join("ws2");
     let ws3_path = test_env.env_root().join("ws3");
+    let ws4_path = test_env.env_root().join("ws4");
 
     test_env.jj_cmd_ok(&ws1_path, &["sparse", "set", "--clear", "--add=foo"]);
     test_env.jj_cmd_ok(&ws1_path, &["workspace", "add", "../ws2"]);
@@ -91,6 +92,23 @@ fn test_workspaces_sparse_patterns() {
     bar
     foo
     "###);
+
+    // The patterns can be specified instead of being copied
+    test_env.jj_cmd_ok(
+        &ws3_path,
+        &[
+            "workspace",
+            "add",
+            "../ws4",
+            "--sparse=baz",
+            "--sparse=qux/quux",
+        ],
+    );
+    let stdout = test_env.jj_cmd_success(&ws4_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    baz
+    qux/quux
+    "###);
 }
 
 /// Test adding a second workspace while the current workspace is editing a
@@ -316,6 +334,9 @@ fn test_workspaces_conflicting_edits() {
     insta::assert_snapshot!(stderr, @r###"
     Concurrent modification detected, resolving automatically.
     Rebased 1 descendant commits onto commits rewritten by other operation
+    Warning: Concurrent operations left conflicts in:
+      working-copy commit of workspace secondary
+    Hint: Use `jj edit` to switch the working copy to the commit of the other operation.
     Working copy now at: pmmvwywv?? a1896a17 (empty) (no description set)
     Added 0 files, modified 1 files, removed 0 files
     "###);
@@ -406,6 +427,45 @@ fn test_workspaces_updated_by_other() {
     "###);
 }
 
+#[test]
+fn test_workspaces_auto_update_stale() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config("workspace.auto-update-stale = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "--git", "main"]);
+    let main_path = test_env.env_root().join("main");
+    let secondary_path = test_env.env_root().join("secondary");
+
+    std::fs::write(main_path.join("file"), "contents\n").unwrap();
+    test_env.jj_cmd_ok(&main_path, &["new"]);
+    test_env.jj_cmd_ok(&main_path, &["workspace", "add", "../secondary"]);
+
+    // Rewrite the check-out commit in one workspace.
+    std::fs::write(main_path.join("file"), "changed in main\n").unwrap();
+    test_env.jj_cmd_ok(&main_path, &["squash"]);
+
+    // The stale working copy is updated before running the command
+    let (stdout, stderr) = test_env.jj_cmd_ok(&secondary_path, &["st"]);
+    insta::assert_snapshot!(stdout, @r###"
+    The working copy is clean
+    Working copy : pmmvwywv 825d3192 (empty) (no description set)
+    Parent commit: qpvuntsm 6a4ca0d5 (no description set)
+    "###);
+    insta::assert_snapshot!(stderr, @r###"
+    The working copy is stale (not updated since operation 58b580b12eee). Updating it.
+    Working copy now at: pmmvwywv 825d3192 (empty) (no description set)
+    Added 0 files, modified 1 files, removed 0 files
+    "###);
+    let contents = std::fs::read_to_string(secondary_path.join("file")).unwrap();
+    insta::assert_snapshot!(contents, @"changed in main");
+    insta::assert_snapshot!(get_log_output(&test_env, &secondary_path), @r###"
+    ◉  8bfd43bc3f4f default@
+    │ @  825d3192b3b5 secondary@
+    ├─╯
+    ◉  6a4ca0d5d24e
+    ◉  000000000000
+    "###);
+}
+
 #[test]
 fn test_workspaces_current_op_discarded_by_other() {
     let test_env = TestEnvironment::default();
@@ -665,6 +725,62 @@ fn test_workspaces_forget() {
     insta::assert_snapshot!(stdout, @"");
 }
 
+#[test]
+fn test_workspaces_forget_clean() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["init", "--git", "main"]);
+    let main_path = test_env.env_root().join("main");
+
+    std::fs::write(main_path.join("file"), "contents").unwrap();
+    test_env.jj_cmd_ok(&main_path, &["new"]);
+    test_env.jj_cmd_ok(&main_path, &["workspace", "add", "../secondary"]);
+    test_env.jj_cmd_ok(&main_path, &["workspace", "add", "../third"]);
+
+    // Nothing to clean up while all the directories exist
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list", "--stale"]);
+    insta::assert_snapshot!(stdout, @"");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&main_path, &["workspace", "forget", "--clean"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"No workspaces with missing directories.");
+
+    std::fs::remove_dir_all(test_env.env_root().join("secondary")).unwrap();
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list", "--stale"]);
+    insta::assert_snapshot!(stdout, @"secondary: pmmvwywv feda1c4e (empty) (no description set)");
+
+    // The workspace names can't be combined with --clean
+    let stderr =
+        test_env.jj_cmd_cli_error(&main_path, &["workspace", "forget", "--clean", "third"]);
+    insta::assert_snapshot!(stderr, @r###"
+    error: the argument '--clean' cannot be used with '[WORKSPACES]...'
+
+    Usage: jj workspace forget --clean [WORKSPACES]...
+
+    For more information, try '--help'.
+    "###);
+
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&main_path, &["workspace", "forget", "--clean", "--abandon"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"");
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
+    insta::assert_snapshot!(stdout, @r###"
+    default: rlvkpnrz e949be04 (empty) (no description set)
+    third: rzvqmyuk 485853ed (empty) (no description set)
+    "###);
+    // The working-copy commit of the forgotten workspace was abandoned
+    insta::assert_snapshot!(get_log_output(&test_env, &main_path), @r###"
+    ◉  485853edd4e7 third@
+    │ @  e949be04e93e default@
+    ├─╯
+    ◉  123ed18e4c4c
+    ◉  000000000000
+    "###);
+
+    // The directory is no longer reported once the workspace is forgotten
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list", "--stale"]);
+    insta::assert_snapshot!(stdout, @"");
+}
+
 #[test]
 fn test_workspaces_forget_multi_transaction() {
     let test_env = TestEnvironment::default();
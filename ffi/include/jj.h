@@ -0,0 +1,70 @@
+/*
+ * Copyright 2024 The Jujutsu Authors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Read-only access to jj repositories.
+ *
+ * Queries return JSON documents as NUL-terminated strings, which must be
+ * freed with jj_string_free(). On failure, they return NULL and the error
+ * message can be read with jj_last_error(). Revisions are revset expressions
+ * which must resolve to a single commit.
+ */
+
+#ifndef JJ_H
+#define JJ_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct JjSession JjSession;
+
+/* Message of the last error on this thread, or NULL. Owned by the library. */
+const char *jj_last_error(void);
+
+/* Opens the workspace at `path` at its latest operation. */
+JjSession *jj_session_open(const char *path);
+void jj_session_free(JjSession *session);
+
+void jj_string_free(char *s);
+void jj_bytes_free(uint8_t *data, size_t len);
+
+/* Array of commits with commit_id, change_id, parents, description, author,
+ * and committer. */
+char *jj_evaluate_revset(const JjSession *session, const char *revset);
+
+/* Array of {path, type} for the files in the tree of `revision`. */
+char *jj_tree_entries(const JjSession *session, const char *revision);
+
+/* Contents of the file at `path` in `revision`, with the length in `len`.
+ * Free with jj_bytes_free(). */
+uint8_t *jj_read_file(const JjSession *session, const char *revision,
+                      const char *path, size_t *len);
+
+/* Array of {path, status} for the files changed in `revision`. */
+char *jj_diff_summary(const JjSession *session, const char *revision);
+
+/* Array of operations, latest first, at most `limit` of them (0 for all). */
+char *jj_op_log(const JjSession *session, size_t limit);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* JJ_H */
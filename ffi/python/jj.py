@@ -0,0 +1,122 @@
+# Copyright 2024 The Jujutsu Authors
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+# https://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+"""Read-only access to jj repositories through the jj-ffi library.
+
+The library is loaded from $JJ_FFI_LIBRARY, or else found by the system's
+dynamic loader.
+
+    with jj.Session("path/to/workspace") as session:
+        for commit in session.evaluate_revset("::@"):
+            print(commit["change_id"], commit["description"])
+"""
+
+import ctypes
+import ctypes.util
+import json
+import os
+
+
+def _load_library():
+    path = os.environ.get("JJ_FFI_LIBRARY") or ctypes.util.find_library("jj_ffi")
+    if path is None:
+        raise OSError("Could not find the jj_ffi library; set $JJ_FFI_LIBRARY")
+    lib = ctypes.CDLL(path)
+    session_p = ctypes.c_void_p
+    lib.jj_last_error.restype = ctypes.c_char_p
+    lib.jj_session_open.argtypes = [ctypes.c_char_p]
+    lib.jj_session_open.restype = session_p
+    lib.jj_session_free.argtypes = [session_p]
+    lib.jj_string_free.argtypes = [ctypes.c_void_p]
+    lib.jj_bytes_free.argtypes = [ctypes.c_void_p, ctypes.c_size_t]
+    for name in ["jj_evaluate_revset", "jj_tree_entries", "jj_diff_summary"]:
+        getattr(lib, name).argtypes = [session_p, ctypes.c_char_p]
+        getattr(lib, name).restype = ctypes.c_void_p
+    lib.jj_read_file.argtypes = [
+        session_p,
+        ctypes.c_char_p,
+        ctypes.c_char_p,
+        ctypes.POINTER(ctypes.c_size_t),
+    ]
+    lib.jj_read_file.restype = ctypes.c_void_p
+    lib.jj_op_log.argtypes = [session_p, ctypes.c_size_t]
+    lib.jj_op_log.restype = ctypes.c_void_p
+    return lib
+
+
+_lib = _load_library()
+
+
+class JjError(Exception):
+    pass
+
+
+def _check(result):
+    if not result:
+        raise JjError(_lib.jj_last_error().decode())
+    return result
+
+
+def _json(result):
+    _check(result)
+    try:
+        return json.loads(ctypes.string_at(result).decode())
+    finally:
+        _lib.jj_string_free(result)
+
+
+class Session:
+    """A workspace opened at the latest operation of its repo."""
+
+    def __init__(self, path):
+        self._session = None
+        self._session = _check(_lib.jj_session_open(os.fsencode(path)))
+
+    def close(self):
+        if self._session:
+            _lib.jj_session_free(self._session)
+            self._session = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        self.close()
+
+    def evaluate_revset(self, revset):
+        return _json(_lib.jj_evaluate_revset(self._session, revset.encode()))
+
+    def tree_entries(self, revision):
+        return _json(_lib.jj_tree_entries(self._session, revision.encode()))
+
+    def read_file(self, revision, path):
+        length = ctypes.c_size_t()
+        data = _check(
+            _lib.jj_read_file(
+                self._session, revision.encode(), path.encode(), ctypes.byref(length)
+            )
+        )
+        try:
+            return ctypes.string_at(data, length.value)
+        finally:
+            _lib.jj_bytes_free(data, length)
+
+    def diff_summary(self, revision):
+        return _json(_lib.jj_diff_summary(self._session, revision.encode()))
+
+    def op_log(self, limit=0):
+        return _json(_lib.jj_op_log(self._session, limit))
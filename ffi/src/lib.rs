@@ -0,0 +1,373 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C ABI for read-only access to jj repositories.
+//!
+//! A session is opened with [`jj_session_open()`] and freed with
+//! [`jj_session_free()`]. Queries return JSON documents as NUL-terminated
+//! strings, which must be freed with [`jj_string_free()`]. On failure, they
+//! return a null pointer and the error message can be read with
+//! [`jj_last_error()`]. Revisions are given as revset expressions which must
+//! resolve to a single commit.
+//!
+//! The declarations are in `include/jj.h`, and `python/jj.py` wraps them for
+//! Python.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use jj_lib::backend::{Signature, Timestamp, TreeValue};
+use jj_lib::commit::Commit;
+use jj_lib::hex_util::to_reverse_hex;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::merge::MergedTreeValue;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_walk;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::rewrite::merge_commit_trees;
+use jj_lib::session::WorkspaceSession;
+use jj_lib::settings::UserSettings;
+use serde_json::{json, Value};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A workspace opened for reading, with the repo at its latest operation.
+pub struct JjSession {
+    session: WorkspaceSession,
+}
+
+fn set_last_error(message: String) {
+    // Error messages can't contain NUL, but be safe.
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, recording its error or panic as the last error.
+fn catch<T>(f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(message)) => {
+            set_last_error(message);
+            None
+        }
+        Err(_) => {
+            set_last_error("Internal error: jj panicked".to_owned());
+            None
+        }
+    }
+}
+
+/// # Safety
+///
+/// `s` must be a valid NUL-terminated string.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("Argument `{name}` is null"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| format!("Argument `{name}` is not valid UTF-8"))
+}
+
+/// # Safety
+///
+/// `session` must be null or a pointer returned by [`jj_session_open()`].
+unsafe fn session_arg<'a>(session: *const JjSession) -> Result<&'a WorkspaceSession, String> {
+    session
+        .as_ref()
+        .map(|s| &s.session)
+        .ok_or_else(|| "Argument `session` is null".to_owned())
+}
+
+fn json_string(value: Value) -> *mut c_char {
+    CString::new(value.to_string()).unwrap().into_raw()
+}
+
+fn timestamp_json(timestamp: &Timestamp) -> Value {
+    json!({
+        "millis": timestamp.timestamp.0,
+        "tz_offset": timestamp.tz_offset,
+    })
+}
+
+fn signature_json(signature: &Signature) -> Value {
+    json!({
+        "name": signature.name,
+        "email": signature.email,
+        "timestamp": timestamp_json(&signature.timestamp),
+    })
+}
+
+fn commit_json(commit: &Commit) -> Value {
+    json!({
+        "commit_id": commit.id().hex(),
+        "change_id": to_reverse_hex(&commit.change_id().hex()).unwrap(),
+        "parents": commit.parent_ids().iter().map(|id| id.hex()).collect::<Vec<_>>(),
+        "description": commit.description(),
+        "author": signature_json(commit.author()),
+        "committer": signature_json(commit.committer()),
+    })
+}
+
+fn entry_type(value: &MergedTreeValue) -> &'static str {
+    match value.as_resolved() {
+        Some(Some(TreeValue::File {
+            executable: false, ..
+        })) => "file",
+        Some(Some(TreeValue::File {
+            executable: true, ..
+        })) => "executable",
+        Some(Some(TreeValue::Symlink(_))) => "symlink",
+        Some(Some(TreeValue::GitSubmodule(_))) => "git-submodule",
+        Some(Some(TreeValue::Tree(_))) => "tree",
+        Some(Some(TreeValue::Conflict(_))) | Some(None) | None => "conflict",
+    }
+}
+
+fn resolve_commit(session: &WorkspaceSession, revision: &str) -> Result<Commit, String> {
+    session
+        .resolve_single_commit(revision)
+        .map_err(|err| err.to_string())
+}
+
+fn repo_path(path: &str) -> Result<RepoPathBuf, String> {
+    RepoPathBuf::from_relative_path(path).map_err(|err| err.to_string())
+}
+
+/// Returns the message of the last error on this thread, or null if there
+/// was none. The string is owned by the library and valid until the next call
+/// on this thread.
+#[no_mangle]
+pub extern "C" fn jj_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Opens the workspace at `path` and loads the repo at its latest operation.
+/// The working copy isn't snapshotted. Returns null on failure.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn jj_session_open(path: *const c_char) -> *mut JjSession {
+    catch(|| {
+        let path = str_arg(path, "path")?;
+        let settings = UserSettings::from_config(config::Config::default());
+        let session =
+            WorkspaceSession::load(&settings, Path::new(path)).map_err(|err| err.to_string())?;
+        Ok(Box::into_raw(Box::new(JjSession { session })))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Frees a session returned by [`jj_session_open()`].
+///
+/// # Safety
+///
+/// `session` must be null or a pointer returned by [`jj_session_open()`] that
+/// wasn't freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jj_session_free(session: *mut JjSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
+/// Frees a string returned by the library.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by the library that wasn't freed
+/// yet. The string returned by [`jj_last_error()`] must not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn jj_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Frees file contents returned by [`jj_read_file()`].
+///
+/// # Safety
+///
+/// `data` must be null or a pointer returned by [`jj_read_file()`] with its
+/// `len`, and must not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jj_bytes_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// Evaluates `revset` and returns a JSON array of the commits in it. Each
+/// commit is an object with the `commit_id`, `change_id`, `parents`,
+/// `description`, `author`, and `committer`.
+///
+/// # Safety
+///
+/// `session` must be a pointer returned by [`jj_session_open()`] and `revset`
+/// a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn jj_evaluate_revset(
+    session: *const JjSession,
+    revset: *const c_char,
+) -> *mut c_char {
+    catch(|| {
+        let session = session_arg(session)?;
+        let commits = session
+            .evaluate_revset(str_arg(revset, "revset")?)
+            .map_err(|err| err.to_string())?;
+        Ok(json_string(commits.iter().map(commit_json).collect()))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Returns a JSON array of the files in the tree of `revision`. Each entry is
+/// an object with the `path` and the `type`: "file", "executable", "symlink",
+/// "git-submodule", or "conflict".
+///
+/// # Safety
+///
+/// `session` must be a pointer returned by [`jj_session_open()`] and
+/// `revision` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn jj_tree_entries(
+    session: *const JjSession,
+    revision: *const c_char,
+) -> *mut c_char {
+    catch(|| {
+        let session = session_arg(session)?;
+        let commit = resolve_commit(session, str_arg(revision, "revision")?)?;
+        let tree = commit.tree().map_err(|err| err.to_string())?;
+        let entries = tree
+            .entries()
+            .map(|(path, value)| {
+                json!({
+                    "path": path.as_internal_file_string(),
+                    "type": entry_type(&value),
+                })
+            })
+            .collect();
+        Ok(json_string(entries))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Reads the file at `path` in the tree of `revision` and stores its length in
+/// `len`. The contents must be freed with [`jj_bytes_free()`]. Returns null if
+/// the path doesn't exist or isn't a regular file.
+///
+/// # Safety
+///
+/// `session` must be a pointer returned by [`jj_session_open()`], `revision`
+/// and `path` valid NUL-terminated strings, and `len` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn jj_read_file(
+    session: *const JjSession,
+    revision: *const c_char,
+    path: *const c_char,
+    len: *mut usize,
+) -> *mut u8 {
+    catch(|| {
+        let session = session_arg(session)?;
+        let commit = resolve_commit(session, str_arg(revision, "revision")?)?;
+        let path_str = str_arg(path, "path")?;
+        let contents = session
+            .read_file(&commit, &repo_path(path_str)?)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("No such path: {path_str}"))?;
+        let contents = contents.into_boxed_slice();
+        *len = contents.len();
+        Ok(Box::into_raw(contents).cast::<u8>())
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Returns a JSON array of the files changed in `revision` compared to its
+/// parents. Each entry is an object with the `path` and the `status`: "added",
+/// "removed", or "modified".
+///
+/// # Safety
+///
+/// `session` must be a pointer returned by [`jj_session_open()`] and
+/// `revision` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn jj_diff_summary(
+    session: *const JjSession,
+    revision: *const c_char,
+) -> *mut c_char {
+    catch(|| {
+        let session = session_arg(session)?;
+        let commit = resolve_commit(session, str_arg(revision, "revision")?)?;
+        let repo = session.repo().as_ref();
+        let from_tree =
+            merge_commit_trees(repo, &commit.parents()).map_err(|err| err.to_string())?;
+        let to_tree = commit.tree().map_err(|err| err.to_string())?;
+        let mut changes = vec![];
+        for (path, diff) in from_tree.diff(&to_tree, &EverythingMatcher) {
+            let (before, after) = diff.map_err(|err| err.to_string())?;
+            let status = if before.is_absent() {
+                "added"
+            } else if after.is_absent() {
+                "removed"
+            } else {
+                "modified"
+            };
+            changes.push(json!({
+                "path": path.as_internal_file_string(),
+                "status": status,
+            }));
+        }
+        Ok(json_string(changes.into()))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Returns a JSON array of the operations in the operation log, latest first,
+/// up to `limit` operations (all of them if `limit` is 0). Each operation is
+/// an object with the `operation_id`, `parents`, `description`, `hostname`,
+/// `username`, `start_time`, and `end_time`.
+///
+/// # Safety
+///
+/// `session` must be a pointer returned by [`jj_session_open()`].
+#[no_mangle]
+pub unsafe extern "C" fn jj_op_log(session: *const JjSession, limit: usize) -> *mut c_char {
+    catch(|| {
+        let session = session_arg(session)?;
+        let head_op = session.repo().operation().clone();
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        let mut ops = vec![];
+        for op in op_walk::walk_ancestors(&[head_op]).take(limit) {
+            let op = op.map_err(|err| err.to_string())?;
+            let metadata = op.metadata();
+            ops.push(json!({
+                "operation_id": op.id().hex(),
+                "parents": op.parent_ids().iter().map(|id| id.hex()).collect::<Vec<_>>(),
+                "description": metadata.description,
+                "hostname": metadata.hostname,
+                "username": metadata.username,
+                "start_time": timestamp_json(&metadata.start_time),
+                "end_time": timestamp_json(&metadata.end_time),
+            }));
+        }
+        Ok(json_string(ops.into()))
+    })
+    .unwrap_or(ptr::null_mut())
+}
@@ -0,0 +1,135 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ffi::{c_char, CStr, CString};
+use std::{fs, ptr, slice};
+
+use jj_ffi::*;
+use jj_lib::session::WorkspaceSession;
+use serde_json::Value;
+use testutils::{TestRepoBackend, TestWorkspace};
+
+fn c_str(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(jj_last_error()) }
+        .to_str()
+        .unwrap()
+        .to_owned()
+}
+
+fn take_json(s: *mut c_char) -> Value {
+    assert!(!s.is_null(), "{}", last_error());
+    let value = serde_json::from_str(unsafe { CStr::from_ptr(s) }.to_str().unwrap()).unwrap();
+    unsafe { jj_string_free(s) };
+    value
+}
+
+#[test]
+fn test_ffi_queries() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init_with_backend(&settings, TestRepoBackend::Git);
+    let workspace_root = test_workspace.workspace.workspace_root().clone();
+    {
+        let mut session = WorkspaceSession::load(&settings, &workspace_root).unwrap();
+        fs::write(workspace_root.join("file"), "contents").unwrap();
+        session.snapshot().unwrap();
+        let commit = session.working_copy_commit().unwrap().unwrap();
+        session.describe(&commit, "first").unwrap();
+    }
+
+    let path = c_str(workspace_root.to_str().unwrap());
+    let session = unsafe { jj_session_open(path.as_ptr()) };
+    assert!(!session.is_null(), "{}", last_error());
+    let rev = c_str("@");
+
+    let commits = take_json(unsafe { jj_evaluate_revset(session, c_str("::@").as_ptr()) });
+    let commits = commits.as_array().unwrap();
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits[0]["description"], "first");
+    assert_eq!(commits[0]["author"]["name"], "Test User");
+    assert_eq!(commits[0]["parents"][0], commits[1]["commit_id"]);
+
+    let entries = take_json(unsafe { jj_tree_entries(session, rev.as_ptr()) });
+    assert_eq!(
+        entries,
+        serde_json::json!([{"path": "file", "type": "file"}])
+    );
+
+    let diff = take_json(unsafe { jj_diff_summary(session, rev.as_ptr()) });
+    assert_eq!(
+        diff,
+        serde_json::json!([{"path": "file", "status": "added"}])
+    );
+
+    let mut len = 0;
+    let data = unsafe { jj_read_file(session, rev.as_ptr(), c_str("file").as_ptr(), &mut len) };
+    assert!(!data.is_null(), "{}", last_error());
+    assert_eq!(unsafe { slice::from_raw_parts(data, len) }, b"contents");
+    unsafe { jj_bytes_free(data, len) };
+
+    let ops = take_json(unsafe { jj_op_log(session, 2) });
+    let ops = ops.as_array().unwrap();
+    assert_eq!(ops.len(), 2);
+    assert_eq!(ops[0]["parents"][0], ops[1]["operation_id"]);
+    assert_eq!(ops[1]["description"], "snapshot working copy");
+
+    unsafe { jj_session_free(session) };
+}
+
+#[test]
+fn test_ffi_errors() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init_with_backend(&settings, TestRepoBackend::Git);
+    let workspace_root = test_workspace.workspace.workspace_root();
+
+    let session = unsafe { jj_session_open(c_str("/nonexistent").as_ptr()) };
+    assert!(session.is_null());
+    assert_eq!(last_error(), "There is no Jujutsu repo in /nonexistent");
+
+    let path = c_str(workspace_root.to_str().unwrap());
+    let session = unsafe { jj_session_open(path.as_ptr()) };
+    assert!(!session.is_null(), "{}", last_error());
+
+    let result = unsafe { jj_evaluate_revset(session, c_str("nonexistent").as_ptr()) };
+    assert!(result.is_null());
+    assert_eq!(last_error(), r#"Revision "nonexistent" doesn't exist"#);
+
+    let result = unsafe { jj_diff_summary(session, c_str("all()").as_ptr()) };
+    assert!(result.is_null());
+    assert_eq!(
+        last_error(),
+        r#"Revset "all()" resolved to 2 revisions instead of one"#
+    );
+
+    let mut len = 0;
+    let data = unsafe {
+        jj_read_file(
+            session,
+            c_str("@").as_ptr(),
+            c_str("missing").as_ptr(),
+            &mut len,
+        )
+    };
+    assert!(data.is_null());
+    assert_eq!(last_error(), "No such path: missing");
+
+    let result = unsafe { jj_tree_entries(ptr::null(), c_str("@").as_ptr()) };
+    assert!(result.is_null());
+    assert_eq!(last_error(), "Argument `session` is null");
+
+    unsafe { jj_session_free(session) };
+}
@@ -23,11 +23,11 @@ use std::time::SystemTime;
 use async_trait::async_trait;
 use thiserror::Error;
 
-use crate::content_hash::ContentHash;
+use crate::content_hash::{ContentHash, DigestUpdate};
 use crate::index::Index;
 use crate::merge::Merge;
 use crate::object_id::{id_type, ObjectId};
-use crate::repo_path::{RepoPath, RepoPathComponent, RepoPathComponentBuf};
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent, RepoPathComponentBuf};
 use crate::signing::SignResult;
 
 id_type!(
@@ -125,7 +125,7 @@ impl MergedTreeId {
     }
 }
 
-#[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Commit {
     pub parents: Vec<CommitId>,
     pub predecessors: Vec<CommitId>,
@@ -135,6 +135,36 @@ pub struct Commit {
     pub author: Signature,
     pub committer: Signature,
     pub secure_sig: Option<SecureSig>,
+    /// Files which were copied or moved from the parents. Sorted by target.
+    pub copies: Vec<CopyRecord>,
+}
+
+impl ContentHash for Commit {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        self.parents.hash(state);
+        self.predecessors.hash(state);
+        self.root_tree.hash(state);
+        self.change_id.hash(state);
+        self.description.hash(state);
+        self.author.hash(state);
+        self.committer.hash(state);
+        self.secure_sig.hash(state);
+        // Only hashed if present, so that the ids of the commits without copies
+        // are the same as before the copies were recorded.
+        if !self.copies.is_empty() {
+            self.copies.hash(state);
+        }
+    }
+}
+
+/// Records that the file at `target` was copied from `source` in the parent
+/// tree. If `source` no longer exists in the commit, the file was moved.
+#[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
+pub struct CopyRecord {
+    pub target: RepoPathBuf,
+    pub source: RepoPathBuf,
+    /// The id of the source file in the parent tree.
+    pub source_file: FileId,
 }
 
 #[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
@@ -332,6 +362,7 @@ pub fn make_root_commit(root_change_id: ChangeId, empty_tree_id: TreeId) -> Comm
         author: signature.clone(),
         committer: signature,
         secure_sig: None,
+        copies: vec![],
     }
 }
 
@@ -411,3 +442,63 @@ pub trait Backend: Send + Sync + Debug {
     /// risk of deleting new commits created concurrently by another process.
     fn gc(&self, index: &dyn Index, keep_newer: SystemTime) -> BackendResult<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_hash::blake2b_hash;
+
+    #[test]
+    fn test_commit_hash_without_copies() {
+        // The fields of `Commit` before the copies were recorded
+        #[derive(ContentHash)]
+        struct CommitWithoutCopies {
+            parents: Vec<CommitId>,
+            predecessors: Vec<CommitId>,
+            root_tree: MergedTreeId,
+            change_id: ChangeId,
+            description: String,
+            author: Signature,
+            committer: Signature,
+            secure_sig: Option<SecureSig>,
+        }
+
+        let signature = Signature {
+            name: "Some One".to_owned(),
+            email: "some.one@example.com".to_owned(),
+            timestamp: Timestamp {
+                timestamp: MillisSinceEpoch(0),
+                tz_offset: 0,
+            },
+        };
+        let mut commit = Commit {
+            parents: vec![CommitId::from_hex("aa")],
+            predecessors: vec![],
+            root_tree: MergedTreeId::resolved(TreeId::from_hex("bb")),
+            change_id: ChangeId::from_hex("cc"),
+            description: "description".to_owned(),
+            author: signature.clone(),
+            committer: signature.clone(),
+            secure_sig: None,
+            copies: vec![],
+        };
+        let commit_without_copies = CommitWithoutCopies {
+            parents: commit.parents.clone(),
+            predecessors: commit.predecessors.clone(),
+            root_tree: commit.root_tree.clone(),
+            change_id: commit.change_id.clone(),
+            description: commit.description.clone(),
+            author: signature.clone(),
+            committer: signature,
+            secure_sig: None,
+        };
+        assert_eq!(blake2b_hash(&commit), blake2b_hash(&commit_without_copies));
+
+        commit.copies.push(CopyRecord {
+            target: RepoPathBuf::from_internal_string("target"),
+            source: RepoPathBuf::from_internal_string("source"),
+            source_file: FileId::from_hex("dd"),
+        });
+        assert_ne!(blake2b_hash(&commit), blake2b_hash(&commit_without_copies));
+    }
+}
@@ -20,7 +20,7 @@ use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use crate::backend;
-use crate::backend::{BackendError, ChangeId, CommitId, MergedTreeId, Signature};
+use crate::backend::{BackendError, ChangeId, CommitId, CopyRecord, MergedTreeId, Signature};
 use crate::merged_tree::MergedTree;
 use crate::signing::{SignResult, Verification};
 use crate::store::Store;
@@ -137,6 +137,12 @@ impl Commit {
         &self.data.committer
     }
 
+    /// Files which were copied or moved from the parents, as recorded when the
+    /// commit was created.
+    pub fn copies(&self) -> &[CopyRecord] {
+        &self.data.copies
+    }
+
     /// A commit is discardable if it has one parent, no change from its
     /// parent, and an empty description.
     pub fn is_discardable(&self) -> bool {
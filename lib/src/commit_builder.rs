@@ -16,9 +16,15 @@
 
 use std::sync::Arc;
 
-use crate::backend::{self, BackendResult, ChangeId, CommitId, MergedTreeId, Signature, SigningFn};
+use itertools::Itertools as _;
+
+use crate::backend::{
+    self, BackendResult, ChangeId, CommitId, CopyRecord, MergedTreeId, Signature, SigningFn,
+    TreeValue,
+};
 use crate::commit::Commit;
 use crate::repo::{MutableRepo, Repo};
+use crate::rewrite::merge_commit_trees;
 use crate::settings::{JJRng, SignSettings, UserSettings};
 use crate::signing::SignBehavior;
 
@@ -28,6 +34,7 @@ pub struct CommitBuilder<'repo> {
     rng: Arc<JJRng>,
     commit: backend::Commit,
     rewrite_source: Option<Commit>,
+    copies_set: bool,
     sign_settings: SignSettings,
 }
 
@@ -52,12 +59,14 @@ impl CommitBuilder<'_> {
             author: signature.clone(),
             committer: signature,
             secure_sig: None,
+            copies: vec![],
         };
         CommitBuilder {
             mut_repo,
             rng,
             commit,
             rewrite_source: None,
+            copies_set: false,
             sign_settings: settings.sign_settings(),
         }
     }
@@ -90,6 +99,7 @@ impl CommitBuilder<'_> {
             commit,
             rng: settings.get_rng(),
             rewrite_source: Some(predecessor.clone()),
+            copies_set: false,
             sign_settings: settings.sign_settings(),
         }
     }
@@ -165,6 +175,22 @@ impl CommitBuilder<'_> {
         self
     }
 
+    pub fn copies(&self) -> &[CopyRecord] {
+        &self.commit.copies
+    }
+
+    /// Sets the files copied or moved from the parents. The records are sorted
+    /// by target path.
+    ///
+    /// If they aren't set, the records of the rewritten commit which no longer
+    /// hold after the parents or the tree changed are dropped.
+    pub fn set_copies(mut self, mut copies: Vec<CopyRecord>) -> Self {
+        copies.sort_by(|a, b| a.target.cmp(&b.target));
+        self.commit.copies = copies;
+        self.copies_set = true;
+        self
+    }
+
     pub fn sign_settings(&self) -> &SignSettings {
         &self.sign_settings
     }
@@ -180,6 +206,7 @@ impl CommitBuilder<'_> {
     }
 
     pub fn write(mut self) -> BackendResult<Commit> {
+        self.retain_valid_copies()?;
         let sign_settings = &self.sign_settings;
         let store = self.mut_repo.store();
 
@@ -205,4 +232,38 @@ impl CommitBuilder<'_> {
         }
         Ok(commit)
     }
+
+    /// Drops the copy records inherited from the rewritten commit whose target
+    /// no longer exists, or whose source no longer has the recorded contents
+    /// in the parents.
+    fn retain_valid_copies(&mut self) -> BackendResult<()> {
+        let Some(rewrite_source) = &self.rewrite_source else {
+            return Ok(());
+        };
+        if self.copies_set
+            || self.commit.copies.is_empty()
+            || (rewrite_source.parent_ids() == self.commit.parents
+                && rewrite_source.tree_id() == &self.commit.root_tree)
+        {
+            return Ok(());
+        }
+        let store = self.mut_repo.store();
+        let tree = store.get_root_tree(&self.commit.root_tree)?;
+        let parents: Vec<_> = self
+            .commit
+            .parents
+            .iter()
+            .map(|id| store.get_commit(id))
+            .try_collect()?;
+        let parent_tree = merge_commit_trees(&*self.mut_repo, &parents)?;
+        self.commit.copies.retain(|copy| {
+            let source_value = parent_tree.path_value(&copy.source);
+            tree.path_value(&copy.target).is_present()
+                && matches!(
+                    source_value.as_resolved(),
+                    Some(Some(TreeValue::File { id, .. })) if *id == copy.source_file
+                )
+        });
+        Ok(())
+    }
 }
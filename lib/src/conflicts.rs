@@ -16,6 +16,7 @@
 
 use std::io::{Read, Write};
 use std::iter::zip;
+use std::str::FromStr;
 
 use futures::StreamExt;
 use itertools::Itertools;
@@ -28,11 +29,130 @@ use crate::merge::{Merge, MergeBuilder, MergedTreeValue};
 use crate::repo_path::RepoPath;
 use crate::store::Store;
 
-const CONFLICT_START_LINE: &[u8] = b"<<<<<<<\n";
-const CONFLICT_END_LINE: &[u8] = b">>>>>>>\n";
-const CONFLICT_DIFF_LINE: &[u8] = b"%%%%%%%\n";
-const CONFLICT_MINUS_LINE: &[u8] = b"-------\n";
-const CONFLICT_PLUS_LINE: &[u8] = b"+++++++\n";
+/// Length of conflict markers unless configured otherwise.
+pub const DEFAULT_CONFLICT_MARKER_LEN: usize = 7;
+
+/// Format in which conflicts are materialized.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConflictMarkerStyle {
+    /// Shows a diff from a base to one side, and snapshots of the other sides.
+    #[default]
+    Diff,
+    /// Shows snapshots of both sides and of the base, like Git's "diff3" style.
+    /// Conflicts with more than two sides are shown in the `Diff` style.
+    Diff3,
+    /// Like `Diff3`, but lines added by both sides at the start or the end of a
+    /// conflict are moved out of it, like Git's "zdiff3" style.
+    Zdiff3,
+}
+
+impl FromStr for ConflictMarkerStyle {
+    type Err = config::ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "diff" => Ok(Self::Diff),
+            "diff3" | "git" => Ok(Self::Diff3),
+            "zdiff3" => Ok(Self::Zdiff3),
+            other => Err(config::ConfigError::Message(format!(
+                "unknown conflict marker style: {other}"
+            ))),
+        }
+    }
+}
+
+/// How conflict markers are written to and parsed from files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConflictMarkerOptions {
+    pub style: ConflictMarkerStyle,
+    /// Number of characters of each marker. Lines of other lengths are not
+    /// considered markers, so files which contain e.g. `<<<<<<<` lines can
+    /// use longer markers.
+    pub marker_len: usize,
+}
+
+impl Default for ConflictMarkerOptions {
+    fn default() -> Self {
+        ConflictMarkerOptions {
+            style: ConflictMarkerStyle::default(),
+            marker_len: DEFAULT_CONFLICT_MARKER_LEN,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ConflictMarker {
+    Start,
+    End,
+    Diff,
+    Minus,
+    Plus,
+    Base,
+    Separator,
+}
+
+impl ConflictMarker {
+    fn char(self) -> u8 {
+        match self {
+            ConflictMarker::Start => b'<',
+            ConflictMarker::End => b'>',
+            ConflictMarker::Diff => b'%',
+            ConflictMarker::Minus => b'-',
+            ConflictMarker::Plus => b'+',
+            ConflictMarker::Base => b'|',
+            ConflictMarker::Separator => b'=',
+        }
+    }
+
+    fn from_char(c: u8) -> Option<Self> {
+        [
+            ConflictMarker::Start,
+            ConflictMarker::End,
+            ConflictMarker::Diff,
+            ConflictMarker::Minus,
+            ConflictMarker::Plus,
+            ConflictMarker::Base,
+            ConflictMarker::Separator,
+        ]
+        .into_iter()
+        .find(|marker| marker.char() == c)
+    }
+
+    /// Whether the marker may be followed by a label, as Git does.
+    fn may_have_label(self) -> bool {
+        matches!(
+            self,
+            ConflictMarker::Start | ConflictMarker::End | ConflictMarker::Base
+        )
+    }
+}
+
+fn write_marker(
+    marker: ConflictMarker,
+    marker_len: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    output.write_all(&vec![marker.char(); marker_len])?;
+    output.write_all(b"\n")
+}
+
+/// Parses a line consisting of exactly `marker_len` marker characters.
+fn parse_marker(line: &[u8], marker_len: usize) -> Option<ConflictMarker> {
+    let line = line.strip_suffix(b"\n")?;
+    if line.len() < marker_len {
+        return None;
+    }
+    let (marker, label) = line.split_at(marker_len);
+    let kind = ConflictMarker::from_char(marker[0])?;
+    if !marker.iter().all(|&c| c == marker[0]) {
+        return None;
+    }
+    if label.is_empty() || (kind.may_have_label() && label.starts_with(b" ")) {
+        Some(kind)
+    } else {
+        None
+    }
+}
 
 fn write_diff_hunks(hunks: &[DiffHunk], file: &mut dyn Write) -> std::io::Result<()> {
     for hunk in hunks {
@@ -92,11 +212,12 @@ pub async fn materialize(
     conflict: &MergedTreeValue,
     store: &Store,
     path: &RepoPath,
+    options: ConflictMarkerOptions,
     output: &mut dyn Write,
 ) -> std::io::Result<()> {
     if let Some(file_merge) = conflict.to_file_merge() {
         let content = extract_as_single_hunk(&file_merge, store, path).await;
-        materialize_merge_result(&content, output)
+        materialize_merge_result(&content, options, output)
     } else {
         // Unless all terms are regular files, we can't do much better than to try to
         // describe the merge.
@@ -141,6 +262,7 @@ pub async fn materialize_tree_value(
     store: &Store,
     path: &RepoPath,
     value: MergedTreeValue,
+    options: ConflictMarkerOptions,
 ) -> BackendResult<MaterializedTreeValue> {
     match value.into_resolved() {
         Ok(None) => Ok(MaterializedTreeValue::Absent),
@@ -163,7 +285,7 @@ pub async fn materialize_tree_value(
         }
         Err(conflict) => {
             let mut contents = vec![];
-            materialize(&conflict, store, path, &mut contents)
+            materialize(&conflict, store, path, options, &mut contents)
                 .await
                 .expect("Failed to materialize conflict to in-memory buffer");
             Ok(MaterializedTreeValue::Conflict {
@@ -176,6 +298,7 @@ pub async fn materialize_tree_value(
 
 pub fn materialize_merge_result(
     single_hunk: &Merge<ContentHunk>,
+    options: ConflictMarkerOptions,
     output: &mut dyn Write,
 ) -> std::io::Result<()> {
     let slices = single_hunk.map(|content| content.0.as_slice());
@@ -188,54 +311,16 @@ pub fn materialize_merge_result(
             for hunk in hunks {
                 if let Some(content) = hunk.as_resolved() {
                     output.write_all(&content.0)?;
-                } else {
-                    output.write_all(CONFLICT_START_LINE)?;
-                    let mut add_index = 0;
-                    for left in hunk.removes() {
-                        let right1 = if let Some(right1) = hunk.get_add(add_index) {
-                            right1
-                        } else {
-                            // If we have no more positive terms, emit the remaining negative
-                            // terms as snapshots.
-                            output.write_all(CONFLICT_MINUS_LINE)?;
-                            output.write_all(&left.0)?;
-                            continue;
-                        };
-                        let diff1 = Diff::for_tokenizer(&[&left.0, &right1.0], &find_line_ranges)
-                            .hunks()
-                            .collect_vec();
-                        // Check if the diff against the next positive term is better. Since
-                        // we want to preserve the order of the terms, we don't match against
-                        // any later positive terms.
-                        if let Some(right2) = hunk.get_add(add_index + 1) {
-                            let diff2 =
-                                Diff::for_tokenizer(&[&left.0, &right2.0], &find_line_ranges)
-                                    .hunks()
-                                    .collect_vec();
-                            if diff_size(&diff2) < diff_size(&diff1) {
-                                // If the next positive term is a better match, emit
-                                // the current positive term as a snapshot and the next
-                                // positive term as a diff.
-                                output.write_all(CONFLICT_PLUS_LINE)?;
-                                output.write_all(&right1.0)?;
-                                output.write_all(CONFLICT_DIFF_LINE)?;
-                                write_diff_hunks(&diff2, output)?;
-                                add_index += 2;
-                                continue;
-                            }
-                        }
-
-                        output.write_all(CONFLICT_DIFF_LINE)?;
-                        write_diff_hunks(&diff1, output)?;
-                        add_index += 1;
-                    }
-
-                    //  Emit the remaining positive terms as snapshots.
-                    for slice in hunk.adds().skip(add_index) {
-                        output.write_all(CONFLICT_PLUS_LINE)?;
-                        output.write_all(&slice.0)?;
+                    continue;
+                }
+                match options.style {
+                    ConflictMarkerStyle::Diff3 | ConflictMarkerStyle::Zdiff3
+                        if hunk.num_sides() == 2 =>
+                    {
+                        let zealous = options.style == ConflictMarkerStyle::Zdiff3;
+                        materialize_git_style_hunk(&hunk, zealous, options.marker_len, output)?;
                     }
-                    output.write_all(CONFLICT_END_LINE)?;
+                    _ => materialize_diff_style_hunk(&hunk, options.marker_len, output)?,
                 }
             }
         }
@@ -243,6 +328,109 @@ pub fn materialize_merge_result(
     Ok(())
 }
 
+fn materialize_diff_style_hunk(
+    hunk: &Merge<ContentHunk>,
+    marker_len: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    write_marker(ConflictMarker::Start, marker_len, output)?;
+    let mut add_index = 0;
+    for left in hunk.removes() {
+        let right1 = if let Some(right1) = hunk.get_add(add_index) {
+            right1
+        } else {
+            // If we have no more positive terms, emit the remaining negative
+            // terms as snapshots.
+            write_marker(ConflictMarker::Minus, marker_len, output)?;
+            output.write_all(&left.0)?;
+            continue;
+        };
+        let diff1 = Diff::for_tokenizer(&[&left.0, &right1.0], &find_line_ranges)
+            .hunks()
+            .collect_vec();
+        // Check if the diff against the next positive term is better. Since
+        // we want to preserve the order of the terms, we don't match against
+        // any later positive terms.
+        if let Some(right2) = hunk.get_add(add_index + 1) {
+            let diff2 = Diff::for_tokenizer(&[&left.0, &right2.0], &find_line_ranges)
+                .hunks()
+                .collect_vec();
+            if diff_size(&diff2) < diff_size(&diff1) {
+                // If the next positive term is a better match, emit
+                // the current positive term as a snapshot and the next
+                // positive term as a diff.
+                write_marker(ConflictMarker::Plus, marker_len, output)?;
+                output.write_all(&right1.0)?;
+                write_marker(ConflictMarker::Diff, marker_len, output)?;
+                write_diff_hunks(&diff2, output)?;
+                add_index += 2;
+                continue;
+            }
+        }
+
+        write_marker(ConflictMarker::Diff, marker_len, output)?;
+        write_diff_hunks(&diff1, output)?;
+        add_index += 1;
+    }
+
+    //  Emit the remaining positive terms as snapshots.
+    for slice in hunk.adds().skip(add_index) {
+        write_marker(ConflictMarker::Plus, marker_len, output)?;
+        output.write_all(&slice.0)?;
+    }
+    write_marker(ConflictMarker::End, marker_len, output)
+}
+
+/// Writes a 2-sided conflict hunk with snapshots of both sides and of the base.
+/// If `zealous`, lines common to both sides at the start and the end of the
+/// hunk are written outside of the markers.
+fn materialize_git_style_hunk(
+    hunk: &Merge<ContentHunk>,
+    zealous: bool,
+    marker_len: usize,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    let left = hunk.get_add(0).unwrap().0.as_slice();
+    let base = hunk.get_remove(0).unwrap().0.as_slice();
+    let right = hunk.get_add(1).unwrap().0.as_slice();
+    let (prefix_len, suffix_len) = if zealous {
+        common_line_affix_lens(left, right)
+    } else {
+        (0, 0)
+    };
+    output.write_all(&left[..prefix_len])?;
+    write_marker(ConflictMarker::Start, marker_len, output)?;
+    output.write_all(&left[prefix_len..left.len() - suffix_len])?;
+    write_marker(ConflictMarker::Base, marker_len, output)?;
+    output.write_all(base)?;
+    write_marker(ConflictMarker::Separator, marker_len, output)?;
+    output.write_all(&right[prefix_len..right.len() - suffix_len])?;
+    write_marker(ConflictMarker::End, marker_len, output)?;
+    output.write_all(&left[left.len() - suffix_len..])
+}
+
+/// Returns the lengths in bytes of the common leading and trailing lines of
+/// `left` and `right`, which don't overlap.
+fn common_line_affix_lens(left: &[u8], right: &[u8]) -> (usize, usize) {
+    let left_lines = left.split_inclusive(|b| *b == b'\n').collect_vec();
+    let right_lines = right.split_inclusive(|b| *b == b'\n').collect_vec();
+    let prefix_count = zip(&left_lines, &right_lines)
+        .take_while(|(l, r)| l == r)
+        .count();
+    let suffix_count = zip(
+        left_lines[prefix_count..].iter().rev(),
+        right_lines[prefix_count..].iter().rev(),
+    )
+    .take_while(|(l, r)| l == r)
+    .count();
+    let prefix_len = left_lines[..prefix_count].iter().map(|l| l.len()).sum();
+    let suffix_len = left_lines[left_lines.len() - suffix_count..]
+        .iter()
+        .map(|l| l.len())
+        .sum();
+    (prefix_len, suffix_len)
+}
+
 fn diff_size(hunks: &[DiffHunk]) -> usize {
     hunks
         .iter()
@@ -256,25 +444,35 @@ fn diff_size(hunks: &[DiffHunk]) -> usize {
 /// Parses conflict markers from a slice. Returns None if there were no valid
 /// conflict markers. The caller has to provide the expected number of merge
 /// sides (adds). Conflict markers that are otherwise valid will be considered
-/// invalid if they don't have the expected arity.
+/// invalid if they don't have the expected arity. Both the `Diff` and the
+/// Git-like marker styles are recognized, but only markers of `marker_len`
+/// characters are.
 // TODO: "parse" is not usually the opposite of "materialize", so maybe we
 // should rename them to "serialize" and "deserialize"?
-pub fn parse_conflict(input: &[u8], num_sides: usize) -> Option<Vec<Merge<ContentHunk>>> {
+pub fn parse_conflict(
+    input: &[u8],
+    num_sides: usize,
+    marker_len: usize,
+) -> Option<Vec<Merge<ContentHunk>>> {
     if input.is_empty() {
         return None;
     }
     let mut hunks = vec![];
     let mut pos = 0;
     let mut resolved_start = 0;
+    // Start of the start marker line, and of the conflict body after it
     let mut conflict_start = None;
     for line in input.split_inclusive(|b| *b == b'\n') {
-        if line == CONFLICT_START_LINE {
-            conflict_start = Some(pos);
-        } else if conflict_start.is_some() && line == CONFLICT_END_LINE {
-            let conflict_body = &input[conflict_start.unwrap() + CONFLICT_START_LINE.len()..pos];
-            let hunk = parse_conflict_hunk(conflict_body);
+        let marker = parse_marker(line, marker_len);
+        if marker == Some(ConflictMarker::Start) {
+            conflict_start = Some((pos, pos + line.len()));
+        } else if let Some((start, body_start)) =
+            conflict_start.filter(|_| marker == Some(ConflictMarker::End))
+        {
+            let conflict_body = &input[body_start..pos];
+            let hunk = parse_conflict_hunk(conflict_body, marker_len);
             if hunk.num_sides() == num_sides {
-                let resolved_slice = &input[resolved_start..conflict_start.unwrap()];
+                let resolved_slice = &input[resolved_start..start];
                 if !resolved_slice.is_empty() {
                     hunks.push(Merge::resolved(ContentHunk(resolved_slice.to_vec())));
                 }
@@ -298,7 +496,20 @@ pub fn parse_conflict(input: &[u8], num_sides: usize) -> Option<Vec<Merge<Conten
     }
 }
 
-fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
+fn parse_conflict_hunk(input: &[u8], marker_len: usize) -> Merge<ContentHunk> {
+    let first_marker = input
+        .split_inclusive(|b| *b == b'\n')
+        .next()
+        .and_then(|line| parse_marker(line, marker_len));
+    match first_marker {
+        Some(ConflictMarker::Diff | ConflictMarker::Minus | ConflictMarker::Plus) => {
+            parse_diff_style_hunk(input, marker_len)
+        }
+        _ => parse_git_style_hunk(input, marker_len),
+    }
+}
+
+fn parse_diff_style_hunk(input: &[u8], marker_len: usize) -> Merge<ContentHunk> {
     enum State {
         Diff,
         Minus,
@@ -309,19 +520,19 @@ fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
     let mut removes = vec![];
     let mut adds = vec![];
     for line in input.split_inclusive(|b| *b == b'\n') {
-        match line {
-            CONFLICT_DIFF_LINE => {
+        match parse_marker(line, marker_len) {
+            Some(ConflictMarker::Diff) => {
                 state = State::Diff;
                 removes.push(ContentHunk(vec![]));
                 adds.push(ContentHunk(vec![]));
                 continue;
             }
-            CONFLICT_MINUS_LINE => {
+            Some(ConflictMarker::Minus) => {
                 state = State::Minus;
                 removes.push(ContentHunk(vec![]));
                 continue;
             }
-            CONFLICT_PLUS_LINE => {
+            Some(ConflictMarker::Plus) => {
                 state = State::Plus;
                 adds.push(ContentHunk(vec![]));
                 continue;
@@ -358,6 +569,32 @@ fn parse_conflict_hunk(input: &[u8]) -> Merge<ContentHunk> {
     Merge::from_removes_adds(removes, adds)
 }
 
+/// Parses a 2-sided conflict hunk in the style of Git's "diff3", i.e. the left
+/// side, the base, and the right side separated by `|||||||` and `=======`.
+fn parse_git_style_hunk(input: &[u8], marker_len: usize) -> Merge<ContentHunk> {
+    let mut sections = vec![ContentHunk(vec![])];
+    for line in input.split_inclusive(|b| *b == b'\n') {
+        let expected_marker = match sections.len() {
+            1 => ConflictMarker::Base,
+            2 => ConflictMarker::Separator,
+            _ => {
+                sections.last_mut().unwrap().0.extend_from_slice(line);
+                continue;
+            }
+        };
+        if parse_marker(line, marker_len) == Some(expected_marker) {
+            sections.push(ContentHunk(vec![]));
+        } else {
+            sections.last_mut().unwrap().0.extend_from_slice(line);
+        }
+    }
+    let Ok([left, base, right]) = <[ContentHunk; 3]>::try_from(sections) else {
+        // Doesn't look like a conflict
+        return Merge::resolved(ContentHunk(vec![]));
+    };
+    Merge::from_removes_adds(vec![base], vec![left, right])
+}
+
 /// Parses conflict markers in `content` and returns an updated version of
 /// `file_ids` with the new contents. If no (valid) conflict markers remain, a
 /// single resolves `FileId` will be returned.
@@ -366,6 +603,7 @@ pub async fn update_from_content(
     store: &Store,
     path: &RepoPath,
     content: &[u8],
+    options: ConflictMarkerOptions,
 ) -> BackendResult<Merge<Option<FileId>>> {
     // First check if the new content is unchanged compared to the old content. If
     // it is, we don't need parse the content or write any new objects to the
@@ -374,12 +612,12 @@ pub async fn update_from_content(
     // copy.
     let mut old_content = Vec::with_capacity(content.len());
     let merge_hunk = extract_as_single_hunk(file_ids, store, path).await;
-    materialize_merge_result(&merge_hunk, &mut old_content).unwrap();
+    materialize_merge_result(&merge_hunk, options, &mut old_content).unwrap();
     if content == old_content {
         return Ok(file_ids.clone());
     }
 
-    let Some(hunks) = parse_conflict(content, file_ids.num_sides()) else {
+    let Some(hunks) = parse_conflict(content, file_ids.num_sides(), options.marker_len) else {
         // Either there are no self markers of they don't have the expected arity
         let file_id = store.write_file(path, &mut &content[..])?;
         return Ok(Merge::normal(file_id));
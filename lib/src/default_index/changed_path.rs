@@ -0,0 +1,188 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bloom filters of the paths changed by commits.
+//!
+//! The filters are similar to git's changed-path Bloom filters. Each filter
+//! records the paths changed by a commit compared to its parent, plus all of
+//! their ancestor directories, so a path-limited query can skip the commits
+//! which definitely didn't change the path without diffing their trees.
+
+#![allow(missing_docs)]
+
+use std::collections::HashSet;
+
+use crate::commit::Commit;
+use crate::matchers::EverythingMatcher;
+use crate::repo_path::{RepoPath, RepoPathBuf};
+
+/// Number of bits allocated to each path in the filter.
+const BITS_PER_PATH: usize = 10;
+
+/// Number of bits set for each path in the filter.
+const NUM_HASHES: u64 = 7;
+
+/// If a commit changes more paths than this, a filter which matches all paths
+/// is stored instead.
+const MAX_CHANGED_PATHS: usize = 512;
+
+/// Borrowed changed-path Bloom filter of a commit.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ChangedPathFilter<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ChangedPathFilter<'a> {
+    /// Wraps the serialized `data`, which must not be empty.
+    pub fn new(data: &'a [u8]) -> Self {
+        assert!(!data.is_empty());
+        ChangedPathFilter { data }
+    }
+
+    /// Returns the serialized filter.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns `false` if the commit definitely didn't change the `path` or
+    /// any path under it.
+    pub fn might_contain(&self, path: &RepoPath) -> bool {
+        let num_bits = (self.data.len() * 8) as u64;
+        bit_positions(path, num_bits).all(|bit| self.data[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+}
+
+/// Builds the serialized filter containing the given `paths` and all of their
+/// ancestor directories.
+pub(super) fn build_changed_path_filter<'a>(
+    paths: impl IntoIterator<Item = &'a RepoPath>,
+) -> Vec<u8> {
+    let mut entries: HashSet<&RepoPath> = HashSet::new();
+    for path in paths {
+        let mut maybe_path = Some(path);
+        while let Some(path) = maybe_path.filter(|path| !path.is_root()) {
+            if !entries.insert(path) {
+                break;
+            }
+            maybe_path = path.parent();
+        }
+    }
+    if entries.len() > MAX_CHANGED_PATHS {
+        return vec![0xff];
+    }
+    let num_bytes = usize::max(1, (entries.len() * BITS_PER_PATH).div_ceil(8));
+    let mut data = vec![0; num_bytes];
+    let num_bits = (num_bytes * 8) as u64;
+    for path in entries {
+        for bit in bit_positions(path, num_bits) {
+            data[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+    data
+}
+
+/// Builds the serialized filter of the paths changed by the `commit`.
+///
+/// Returns an empty vector if the filter can't be built cheaply, in which case
+/// the commit should be assumed to change any path.
+pub(super) fn changed_path_filter_for_commit(commit: &Commit) -> Vec<u8> {
+    // Merge commits would have to be diffed against the merged parent trees,
+    // which is about as expensive as evaluating the query.
+    let [parent_id] = commit.parent_ids() else {
+        return vec![];
+    };
+    let Ok(parent) = commit.store().get_commit(parent_id) else {
+        return vec![];
+    };
+    if parent.tree_id() == commit.tree_id() {
+        return build_changed_path_filter([]);
+    }
+    let (Ok(from_tree), Ok(to_tree)) = (parent.tree(), commit.tree()) else {
+        return vec![];
+    };
+    let Ok(changed_paths) = from_tree
+        .diff(&to_tree, &EverythingMatcher)
+        .take(MAX_CHANGED_PATHS + 1)
+        .map(|(path, diff)| diff.map(|_| path))
+        .collect::<Result<Vec<RepoPathBuf>, _>>()
+    else {
+        return vec![];
+    };
+    build_changed_path_filter(changed_paths.iter().map(AsRef::as_ref))
+}
+
+/// Iterates the bit positions of the `path`, using double hashing.
+fn bit_positions(path: &RepoPath, num_bits: u64) -> impl Iterator<Item = usize> {
+    let bytes = path.as_internal_file_string().as_bytes();
+    let hash1 = fnv1a_64(0xcbf2_9ce4_8422_2325, bytes);
+    let hash2 = fnv1a_64(0x8422_2325_cbf2_9ce4, bytes) | 1;
+    (0..NUM_HASHES).map(move |i| {
+        let hash = hash1.wrapping_add(i.wrapping_mul(hash2));
+        (hash % num_bits) as usize
+    })
+}
+
+fn fnv1a_64(seed: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(seed, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
+    #[test]
+    fn test_changed_path_filter_contains_paths_and_ancestors() {
+        let data = build_changed_path_filter([repo_path("dir/sub/file"), repo_path("other")]);
+        let filter = ChangedPathFilter::new(&data);
+        assert!(filter.might_contain(repo_path("dir/sub/file")));
+        assert!(filter.might_contain(repo_path("dir/sub")));
+        assert!(filter.might_contain(repo_path("dir")));
+        assert!(filter.might_contain(repo_path("other")));
+    }
+
+    #[test]
+    fn test_changed_path_filter_rejects_most_other_paths() {
+        let paths = (0..100)
+            .map(|i| RepoPathBuf::from_internal_string(format!("dir/file{i}")))
+            .collect::<Vec<_>>();
+        let data = build_changed_path_filter(paths.iter().map(AsRef::as_ref));
+        let filter = ChangedPathFilter::new(&data);
+        assert!(paths.iter().all(|path| filter.might_contain(path)));
+        let false_positives = (0..1000)
+            .map(|i| RepoPathBuf::from_internal_string(format!("other/file{i}")))
+            .filter(|path| filter.might_contain(path))
+            .count();
+        assert!(false_positives < 50, "{false_positives} false positives");
+    }
+
+    #[test]
+    fn test_changed_path_filter_empty_and_too_large() {
+        let data = build_changed_path_filter([]);
+        assert_eq!(data, vec![0]);
+        assert!(!ChangedPathFilter::new(&data).might_contain(repo_path("file")));
+
+        let paths = (0..=MAX_CHANGED_PATHS)
+            .map(|i| RepoPathBuf::from_internal_string(format!("file{i}")))
+            .collect::<Vec<_>>();
+        let data = build_changed_path_filter(paths.iter().map(AsRef::as_ref));
+        assert_eq!(data, vec![0xff]);
+        assert!(ChangedPathFilter::new(&data).might_contain(repo_path("unrelated")));
+    }
+}
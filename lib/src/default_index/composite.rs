@@ -22,6 +22,7 @@ use std::sync::{Arc, Mutex};
 use itertools::Itertools;
 use ref_cast::{ref_cast_custom, RefCastCustom};
 
+use super::changed_path::ChangedPathFilter;
 use super::entry::{
     IndexEntry, IndexPosition, IndexPositionByGeneration, LocalPosition, SmallIndexPositionsVec,
     SmallLocalPositionsVec,
@@ -45,6 +46,14 @@ pub(super) trait IndexSegment: Send + Sync {
 
     fn name(&self) -> Option<String>;
 
+    /// Size of the segment file in bytes, or `None` if the segment hasn't been
+    /// saved.
+    fn file_size(&self) -> Option<u64>;
+
+    /// Returns true if changed-path filters are computed for the commits added
+    /// to this segment.
+    fn has_changed_path_filters(&self) -> bool;
+
     fn commit_id_to_pos(&self, commit_id: &CommitId) -> Option<LocalPosition>;
 
     /// Suppose the given `commit_id` exists, returns the previous and next
@@ -68,6 +77,10 @@ pub(super) trait IndexSegment: Send + Sync {
 
     fn generation_number(&self, local_pos: LocalPosition) -> u32;
 
+    /// Returns the changed-path filter of the entry, or `None` if there's no
+    /// filter for the entry.
+    fn changed_path_filter(&self, local_pos: LocalPosition) -> Option<ChangedPathFilter<'_>>;
+
     fn commit_id(&self, local_pos: LocalPosition) -> CommitId;
 
     fn change_id(&self, local_pos: LocalPosition) -> ChangeId;
@@ -153,6 +166,7 @@ impl CompositeIndex {
             .map(|segment| IndexLevelStats {
                 num_commits: segment.num_local_commits(),
                 name: segment.name(),
+                file_size: segment.file_size(),
             })
             .collect_vec();
         levels.reverse();
@@ -557,6 +571,7 @@ impl<I: AsCompositeIndex + Send + Sync> ChangeIdIndex for ChangeIdIndexImpl<I> {
 pub struct IndexLevelStats {
     pub num_commits: u32,
     pub name: Option<String>,
+    pub file_size: Option<u64>,
 }
 
 pub struct IndexStats {
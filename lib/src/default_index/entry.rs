@@ -19,6 +19,7 @@ use std::hash::{Hash, Hasher};
 
 use smallvec::SmallVec;
 
+use super::changed_path::ChangedPathFilter;
 use super::composite::{CompositeIndex, DynIndexSegment};
 use crate::backend::{ChangeId, CommitId};
 use crate::object_id::ObjectId;
@@ -102,6 +103,10 @@ impl<'a> IndexEntry<'a> {
         self.source.change_id(self.local_pos)
     }
 
+    pub(super) fn changed_path_filter(&self) -> Option<ChangedPathFilter<'a>> {
+        self.source.changed_path_filter(self.local_pos)
+    }
+
     pub fn num_parents(&self) -> u32 {
         self.source.num_parents(self.local_pos)
     }
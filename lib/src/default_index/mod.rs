@@ -21,6 +21,7 @@
 
 #![allow(missing_docs)]
 
+mod changed_path;
 mod composite;
 mod entry;
 mod mutable;
@@ -44,6 +45,7 @@ mod tests {
     use smallvec::smallvec_inline;
     use test_case::test_case;
 
+    use super::changed_path::build_changed_path_filter;
     use super::composite::{DynIndexSegment, IndexSegment};
     use super::entry::SmallIndexPositionsVec;
     use super::mutable::MutableIndexSegment;
@@ -52,6 +54,7 @@ mod tests {
     use crate::default_index::entry::{LocalPosition, SmallLocalPositionsVec};
     use crate::index::Index;
     use crate::object_id::{HexPrefix, ObjectId, PrefixResolution};
+    use crate::repo_path::RepoPath;
 
     /// Generator of unique 16-byte CommitId excluding root id
     fn commit_id_generator() -> impl FnMut() -> CommitId {
@@ -132,6 +135,57 @@ mod tests {
         assert_eq!(entry.parents().len(), 0);
     }
 
+    #[test_case(false; "memory")]
+    #[test_case(true; "file")]
+    fn index_changed_path_filters(on_disk: bool) {
+        let temp_dir = testutils::new_temp_dir();
+        let mut new_change_id = change_id_generator();
+        let mut mutable_segment = MutableIndexSegment::full(3, 16);
+        mutable_segment.enable_changed_path_filters();
+        let id_0 = CommitId::from_hex("000000");
+        let id_1 = CommitId::from_hex("111111");
+        let id_2 = CommitId::from_hex("222222");
+        let filter_1 = build_changed_path_filter([RepoPath::from_internal_string("dir/file")]);
+        let filter_2 = build_changed_path_filter([]);
+        mutable_segment.add_commit_entry(id_0.clone(), new_change_id(), &[], vec![]);
+        mutable_segment.add_commit_entry(
+            id_1.clone(),
+            new_change_id(),
+            &[id_0.clone()],
+            filter_1.clone(),
+        );
+        mutable_segment.add_commit_entry(
+            id_2.clone(),
+            new_change_id(),
+            &[id_1.clone()],
+            filter_2.clone(),
+        );
+        let index_segment: Box<DynIndexSegment> = if on_disk {
+            let saved_index = mutable_segment.save_in(temp_dir.path()).unwrap();
+            // Segments added on top also compute filters
+            assert!(
+                MutableIndexSegment::incremental(saved_index.clone()).has_changed_path_filters()
+            );
+            Box::new(Arc::try_unwrap(saved_index).unwrap())
+        } else {
+            Box::new(mutable_segment)
+        };
+        let index = CompositeIndex::new(index_segment.as_ref());
+        assert!(index_segment.has_changed_path_filters());
+
+        let entry_0 = index.entry_by_id(&id_0).unwrap();
+        assert!(entry_0.changed_path_filter().is_none());
+        let entry_1 = index.entry_by_id(&id_1).unwrap();
+        let filter = entry_1.changed_path_filter().unwrap();
+        assert_eq!(filter.as_bytes(), filter_1);
+        assert!(filter.might_contain(RepoPath::from_internal_string("dir")));
+        assert!(filter.might_contain(RepoPath::from_internal_string("dir/file")));
+        let entry_2 = index.entry_by_id(&id_2).unwrap();
+        let filter = entry_2.changed_path_filter().unwrap();
+        assert_eq!(filter.as_bytes(), filter_2);
+        assert!(!filter.might_contain(RepoPath::from_internal_string("dir")));
+    }
+
     #[test]
     #[should_panic(expected = "parent commit is not indexed")]
     fn index_missing_parent_commit() {
@@ -29,6 +29,7 @@ use itertools::Itertools;
 use smallvec::{smallvec, SmallVec};
 use tempfile::NamedTempFile;
 
+use super::changed_path::{changed_path_filter_for_commit, ChangedPathFilter};
 use super::composite::{
     AsCompositeIndex, ChangeIdIndexImpl, CompositeIndex, DynIndexSegment, IndexSegment,
 };
@@ -50,6 +51,8 @@ struct MutableGraphEntry {
     change_id: ChangeId,
     generation_number: u32,
     parent_positions: SmallIndexPositionsVec,
+    // Empty if there's no filter for this entry
+    changed_path_filter: Vec<u8>,
 }
 
 pub(super) struct MutableIndexSegment {
@@ -60,6 +63,7 @@ pub(super) struct MutableIndexSegment {
     graph: Vec<MutableGraphEntry>,
     commit_lookup: BTreeMap<CommitId, LocalPosition>,
     change_lookup: BTreeMap<ChangeId, SmallLocalPositionsVec>,
+    changed_path_filters: bool,
 }
 
 impl MutableIndexSegment {
@@ -72,6 +76,7 @@ impl MutableIndexSegment {
             graph: vec![],
             commit_lookup: BTreeMap::new(),
             change_lookup: BTreeMap::new(),
+            changed_path_filters: false,
         }
     }
 
@@ -79,6 +84,7 @@ impl MutableIndexSegment {
         let num_parent_commits = parent_file.as_composite().num_commits();
         let commit_id_length = parent_file.commit_id_length();
         let change_id_length = parent_file.change_id_length();
+        let changed_path_filters = parent_file.has_changed_path_filters();
         Self {
             parent_file: Some(parent_file),
             num_parent_commits,
@@ -87,6 +93,7 @@ impl MutableIndexSegment {
             graph: vec![],
             commit_lookup: BTreeMap::new(),
             change_lookup: BTreeMap::new(),
+            changed_path_filters,
         }
     }
 
@@ -94,19 +101,43 @@ impl MutableIndexSegment {
         CompositeIndex::new(self)
     }
 
+    pub(super) fn enable_changed_path_filters(&mut self) {
+        self.changed_path_filters = true;
+    }
+
     pub(super) fn add_commit(&mut self, commit: &Commit) {
-        self.add_commit_data(
+        if self.as_composite().has_id(commit.id()) {
+            return;
+        }
+        let changed_path_filter = if self.changed_path_filters {
+            changed_path_filter_for_commit(commit)
+        } else {
+            vec![]
+        };
+        self.add_commit_entry(
             commit.id().clone(),
             commit.change_id().clone(),
             commit.parent_ids(),
+            changed_path_filter,
         );
     }
 
+    #[cfg(test)]
     pub(super) fn add_commit_data(
         &mut self,
         commit_id: CommitId,
         change_id: ChangeId,
         parent_ids: &[CommitId],
+    ) {
+        self.add_commit_entry(commit_id, change_id, parent_ids, vec![]);
+    }
+
+    pub(super) fn add_commit_entry(
+        &mut self,
+        commit_id: CommitId,
+        change_id: ChangeId,
+        parent_ids: &[CommitId],
+        changed_path_filter: Vec<u8>,
     ) {
         if self.as_composite().has_id(&commit_id) {
             return;
@@ -116,6 +147,7 @@ impl MutableIndexSegment {
             change_id,
             generation_number: 0,
             parent_positions: SmallVec::new(),
+            changed_path_filter,
         };
         for parent_id in parent_ids {
             let parent_entry = self
@@ -144,7 +176,15 @@ impl MutableIndexSegment {
         for pos in other_segment.num_parent_commits()..other.num_commits() {
             let entry = other.entry_by_pos(IndexPosition(pos));
             let parent_ids = entry.parents().map(|entry| entry.commit_id()).collect_vec();
-            self.add_commit_data(entry.commit_id(), entry.change_id(), &parent_ids);
+            let changed_path_filter = entry
+                .changed_path_filter()
+                .map_or_else(Vec::new, |filter| filter.as_bytes().to_vec());
+            self.add_commit_entry(
+                entry.commit_id(),
+                entry.change_id(),
+                &parent_ids,
+                changed_path_filter,
+            );
         }
     }
 
@@ -210,6 +250,9 @@ impl MutableIndexSegment {
         buf.extend(0_u32.to_le_bytes());
         let change_overflow_offset = buf.len();
         buf.extend(0_u32.to_le_bytes());
+        buf.extend(u32::from(self.changed_path_filters).to_le_bytes());
+        let changed_path_filters_size_offset = buf.len();
+        buf.extend(0_u32.to_le_bytes());
 
         // Positions of change ids in the sorted table
         let change_id_pos_map: HashMap<&ChangeId, u32> = self
@@ -294,6 +337,20 @@ impl MutableIndexSegment {
         for LocalPosition(pos) in change_overflow {
             buf.extend(pos.to_le_bytes());
         }
+
+        if self.changed_path_filters {
+            let mut changed_path_filters_size = 0_u32;
+            for entry in &self.graph {
+                changed_path_filters_size +=
+                    u32::try_from(entry.changed_path_filter.len()).unwrap();
+                buf.extend(changed_path_filters_size.to_le_bytes());
+            }
+            buf[changed_path_filters_size_offset..][..4]
+                .copy_from_slice(&changed_path_filters_size.to_le_bytes());
+            for entry in &self.graph {
+                buf.extend_from_slice(&entry.changed_path_filter);
+            }
+        }
     }
 
     /// If the MutableIndex has more than half the commits of its parent
@@ -323,6 +380,7 @@ impl MutableIndexSegment {
         } else {
             MutableIndexSegment::full(self.commit_id_length, self.change_id_length)
         };
+        squashed.changed_path_filters = self.changed_path_filters;
         for parent_file in files_to_squash.iter().rev() {
             squashed.add_commits_from(parent_file.as_ref());
         }
@@ -352,6 +410,7 @@ impl MutableIndexSegment {
 
         Ok(ReadonlyIndexSegment::load_with_parent_file(
             &mut &buf[local_entries_offset..],
+            buf.len() as u64,
             index_file_id_hex,
             self.parent_file,
             self.commit_id_length,
@@ -378,6 +437,14 @@ impl IndexSegment for MutableIndexSegment {
         None
     }
 
+    fn file_size(&self) -> Option<u64> {
+        None
+    }
+
+    fn has_changed_path_filters(&self) -> bool {
+        self.changed_path_filters
+    }
+
     fn commit_id_to_pos(&self, commit_id: &CommitId) -> Option<LocalPosition> {
         self.commit_lookup.get(commit_id).copied()
     }
@@ -416,6 +483,11 @@ impl IndexSegment for MutableIndexSegment {
         self.graph[local_pos.0 as usize].generation_number
     }
 
+    fn changed_path_filter(&self, local_pos: LocalPosition) -> Option<ChangedPathFilter<'_>> {
+        let data = &self.graph[local_pos.0 as usize].changed_path_filter;
+        (!data.is_empty()).then(|| ChangedPathFilter::new(data))
+    }
+
     fn commit_id(&self, local_pos: LocalPosition) -> CommitId {
         self.graph[local_pos.0 as usize].commit_id.clone()
     }
@@ -451,6 +523,12 @@ impl DefaultMutableIndex {
         DefaultMutableIndex(mutable_segment)
     }
 
+    /// Computes changed-path filters for the commits added to this index and
+    /// to the indexes derived from it.
+    pub(super) fn enable_changed_path_filters(&mut self) {
+        self.0.enable_changed_path_filters();
+    }
+
     #[cfg(test)]
     pub(crate) fn add_commit_data(
         &mut self,
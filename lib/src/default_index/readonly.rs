@@ -26,6 +26,7 @@ use std::sync::Arc;
 use smallvec::smallvec;
 use thiserror::Error;
 
+use super::changed_path::ChangedPathFilter;
 use super::composite::{AsCompositeIndex, ChangeIdIndexImpl, CompositeIndex, IndexSegment};
 use super::entry::{IndexPosition, LocalPosition, SmallIndexPositionsVec, SmallLocalPositionsVec};
 use super::mutable::DefaultMutableIndex;
@@ -87,7 +88,7 @@ impl ReadonlyIndexLoadError {
 }
 
 /// Current format version of the index segment file.
-pub(crate) const INDEX_SEGMENT_FILE_FORMAT_VERSION: u32 = 6;
+pub(crate) const INDEX_SEGMENT_FILE_FORMAT_VERSION: u32 = 7;
 
 /// If set, the value is stored in the overflow table.
 pub(crate) const OVERFLOW_FLAG: u32 = 0x8000_0000;
@@ -169,6 +170,8 @@ impl CommitGraphEntry<'_> {
 /// u32: number of local change ids
 /// u32: number of overflow parent entries
 /// u32: number of overflow change id positions
+/// u32: 1 if changed-path filters are stored, 0 otherwise
+/// u32: size of the changed-path filters in bytes
 /// for each entry, in some topological order with parents first:
 ///   u32: generation number
 ///   if number of parents <= 2:
@@ -194,6 +197,10 @@ impl CommitGraphEntry<'_> {
 ///   u32: global index position
 /// for each overflow change id entry:
 ///   u32: local position in the graph entries table
+/// if changed-path filters are stored:
+///   for each entry, in the graph entries order:
+///     u32: end offset of the entry's filter (an empty filter means no filter)
+///   <size of the changed-path filters number of bytes>: filters
 /// ```
 ///
 /// Note that u32 fields are 4-byte aligned so long as the parent file name
@@ -217,7 +224,11 @@ pub(super) struct ReadonlyIndexSegment {
     change_pos_table_base: usize,
     parent_overflow_base: usize,
     change_overflow_base: usize,
+    // Present if changed-path filters are stored
+    changed_path_filter_bases: Option<(usize, usize)>,
     data: Vec<u8>,
+    // Size of the whole file, including the header
+    file_size: u64,
 }
 
 impl Debug for ReadonlyIndexSegment {
@@ -239,12 +250,24 @@ impl ReadonlyIndexSegment {
     ) -> Result<Arc<ReadonlyIndexSegment>, ReadonlyIndexLoadError> {
         let mut file = File::open(dir.join(&name))
             .map_err(|err| ReadonlyIndexLoadError::from_io_err(&name, err))?;
-        Self::load_from(&mut file, dir, name, commit_id_length, change_id_length)
+        let file_size = file
+            .metadata()
+            .map_err(|err| ReadonlyIndexLoadError::from_io_err(&name, err))?
+            .len();
+        Self::load_from(
+            &mut file,
+            file_size,
+            dir,
+            name,
+            commit_id_length,
+            change_id_length,
+        )
     }
 
     /// Loads both parent segments and local entries from the given `file`.
     pub(super) fn load_from(
         file: &mut dyn Read,
+        file_size: u64,
         dir: &Path,
         name: String,
         commit_id_length: usize,
@@ -283,6 +306,7 @@ impl ReadonlyIndexSegment {
         };
         Self::load_with_parent_file(
             file,
+            file_size,
             name,
             maybe_parent_file,
             commit_id_length,
@@ -291,9 +315,11 @@ impl ReadonlyIndexSegment {
     }
 
     /// Loads local entries from the given `file`, returns new segment linked to
-    /// the given `parent_file`.
+    /// the given `parent_file`. `file_size` is the size of the whole segment
+    /// file.
     pub(super) fn load_with_parent_file(
         file: &mut dyn Read,
+        file_size: u64,
         name: String,
         parent_file: Option<Arc<ReadonlyIndexSegment>>,
         commit_id_length: usize,
@@ -312,6 +338,8 @@ impl ReadonlyIndexSegment {
         let num_local_change_ids = read_u32(file)?;
         let num_parent_overflow_entries = read_u32(file)?;
         let num_change_overflow_entries = read_u32(file)?;
+        let has_changed_path_filters = read_u32(file)? != 0;
+        let changed_path_filters_size = read_u32(file)?;
         let mut data = vec![];
         file.read_to_end(&mut data).map_err(from_io_err)?;
 
@@ -322,6 +350,11 @@ impl ReadonlyIndexSegment {
         let change_pos_table_size = (num_local_change_ids as usize) * 4;
         let parent_overflow_size = (num_parent_overflow_entries as usize) * 4;
         let change_overflow_size = (num_change_overflow_entries as usize) * 4;
+        let changed_path_filter_offsets_size = if has_changed_path_filters {
+            (num_local_commits as usize) * 4
+        } else {
+            0
+        };
 
         let graph_base = 0;
         let commit_lookup_base = graph_base + graph_size;
@@ -329,7 +362,10 @@ impl ReadonlyIndexSegment {
         let change_pos_table_base = change_id_table_base + change_id_table_size;
         let parent_overflow_base = change_pos_table_base + change_pos_table_size;
         let change_overflow_base = parent_overflow_base + parent_overflow_size;
-        let expected_size = change_overflow_base + change_overflow_size;
+        let changed_path_filter_offsets_base = change_overflow_base + change_overflow_size;
+        let changed_path_filter_data_base =
+            changed_path_filter_offsets_base + changed_path_filter_offsets_size;
+        let expected_size = changed_path_filter_data_base + changed_path_filters_size as usize;
 
         if data.len() != expected_size {
             return Err(ReadonlyIndexLoadError::invalid_data(
@@ -352,7 +388,12 @@ impl ReadonlyIndexSegment {
             change_pos_table_base,
             parent_overflow_base,
             change_overflow_base,
+            changed_path_filter_bases: has_changed_path_filters.then_some((
+                changed_path_filter_offsets_base,
+                changed_path_filter_data_base,
+            )),
             data,
+            file_size,
         }))
     }
 
@@ -416,7 +457,10 @@ impl ReadonlyIndexSegment {
 
     /// Scans graph entry positions stored in the overflow change ids table.
     fn overflow_changes_from(&self, overflow_pos: u32) -> impl Iterator<Item = LocalPosition> + '_ {
-        let table = &self.data[self.change_overflow_base..];
+        let end = self
+            .changed_path_filter_bases
+            .map_or(self.data.len(), |(offsets_base, _)| offsets_base);
+        let table = &self.data[self.change_overflow_base..end];
         let offset = (overflow_pos as usize) * 4;
         table[offset..]
             .chunks_exact(4)
@@ -458,6 +502,14 @@ impl IndexSegment for ReadonlyIndexSegment {
         Some(self.name.clone())
     }
 
+    fn file_size(&self) -> Option<u64> {
+        Some(self.file_size)
+    }
+
+    fn has_changed_path_filters(&self) -> bool {
+        self.changed_path_filter_bases.is_some()
+    }
+
     fn commit_id_to_pos(&self, commit_id: &CommitId) -> Option<LocalPosition> {
         self.commit_id_byte_prefix_to_lookup_pos(commit_id.as_bytes())
             .ok()
@@ -532,6 +584,18 @@ impl IndexSegment for ReadonlyIndexSegment {
         self.graph_entry(local_pos).generation_number()
     }
 
+    fn changed_path_filter(&self, local_pos: LocalPosition) -> Option<ChangedPathFilter<'_>> {
+        let (offsets_base, data_base) = self.changed_path_filter_bases?;
+        let offsets = &self.data[offsets_base..data_base];
+        let read_offset =
+            |pos: usize| u32::from_le_bytes(offsets[pos * 4..][..4].try_into().unwrap()) as usize;
+        let pos = local_pos.0 as usize;
+        let start = if pos == 0 { 0 } else { read_offset(pos - 1) };
+        let end = read_offset(pos);
+        (start < end)
+            .then(|| ChangedPathFilter::new(&self.data[data_base + start..data_base + end]))
+    }
+
     fn commit_id(&self, local_pos: LocalPosition) -> CommitId {
         self.graph_entry(local_pos).commit_id()
     }
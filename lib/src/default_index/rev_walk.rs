@@ -14,7 +14,7 @@
 
 #![allow(missing_docs)]
 
-use std::cmp::{max, Reverse};
+use std::cmp::{max, min, Reverse};
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::iter::{Fuse, FusedIterator};
 use std::ops::Range;
@@ -426,18 +426,36 @@ impl<'a> RevWalkBuilder<'a> {
     ///
     /// Use this if you are only interested in descendants of the given roots.
     /// The caller still needs to filter out unwanted entries.
+    ///
+    /// Ancestors whose index position or generation number is smaller than
+    /// the ones of all roots can't be descendants of the roots, so they aren't
+    /// visited. The generation number prunes the walk of long side branches
+    /// which were forked before the roots but indexed after them.
     pub fn ancestors_until_roots(
         self,
         root_positions: impl IntoIterator<Item = IndexPosition>,
-    ) -> RevWalkAncestors<'a> {
-        // We can also make it stop visiting based on the generation number. Maybe
-        // it will perform better for unbalanced branchy history.
-        // https://github.com/martinvonz/jj/pull/1492#discussion_r1160678325
-        let min_pos = root_positions
-            .into_iter()
-            .min()
-            .unwrap_or(IndexPosition::MAX);
-        self.ancestors_with_min_pos(min_pos)
+    ) -> RevWalkAncestorsUntilRoots<'a> {
+        let index = self.index;
+        let mut min_pos = IndexPosition::MAX;
+        let mut min_generation = u32::MAX;
+        for pos in root_positions {
+            min_pos = min(min_pos, pos);
+            min_generation = min(min_generation, index.entry_by_pos(pos).generation_number());
+        }
+        let mut queue = RevWalkQueue::with_min_pos(min_pos);
+        for pos in self.wanted {
+            if pos >= min_pos && index.entry_by_pos(pos).generation_number() >= min_generation {
+                queue.push_wanted(pos, ());
+            }
+        }
+        queue.extend_unwanted(self.unwanted);
+        RevWalkBorrowedIndexIter {
+            index,
+            walk: RevWalkMinGenerationImpl {
+                queue,
+                min_generation,
+            },
+        }
     }
 
     /// Fully consumes ancestors and walks back from the `root_positions`.
@@ -535,6 +553,49 @@ impl<I: RevWalkIndex + ?Sized> RevWalk<I> for RevWalkImpl<I::Position> {
     }
 }
 
+pub(super) type RevWalkAncestorsUntilRoots<'a> =
+    RevWalkBorrowedIndexIter<'a, CompositeIndex, RevWalkMinGenerationImpl>;
+
+/// Walks ancestors, skipping the ones of lower generation than
+/// `min_generation`, and therefore all of their ancestors.
+#[derive(Clone)]
+#[must_use]
+pub(super) struct RevWalkMinGenerationImpl {
+    queue: RevWalkQueue<IndexPosition, ()>,
+    min_generation: u32,
+}
+
+impl RevWalk<CompositeIndex> for RevWalkMinGenerationImpl {
+    type Item = IndexPosition;
+
+    fn next(&mut self, index: &CompositeIndex) -> Option<Self::Item> {
+        while let Some(item) = self.queue.pop() {
+            self.queue.skip_while_eq(&item.pos);
+            if item.is_wanted() {
+                for parent in index.entry_by_pos(item.pos).parents() {
+                    if parent.generation_number() >= self.min_generation {
+                        self.queue.push_wanted(parent.position(), ());
+                    }
+                }
+                return Some(item.pos);
+            } else if self.queue.items.len() == self.queue.unwanted_count {
+                // No more wanted entries to walk
+                debug_assert!(!self.queue.items.iter().any(|x| x.is_wanted()));
+                return None;
+            } else {
+                self.queue
+                    .extend_unwanted(index.adjacent_positions(item.pos));
+            }
+        }
+
+        debug_assert_eq!(
+            self.queue.items.iter().filter(|x| !x.is_wanted()).count(),
+            self.queue.unwanted_count
+        );
+        None
+    }
+}
+
 pub(super) type RevWalkAncestorsGenerationRange<'a> =
     RevWalkBorrowedIndexIter<'a, CompositeIndex, RevWalkGenerationRangeImpl<IndexPosition>>;
 pub(super) type RevWalkDescendantsGenerationRange = RevWalkOwnedIndexIter<
@@ -956,9 +1017,8 @@ mod tests {
         assert_eq!(iter.next().map(to_commit_id), Some(id_7.clone()));
         assert_eq!(iter.next().map(to_commit_id), Some(id_6.clone()));
         assert_eq!(iter.next().map(to_commit_id), Some(id_5.clone()));
-        assert_eq!(iter.walk.queue.items.len(), 2);
-        assert_eq!(iter.next().map(to_commit_id), Some(id_4.clone()));
-        assert_eq!(iter.walk.queue.items.len(), 1); // id_1 shouldn't be queued
+        // id_4 shouldn't be queued because its generation is lower than id_3's
+        assert_eq!(iter.walk.queue.items.len(), 1);
         assert_eq!(iter.next().map(to_commit_id), Some(id_3.clone()));
         assert_eq!(iter.walk.queue.items.len(), 0); // id_2 shouldn't be queued
         assert!(iter.next().is_none());
@@ -966,6 +1026,22 @@ mod tests {
         let iter = make_iter(&[id_6.clone(), id_7.clone(), id_2.clone()], &[id_3.clone()]);
         assert_eq!(iter.walk.queue.items.len(), 2); // id_2 shouldn't be queued
 
+        let iter = make_iter(&[id_4.clone(), id_7.clone()], &[id_3.clone()]);
+        assert_eq!(iter.walk.queue.items.len(), 1); // id_4 shouldn't be queued
+
+        let iter = make_iter(&[id_6.clone(), id_7.clone()], &[id_3.clone(), id_4.clone()]);
+        let walked = iter.map(to_commit_id).collect_vec();
+        assert_eq!(
+            walked,
+            vec![
+                id_7.clone(),
+                id_6.clone(),
+                id_5.clone(),
+                id_4.clone(),
+                id_3.clone()
+            ]
+        );
+
         let iter = make_iter(&[id_6.clone(), id_7.clone()], &[]);
         assert!(iter.walk.queue.items.is_empty()); // no ids should be queued
     }
@@ -17,19 +17,24 @@
 use std::cell::RefCell;
 use std::cmp::{Ordering, Reverse};
 use std::collections::{BTreeSet, BinaryHeap, HashSet};
+use std::io::Read as _;
 use std::ops::Range;
 use std::rc::Rc;
 use std::sync::Arc;
-use std::{fmt, iter};
+use std::{fmt, iter, str};
 
 use itertools::Itertools;
+use pollster::FutureExt as _;
 
 use super::rev_walk::{EagerRevWalk, PeekableRevWalk, RevWalk, RevWalkBuilder};
 use super::revset_graph_iterator::RevsetGraphWalk;
 use crate::backend::{ChangeId, CommitId, MillisSinceEpoch};
+use crate::conflicts::{self, ConflictMarkerOptions, MaterializedTreeValue};
 use crate::default_index::{AsCompositeIndex, CompositeIndex, IndexEntry, IndexPosition};
+use crate::fileset::{FilePattern, FilesetExpression};
 use crate::matchers::{Matcher, Visit};
-use crate::repo_path::RepoPath;
+use crate::merge::MergedTreeValue;
+use crate::repo_path::{RepoPath, RepoPathBuf};
 use crate::revset::{
     ResolvedExpression, ResolvedPredicateExpression, Revset, RevsetEvaluationError,
     RevsetFilterPredicate, GENERATION_RANGE_FULL,
@@ -37,6 +42,7 @@ use crate::revset::{
 use crate::revset_graph::RevsetGraphEdge;
 use crate::rewrite;
 use crate::store::Store;
+use crate::str_util::StringPattern;
 
 type BoxedPredicateFn<'a> = Box<dyn FnMut(&CompositeIndex, IndexPosition) -> bool + 'a>;
 pub(super) type BoxedRevWalk<'a> = Box<dyn RevWalk<CompositeIndex, Item = IndexPosition> + 'a>;
@@ -1038,11 +1044,45 @@ fn build_predicate_fn(
                     || pattern.matches(&commit.committer().email)
             })
         }
+        RevsetFilterPredicate::AuthorDate(pattern) => {
+            let pattern = *pattern;
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id()).unwrap();
+                pattern.matches(&commit.author().timestamp)
+            })
+        }
+        RevsetFilterPredicate::CommitterDate(pattern) => {
+            let pattern = *pattern;
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id()).unwrap();
+                pattern.matches(&commit.committer().timestamp)
+            })
+        }
         RevsetFilterPredicate::File(expr) => {
             let matcher: Rc<dyn Matcher> = expr.to_matcher().into();
+            let filter_paths = changed_path_filter_paths(expr);
             box_pure_predicate_fn(move |index, pos| {
                 let entry = index.entry_by_pos(pos);
-                has_diff_from_parent(&store, index, &entry, matcher.as_ref())
+                may_have_changed_paths(&entry, filter_paths.as_deref())
+                    && has_diff_from_parent(&store, index, &entry, matcher.as_ref())
+            })
+        }
+        RevsetFilterPredicate::DiffContains { text, files } => {
+            let text = text.clone();
+            let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            let filter_paths = changed_path_filter_paths(files);
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                may_have_changed_paths(&entry, filter_paths.as_deref())
+                    && matches_diff_from_parent(
+                        &store,
+                        index,
+                        &entry,
+                        &text,
+                        files_matcher.as_ref(),
+                    )
             })
         }
         RevsetFilterPredicate::HasConflict => box_pure_predicate_fn(move |index, pos| {
@@ -1053,6 +1093,40 @@ fn build_predicate_fn(
     }
 }
 
+/// Returns the paths to look up in the changed-path filters to tell if a commit
+/// may change any file matching the `expression`, or `None` if the filters
+/// can't be used for the `expression`.
+fn changed_path_filter_paths(expression: &FilesetExpression) -> Option<Vec<RepoPathBuf>> {
+    match expression {
+        FilesetExpression::None => Some(vec![]),
+        FilesetExpression::All => None,
+        FilesetExpression::Pattern(pattern) => {
+            let (FilePattern::FilePath(path) | FilePattern::PrefixPath(path)) = pattern;
+            (!path.is_root()).then(|| vec![path.clone()])
+        }
+        FilesetExpression::UnionAll(expressions) => {
+            let paths: Option<Vec<_>> = expressions.iter().map(changed_path_filter_paths).collect();
+            paths.map(|paths| paths.concat())
+        }
+        // A commit which changes none of the files in one of the operands
+        // changes none of the files in the intersection.
+        FilesetExpression::Intersection(expression1, expression2) => {
+            changed_path_filter_paths(expression1)
+                .or_else(|| changed_path_filter_paths(expression2))
+        }
+        FilesetExpression::Difference(expression1, _) => changed_path_filter_paths(expression1),
+    }
+}
+
+/// Returns false if the changed-path filter of the `entry` tells that none of
+/// the `paths` (or paths under them) were changed.
+fn may_have_changed_paths(entry: &IndexEntry<'_>, paths: Option<&[RepoPathBuf]>) -> bool {
+    match (paths, entry.changed_path_filter()) {
+        (Some(paths), Some(filter)) => paths.iter().any(|path| filter.might_contain(path)),
+        _ => true,
+    }
+}
+
 fn has_diff_from_parent(
     store: &Arc<Store>,
     index: &CompositeIndex,
@@ -1075,6 +1149,59 @@ fn has_diff_from_parent(
     from_tree.diff(&to_tree, matcher).next().is_some()
 }
 
+fn matches_diff_from_parent(
+    store: &Arc<Store>,
+    index: &CompositeIndex,
+    entry: &IndexEntry<'_>,
+    text_pattern: &StringPattern,
+    files_matcher: &dyn Matcher,
+) -> bool {
+    let commit = store.get_commit(&entry.commit_id()).unwrap();
+    let parents = commit.parents();
+    let from_tree = rewrite::merge_commit_trees_without_repo(store, &index, &parents).unwrap();
+    let to_tree = commit.tree().unwrap();
+    from_tree.diff(&to_tree, files_matcher).any(|(path, diff)| {
+        let (left_value, right_value) = diff.unwrap();
+        let left_content = to_file_content(store, &path, left_value);
+        let right_content = to_file_content(store, &path, right_value);
+        // Filter lines prior to comparison. This might produce inferior hunks
+        // due to lack of contexts, but is way faster than full diff.
+        let left_lines = match_lines(&left_content, text_pattern);
+        let right_lines = match_lines(&right_content, text_pattern);
+        left_lines.ne(right_lines)
+    })
+}
+
+fn match_lines<'a: 'b, 'b>(
+    text: &'a [u8],
+    pattern: &'b StringPattern,
+) -> impl Iterator<Item = &'a [u8]> + 'b {
+    // The pattern is matched line by line so that it can be anchored to line
+    // start/end. For example, exact:"" will match blank lines.
+    text.split_inclusive(|b| *b == b'\n').filter(|line| {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        str::from_utf8(line).is_ok_and(|line| pattern.matches(line))
+    })
+}
+
+fn to_file_content(store: &Store, path: &RepoPath, value: MergedTreeValue) -> Vec<u8> {
+    let materialized =
+        conflicts::materialize_tree_value(store, path, value, ConflictMarkerOptions::default())
+            .block_on()
+            .unwrap();
+    match materialized {
+        MaterializedTreeValue::Absent => vec![],
+        MaterializedTreeValue::File { mut reader, .. } => {
+            let mut content = vec![];
+            reader.read_to_end(&mut content).unwrap();
+            content
+        }
+        MaterializedTreeValue::Symlink { id: _, target } => target.into_bytes(),
+        MaterializedTreeValue::Conflict { contents, .. } => contents,
+        MaterializedTreeValue::GitSubmodule(_) | MaterializedTreeValue::Tree(_) => vec![],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
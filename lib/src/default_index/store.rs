@@ -123,6 +123,26 @@ impl DefaultIndexStore {
         Ok(())
     }
 
+    /// Returns true if changed-path filters are computed when the index is
+    /// rebuilt from scratch.
+    pub fn changed_path_filters_enabled(&self) -> bool {
+        self.changed_path_filters_marker_path().is_file()
+    }
+
+    /// Enables or disables the changed-path filters of the indexes built from
+    /// scratch. The existing index has to be rebuilt for the change to take
+    /// effect.
+    pub fn set_changed_path_filters_enabled(&self, enabled: bool) -> Result<(), PathError> {
+        let path = self.changed_path_filters_marker_path();
+        if enabled {
+            fs::write(&path, b"").context(&path)
+        } else if path.is_file() {
+            fs::remove_file(&path).context(&path)
+        } else {
+            Ok(())
+        }
+    }
+
     fn ensure_base_dirs(&self) -> Result<(), PathError> {
         for dir in [self.operations_dir(), self.segments_dir()] {
             file_util::create_or_reuse_dir(&dir).context(&dir)?;
@@ -138,6 +158,10 @@ impl DefaultIndexStore {
         self.dir.join("segments")
     }
 
+    fn changed_path_filters_marker_path(&self) -> PathBuf {
+        self.dir.join("changed_path_filters")
+    }
+
     fn load_index_segments_at_operation(
         &self,
         op_id: &OperationId,
@@ -212,6 +236,9 @@ impl DefaultIndexStore {
             None => {
                 maybe_parent_file = None;
                 mutable_index = DefaultMutableIndex::full(commit_id_length, change_id_length);
+                if self.changed_path_filters_enabled() {
+                    mutable_index.enable_changed_path_filters();
+                }
             }
             Some(parent_op_id) => {
                 let parent_file = self.load_index_segments_at_operation(
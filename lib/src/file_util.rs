@@ -148,6 +148,53 @@ pub fn persist_content_addressed_temp_file<P: AsRef<Path>>(
     }
 }
 
+/// Creates the file at `dest` as a copy-on-write clone (a "reflink") of the
+/// file at `src`, which shares its data until either file is modified.
+///
+/// Fails if `dest` exists, or if the filesystem doesn't support clones, in
+/// which case the file should be copied instead.
+pub fn try_clone_file(src: &Path, dest: &Path) -> io::Result<()> {
+    #[cfg(all(
+        target_os = "linux",
+        not(any(target_arch = "sparc", target_arch = "sparc64"))
+    ))]
+    {
+        let src_file = File::open(src)?;
+        let dest_file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true) // Don't overwrite existing file. Don't follow symlink.
+            .open(dest)?;
+        if let Err(err) = rustix::fs::ioctl_ficlone(&dest_file, &src_file) {
+            drop(dest_file);
+            fs::remove_file(dest).ok();
+            return Err(err.into());
+        }
+        Ok(())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let src_file = File::open(src)?;
+        rustix::fs::fclonefileat(
+            &src_file,
+            rustix::fs::CWD,
+            dest,
+            rustix::fs::CloneFlags::NOFOLLOW,
+        )?;
+        Ok(())
+    }
+    #[cfg(not(any(
+        all(
+            target_os = "linux",
+            not(any(target_arch = "sparc", target_arch = "sparc64"))
+        ),
+        target_os = "macos"
+    )))]
+    {
+        let _ = (src, dest);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
 #[cfg(unix)]
 mod platform {
     use std::io;
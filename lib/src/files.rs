@@ -16,6 +16,7 @@
 
 use std::collections::VecDeque;
 use std::fmt::{Debug, Error, Formatter};
+use std::iter::zip;
 
 use itertools::Itertools;
 
@@ -159,6 +160,18 @@ pub enum MergeResult {
 }
 
 pub fn merge(slices: &Merge<&[u8]>) -> MergeResult {
+    merge_inner(slices, false)
+}
+
+/// Like `merge()`, but also resolves the conflicting hunks whose sides only
+/// differ in whitespace. Such a hunk takes the content of a side which made a
+/// non-whitespace change, so the whitespace changes of the other sides to it
+/// are lost.
+pub fn merge_ignoring_whitespace(slices: &Merge<&[u8]>) -> MergeResult {
+    merge_inner(slices, true)
+}
+
+fn merge_inner(slices: &Merge<&[u8]>, ignore_whitespace: bool) -> MergeResult {
     // TODO: Using the first remove as base (first in the inputs) is how it's
     // usually done for 3-way conflicts. Are there better heuristics when there are
     // more than 3 parts?
@@ -176,6 +189,11 @@ pub fn merge(slices: &Merge<&[u8]>) -> MergeResult {
             DiffHunk::Different(parts) => {
                 if let Some(resolved) = trivial_merge(&parts[..num_diffs], &parts[num_diffs..]) {
                     resolved_hunk.0.extend(*resolved);
+                } else if let Some(resolved) = ignore_whitespace
+                    .then(|| resolve_ignoring_whitespace(&parts[..num_diffs], &parts[num_diffs..]))
+                    .flatten()
+                {
+                    resolved_hunk.0.extend(resolved);
                 } else {
                     if !resolved_hunk.0.is_empty() {
                         merge_hunks.push(Merge::resolved(resolved_hunk));
@@ -204,6 +222,23 @@ pub fn merge(slices: &Merge<&[u8]>) -> MergeResult {
     }
 }
 
+fn resolve_ignoring_whitespace<'a>(removes: &[&'a [u8]], adds: &[&'a [u8]]) -> Option<&'a [u8]> {
+    let strip = |part: &[u8]| {
+        part.iter()
+            .copied()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect_vec()
+    };
+    let stripped_removes = removes.iter().map(|part| strip(part)).collect_vec();
+    let stripped_adds = adds.iter().map(|part| strip(part)).collect_vec();
+    let resolved = trivial_merge(&stripped_removes, &stripped_adds)?;
+    // Prefer a side which changed more than the whitespace
+    zip(adds, &stripped_adds)
+        .filter(|(_, stripped)| *stripped == resolved)
+        .map(|(part, _)| *part)
+        .min_by_key(|part| removes.contains(part))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -418,4 +453,44 @@ b {
             ))
         );
     }
+
+    #[test]
+    fn test_merge_ignoring_whitespace() {
+        let merge = |removes: &[&[u8]], adds: &[&[u8]]| {
+            super::merge_ignoring_whitespace(&Merge::from_removes_adds(
+                removes.to_vec(),
+                adds.to_vec(),
+            ))
+        };
+        // One side reformatted, one side changed
+        assert_eq!(
+            merge(&[b"a(1,2)\n"], &[b"a(1, 2)\n", b"a(1,3)\n"]),
+            MergeResult::Resolved(hunk(b"a(1,3)\n"))
+        );
+        assert_eq!(
+            merge(&[b"a(1,2)\n"], &[b"a(1,3)\n", b"a(1, 2)\n"]),
+            MergeResult::Resolved(hunk(b"a(1,3)\n"))
+        );
+        // Both sides reformatted differently
+        assert_eq!(
+            merge(&[b"a(1,2)\n"], &[b"a(1, 2)\n", b"a( 1,2 )\n"]),
+            MergeResult::Resolved(hunk(b"a(1, 2)\n"))
+        );
+        // Unrelated hunks are merged as usual
+        assert_eq!(
+            merge(
+                &[b"a(1,2)\nb\nc(3,4)\n"],
+                &[b"a(1, 2)\nb\nc(3, 4)\n", b"a(1,2)\nb\nc(3,5)\n"]
+            ),
+            MergeResult::Resolved(hunk(b"a(1, 2)\nb\nc(3,5)\n"))
+        );
+        // Non-whitespace changes still conflict
+        assert_eq!(
+            merge(&[b"a(1,2)\n"], &[b"a(1, 3)\n", b"a(1,4)\n"]),
+            MergeResult::Conflict(vec![Merge::from_removes_adds(
+                vec![hunk(b"a(1,2)\n")],
+                vec![hunk(b"a(1, 3)\n"), hunk(b"a(1,4)\n")]
+            )])
+        );
+    }
 }
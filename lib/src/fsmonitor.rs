@@ -75,7 +75,9 @@ pub mod watchman {
     use watchman_client::expr;
     use watchman_client::prelude::{
         Clock as InnerClock, ClockSpec, NameOnly, QueryRequestCommon, QueryResult,
+        SubscribeRequest,
     };
+    use watchman_client::{Subscription, SubscriptionData};
 
     /// Represents an instance in time from the perspective of the filesystem
     /// monitor.
@@ -139,6 +141,37 @@ pub mod watchman {
 
         #[error("Failed to query Watchman")]
         WatchmanQueryError(#[source] watchman_client::Error),
+
+        #[error("Failed to subscribe to Watchman")]
+        WatchmanSubscribeError(#[source] watchman_client::Error),
+
+        #[error("Watchman canceled the subscription")]
+        SubscriptionCanceled,
+
+        #[error("Failed to start the Watchman client runtime")]
+        RuntimeError(#[source] std::io::Error),
+    }
+
+    /// Matches the files of the working copy, excluding the `.git` and `.jj`
+    /// directories.
+    fn working_copy_expression() -> expr::Expr {
+        let exclude_dirs = [Path::new(".git"), Path::new(".jj")];
+        let excludes = itertools::chain(
+            // the directories themselves
+            [expr::Expr::Name(expr::NameTerm {
+                paths: exclude_dirs.iter().map(|&name| name.to_owned()).collect(),
+                wholename: true,
+            })],
+            // and all files under the directories
+            exclude_dirs.iter().map(|&name| {
+                expr::Expr::DirName(expr::DirNameTerm {
+                    path: name.to_owned(),
+                    depth: None,
+                })
+            }),
+        )
+        .collect();
+        expr::Expr::Not(Box::new(expr::Expr::Any(excludes)))
     }
 
     /// Handle to the underlying Watchman instance.
@@ -184,23 +217,7 @@ pub mod watchman {
         ) -> Result<(Clock, Option<Vec<PathBuf>>), Error> {
             // TODO: might be better to specify query options by caller, but we
             // shouldn't expose the underlying watchman API too much.
-            let exclude_dirs = [Path::new(".git"), Path::new(".jj")];
-            let excludes = itertools::chain(
-                // the directories themselves
-                [expr::Expr::Name(expr::NameTerm {
-                    paths: exclude_dirs.iter().map(|&name| name.to_owned()).collect(),
-                    wholename: true,
-                })],
-                // and all files under the directories
-                exclude_dirs.iter().map(|&name| {
-                    expr::Expr::DirName(expr::DirNameTerm {
-                        path: name.to_owned(),
-                        depth: None,
-                    })
-                }),
-            )
-            .collect();
-            let expression = expr::Expr::Not(Box::new(expr::Expr::Any(excludes)));
+            let expression = working_copy_expression();
 
             info!("Querying Watchman for changed files...");
             let QueryResult {
@@ -243,4 +260,70 @@ pub mod watchman {
             }
         }
     }
+
+    /// Blocking watcher that waits for files in the working copy to change.
+    ///
+    /// The subscription is registered when the watcher is created, so the
+    /// changes made after that are reported even if they happen before
+    /// `wait_for_changes()` is called.
+    pub struct ChangeWatcher {
+        runtime: tokio::runtime::Runtime,
+        // Keeps the connection to Watchman open.
+        _fsmonitor: Fsmonitor,
+        subscription: Subscription<NameOnly>,
+    }
+
+    impl ChangeWatcher {
+        /// Subscribes to the changes in the working copy at
+        /// `working_copy_path`.
+        #[instrument]
+        pub fn new(working_copy_path: &Path) -> Result<Self, Error> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(Error::RuntimeError)?;
+            let (fsmonitor, subscription) = runtime.block_on(async {
+                let fsmonitor = Fsmonitor::init(working_copy_path).await?;
+                let (subscription, _response) = fsmonitor
+                    .client
+                    .subscribe::<NameOnly>(
+                        &fsmonitor.resolved_root,
+                        SubscribeRequest {
+                            expression: Some(working_copy_expression()),
+                            empty_on_fresh_instance: true,
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .map_err(Error::WatchmanSubscribeError)?;
+                Ok::<_, Error>((fsmonitor, subscription))
+            })?;
+            Ok(ChangeWatcher {
+                runtime,
+                _fsmonitor: fsmonitor,
+                subscription,
+            })
+        }
+
+        /// Blocks until some files in the working copy have changed.
+        #[instrument(skip(self))]
+        pub fn wait_for_changes(&mut self) -> Result<(), Error> {
+            self.runtime.block_on(async {
+                loop {
+                    match self
+                        .subscription
+                        .next()
+                        .await
+                        .map_err(Error::WatchmanQueryError)?
+                    {
+                        SubscriptionData::FilesChanged(QueryResult {
+                            files: Some(files), ..
+                        }) if !files.is_empty() => return Ok(()),
+                        SubscriptionData::Canceled => return Err(Error::SubscriptionCanceled),
+                        _ => {}
+                    }
+                }
+            })
+        }
+    }
 }
@@ -18,10 +18,11 @@ use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::default::Default;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{fmt, iter, str};
 
 use git2::Oid;
+use gix::prelude::ReferenceExt as _;
 use itertools::Itertools;
 use tempfile::NamedTempFile;
 use thiserror::Error;
@@ -116,6 +117,38 @@ fn get_git_repo(store: &Store) -> Option<gix::Repository> {
     get_git_backend(store).map(|backend| backend.git_repo())
 }
 
+/// Returns true if `raw_ref` points to the previously `known_target`, either
+/// directly or through an annotated tag stored in the packed-refs file, without
+/// looking up any object.
+fn is_known_git_ref_target(raw_ref: &gix::refs::Reference, known_target: &RefTarget) -> bool {
+    let Some(id) = known_target.as_normal() else {
+        return false;
+    };
+    matches!(raw_ref.target.try_id(), Some(oid) if oid.as_bytes() == id.as_bytes())
+        || matches!(raw_ref.peeled, Some(oid) if oid.as_bytes() == id.as_bytes())
+}
+
+/// Reads the Git refs under the `prefixes` (such as `"refs/heads/"`) in a
+/// single pass. The packed-refs file is read once instead of being searched
+/// for each ref, which matters for repos with many refs.
+fn read_git_refs(
+    git_repo: &gix::Repository,
+    prefixes: &[&str],
+) -> Result<Vec<gix::refs::Reference>, Box<dyn std::error::Error + Send + Sync>> {
+    let packed_buffer = git_repo.refs.cached_packed_buffer()?;
+    let packed = packed_buffer.as_ref().map(|buffer| &***buffer);
+    let mut git_refs = vec![];
+    for prefix in prefixes {
+        for git_ref in git_repo
+            .refs
+            .iter_prefixed_packed(Path::new(prefix), packed)?
+        {
+            git_refs.push(git_ref?);
+        }
+    }
+    Ok(git_refs)
+}
+
 /// Checks if `git_ref` points to a Git commit object, and returns its id.
 ///
 /// If the ref points to the previously `known_target` (i.e. unchanged), this
@@ -129,12 +162,7 @@ fn resolve_git_ref_to_commit_id(
     // Try fast path if we have a candidate id which is known to be a commit object.
     if let Some(id) = known_target.as_normal() {
         let raw_ref = &git_ref.inner;
-        if matches!(raw_ref.target.try_id(), Some(oid) if oid.as_bytes() == id.as_bytes()) {
-            return Some(id.clone());
-        }
-        if matches!(raw_ref.peeled, Some(oid) if oid.as_bytes() == id.as_bytes()) {
-            // Perhaps an annotated tag stored in packed-refs file, and pointing to the
-            // already known target commit.
+        if is_known_git_ref_target(raw_ref, known_target) {
             return Some(id.clone());
         }
         // A tag (according to ref name.) Try to peel one more level. This is slightly
@@ -417,18 +445,11 @@ fn diff_refs_to_import(
 
     let mut changed_git_refs = Vec::new();
     let mut changed_remote_refs = BTreeMap::new();
-    let git_references = git_repo.references().map_err(GitImportError::from_git)?;
-    let chain_git_refs_iters = || -> Result<_, gix::reference::iter::init::Error> {
-        // Exclude uninteresting directories such as refs/jj/keep.
-        Ok(itertools::chain!(
-            git_references.local_branches()?,
-            git_references.remote_branches()?,
-            git_references.tags()?,
-        ))
-    };
-    for git_ref in chain_git_refs_iters().map_err(GitImportError::from_git)? {
-        let git_ref = git_ref.map_err(GitImportError::from_git)?;
-        let Ok(full_name) = str::from_utf8(git_ref.name().as_bstr()) else {
+    // Exclude uninteresting directories such as refs/jj/keep.
+    let git_refs = read_git_refs(git_repo, &["refs/heads/", "refs/remotes/", "refs/tags/"])
+        .map_err(GitImportError::from_git)?;
+    for git_ref in &git_refs {
+        let Ok(full_name) = str::from_utf8(git_ref.name.as_bstr()) else {
             // Skip non-utf8 refs.
             continue;
         };
@@ -443,7 +464,18 @@ fn diff_refs_to_import(
             return Err(GitImportError::RemoteReservedForLocalGitRepo);
         }
         let old_git_target = known_git_refs.get(full_name).copied().flatten();
-        let Some(id) = resolve_git_ref_to_commit_id(&git_ref, old_git_target) else {
+        // Skip the unchanged refs without looking up their targets.
+        if is_known_git_ref_target(git_ref, old_git_target)
+            && known_remote_refs
+                .get(&ref_name)
+                .is_some_and(|(old_remote_target, _)| *old_remote_target == old_git_target)
+        {
+            known_git_refs.remove(full_name);
+            known_remote_refs.remove(&ref_name);
+            continue;
+        }
+        let attached_ref = git_ref.clone().attach(git_repo);
+        let Some(id) = resolve_git_ref_to_commit_id(&attached_ref, old_git_target) else {
             // Skip (or remove existing) invalid refs.
             continue;
         };
@@ -672,28 +704,77 @@ pub fn export_some_refs(
             }
         }
     }
+    let mut ref_updates = vec![];
     for (parsed_ref_name, old_oid) in branches_to_delete {
         let Some(git_ref_name) = to_git_ref_name(&parsed_ref_name) else {
             failed_branches.insert(parsed_ref_name, FailedRefExportReason::InvalidGitName);
             continue;
         };
-        if let Err(reason) = delete_git_ref(&git_repo, &git_ref_name, &old_oid) {
-            failed_branches.insert(parsed_ref_name, reason);
-        } else {
-            let new_target = RefTarget::absent();
-            mut_repo.set_git_ref_target(&git_ref_name, new_target);
-        }
+        ref_updates.push(ExportedRefUpdate {
+            parsed_ref_name,
+            git_ref_name,
+            old_oid: Some(old_oid),
+            new_oid: None,
+        });
     }
     for (parsed_ref_name, (old_oid, new_oid)) in branches_to_update {
         let Some(git_ref_name) = to_git_ref_name(&parsed_ref_name) else {
             failed_branches.insert(parsed_ref_name, FailedRefExportReason::InvalidGitName);
             continue;
         };
-        if let Err(reason) = update_git_ref(&git_repo, &git_ref_name, old_oid, new_oid) {
-            failed_branches.insert(parsed_ref_name, reason);
+        ref_updates.push(ExportedRefUpdate {
+            parsed_ref_name,
+            git_ref_name,
+            old_oid,
+            new_oid: Some(new_oid),
+        });
+    }
+
+    // Updating the refs one by one is slow if there are many of them, especially
+    // if they are stored in the packed-refs file, which would be rewritten for
+    // each of them. So the refs which weren't modified in Git are updated in a
+    // single transaction. The others, or all of them if the transaction fails,
+    // are updated one by one to report the failures for each ref. The current
+    // targets are read in a single pass rather than looked up for each ref.
+    let git_branch_targets: HashMap<String, gix::ObjectId> =
+        read_git_refs(&git_repo, &["refs/heads/"])
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|git_ref| {
+                let oid = git_ref.target.try_id()?.to_owned();
+                let name = String::from_utf8(git_ref.name.into_inner().into()).ok()?;
+                Some((name, oid))
+            })
+            .collect();
+    let (batched_updates, mut unbatched_updates): (Vec<_>, Vec<_>) = ref_updates
+        .into_iter()
+        .partition(|update| update.is_unchanged_in_git(&git_branch_targets));
+    if !batched_updates.is_empty() {
+        let edits: Option<Vec<_>> = batched_updates
+            .iter()
+            .map(ExportedRefUpdate::to_edit)
+            .collect();
+        if edits.is_some_and(|edits| git_repo.edit_references(edits).is_ok()) {
+            for update in &batched_updates {
+                mut_repo.set_git_ref_target(&update.git_ref_name, update.new_target());
+            }
         } else {
-            let new_target = RefTarget::normal(CommitId::from_bytes(new_oid.as_bytes()));
-            mut_repo.set_git_ref_target(&git_ref_name, new_target);
+            unbatched_updates.extend(batched_updates);
+        }
+    }
+    // Deletions first, so a deleted ref doesn't prevent updating another ref
+    // whose name starts with it.
+    unbatched_updates.sort_by_key(|update| update.new_oid.is_some());
+    for update in unbatched_updates {
+        let result = if let Some(new_oid) = update.new_oid {
+            update_git_ref(&git_repo, &update.git_ref_name, update.old_oid, new_oid)
+        } else {
+            delete_git_ref(&git_repo, &update.git_ref_name, &update.old_oid.unwrap())
+        };
+        if let Err(reason) = result {
+            failed_branches.insert(update.parsed_ref_name, reason);
+        } else {
+            mut_repo.set_git_ref_target(&update.git_ref_name, update.new_target());
         }
     }
 
@@ -829,6 +910,61 @@ fn diff_refs_to_export(
     }
 }
 
+/// Change of a Git ref to export.
+#[derive(Debug)]
+struct ExportedRefUpdate {
+    parsed_ref_name: RefName,
+    git_ref_name: String,
+    /// Last known target in Git, if the ref existed.
+    old_oid: Option<gix::ObjectId>,
+    /// New target, or `None` if the ref is deleted.
+    new_oid: Option<gix::ObjectId>,
+}
+
+impl ExportedRefUpdate {
+    /// Returns true if the ref still has the last known target in Git, given
+    /// the current `git_branch_targets`.
+    fn is_unchanged_in_git(&self, git_branch_targets: &HashMap<String, gix::ObjectId>) -> bool {
+        git_branch_targets.get(&self.git_ref_name) == self.old_oid.as_ref()
+    }
+
+    /// Returns the edit for the transaction, or `None` if the name isn't valid.
+    fn to_edit(&self) -> Option<gix::refs::transaction::RefEdit> {
+        use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+        let expected = match self.old_oid {
+            Some(old_oid) => PreviousValue::MustExistAndMatch(old_oid.into()),
+            None => PreviousValue::MustNotExist,
+        };
+        let change = match self.new_oid {
+            Some(new_oid) => Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: "export from jj".into(),
+                },
+                expected,
+                new: new_oid.into(),
+            },
+            None => Change::Delete {
+                expected,
+                log: RefLog::AndReference,
+            },
+        };
+        Some(RefEdit {
+            change,
+            name: self.git_ref_name.as_str().try_into().ok()?,
+            deref: false,
+        })
+    }
+
+    fn new_target(&self) -> RefTarget {
+        match self.new_oid {
+            Some(new_oid) => RefTarget::normal(CommitId::from_bytes(new_oid.as_bytes())),
+            None => RefTarget::absent(),
+        }
+    }
+}
+
 fn delete_git_ref(
     git_repo: &gix::Repository,
     git_ref_name: &str,
@@ -1109,6 +1245,7 @@ pub fn fetch(
     git_repo: &git2::Repository,
     remote_name: &str,
     branch_names: &[StringPattern],
+    excluded_branches: &[String],
     callbacks: RemoteCallbacks<'_>,
     git_settings: &GitSettings,
 ) -> Result<GitFetchStats, GitFetchError> {
@@ -1150,6 +1287,16 @@ pub fn fetch(
     remote.prune(None)?;
     tracing::debug!("remote.update_tips");
     remote.update_tips(None, false, git2::AutotagOption::Unspecified, None)?;
+    // libgit2 doesn't support negative refspecs, so the excluded branches are
+    // fetched too. Delete their remote-tracking branches so they aren't
+    // imported later.
+    for branch in excluded_branches {
+        match git_repo.find_reference(&format!("refs/remotes/{remote_name}/{branch}")) {
+            Ok(mut git_ref) => git_ref.delete()?,
+            Err(err) if err.code() == git2::ErrorCode::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
     // TODO: We could make it optional to get the default branch since we only care
     // about it on clone.
     let mut default_branch = None;
@@ -1172,7 +1319,10 @@ pub fn fetch(
     tracing::debug!("import_refs");
     let import_stats = import_some_refs(mut_repo, git_settings, |ref_name| {
         to_remote_branch(ref_name, remote_name)
-            .map(|branch| branch_names.iter().any(|pattern| pattern.matches(branch)))
+            .map(|branch| {
+                branch_names.iter().any(|pattern| pattern.matches(branch))
+                    && !excluded_branches.iter().any(|excluded| excluded == branch)
+            })
             .unwrap_or_else(|| matches!(ref_name, RefName::Tag(_)))
     })?;
     let stats = GitFetchStats {
@@ -1304,6 +1454,216 @@ pub fn push_updates(
     result
 }
 
+/// Lists the branches on the remote and their targets, like `git ls-remote
+/// --heads`. The remote-tracking branches aren't updated.
+pub fn list_remote_branches(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<BTreeMap<String, CommitId>, GitPushError> {
+    if remote_name == REMOTE_NAME_FOR_LOCAL_GIT_REPO {
+        return Err(GitPushError::RemoteReservedForLocalGitRepo);
+    }
+    let mut remote = git_repo.find_remote(remote_name).map_err(|err| {
+        if is_remote_not_found_err(&err) {
+            GitPushError::NoSuchRemote(remote_name.to_string())
+        } else {
+            GitPushError::InternalGitError(err)
+        }
+    })?;
+    let mut proxy_options = git2::ProxyOptions::new();
+    proxy_options.auto();
+    let connection = remote.connect_auth(
+        git2::Direction::Fetch,
+        Some(callbacks.into_git()),
+        Some(proxy_options),
+    )?;
+    let branches = connection
+        .list()?
+        .iter()
+        .filter_map(|head| {
+            let name = head.name().strip_prefix("refs/heads/")?;
+            Some((name.to_owned(), CommitId::from_bytes(head.oid().as_bytes())))
+        })
+        .collect();
+    Ok(branches)
+}
+
+/// Git ref in which `jj note` stores the notes.
+pub const NOTES_REF: &str = "refs/notes/commits";
+
+#[derive(Error, Debug)]
+pub enum GitNoteError {
+    #[error("Commit {} already has a note", .0.hex())]
+    AlreadyExists(CommitId),
+    #[error("Commit {} has no note", .0.hex())]
+    NotFound(CommitId),
+    #[error("Unexpected git error when updating notes")]
+    InternalGitError(#[from] git2::Error),
+}
+
+/// Returns the note attached to the commit, if any.
+pub fn read_note(
+    git_repo: &git2::Repository,
+    commit_id: &CommitId,
+) -> Result<Option<String>, git2::Error> {
+    let oid = Oid::from_bytes(commit_id.as_bytes()).unwrap();
+    match git_repo.find_note(Some(NOTES_REF), oid) {
+        Ok(note) => Ok(Some(
+            String::from_utf8_lossy(note.message_bytes()).into_owned(),
+        )),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Returns all the notes, indexed by the commit they're attached to.
+pub fn read_all_notes(
+    git_repo: &git2::Repository,
+) -> Result<HashMap<CommitId, String>, git2::Error> {
+    let notes = match git_repo.notes(Some(NOTES_REF)) {
+        Ok(notes) => notes,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err),
+    };
+    let mut all_notes = HashMap::new();
+    for entry in notes {
+        let (note_id, annotated_id) = entry?;
+        let blob = git_repo.find_blob(note_id)?;
+        all_notes.insert(
+            CommitId::from_bytes(annotated_id.as_bytes()),
+            String::from_utf8_lossy(blob.content()).into_owned(),
+        );
+    }
+    Ok(all_notes)
+}
+
+/// Attaches a note to the commit, replacing the existing note if `force` is
+/// true.
+pub fn write_note(
+    git_repo: &git2::Repository,
+    commit_id: &CommitId,
+    message: &str,
+    signature: &git2::Signature,
+    force: bool,
+) -> Result<(), GitNoteError> {
+    let oid = Oid::from_bytes(commit_id.as_bytes()).unwrap();
+    match git_repo.note(signature, signature, Some(NOTES_REF), oid, message, force) {
+        Ok(_) => Ok(()),
+        Err(err) if err.code() == git2::ErrorCode::Exists => {
+            Err(GitNoteError::AlreadyExists(commit_id.clone()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Removes the note attached to the commit.
+pub fn remove_note(
+    git_repo: &git2::Repository,
+    commit_id: &CommitId,
+    signature: &git2::Signature,
+) -> Result<(), GitNoteError> {
+    let oid = Oid::from_bytes(commit_id.as_bytes()).unwrap();
+    match git_repo.note_delete(oid, Some(NOTES_REF), signature, signature) {
+        Ok(()) => Ok(()),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => {
+            Err(GitNoteError::NotFound(commit_id.clone()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Ref in which the notes of the remote are stored when fetched.
+fn remote_notes_ref_name(remote_name: &str) -> String {
+    let notes_name = NOTES_REF.strip_prefix("refs/notes/").unwrap();
+    format!("refs/notes/remotes/{remote_name}/{notes_name}")
+}
+
+/// What `fetch_notes()` did with the notes of the remote.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NotesFetchOutcome {
+    /// The local notes already contained the remote notes.
+    Unchanged,
+    /// The local notes were fast-forwarded to the remote notes.
+    Updated,
+    /// Both the local and the remote notes have changes the other doesn't
+    /// have. The remote notes are left in the returned ref.
+    Diverged(String),
+}
+
+/// Fetches the notes from the remote, and fast-forwards the local notes to
+/// them if possible.
+///
+/// The remote notes are stored in `refs/notes/remotes/<remote>/...`.
+pub fn fetch_notes(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<NotesFetchOutcome, GitFetchError> {
+    let mut remote = git_repo.find_remote(remote_name).map_err(|err| {
+        if is_remote_not_found_err(&err) {
+            GitFetchError::NoSuchRemote(remote_name.to_string())
+        } else {
+            GitFetchError::InternalGitError(err)
+        }
+    })?;
+    let remote_notes_ref = remote_notes_ref_name(remote_name);
+    let mut fetch_options = git2::FetchOptions::new();
+    let mut proxy_options = git2::ProxyOptions::new();
+    proxy_options.auto();
+    fetch_options.proxy_options(proxy_options);
+    fetch_options.remote_callbacks(callbacks.into_git());
+    fetch_options.download_tags(git2::AutotagOption::None);
+    let refspec = format!("+{NOTES_REF}:{remote_notes_ref}");
+    remote.fetch(&[refspec], Some(&mut fetch_options), None)?;
+    remote.disconnect()?;
+
+    let Some(remote_oid) = git_repo.refname_to_id(&remote_notes_ref).ok() else {
+        // The remote has no notes
+        return Ok(NotesFetchOutcome::Unchanged);
+    };
+    let Some(local_oid) = git_repo.refname_to_id(NOTES_REF).ok() else {
+        git_repo.reference(NOTES_REF, remote_oid, false, "fetch notes")?;
+        return Ok(NotesFetchOutcome::Updated);
+    };
+    if local_oid == remote_oid || git_repo.graph_descendant_of(local_oid, remote_oid)? {
+        Ok(NotesFetchOutcome::Unchanged)
+    } else if git_repo.graph_descendant_of(remote_oid, local_oid)? {
+        git_repo.reference_matching(NOTES_REF, remote_oid, true, local_oid, "fetch notes")?;
+        Ok(NotesFetchOutcome::Updated)
+    } else {
+        Ok(NotesFetchOutcome::Diverged(remote_notes_ref))
+    }
+}
+
+/// Pushes the notes to the remote. The notes on the remote must be an
+/// ancestor of the local notes.
+///
+/// Returns false if there are no notes to push.
+pub fn push_notes(
+    git_repo: &git2::Repository,
+    remote_name: &str,
+    callbacks: RemoteCallbacks<'_>,
+) -> Result<bool, GitPushError> {
+    let Ok(local_oid) = git_repo.refname_to_id(NOTES_REF) else {
+        return Ok(false);
+    };
+    push_refs(
+        git_repo,
+        remote_name,
+        &[NOTES_REF],
+        &[format!("{NOTES_REF}:{NOTES_REF}")],
+        callbacks,
+    )?;
+    git_repo.reference(
+        &remote_notes_ref_name(remote_name),
+        local_oid,
+        true,
+        "push notes",
+    )?;
+    Ok(true)
+}
+
 fn push_refs(
     git_repo: &git2::Repository,
     remote_name: &str,
@@ -1362,6 +1722,7 @@ fn push_refs(
 #[allow(clippy::type_complexity)]
 pub struct RemoteCallbacks<'a> {
     pub progress: Option<&'a mut dyn FnMut(&Progress)>,
+    pub push_progress: Option<&'a mut dyn FnMut(&PushProgress)>,
     pub sideband_progress: Option<&'a mut dyn FnMut(&[u8])>,
     pub get_ssh_keys: Option<&'a mut dyn FnMut(&str) -> Vec<PathBuf>>,
     pub get_password: Option<&'a mut dyn FnMut(&str, &str) -> Option<String>>,
@@ -1382,6 +1743,15 @@ impl<'a> RemoteCallbacks<'a> {
                 true
             });
         }
+        if let Some(push_progress_cb) = self.push_progress {
+            callbacks.push_transfer_progress(move |objects_sent, total_objects, bytes_sent| {
+                push_progress_cb(&PushProgress {
+                    objects_sent,
+                    total_objects,
+                    bytes_sent,
+                });
+            });
+        }
         if let Some(sideband_progress_cb) = self.sideband_progress {
             callbacks.sideband_progress(move |data| {
                 sideband_progress_cb(data);
@@ -1471,6 +1841,13 @@ pub struct Progress {
     pub overall: f32,
 }
 
+/// Progress of the transfer of objects to the remote during a push.
+pub struct PushProgress {
+    pub objects_sent: usize,
+    pub total_objects: usize,
+    pub bytes_sent: usize,
+}
+
 #[derive(Default)]
 struct PartialSubmoduleConfig {
     path: Option<String>,
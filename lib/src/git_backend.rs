@@ -34,16 +34,16 @@ use thiserror::Error;
 
 use crate::backend::{
     make_root_commit, Backend, BackendError, BackendInitError, BackendLoadError, BackendResult,
-    ChangeId, Commit, CommitId, Conflict, ConflictId, ConflictTerm, FileId, MergedTreeId,
-    MillisSinceEpoch, SecureSig, Signature, SigningFn, SymlinkId, Timestamp, Tree, TreeId,
-    TreeValue,
+    ChangeId, Commit, CommitId, Conflict, ConflictId, ConflictTerm, CopyRecord, FileId,
+    MergedTreeId, MillisSinceEpoch, SecureSig, Signature, SigningFn, SymlinkId, Timestamp, Tree,
+    TreeId, TreeValue,
 };
 use crate::file_util::{IoResultExt as _, PathError};
 use crate::index::Index;
 use crate::lock::FileLock;
 use crate::merge::{Merge, MergeBuilder};
 use crate::object_id::ObjectId;
-use crate::repo_path::{RepoPath, RepoPathComponentBuf};
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponentBuf};
 use crate::settings::UserSettings;
 use crate::stacked_table::{
     MutableTable, ReadonlyTable, TableSegment, TableStore, TableStoreError,
@@ -519,6 +519,7 @@ fn commit_from_git_without_root_parent(
         author,
         committer,
         secure_sig,
+        copies: vec![],
     })
 }
 
@@ -586,6 +587,15 @@ fn serialize_extras(commit: &Commit) -> Vec<u8> {
     for predecessor in &commit.predecessors {
         proto.predecessors.push(predecessor.to_bytes());
     }
+    for copy in &commit.copies {
+        proto
+            .copies
+            .push(crate::protos::git_store::commit::CopyRecord {
+                target: copy.target.as_internal_file_string().to_owned(),
+                source: copy.source.as_internal_file_string().to_owned(),
+                source_file: copy.source_file.to_bytes(),
+            });
+    }
     proto.encode_to_vec()
 }
 
@@ -619,6 +629,13 @@ fn deserialize_extras(commit: &mut Commit, bytes: &[u8]) {
     for predecessor in &proto.predecessors {
         commit.predecessors.push(CommitId::from_bytes(predecessor));
     }
+    for copy in proto.copies {
+        commit.copies.push(CopyRecord {
+            target: RepoPathBuf::from_internal_string(copy.target),
+            source: RepoPathBuf::from_internal_string(copy.source),
+            source_file: FileId::new(copy.source_file),
+        });
+    }
 }
 
 /// Returns `RefEdit` that will create a ref in `refs/jj/keep` if not exist.
@@ -1659,6 +1676,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         // No parents
@@ -1737,6 +1755,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         // When writing a tree-level conflict, the root tree on the git side has the
@@ -1820,6 +1839,7 @@ mod tests {
             author: signature.clone(),
             committer: signature,
             secure_sig: None,
+            copies: vec![],
         };
         let commit_id = backend.write_commit(commit, None).unwrap().0;
         let git_refs: Vec<_> = git_repo
@@ -1897,6 +1917,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
         // libgit2 doesn't seem to preserve negative timestamps, so set it to at least 1
         // second after the epoch, so the timestamp adjustment can remove 1
@@ -1938,6 +1959,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         let mut signer = |data: &_| {
@@ -0,0 +1,777 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import of Mercurial repos.
+//!
+//! The changesets are read from a stream in the format of `git fast-import`,
+//! such as the one written by `hg fastexport`. The Mercurial changeset ids of
+//! the commits are read from a marks file or from the `original-oid` commands
+//! of the stream. They're used to derive the change ids of the commits, and to
+//! map the obsolescence markers of the Mercurial repo to the predecessors of
+//! the commits.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::backend::{
+    self, BackendError, ChangeId, MergedTreeId, MillisSinceEpoch, Signature, Timestamp, TreeValue,
+};
+use crate::commit::Commit;
+use crate::dag_walk;
+use crate::matchers::PrefixMatcher;
+use crate::merge::Merge;
+use crate::merged_tree::MergedTreeBuilder;
+use crate::repo::{MutableRepo, Repo as _};
+use crate::repo_path::RepoPathBuf;
+use crate::settings::UserSettings;
+use crate::store::Store;
+
+#[derive(Debug, Error)]
+pub enum HgImportError {
+    #[error("Invalid fast-import stream at line {line}: {message}")]
+    InvalidStream { line: usize, message: String },
+    #[error("Invalid marks file at line {line}")]
+    InvalidMarks { line: usize },
+    #[error("Invalid obsolescence markers")]
+    InvalidObsMarkers(#[source] serde_json::Error),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// Obsolescence marker recording that the `predecessor` changeset was
+/// rewritten into the `successors`, or pruned if there are none.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ObsMarker {
+    #[serde(rename = "prednode", alias = "precnode")]
+    pub predecessor: String,
+    #[serde(rename = "succnodes")]
+    pub successors: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct HgImportStats {
+    /// Number of imported commits, including the obsolete ones.
+    pub num_commits: usize,
+    /// Number of obsolete commits. They're hidden unless they have visible
+    /// descendants.
+    pub num_obsolete: usize,
+}
+
+/// Parses the JSON output of `hg debugobsolete -Tjson`.
+pub fn parse_obs_markers(data: &[u8]) -> Result<Vec<ObsMarker>, HgImportError> {
+    serde_json::from_slice(data).map_err(HgImportError::InvalidObsMarkers)
+}
+
+/// Parses a marks file, in which each line maps a mark to the id of an
+/// object, such as `:1 <changeset id>`.
+pub fn parse_marks(data: &str) -> Result<HashMap<u64, String>, HgImportError> {
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| {
+            let (mark, id) = line
+                .strip_prefix(':')
+                .and_then(|line| line.split_once(' '))
+                .ok_or(HgImportError::InvalidMarks { line: index + 1 })?;
+            let mark = mark
+                .parse()
+                .map_err(|_| HgImportError::InvalidMarks { line: index + 1 })?;
+            Ok((mark, id.trim().to_owned()))
+        })
+        .collect()
+}
+
+/// Imports the commits of the fast-import `stream` into the repo.
+///
+/// The `marks` map the marks of the stream to Mercurial changeset ids. The
+/// successors of obsolete changesets keep the change id of their predecessor,
+/// and the obsolete changesets are only reachable from the evolution log of
+/// their successors, unless they have visible descendants.
+pub fn import_fast_export(
+    mut_repo: &mut MutableRepo,
+    settings: &UserSettings,
+    stream: &[u8],
+    marks: &HashMap<u64, String>,
+    obs_markers: &[ObsMarker],
+) -> Result<HgImportStats, HgImportError> {
+    let store = mut_repo.store().clone();
+    let mut commits = read_stream(&store, stream)?;
+    for commit in &mut commits {
+        if commit.node.is_none() {
+            commit.node = commit.mark.and_then(|mark| marks.get(&mark)).cloned();
+        }
+    }
+
+    let node_to_index: HashMap<&str, usize> = commits
+        .iter()
+        .enumerate()
+        .filter_map(|(index, commit)| Some((commit.node.as_deref()?, index)))
+        .collect();
+    let mut predecessors = vec![vec![]; commits.len()];
+    let mut change_id_sources = vec![None; commits.len()];
+    let mut obsolete = vec![false; commits.len()];
+    for marker in obs_markers {
+        // Markers can refer to changesets which weren't exported
+        let Some(&predecessor) = node_to_index.get(marker.predecessor.as_str()) else {
+            continue;
+        };
+        obsolete[predecessor] = true;
+        for (position, successor) in marker.successors.iter().enumerate() {
+            let Some(&successor) = node_to_index.get(successor.as_str()) else {
+                continue;
+            };
+            if !predecessors[successor].contains(&predecessor) {
+                predecessors[successor].push(predecessor);
+            }
+            // The other successors of a split get new change ids
+            if position == 0 && change_id_sources[successor].is_none() {
+                change_id_sources[successor] = Some(predecessor);
+            }
+        }
+    }
+
+    let parents = commits
+        .iter()
+        .map(|commit| &commit.parents[..])
+        .collect_vec();
+    remove_predecessor_cycles(&parents, &mut predecessors);
+    for (index, source) in change_id_sources.iter_mut().enumerate() {
+        if source.is_some_and(|source| !predecessors[index].contains(&source)) {
+            *source = None;
+        }
+    }
+    let order = dag_walk::topo_order_forward(
+        0..commits.len(),
+        |&index| index,
+        |&index| {
+            parents[index]
+                .iter()
+                .chain(&predecessors[index])
+                .copied()
+                .collect_vec()
+        },
+    );
+    let change_id_length = store.change_id_length();
+    let mut written: Vec<Option<Commit>> = vec![None; commits.len()];
+    for index in order {
+        let commit = &commits[index];
+        let get_written = |index: usize| written[index].as_ref().unwrap();
+        let change_id = match change_id_sources[index] {
+            Some(source) => get_written(source).change_id().clone(),
+            None => commit
+                .node
+                .as_deref()
+                .and_then(change_id_from_node)
+                .unwrap_or_else(|| settings.get_rng().new_change_id(change_id_length)),
+        };
+        let parents = if commit.parents.is_empty() {
+            vec![store.root_commit_id().clone()]
+        } else {
+            commit
+                .parents
+                .iter()
+                .map(|&parent| get_written(parent).id().clone())
+                .collect()
+        };
+        let backend_commit = backend::Commit {
+            parents,
+            predecessors: predecessors[index]
+                .iter()
+                .map(|&predecessor| get_written(predecessor).id().clone())
+                .collect(),
+            root_tree: commit.tree_id.clone(),
+            change_id,
+            description: commit.description.clone(),
+            author: commit.author.clone(),
+            committer: commit.committer.clone(),
+            secure_sig: None,
+            copies: vec![],
+        };
+        written[index] = Some(store.write_commit(backend_commit, None)?);
+    }
+
+    let heads = written
+        .into_iter()
+        .zip(&obsolete)
+        .filter_map(|(commit, obsolete)| (!obsolete).then(|| commit.unwrap()))
+        .collect_vec();
+    mut_repo.add_heads(&heads)?;
+    Ok(HgImportStats {
+        num_commits: commits.len(),
+        num_obsolete: obsolete.iter().filter(|&&obsolete| obsolete).count(),
+    })
+}
+
+/// Removes the predecessors which would make a commit its own ancestor in the
+/// graph of parents and predecessors, e.g. if two changesets were made
+/// obsolete by each other.
+///
+/// The parents always come before their children in the stream, so every
+/// cycle has a predecessor which comes after its successor.
+fn remove_predecessor_cycles(parents: &[&[usize]], predecessors: &mut [Vec<usize>]) {
+    for index in 0..predecessors.len() {
+        let mut position = 0;
+        while let Some(&predecessor) = predecessors[index].get(position) {
+            let closes_cycle = predecessor > index
+                && dag_walk::dfs(
+                    [predecessor],
+                    |&index| index,
+                    |&index| {
+                        parents[index]
+                            .iter()
+                            .chain(&predecessors[index])
+                            .copied()
+                            .collect_vec()
+                    },
+                )
+                .any(|ancestor| ancestor == index);
+            if closes_cycle {
+                predecessors[index].remove(position);
+            } else {
+                position += 1;
+            }
+        }
+    }
+}
+
+/// Derives the change id from the changeset id the same way as the Git backend
+/// derives it from the commit id.
+fn change_id_from_node(node: &str) -> Option<ChangeId> {
+    let bytes = hex::decode(node).ok().filter(|bytes| bytes.len() == 20)?;
+    Some(ChangeId::new(
+        bytes[4..].iter().rev().map(|b| b.reverse_bits()).collect(),
+    ))
+}
+
+/// Commit read from the stream, before it's written.
+struct StreamCommit {
+    mark: Option<u64>,
+    node: Option<String>,
+    /// Indices of the parent commits in the stream.
+    parents: Vec<usize>,
+    tree_id: MergedTreeId,
+    author: Signature,
+    committer: Signature,
+    description: String,
+}
+
+/// Reads the commits of the stream, and writes their trees and files.
+fn read_stream(store: &Arc<Store>, data: &[u8]) -> Result<Vec<StreamCommit>, HgImportError> {
+    let mut reader = StreamReader { data, line: 0 };
+    let mut blobs: HashMap<u64, Vec<u8>> = HashMap::new();
+    let mut commits: Vec<StreamCommit> = vec![];
+    let mut commit_marks: HashMap<u64, usize> = HashMap::new();
+    let mut ref_tips: HashMap<String, usize> = HashMap::new();
+    while let Some(line) = reader.next_line()? {
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            _ if command.is_empty() || command.starts_with('#') => {}
+            "blob" => {
+                let mark = reader
+                    .optional("mark")?
+                    .map(|arg| reader.parse_mark(arg))
+                    .transpose()?;
+                reader.optional("original-oid")?;
+                let content = reader.read_data()?;
+                if let Some(mark) = mark {
+                    blobs.insert(mark, content);
+                }
+            }
+            "commit" => {
+                let ref_name = arg.to_owned();
+                let mark = reader
+                    .optional("mark")?
+                    .map(|arg| reader.parse_mark(arg))
+                    .transpose()?;
+                let node = reader.optional("original-oid")?.map(str::to_owned);
+                let author = reader
+                    .optional("author")?
+                    .map(|arg| reader.parse_person(arg))
+                    .transpose()?;
+                let committer = match reader.optional("committer")? {
+                    Some(arg) => reader.parse_person(arg)?,
+                    None => return Err(reader.error("expected committer")),
+                };
+                reader.optional("encoding")?;
+                let description = String::from_utf8_lossy(&reader.read_data()?).into_owned();
+                let mut parents = vec![];
+                let resolve = |reader: &StreamReader, arg: &str| {
+                    let index = match arg.strip_prefix(':') {
+                        Some(mark) => commit_marks.get(&reader.parse_mark(mark)?),
+                        None => ref_tips.get(arg),
+                    };
+                    index
+                        .copied()
+                        .ok_or_else(|| reader.error(format!("unknown commit {arg}")))
+                };
+                match reader.optional("from")? {
+                    Some(arg) => parents.push(resolve(&reader, arg)?),
+                    // The commit continues the branch by default
+                    None => parents.extend(ref_tips.get(&ref_name).copied()),
+                }
+                while let Some(arg) = reader.optional("merge")? {
+                    parents.push(resolve(&reader, arg)?);
+                }
+                let base_tree_id = match parents.first() {
+                    Some(&parent) => commits[parent].tree_id.clone(),
+                    None => store.empty_merged_tree_id(),
+                };
+                let tree_id = read_file_changes(&mut reader, store, &blobs, base_tree_id)?;
+
+                let index = commits.len();
+                if let Some(mark) = mark {
+                    commit_marks.insert(mark, index);
+                }
+                ref_tips.insert(ref_name, index);
+                commits.push(StreamCommit {
+                    mark,
+                    node,
+                    parents,
+                    tree_id,
+                    author: author.unwrap_or_else(|| committer.clone()),
+                    committer,
+                    description: normalize_description(description),
+                });
+            }
+            "reset" => match reader.optional("from")? {
+                Some(from) => {
+                    let index = match from.strip_prefix(':') {
+                        Some(mark) => commit_marks.get(&reader.parse_mark(mark)?),
+                        None => ref_tips.get(from),
+                    };
+                    let index =
+                        *index.ok_or_else(|| reader.error(format!("unknown commit {from}")))?;
+                    ref_tips.insert(arg.to_owned(), index);
+                }
+                None => {
+                    ref_tips.remove(arg);
+                }
+            },
+            // Tags are also recorded in the .hgtags file
+            "tag" => {
+                reader.optional("mark")?;
+                reader.optional("from")?;
+                reader.optional("original-oid")?;
+                reader.optional("tagger")?;
+                reader.read_data()?;
+            }
+            "progress" | "checkpoint" | "feature" | "option" => {}
+            "done" => break,
+            _ => return Err(reader.error(format!("unsupported command {command}"))),
+        }
+    }
+    Ok(commits)
+}
+
+/// Applies the file changes of a commit to the tree of its first parent.
+fn read_file_changes(
+    reader: &mut StreamReader,
+    store: &Arc<Store>,
+    blobs: &HashMap<u64, Vec<u8>>,
+    base_tree_id: MergedTreeId,
+) -> Result<MergedTreeId, HgImportError> {
+    let mut tree_builder = MergedTreeBuilder::new(base_tree_id);
+    while let Some(line) = reader.peek_line()? {
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "M" => {
+                reader.next_line()?;
+                let mut parts = arg.splitn(3, ' ');
+                let (Some(mode), Some(data_ref), Some(path)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(reader.error("invalid file change"));
+                };
+                let path = reader.parse_path(path)?;
+                let inline_content;
+                let content = if data_ref == "inline" {
+                    inline_content = reader.read_data()?;
+                    &inline_content
+                } else if let Some(mark) = data_ref.strip_prefix(':') {
+                    blobs
+                        .get(&reader.parse_mark(mark)?)
+                        .ok_or_else(|| reader.error(format!("unknown blob {data_ref}")))?
+                } else {
+                    return Err(reader.error(format!("unsupported blob {data_ref}")));
+                };
+                let value = match mode {
+                    "100644" | "644" | "100755" | "755" => TreeValue::File {
+                        id: store.write_file(&path, &mut content.as_slice())?,
+                        executable: mode.ends_with("755"),
+                    },
+                    "120000" => {
+                        let target = String::from_utf8(content.clone())
+                            .map_err(|_| reader.error("symlink target isn't valid UTF-8"))?;
+                        TreeValue::Symlink(store.write_symlink(&path, &target)?)
+                    }
+                    // Subrepos aren't supported
+                    "160000" => continue,
+                    _ => return Err(reader.error(format!("unsupported file mode {mode}"))),
+                };
+                tree_builder.set_or_remove(path, Merge::normal(value));
+            }
+            "D" => {
+                reader.next_line()?;
+                // Removes the directory if the path is a directory
+                tree_builder.set_or_remove(reader.parse_path(arg)?, Merge::absent());
+            }
+            "C" | "R" => {
+                reader.next_line()?;
+                let (source, target) =
+                    split_paths(arg).ok_or_else(|| reader.error("invalid file copy"))?;
+                let source = reader.parse_path(&source)?;
+                let target = reader.parse_path(&target)?;
+                // The source may be a directory, or be changed by the previous
+                // file changes
+                let tree_id = tree_builder.write_tree(store)?;
+                let tree = store.get_root_tree(&tree_id)?;
+                tree_builder = MergedTreeBuilder::new(tree_id);
+                for (path, value) in tree.entries_matching(&PrefixMatcher::new([&source])) {
+                    let suffix = path.strip_prefix(&source).unwrap();
+                    let target_path = RepoPathBuf::from_internal_string(
+                        [
+                            target.as_internal_file_string(),
+                            suffix.as_internal_file_string(),
+                        ]
+                        .iter()
+                        .filter(|part| !part.is_empty())
+                        .join("/"),
+                    );
+                    tree_builder.set_or_remove(target_path, value);
+                }
+                if command == "R" {
+                    tree_builder.set_or_remove(source, Merge::absent());
+                }
+            }
+            "deleteall" => {
+                reader.next_line()?;
+                tree_builder = MergedTreeBuilder::new(store.empty_merged_tree_id());
+            }
+            "N" => {
+                reader.next_line()?;
+                if arg.starts_with("inline ") {
+                    reader.read_data()?;
+                }
+            }
+            _ => break,
+        }
+    }
+    Ok(tree_builder.write_tree(store)?)
+}
+
+/// Descriptions written by jj end with a newline, unlike the ones of Mercurial.
+fn normalize_description(mut description: String) -> String {
+    if !description.is_empty() && !description.ends_with('\n') {
+        description.push('\n');
+    }
+    description
+}
+
+/// Splits the source and target paths of a copy or rename. The source path is
+/// quoted if it contains spaces.
+fn split_paths(arg: &str) -> Option<(String, String)> {
+    if arg.starts_with('"') {
+        let end = find_closing_quote(arg)?;
+        let (source, target) = arg.split_at(end + 1);
+        Some((unquote(source)?, unquote(target.strip_prefix(' ')?)?))
+    } else {
+        let (source, target) = arg.split_once(' ')?;
+        Some((source.to_owned(), unquote(target)?))
+    }
+}
+
+fn find_closing_quote(quoted: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in quoted.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Removes the C-style quotes of a path, if it's quoted.
+fn unquote(path: &str) -> Option<String> {
+    let Some(quoted) = path.strip_prefix('"') else {
+        return Some(path.to_owned());
+    };
+    let quoted = quoted.strip_suffix('"')?;
+    let mut bytes = vec![];
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let byte = match chars.next()? {
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            'v' => 0x0b,
+            c @ '0'..='7' => {
+                let digits: String = [Some(c), chars.next(), chars.next()]
+                    .into_iter()
+                    .collect::<Option<_>>()?;
+                u8::from_str_radix(&digits, 8).ok()?
+            }
+            c @ ('\\' | '"') => c as u8,
+            _ => return None,
+        };
+        bytes.push(byte);
+    }
+    String::from_utf8(bytes).ok()
+}
+
+struct StreamReader<'a> {
+    data: &'a [u8],
+    /// Number of the last line read, starting from 1.
+    line: usize,
+}
+
+impl<'a> StreamReader<'a> {
+    fn error(&self, message: impl Into<String>) -> HgImportError {
+        HgImportError::InvalidStream {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn peek_line(&self) -> Result<Option<&'a str>, HgImportError> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+        let end = self
+            .data
+            .iter()
+            .position(|&b| b == b'\n')
+            .unwrap_or(self.data.len());
+        let line =
+            std::str::from_utf8(&self.data[..end]).map_err(|_| HgImportError::InvalidStream {
+                line: self.line + 1,
+                message: "line isn't valid UTF-8".to_owned(),
+            })?;
+        Ok(Some(line))
+    }
+
+    fn next_line(&mut self) -> Result<Option<&'a str>, HgImportError> {
+        let line = self.peek_line()?;
+        if let Some(line) = line {
+            self.data = self.data.get(line.len() + 1..).unwrap_or_default();
+            self.line += 1;
+        }
+        Ok(line)
+    }
+
+    /// Reads the line starting with the `keyword` if it's the next one, and
+    /// returns its argument.
+    fn optional(&mut self, keyword: &str) -> Result<Option<&'a str>, HgImportError> {
+        let Some(line) = self.peek_line()? else {
+            return Ok(None);
+        };
+        let arg = match line.strip_prefix(keyword) {
+            Some("") => "",
+            Some(rest) => match rest.strip_prefix(' ') {
+                Some(arg) => arg,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        self.next_line()?;
+        Ok(Some(arg))
+    }
+
+    /// Reads a `data` command and the data following it.
+    fn read_data(&mut self) -> Result<Vec<u8>, HgImportError> {
+        let Some(arg) = self.optional("data")? else {
+            return Err(self.error("expected data"));
+        };
+        if let Some(delimiter) = arg.strip_prefix("<<") {
+            let mut content = vec![];
+            loop {
+                match self.next_line()? {
+                    Some(line) if line == delimiter => break,
+                    Some(line) => {
+                        content.extend_from_slice(line.as_bytes());
+                        content.push(b'\n');
+                    }
+                    None => return Err(self.error("data is truncated")),
+                }
+            }
+            return Ok(content);
+        }
+        let len: usize = arg
+            .parse()
+            .map_err(|_| self.error(format!("invalid data length {arg}")))?;
+        if self.data.len() < len {
+            return Err(self.error("data is truncated"));
+        }
+        let (content, rest) = self.data.split_at(len);
+        self.line += content.iter().filter(|&&b| b == b'\n').count();
+        self.data = rest.strip_prefix(b"\n").unwrap_or(rest);
+        Ok(content.to_vec())
+    }
+
+    fn parse_mark(&self, arg: &str) -> Result<u64, HgImportError> {
+        arg.trim_start_matches(':')
+            .parse()
+            .map_err(|_| self.error(format!("invalid mark {arg}")))
+    }
+
+    /// Parses a person with a raw date, such as `Name <email> 1234567890
+    /// +0100`.
+    fn parse_person(&self, arg: &str) -> Result<Signature, HgImportError> {
+        let parse = || {
+            let (name, rest) = arg.split_once('<')?;
+            let (email, date) = rest.split_once('>')?;
+            let (seconds, offset) = date.trim().split_once(' ')?;
+            let seconds: i64 = seconds.parse().ok()?;
+            let sign = match offset.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let hours: i32 = offset.get(1..3)?.parse().ok()?;
+            let minutes: i32 = offset.get(3..5)?.parse().ok()?;
+            Some(Signature {
+                name: name.trim_end().to_owned(),
+                email: email.to_owned(),
+                timestamp: Timestamp {
+                    timestamp: MillisSinceEpoch(seconds * 1000),
+                    tz_offset: sign * (hours * 60 + minutes),
+                },
+            })
+        };
+        parse().ok_or_else(|| self.error(format!("invalid person {arg}")))
+    }
+
+    fn parse_path(&self, arg: &str) -> Result<RepoPathBuf, HgImportError> {
+        let path = unquote(arg).ok_or_else(|| self.error(format!("invalid path {arg}")))?;
+        if path.is_empty() || path.starts_with('/') || path.ends_with('/') || path.contains("//") {
+            return Err(self.error(format!("invalid path {arg}")));
+        }
+        Ok(RepoPathBuf::from_internal_string(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_marks() {
+        let marks = parse_marks(":1 0123\n\n:12 abcd\n").unwrap();
+        assert_eq!(marks.len(), 2);
+        assert_eq!(marks[&1], "0123");
+        assert_eq!(marks[&12], "abcd");
+        assert!(matches!(
+            parse_marks(":1 0123\n2 abcd\n"),
+            Err(HgImportError::InvalidMarks { line: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_obs_markers() {
+        let markers = parse_obs_markers(
+            br#"[
+             {"date": [0, 0], "flag": 0, "prednode": "aa", "succnodes": ["bb", "cc"]},
+             {"date": [0, 0], "flag": 0, "precnode": "dd", "succnodes": []}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            markers,
+            vec![
+                ObsMarker {
+                    predecessor: "aa".to_owned(),
+                    successors: vec!["bb".to_owned(), "cc".to_owned()],
+                },
+                ObsMarker {
+                    predecessor: "dd".to_owned(),
+                    successors: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_predecessor_cycles() {
+        // 1 and 2 are children of 0, and obsolete each other
+        let parents: [&[usize]; 3] = [&[], &[0], &[0]];
+        let mut predecessors = vec![vec![], vec![2], vec![1]];
+        remove_predecessor_cycles(&parents, &mut predecessors);
+        assert_eq!(predecessors, vec![vec![], vec![], vec![1]]);
+
+        // 0 is obsoleted by its own child
+        let parents: [&[usize]; 2] = [&[], &[0]];
+        let mut predecessors = vec![vec![1], vec![0]];
+        remove_predecessor_cycles(&parents, &mut predecessors);
+        assert_eq!(predecessors, vec![vec![], vec![0]]);
+
+        // Predecessors which come later in the stream are kept if there's no
+        // cycle
+        let parents: [&[usize]; 3] = [&[], &[0], &[0]];
+        let mut predecessors = vec![vec![], vec![2], vec![]];
+        remove_predecessor_cycles(&parents, &mut predecessors);
+        assert_eq!(predecessors, vec![vec![], vec![2], vec![]]);
+    }
+
+    #[test]
+    fn test_parse_person() {
+        let reader = StreamReader { data: b"", line: 0 };
+        assert_eq!(
+            reader
+                .parse_person("Some One <some.one@example.com> 1000000000 -0130")
+                .unwrap(),
+            Signature {
+                name: "Some One".to_owned(),
+                email: "some.one@example.com".to_owned(),
+                timestamp: Timestamp {
+                    timestamp: MillisSinceEpoch(1000000000000),
+                    tz_offset: -90,
+                },
+            }
+        );
+        assert_eq!(reader.parse_person("<> 0 +0000").unwrap().name, "");
+        assert!(reader.parse_person("Some One 1000000000 +0000").is_err());
+    }
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("a b").as_deref(), Some("a b"));
+        assert_eq!(unquote(r#""a \"b\"\n""#).as_deref(), Some("a \"b\"\n"));
+        assert_eq!(unquote(r#""\303\251""#).as_deref(), Some("é"));
+        assert_eq!(unquote(r#""a"#), None);
+        assert_eq!(
+            split_paths(r#""a b" "c d""#),
+            Some(("a b".to_owned(), "c d".to_owned()))
+        );
+        assert_eq!(
+            split_paths("a c d"),
+            Some(("a".to_owned(), "c d".to_owned()))
+        );
+    }
+}
@@ -46,6 +46,7 @@ pub mod fsmonitor;
 pub mod git;
 pub mod git_backend;
 pub mod gitignore;
+pub mod hg;
 pub mod gpg_signing;
 pub mod hex_util;
 pub mod id_prefix;
@@ -55,6 +56,7 @@ pub mod local_working_copy;
 pub mod lock;
 pub mod matchers;
 pub mod merge;
+pub mod merge_driver;
 pub mod merged_tree;
 pub mod object_id;
 pub mod op_heads_store;
@@ -64,20 +66,26 @@ pub mod operation;
 #[allow(missing_docs)]
 pub mod protos;
 pub mod refs;
+pub mod rename_detection;
 pub mod repo;
 pub mod repo_path;
 pub mod revset;
 pub mod revset_graph;
 pub mod rewrite;
+pub mod scratch;
+pub mod session;
 pub mod settings;
 pub mod signing;
 pub mod simple_op_heads_store;
 pub mod simple_op_store;
+pub mod sparse;
 pub mod ssh_signing;
 pub mod stacked_table;
 pub mod store;
 pub mod str_util;
 pub mod submodule_store;
+pub mod sync;
+pub mod time_util;
 pub mod transaction;
 pub mod tree;
 pub mod tree_builder;
@@ -15,12 +15,13 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
+use std::collections::VecDeque;
 use std::fmt::Debug;
-use std::fs;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
+use std::{fs, io};
 
 use async_trait::async_trait;
 use blake2::{Blake2b512, Digest};
@@ -29,19 +30,43 @@ use tempfile::NamedTempFile;
 
 use crate::backend::{
     make_root_commit, Backend, BackendError, BackendResult, ChangeId, Commit, CommitId, Conflict,
-    ConflictId, ConflictTerm, FileId, MergedTreeId, MillisSinceEpoch, SecureSig, Signature,
-    SigningFn, SymlinkId, Timestamp, Tree, TreeId, TreeValue,
+    ConflictId, ConflictTerm, CopyRecord, FileId, MergedTreeId, MillisSinceEpoch, SecureSig,
+    Signature, SigningFn, SymlinkId, Timestamp, Tree, TreeId, TreeValue,
 };
 use crate::content_hash::blake2b_hash;
 use crate::file_util::persist_content_addressed_temp_file;
 use crate::index::Index;
 use crate::merge::MergeBuilder;
 use crate::object_id::ObjectId;
-use crate::repo_path::{RepoPath, RepoPathComponentBuf};
+use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponentBuf};
 
 const COMMIT_ID_LENGTH: usize = 64;
 const CHANGE_ID_LENGTH: usize = 16;
 
+// Large files are split where the rolling hash of the last bytes has this many
+// leading zero bits, so the chunks are about 1 MiB on average, and an edit only
+// changes the chunks around it.
+const CHUNK_HASH_BITS: u32 = 20;
+const MIN_CHUNK_SIZE: usize = 256 * 1024;
+const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Random value mixed into the rolling hash for each byte value.
+const CHUNK_GEAR: [u64; 256] = {
+    let mut gear = [0; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < gear.len() {
+        // splitmix64
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        gear[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    gear
+};
+
 fn map_not_found_err(err: std::io::Error, id: &impl ObjectId) -> BackendError {
     if err.kind() == std::io::ErrorKind::NotFound {
         BackendError::ObjectNotFound {
@@ -68,6 +93,7 @@ pub struct LocalBackend {
     root_commit_id: CommitId,
     root_change_id: ChangeId,
     empty_tree_id: TreeId,
+    large_file_threshold: Option<u64>,
 }
 
 impl LocalBackend {
@@ -81,6 +107,8 @@ impl LocalBackend {
         fs::create_dir(store_path.join("files")).unwrap();
         fs::create_dir(store_path.join("symlinks")).unwrap();
         fs::create_dir(store_path.join("conflicts")).unwrap();
+        fs::create_dir(store_path.join("large-files")).unwrap();
+        fs::create_dir(store_path.join("chunks")).unwrap();
         let backend = Self::load(store_path);
         let empty_tree_id = backend
             .write_tree(RepoPath::root(), &Tree::default())
@@ -98,9 +126,18 @@ impl LocalBackend {
             root_commit_id,
             root_change_id,
             empty_tree_id,
+            large_file_threshold: None,
         }
     }
 
+    /// Makes the files larger than `threshold` bytes be stored as chunks, so
+    /// the unchanged parts of large files are shared between their versions.
+    /// The file ids don't depend on how the files are stored.
+    pub fn with_large_file_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.large_file_threshold = threshold;
+        self
+    }
+
     fn file_path(&self, id: &FileId) -> PathBuf {
         self.path.join("files").join(id.hex())
     }
@@ -120,6 +157,88 @@ impl LocalBackend {
     fn conflict_path(&self, id: &ConflictId) -> PathBuf {
         self.path.join("conflicts").join(id.hex())
     }
+
+    fn large_file_path(&self, id: &FileId) -> PathBuf {
+        self.path.join("large-files").join(id.hex())
+    }
+
+    fn chunk_path(&self, id: &[u8]) -> PathBuf {
+        self.path.join("chunks").join(hex::encode(id))
+    }
+
+    fn read_large_file(&self, id: &FileId) -> BackendResult<Box<dyn Read>> {
+        let path = self.large_file_path(id);
+        let buf = fs::read(path).map_err(|err| map_not_found_err(err, id))?;
+
+        let proto = crate::protos::local_store::LargeFile::decode(&*buf).map_err(to_other_err)?;
+        Ok(Box::new(LargeFileReader {
+            chunk_paths: proto
+                .chunk_ids
+                .iter()
+                .map(|chunk_id| self.chunk_path(chunk_id))
+                .collect(),
+            current_chunk: None,
+        }))
+    }
+
+    /// Splits the `contents` into chunks as they're read, and writes a pointer
+    /// to the chunks instead of the file.
+    fn write_large_file(&self, contents: impl Read) -> BackendResult<FileId> {
+        // The directories don't exist in repos created by older versions
+        fs::create_dir_all(self.path.join("large-files")).map_err(to_other_err)?;
+        fs::create_dir_all(self.path.join("chunks")).map_err(to_other_err)?;
+        let mut reader = BufReader::with_capacity(1 << 16, contents);
+        let mut hasher = Blake2b512::new();
+        let mut chunk_ids = vec![];
+        let mut chunk = vec![];
+        while read_chunk(&mut reader, &mut chunk).map_err(to_other_err)? {
+            hasher.update(&chunk);
+            chunk_ids.push(self.write_chunk(&chunk)?);
+        }
+        let id = FileId::new(hasher.finalize().to_vec());
+
+        let pointer_file = NamedTempFile::new_in(&self.path).map_err(to_other_err)?;
+        let proto = crate::protos::local_store::LargeFile { chunk_ids };
+        pointer_file
+            .as_file()
+            .write_all(&proto.encode_to_vec())
+            .map_err(to_other_err)?;
+        persist_content_addressed_temp_file(pointer_file, self.large_file_path(&id))
+            .map_err(to_other_err)?;
+        Ok(id)
+    }
+
+    fn write_whole_file(&self, contents: &mut dyn Read) -> BackendResult<FileId> {
+        let temp_file = NamedTempFile::new_in(&self.path).map_err(to_other_err)?;
+        let mut encoder = zstd::Encoder::new(temp_file.as_file(), 0).map_err(to_other_err)?;
+        let mut hasher = Blake2b512::new();
+        let mut buff: Vec<u8> = vec![0; 1 << 14];
+        loop {
+            let bytes_read = contents.read(&mut buff).map_err(to_other_err)?;
+            if bytes_read == 0 {
+                break;
+            }
+            let bytes = &buff[..bytes_read];
+            encoder.write_all(bytes).map_err(to_other_err)?;
+            hasher.update(bytes);
+        }
+        encoder.finish().map_err(to_other_err)?;
+        let id = FileId::new(hasher.finalize().to_vec());
+
+        persist_content_addressed_temp_file(temp_file, self.file_path(&id))
+            .map_err(to_other_err)?;
+        Ok(id)
+    }
+
+    fn write_chunk(&self, chunk: &[u8]) -> BackendResult<Vec<u8>> {
+        let temp_file = NamedTempFile::new_in(&self.path).map_err(to_other_err)?;
+        zstd::stream::copy_encode(chunk, temp_file.as_file(), 0).map_err(to_other_err)?;
+        let id = Blake2b512::digest(chunk).to_vec();
+
+        persist_content_addressed_temp_file(temp_file, self.chunk_path(&id))
+            .map_err(to_other_err)?;
+        Ok(id)
+    }
 }
 
 #[async_trait]
@@ -158,30 +277,31 @@ impl Backend for LocalBackend {
 
     async fn read_file(&self, _path: &RepoPath, id: &FileId) -> BackendResult<Box<dyn Read>> {
         let path = self.file_path(id);
-        let file = File::open(path).map_err(|err| map_not_found_err(err, id))?;
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return self.read_large_file(id);
+            }
+            Err(err) => return Err(map_not_found_err(err, id)),
+        };
         Ok(Box::new(zstd::Decoder::new(file).map_err(to_other_err)?))
     }
 
     fn write_file(&self, _path: &RepoPath, contents: &mut dyn Read) -> BackendResult<FileId> {
-        let temp_file = NamedTempFile::new_in(&self.path).map_err(to_other_err)?;
-        let mut encoder = zstd::Encoder::new(temp_file.as_file(), 0).map_err(to_other_err)?;
-        let mut hasher = Blake2b512::new();
-        let mut buff: Vec<u8> = vec![0; 1 << 14];
-        loop {
-            let bytes_read = contents.read(&mut buff).map_err(to_other_err)?;
-            if bytes_read == 0 {
-                break;
-            }
-            let bytes = &buff[..bytes_read];
-            encoder.write_all(bytes).map_err(to_other_err)?;
-            hasher.update(bytes);
-        }
-        encoder.finish().map_err(to_other_err)?;
-        let id = FileId::new(hasher.finalize().to_vec());
-
-        persist_content_addressed_temp_file(temp_file, self.file_path(&id))
+        let Some(threshold) = self.large_file_threshold else {
+            return self.write_whole_file(contents);
+        };
+        // Buffer up to the threshold to tell whether the file is large
+        let mut head = vec![];
+        (&mut *contents)
+            .take(threshold.saturating_add(1))
+            .read_to_end(&mut head)
             .map_err(to_other_err)?;
-        Ok(id)
+        if head.len() as u64 > threshold {
+            self.write_large_file(head.as_slice().chain(contents))
+        } else {
+            self.write_whole_file(&mut head.as_slice())
+        }
     }
 
     async fn read_symlink(&self, _path: &RepoPath, id: &SymlinkId) -> Result<String, BackendError> {
@@ -306,6 +426,63 @@ impl Backend for LocalBackend {
     }
 }
 
+/// Reads the chunks of a large file, decompressing each chunk only once the
+/// previous ones have been read.
+struct LargeFileReader {
+    chunk_paths: VecDeque<PathBuf>,
+    current_chunk: Option<zstd::Decoder<'static, BufReader<File>>>,
+}
+
+impl Read for LargeFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(decoder) = &mut self.current_chunk {
+                let bytes_read = decoder.read(buf)?;
+                if bytes_read > 0 {
+                    return Ok(bytes_read);
+                }
+            }
+            let Some(path) = self.chunk_paths.pop_front() else {
+                return Ok(0);
+            };
+            self.current_chunk = Some(zstd::Decoder::new(File::open(path)?)?);
+        }
+    }
+}
+
+/// Reads the next content-defined chunk of the `reader` into `chunk`. Returns
+/// false at the end of the stream.
+fn read_chunk(reader: &mut impl BufRead, chunk: &mut Vec<u8>) -> io::Result<bool> {
+    chunk.clear();
+    let mut hash: u64 = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(!chunk.is_empty());
+        }
+        let mut cut = None;
+        for (i, &byte) in buf.iter().enumerate() {
+            hash = (hash << 1).wrapping_add(CHUNK_GEAR[usize::from(byte)]);
+            let len = chunk.len() + i + 1;
+            if len >= MAX_CHUNK_SIZE
+                || (len >= MIN_CHUNK_SIZE && hash >> (u64::BITS - CHUNK_HASH_BITS) == 0)
+            {
+                cut = Some(i + 1);
+                break;
+            }
+        }
+        let consumed = cut.unwrap_or(buf.len());
+        chunk.extend_from_slice(&buf[..consumed]);
+        reader.consume(consumed);
+        if cut.is_some() {
+            return Ok(true);
+        }
+    }
+}
+
 #[allow(unknown_lints)] // XXX FIXME (aseipp): nightly bogons; re-test this occasionally
 #[allow(clippy::assigning_clones)]
 pub fn commit_to_proto(commit: &Commit) -> crate::protos::local_store::Commit {
@@ -329,10 +506,11 @@ pub fn commit_to_proto(commit: &Commit) -> crate::protos::local_store::Commit {
     proto.description = commit.description.clone();
     proto.author = Some(signature_to_proto(&commit.author));
     proto.committer = Some(signature_to_proto(&commit.committer));
+    proto.copies = commit.copies.iter().map(copy_record_to_proto).collect();
     proto
 }
 
-fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
+pub(crate) fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
     // Note how .take() sets the secure_sig field to None before we encode the data.
     // Needs to be done first since proto is partially moved a bunch below
     let secure_sig = proto.secure_sig.take().map(|sig| SecureSig {
@@ -359,6 +537,11 @@ fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
         author: signature_from_proto(proto.author.unwrap_or_default()),
         committer: signature_from_proto(proto.committer.unwrap_or_default()),
         secure_sig,
+        copies: proto
+            .copies
+            .into_iter()
+            .map(copy_record_from_proto)
+            .collect(),
     }
 }
 
@@ -458,6 +641,22 @@ fn signature_from_proto(proto: crate::protos::local_store::commit::Signature) ->
     }
 }
 
+fn copy_record_to_proto(copy: &CopyRecord) -> crate::protos::local_store::commit::CopyRecord {
+    crate::protos::local_store::commit::CopyRecord {
+        target: copy.target.as_internal_file_string().to_owned(),
+        source: copy.source.as_internal_file_string().to_owned(),
+        source_file: copy.source_file.to_bytes(),
+    }
+}
+
+fn copy_record_from_proto(proto: crate::protos::local_store::commit::CopyRecord) -> CopyRecord {
+    CopyRecord {
+        target: RepoPathBuf::from_internal_string(proto.target),
+        source: RepoPathBuf::from_internal_string(proto.source),
+        source_file: FileId::new(proto.source_file),
+    }
+}
+
 fn conflict_to_proto(conflict: &Conflict) -> crate::protos::local_store::Conflict {
     let mut proto = crate::protos::local_store::Conflict::default();
     for term in &conflict.removes {
@@ -515,6 +714,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         // No parents
@@ -549,6 +749,61 @@ mod tests {
         assert_eq!(root_merge_commit, commit);
     }
 
+    #[test]
+    fn write_large_file() {
+        let temp_dir = testutils::new_temp_dir();
+        let store_path = temp_dir.path();
+        let path = RepoPath::from_internal_string("file");
+
+        let backend = LocalBackend::init(store_path).with_large_file_threshold(Some(1024));
+        let read_content = |id: &FileId| {
+            let mut content = vec![];
+            let mut reader = backend.read_file(path, id).block_on().unwrap();
+            reader.read_to_end(&mut content).unwrap();
+            content
+        };
+        let count_chunks = || fs::read_dir(store_path.join("chunks")).unwrap().count();
+
+        // Small files are stored whole
+        let small_id = backend.write_file(path, &mut &b"small"[..]).unwrap();
+        assert!(backend.file_path(&small_id).exists());
+        assert_eq!(read_content(&small_id), b"small");
+
+        // Pseudo-random content is split into several chunks
+        let mut state: u64 = 1;
+        let mut content: Vec<u8> = std::iter::repeat_with(|| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .take(6 * 1024 * 1024)
+        .collect();
+        let id = backend.write_file(path, &mut content.as_slice()).unwrap();
+        assert!(!backend.file_path(&id).exists());
+        assert!(backend.large_file_path(&id).exists());
+        assert!(read_content(&id) == content);
+        let num_chunks = count_chunks();
+        assert!(num_chunks >= 2);
+
+        // The id doesn't depend on how the file is stored
+        let other_temp_dir = testutils::new_temp_dir();
+        let other_backend = LocalBackend::init(other_temp_dir.path());
+        assert_eq!(
+            other_backend
+                .write_file(path, &mut content.as_slice())
+                .unwrap(),
+            id
+        );
+
+        // Only the last chunk changes if the end of the file is modified
+        *content.last_mut().unwrap() ^= 1;
+        let modified_id = backend.write_file(path, &mut content.as_slice()).unwrap();
+        assert_ne!(modified_id, id);
+        assert!(read_content(&modified_id) == content);
+        assert_eq!(count_chunks(), num_chunks + 1);
+    }
+
     fn create_signature() -> Signature {
         Signature {
             name: "Someone".to_string(),
@@ -15,6 +15,7 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::error::Error;
 use std::fs::{File, Metadata, OpenOptions};
@@ -25,7 +26,7 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::Arc;
-use std::time::UNIX_EPOCH;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, iter, mem, slice};
 
 use futures::StreamExt;
@@ -43,8 +44,10 @@ use crate::backend::{
     BackendError, FileId, MergedTreeId, MillisSinceEpoch, SymlinkId, TreeId, TreeValue,
 };
 use crate::commit::Commit;
-use crate::conflicts::{self, materialize_tree_value, MaterializedTreeValue};
-use crate::file_util::{check_symlink_support, try_symlink};
+use crate::conflicts::{
+    self, materialize_tree_value, ConflictMarkerOptions, MaterializedTreeValue,
+};
+use crate::file_util::{self, check_symlink_support, try_symlink};
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::watchman;
 use crate::fsmonitor::FsmonitorKind;
@@ -59,11 +62,12 @@ use crate::object_id::ObjectId;
 use crate::op_store::{OperationId, WorkspaceId};
 use crate::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent};
 use crate::settings::HumanByteSize;
+use crate::sparse::{read_sparse_profile, sparse_matcher, SparsePattern};
 use crate::store::Store;
 use crate::tree::Tree;
 use crate::working_copy::{
-    CheckoutError, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError, SnapshotOptions,
-    SnapshotProgress, WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
+    CheckoutError, CheckoutOptions, CheckoutStats, LockedWorkingCopy, ResetError, SnapshotError,
+    SnapshotOptions, SnapshotProgress, WorkingCopy, WorkingCopyFactory, WorkingCopyStateError,
 };
 
 #[cfg(unix)]
@@ -283,14 +287,111 @@ impl<'a> IntoIterator for FileStates<'a> {
     }
 }
 
+/// Cache of the contents of the files written by checkouts, keyed by file id.
+///
+/// The files are cloned from the cache instead of being read from the store
+/// and written again, and the new files are cloned into the cache, on
+/// filesystems which support copy-on-write clones. The least recently used
+/// files are evicted when the cache grows over its maximum size. The cache can
+/// be deleted at any time.
+struct FileCache {
+    dir: PathBuf,
+    /// Cleared when a clone fails, since the filesystem then likely doesn't
+    /// support clones.
+    enabled: Cell<bool>,
+}
+
+impl FileCache {
+    fn new(dir: PathBuf) -> Self {
+        let enabled = file_util::create_or_reuse_dir(&dir).is_ok();
+        FileCache {
+            dir,
+            enabled: Cell::new(enabled),
+        }
+    }
+
+    fn cached_path(&self, id: &FileId) -> Option<PathBuf> {
+        if !self.enabled.get() {
+            return None;
+        }
+        let path = self.dir.join(id.hex());
+        path.is_file().then_some(path)
+    }
+
+    /// Clones the file at `cached_path` to `disk_path`. Returns false if the
+    /// clone failed, in which case the cache is disabled.
+    fn clone_to(&self, cached_path: &Path, disk_path: &Path) -> bool {
+        let cloned = file_util::try_clone_file(cached_path, disk_path).is_ok();
+        if cloned {
+            // The modification time tracks the last use for the eviction
+            OpenOptions::new()
+                .write(true)
+                .open(cached_path)
+                .and_then(|file| file.set_modified(SystemTime::now()))
+                .ok();
+        } else {
+            self.enabled.set(false);
+        }
+        cloned
+    }
+
+    /// Clones the file at `disk_path`, which has the contents of `id`, into
+    /// the cache.
+    fn insert(&self, id: &FileId, disk_path: &Path) {
+        if !self.enabled.get() {
+            return;
+        }
+        let temp_path = self.dir.join(format!("{}.tmp", id.hex()));
+        fs::remove_file(&temp_path).ok();
+        if file_util::try_clone_file(disk_path, &temp_path).is_err()
+            || fs::rename(&temp_path, self.dir.join(id.hex())).is_err()
+        {
+            fs::remove_file(&temp_path).ok();
+            self.enabled.set(false);
+        }
+    }
+
+    /// Removes the least recently used files until the total size of the
+    /// cache is at most `max_size`. Errors are ignored, since the cache is
+    /// only an optimization.
+    fn evict(&self, max_size: u64) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                let last_used = metadata.modified().ok()?;
+                Some((last_used, metadata.len(), entry.path()))
+            })
+            .collect_vec();
+        let mut total_size: u64 = files.iter().map(|(_, size, _)| size).sum();
+        if total_size <= max_size {
+            return;
+        }
+        files.sort_unstable();
+        for (_, size, path) in files {
+            if total_size <= max_size {
+                break;
+            }
+            if fs::remove_file(path).is_ok() {
+                total_size -= size;
+            }
+        }
+    }
+}
+
 pub struct TreeState {
     store: Arc<Store>,
     working_copy_path: PathBuf,
     state_path: PathBuf,
     tree_id: MergedTreeId,
     file_states: FileStatesMap,
-    // Currently only path prefixes
-    sparse_patterns: Vec<RepoPathBuf>,
+    sparse_patterns: Vec<SparsePattern>,
+    /// The sparse profile `sparse_patterns` are read from and kept in sync with
+    /// on checkout.
+    sparse_profile: Option<String>,
     own_mtime: MillisSinceEpoch,
     symlink_support: bool,
 
@@ -369,16 +470,36 @@ fn is_file_state_entries_proto_unique_and_sorted(
 
 fn sparse_patterns_from_proto(
     proto: Option<&crate::protos::working_copy::SparsePatterns>,
-) -> Vec<RepoPathBuf> {
+) -> Vec<SparsePattern> {
     let mut sparse_patterns = vec![];
     if let Some(proto_sparse_patterns) = proto {
+        // The globs were validated before they were saved.
+        let parse_globs = |globs: &[String]| {
+            globs
+                .iter()
+                .filter_map(|glob| glob::Pattern::new(glob).ok())
+                .collect_vec()
+        };
         for prefix in &proto_sparse_patterns.prefixes {
-            sparse_patterns.push(RepoPathBuf::from_internal_string(prefix));
+            sparse_patterns.push(SparsePattern::Prefix(RepoPathBuf::from_internal_string(
+                prefix,
+            )));
+        }
+        for glob in parse_globs(&proto_sparse_patterns.globs) {
+            sparse_patterns.push(SparsePattern::Glob(glob));
+        }
+        for prefix in &proto_sparse_patterns.excluded_prefixes {
+            sparse_patterns.push(SparsePattern::ExcludePrefix(
+                RepoPathBuf::from_internal_string(prefix),
+            ));
+        }
+        for glob in parse_globs(&proto_sparse_patterns.excluded_globs) {
+            sparse_patterns.push(SparsePattern::ExcludeGlob(glob));
         }
     } else {
         // For compatibility with old working copies.
         // TODO: Delete this is late 2022 or so.
-        sparse_patterns.push(RepoPathBuf::root());
+        sparse_patterns.push(SparsePattern::Prefix(RepoPathBuf::root()));
     }
     sparse_patterns
 }
@@ -424,6 +545,35 @@ fn create_parent_dirs(
     Ok(false)
 }
 
+/// Moves the file or directory at `disk_path` to its `repo_path` in
+/// `backup_dir`. If there's already a backup there, a numbered suffix is added
+/// to the name of the new one.
+fn back_up_file(
+    backup_dir: &Path,
+    repo_path: &RepoPath,
+    disk_path: &Path,
+) -> Result<(), CheckoutError> {
+    let mut backup_path = repo_path.to_fs_path(backup_dir);
+    let file_name = backup_path.file_name().unwrap().to_owned();
+    let mut suffix = 0;
+    while backup_path.symlink_metadata().is_ok() {
+        suffix += 1;
+        let mut new_file_name = file_name.clone();
+        new_file_name.push(format!(".~{suffix}~"));
+        backup_path.set_file_name(new_file_name);
+    }
+    fs::create_dir_all(backup_path.parent().unwrap())
+        .and_then(|()| fs::rename(disk_path, &backup_path))
+        .map_err(|err| CheckoutError::Other {
+            message: format!(
+                "Failed to move {} to {}",
+                disk_path.display(),
+                backup_path.display()
+            ),
+            err: err.into(),
+        })
+}
+
 fn mtime_from_metadata(metadata: &Metadata) -> MillisSinceEpoch {
     let time = metadata
         .modified()
@@ -518,12 +668,16 @@ impl TreeState {
         self.file_states.all()
     }
 
-    pub fn sparse_patterns(&self) -> &Vec<RepoPathBuf> {
+    pub fn sparse_patterns(&self) -> &Vec<SparsePattern> {
         &self.sparse_patterns
     }
 
+    pub fn sparse_profile(&self) -> Option<&str> {
+        self.sparse_profile.as_deref()
+    }
+
     fn sparse_matcher(&self) -> Box<dyn Matcher> {
-        Box::new(PrefixMatcher::new(&self.sparse_patterns))
+        sparse_matcher(&self.sparse_patterns)
     }
 
     pub fn init(
@@ -546,7 +700,8 @@ impl TreeState {
             state_path,
             tree_id,
             file_states: FileStatesMap::new(),
-            sparse_patterns: vec![RepoPathBuf::root()],
+            sparse_patterns: vec![SparsePattern::Prefix(RepoPathBuf::root())],
+            sparse_profile: None,
             own_mtime: MillisSinceEpoch(0),
             symlink_support: check_symlink_support().unwrap_or(false),
             watchman_clock: None,
@@ -611,13 +766,18 @@ impl TreeState {
         }
         self.file_states = FileStatesMap::from_proto_unsorted(proto.file_states);
         self.sparse_patterns = sparse_patterns_from_proto(proto.sparse_patterns.as_ref());
+        self.sparse_profile = proto
+            .sparse_patterns
+            .as_ref()
+            .map(|proto| proto.profile.clone())
+            .filter(|profile| !profile.is_empty());
         self.watchman_clock = proto.watchman_clock;
         Ok(())
     }
 
     #[allow(unknown_lints)] // XXX FIXME (aseipp): nightly bogons; re-test this occasionally
     #[allow(clippy::assigning_clones)]
-    fn save(&mut self) -> Result<(), TreeStateError> {
+    pub(crate) fn save(&mut self) -> Result<(), TreeStateError> {
         let mut proto: crate::protos::working_copy::TreeState = Default::default();
         match &self.tree_id {
             MergedTreeId::Legacy(tree_id) => {
@@ -630,11 +790,21 @@ impl TreeState {
 
         proto.file_states = self.file_states.data.clone();
         let mut sparse_patterns = crate::protos::working_copy::SparsePatterns::default();
-        for path in &self.sparse_patterns {
-            sparse_patterns
-                .prefixes
-                .push(path.as_internal_file_string().to_owned());
+        for pattern in &self.sparse_patterns {
+            match pattern {
+                SparsePattern::Prefix(path) => sparse_patterns
+                    .prefixes
+                    .push(path.as_internal_file_string().to_owned()),
+                SparsePattern::Glob(glob) => sparse_patterns.globs.push(glob.as_str().to_owned()),
+                SparsePattern::ExcludePrefix(path) => sparse_patterns
+                    .excluded_prefixes
+                    .push(path.as_internal_file_string().to_owned()),
+                SparsePattern::ExcludeGlob(glob) => sparse_patterns
+                    .excluded_globs
+                    .push(glob.as_str().to_owned()),
+            }
         }
+        sparse_patterns.profile = self.sparse_profile.clone().unwrap_or_default();
         proto.sparse_patterns = Some(sparse_patterns);
         proto.watchman_clock = self.watchman_clock.clone();
 
@@ -739,6 +909,8 @@ impl TreeState {
             fsmonitor_kind,
             progress,
             max_new_file_size,
+            exclude_paths,
+            conflict_marker_options,
         } = options;
 
         let sparse_matcher = self.sparse_matcher();
@@ -753,8 +925,11 @@ impl TreeState {
             None => &EverythingMatcher,
             Some(fsmonitor_matcher) => fsmonitor_matcher.as_ref(),
         };
+        // The excluded paths are neither visited nor considered deleted
+        let exclude_matcher = PrefixMatcher::new(&exclude_paths);
+        let fsmonitor_matcher = DifferenceMatcher::new(fsmonitor_matcher, &exclude_matcher);
 
-        let matcher = IntersectionMatcher::new(sparse_matcher.as_ref(), fsmonitor_matcher);
+        let matcher = IntersectionMatcher::new(sparse_matcher.as_ref(), &fsmonitor_matcher);
         if matcher.visit(RepoPath::root()).is_nothing() {
             // No need to iterate file states to build empty deleted_files.
             self.watchman_clock = watchman_clock;
@@ -782,6 +957,7 @@ impl TreeState {
                 directory_to_visit,
                 progress,
                 max_new_file_size,
+                conflict_marker_options,
             )
         })?;
 
@@ -855,6 +1031,7 @@ impl TreeState {
         directory_to_visit: DirectoryToVisit,
         progress: Option<&SnapshotProgress>,
         max_new_file_size: u64,
+        conflict_marker_options: ConflictMarkerOptions,
     ) -> Result<(), SnapshotError> {
         let DirectoryToVisit {
             dir,
@@ -935,6 +1112,7 @@ impl TreeState {
                                     Some(&current_file_state),
                                     current_tree,
                                     &new_file_state,
+                                    conflict_marker_options,
                                 )?;
                                 if let Some(tree_value) = update {
                                     tree_entries_tx
@@ -964,6 +1142,7 @@ impl TreeState {
                             directory_to_visit,
                             progress,
                             max_new_file_size,
+                            conflict_marker_options,
                         )?;
                     }
                 } else if matcher.matches(&path) {
@@ -997,6 +1176,7 @@ impl TreeState {
                                 maybe_current_file_state.as_ref(),
                                 current_tree,
                                 &new_file_state,
+                                conflict_marker_options,
                             )?;
                             if let Some(tree_value) = update {
                                 tree_entries_tx.send((path.clone(), tree_value)).ok();
@@ -1065,6 +1245,7 @@ impl TreeState {
         maybe_current_file_state: Option<&FileState>,
         current_tree: &MergedTree,
         new_file_state: &FileState,
+        conflict_marker_options: ConflictMarkerOptions,
     ) -> Result<Option<MergedTreeValue>, SnapshotError> {
         let clean = match maybe_current_file_state {
             None => {
@@ -1098,6 +1279,7 @@ impl TreeState {
                     &disk_path,
                     &current_tree_values,
                     executable,
+                    conflict_marker_options,
                 )?,
                 FileType::Symlink => {
                     let id = self.write_symlink_to_store(repo_path, &disk_path)?;
@@ -1119,6 +1301,7 @@ impl TreeState {
         disk_path: &Path,
         current_tree_values: &MergedTreeValue,
         executable: FileExecutableFlag,
+        conflict_marker_options: ConflictMarkerOptions,
     ) -> Result<MergedTreeValue, SnapshotError> {
         // If the file contained a conflict before and is now a normal file on disk, we
         // try to parse any conflict markers in the file into a conflict.
@@ -1147,6 +1330,7 @@ impl TreeState {
                 self.store.as_ref(),
                 repo_path,
                 &content,
+                conflict_marker_options,
             )
             .block_on()?;
             match new_file_ids.into_resolved() {
@@ -1203,6 +1387,29 @@ impl TreeState {
         Ok(FileState::for_file(executable, size, &metadata))
     }
 
+    /// Writes the file at `disk_path` as a clone of the file at `cached_path`,
+    /// or as a copy if the filesystem doesn't support clones.
+    fn write_file_from_cache(
+        &self,
+        file_cache: &FileCache,
+        cached_path: &Path,
+        disk_path: &Path,
+        executable: bool,
+    ) -> Result<FileState, CheckoutError> {
+        if !file_cache.clone_to(cached_path, disk_path) {
+            let mut cached_file = File::open(cached_path).map_err(|err| CheckoutError::Other {
+                message: format!("Failed to open cached file {}", cached_path.display()),
+                err: err.into(),
+            })?;
+            return self.write_file(disk_path, &mut cached_file, executable);
+        }
+        self.set_executable(disk_path, executable)?;
+        let metadata = disk_path
+            .symlink_metadata()
+            .map_err(|err| checkout_error_for_stat_error(err, disk_path))?;
+        Ok(FileState::for_file(executable, metadata.len(), &metadata))
+    }
+
     fn write_symlink(&self, disk_path: &Path, target: String) -> Result<FileState, CheckoutError> {
         let target = PathBuf::from(&target);
         try_symlink(&target, disk_path).map_err(|err| CheckoutError::Other {
@@ -1246,6 +1453,24 @@ impl TreeState {
         Ok(FileState::for_file(false, size, &metadata))
     }
 
+    /// Returns whether the file at `disk_path` was changed since it was last
+    /// snapshotted or checked out.
+    fn is_modified_on_disk(&self, path: &RepoPath, disk_path: &Path) -> bool {
+        let Some(current_state) = self.file_states.all().get(path) else {
+            return false;
+        };
+        // The state of the files written by `reset()` is unknown
+        if current_state.mtime == MillisSinceEpoch(0)
+            || current_state.file_type == FileType::GitSubmodule
+        {
+            return false;
+        }
+        match disk_path.symlink_metadata() {
+            Ok(metadata) => file_state(&metadata) != Some(current_state),
+            Err(_) => false,
+        }
+    }
+
     #[cfg_attr(windows, allow(unused_variables))]
     fn set_executable(&self, disk_path: &Path, executable: bool) -> Result<(), CheckoutError> {
         #[cfg(unix)]
@@ -1257,23 +1482,55 @@ impl TreeState {
         Ok(())
     }
 
-    pub fn check_out(&mut self, new_tree: &MergedTree) -> Result<CheckoutStats, CheckoutError> {
+    pub fn check_out(
+        &mut self,
+        new_tree: &MergedTree,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
         let old_tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
                 source: Box::new(err),
             },
             other => CheckoutError::InternalBackendError(other),
         })?;
-        let stats = self
-            .update(&old_tree, new_tree, self.sparse_matcher().as_ref())
+        let mut stats = self
+            .update(&old_tree, new_tree, self.sparse_matcher().as_ref(), options)
             .block_on()?;
         self.tree_id = new_tree.id();
+        if let Some(name) = self.sparse_profile.clone() {
+            // If the new tree doesn't have a usable profile, keep the current
+            // patterns until a later checkout brings it back.
+            if let Ok(sparse_patterns) = read_sparse_profile(new_tree, &name) {
+                if sparse_patterns != self.sparse_patterns {
+                    let sparse_stats = self.update_sparse_patterns(sparse_patterns, options)?;
+                    stats.added_files += sparse_stats.added_files;
+                    stats.removed_files += sparse_stats.removed_files;
+                    stats.skipped_files += sparse_stats.skipped_files;
+                    stats.skipped_paths.extend(sparse_stats.skipped_paths);
+                    stats.backed_up_paths.extend(sparse_stats.backed_up_paths);
+                }
+            }
+        }
         Ok(stats)
     }
 
     pub fn set_sparse_patterns(
         &mut self,
-        sparse_patterns: Vec<RepoPathBuf>,
+        sparse_patterns: Vec<SparsePattern>,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let stats = self.update_sparse_patterns(sparse_patterns, options)?;
+        self.sparse_profile = None;
+        Ok(stats)
+    }
+
+    /// Replaces the sparse patterns with the ones from the sparse profile
+    /// `name` in the current tree. The patterns are updated from the profile on
+    /// later checkouts until other patterns are set.
+    pub fn set_sparse_profile(
+        &mut self,
+        name: &str,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
         let tree = self.current_tree().map_err(|err| match err {
             err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
@@ -1281,34 +1538,95 @@ impl TreeState {
             },
             other => CheckoutError::InternalBackendError(other),
         })?;
-        let old_matcher = PrefixMatcher::new(&self.sparse_patterns);
-        let new_matcher = PrefixMatcher::new(&sparse_patterns);
+        let sparse_patterns =
+            read_sparse_profile(&tree, name).map_err(|err| CheckoutError::Other {
+                message: "Failed to read the sparse profile".to_string(),
+                err: err.into(),
+            })?;
+        let stats = self.update_sparse_patterns(sparse_patterns, options)?;
+        self.sparse_profile = Some(name.to_owned());
+        Ok(stats)
+    }
+
+    fn update_sparse_patterns(
+        &mut self,
+        sparse_patterns: Vec<SparsePattern>,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let tree = self.current_tree().map_err(|err| match err {
+            err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
+                source: Box::new(err),
+            },
+            other => CheckoutError::InternalBackendError(other),
+        })?;
+        let old_matcher = sparse_matcher(&self.sparse_patterns);
+        let new_matcher = sparse_matcher(&sparse_patterns);
         let added_matcher = DifferenceMatcher::new(&new_matcher, &old_matcher);
         let removed_matcher = DifferenceMatcher::new(&old_matcher, &new_matcher);
         let empty_tree = MergedTree::resolved(Tree::null(self.store.clone(), RepoPathBuf::root()));
-        let added_stats = self.update(&empty_tree, &tree, &added_matcher).block_on()?;
+        let added_stats = self
+            .update(&empty_tree, &tree, &added_matcher, options)
+            .block_on()?;
         let removed_stats = self
-            .update(&tree, &empty_tree, &removed_matcher)
+            .update(&tree, &empty_tree, &removed_matcher, options)
             .block_on()?;
         self.sparse_patterns = sparse_patterns;
         assert_eq!(added_stats.updated_files, 0);
         assert_eq!(added_stats.removed_files, 0);
         assert_eq!(removed_stats.updated_files, 0);
         assert_eq!(removed_stats.added_files, 0);
-        assert_eq!(removed_stats.skipped_files, 0);
         Ok(CheckoutStats {
             updated_files: 0,
             added_files: added_stats.added_files,
             removed_files: removed_stats.removed_files,
-            skipped_files: added_stats.skipped_files,
+            skipped_files: added_stats.skipped_files + removed_stats.skipped_files,
+            skipped_paths: [added_stats.skipped_paths, removed_stats.skipped_paths].concat(),
+            backed_up_paths: [added_stats.backed_up_paths, removed_stats.backed_up_paths].concat(),
         })
     }
 
+    /// Writes again the tracked files which were modified or deleted since the
+    /// last checkout, without snapshotting them. Untracked files are kept.
+    pub fn discard_changes(
+        &mut self,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let tree = self.current_tree().map_err(|err| match err {
+            err @ BackendError::ObjectNotFound { .. } => CheckoutError::SourceNotFound {
+                source: Box::new(err),
+            },
+            other => CheckoutError::InternalBackendError(other),
+        })?;
+        let mut modified_paths = vec![];
+        for (path, state) in self.file_states.all() {
+            if state.file_type == FileType::GitSubmodule {
+                continue;
+            }
+            let disk_path = path.to_fs_path(&self.working_copy_path);
+            // The state of the files written by `reset()` is unknown
+            let modified = state.mtime == MillisSinceEpoch(0)
+                || match disk_path.symlink_metadata() {
+                    Ok(metadata) => file_state(&metadata) != Some(state),
+                    Err(_) => true,
+                };
+            if modified {
+                fs::remove_file(&disk_path).ok();
+                modified_paths.push(path.to_owned());
+            }
+        }
+        // The files are written as if they were added, since they were removed
+        let matcher = FilesMatcher::new(&modified_paths);
+        let empty_tree = MergedTree::resolved(Tree::null(self.store.clone(), RepoPathBuf::root()));
+        self.update(&empty_tree, &tree, &matcher, options)
+            .block_on()
+    }
+
     async fn update(
         &mut self,
         old_tree: &MergedTree,
         new_tree: &MergedTree,
         matcher: &dyn Matcher,
+        options: &CheckoutOptions<'_>,
     ) -> Result<CheckoutStats, CheckoutError> {
         // TODO: maybe it's better not include the skipped counts in the "intended"
         // counts
@@ -1317,17 +1635,46 @@ impl TreeState {
             added_files: 0,
             removed_files: 0,
             skipped_files: 0,
+            skipped_paths: vec![],
+            backed_up_paths: vec![],
         };
         let mut changed_file_states = Vec::new();
         let mut deleted_files = HashSet::new();
+        let file_cache = options
+            .use_reflinks
+            .then(|| FileCache::new(self.state_path.join("file_cache")));
         let mut diff_stream = Box::pin(
             old_tree
                 .diff_stream(new_tree, matcher)
                 .map(|(path, diff)| async {
                     match diff {
                         Ok((before, after)) => {
-                            let result = materialize_tree_value(&self.store, &path, after).await;
-                            (path, result.map(|value| (before.is_present(), value)))
+                            // Files in the cache don't need to be read from the store
+                            if let Some(Some(TreeValue::File { id, executable })) =
+                                after.as_resolved()
+                            {
+                                if let Some(cached_path) =
+                                    file_cache.as_ref().and_then(|cache| cache.cached_path(id))
+                                {
+                                    let value = MaterializedTreeValue::File {
+                                        id: id.clone(),
+                                        executable: *executable,
+                                        reader: Box::new(std::io::empty()),
+                                    };
+                                    return (
+                                        path,
+                                        Ok((before.is_present(), value, Some(cached_path))),
+                                    );
+                                }
+                            }
+                            let result = materialize_tree_value(
+                                &self.store,
+                                &path,
+                                after,
+                                options.conflict_marker_options,
+                            )
+                            .await;
+                            (path, result.map(|value| (before.is_present(), value, None)))
                         }
                         Err(err) => (path, Err(err)),
                     }
@@ -1335,7 +1682,7 @@ impl TreeState {
                 .buffered(self.store.concurrency().max(1)),
         );
         while let Some((path, data)) = diff_stream.next().await {
-            let (present_before, after) = data?;
+            let (present_before, after, cached_path) = data?;
             if after.is_absent() {
                 stats.removed_files += 1;
             } else if !present_before {
@@ -1343,24 +1690,44 @@ impl TreeState {
             } else {
                 stats.updated_files += 1;
             }
+            if let Some(progress) = options.progress {
+                progress(&path, &stats);
+            }
             let disk_path = path.to_fs_path(&self.working_copy_path);
 
-            if present_before {
+            // Don't clobber untracked files or changes made since the last snapshot
+            let in_the_way = if present_before {
+                self.is_modified_on_disk(&path, &disk_path)
+            } else {
+                disk_path.exists()
+            };
+            if in_the_way {
+                if let Some(backup_dir) = &options.backup_dir {
+                    back_up_file(backup_dir, &path, &disk_path)?;
+                    stats.backed_up_paths.push(path.clone());
+                } else {
+                    stats.skipped_files += 1;
+                    stats.skipped_paths.push(path.clone());
+                    if after.is_absent() {
+                        // Keep the file, but stop tracking it
+                        deleted_files.insert(path);
+                    } else {
+                        changed_file_states.push((path, FileState::placeholder()));
+                    }
+                    continue;
+                }
+            } else if present_before {
                 fs::remove_file(&disk_path).ok();
-            } else if disk_path.exists() {
-                changed_file_states.push((path, FileState::placeholder()));
-                stats.skipped_files += 1;
-                continue;
             }
             if after.is_present() {
                 let skip = create_parent_dirs(&self.working_copy_path, &path)?;
                 if skip {
-                    changed_file_states.push((path, FileState::placeholder()));
                     stats.skipped_files += 1;
+                    stats.skipped_paths.push(path.clone());
+                    changed_file_states.push((path, FileState::placeholder()));
                     continue;
                 }
             }
-            // TODO: Check that the file has not changed before overwriting/removing it.
             let file_state = match after {
                 MaterializedTreeValue::Absent => {
                     let mut parent_dir = disk_path.parent().unwrap();
@@ -1374,10 +1741,26 @@ impl TreeState {
                     continue;
                 }
                 MaterializedTreeValue::File {
+                    id,
                     executable,
                     mut reader,
-                    ..
-                } => self.write_file(&disk_path, &mut reader, executable)?,
+                } => {
+                    let file_cache = file_cache.as_ref();
+                    if let Some(cached_path) = cached_path {
+                        self.write_file_from_cache(
+                            file_cache.unwrap(),
+                            &cached_path,
+                            &disk_path,
+                            executable,
+                        )?
+                    } else {
+                        let file_state = self.write_file(&disk_path, &mut reader, executable)?;
+                        if let Some(file_cache) = file_cache {
+                            file_cache.insert(&id, &disk_path);
+                        }
+                        file_state
+                    }
+                }
                 MaterializedTreeValue::Symlink { id: _, target } => {
                     if self.symlink_support {
                         self.write_symlink(&disk_path, target)?
@@ -1398,6 +1781,9 @@ impl TreeState {
             };
             changed_file_states.push((path, file_state));
         }
+        if let Some(file_cache) = &file_cache {
+            file_cache.evict(options.reflink_cache_size);
+        }
         self.file_states
             .merge_in(changed_file_states, &deleted_files);
         Ok(stats)
@@ -1513,10 +1899,14 @@ impl WorkingCopy for LocalWorkingCopy {
         Ok(self.tree_state()?.current_tree_id())
     }
 
-    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError> {
+    fn sparse_patterns(&self) -> Result<&[SparsePattern], WorkingCopyStateError> {
         Ok(self.tree_state()?.sparse_patterns())
     }
 
+    fn sparse_profile(&self) -> Result<Option<&str>, WorkingCopyStateError> {
+        Ok(self.tree_state()?.sparse_profile())
+    }
+
     fn start_mutation(&self) -> Result<Box<dyn LockedWorkingCopy>, WorkingCopyStateError> {
         let lock_path = self.state_path.join("working_copy.lock");
         let lock = FileLock::lock(lock_path);
@@ -1751,7 +2141,11 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         Ok(tree_state.current_tree_id().clone())
     }
 
-    fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError> {
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
         // TODO: Write a "pending_checkout" file with the new TreeId so we can
         // continue an interrupted update if we find such a file.
         let new_tree = commit.tree()?;
@@ -1762,7 +2156,7 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
                 message: "Failed to load the working copy state".to_string(),
                 err: err.into(),
             })?
-            .check_out(&new_tree)?;
+            .check_out(&new_tree, options)?;
         self.tree_state_dirty = true;
         Ok(stats)
     }
@@ -1795,13 +2189,18 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         Ok(())
     }
 
-    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError> {
+    fn sparse_patterns(&self) -> Result<&[SparsePattern], WorkingCopyStateError> {
         self.wc.sparse_patterns()
     }
 
+    fn sparse_profile(&self) -> Result<Option<&str>, WorkingCopyStateError> {
+        self.wc.sparse_profile()
+    }
+
     fn set_sparse_patterns(
         &mut self,
-        new_sparse_patterns: Vec<RepoPathBuf>,
+        new_sparse_patterns: Vec<SparsePattern>,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
         // TODO: Write a "pending_checkout" file with new sparse patterns so we can
         // continue an interrupted update if we find such a file.
@@ -1812,7 +2211,24 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
                 message: "Failed to load the working copy state".to_string(),
                 err: err.into(),
             })?
-            .set_sparse_patterns(new_sparse_patterns)?;
+            .set_sparse_patterns(new_sparse_patterns, options)?;
+        self.tree_state_dirty = true;
+        Ok(stats)
+    }
+
+    fn set_sparse_profile(
+        &mut self,
+        name: &str,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError> {
+        let stats = self
+            .wc
+            .tree_state_mut()
+            .map_err(|err| CheckoutError::Other {
+                message: "Failed to load the working copy state".to_string(),
+                err: err.into(),
+            })?
+            .set_sparse_profile(name, options)?;
         self.tree_state_dirty = true;
         Ok(stats)
     }
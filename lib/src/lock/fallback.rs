@@ -62,6 +62,24 @@ impl FileLock {
             Ok(file_lock) => file_lock,
         }
     }
+
+    /// Takes the lock if the lock file doesn't exist. A lock file left behind
+    /// by a process which was killed can't be told apart from a held lock.
+    pub fn try_lock(path: PathBuf) -> Option<FileLock> {
+        let mut options = OpenOptions::new();
+        options.create_new(true);
+        options.write(true);
+        match options.open(&path) {
+            Ok(file) => Some(FileLock { path, _file: file }),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => None,
+            Err(err) if cfg!(windows) && err.kind() == std::io::ErrorKind::PermissionDenied => None,
+            Err(err) => panic!(
+                "failed to create lock file {}: {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
 }
 
 impl Drop for FileLock {
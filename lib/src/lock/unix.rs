@@ -46,6 +46,27 @@ impl FileLock {
             return Self { path, file };
         }
     }
+
+    /// Takes the lock if it's not held by anyone, including when the lockfile
+    /// was left behind by a process which was killed.
+    pub fn try_lock(path: PathBuf) -> Option<FileLock> {
+        loop {
+            let file = File::create(&path).expect("failed to open lockfile");
+            match rustix::fs::flock(&file, FlockOperation::NonBlockingLockExclusive) {
+                Ok(()) => {}
+                Err(rustix::io::Errno::WOULDBLOCK) => return None,
+                Err(err) => panic!("failed to lock lockfile: {err}"),
+            }
+
+            let stat = rustix::fs::fstat(&file).expect("failed to stat lockfile");
+            if stat.st_nlink == 0 {
+                // Lockfile was deleted by the previous holder, see `lock()`
+                continue;
+            }
+
+            return Some(Self { path, file });
+        }
+    }
 }
 
 impl Drop for FileLock {
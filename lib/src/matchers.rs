@@ -183,6 +183,50 @@ impl Matcher for PrefixMatcher {
     }
 }
 
+/// Matches file paths with glob patterns.
+///
+/// Patterns are matched against the whole path from the root, and a wildcard
+/// doesn't match a path separator unless it's `**`.
+#[derive(Clone, Debug)]
+pub struct FileGlobsMatcher {
+    globs: Vec<glob::Pattern>,
+}
+
+impl FileGlobsMatcher {
+    pub fn new(globs: impl IntoIterator<Item = glob::Pattern>) -> Self {
+        FileGlobsMatcher {
+            globs: globs.into_iter().collect(),
+        }
+    }
+}
+
+impl Matcher for FileGlobsMatcher {
+    fn matches(&self, file: &RepoPath) -> bool {
+        let options = glob::MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let path = file.as_internal_file_string();
+        self.globs
+            .iter()
+            .any(|glob| glob.matches_with(path, options))
+    }
+
+    fn visit(&self, _dir: &RepoPath) -> Visit {
+        if self.globs.is_empty() {
+            Visit::Nothing
+        } else {
+            // TODO: Visit only the directories matching the literal prefixes of
+            // the patterns.
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        }
+    }
+}
+
 /// Matches paths that are matched by any of the input matchers.
 #[derive(Clone, Debug)]
 pub struct UnionMatcher<M1, M2> {
@@ -634,6 +678,31 @@ mod tests {
         assert_eq!(m.visit(repo_path("foo/bar/baz")), Visit::AllRecursively);
     }
 
+    #[test]
+    fn test_fileglobsmatcher() {
+        let m = FileGlobsMatcher::new([]);
+        assert!(!m.matches(repo_path("foo")));
+        assert_eq!(m.visit(RepoPath::root()), Visit::Nothing);
+
+        let m = FileGlobsMatcher::new([
+            glob::Pattern::new("*.md").unwrap(),
+            glob::Pattern::new("docs/**/*.png").unwrap(),
+        ]);
+        assert!(m.matches(repo_path("README.md")));
+        // A single wildcard doesn't match a path separator
+        assert!(!m.matches(repo_path("foo/README.md")));
+        assert!(m.matches(repo_path("docs/logo.png")));
+        assert!(m.matches(repo_path("docs/images/logo.png")));
+        assert!(!m.matches(repo_path("images/logo.png")));
+        assert_eq!(
+            m.visit(repo_path("foo")),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        );
+    }
+
     #[test]
     fn test_unionmatcher_concatenate_roots() {
         let m1 = PrefixMatcher::new([repo_path("foo"), repo_path("bar")]);
@@ -16,7 +16,7 @@
 //! some common types of merged values.
 
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::hash::Hash;
 use std::io::Write;
@@ -272,6 +272,42 @@ impl<T> Merge<T> {
         self
     }
 
+    /// Drops changes which are made on more than one side, such as B->A in
+    /// `C + (A - B) + (A - B)`, so that they are only counted once. This
+    /// usually happens when the same commit is merged more than once.
+    ///
+    /// Like the "same change" rule of `trivial_merge()`, this doesn't preserve
+    /// the algebraic meaning of the merge: `C + 2 * (A - B)` becomes
+    /// `C + (A - B)`. Changes are made of each remove and the add after it, so
+    /// different changes to or from the same value are kept. Since the first
+    /// add isn't part of any change, it can take the place of any other add.
+    pub fn dedup_changes(self) -> Self
+    where
+        T: Eq + Hash,
+    {
+        for first in 0..self.num_sides() {
+            let mut order = (0..self.values.len()).collect_vec();
+            order.swap(0, first * 2);
+            let mut seen = HashSet::new();
+            let mut dropped: HashSet<usize> = HashSet::new();
+            for change in order[1..].chunks(2) {
+                if !seen.insert(change.iter().map(|&i| &self.values[i]).collect_vec()) {
+                    dropped.extend(change);
+                }
+            }
+            if !dropped.is_empty() {
+                let mut values = self.values.into_iter().map(Some).collect_vec();
+                let values = order
+                    .into_iter()
+                    .filter(|i| !dropped.contains(i))
+                    .map(|i| values[i].take().unwrap())
+                    .collect();
+                return Merge { values };
+            }
+        }
+        self
+    }
+
     /// If this merge can be trivially resolved, returns the value it resolves
     /// to.
     pub fn resolve_trivial(&self) -> Option<&T>
@@ -807,6 +843,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dedup_changes() {
+        // 1-way and 3-way merges have nothing to deduplicate
+        assert_eq!(c(&[], &[0]).dedup_changes(), c(&[], &[0]));
+        assert_eq!(c(&[0], &[1, 1]).dedup_changes(), c(&[0], &[1, 1]));
+        assert_eq!(c(&[0], &[1, 2]).dedup_changes(), c(&[0], &[1, 2]));
+        // The same change on two sides
+        assert_eq!(c(&[0, 0], &[1, 2, 2]).dedup_changes(), c(&[0], &[1, 2]));
+        assert_eq!(c(&[0, 0], &[2, 1, 1]).dedup_changes(), c(&[0], &[2, 1]));
+        assert_eq!(c(&[0, 0], &[1, 1, 1]).dedup_changes(), c(&[0], &[1, 1]));
+        // The first side can be the same as a changed side
+        assert_eq!(c(&[0, 0], &[2, 1, 2]).dedup_changes(), c(&[0], &[1, 2]));
+        // Different changes to the same value aren't deduplicated
+        assert_eq!(
+            c(&[0, 1], &[2, 3, 3]).dedup_changes(),
+            c(&[0, 1], &[2, 3, 3])
+        );
+        // Changes are paired by position
+        assert_eq!(
+            c(&[0, 0, 1, 2], &[3, 4, 5, 6, 6]).dedup_changes(),
+            c(&[0, 0, 1, 2], &[3, 4, 5, 6, 6])
+        );
+        // Different changes from the same value aren't deduplicated
+        assert_eq!(
+            c(&[0, 0], &[1, 2, 3]).dedup_changes(),
+            c(&[0, 0], &[1, 2, 3])
+        );
+        // The same change on three sides
+        assert_eq!(
+            c(&[0, 0, 0], &[1, 2, 2, 2]).dedup_changes(),
+            c(&[0], &[1, 2])
+        );
+        // Two duplicated changes
+        assert_eq!(
+            c(&[0, 1, 0, 1], &[2, 3, 4, 3, 4]).dedup_changes(),
+            c(&[0, 1], &[2, 3, 4])
+        );
+
+        // `resolve_trivial()` is unaffected by `dedup_changes()`
+        for removes in (0..3).combinations_with_replacement(2) {
+            for adds in (0..3).combinations_with_replacement(3) {
+                let merge = c(&removes, &adds);
+                assert_eq!(
+                    merge.clone().dedup_changes().resolve_trivial(),
+                    merge.resolve_trivial(),
+                    "dedup_changes() changed result of resolve_trivial() for {merge:?}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_merge_invariants() {
         fn check_invariants(removes: &[u32], adds: &[u32]) {
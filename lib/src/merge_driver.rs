@@ -0,0 +1,263 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merge drivers to resolve file conflicts which the built-in merge couldn't,
+//! selected by path.
+
+#![allow(missing_docs)]
+
+use std::collections::HashMap;
+
+use itertools::Itertools as _;
+
+use crate::files::{self, MergeResult};
+use crate::merge::Merge;
+use crate::repo_path::RepoPath;
+use crate::settings::UserSettings;
+
+/// Program name of the built-in union merge driver.
+pub const UNION_MERGE_DRIVER: &str = ":union";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MergeDriverKind {
+    /// Keeps the lines of all sides of each conflicting hunk.
+    Union,
+    /// Runs `program` with the `merge_args`, which should write the merged
+    /// content to `$output`. External drivers are only run by the commands
+    /// which resolve or rebase commits, not when merging trees.
+    External {
+        program: String,
+        merge_args: Vec<String>,
+    },
+}
+
+/// Merge driver which is used for the files matching any of the `paths`. A
+/// driver without a `kind` only sets merge options for these files.
+#[derive(Clone, Debug)]
+pub struct MergeDriver {
+    pub name: String,
+    paths: Vec<glob::Pattern>,
+    pub kind: Option<MergeDriverKind>,
+    /// Overrides the `merge.ignore-whitespace` setting for the matching paths.
+    pub ignore_whitespace: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct MergeDriverConfig {
+    paths: Vec<String>,
+    program: Option<String>,
+    #[serde(default = "default_merge_args")]
+    merge_args: Vec<String>,
+    #[serde(default)]
+    ignore_whitespace: Option<bool>,
+}
+
+fn default_merge_args() -> Vec<String> {
+    ["$base", "$left", "$right", "$output"]
+        .map(str::to_owned)
+        .to_vec()
+}
+
+impl MergeDriver {
+    /// Creates a driver used for the paths matching any of the glob
+    /// `patterns`, which are relative to the repository root.
+    pub fn new(
+        name: impl Into<String>,
+        patterns: &[impl AsRef<str>],
+        kind: Option<MergeDriverKind>,
+    ) -> Result<Self, glob::PatternError> {
+        let paths = patterns
+            .iter()
+            .map(|pattern| glob::Pattern::new(pattern.as_ref()))
+            .try_collect()?;
+        Ok(MergeDriver {
+            name: name.into(),
+            paths,
+            kind,
+            ignore_whitespace: None,
+        })
+    }
+
+    pub fn matches(&self, path: &RepoPath) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let path = path.as_internal_file_string();
+        self.paths
+            .iter()
+            .any(|pattern| pattern.matches_with(path, options))
+    }
+
+    /// Merges the file `contents` if this is a built-in driver. Returns `None`
+    /// for the other drivers, which aren't run when merging trees; it's up to
+    /// the caller to run the external drivers if appropriate. The built-in
+    /// drivers ignore whitespace-only differences if `ignore_whitespace` is
+    /// true.
+    pub fn merge(&self, contents: &Merge<&[u8]>, ignore_whitespace: bool) -> Option<Vec<u8>> {
+        match self.kind.as_ref()? {
+            MergeDriverKind::Union => Some(union_merge(contents, ignore_whitespace)),
+            MergeDriverKind::External { .. } => None,
+        }
+    }
+}
+
+/// Merge drivers configured for the repository.
+#[derive(Clone, Debug, Default)]
+pub struct MergeDrivers {
+    drivers: Vec<MergeDriver>,
+    ignore_whitespace: bool,
+}
+
+impl MergeDrivers {
+    pub fn new(drivers: Vec<MergeDriver>, ignore_whitespace: bool) -> Self {
+        MergeDrivers {
+            drivers,
+            ignore_whitespace,
+        }
+    }
+
+    /// Loads the drivers from the `merge-drivers` table, ordered by name, and
+    /// the `merge.ignore-whitespace` setting.
+    pub fn from_settings(settings: &UserSettings) -> Result<Self, config::ConfigError> {
+        let ignore_whitespace = match settings.config().get_bool("merge.ignore-whitespace") {
+            Ok(value) => value,
+            Err(config::ConfigError::NotFound(_)) => false,
+            Err(err) => return Err(err),
+        };
+        let table: HashMap<String, MergeDriverConfig> = match settings.config().get("merge-drivers")
+        {
+            Ok(table) => table,
+            Err(config::ConfigError::NotFound(_)) => {
+                return Ok(MergeDrivers::new(vec![], ignore_whitespace))
+            }
+            Err(err) => return Err(err),
+        };
+        let drivers = table
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(name, config)| {
+                let kind = config.program.map(|program| {
+                    if program == UNION_MERGE_DRIVER {
+                        MergeDriverKind::Union
+                    } else {
+                        MergeDriverKind::External {
+                            program,
+                            merge_args: config.merge_args,
+                        }
+                    }
+                });
+                let mut driver = MergeDriver::new(&name, &config.paths, kind).map_err(|err| {
+                    config::ConfigError::Message(format!(
+                        "Invalid path pattern in merge-drivers.{name}: {err}"
+                    ))
+                })?;
+                driver.ignore_whitespace = config.ignore_whitespace;
+                Ok::<_, config::ConfigError>(driver)
+            })
+            .try_collect()?;
+        Ok(MergeDrivers::new(drivers, ignore_whitespace))
+    }
+
+    /// Whether any of the drivers runs an external program.
+    pub fn has_external(&self) -> bool {
+        self.drivers
+            .iter()
+            .any(|driver| matches!(driver.kind, Some(MergeDriverKind::External { .. })))
+    }
+
+    /// Returns the first driver which matches the `path`.
+    pub fn find(&self, path: &RepoPath) -> Option<&MergeDriver> {
+        self.drivers.iter().find(|driver| driver.matches(path))
+    }
+
+    /// Whether to ignore whitespace-only differences when merging the file at
+    /// the `path`.
+    pub fn ignore_whitespace(&self, path: &RepoPath) -> bool {
+        self.find(path)
+            .and_then(|driver| driver.ignore_whitespace)
+            .unwrap_or(self.ignore_whitespace)
+    }
+}
+
+/// Merges the `contents`, resolving each conflicting hunk by concatenating the
+/// added sides in order.
+pub fn union_merge(contents: &Merge<&[u8]>, ignore_whitespace: bool) -> Vec<u8> {
+    let merge_result = if ignore_whitespace {
+        files::merge_ignoring_whitespace(contents)
+    } else {
+        files::merge(contents)
+    };
+    match merge_result {
+        MergeResult::Resolved(content) => content.0,
+        MergeResult::Conflict(hunks) => hunks
+            .iter()
+            .flat_map(|hunk| match hunk.as_resolved() {
+                Some(content) => vec![content.0.as_slice()],
+                None => hunk.adds().map(|content| content.0.as_slice()).collect(),
+            })
+            .collect_vec()
+            .concat(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let driver = MergeDriver::new(
+            "test",
+            &["CHANGELOG.md", "**/Cargo.lock"],
+            Some(MergeDriverKind::Union),
+        )
+        .unwrap();
+        let matches = |path| driver.matches(RepoPath::from_internal_string(path));
+        assert!(matches("CHANGELOG.md"));
+        assert!(!matches("docs/CHANGELOG.md"));
+        assert!(matches("Cargo.lock"));
+        assert!(matches("cli/Cargo.lock"));
+        assert!(!matches("Cargo.lock.orig"));
+    }
+
+    #[test]
+    fn test_ignore_whitespace() {
+        let mut driver = MergeDriver::new("test", &["*.py"], None).unwrap();
+        driver.ignore_whitespace = Some(false);
+        let drivers = MergeDrivers::new(vec![driver], true);
+        let ignore_whitespace =
+            |path| drivers.ignore_whitespace(RepoPath::from_internal_string(path));
+        assert!(!ignore_whitespace("main.py"));
+        assert!(ignore_whitespace("main.rs"));
+    }
+
+    #[test]
+    fn test_union_merge() {
+        let merge = |removes: &[&'static str], adds: &[&'static str]| {
+            let contents = Merge::from_removes_adds(
+                removes.iter().map(|s| s.as_bytes()),
+                adds.iter().map(|s| s.as_bytes()),
+            );
+            String::from_utf8(union_merge(&contents, false)).unwrap()
+        };
+        assert_eq!(merge(&["a\n"], &["a\nb\n", "a\nc\n"]), "a\nb\nc\n");
+        assert_eq!(merge(&["a\nb\n"], &["a\nB\n", "a\nb\n"]), "a\nB\n");
+        assert_eq!(
+            merge(&["x\n", "x\n"], &["x\n1\n", "x\n2\n", "x\n3\n"]),
+            "x\n1\n2\n3\n"
+        );
+    }
+}
@@ -383,12 +383,22 @@ impl MergedTree {
                 }
             };
             let nested = Merge::from_vec(vec![to_merge(self)?, to_merge(base)?, to_merge(other)?]);
-            let tree = merge_trees(&nested.flatten().simplify())?;
+            let mut tree = merge_trees(&nested.flatten().simplify().dedup_changes())?;
             // If the result can be resolved, then `merge_trees()` above would have returned
             // a resolved merge. However, that function will always preserve the arity of
             // conflicts it cannot resolve. So we simplify the conflict again
-            // here to possibly reduce a complex conflict to a simpler one.
-            let tree = tree.simplify();
+            // here to possibly reduce a complex conflict to a simpler one. If that leaves
+            // duplicated changes behind, dropping them may enable further resolutions, so
+            // merge the remaining trees again.
+            tree = tree.simplify();
+            loop {
+                let num_sides = tree.num_sides();
+                tree = tree.dedup_changes();
+                if tree.num_sides() == num_sides {
+                    break;
+                }
+                tree = merge_trees(&tree)?.simplify();
+            }
             // If debug assertions are enabled, check that the merge was idempotent. In
             // particular,  that this last simplification doesn't enable further automatic
             // resolutions
@@ -541,7 +551,7 @@ fn merge_tree_values(
         // Try to resolve file conflicts by merging the file contents. Treats missing
         // files as empty. The values may contain trees canceling each other (notably
         // padded absent trees), so we need to simplify them first.
-        let simplified = values.clone().simplify();
+        let simplified = values.clone().simplify().dedup_changes();
         // No fast path for simplified.is_resolved(). If it could be resolved, it would
         // have been caught by values.resolve_trivial() above.
         if let Some(resolved) = try_resolve_file_conflict(store, path, &simplified)? {
@@ -13,6 +13,9 @@ pub struct Commit {
     /// TODO(#1624): delete when we assume that all commits use this format
     #[prost(bool, tag = "10")]
     pub uses_tree_conflict_format: bool,
+    /// Sorted by target. Git doesn't record copies, so they're only stored here.
+    #[prost(message, repeated, tag = "11")]
+    pub copies: ::prost::alloc::vec::Vec<commit::CopyRecord>,
     #[deprecated]
     #[prost(bool, tag = "8")]
     pub is_open: bool,
@@ -20,3 +23,16 @@ pub struct Commit {
     #[prost(bool, tag = "9")]
     pub is_pruned: bool,
 }
+/// Nested message and enum types in `Commit`.
+pub mod commit {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CopyRecord {
+        #[prost(string, tag = "1")]
+        pub target: ::prost::alloc::string::String,
+        #[prost(string, tag = "2")]
+        pub source: ::prost::alloc::string::String,
+        #[prost(bytes = "vec", tag = "3")]
+        pub source_file: ::prost::alloc::vec::Vec<u8>,
+    }
+}
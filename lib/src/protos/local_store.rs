@@ -68,6 +68,9 @@ pub struct Commit {
     pub committer: ::core::option::Option<commit::Signature>,
     #[prost(bytes = "vec", optional, tag = "9")]
     pub secure_sig: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Sorted by target
+    #[prost(message, repeated, tag = "10")]
+    pub copies: ::prost::alloc::vec::Vec<commit::CopyRecord>,
 }
 /// Nested message and enum types in `Commit`.
 pub mod commit {
@@ -89,6 +92,16 @@ pub mod commit {
         #[prost(message, optional, tag = "3")]
         pub timestamp: ::core::option::Option<Timestamp>,
     }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CopyRecord {
+        #[prost(string, tag = "1")]
+        pub target: ::prost::alloc::string::String,
+        #[prost(string, tag = "2")]
+        pub source: ::prost::alloc::string::String,
+        #[prost(bytes = "vec", tag = "3")]
+        pub source_file: ::prost::alloc::vec::Vec<u8>,
+    }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -107,3 +120,11 @@ pub mod conflict {
         pub content: ::core::option::Option<super::TreeValue>,
     }
 }
+/// Pointer to the chunks of a file which is too large to be stored whole
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LargeFile {
+    /// Ids of the chunks, in order
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub chunk_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
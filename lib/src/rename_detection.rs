@@ -0,0 +1,473 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Similarity-based detection of renamed and copied files in tree diffs.
+
+#![allow(missing_docs)]
+
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use itertools::Itertools as _;
+
+use crate::backend::{BackendError, BackendResult, CopyRecord, FileId, TreeValue};
+use crate::diff::{self, Diff, DiffHunk};
+use crate::matchers::EverythingMatcher;
+use crate::merge::{Merge, MergedTreeValue};
+use crate::merged_tree::{MergedTree, MergedTreeBuilder};
+use crate::object_id::ObjectId as _;
+use crate::repo_path::{RepoPath, RepoPathBuf};
+use crate::store::Store;
+
+/// Files which are at least this similar (in percent) are considered renamed
+/// or copied.
+pub const DEFAULT_SIMILARITY_THRESHOLD: u32 = 50;
+
+/// Inexact detection is skipped if the number of source and target pairs to
+/// compare exceeds this.
+const MAX_INEXACT_CANDIDATE_PAIRS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RenameKind {
+    /// The source file was removed.
+    Rename,
+    /// The source file still exists in the new tree.
+    Copy,
+}
+
+/// A file which was moved or copied from `source` to `target`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DetectedRename {
+    pub source: RepoPathBuf,
+    pub target: RepoPathBuf,
+    pub kind: RenameKind,
+    /// Similarity of the source and target contents in percent.
+    pub similarity: u32,
+}
+
+/// Options for `detect_renames()`.
+#[derive(Clone, Debug)]
+pub struct RenameDetectionOptions {
+    /// Minimum similarity (in percent) of files to be paired.
+    pub threshold: u32,
+    /// Whether to detect copies from modified and renamed files in addition to
+    /// renames.
+    pub find_copies: bool,
+    /// Whether to pair files by content similarity. If disabled, only the
+    /// recorded `copies` are reported.
+    pub detect_similar: bool,
+    /// Copies recorded in the commits, which take precedence over the detected
+    /// ones.
+    pub copies: Vec<CopyRecord>,
+}
+
+impl Default for RenameDetectionOptions {
+    fn default() -> Self {
+        RenameDetectionOptions {
+            threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            find_copies: true,
+            detect_similar: true,
+            copies: vec![],
+        }
+    }
+}
+
+struct FileEntry<'a> {
+    path: &'a RepoPath,
+    id: &'a FileId,
+    content: Vec<u8>,
+}
+
+/// Pairs files removed in the `diff` with files added in it.
+///
+/// The `diff` is a list of `(path, before, after)` entries such as the ones
+/// emitted by `MergedTree::diff()`. Only resolved regular files are considered.
+/// A removed file is renamed to at most one added file. If copy detection is
+/// enabled, the remaining added files may be paired with removed or modified
+/// files as copies. The result is sorted by target path.
+///
+/// A recorded copy applies if its target was added and its source had the
+/// recorded content before. If the source isn't in the `diff`, it's assumed to
+/// be unchanged, so the file was copied.
+pub fn detect_renames(
+    store: &Store,
+    diff: &[(RepoPathBuf, MergedTreeValue, MergedTreeValue)],
+    options: &RenameDetectionOptions,
+) -> BackendResult<Vec<DetectedRename>> {
+    let mut removed = vec![];
+    let mut modified = vec![];
+    let mut added = vec![];
+    for (path, before, after) in diff {
+        match (as_file(before), after.is_absent(), before.is_absent()) {
+            (Some(id), true, _) => removed.push((path.as_ref(), id)),
+            (Some(id), false, _) => modified.push((path.as_ref(), id)),
+            (None, _, true) => {
+                if let Some(id) = as_file(after) {
+                    added.push((path.as_ref(), id));
+                }
+            }
+            _ => {}
+        }
+    }
+    let has_sources = (options.detect_similar
+        && (!removed.is_empty() || (options.find_copies && !modified.is_empty())))
+        || !options.copies.is_empty();
+    if added.is_empty() || !has_sources {
+        return Ok(vec![]);
+    }
+
+    let removed = read_entries(store, removed)?;
+    let added = read_entries(store, added)?;
+    let mut renames = vec![];
+    let mut used_sources = HashSet::new();
+    let mut used_targets = HashSet::new();
+
+    for copy in &options.copies {
+        let Some(j) = added
+            .iter()
+            .position(|entry| entry.path == copy.target.as_ref())
+        else {
+            continue;
+        };
+        let kind = match diff.iter().find(|(path, _, _)| *path == copy.source) {
+            Some((_, before, after)) => {
+                if as_file(before) != Some(&copy.source_file) {
+                    continue;
+                }
+                if after.is_absent() {
+                    RenameKind::Rename
+                } else {
+                    RenameKind::Copy
+                }
+            }
+            None => RenameKind::Copy,
+        };
+        if used_targets.contains(&j) || (kind == RenameKind::Copy && !options.find_copies) {
+            continue;
+        }
+        if kind == RenameKind::Rename {
+            if let Some(i) = removed
+                .iter()
+                .position(|entry| entry.path == copy.source.as_ref())
+            {
+                if !used_sources.insert(i) {
+                    continue;
+                }
+            }
+        }
+        used_targets.insert(j);
+        let source_content = read_content(store, &copy.source, &copy.source_file)?;
+        renames.push(DetectedRename {
+            source: copy.source.clone(),
+            target: copy.target.clone(),
+            kind,
+            similarity: similarity(&source_content, &added[j].content),
+        });
+    }
+
+    if !options.detect_similar {
+        renames.sort_by(|a, b| a.target.cmp(&b.target));
+        return Ok(renames);
+    }
+
+    // Exact renames are cheap to find, so pair them first.
+    let mut removed_by_id: HashMap<&FileId, Vec<usize>> = HashMap::new();
+    for (i, entry) in removed.iter().enumerate() {
+        removed_by_id.entry(entry.id).or_default().push(i);
+    }
+    for (j, target) in added.iter().enumerate() {
+        let Some(candidates) = removed_by_id.get(target.id) else {
+            continue;
+        };
+        if let Some(&i) = candidates.iter().find(|i| !used_sources.contains(*i)) {
+            used_sources.insert(i);
+            used_targets.insert(j);
+            renames.push(new_rename(&removed[i], target, RenameKind::Rename, 100));
+        }
+    }
+
+    // Then pair the remaining files by decreasing similarity.
+    let unused_removed = (0..removed.len())
+        .filter(|i| !used_sources.contains(i))
+        .collect_vec();
+    let unused_added = (0..added.len())
+        .filter(|j| !used_targets.contains(j))
+        .collect_vec();
+    if unused_removed.len() * unused_added.len() <= MAX_INEXACT_CANDIDATE_PAIRS {
+        let mut candidates = vec![];
+        for &i in &unused_removed {
+            for &j in &unused_added {
+                let score = similarity(&removed[i].content, &added[j].content);
+                if score >= options.threshold {
+                    candidates.push((score, i, j));
+                }
+            }
+        }
+        // Prefer higher scores, then the order of paths for stable results.
+        candidates.sort_by_key(|&(score, i, j)| (u32::MAX - score, i, j));
+        for (score, i, j) in candidates {
+            if used_sources.contains(&i) || used_targets.contains(&j) {
+                continue;
+            }
+            used_sources.insert(i);
+            used_targets.insert(j);
+            renames.push(new_rename(
+                &removed[i],
+                &added[j],
+                RenameKind::Rename,
+                score,
+            ));
+        }
+    }
+
+    if options.find_copies {
+        let modified = read_entries(store, modified)?;
+        let sources = removed.iter().chain(&modified).collect_vec();
+        let unused_added = (0..added.len())
+            .filter(|j| !used_targets.contains(j))
+            .collect_vec();
+        if sources.len() * unused_added.len() <= MAX_INEXACT_CANDIDATE_PAIRS {
+            for j in unused_added {
+                let target = &added[j];
+                let best = sources
+                    .iter()
+                    .map(|source| {
+                        let score = if source.id == target.id {
+                            100
+                        } else {
+                            similarity(&source.content, &target.content)
+                        };
+                        (score, source)
+                    })
+                    .filter(|(score, _)| *score >= options.threshold)
+                    // Pick the first source among the equally similar ones.
+                    .rev()
+                    .max_by_key(|(score, _)| *score);
+                if let Some((score, source)) = best {
+                    renames.push(new_rename(source, target, RenameKind::Copy, score));
+                }
+            }
+        }
+    }
+
+    renames.sort_by(|a, b| a.target.cmp(&b.target));
+    Ok(renames)
+}
+
+/// Merges `side2` into `side1`, using `base` as base, and applies the changes
+/// to the files which were renamed on the other side.
+///
+/// The renames are detected by content similarity, or recorded in `copies`.
+/// The records which don't apply to `base` and the sides are ignored. Unlike
+/// `MergedTree::merge()`, this reads the contents of the added and removed
+/// files if the plain merge has conflicts.
+pub fn merge_following_renames(
+    side1: &MergedTree,
+    base: &MergedTree,
+    side2: &MergedTree,
+    copies: &[CopyRecord],
+) -> BackendResult<MergedTree> {
+    let merged = side1.merge(base, side2)?;
+    if !merged.has_conflict() {
+        return Ok(merged);
+    }
+    match move_renamed_files(side1, base, side2, &merged, copies)? {
+        Some([side1, base, side2]) => side1.merge(&base, &side2),
+        None => Ok(merged),
+    }
+}
+
+/// Finds files which were modified on one side and renamed on the other side,
+/// and moves them to the new path in the base and the modifying side. Returns
+/// the adjusted `[side1, base, side2]` trees if any such files were found.
+fn move_renamed_files(
+    side1: &MergedTree,
+    base: &MergedTree,
+    side2: &MergedTree,
+    merged: &MergedTree,
+    copies: &[CopyRecord],
+) -> BackendResult<Option<[MergedTree; 3]>> {
+    let is_resolved =
+        |tree: &MergedTree| matches!(tree, MergedTree::Merge(trees) if trees.is_resolved());
+    if ![side1, base, side2].into_iter().all(is_resolved) {
+        return Ok(None);
+    }
+    let store = base.store();
+    let find_renames = |side: &MergedTree| -> BackendResult<HashMap<RepoPathBuf, RepoPathBuf>> {
+        let diff: Vec<_> = base
+            .diff(side, &EverythingMatcher)
+            .map(|(path, diff)| diff.map(|(before, after)| (path, before, after)))
+            .try_collect()?;
+        let options = RenameDetectionOptions {
+            find_copies: false,
+            copies: copies.to_vec(),
+            ..Default::default()
+        };
+        let renames = detect_renames(store, &diff, &options)?;
+        Ok(renames
+            .into_iter()
+            .map(|rename| (rename.source, rename.target))
+            .collect())
+    };
+    let mut side1_renames = None;
+    let mut side2_renames = None;
+    // (source, target) pairs to move in side 1 and base, and in side 2 and base
+    let mut side1_moves = vec![];
+    let mut side2_moves = vec![];
+    for (path, _) in merged.conflicts() {
+        let base_value = base.path_value(&path);
+        if !matches!(base_value.as_resolved(), Some(Some(TreeValue::File { .. }))) {
+            continue;
+        }
+        let side1_value = side1.path_value(&path);
+        let side2_value = side2.path_value(&path);
+        let (renames, modified_side, moves) = if side2_value.is_absent() {
+            (&mut side2_renames, side1, &mut side1_moves)
+        } else if side1_value.is_absent() {
+            (&mut side1_renames, side2, &mut side2_moves)
+        } else {
+            continue;
+        };
+        let renaming_side = if side2_value.is_absent() {
+            side2
+        } else {
+            side1
+        };
+        if renames.is_none() {
+            *renames = Some(find_renames(renaming_side)?);
+        }
+        let is_file = matches!(
+            modified_side.path_value(&path).as_resolved(),
+            Some(Some(TreeValue::File { .. }))
+        );
+        if let Some(target) = renames.as_ref().unwrap().get(&path) {
+            if is_file && modified_side.path_value(target).is_absent() {
+                moves.push((path, target.clone()));
+            }
+        }
+    }
+    if side1_moves.is_empty() && side2_moves.is_empty() {
+        return Ok(None);
+    }
+    let move_files = |tree: &MergedTree, moves: &[&(RepoPathBuf, RepoPathBuf)]| {
+        let mut builder = MergedTreeBuilder::new(tree.id());
+        for (source, target) in moves {
+            let Ok(Some(TreeValue::File { id, executable })) =
+                tree.path_value(source).into_resolved()
+            else {
+                return Err(BackendError::Other(
+                    format!("Moved path {source:?} isn't a file").into(),
+                ));
+            };
+            // The file id may depend on the path, so the content is rewritten
+            // at the new path.
+            let mut content = store.read_file(source, &id)?;
+            let id = store.write_file(target, &mut content)?;
+            let value = TreeValue::File { id, executable };
+            builder.set_or_remove(target.clone(), Merge::normal(value));
+            builder.set_or_remove(source.clone(), Merge::absent());
+        }
+        let tree_id = builder.write_tree(store)?;
+        store.get_root_tree(&tree_id)
+    };
+    Ok(Some([
+        move_files(side1, &side1_moves.iter().collect_vec())?,
+        move_files(base, &side1_moves.iter().chain(&side2_moves).collect_vec())?,
+        move_files(side2, &side2_moves.iter().collect_vec())?,
+    ]))
+}
+
+fn as_file(value: &MergedTreeValue) -> Option<&FileId> {
+    match value.as_resolved() {
+        Some(Some(TreeValue::File { id, .. })) => Some(id),
+        _ => None,
+    }
+}
+
+fn read_entries<'a>(
+    store: &Store,
+    files: Vec<(&'a RepoPath, &'a FileId)>,
+) -> BackendResult<Vec<FileEntry<'a>>> {
+    files
+        .into_iter()
+        .map(|(path, id)| {
+            let content = read_content(store, path, id)?;
+            Ok(FileEntry { path, id, content })
+        })
+        // Empty files are all alike, so they can't be paired meaningfully.
+        .filter_ok(|entry| !entry.content.is_empty())
+        .try_collect()
+}
+
+fn read_content(store: &Store, path: &RepoPath, id: &FileId) -> BackendResult<Vec<u8>> {
+    let mut content = vec![];
+    store
+        .read_file(path, id)?
+        .read_to_end(&mut content)
+        .map_err(|err| BackendError::ReadObject {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: err.into(),
+        })?;
+    Ok(content)
+}
+
+fn new_rename(
+    source: &FileEntry,
+    target: &FileEntry,
+    kind: RenameKind,
+    similarity: u32,
+) -> DetectedRename {
+    DetectedRename {
+        source: source.path.to_owned(),
+        target: target.path.to_owned(),
+        kind,
+        similarity,
+    }
+}
+
+/// Computes how similar the contents are, in percent.
+///
+/// The similarity is the number of bytes in matching lines relative to the
+/// size of the larger content.
+pub fn similarity(left: &[u8], right: &[u8]) -> u32 {
+    let max_len = left.len().max(right.len());
+    if max_len == 0 {
+        return 100;
+    }
+    let diff = Diff::for_tokenizer(&[left, right], &diff::find_line_ranges);
+    let matching_len: usize = diff
+        .hunks()
+        .map(|hunk| match hunk {
+            DiffHunk::Matching(content) => content.len(),
+            DiffHunk::Different(_) => 0,
+        })
+        .sum();
+    (matching_len * 100 / max_len) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(b"", b""), 100);
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\n"), 100);
+        assert_eq!(similarity(b"a\nb\n", b""), 0);
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nc\nx\n"), 75);
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\nc\nd\n"), 50);
+        assert_eq!(similarity(b"a\nb\n", b"x\ny\n"), 0);
+    }
+}
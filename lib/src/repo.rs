@@ -40,6 +40,7 @@ use crate::file_util::{IoResultExt as _, PathError};
 use crate::git_backend::GitBackend;
 use crate::index::{ChangeIdIndex, Index, IndexStore, MutableIndex, ReadonlyIndex};
 use crate::local_backend::LocalBackend;
+use crate::merge_driver::MergeDrivers;
 use crate::object_id::{HexPrefix, ObjectId, PrefixResolution};
 use crate::op_heads_store::{self, OpHeadResolutionError, OpHeadsStore};
 use crate::op_store::{
@@ -116,6 +117,8 @@ pub enum RepoInitError {
     Backend(#[from] BackendInitError),
     #[error(transparent)]
     Path(#[from] PathError),
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
 }
 
 impl ReadonlyRepo {
@@ -156,7 +159,12 @@ impl ReadonlyRepo {
         let backend = backend_initializer(user_settings, &store_path)?;
         let backend_path = store_path.join("type");
         fs::write(&backend_path, backend.name()).context(&backend_path)?;
-        let store = Store::new(backend, signer, user_settings.use_tree_conflict_format());
+        let store = Store::new(
+            backend,
+            signer,
+            user_settings.use_tree_conflict_format(),
+            MergeDrivers::from_settings(user_settings)?,
+        );
         let repo_settings = user_settings.with_repo(&repo_path).unwrap();
 
         let op_store_path = repo_path.join("op_store");
@@ -362,7 +370,14 @@ impl Default for StoreFactories {
         // Backends
         factories.add_backend(
             LocalBackend::name(),
-            Box::new(|_settings, store_path| Ok(Box::new(LocalBackend::load(store_path)))),
+            Box::new(|settings, store_path| {
+                let threshold = settings
+                    .large_file_threshold()
+                    .map_err(|err| BackendLoadError(err.into()))?;
+                Ok(Box::new(
+                    LocalBackend::load(store_path).with_large_file_threshold(threshold),
+                ))
+            }),
         );
         factories.add_backend(
             GitBackend::name(),
@@ -413,6 +428,8 @@ pub enum StoreLoadError {
     Backend(#[from] BackendLoadError),
     #[error(transparent)]
     Signing(#[from] SignInitError),
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
 }
 
 impl StoreFactories {
@@ -607,6 +624,7 @@ impl RepoLoader {
             store_factories.load_backend(user_settings, &repo_path.join("store"))?,
             Signer::from_settings(user_settings)?,
             user_settings.use_tree_conflict_format(),
+            MergeDrivers::from_settings(user_settings)?,
         );
         let repo_settings = user_settings.with_repo(repo_path).unwrap();
         let op_store =
@@ -700,7 +718,9 @@ impl RepoLoader {
         let base_repo = self.load_at(&op_heads[0])?;
         let mut tx = base_repo.start_transaction(user_settings);
         for other_op_head in op_heads.into_iter().skip(1) {
-            tx.merge_operation(other_op_head)?;
+            // The conflicts stay in the view, and there's no one to report
+            // them to here.
+            let _conflicts = tx.merge_operation(other_op_head)?;
             tx.mut_repo().rebase_descendants(user_settings)?;
         }
         let merged_repo = tx
@@ -1294,7 +1314,14 @@ impl MutableRepo {
         self.view.mark_dirty();
     }
 
-    pub fn merge(&mut self, base_repo: &ReadonlyRepo, other_repo: &ReadonlyRepo) {
+    /// Merges the changes from `base_repo` to `other_repo` into this repo.
+    ///
+    /// Returns the conflicts the merge left in the view.
+    pub fn merge(
+        &mut self,
+        base_repo: &ReadonlyRepo,
+        other_repo: &ReadonlyRepo,
+    ) -> BackendResult<Vec<ViewConflict>> {
         // First, merge the index, so we can take advantage of a valid index when
         // merging the view. Merging in base_repo's index isn't typically
         // necessary, but it can be if base_repo is ahead of either self or other_repo
@@ -1303,22 +1330,43 @@ impl MutableRepo {
         self.index.merge_in(other_repo.readonly_index());
 
         self.view.ensure_clean(|v| self.enforce_view_invariants(v));
-        self.merge_view(&base_repo.view, &other_repo.view);
+        let conflicts = self.merge_view(&base_repo.view, &other_repo.view)?;
         self.view.mark_dirty();
+        Ok(conflicts)
     }
 
-    fn merge_view(&mut self, base: &View, other: &View) {
+    fn merge_view(&mut self, base: &View, other: &View) -> BackendResult<Vec<ViewConflict>> {
+        let mut conflicts = vec![];
+        // Working-copy commits that the other side created but that lost to the
+        // self side. They're not added to the merged view if they're empty.
+        let mut dropped_commit_ids = HashSet::new();
         // Merge working-copy commits. If there's a conflict, we keep the self side.
         for (workspace_id, base_wc_commit) in base.wc_commit_ids() {
-            let self_wc_commit = self.view().get_wc_commit_id(workspace_id);
+            let self_wc_commit = self.view().get_wc_commit_id(workspace_id).cloned();
             let other_wc_commit = other.get_wc_commit_id(workspace_id);
-            if other_wc_commit == Some(base_wc_commit) || other_wc_commit == self_wc_commit {
+            if other_wc_commit == Some(base_wc_commit) || other_wc_commit == self_wc_commit.as_ref()
+            {
                 // The other side didn't change or both sides changed in the
                 // same way.
             } else if let Some(other_wc_commit) = other_wc_commit {
-                if self_wc_commit == Some(base_wc_commit) {
+                if self_wc_commit.as_ref() == Some(base_wc_commit) {
                     self.view_mut()
                         .set_wc_commit(workspace_id.clone(), other_wc_commit.clone());
+                } else if let Some(self_wc_commit) = self_wc_commit {
+                    if self.is_new_discardable_wc_commit(
+                        base,
+                        other,
+                        base_wc_commit,
+                        other_wc_commit,
+                    ) {
+                        dropped_commit_ids.insert(other_wc_commit.clone());
+                    } else {
+                        conflicts.push(ViewConflict::WorkingCopy {
+                            workspace_id: workspace_id.clone(),
+                            kept_id: self_wc_commit,
+                            other_id: other_wc_commit.clone(),
+                        });
+                    }
                 }
             } else {
                 // The other side removed the workspace. We want to remove it even if the self
@@ -1336,26 +1384,45 @@ impl MutableRepo {
             }
         }
 
+        // The dropped commits are replaced by their parents, as if the other side
+        // had abandoned them.
+        let mut other_heads = other.heads().clone();
+        for commit_id in &dropped_commit_ids {
+            other_heads.remove(commit_id);
+            let commit = self.store().get_commit(commit_id)?;
+            other_heads.extend(commit.parent_ids().iter().cloned());
+        }
         let base_heads = base.heads().iter().cloned().collect_vec();
         let own_heads = self.view().heads().iter().cloned().collect_vec();
-        let other_heads = other.heads().iter().cloned().collect_vec();
         self.record_rewrites(&base_heads, &own_heads);
-        self.record_rewrites(&base_heads, &other_heads);
+        self.record_rewrites(&base_heads, &other_heads.iter().cloned().collect_vec());
         // No need to remove heads removed by `other` because we already marked them
         // abandoned or rewritten.
-        for added_head in other.heads().difference(base.heads()) {
+        for added_head in other_heads.difference(base.heads()) {
             self.view_mut().add_head(added_head);
         }
 
         let changed_local_branches =
             diff_named_ref_targets(base.local_branches(), other.local_branches());
         for (name, (base_target, other_target)) in changed_local_branches {
+            let was_conflicted = self.get_local_branch(name).has_conflict();
             self.merge_local_branch(name, base_target, other_target);
+            if !was_conflicted
+                && !other_target.has_conflict()
+                && self.get_local_branch(name).has_conflict()
+            {
+                conflicts.push(ViewConflict::LocalBranch(name.to_owned()));
+            }
         }
 
         let changed_tags = diff_named_ref_targets(base.tags(), other.tags());
         for (name, (base_target, other_target)) in changed_tags {
+            let was_conflicted = self.get_tag(name).has_conflict();
             self.merge_tag(name, base_target, other_target);
+            if !was_conflicted && !other_target.has_conflict() && self.get_tag(name).has_conflict()
+            {
+                conflicts.push(ViewConflict::Tag(name.to_owned()));
+            }
         }
 
         let changed_git_refs = diff_named_ref_targets(base.git_refs(), other.git_refs());
@@ -1366,7 +1433,23 @@ impl MutableRepo {
         let changed_remote_branches =
             diff_named_remote_refs(base.all_remote_branches(), other.all_remote_branches());
         for ((name, remote_name), (base_ref, other_ref)) in changed_remote_branches {
+            let was_conflicted = self
+                .get_remote_branch(name, remote_name)
+                .target
+                .has_conflict();
             self.merge_remote_branch(name, remote_name, base_ref, other_ref);
+            if !was_conflicted
+                && !other_ref.target.has_conflict()
+                && self
+                    .get_remote_branch(name, remote_name)
+                    .target
+                    .has_conflict()
+            {
+                conflicts.push(ViewConflict::RemoteBranch {
+                    name: name.to_owned(),
+                    remote_name: remote_name.to_owned(),
+                });
+            }
         }
 
         let new_git_head_target = merge_ref_targets(
@@ -1376,6 +1459,55 @@ impl MutableRepo {
             other.git_head(),
         );
         self.set_git_head_target(new_git_head_target);
+        Ok(conflicts)
+    }
+
+    /// Returns true if `commit_id` is an empty working-copy commit created by
+    /// the `other` side on top of the existing commits, which can be dropped
+    /// without losing any change.
+    fn is_new_discardable_wc_commit(
+        &self,
+        base: &View,
+        other: &View,
+        base_wc_commit_id: &CommitId,
+        commit_id: &CommitId,
+    ) -> bool {
+        if !other.heads().contains(commit_id)
+            || base.heads().contains(commit_id)
+            || other.workspaces_for_wc_commit_id(commit_id).len() != 1
+        {
+            return false;
+        }
+        let index = self.index();
+        if base
+            .heads()
+            .iter()
+            .any(|head_id| index.is_ancestor(commit_id, head_id))
+        {
+            return false;
+        }
+        let is_referenced = itertools::chain!(
+            other.local_branches().map(|(_, target)| target),
+            other.tags().values(),
+            other.git_refs().values(),
+            other
+                .all_remote_branches()
+                .map(|(_, remote_ref)| &remote_ref.target),
+            [other.git_head()],
+        )
+        .any(|target| target.added_ids().contains(commit_id));
+        if is_referenced {
+            return false;
+        }
+        let store = self.store();
+        let (Ok(commit), Ok(base_wc_commit)) = (
+            store.get_commit(commit_id),
+            store.get_commit(base_wc_commit_id),
+        ) else {
+            return false;
+        };
+        // A rewritten working-copy commit would have to be abandoned instead.
+        commit.is_discardable() && commit.change_id() != base_wc_commit.change_id()
     }
 
     /// Finds and records commits that were rewritten or abandoned between
@@ -1465,6 +1597,44 @@ impl Repo for MutableRepo {
     }
 }
 
+/// Conflict left in the view by merging concurrent operations.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ViewConflict {
+    /// Both operations changed the working-copy commit of the workspace. The
+    /// working copy was kept at `kept_id`.
+    WorkingCopy {
+        workspace_id: WorkspaceId,
+        kept_id: CommitId,
+        other_id: CommitId,
+    },
+    /// Both operations moved the local branch.
+    LocalBranch(String),
+    /// Both operations moved the remote-tracking branch.
+    RemoteBranch { name: String, remote_name: String },
+    /// Both operations moved the tag.
+    Tag(String),
+}
+
+impl ViewConflict {
+    /// Returns true if the conflict is still present in the `view`.
+    ///
+    /// The ref conflicts may be resolved by rebasing descendants of the
+    /// commits rewritten by the merged operations.
+    pub fn is_present(&self, view: &View) -> bool {
+        match self {
+            ViewConflict::WorkingCopy { workspace_id, .. } => {
+                view.get_wc_commit_id(workspace_id).is_some()
+            }
+            ViewConflict::LocalBranch(name) => view.get_local_branch(name).has_conflict(),
+            ViewConflict::RemoteBranch { name, remote_name } => view
+                .get_remote_branch(name, remote_name)
+                .target
+                .has_conflict(),
+            ViewConflict::Tag(name) => view.get_tag(name).has_conflict(),
+        }
+    }
+}
+
 /// Error from attempts to check out the root commit for editing
 #[derive(Debug, Error)]
 #[error("Cannot rewrite the root commit")]
@@ -37,12 +37,14 @@ use crate::dsl_util::StringLiteralParser;
 use crate::fileset::{FilePattern, FilesetExpression, FilesetParseContext};
 use crate::git;
 use crate::hex_util::to_forward_hex;
+use crate::index::AllHeadsForGcUnsupported;
 use crate::object_id::{HexPrefix, PrefixResolution};
 use crate::op_store::WorkspaceId;
 use crate::repo::Repo;
 use crate::revset_graph::RevsetGraphEdge;
 use crate::store::Store;
 use crate::str_util::StringPattern;
+use crate::time_util::DatePattern;
 
 /// Error occurred during symbol resolution.
 #[derive(Debug, Error)]
@@ -60,6 +62,8 @@ pub enum RevsetResolutionError {
     AmbiguousCommitIdPrefix(String),
     #[error("Change ID prefix \"{0}\" is ambiguous")]
     AmbiguousChangeIdPrefix(String),
+    #[error("Hidden commits can't be listed with this index")]
+    HiddenCommitsUnsupported(#[source] AllHeadsForGcUnsupported),
     #[error("Unexpected error from store")]
     StoreError(#[source] BackendError),
 }
@@ -306,6 +310,8 @@ pub enum RevsetCommitRef {
         remote: String,
     },
     VisibleHeads,
+    /// Heads of all the commits in the index, including the hidden ones.
+    AllHeads,
     Root,
     Branches(StringPattern),
     RemoteBranches {
@@ -327,8 +333,17 @@ pub enum RevsetFilterPredicate {
     Author(StringPattern),
     /// Commits with committer's name or email containing the needle.
     Committer(StringPattern),
+    /// Commits with author dates matching the given date pattern.
+    AuthorDate(DatePattern),
+    /// Commits with committer dates matching the given date pattern.
+    CommitterDate(DatePattern),
     /// Commits modifying the paths specified by the fileset.
     File(FilesetExpression),
+    /// Commits containing diffs matching the `text` pattern within the `files`.
+    DiffContains {
+        text: StringPattern,
+        files: FilesetExpression,
+    },
     /// Commits with conflicts
     HasConflict,
 }
@@ -425,6 +440,14 @@ impl RevsetExpression {
         Rc::new(RevsetExpression::CommitRef(RevsetCommitRef::Root))
     }
 
+    /// Commits which are in the index but aren't visible, such as the
+    /// predecessors of rewritten commits and the abandoned commits.
+    pub fn hidden() -> Rc<RevsetExpression> {
+        Rc::new(RevsetExpression::CommitRef(RevsetCommitRef::AllHeads))
+            .ancestors()
+            .minus(&RevsetExpression::visible_heads().ancestors())
+    }
+
     pub fn branches(pattern: StringPattern) -> Rc<RevsetExpression> {
         Rc::new(RevsetExpression::CommitRef(RevsetCommitRef::Branches(
             pattern,
@@ -1245,6 +1268,10 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
         expect_no_arguments(name, arguments_pair)?;
         Ok(RevsetExpression::root())
     });
+    map.insert("hidden", |name, arguments_pair, _state| {
+        expect_no_arguments(name, arguments_pair)?;
+        Ok(RevsetExpression::hidden())
+    });
     map.insert("branches", |name, arguments_pair, state| {
         let ([], [opt_arg]) = expect_arguments(name, arguments_pair)?;
         let pattern = if let Some(arg) = opt_arg {
@@ -1327,6 +1354,20 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
             pattern,
         )))
     });
+    map.insert("author_date", |name, arguments_pair, state| {
+        let arg = expect_one_argument(name, arguments_pair)?;
+        let pattern = parse_function_argument_to_date_pattern(name, arg, state)?;
+        Ok(RevsetExpression::filter(RevsetFilterPredicate::AuthorDate(
+            pattern,
+        )))
+    });
+    map.insert("committer_date", |name, arguments_pair, state| {
+        let arg = expect_one_argument(name, arguments_pair)?;
+        let pattern = parse_function_argument_to_date_pattern(name, arg, state)?;
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::CommitterDate(pattern),
+        ))
+    });
     map.insert("empty", |name, arguments_pair, _state| {
         expect_no_arguments(name, arguments_pair)?;
         Ok(
@@ -1363,6 +1404,30 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
             ))
         }
     });
+    map.insert("diff_contains", |name, arguments_pair, state| {
+        let ([text_arg], [files_opt_arg]) = expect_arguments(name, arguments_pair)?;
+        let text = parse_function_argument_to_string_pattern(name, text_arg, state)?;
+        let files = if let Some(files_arg) = files_opt_arg {
+            let files_span = files_arg.as_span();
+            let Some(ctx) = state.workspace_ctx else {
+                return Err(RevsetParseError::with_span(
+                    RevsetParseErrorKind::FsPathWithoutWorkspace,
+                    files_span,
+                ));
+            };
+            let ctx = FilesetParseContext {
+                cwd: ctx.cwd,
+                workspace_root: ctx.workspace_root,
+            };
+            let pattern = parse_function_argument_to_file_pattern(name, files_arg, state, &ctx)?;
+            FilesetExpression::pattern(pattern)
+        } else {
+            FilesetExpression::all()
+        };
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::DiffContains { text, files },
+        ))
+    });
     map.insert("conflict", |name, arguments_pair, _state| {
         expect_no_arguments(name, arguments_pair)?;
         Ok(RevsetExpression::filter(RevsetFilterPredicate::HasConflict))
@@ -1516,6 +1581,22 @@ fn parse_function_argument_to_string_pattern(
     parse_function_argument_as_pattern("string pattern", name, pair, state, parse_pattern)
 }
 
+fn parse_function_argument_to_date_pattern(
+    name: &str,
+    pair: Pair<Rule>,
+    state: ParseState,
+) -> Result<DatePattern, RevsetParseError> {
+    let now = chrono::Local::now();
+    let parse_pattern =
+        |value: &str, kind: Option<&str>| -> Result<_, Box<dyn error::Error + Send + Sync>> {
+            match kind {
+                Some(kind) => Ok(DatePattern::from_str_kind(value, kind, now)?),
+                None => Err(r#"Date pattern must be prefixed with "after:" or "before:""#.into()),
+            }
+        };
+    parse_function_argument_as_pattern("date pattern", name, pair, state, parse_pattern)
+}
+
 fn parse_function_argument_as_pattern<T, E: Into<Box<dyn error::Error + Send + Sync>>>(
     type_name: &str,
     function_name: &str,
@@ -2230,6 +2311,13 @@ fn resolve_commit_ref(
             Ok(wc_commits)
         }
         RevsetCommitRef::VisibleHeads => Ok(repo.view().heads().iter().cloned().collect_vec()),
+        RevsetCommitRef::AllHeads => {
+            let all_heads = repo
+                .index()
+                .all_heads_for_gc()
+                .map_err(RevsetResolutionError::HiddenCommitsUnsupported)?;
+            Ok(all_heads.collect())
+        }
         RevsetCommitRef::Root => Ok(vec![repo.store().root_commit_id().clone()]),
         RevsetCommitRef::Branches(pattern) => {
             let commit_ids = repo
@@ -2291,6 +2379,7 @@ fn resolve_symbols(
                         | RevsetResolutionError::EmptyString
                         | RevsetResolutionError::AmbiguousCommitIdPrefix(_)
                         | RevsetResolutionError::AmbiguousChangeIdPrefix(_)
+                        | RevsetResolutionError::HiddenCommitsUnsupported(_)
                         | RevsetResolutionError::StoreError(_) => Err(err),
                     })
                     .map(Some) // Always rewrite subtree
@@ -2586,6 +2675,7 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::*;
+    use crate::backend::MillisSinceEpoch;
     use crate::repo_path::RepoPathBuf;
 
     fn parse(revset_str: &str) -> Result<Rc<RevsetExpression>, RevsetParseErrorKind> {
@@ -3158,6 +3248,36 @@ mod tests {
             Err(RevsetParseErrorKind::NotInfixOperator { .. })
         );
 
+        // Date pattern requires explicit kind.
+        assert_eq!(
+            parse(r#"author_date(after:"2024-01-01T00:00:00Z")"#),
+            Ok(RevsetExpression::filter(RevsetFilterPredicate::AuthorDate(
+                DatePattern::AtOrAfter(MillisSinceEpoch(1704067200000))
+            )))
+        );
+        assert_eq!(
+            parse(r#"committer_date(before:"2024-01-01T00:00:00Z")"#),
+            Ok(RevsetExpression::filter(
+                RevsetFilterPredicate::CommitterDate(DatePattern::Before(MillisSinceEpoch(
+                    1704067200000
+                )))
+            ))
+        );
+        assert_eq!(
+            parse(r#"author_date("2024-01-01")"#),
+            Err(RevsetParseErrorKind::InvalidFunctionArguments {
+                name: "author_date".to_owned(),
+                message: "Invalid date pattern".to_owned()
+            })
+        );
+        assert_eq!(
+            parse(r#"committer_date(after:"not a date")"#),
+            Err(RevsetParseErrorKind::InvalidFunctionArguments {
+                name: "committer_date".to_owned(),
+                message: "Invalid date pattern".to_owned()
+            })
+        );
+
         // String pattern isn't allowed at top level.
         assert_matches!(
             parse(r#"exact:"foo""#),
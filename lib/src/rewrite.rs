@@ -24,7 +24,7 @@ use tracing::instrument;
 
 use crate::backend::{BackendError, BackendResult, CommitId, MergedTreeId};
 use crate::commit::Commit;
-use crate::dag_walk;
+use crate::hex_util::to_reverse_hex;
 use crate::index::Index;
 use crate::matchers::{Matcher, Visit};
 use crate::merged_tree::{MergedTree, MergedTreeBuilder};
@@ -35,6 +35,7 @@ use crate::repo_path::RepoPath;
 use crate::revset::{RevsetExpression, RevsetIteratorExt};
 use crate::settings::UserSettings;
 use crate::store::Store;
+use crate::{dag_walk, rename_detection};
 
 #[instrument(skip(repo))]
 pub fn merge_commit_trees(repo: &dyn Repo, commits: &[Commit]) -> BackendResult<MergedTree> {
@@ -167,10 +168,25 @@ pub fn rebase_commit_with_options(
         let old_base_tree = merge_commit_trees(mut_repo, &old_parents)?;
         let new_base_tree = merge_commit_trees(mut_repo, new_parents)?;
         let old_tree = old_commit.tree()?;
-        (
-            Some(old_base_tree.id()),
-            new_base_tree.merge(&old_base_tree, &old_tree)?.id(),
-        )
+        let new_tree = if options.follow_renames {
+            // Follow the renames recorded in the rebased commit and in the new
+            // parents, in addition to the detected ones
+            let copies = old_commit
+                .copies()
+                .iter()
+                .chain(new_parents.iter().flat_map(|parent| parent.copies()))
+                .cloned()
+                .collect_vec();
+            rename_detection::merge_following_renames(
+                &new_base_tree,
+                &old_base_tree,
+                &old_tree,
+                &copies,
+            )?
+        } else {
+            new_base_tree.merge(&old_base_tree, &old_tree)?
+        };
+        (Some(old_base_tree.id()), new_tree.id())
     };
     // Ensure we don't abandon commits with multiple parents (merge commits), even
     // if they're empty.
@@ -227,21 +243,69 @@ pub fn back_out_commit(
     old_commit: &Commit,
     new_parents: &[Commit],
 ) -> BackendResult<Commit> {
-    let old_base_tree = merge_commit_trees(mut_repo, &old_commit.parents())?;
-    let new_base_tree = merge_commit_trees(mut_repo, new_parents)?;
-    let old_tree = old_commit.tree()?;
-    let new_tree = new_base_tree.merge(&old_tree, &old_base_tree)?;
+    back_out_commits(
+        settings,
+        mut_repo,
+        std::slice::from_ref(old_commit),
+        new_parents,
+    )
+}
+
+/// Creates a single commit on top of `new_parents` which reverses the changes
+/// in all of the `old_commits`. They are backed out in the given order, so
+/// descendants should come before their ancestors.
+pub fn back_out_commits(
+    settings: &UserSettings,
+    mut_repo: &mut MutableRepo,
+    old_commits: &[Commit],
+    new_parents: &[Commit],
+) -> BackendResult<Commit> {
+    let mut new_tree = merge_commit_trees(mut_repo, new_parents)?;
+    for old_commit in old_commits {
+        let old_base_tree = merge_commit_trees(mut_repo, &old_commit.parents())?;
+        let old_tree = old_commit.tree()?;
+        new_tree = new_tree.merge(&old_tree, &old_base_tree)?;
+    }
     let new_parent_ids = new_parents
         .iter()
         .map(|commit| commit.id().clone())
         .collect();
-    // TODO: i18n the description based on repo language
     mut_repo
         .new_commit(settings, new_parent_ids, new_tree.id())
-        .set_description(format!("backout of commit {}", &old_commit.id().hex()))
+        .set_description(back_out_description(old_commits))
         .write()
 }
 
+// TODO: i18n the description based on repo language
+fn back_out_description(old_commits: &[Commit]) -> String {
+    let change_hex = |commit: &Commit| to_reverse_hex(&commit.change_id().hex()).unwrap();
+    let subject = |commit: &Commit| commit.description().lines().next().unwrap_or("").to_owned();
+    if let [old_commit] = old_commits {
+        let title = match subject(old_commit).as_str() {
+            "" => format!("Back out change {}", &change_hex(old_commit)[..12]),
+            subject => format!("Back out \"{subject}\""),
+        };
+        format!(
+            "{title}\n\nThis backs out change {} (commit {}).\n",
+            change_hex(old_commit),
+            old_commit.id().hex()
+        )
+    } else {
+        let mut description = format!(
+            "Back out {} changes\n\nThis backs out the following changes:\n",
+            old_commits.len()
+        );
+        for old_commit in old_commits {
+            let subject = match subject(old_commit).as_str() {
+                "" => "(no description set)".to_owned(),
+                subject => subject.to_owned(),
+            };
+            description.push_str(&format!("* {} {subject}\n", &change_hex(old_commit)[..12]));
+        }
+        description
+    }
+}
+
 #[derive(Clone, Default, PartialEq, Eq, Debug)]
 pub enum EmptyBehaviour {
     /// Always keep empty commits
@@ -269,6 +333,9 @@ pub struct RebaseOptions {
     /// If a merge commit would end up with one parent being an ancestor of the
     /// other, then filter out the ancestor.
     pub simplify_ancestor_merge: bool,
+    /// If a file modified in the rebased commit was renamed in the new parents
+    /// (or the other way around), apply the modification to the renamed file.
+    pub follow_renames: bool,
 }
 
 pub(crate) struct DescendantRebaser<'settings, 'repo> {
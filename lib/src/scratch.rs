@@ -0,0 +1,315 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pool of hidden working copies which commands can borrow to materialize
+//! commits other than the working-copy commit.
+//!
+//! Each scratch working copy is kept after use, so that checking out a similar
+//! tree later only needs to update the files which differ, and untracked files
+//! (like build outputs) are kept between uses. Changes made to the tracked
+//! files are discarded.
+
+#![allow(missing_docs)]
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use thiserror::Error;
+
+use crate::backend::{BackendError, MergedTreeId};
+use crate::file_util::{create_or_reuse_dir, IoResultExt as _, PathError};
+use crate::local_working_copy::{TreeState, TreeStateError};
+use crate::lock::FileLock;
+use crate::matchers::EverythingMatcher;
+use crate::merged_tree::MergedTree;
+use crate::store::Store;
+use crate::working_copy::{CheckoutError, CheckoutOptions};
+
+/// Number of scratch working copies kept when they are not in use.
+pub const DEFAULT_MAX_SCRATCH_WORKING_COPIES: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum ScratchError {
+    #[error(transparent)]
+    Path(#[from] PathError),
+    #[error(transparent)]
+    TreeState(#[from] TreeStateError),
+    #[error(transparent)]
+    Checkout(#[from] CheckoutError),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+/// The scratch working copies stored in a directory, typically
+/// `.jj/scratch/` in a workspace.
+pub struct ScratchPool {
+    store: Arc<Store>,
+    dir: PathBuf,
+    max_size: usize,
+}
+
+struct Slot {
+    name: String,
+    dir: PathBuf,
+    last_used: SystemTime,
+}
+
+impl Slot {
+    fn working_copy_path(&self) -> PathBuf {
+        self.dir.join("workspace")
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.dir.join("working_copy")
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.dir.join("lock")
+    }
+
+    fn load_tree_state(&self, store: &Arc<Store>) -> Result<TreeState, TreeStateError> {
+        TreeState::load(store.clone(), self.working_copy_path(), self.state_path())
+    }
+}
+
+impl ScratchPool {
+    /// Creates a pool keeping up to `max_size` scratch working copies in
+    /// `dir`. The directory is created when a working copy is first borrowed.
+    pub fn new(store: Arc<Store>, dir: PathBuf, max_size: usize) -> Self {
+        ScratchPool {
+            store,
+            dir,
+            max_size,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Checks out `tree` in a scratch working copy, which isn't used by anyone
+    /// else until the returned value is dropped.
+    ///
+    /// The free working copy whose tree is the most similar to `tree` is
+    /// reused, and a new one is created if none is free. When several are as
+    /// similar, the least recently used one is picked.
+    pub fn check_out(
+        &self,
+        tree: &MergedTree,
+        options: &CheckoutOptions,
+    ) -> Result<ScratchWorkingCopy<'_>, ScratchError> {
+        create_or_reuse_dir(&self.dir).context(&self.dir)?;
+        let pool_lock = FileLock::lock(self.dir.join("lock"));
+        let free_slots = self.free_slots()?;
+        let mut best_slot = None;
+        let mut best_distance = usize::MAX;
+        for (slot, lock) in free_slots {
+            let Ok(tree_state) = slot.load_tree_state(&self.store) else {
+                continue;
+            };
+            let distance = self.tree_distance(tree_state.current_tree_id(), tree, best_distance);
+            if distance < best_distance {
+                best_slot = Some((slot, lock));
+                best_distance = distance;
+            }
+        }
+        // The lock marks the slot as used once the pool lock is released
+        let (slot, lock) = match best_slot {
+            Some(slot_and_lock) => slot_and_lock,
+            None => {
+                let slot = self.create_slot()?;
+                let lock = FileLock::lock(slot.lock_path());
+                (slot, lock)
+            }
+        };
+        drop(pool_lock);
+
+        let scratch = ScratchWorkingCopy {
+            pool: self,
+            working_copy_path: slot.working_copy_path(),
+            _lock: lock,
+        };
+        let last_used_path = slot.dir.join("last_used");
+        fs::write(&last_used_path, "").context(&last_used_path)?;
+        if !matches!(self.update_slot(&slot, tree, options), Ok(true)) {
+            // The files are likely in a bad state, so start from scratch
+            for dir in [slot.working_copy_path(), slot.state_path()] {
+                fs::remove_dir_all(&dir).context(&dir)?;
+            }
+            self.init_slot_dirs(&slot)?;
+            self.update_slot(&slot, tree, options)?;
+        }
+        Ok(scratch)
+    }
+
+    /// Removes all the scratch working copies which are not in use, including
+    /// the ones left behind by processes which were killed.
+    pub fn clean(&self) -> Result<usize, ScratchError> {
+        if !self.dir.is_dir() {
+            return Ok(0);
+        }
+        let _pool_lock = FileLock::lock(self.dir.join("lock"));
+        let free_slots = self.free_slots()?;
+        let num_removed = free_slots.len();
+        for (slot, lock) in free_slots {
+            // Nobody can take the slot while the pool lock is held
+            drop(lock);
+            fs::remove_dir_all(&slot.dir).context(&slot.dir)?;
+        }
+        Ok(num_removed)
+    }
+
+    /// Removes the least recently used free scratch working copies so that at
+    /// most `max_size` are kept.
+    fn evict(&self) -> Result<(), ScratchError> {
+        let _pool_lock = FileLock::lock(self.dir.join("lock"));
+        let num_excess = self.slots()?.len().saturating_sub(self.max_size);
+        let free_slots = self.free_slots()?;
+        // Free slots are sorted from least recently used
+        for (slot, lock) in free_slots.into_iter().take(num_excess) {
+            drop(lock);
+            fs::remove_dir_all(&slot.dir).context(&slot.dir)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the scratch working copies, from the least recently used.
+    fn slots(&self) -> Result<Vec<Slot>, ScratchError> {
+        let mut slots = vec![];
+        for entry in self.dir.read_dir().context(&self.dir)? {
+            let entry = entry.context(&self.dir)?;
+            let dir = entry.path();
+            let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if name.parse::<usize>().is_err() || !dir.is_dir() {
+                continue;
+            }
+            let last_used = dir
+                .join("last_used")
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            slots.push(Slot {
+                name,
+                dir,
+                last_used,
+            });
+        }
+        slots.sort_by_key(|slot| slot.last_used);
+        Ok(slots)
+    }
+
+    /// Returns the scratch working copies which are not in use, from the least
+    /// recently used, with their lock held.
+    ///
+    /// The slots whose user was killed are free, since their lock was released
+    /// with the process, even though their lock file is left behind.
+    fn free_slots(&self) -> Result<Vec<(Slot, FileLock)>, ScratchError> {
+        let slots = self
+            .slots()?
+            .into_iter()
+            .filter_map(|slot| {
+                let lock = FileLock::try_lock(slot.lock_path())?;
+                Some((slot, lock))
+            })
+            .collect();
+        Ok(slots)
+    }
+
+    fn create_slot(&self) -> Result<Slot, ScratchError> {
+        let names: HashSet<String> = self.slots()?.into_iter().map(|slot| slot.name).collect();
+        let name = (0..)
+            .map(|n: usize| n.to_string())
+            .find(|name| !names.contains(name))
+            .unwrap();
+        let slot = Slot {
+            dir: self.dir.join(&name),
+            name,
+            last_used: SystemTime::now(),
+        };
+        create_or_reuse_dir(&slot.dir).context(&slot.dir)?;
+        self.init_slot_dirs(&slot)?;
+        Ok(slot)
+    }
+
+    fn init_slot_dirs(&self, slot: &Slot) -> Result<(), ScratchError> {
+        for dir in [slot.working_copy_path(), slot.state_path()] {
+            create_or_reuse_dir(&dir).context(&dir)?;
+        }
+        TreeState::init(
+            self.store.clone(),
+            slot.working_copy_path(),
+            slot.state_path(),
+        )?;
+        Ok(())
+    }
+
+    /// Discards the changes made to the tracked files of the scratch working
+    /// copy since it was checked out, and updates it to `tree`. Nothing is
+    /// written to the store. Returns false if some files couldn't be updated.
+    fn update_slot(
+        &self,
+        slot: &Slot,
+        tree: &MergedTree,
+        options: &CheckoutOptions,
+    ) -> Result<bool, ScratchError> {
+        let mut tree_state = slot.load_tree_state(&self.store)?;
+        // Slots with files in the way are recreated instead of backed up
+        let options = CheckoutOptions {
+            backup_dir: None,
+            ..options.clone()
+        };
+        let discard_stats = tree_state.discard_changes(&options)?;
+        let stats = tree_state.check_out(tree, &options)?;
+        tree_state.save()?;
+        Ok(discard_stats.skipped_files == 0 && stats.skipped_files == 0)
+    }
+
+    /// Number of paths which differ between the trees, or `max` if there are
+    /// at least as many.
+    fn tree_distance(&self, tree_id: &MergedTreeId, tree: &MergedTree, max: usize) -> usize {
+        if *tree_id == tree.id() {
+            return 0;
+        }
+        let Ok(other_tree) = self.store.get_root_tree(tree_id) else {
+            return max;
+        };
+        other_tree.diff(tree, &EverythingMatcher).take(max).count()
+    }
+}
+
+/// A scratch working copy borrowed from a `ScratchPool`. It is returned to the
+/// pool when dropped.
+pub struct ScratchWorkingCopy<'a> {
+    pool: &'a ScratchPool,
+    working_copy_path: PathBuf,
+    _lock: FileLock,
+}
+
+impl ScratchWorkingCopy<'_> {
+    /// The directory where the files are checked out.
+    pub fn working_copy_path(&self) -> &Path {
+        &self.working_copy_path
+    }
+}
+
+impl Drop for ScratchWorkingCopy<'_> {
+    fn drop(&mut self) {
+        self.pool.evict().ok();
+    }
+}
@@ -0,0 +1,368 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! High-level API for tools embedding jj.
+//!
+//! [`WorkspaceSession`] bundles a loaded workspace with the repo at its latest
+//! operation and provides the common read and write operations, taking care of
+//! rebasing descendants and updating the working copy like the `jj` CLI does.
+
+use std::io::Read as _;
+use std::path::Path;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::backend::{BackendError, CommitId, TreeValue};
+use crate::commit::Commit;
+use crate::gitignore::GitIgnoreFile;
+use crate::object_id::ObjectId as _;
+use crate::repo::{
+    CheckOutCommitError, EditCommitError, ReadonlyRepo, Repo as _, RepoLoaderError, StoreFactories,
+};
+use crate::repo_path::{RepoPath, RepoPathBuf};
+use crate::revset::{
+    self, DefaultSymbolResolver, RevsetAliasesMap, RevsetEvaluationError, RevsetIteratorExt as _,
+    RevsetParseContext, RevsetParseError, RevsetResolutionError, RevsetWorkspaceContext,
+};
+use crate::rewrite::merge_commit_trees;
+use crate::settings::UserSettings;
+use crate::transaction::Transaction;
+use crate::working_copy::{
+    CheckoutError, CheckoutOptions, SnapshotError, SnapshotOptions, WorkingCopyStateError,
+};
+use crate::workspace::{default_working_copy_factories, Workspace, WorkspaceLoadError};
+
+/// Error from a [`WorkspaceSession`] operation.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum SessionError {
+    /// The workspace couldn't be loaded.
+    #[error(transparent)]
+    WorkspaceLoad(#[from] WorkspaceLoadError),
+    /// The repo couldn't be loaded at its latest operation.
+    #[error(transparent)]
+    RepoLoad(#[from] RepoLoaderError),
+    /// A revset expression couldn't be parsed.
+    #[error(transparent)]
+    RevsetParse(#[from] RevsetParseError),
+    /// A symbol in a revset expression couldn't be resolved.
+    #[error(transparent)]
+    RevsetResolution(#[from] RevsetResolutionError),
+    /// A revset couldn't be evaluated.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    /// A revset expected to contain a single commit didn't.
+    #[error(r#"Revset "{revset}" resolved to {count} revisions instead of one"#)]
+    NotSingleRevision {
+        /// The revset expression.
+        revset: String,
+        /// The number of revisions it resolved to.
+        count: usize,
+    },
+    /// A path isn't a regular file.
+    #[error("Path {} is not a regular file", path.as_internal_file_string())]
+    NotAFile {
+        /// The path in the tree.
+        path: RepoPathBuf,
+    },
+    /// The working copy isn't at the working-copy commit of the repo.
+    #[error("The working copy is stale")]
+    StaleWorkingCopy,
+    /// The commit backend failed.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// A commit couldn't be edited in the workspace.
+    #[error(transparent)]
+    EditCommit(#[from] EditCommitError),
+    /// A commit couldn't be checked out in the workspace.
+    #[error(transparent)]
+    CheckOutCommit(#[from] CheckOutCommitError),
+    /// The working copy couldn't be updated.
+    #[error(transparent)]
+    Checkout(#[from] CheckoutError),
+    /// The working copy couldn't be snapshotted.
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+    /// The working copy state couldn't be read or written.
+    #[error(transparent)]
+    WorkingCopyState(#[from] WorkingCopyStateError),
+    /// The settings were invalid.
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
+    /// Reading file contents failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A workspace and the repo at the operation it was last loaded at.
+pub struct WorkspaceSession {
+    settings: UserSettings,
+    workspace: Workspace,
+    repo: Arc<ReadonlyRepo>,
+}
+
+impl WorkspaceSession {
+    /// Loads the workspace at `workspace_root` with the default backends and
+    /// the repo at its latest operation.
+    pub fn load(settings: &UserSettings, workspace_root: &Path) -> Result<Self, SessionError> {
+        let workspace = Workspace::load(
+            settings,
+            workspace_root,
+            &StoreFactories::default(),
+            &default_working_copy_factories(),
+        )?;
+        Self::from_workspace(settings, workspace)
+    }
+
+    /// Creates a session for an already loaded `workspace`, which allows using
+    /// custom backends. Loads the repo at its latest operation.
+    pub fn from_workspace(
+        settings: &UserSettings,
+        workspace: Workspace,
+    ) -> Result<Self, SessionError> {
+        let repo = workspace.repo_loader().load_at_head(settings)?;
+        Ok(WorkspaceSession {
+            settings: settings.clone(),
+            workspace,
+            repo,
+        })
+    }
+
+    /// The settings the session was created with.
+    pub fn settings(&self) -> &UserSettings {
+        &self.settings
+    }
+
+    /// The workspace.
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    /// The repo at the operation the session was last loaded or updated at.
+    pub fn repo(&self) -> &Arc<ReadonlyRepo> {
+        &self.repo
+    }
+
+    /// Reloads the repo at its latest operation, picking up the changes made
+    /// by other processes.
+    pub fn reload(&mut self) -> Result<(), SessionError> {
+        self.repo = self.workspace.repo_loader().load_at_head(&self.settings)?;
+        Ok(())
+    }
+
+    /// The working-copy commit of the workspace, if any.
+    pub fn working_copy_commit(&self) -> Result<Option<Commit>, SessionError> {
+        let workspace_id = self.workspace.workspace_id();
+        match self.repo.view().get_wc_commit_id(workspace_id) {
+            Some(id) => Ok(Some(self.repo.store().get_commit(id)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Evaluates the revset expression `revset_str` like the `jj` CLI does,
+    /// without user-defined aliases. Returns the commits in the order of the
+    /// revset, with children before their parents.
+    pub fn evaluate_revset(&self, revset_str: &str) -> Result<Vec<Commit>, SessionError> {
+        let workspace_root = self.workspace.workspace_root();
+        let context = RevsetParseContext {
+            aliases_map: &RevsetAliasesMap::new(),
+            user_email: self.settings.user_email(),
+            workspace: Some(RevsetWorkspaceContext {
+                cwd: workspace_root,
+                workspace_id: self.workspace.workspace_id(),
+                workspace_root,
+            }),
+        };
+        let expression = revset::optimize(revset::parse(revset_str, &context)?);
+        let repo = self.repo.as_ref();
+        let revset = expression
+            .resolve_user_expression(repo, &DefaultSymbolResolver::new(repo))?
+            .evaluate(repo)?;
+        let commits = revset.iter().commits(repo.store()).try_collect()?;
+        Ok(commits)
+    }
+
+    /// Evaluates `revset_str` and returns the commit if it contains exactly
+    /// one.
+    pub fn resolve_single_commit(&self, revset_str: &str) -> Result<Commit, SessionError> {
+        let mut commits = self.evaluate_revset(revset_str)?;
+        if commits.len() != 1 {
+            return Err(SessionError::NotSingleRevision {
+                revset: revset_str.to_owned(),
+                count: commits.len(),
+            });
+        }
+        Ok(commits.pop().unwrap())
+    }
+
+    /// Reads the commit with the given id.
+    pub fn commit(&self, id: &CommitId) -> Result<Commit, SessionError> {
+        Ok(self.repo.store().get_commit(id)?)
+    }
+
+    /// Reads the contents of the file at `path` in the tree of `commit`.
+    /// Returns `None` if there's no such path, and an error if the path isn't
+    /// a regular file or is conflicted.
+    pub fn read_file(
+        &self,
+        commit: &Commit,
+        path: &RepoPath,
+    ) -> Result<Option<Vec<u8>>, SessionError> {
+        let value = commit.tree()?.path_value(path);
+        match value.into_resolved() {
+            Ok(None) => Ok(None),
+            Ok(Some(TreeValue::File { id, .. })) => {
+                let mut contents = vec![];
+                self.repo
+                    .store()
+                    .read_file(path, &id)?
+                    .read_to_end(&mut contents)?;
+                Ok(Some(contents))
+            }
+            _ => Err(SessionError::NotAFile {
+                path: path.to_owned(),
+            }),
+        }
+    }
+
+    /// Starts a transaction on the repo. Finish it with
+    /// [`WorkspaceSession::finish_transaction()`].
+    pub fn start_transaction(&self) -> Transaction {
+        self.repo.start_transaction(&self.settings)
+    }
+
+    /// Rebases the descendants of the rewritten commits, records the operation
+    /// with the given `description`, and updates the working copy if the
+    /// working-copy commit changed.
+    pub fn finish_transaction(
+        &mut self,
+        mut tx: Transaction,
+        description: impl Into<String>,
+    ) -> Result<(), SessionError> {
+        tx.mut_repo().rebase_descendants(&self.settings)?;
+        let old_wc_commit = self.working_copy_commit()?;
+        let new_repo = tx.commit(description);
+        let workspace_id = self.workspace.workspace_id();
+        let new_wc_commit = match new_repo.view().get_wc_commit_id(workspace_id) {
+            Some(id) if old_wc_commit.as_ref().map(|c| c.id()) != Some(id) => {
+                Some(new_repo.store().get_commit(id)?)
+            }
+            _ => None,
+        };
+        self.repo = new_repo;
+        if let Some(new_wc_commit) = new_wc_commit {
+            let options = CheckoutOptions {
+                conflict_marker_options: self.settings.conflict_marker_options()?,
+                progress: None,
+                use_reflinks: self.settings.use_reflinks(),
+                reflink_cache_size: self.settings.reflink_cache_size(),
+                backup_dir: self
+                    .settings
+                    .backup_conflicting_files()
+                    .then(|| self.workspace.workspace_root().join(".jj").join("backup")),
+            };
+            self.workspace.check_out(
+                self.repo.op_id().clone(),
+                old_wc_commit.as_ref().map(|c| c.tree_id()),
+                &new_wc_commit,
+                &options,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Records the changes in the working copy in the working-copy commit.
+    /// Global and `.git/info/exclude` ignores aren't applied, only the
+    /// `.gitignore` files in the working copy. Does nothing if the workspace
+    /// has no working-copy commit.
+    pub fn snapshot(&mut self) -> Result<(), SessionError> {
+        let Some(wc_commit) = self.working_copy_commit()? else {
+            return Ok(());
+        };
+        let mut locked_ws = self.workspace.start_working_copy_mutation()?;
+        if locked_ws.locked_wc().old_tree_id() != wc_commit.tree_id() {
+            return Err(SessionError::StaleWorkingCopy);
+        }
+        let new_tree_id = locked_ws.locked_wc().snapshot(SnapshotOptions {
+            base_ignores: GitIgnoreFile::empty(),
+            fsmonitor_kind: self.settings.fsmonitor_kind()?,
+            progress: None,
+            max_new_file_size: self.settings.max_new_file_size()?,
+            exclude_paths: self.settings.snapshot_exclude_paths()?,
+            conflict_marker_options: self.settings.conflict_marker_options()?,
+        })?;
+        if new_tree_id != *wc_commit.tree_id() {
+            let mut tx = self.repo.start_transaction(&self.settings);
+            let mut_repo = tx.mut_repo();
+            mut_repo
+                .rewrite_commit(&self.settings, &wc_commit)
+                .set_tree_id(new_tree_id)
+                .write()?;
+            mut_repo.rebase_descendants(&self.settings)?;
+            self.repo = tx.commit("snapshot working copy");
+        }
+        locked_ws.finish(self.repo.op_id().clone())?;
+        Ok(())
+    }
+
+    /// Sets the description of `commit`. Returns the rewritten commit.
+    pub fn describe(&mut self, commit: &Commit, description: &str) -> Result<Commit, SessionError> {
+        let mut tx = self.start_transaction();
+        let new_commit = tx
+            .mut_repo()
+            .rewrite_commit(&self.settings, commit)
+            .set_description(description)
+            .write()?;
+        self.finish_transaction(tx, format!("describe commit {}", commit.id().hex()))?;
+        Ok(new_commit)
+    }
+
+    /// Creates a new change on top of `parents` and edits it in the workspace.
+    /// Returns the new commit.
+    pub fn new_change(
+        &mut self,
+        parents: &[Commit],
+        description: &str,
+    ) -> Result<Commit, SessionError> {
+        let mut tx = self.start_transaction();
+        let tree = merge_commit_trees(tx.repo(), parents)?;
+        let parent_ids = parents.iter().map(|c| c.id().clone()).collect();
+        let new_commit = tx
+            .mut_repo()
+            .new_commit(&self.settings, parent_ids, tree.id())
+            .set_description(description)
+            .write()?;
+        let workspace_id = self.workspace.workspace_id().clone();
+        tx.mut_repo().edit(workspace_id, &new_commit)?;
+        self.finish_transaction(tx, "new empty commit")?;
+        Ok(new_commit)
+    }
+
+    /// Makes `commit` the working-copy commit of the workspace.
+    pub fn edit(&mut self, commit: &Commit) -> Result<(), SessionError> {
+        let mut tx = self.start_transaction();
+        let workspace_id = self.workspace.workspace_id().clone();
+        tx.mut_repo().edit(workspace_id, commit)?;
+        self.finish_transaction(tx, format!("edit commit {}", commit.id().hex()))
+    }
+
+    /// Abandons `commit`, rebasing its descendants onto its parents.
+    pub fn abandon(&mut self, commit: &Commit) -> Result<(), SessionError> {
+        let mut tx = self.start_transaction();
+        tx.mut_repo().record_abandoned_commit(commit.id().clone());
+        self.finish_transaction(tx, format!("abandon commit {}", commit.id().hex()))
+    }
+}
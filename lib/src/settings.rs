@@ -17,13 +17,18 @@
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+use blake2::{Blake2b512, Digest as _};
 use chrono::DateTime;
 use rand::prelude::*;
 use rand_chacha::ChaCha20Rng;
 
 use crate::backend::{ChangeId, Commit, Signature, Timestamp};
+use crate::conflicts::{ConflictMarkerOptions, ConflictMarkerStyle, DEFAULT_CONFLICT_MARKER_LEN};
 use crate::fmt_util::binary_prefix;
 use crate::fsmonitor::FsmonitorKind;
+use crate::object_id::ObjectId as _;
+use crate::op_store::OperationId;
+use crate::repo_path::RepoPathBuf;
 use crate::signing::SignBehavior;
 
 #[derive(Debug, Clone)]
@@ -143,10 +148,49 @@ impl UserSettings {
         Ok(RepoSettings { _config: config })
     }
 
+    /// Restarts the RNG from the operation the repo is loaded at, if the
+    /// change ids should be deterministic.
+    pub(crate) fn reseed_rng_for_operation(&self, operation_id: &OperationId) {
+        if self.deterministic_ids() {
+            let seed = get_rng_seed_config(&self.config);
+            self.rng.reseed(seed, operation_id.as_bytes());
+        }
+    }
+
     pub fn get_rng(&self) -> Arc<JJRng> {
         self.rng.clone()
     }
 
+    /// Whether the change ids of new commits should be derived from the
+    /// operation they're created on, instead of being random.
+    pub fn deterministic_ids(&self) -> bool {
+        self.config
+            .get_bool("debug.deterministic-ids")
+            .unwrap_or(false)
+    }
+
+    /// Whether checked-out files should be cloned from a cache on filesystems
+    /// supporting reflinks.
+    pub fn use_reflinks(&self) -> bool {
+        self.config.get_bool("checkout.reflink").unwrap_or(true)
+    }
+
+    /// Maximum size in bytes of the cache of checked-out files used with
+    /// reflinks.
+    pub fn reflink_cache_size(&self) -> u64 {
+        self.config
+            .get::<HumanByteSize>("checkout.reflink-cache-size")
+            .map_or(1024 * 1024 * 1024, |size| size.0)
+    }
+
+    /// Whether the untracked or modified files which are in the way of a
+    /// checkout should be moved to `.jj/backup/` instead of being kept.
+    pub fn backup_conflicting_files(&self) -> bool {
+        self.config
+            .get_bool("checkout.backup-conflicting-files")
+            .unwrap_or(false)
+    }
+
     pub fn use_tree_conflict_format(&self) -> bool {
         self.config
             .get_bool("format.tree-level-conflicts")
@@ -172,6 +216,25 @@ impl UserSettings {
         }
     }
 
+    pub fn conflict_marker_options(&self) -> Result<ConflictMarkerOptions, config::ConfigError> {
+        let style = match self.config.get_string("ui.conflict-marker-style") {
+            Ok(style) => style.parse()?,
+            Err(config::ConfigError::NotFound(_)) => ConflictMarkerStyle::default(),
+            Err(err) => return Err(err),
+        };
+        let marker_len = match self.config.get::<usize>("ui.conflict-marker-length") {
+            Ok(len) if len < DEFAULT_CONFLICT_MARKER_LEN => {
+                return Err(config::ConfigError::Message(format!(
+                    "ui.conflict-marker-length must be at least {DEFAULT_CONFLICT_MARKER_LEN}"
+                )));
+            }
+            Ok(len) => len,
+            Err(config::ConfigError::NotFound(_)) => DEFAULT_CONFLICT_MARKER_LEN,
+            Err(err) => return Err(err),
+        };
+        Ok(ConflictMarkerOptions { style, marker_len })
+    }
+
     // Must not be changed to avoid git pushing older commits with no set email
     // address
     pub const USER_EMAIL_PLACEHOLDER: &'static str = "(no email configured)";
@@ -271,6 +334,35 @@ impl UserSettings {
         }
     }
 
+    /// Size above which the native backend stores files as chunks, or `None` if
+    /// files are always stored whole.
+    pub fn large_file_threshold(&self) -> Result<Option<u64>, config::ConfigError> {
+        match self
+            .config
+            .get::<HumanByteSize>("native-backend.large-file-threshold")
+        {
+            Ok(HumanByteSize(0)) | Err(config::ConfigError::NotFound(_)) => Ok(None),
+            Ok(HumanByteSize(size)) => Ok(Some(size)),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn snapshot_exclude_paths(&self) -> Result<Vec<RepoPathBuf>, config::ConfigError> {
+        let paths = match self.config.get::<Vec<String>>("snapshot.exclude-paths") {
+            Ok(paths) => paths,
+            Err(config::ConfigError::NotFound(_)) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+        paths
+            .iter()
+            .map(|path| {
+                RepoPathBuf::from_relative_path(path).map_err(|err| {
+                    config::ConfigError::Message(format!("Invalid snapshot.exclude-paths: {err}"))
+                })
+            })
+            .collect()
+    }
+
     // separate from sign_settings as those two are needed in pretty different
     // places
     pub fn signing_backend(&self) -> Option<String> {
@@ -308,6 +400,17 @@ impl JJRng {
         Self(Mutex::new(JJRng::internal_rng_from_seed(seed)))
     }
 
+    /// Restarts the RNG from a seed derived from `seed` and `data`, so the same
+    /// ids are generated from then on for the same inputs.
+    pub(crate) fn reseed(&self, seed: Option<u64>, data: &[u8]) {
+        let mut hasher = Blake2b512::new();
+        hasher.update(seed.unwrap_or_default().to_le_bytes());
+        hasher.update(data);
+        let hash = hasher.finalize();
+        let rng_seed = hash[..32].try_into().unwrap();
+        *self.0.lock().unwrap() = ChaCha20Rng::from_seed(rng_seed);
+    }
+
     fn internal_rng_from_seed(seed: Option<u64>) -> ChaCha20Rng {
         match seed {
             Some(seed) => ChaCha20Rng::seed_from_u64(seed),
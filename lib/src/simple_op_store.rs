@@ -377,7 +377,7 @@ fn operation_metadata_from_proto(
     }
 }
 
-fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operation {
+pub(crate) fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operation {
     let mut proto = crate::protos::op_store::Operation {
         view_id: operation.view_id.as_bytes().to_vec(),
         metadata: Some(operation_metadata_to_proto(&operation.metadata)),
@@ -389,7 +389,7 @@ fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operati
     proto
 }
 
-fn operation_from_proto(proto: crate::protos::op_store::Operation) -> Operation {
+pub(crate) fn operation_from_proto(proto: crate::protos::op_store::Operation) -> Operation {
     let parents = proto.parents.into_iter().map(OperationId::new).collect();
     let view_id = ViewId::new(proto.view_id);
     let metadata = operation_metadata_from_proto(proto.metadata.unwrap_or_default());
@@ -400,7 +400,7 @@ fn operation_from_proto(proto: crate::protos::op_store::Operation) -> Operation
     }
 }
 
-fn view_to_proto(view: &View) -> crate::protos::op_store::View {
+pub(crate) fn view_to_proto(view: &View) -> crate::protos::op_store::View {
     let mut proto = crate::protos::op_store::View {
         // New/loaded view should have been migrated to the latest format
         has_git_refs_migrated_to_remote: true,
@@ -437,7 +437,7 @@ fn view_to_proto(view: &View) -> crate::protos::op_store::View {
     proto
 }
 
-fn view_from_proto(proto: crate::protos::op_store::View) -> View {
+pub(crate) fn view_from_proto(proto: crate::protos::op_store::View) -> View {
     let mut view = View::default();
     // For compatibility with old repos before we had support for multiple working
     // copies
@@ -0,0 +1,310 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Patterns selecting the paths present in a sparse working copy.
+
+use std::io::Read as _;
+use std::{error, fmt};
+
+use itertools::Itertools as _;
+use thiserror::Error;
+
+use crate::backend::{BackendError, TreeValue};
+use crate::matchers::{DifferenceMatcher, FileGlobsMatcher, Matcher, PrefixMatcher, UnionMatcher};
+use crate::merged_tree::MergedTree;
+use crate::repo_path::{RelativePathParseError, RepoPathBuf};
+
+/// Directory in the tree where the sparse profiles are stored.
+pub const SPARSE_PROFILES_DIR: &str = ".sparse-profiles";
+
+/// Error occurred during sparse pattern parsing.
+#[derive(Debug, Error)]
+pub enum SparsePatternParseError {
+    /// Failed to parse workspace-relative path.
+    #[error(transparent)]
+    RelativePath(#[from] RelativePathParseError),
+    /// Failed to parse glob pattern.
+    #[error(transparent)]
+    GlobPattern(#[from] glob::PatternError),
+}
+
+/// Pattern selecting which paths are present in the working copy.
+///
+/// A path is present if it's matched by any of the including patterns and by
+/// none of the excluding patterns.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SparsePattern {
+    /// Includes the path and everything under it.
+    Prefix(RepoPathBuf),
+    /// Includes the files whose workspace-relative path matches the glob.
+    Glob(glob::Pattern),
+    /// Excludes the path and everything under it.
+    ExcludePrefix(RepoPathBuf),
+    /// Excludes the files whose workspace-relative path matches the glob.
+    ExcludeGlob(glob::Pattern),
+}
+
+impl SparsePattern {
+    /// Parses the given string as a `SparsePattern`.
+    ///
+    /// A leading "!" turns the pattern into an exclusion. The rest is a glob if
+    /// it starts with "glob:", and a workspace-relative path otherwise.
+    pub fn parse(src: &str) -> Result<SparsePattern, SparsePatternParseError> {
+        let (exclude, src) = match src.strip_prefix('!') {
+            Some(src) => (true, src),
+            None => (false, src),
+        };
+        let pattern = if let Some(glob) = src.strip_prefix("glob:") {
+            let glob = glob::Pattern::new(glob)?;
+            if exclude {
+                SparsePattern::ExcludeGlob(glob)
+            } else {
+                SparsePattern::Glob(glob)
+            }
+        } else {
+            let path = RepoPathBuf::from_relative_path(src)?;
+            if exclude {
+                SparsePattern::ExcludePrefix(path)
+            } else {
+                SparsePattern::Prefix(path)
+            }
+        };
+        Ok(pattern)
+    }
+}
+
+impl From<RepoPathBuf> for SparsePattern {
+    fn from(path: RepoPathBuf) -> Self {
+        SparsePattern::Prefix(path)
+    }
+}
+
+impl fmt::Display for SparsePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt_path = |f: &mut fmt::Formatter<'_>, path: &RepoPathBuf| {
+            if path.is_root() {
+                write!(f, ".")
+            } else {
+                write!(f, "{}", path.as_internal_file_string())
+            }
+        };
+        match self {
+            SparsePattern::Prefix(path) => fmt_path(f, path),
+            SparsePattern::Glob(glob) => write!(f, "glob:{}", glob.as_str()),
+            SparsePattern::ExcludePrefix(path) => {
+                write!(f, "!")?;
+                fmt_path(f, path)
+            }
+            SparsePattern::ExcludeGlob(glob) => write!(f, "!glob:{}", glob.as_str()),
+        }
+    }
+}
+
+/// Builds a matcher for the paths selected by the sparse `patterns`.
+pub fn sparse_matcher(patterns: &[SparsePattern]) -> Box<dyn Matcher> {
+    let mut prefixes = vec![];
+    let mut globs = vec![];
+    let mut excluded_prefixes = vec![];
+    let mut excluded_globs = vec![];
+    for pattern in patterns {
+        match pattern {
+            SparsePattern::Prefix(path) => prefixes.push(path),
+            SparsePattern::Glob(glob) => globs.push(glob.clone()),
+            SparsePattern::ExcludePrefix(path) => excluded_prefixes.push(path),
+            SparsePattern::ExcludeGlob(glob) => excluded_globs.push(glob.clone()),
+        }
+    }
+    let included = PrefixMatcher::new(prefixes);
+    if globs.is_empty() && excluded_prefixes.is_empty() && excluded_globs.is_empty() {
+        return Box::new(included);
+    }
+    let included = UnionMatcher::new(included, FileGlobsMatcher::new(globs));
+    let excluded = UnionMatcher::new(
+        PrefixMatcher::new(excluded_prefixes),
+        FileGlobsMatcher::new(excluded_globs),
+    );
+    Box::new(DifferenceMatcher::new(included, excluded))
+}
+
+/// Sorts the patterns and removes duplicates, listing the including patterns
+/// before the excluding ones.
+pub fn normalize_sparse_patterns(
+    patterns: impl IntoIterator<Item = SparsePattern>,
+) -> Vec<SparsePattern> {
+    patterns.into_iter().sorted_unstable().dedup().collect()
+}
+
+/// Error occurred while reading a sparse profile from a tree.
+#[derive(Debug, Error)]
+pub enum SparseProfileError {
+    /// The name can't be used as a file name.
+    #[error(r#"Invalid sparse profile name "{0}""#)]
+    InvalidName(String),
+    /// There's no profile file with that name in the tree.
+    #[error(r#"No sparse profile named "{0}""#)]
+    NotFound(String),
+    /// The profile file has conflicts.
+    #[error(r#"Sparse profile "{0}" has conflicts"#)]
+    Conflicted(String),
+    /// Failed to read the profile file.
+    #[error(r#"Failed to read sparse profile "{name}""#)]
+    Backend {
+        /// Name of the profile.
+        name: String,
+        /// The underlying error.
+        source: BackendError,
+    },
+    /// The profile file isn't a valid profile.
+    #[error(r#"Failed to parse sparse profile "{name}""#)]
+    Parse {
+        /// Name of the profile.
+        name: String,
+        /// The underlying error.
+        source: Box<dyn error::Error + Send + Sync>,
+    },
+}
+
+/// Returns the path of the sparse profile `name` in the tree.
+pub fn sparse_profile_path(name: &str) -> Result<RepoPathBuf, SparseProfileError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(SparseProfileError::InvalidName(name.to_owned()));
+    }
+    Ok(RepoPathBuf::from_internal_string(format!(
+        "{SPARSE_PROFILES_DIR}/{name}.toml"
+    )))
+}
+
+/// Reads the patterns of the sparse profile `name` from the `tree`.
+///
+/// A profile is a TOML file with a `patterns` list, each in the syntax accepted
+/// by `SparsePattern::parse()`.
+pub fn read_sparse_profile(
+    tree: &MergedTree,
+    name: &str,
+) -> Result<Vec<SparsePattern>, SparseProfileError> {
+    let path = sparse_profile_path(name)?;
+    let id = match tree.path_value(&path).into_resolved() {
+        Ok(Some(TreeValue::File { id, .. })) => id,
+        Ok(_) => return Err(SparseProfileError::NotFound(name.to_owned())),
+        Err(_) => return Err(SparseProfileError::Conflicted(name.to_owned())),
+    };
+    let backend_error = |source| SparseProfileError::Backend {
+        name: name.to_owned(),
+        source,
+    };
+    let parse_error = |source: Box<dyn error::Error + Send + Sync>| SparseProfileError::Parse {
+        name: name.to_owned(),
+        source,
+    };
+    let mut content = String::new();
+    tree.store()
+        .read_file(&path, &id)
+        .map_err(backend_error)?
+        .read_to_string(&mut content)
+        .map_err(|err| parse_error(err.into()))?;
+    let patterns: Vec<String> = config::Config::builder()
+        .add_source(config::File::from_str(&content, config::FileFormat::Toml))
+        .build()
+        .and_then(|config| config.get("patterns"))
+        .map_err(|err| parse_error(err.into()))?;
+    let patterns: Vec<_> = patterns
+        .iter()
+        .map(|pattern| SparsePattern::parse(pattern))
+        .try_collect()
+        .map_err(|err| parse_error(err.into()))?;
+    Ok(normalize_sparse_patterns(patterns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo_path::RepoPath;
+
+    fn repo_path(value: &str) -> &RepoPath {
+        RepoPath::from_internal_string(value)
+    }
+
+    fn parse(src: &str) -> SparsePattern {
+        SparsePattern::parse(src).unwrap()
+    }
+
+    #[test]
+    fn test_parse_display() {
+        for src in [
+            ".",
+            "lib",
+            "lib/src",
+            "glob:**/*.md",
+            "!lib/tests",
+            "!glob:*.png",
+        ] {
+            assert_eq!(parse(src).to_string(), src);
+        }
+        assert_eq!(parse(""), SparsePattern::Prefix(RepoPathBuf::root()));
+        assert_eq!(
+            parse("!."),
+            SparsePattern::ExcludePrefix(RepoPathBuf::root())
+        );
+        assert!(SparsePattern::parse("../lib").is_err());
+        assert!(SparsePattern::parse("glob:a**b").is_err());
+    }
+
+    #[test]
+    fn test_sparse_matcher() {
+        let m = sparse_matcher(&[parse("lib"), parse("glob:*.md"), parse("!lib/tests")]);
+        assert!(m.matches(repo_path("README.md")));
+        assert!(!m.matches(repo_path("docs/index.md")));
+        assert!(m.matches(repo_path("lib/src/lib.rs")));
+        assert!(!m.matches(repo_path("lib/tests/test.rs")));
+        assert!(!m.matches(repo_path("cli/main.rs")));
+
+        let m = sparse_matcher(&[parse("."), parse("!glob:**/*.png")]);
+        assert!(m.matches(repo_path("docs/index.md")));
+        assert!(!m.matches(repo_path("logo.png")));
+        assert!(!m.matches(repo_path("docs/images/logo.png")));
+    }
+
+    #[test]
+    fn test_sparse_profile_path() {
+        assert_eq!(
+            sparse_profile_path("frontend")
+                .unwrap()
+                .as_internal_file_string(),
+            ".sparse-profiles/frontend.toml"
+        );
+        assert!(sparse_profile_path("").is_err());
+        assert!(sparse_profile_path("..").is_err());
+        assert!(sparse_profile_path("a/b").is_err());
+    }
+
+    #[test]
+    fn test_normalize_sparse_patterns() {
+        let patterns = normalize_sparse_patterns([
+            parse("!lib/tests"),
+            parse("lib"),
+            parse("glob:*.md"),
+            parse("."),
+            parse("lib"),
+        ]);
+        assert_eq!(
+            patterns,
+            vec![
+                parse("."),
+                parse("lib"),
+                parse("glob:*.md"),
+                parse("!lib/tests")
+            ]
+        );
+    }
+}
@@ -30,6 +30,7 @@ use crate::backend::{
 use crate::commit::Commit;
 use crate::index::Index;
 use crate::merge::{Merge, MergedTreeValue};
+use crate::merge_driver::MergeDrivers;
 use crate::merged_tree::MergedTree;
 use crate::repo_path::{RepoPath, RepoPathBuf};
 use crate::signing::Signer;
@@ -44,6 +45,7 @@ pub struct Store {
     commit_cache: RwLock<HashMap<CommitId, Arc<backend::Commit>>>,
     tree_cache: RwLock<HashMap<(RepoPathBuf, TreeId), Arc<backend::Tree>>>,
     use_tree_conflict_format: bool,
+    merge_drivers: MergeDrivers,
 }
 
 impl Debug for Store {
@@ -59,6 +61,7 @@ impl Store {
         backend: Box<dyn Backend>,
         signer: Signer,
         use_tree_conflict_format: bool,
+        merge_drivers: MergeDrivers,
     ) -> Arc<Self> {
         Arc::new(Store {
             backend,
@@ -66,6 +69,7 @@ impl Store {
             commit_cache: Default::default(),
             tree_cache: Default::default(),
             use_tree_conflict_format,
+            merge_drivers,
         })
     }
 
@@ -73,6 +77,11 @@ impl Store {
         self.backend.as_any()
     }
 
+    /// Name of the commit backend, as recorded in the repo's store.
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
     pub fn signer(&self) -> &Signer {
         &self.signer
     }
@@ -82,6 +91,11 @@ impl Store {
         self.use_tree_conflict_format
     }
 
+    /// Drivers to resolve file conflicts which can't be merged by lines.
+    pub fn merge_drivers(&self) -> &MergeDrivers {
+        &self.merge_drivers
+    }
+
     pub fn commit_id_length(&self) -> usize {
         self.backend.commit_id_length()
     }
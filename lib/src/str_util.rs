@@ -30,11 +30,14 @@ pub enum StringPatternParseError {
     /// Failed to parse glob pattern.
     #[error(transparent)]
     GlobPattern(glob::PatternError),
+    /// Failed to parse regular expression.
+    #[error(transparent)]
+    Regex(regex::Error),
 }
 
 /// Pattern to be tested against string property like commit description or
 /// branch name.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum StringPattern {
     /// Matches strings exactly equal to `string`.
     Exact(String),
@@ -42,8 +45,26 @@ pub enum StringPattern {
     Glob(glob::Pattern),
     /// Matches strings that contain `substring`.
     Substring(String),
+    /// Matches strings that contain a match of the regular expression.
+    Regex(regex::Regex),
+}
+
+impl PartialEq for StringPattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (StringPattern::Exact(a), StringPattern::Exact(b)) => a == b,
+            (StringPattern::Glob(a), StringPattern::Glob(b)) => a == b,
+            (StringPattern::Substring(a), StringPattern::Substring(b)) => a == b,
+            // Compiled regexes aren't comparable, but equal sources compile to
+            // equivalent ones.
+            (StringPattern::Regex(a), StringPattern::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
 }
 
+impl Eq for StringPattern {}
+
 impl StringPattern {
     /// Pattern that matches any string.
     pub const fn everything() -> Self {
@@ -52,7 +73,8 @@ impl StringPattern {
 
     /// Parses the given string as a `StringPattern`. Everything before the
     /// first ":" is considered the string's prefix. If the prefix is "exact:",
-    /// "glob:", or "substring:", a pattern of the specified kind is returned.
+    /// "glob:", "substring:", or "regex:", a pattern of the specified kind is
+    /// returned.
     /// Returns an error if the string has an unrecognized prefix. Otherwise, a
     /// `StringPattern::Exact` is returned.
     pub fn parse(src: &str) -> Result<StringPattern, StringPatternParseError> {
@@ -77,12 +99,19 @@ impl StringPattern {
         Ok(StringPattern::Glob(pattern))
     }
 
+    /// Parses the given string as regular expression.
+    pub fn regex(src: &str) -> Result<Self, StringPatternParseError> {
+        let regex = regex::Regex::new(src).map_err(StringPatternParseError::Regex)?;
+        Ok(StringPattern::Regex(regex))
+    }
+
     /// Parses the given string as pattern of the specified `kind`.
     pub fn from_str_kind(src: &str, kind: &str) -> Result<Self, StringPatternParseError> {
         match kind {
             "exact" => Ok(StringPattern::exact(src)),
             "glob" => StringPattern::glob(src),
             "substring" => Ok(StringPattern::Substring(src.to_owned())),
+            "regex" => StringPattern::regex(src),
             _ => Err(StringPatternParseError::InvalidKind(kind.to_owned())),
         }
     }
@@ -98,7 +127,7 @@ impl StringPattern {
     pub fn as_exact(&self) -> Option<&str> {
         match self {
             StringPattern::Exact(literal) => Some(literal),
-            StringPattern::Glob(_) | StringPattern::Substring(_) => None,
+            StringPattern::Glob(_) | StringPattern::Substring(_) | StringPattern::Regex(_) => None,
         }
     }
 
@@ -108,13 +137,13 @@ impl StringPattern {
             StringPattern::Exact(literal) => literal,
             StringPattern::Glob(pattern) => pattern.as_str(),
             StringPattern::Substring(needle) => needle,
+            StringPattern::Regex(regex) => regex.as_str(),
         }
     }
 
     /// Converts this pattern to a glob string. Returns `None` if the pattern
     /// can't be represented as a glob.
     pub fn to_glob(&self) -> Option<Cow<'_, str>> {
-        // TODO: If we add Regex pattern, it will return None.
         match self {
             StringPattern::Exact(literal) => Some(glob::Pattern::escape(literal).into()),
             StringPattern::Glob(pattern) => Some(pattern.as_str().into()),
@@ -122,6 +151,7 @@ impl StringPattern {
             StringPattern::Substring(needle) => {
                 Some(format!("*{}*", glob::Pattern::escape(needle)).into())
             }
+            StringPattern::Regex(_) => None,
         }
     }
 
@@ -131,6 +161,7 @@ impl StringPattern {
             StringPattern::Exact(literal) => haystack == literal,
             StringPattern::Glob(pattern) => pattern.matches(haystack),
             StringPattern::Substring(needle) => haystack.contains(needle),
+            StringPattern::Regex(regex) => regex.is_match(haystack),
         }
     }
 
@@ -175,6 +206,7 @@ mod tests {
             StringPattern::Substring("*".into()).to_glob(),
             Some("*[*]*".into())
         );
+        assert_eq!(StringPattern::regex("a.*").unwrap().to_glob(), None);
     }
 
     #[test]
@@ -192,6 +224,16 @@ mod tests {
             StringPattern::parse("substring:foo").unwrap(),
             StringPattern::from_str_kind("foo", "substring").unwrap()
         );
+        assert_eq!(
+            StringPattern::parse("regex:^fo+$").unwrap(),
+            StringPattern::from_str_kind("^fo+$", "regex").unwrap()
+        );
+        assert!(StringPattern::parse("regex:^fo+$").unwrap().matches("foo"));
+        assert!(!StringPattern::parse("regex:^fo+$").unwrap().matches("bar"));
+        assert!(matches! {
+            StringPattern::parse("regex:("),
+            Err(StringPatternParseError::Regex(_))
+        });
 
         // Parse a pattern that contains a : itself.
         assert_eq!(
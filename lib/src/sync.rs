@@ -0,0 +1,1154 @@
+// Copyright 2026 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol to exchange commits and operations directly between two repos.
+//!
+//! Unlike exchanging commits through Git, this keeps the change ids and the
+//! predecessors of the commits, and can exchange the operation logs too. The
+//! two sides of a connection, such as `jj sync` and a `jj` process started on
+//! another host through SSH, talk over a pair of byte streams:
+//!
+//! 1. The client and the server exchange `Hello` messages to check that their
+//!    repos are compatible, and then the ids of their heads.
+//! 2. The client fetches the objects it's missing from the server. It sends
+//!    the ids of the objects it wants, the server replies with their contents,
+//!    and the client finds the ids of more objects in these contents, until it
+//!    has all the objects reachable from the server's heads.
+//! 3. The server fetches the objects it's missing from the client the same way.
+//!
+//! The objects keep their ids, so both repos must use the same commit backend,
+//! and the same operation store if the operations are exchanged.
+
+#![allow(missing_docs)]
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::iter;
+
+use itertools::Itertools as _;
+use prost::Message as _;
+use thiserror::Error;
+
+use crate::backend::{
+    self, BackendError, BackendResult, CommitId, ConflictId, FileId, SymlinkId, TreeId, TreeValue,
+};
+use crate::dag_walk;
+use crate::git::REMOTE_NAME_FOR_LOCAL_GIT_REPO;
+use crate::local_backend::{commit_from_proto, commit_to_proto};
+use crate::merge::{Merge, MergedTreeValue};
+use crate::object_id::ObjectId;
+use crate::op_store::{self, OpStoreError, OperationId, ViewId};
+use crate::operation::Operation;
+use crate::repo::{MutableRepo, ReadonlyRepo, Repo};
+use crate::repo_path::{RepoPathBuf, RepoPathComponentBuf};
+use crate::simple_op_store::{
+    operation_from_proto, operation_to_proto, view_from_proto, view_to_proto,
+};
+use crate::view::View;
+
+/// Version of the protocol, incremented on incompatible changes.
+pub const SYNC_PROTOCOL_VERSION: u32 = 1;
+
+/// Maximum number of objects requested in a single message.
+const MAX_WANTED_OBJECTS: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error("Failed to communicate with the other repo")]
+    Io(#[source] io::Error),
+    #[error("The other repo closed the connection")]
+    Disconnected,
+    #[error("Invalid message from the other repo: {0}")]
+    InvalidMessage(String),
+    #[error(
+        "The other repo uses version {remote} of the sync protocol, but this repo uses version \
+         {local}"
+    )]
+    IncompatibleProtocol { local: u32, remote: u32 },
+    #[error(
+        r#"The other repo uses the "{remote}" {kind}, but this repo uses the "{local}" {kind}"#
+    )]
+    IncompatibleStore {
+        kind: &'static str,
+        local: String,
+        remote: String,
+    },
+    #[error("The {kind} {id} received from the other repo was written with the id {written_id}")]
+    IdMismatch {
+        kind: &'static str,
+        id: String,
+        written_id: String,
+    },
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+}
+
+impl From<io::Error> for SyncError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::BrokenPipe => SyncError::Disconnected,
+            _ => SyncError::Io(err),
+        }
+    }
+}
+
+fn invalid_message(message: impl Into<String>) -> SyncError {
+    SyncError::InvalidMessage(message.into())
+}
+
+/// The heads of the other repo. All the objects reachable from them are in
+/// this repo after syncing.
+#[derive(Debug)]
+pub enum RemoteHeads {
+    /// The visible heads of the other repo.
+    Commits(Vec<CommitId>),
+    /// The operation the other repo was loaded at, if the operation logs were
+    /// exchanged.
+    Operation(Operation),
+}
+
+/// Numbers of objects sent or received.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SyncStats {
+    pub num_commits: usize,
+    pub num_operations: usize,
+}
+
+#[derive(Debug)]
+pub struct SyncResult {
+    pub remote_heads: RemoteHeads,
+    pub received: SyncStats,
+    pub sent: SyncStats,
+}
+
+/// Two-way connection with the other side of the protocol.
+pub struct SyncConnection {
+    reader: BufReader<Box<dyn Read>>,
+    writer: BufWriter<Box<dyn Write>>,
+}
+
+impl SyncConnection {
+    pub fn new(reader: impl Read + 'static, writer: impl Write + 'static) -> Self {
+        SyncConnection {
+            reader: BufReader::new(Box::new(reader)),
+            writer: BufWriter::new(Box::new(writer)),
+        }
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), SyncError> {
+        let data = message.encode();
+        self.writer.write_all(&(data.len() as u64).to_be_bytes())?;
+        self.writer.write_all(&data)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Message, SyncError> {
+        let mut len = [0; 8];
+        self.reader.read_exact(&mut len)?;
+        let len = u64::from_be_bytes(len);
+        let mut data = vec![];
+        (&mut self.reader).take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(SyncError::Disconnected);
+        }
+        Message::decode(&data)
+    }
+}
+
+/// Syncs with the server at the other end of the `connection`, which is
+/// running `sync_as_server()`. The objects are only exchanged, the caller
+/// decides what to do with the returned heads of the server.
+pub fn sync_as_client(
+    connection: &mut SyncConnection,
+    repo: &ReadonlyRepo,
+    op_log: bool,
+) -> Result<SyncResult, SyncError> {
+    let hello = Hello::new(repo, op_log);
+    connection.send(&Message::Hello(hello.clone()))?;
+    let remote_hello = connection.receive()?.into_hello()?;
+    hello.check_compatible(&remote_hello)?;
+    connection.send(&Message::Heads(local_heads(repo, op_log)))?;
+    let remote_head_ids = connection.receive()?.into_heads()?;
+    let received = fetch(connection, repo, op_log, &remote_head_ids)?;
+    let sent = serve(connection, repo)?;
+    Ok(SyncResult {
+        remote_heads: to_remote_heads(repo, op_log, remote_head_ids)?,
+        received,
+        sent,
+    })
+}
+
+/// Syncs with the client at the other end of the `connection`, which decides
+/// whether the operation logs are exchanged.
+pub fn sync_as_server(
+    connection: &mut SyncConnection,
+    repo: &ReadonlyRepo,
+) -> Result<SyncResult, SyncError> {
+    let remote_hello = connection.receive()?.into_hello()?;
+    let op_log = remote_hello.op_log;
+    let hello = Hello::new(repo, op_log);
+    connection.send(&Message::Hello(hello.clone()))?;
+    // The client closes the connection if the repos are incompatible, so that
+    // only one side reports the error.
+    let remote_head_ids = connection.receive()?.into_heads()?;
+    hello.check_compatible(&remote_hello)?;
+    connection.send(&Message::Heads(local_heads(repo, op_log)))?;
+    let sent = serve(connection, repo)?;
+    let received = fetch(connection, repo, op_log, &remote_head_ids)?;
+    Ok(SyncResult {
+        remote_heads: to_remote_heads(repo, op_log, remote_head_ids)?,
+        received,
+        sent,
+    })
+}
+
+fn local_heads(repo: &ReadonlyRepo, op_log: bool) -> Vec<Vec<u8>> {
+    if op_log {
+        vec![repo.op_id().to_bytes()]
+    } else {
+        repo.view().heads().iter().map(|id| id.to_bytes()).collect()
+    }
+}
+
+fn to_remote_heads(
+    repo: &ReadonlyRepo,
+    op_log: bool,
+    head_ids: Vec<Vec<u8>>,
+) -> Result<RemoteHeads, SyncError> {
+    if op_log {
+        let [id] = <[_; 1]>::try_from(head_ids)
+            .map_err(|_| invalid_message("expected a single operation head"))?;
+        let id = OperationId::new(id);
+        let data = repo.op_store().read_operation(&id)?;
+        Ok(RemoteHeads::Operation(Operation::new(
+            repo.op_store().clone(),
+            id,
+            data,
+        )))
+    } else {
+        Ok(RemoteHeads::Commits(
+            head_ids.into_iter().map(CommitId::new).collect(),
+        ))
+    }
+}
+
+/// Makes the visible heads of the other repo visible in this repo.
+pub fn add_remote_heads(mut_repo: &mut MutableRepo, head_ids: &[CommitId]) -> BackendResult<()> {
+    let root_commit_id = mut_repo.store().root_commit_id().clone();
+    let heads: Vec<_> = head_ids
+        .iter()
+        .filter(|&id| *id != root_commit_id)
+        .map(|id| mut_repo.store().get_commit(id))
+        .try_collect()?;
+    mut_repo.add_heads(&heads)
+}
+
+/// Reverts the changes made by merging an operation of another repo to the
+/// state which only makes sense in this repo: the working-copy commits, and
+/// the refs of the Git repo backing this repo. The working-copy commits of the
+/// other repo are abandoned if they don't have any change.
+pub fn keep_local_view_state(
+    mut_repo: &mut MutableRepo,
+    local_view: &View,
+    remote_view: &View,
+) -> BackendResult<()> {
+    let merged_workspace_ids = mut_repo
+        .view()
+        .wc_commit_ids()
+        .keys()
+        .cloned()
+        .collect_vec();
+    for workspace_id in merged_workspace_ids {
+        if local_view.get_wc_commit_id(&workspace_id).is_none() {
+            mut_repo.remove_wc_commit(&workspace_id);
+        }
+    }
+    for (workspace_id, commit_id) in local_view.wc_commit_ids() {
+        mut_repo
+            .set_wc_commit(workspace_id.clone(), commit_id.clone())
+            .expect("working-copy commit shouldn't be the root commit");
+    }
+
+    let git_ref_names: BTreeSet<String> = mut_repo
+        .view()
+        .git_refs()
+        .keys()
+        .chain(local_view.git_refs().keys())
+        .cloned()
+        .collect();
+    for name in &git_ref_names {
+        mut_repo.set_git_ref_target(name, local_view.get_git_ref(name).clone());
+    }
+    mut_repo.set_git_head_target(local_view.git_head().clone());
+    let git_branch_names: BTreeSet<String> = mut_repo
+        .view()
+        .remote_branches(REMOTE_NAME_FOR_LOCAL_GIT_REPO)
+        .chain(local_view.remote_branches(REMOTE_NAME_FOR_LOCAL_GIT_REPO))
+        .map(|(name, _)| name.to_owned())
+        .collect();
+    for name in &git_branch_names {
+        let remote_ref = local_view.get_remote_branch(name, REMOTE_NAME_FOR_LOCAL_GIT_REPO);
+        mut_repo.set_remote_branch(name, REMOTE_NAME_FOR_LOCAL_GIT_REPO, remote_ref.clone());
+    }
+
+    for commit_id in remote_view.wc_commit_ids().values() {
+        let view = mut_repo.view();
+        if !view.heads().contains(commit_id)
+            || view.is_wc_commit_id(commit_id)
+            || local_view.heads().contains(commit_id)
+            || view
+                .local_branches()
+                .any(|(_, target)| target.added_ids().contains(commit_id))
+        {
+            continue;
+        }
+        if mut_repo.store().get_commit(commit_id)?.is_discardable() {
+            mut_repo.record_abandoned_commit(commit_id.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the objects reachable from the `head_ids` of the other repo, and
+/// writes them to the `repo`.
+fn fetch(
+    connection: &mut SyncConnection,
+    repo: &ReadonlyRepo,
+    op_log: bool,
+    head_ids: &[Vec<u8>],
+) -> Result<SyncStats, SyncError> {
+    let kind = if op_log {
+        ObjectKind::Operation
+    } else {
+        ObjectKind::Commit
+    };
+    let mut fetcher = Fetcher::new(repo);
+    for id in head_ids {
+        fetcher.want(kind, RepoPathBuf::root(), id)?;
+    }
+    while !fetcher.pending.is_empty() {
+        let num_wanted = fetcher.pending.len().min(MAX_WANTED_OBJECTS);
+        let wanted = fetcher.pending.drain(..num_wanted).collect_vec();
+        connection.send(&Message::Want(wanted.clone()))?;
+        let contents = connection.receive()?.into_objects()?;
+        if contents.len() != wanted.len() {
+            return Err(invalid_message("wrong number of objects"));
+        }
+        for (object, data) in iter::zip(wanted, contents) {
+            fetcher.add_object(object, data)?;
+        }
+    }
+    connection.send(&Message::Done)?;
+    fetcher.received.write(repo)
+}
+
+/// Sends the objects wanted by the other repo until it's done fetching.
+fn serve(connection: &mut SyncConnection, repo: &ReadonlyRepo) -> Result<SyncStats, SyncError> {
+    let mut stats = SyncStats::default();
+    loop {
+        let wanted = match connection.receive()? {
+            Message::Want(wanted) => wanted,
+            Message::Done => return Ok(stats),
+            message => return Err(message.unexpected()),
+        };
+        let contents: Vec<_> = wanted
+            .iter()
+            .map(|object| read_object(repo, object))
+            .try_collect()?;
+        for object in &wanted {
+            match object.kind {
+                ObjectKind::Commit => stats.num_commits += 1,
+                ObjectKind::Operation => stats.num_operations += 1,
+                _ => {}
+            }
+        }
+        connection.send(&Message::Objects(contents))?;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum ObjectKind {
+    Commit,
+    Tree,
+    File,
+    Symlink,
+    Conflict,
+    Operation,
+    View,
+}
+
+impl ObjectKind {
+    const ALL: [ObjectKind; 7] = [
+        ObjectKind::Commit,
+        ObjectKind::Tree,
+        ObjectKind::File,
+        ObjectKind::Symlink,
+        ObjectKind::Conflict,
+        ObjectKind::Operation,
+        ObjectKind::View,
+    ];
+}
+
+/// Id of an object, with the path of the file or tree for the objects of the
+/// commit backend.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct ObjectRef {
+    kind: ObjectKind,
+    path: RepoPathBuf,
+    id: Vec<u8>,
+}
+
+/// Objects received from the other repo, except for the files and symlinks,
+/// which are written as they're received since they don't refer to other
+/// objects. The other objects are written once they're all received, so that
+/// each object is written after the objects it refers to, which some backends
+/// require.
+#[derive(Default)]
+struct ReceivedObjects {
+    conflicts: Vec<(RepoPathBuf, ConflictId, MergedTreeValue)>,
+    trees: Vec<(RepoPathBuf, TreeId, backend::Tree)>,
+    commits: HashMap<CommitId, backend::Commit>,
+    views: Vec<(ViewId, op_store::View)>,
+    operations: HashMap<OperationId, op_store::Operation>,
+}
+
+impl ReceivedObjects {
+    fn write(mut self, repo: &ReadonlyRepo) -> Result<SyncStats, SyncError> {
+        let store = repo.store();
+        for (path, id, conflict) in &self.conflicts {
+            check_written_id("conflict", id, &store.write_conflict(path, conflict)?)?;
+        }
+        // Subtrees are deeper than their parent trees
+        self.trees
+            .sort_by_key(|(path, _, _)| Reverse(path.components().count()));
+        for (path, id, tree) in self.trees {
+            check_written_id("tree", &id, store.write_tree(&path, tree)?.id())?;
+        }
+
+        let stats = SyncStats {
+            num_commits: self.commits.len(),
+            num_operations: self.operations.len(),
+        };
+        let commit_ids = dag_walk::topo_order_forward(
+            self.commits.keys().cloned(),
+            |id| id.clone(),
+            |id| {
+                let commit = &self.commits[id];
+                commit
+                    .parents
+                    .iter()
+                    .chain(&commit.predecessors)
+                    .filter(|id| self.commits.contains_key(id))
+                    .cloned()
+                    .collect_vec()
+            },
+        );
+        for id in commit_ids {
+            let mut commit = self.commits.remove(&id).unwrap();
+            // Signing the commit with its existing signature gives it the same
+            // id as in the other repo.
+            let written_commit = match commit.secure_sig.take() {
+                Some(secure_sig) => {
+                    let mut sign = |_: &[u8]| Ok(secure_sig.sig.clone());
+                    store.write_commit(commit, Some(&mut sign))?
+                }
+                None => store.write_commit(commit, None)?,
+            };
+            check_written_id("commit", &id, written_commit.id())?;
+        }
+
+        let op_store = repo.op_store();
+        for (id, view) in &self.views {
+            check_written_id("view", id, &op_store.write_view(view)?)?;
+        }
+        let operation_ids = dag_walk::topo_order_forward(
+            self.operations.keys().cloned(),
+            |id| id.clone(),
+            |id| {
+                self.operations[id]
+                    .parents
+                    .iter()
+                    .filter(|id| self.operations.contains_key(id))
+                    .cloned()
+                    .collect_vec()
+            },
+        );
+        for id in operation_ids {
+            let operation = &self.operations[&id];
+            check_written_id("operation", &id, &op_store.write_operation(operation)?)?;
+        }
+        Ok(stats)
+    }
+}
+
+fn check_written_id<T: ObjectId + PartialEq>(
+    kind: &'static str,
+    id: &T,
+    written_id: &T,
+) -> Result<(), SyncError> {
+    if id == written_id {
+        Ok(())
+    } else {
+        Err(SyncError::IdMismatch {
+            kind,
+            id: id.hex(),
+            written_id: written_id.hex(),
+        })
+    }
+}
+
+/// Finds the objects to fetch from the other repo.
+struct Fetcher<'a> {
+    repo: &'a ReadonlyRepo,
+    wanted: HashSet<ObjectRef>,
+    pending: Vec<ObjectRef>,
+    received: ReceivedObjects,
+}
+
+impl<'a> Fetcher<'a> {
+    fn new(repo: &'a ReadonlyRepo) -> Self {
+        Fetcher {
+            repo,
+            wanted: HashSet::new(),
+            pending: vec![],
+            received: ReceivedObjects::default(),
+        }
+    }
+
+    /// Requests the object if it's not already in the repo.
+    fn want(&mut self, kind: ObjectKind, path: RepoPathBuf, id: &[u8]) -> Result<(), SyncError> {
+        let object = ObjectRef {
+            kind,
+            path,
+            id: id.to_vec(),
+        };
+        if self.wanted.contains(&object) || has_object(self.repo, &object)? {
+            return Ok(());
+        }
+        self.wanted.insert(object.clone());
+        self.pending.push(object);
+        Ok(())
+    }
+
+    fn want_tree_value(&mut self, path: RepoPathBuf, value: &TreeValue) -> Result<(), SyncError> {
+        match value {
+            TreeValue::File { id, .. } => self.want(ObjectKind::File, path, id.as_bytes()),
+            TreeValue::Symlink(id) => self.want(ObjectKind::Symlink, path, id.as_bytes()),
+            TreeValue::Tree(id) => self.want(ObjectKind::Tree, path, id.as_bytes()),
+            TreeValue::Conflict(id) => self.want(ObjectKind::Conflict, path, id.as_bytes()),
+            // Submodules are stored outside of the repo
+            TreeValue::GitSubmodule(_) => Ok(()),
+        }
+    }
+
+    /// Records the received object and requests the objects it refers to.
+    fn add_object(&mut self, object: ObjectRef, data: Vec<u8>) -> Result<(), SyncError> {
+        let root = RepoPathBuf::root;
+        match object.kind {
+            ObjectKind::Commit => {
+                let commit = decode_commit(&data)?;
+                for id in commit.parents.iter().chain(&commit.predecessors) {
+                    self.want(ObjectKind::Commit, root(), id.as_bytes())?;
+                }
+                for id in commit.root_tree.to_merge().iter() {
+                    self.want(ObjectKind::Tree, root(), id.as_bytes())?;
+                }
+                let id = CommitId::new(object.id);
+                self.received.commits.insert(id, commit);
+            }
+            ObjectKind::Tree => {
+                let tree = decode_tree(&data)?;
+                for entry in tree.entries() {
+                    self.want_tree_value(object.path.join(entry.name()), entry.value())?;
+                }
+                let id = TreeId::new(object.id);
+                self.received.trees.push((object.path, id, tree));
+            }
+            ObjectKind::File => {
+                let written_id = self
+                    .repo
+                    .store()
+                    .write_file(&object.path, &mut data.as_slice())?;
+                check_written_id("file", &FileId::new(object.id), &written_id)?;
+            }
+            ObjectKind::Symlink => {
+                let target = String::from_utf8(data)
+                    .map_err(|_| invalid_message("symlink target isn't valid UTF-8"))?;
+                let written_id = self.repo.store().write_symlink(&object.path, &target)?;
+                check_written_id("symlink", &SymlinkId::new(object.id), &written_id)?;
+            }
+            ObjectKind::Conflict => {
+                let conflict = decode_conflict(&data)?;
+                for value in conflict.iter().flatten() {
+                    self.want_tree_value(object.path.clone(), value)?;
+                }
+                let id = ConflictId::new(object.id);
+                self.received.conflicts.push((object.path, id, conflict));
+            }
+            ObjectKind::Operation => {
+                let proto = crate::protos::op_store::Operation::decode(data.as_slice())
+                    .map_err(|err| invalid_message(err.to_string()))?;
+                let operation = operation_from_proto(proto);
+                for id in &operation.parents {
+                    self.want(ObjectKind::Operation, root(), id.as_bytes())?;
+                }
+                self.want(ObjectKind::View, root(), operation.view_id.as_bytes())?;
+                let id = OperationId::new(object.id);
+                self.received.operations.insert(id, operation);
+            }
+            ObjectKind::View => {
+                let proto = crate::protos::op_store::View::decode(data.as_slice())
+                    .map_err(|err| invalid_message(err.to_string()))?;
+                let view = View::new(view_from_proto(proto));
+                for id in view.all_referenced_commit_ids() {
+                    self.want(ObjectKind::Commit, root(), id.as_bytes())?;
+                }
+                let id = ViewId::new(object.id);
+                self.received.views.push((id, view.store_view().clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn has_object(repo: &ReadonlyRepo, object: &ObjectRef) -> Result<bool, SyncError> {
+    let store = repo.store();
+    let path = &object.path;
+    let id = object.id.clone();
+    let result = match object.kind {
+        ObjectKind::Commit => {
+            let id = CommitId::new(id);
+            if repo.index().has_id(&id) {
+                return Ok(true);
+            }
+            store.get_commit(&id).map(|_| ())
+        }
+        ObjectKind::Tree => store.get_tree(path, &TreeId::new(id)).map(|_| ()),
+        ObjectKind::File => store.read_file(path, &FileId::new(id)).map(|_| ()),
+        ObjectKind::Symlink => store.read_symlink(path, &SymlinkId::new(id)).map(|_| ()),
+        ObjectKind::Conflict => store.read_conflict(path, &ConflictId::new(id)).map(|_| ()),
+        ObjectKind::Operation | ObjectKind::View => {
+            let op_store = repo.op_store();
+            let result = if object.kind == ObjectKind::Operation {
+                op_store.read_operation(&OperationId::new(id)).map(|_| ())
+            } else {
+                op_store.read_view(&ViewId::new(id)).map(|_| ())
+            };
+            return match result {
+                Ok(()) => Ok(true),
+                Err(OpStoreError::ObjectNotFound { .. }) => Ok(false),
+                Err(err) => Err(err.into()),
+            };
+        }
+    };
+    match result {
+        Ok(()) => Ok(true),
+        Err(BackendError::ObjectNotFound { .. }) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn read_object(repo: &ReadonlyRepo, object: &ObjectRef) -> Result<Vec<u8>, SyncError> {
+    let store = repo.store();
+    let path = &object.path;
+    let id = object.id.clone();
+    let data = match object.kind {
+        ObjectKind::Commit => encode_commit(store.get_commit(&CommitId::new(id))?.store_commit()),
+        ObjectKind::Tree => encode_tree(store.get_tree(path, &TreeId::new(id))?.data()),
+        ObjectKind::File => {
+            let id = FileId::new(id);
+            let mut content = vec![];
+            store
+                .read_file(path, &id)?
+                .read_to_end(&mut content)
+                .map_err(|err| BackendError::ReadObject {
+                    object_type: id.object_type(),
+                    hash: id.hex(),
+                    source: err.into(),
+                })?;
+            content
+        }
+        ObjectKind::Symlink => store.read_symlink(path, &SymlinkId::new(id))?.into_bytes(),
+        ObjectKind::Conflict => encode_conflict(&store.read_conflict(path, &ConflictId::new(id))?),
+        ObjectKind::Operation => {
+            let operation = repo.op_store().read_operation(&OperationId::new(id))?;
+            operation_to_proto(&operation).encode_to_vec()
+        }
+        ObjectKind::View => {
+            let view = repo.op_store().read_view(&ViewId::new(id))?;
+            view_to_proto(&view).encode_to_vec()
+        }
+    };
+    Ok(data)
+}
+
+#[derive(Clone, Debug)]
+struct Hello {
+    version: u32,
+    backend: String,
+    op_store: String,
+    op_log: bool,
+}
+
+impl Hello {
+    fn new(repo: &ReadonlyRepo, op_log: bool) -> Self {
+        Hello {
+            version: SYNC_PROTOCOL_VERSION,
+            backend: repo.store().backend_name().to_owned(),
+            op_store: repo.op_store().name().to_owned(),
+            op_log,
+        }
+    }
+
+    fn check_compatible(&self, remote: &Hello) -> Result<(), SyncError> {
+        if remote.version != self.version {
+            return Err(SyncError::IncompatibleProtocol {
+                local: self.version,
+                remote: remote.version,
+            });
+        }
+        let mut stores = vec![("commit backend", &self.backend, &remote.backend)];
+        if self.op_log {
+            stores.push(("operation store", &self.op_store, &remote.op_store));
+        }
+        for (kind, local, remote) in stores {
+            if local != remote {
+                return Err(SyncError::IncompatibleStore {
+                    kind,
+                    local: local.clone(),
+                    remote: remote.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Message {
+    Hello(Hello),
+    Heads(Vec<Vec<u8>>),
+    Want(Vec<ObjectRef>),
+    /// The contents of the wanted objects, in the same order.
+    Objects(Vec<Vec<u8>>),
+    Done,
+}
+
+impl Message {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        match self {
+            Message::Hello(hello) => {
+                encoder.u8(0);
+                encoder.u64(hello.version.into());
+                encoder.bytes(hello.backend.as_bytes());
+                encoder.bytes(hello.op_store.as_bytes());
+                encoder.u8(hello.op_log.into());
+            }
+            Message::Heads(ids) => {
+                encoder.u8(1);
+                encoder.u64(ids.len() as u64);
+                for id in ids {
+                    encoder.bytes(id);
+                }
+            }
+            Message::Want(objects) => {
+                encoder.u8(2);
+                encoder.u64(objects.len() as u64);
+                for object in objects {
+                    let kind = ObjectKind::ALL.iter().position(|&kind| kind == object.kind);
+                    encoder.u8(kind.unwrap() as u8);
+                    encoder.bytes(object.path.as_internal_file_string().as_bytes());
+                    encoder.bytes(&object.id);
+                }
+            }
+            Message::Objects(contents) => {
+                encoder.u8(3);
+                encoder.u64(contents.len() as u64);
+                for data in contents {
+                    encoder.bytes(data);
+                }
+            }
+            Message::Done => {
+                encoder.u8(4);
+            }
+        }
+        encoder.buf
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, SyncError> {
+        let mut decoder = Decoder::new(data);
+        let message = match decoder.u8()? {
+            0 => Message::Hello(Hello {
+                version: decoder
+                    .u64()?
+                    .try_into()
+                    .map_err(|_| invalid_message("invalid version"))?,
+                backend: decoder.string()?,
+                op_store: decoder.string()?,
+                op_log: decoder.u8()? != 0,
+            }),
+            1 => Message::Heads(decoder.list(|decoder| Ok(decoder.bytes()?.to_vec()))?),
+            2 => Message::Want(decoder.list(|decoder| {
+                let kind = *ObjectKind::ALL
+                    .get(usize::from(decoder.u8()?))
+                    .ok_or_else(|| invalid_message("invalid object kind"))?;
+                let path = decoder.path()?;
+                let id = decoder.bytes()?.to_vec();
+                Ok(ObjectRef { kind, path, id })
+            })?),
+            3 => Message::Objects(decoder.list(|decoder| Ok(decoder.bytes()?.to_vec()))?),
+            4 => Message::Done,
+            tag => return Err(invalid_message(format!("unknown message type {tag}"))),
+        };
+        decoder.finish()?;
+        Ok(message)
+    }
+
+    fn unexpected(&self) -> SyncError {
+        let name = match self {
+            Message::Hello(_) => "Hello",
+            Message::Heads(_) => "Heads",
+            Message::Want(_) => "Want",
+            Message::Objects(_) => "Objects",
+            Message::Done => "Done",
+        };
+        invalid_message(format!("unexpected {name} message"))
+    }
+
+    fn into_hello(self) -> Result<Hello, SyncError> {
+        match self {
+            Message::Hello(hello) => Ok(hello),
+            message => Err(message.unexpected()),
+        }
+    }
+
+    fn into_heads(self) -> Result<Vec<Vec<u8>>, SyncError> {
+        match self {
+            Message::Heads(ids) => Ok(ids),
+            message => Err(message.unexpected()),
+        }
+    }
+
+    fn into_objects(self) -> Result<Vec<Vec<u8>>, SyncError> {
+        match self {
+            Message::Objects(contents) => Ok(contents),
+            message => Err(message.unexpected()),
+        }
+    }
+}
+
+/// Encodes the commit with its signature, so that it can be written with the
+/// same id.
+fn encode_commit(commit: &backend::Commit) -> Vec<u8> {
+    let mut proto = commit_to_proto(commit);
+    proto.secure_sig = commit.secure_sig.as_ref().map(|sig| sig.sig.clone());
+    proto.encode_to_vec()
+}
+
+fn decode_commit(data: &[u8]) -> Result<backend::Commit, SyncError> {
+    let proto = crate::protos::local_store::Commit::decode(data)
+        .map_err(|err| invalid_message(err.to_string()))?;
+    let num_trees = proto.root_tree.len();
+    if num_trees % 2 == 0 || (!proto.uses_tree_conflict_format && num_trees != 1) {
+        return Err(invalid_message("invalid number of root trees"));
+    }
+    Ok(commit_from_proto(proto))
+}
+
+// Trees and conflicts are encoded by hand since the native backend's protos
+// can't represent Git submodules.
+
+fn encode_tree(tree: &backend::Tree) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.u64(tree.entries().count() as u64);
+    for entry in tree.entries() {
+        encoder.bytes(entry.name().as_str().as_bytes());
+        encoder.tree_value(entry.value());
+    }
+    encoder.buf
+}
+
+fn decode_tree(data: &[u8]) -> Result<backend::Tree, SyncError> {
+    let mut decoder = Decoder::new(data);
+    let mut tree = backend::Tree::default();
+    for (name, value) in decoder.list(|decoder| {
+        let name = decoder.string()?;
+        if name.is_empty() || name.contains('/') {
+            return Err(invalid_message(format!("invalid file name {name:?}")));
+        }
+        Ok((name, decoder.tree_value()?))
+    })? {
+        tree.set(RepoPathComponentBuf::from(name), value);
+    }
+    decoder.finish()?;
+    Ok(tree)
+}
+
+fn encode_conflict(conflict: &MergedTreeValue) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.u64(conflict.iter().len() as u64);
+    for value in conflict.iter() {
+        match value {
+            Some(value) => {
+                encoder.u8(1);
+                encoder.tree_value(value);
+            }
+            None => encoder.u8(0),
+        }
+    }
+    encoder.buf
+}
+
+fn decode_conflict(data: &[u8]) -> Result<MergedTreeValue, SyncError> {
+    let mut decoder = Decoder::new(data);
+    let values = decoder.list(|decoder| match decoder.u8()? {
+        0 => Ok(None),
+        _ => Ok(Some(decoder.tree_value()?)),
+    })?;
+    decoder.finish()?;
+    if values.len() % 2 == 0 {
+        return Err(invalid_message("invalid number of conflict terms"));
+    }
+    Ok(Merge::from_vec(values))
+}
+
+#[derive(Default)]
+struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.u64(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn tree_value(&mut self, value: &TreeValue) {
+        let (tag, id) = match value {
+            TreeValue::File {
+                id,
+                executable: false,
+            } => (0, id.as_bytes()),
+            TreeValue::File {
+                id,
+                executable: true,
+            } => (1, id.as_bytes()),
+            TreeValue::Symlink(id) => (2, id.as_bytes()),
+            TreeValue::Tree(id) => (3, id.as_bytes()),
+            TreeValue::GitSubmodule(id) => (4, id.as_bytes()),
+            TreeValue::Conflict(id) => (5, id.as_bytes()),
+        };
+        self.u8(tag);
+        self.bytes(id);
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Decoder { data }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SyncError> {
+        if self.data.len() < len {
+            return Err(invalid_message("truncated message"));
+        }
+        let (value, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(value)
+    }
+
+    fn u8(&mut self) -> Result<u8, SyncError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, SyncError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], SyncError> {
+        let len = self.u64()?;
+        if len > self.data.len() as u64 {
+            return Err(invalid_message("truncated message"));
+        }
+        self.take(len as usize)
+    }
+
+    fn string(&mut self) -> Result<String, SyncError> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| invalid_message("string isn't valid UTF-8"))
+    }
+
+    fn path(&mut self) -> Result<RepoPathBuf, SyncError> {
+        let path = self.string()?;
+        if path.starts_with('/') || path.ends_with('/') || path.contains("//") {
+            return Err(invalid_message(format!("invalid path {path:?}")));
+        }
+        Ok(RepoPathBuf::from_internal_string(path))
+    }
+
+    fn list<T>(
+        &mut self,
+        mut decode_item: impl FnMut(&mut Self) -> Result<T, SyncError>,
+    ) -> Result<Vec<T>, SyncError> {
+        let len = self.u64()?;
+        // Each item takes at least one byte
+        if len > self.data.len() as u64 {
+            return Err(invalid_message("truncated message"));
+        }
+        (0..len).map(|_| decode_item(self)).collect()
+    }
+
+    fn tree_value(&mut self) -> Result<TreeValue, SyncError> {
+        let tag = self.u8()?;
+        let id = self.bytes()?.to_vec();
+        let value = match tag {
+            0 | 1 => TreeValue::File {
+                id: FileId::new(id),
+                executable: tag == 1,
+            },
+            2 => TreeValue::Symlink(SymlinkId::new(id)),
+            3 => TreeValue::Tree(TreeId::new(id)),
+            4 => TreeValue::GitSubmodule(CommitId::new(id)),
+            5 => TreeValue::Conflict(ConflictId::new(id)),
+            _ => return Err(invalid_message(format!("unknown tree value type {tag}"))),
+        };
+        Ok(value)
+    }
+
+    fn finish(self) -> Result<(), SyncError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_message("unexpected data at the end of the message"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: &Message) -> Message {
+        Message::decode(&message.encode()).unwrap()
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let hello = Hello {
+            version: SYNC_PROTOCOL_VERSION,
+            backend: "local".to_owned(),
+            op_store: "simple_op_store".to_owned(),
+            op_log: true,
+        };
+        let Message::Hello(decoded) = round_trip(&Message::Hello(hello)) else {
+            panic!();
+        };
+        assert_eq!(decoded.version, SYNC_PROTOCOL_VERSION);
+        assert_eq!(decoded.backend, "local");
+        assert_eq!(decoded.op_store, "simple_op_store");
+        assert!(decoded.op_log);
+
+        let wanted = vec![
+            ObjectRef {
+                kind: ObjectKind::Commit,
+                path: RepoPathBuf::root(),
+                id: vec![1, 2, 3],
+            },
+            ObjectRef {
+                kind: ObjectKind::File,
+                path: RepoPathBuf::from_internal_string("dir/file"),
+                id: vec![4],
+            },
+        ];
+        let Message::Want(decoded) = round_trip(&Message::Want(wanted.clone())) else {
+            panic!();
+        };
+        assert_eq!(decoded, wanted);
+
+        let contents = vec![vec![], b"contents".to_vec()];
+        let Message::Objects(decoded) = round_trip(&Message::Objects(contents.clone())) else {
+            panic!();
+        };
+        assert_eq!(decoded, contents);
+        assert!(matches!(round_trip(&Message::Done), Message::Done));
+    }
+
+    #[test]
+    fn test_decode_invalid_message() {
+        let data = Message::Heads(vec![vec![1; 20]]).encode();
+        assert!(matches!(
+            Message::decode(&data[..data.len() - 1]),
+            Err(SyncError::InvalidMessage(_))
+        ));
+        assert!(matches!(
+            Message::decode(&[data.as_slice(), &[0]].concat()),
+            Err(SyncError::InvalidMessage(_))
+        ));
+        assert!(matches!(
+            Message::decode(&[5]),
+            Err(SyncError::InvalidMessage(_))
+        ));
+        // A huge count doesn't allocate anything
+        let mut data = vec![1];
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        assert!(matches!(
+            Message::decode(&data),
+            Err(SyncError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_tree_round_trip() {
+        let mut tree = backend::Tree::default();
+        tree.set(
+            RepoPathComponentBuf::from("file"),
+            TreeValue::File {
+                id: FileId::new(vec![1]),
+                executable: true,
+            },
+        );
+        tree.set(
+            RepoPathComponentBuf::from("submodule"),
+            TreeValue::GitSubmodule(CommitId::new(vec![2])),
+        );
+        assert_eq!(decode_tree(&encode_tree(&tree)).unwrap(), tree);
+
+        let conflict = Merge::from_vec(vec![
+            Some(TreeValue::Symlink(SymlinkId::new(vec![3]))),
+            None,
+            Some(TreeValue::Tree(TreeId::new(vec![4]))),
+        ]);
+        assert_eq!(
+            decode_conflict(&encode_conflict(&conflict)).unwrap(),
+            conflict
+        );
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides support for parsing and matching date ranges.
+
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, LocalResult, Months, NaiveDate, NaiveDateTime, TimeZone,
+};
+use thiserror::Error;
+
+use crate::backend::{MillisSinceEpoch, Timestamp};
+
+/// Error occurred during date pattern parsing.
+#[derive(Debug, Error)]
+pub enum DatePatternParseError {
+    /// Unknown pattern kind is specified.
+    #[error(r#"Invalid date pattern kind "{0}:""#)]
+    InvalidKind(String),
+    /// Failed to parse the date string.
+    #[error(r#"Invalid date "{0}""#)]
+    InvalidDate(String),
+}
+
+/// Represents a range of dates that may be matched against.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DatePattern {
+    /// Represents all dates at or after the given instant.
+    AtOrAfter(MillisSinceEpoch),
+    /// Represents all dates before, but not including, the given instant.
+    Before(MillisSinceEpoch),
+}
+
+impl DatePattern {
+    /// Parses a string into a `DatePattern`.
+    ///
+    /// * `s` is the date string to be parsed. See [`parse_datetime()`] for the
+    ///   accepted formats.
+    ///
+    /// * `kind` is either "after" or "before". This determines whether the
+    ///   pattern will match dates after or before the parsed date.
+    ///
+    /// * `now` is the user's current time. It is used to resolve relative dates
+    ///   such as "2 days ago", and supplies the time zone in which dates
+    ///   without explicit offset are interpreted.
+    pub fn from_str_kind(
+        s: &str,
+        kind: &str,
+        now: DateTime<Local>,
+    ) -> Result<DatePattern, DatePatternParseError> {
+        let make_pattern = match kind {
+            "after" => DatePattern::AtOrAfter,
+            "before" => DatePattern::Before,
+            _ => return Err(DatePatternParseError::InvalidKind(kind.to_owned())),
+        };
+        let datetime = parse_datetime(s, now)?;
+        Ok(make_pattern(MillisSinceEpoch(datetime.timestamp_millis())))
+    }
+
+    /// Determines whether a given timestamp is matched by the pattern.
+    pub fn matches(&self, timestamp: &Timestamp) -> bool {
+        match self {
+            DatePattern::AtOrAfter(earliest) => *earliest <= timestamp.timestamp,
+            DatePattern::Before(latest) => timestamp.timestamp < *latest,
+        }
+    }
+}
+
+/// Parses a date string relative to `now`.
+///
+/// The following formats are accepted:
+///
+/// * RFC 3339 timestamp, e.g. `2024-02-01T12:00:00+09:00`
+/// * `YYYY-MM-DD HH:MM[:SS]` and `YYYY-MM-DD`, interpreted in the time zone of
+///   `now`
+/// * `now`, `today`, and `yesterday`, where the latter two resolve to the start
+///   of the day
+/// * `<N> <unit>[s] ago`, where `<unit>` is one of `second`, `minute`, `hour`,
+///   `day`, `week`, `month`, or `year`
+pub fn parse_datetime(
+    s: &str,
+    now: DateTime<Local>,
+) -> Result<DateTime<FixedOffset>, DatePatternParseError> {
+    let invalid_date = || DatePatternParseError::InvalidDate(s.to_owned());
+    let s = s.trim();
+    let tz = *now.offset();
+    let resolve_local = |naive: NaiveDateTime| match tz.from_local_datetime(&naive) {
+        LocalResult::Single(datetime) => Some(datetime),
+        LocalResult::Ambiguous(earliest, _) => Some(earliest),
+        LocalResult::None => None,
+    };
+    let start_of_day = |date: NaiveDate| resolve_local(date.and_hms_opt(0, 0, 0).unwrap());
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(datetime);
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return resolve_local(naive).ok_or_else(invalid_date);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return start_of_day(date).ok_or_else(invalid_date);
+    }
+    let now = now.fixed_offset();
+    match s {
+        "now" => return Ok(now),
+        "today" => return start_of_day(now.date_naive()).ok_or_else(invalid_date),
+        "yesterday" => {
+            let date = now.date_naive().pred_opt().ok_or_else(invalid_date)?;
+            return start_of_day(date).ok_or_else(invalid_date);
+        }
+        _ => {}
+    }
+    parse_relative_datetime(s, now).ok_or_else(invalid_date)
+}
+
+/// Parses `<N> <unit>[s] ago` into the date that long before `now`.
+fn parse_relative_datetime(s: &str, now: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    let mut words = s.split_whitespace();
+    let count: i64 = words.next()?.parse().ok()?;
+    let unit = words.next()?;
+    if words.next()? != "ago" || words.next().is_some() {
+        return None;
+    }
+    // Months and years don't have a fixed duration
+    let sub_months = |months: i64| now.checked_sub_months(Months::new(months.try_into().ok()?));
+    let duration = match unit.strip_suffix('s').unwrap_or(unit) {
+        "second" => Duration::try_seconds(count),
+        "minute" => Duration::try_minutes(count),
+        "hour" => Duration::try_hours(count),
+        "day" => Duration::try_days(count),
+        "week" => Duration::try_weeks(count),
+        "month" => return sub_months(count),
+        "year" => return sub_months(count.checked_mul(12)?),
+        _ => None,
+    }?;
+    now.checked_sub_signed(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        DateTime::parse_from_rfc3339("2024-01-10T12:30:00+00:00")
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    fn test_equal(s: &str, expected: &str) {
+        let parsed = parse_datetime(s, now()).unwrap();
+        let expected = DateTime::parse_from_rfc3339(expected).unwrap();
+        assert_eq!(parsed, expected, "parsing {s:?}");
+    }
+
+    #[test]
+    fn test_parse_datetime_absolute() {
+        test_equal("2024-01-01T00:00:00+09:00", "2023-12-31T15:00:00Z");
+        test_equal("2024-01-01T00:00:00Z", "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_parse_datetime_relative() {
+        test_equal("now", "2024-01-10T12:30:00Z");
+        test_equal("1 second ago", "2024-01-10T12:29:59Z");
+        test_equal("5 minutes ago", "2024-01-10T12:25:00Z");
+        test_equal("2 hours ago", "2024-01-10T10:30:00Z");
+        test_equal("3 days ago", "2024-01-07T12:30:00Z");
+        test_equal("1 week ago", "2024-01-03T12:30:00Z");
+        test_equal("2 months ago", "2023-11-10T12:30:00Z");
+        test_equal("1 year ago", "2023-01-10T12:30:00Z");
+    }
+
+    #[test]
+    fn test_parse_datetime_local() {
+        let tz = *now().offset();
+        let naive = |s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        let local = |s| tz.from_local_datetime(&naive(s)).unwrap();
+        let parse = |s| parse_datetime(s, now()).unwrap();
+        assert_eq!(parse("2024-01-02 03:04:05"), local("2024-01-02 03:04:05"));
+        assert_eq!(parse("2024-01-02 03:04"), local("2024-01-02 03:04:00"));
+        assert_eq!(parse("2024-01-02"), local("2024-01-02 00:00:00"));
+        let today = now().date_naive().and_hms_opt(0, 0, 0).unwrap();
+        assert_eq!(parse("today"), tz.from_local_datetime(&today).unwrap());
+        assert_eq!(
+            parse("yesterday"),
+            tz.from_local_datetime(&(today - Duration::days(1)))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_datetime_invalid() {
+        let parse = |s| parse_datetime(s, now());
+        assert!(parse("").is_err());
+        assert!(parse("2024-13-01").is_err());
+        assert!(parse("3 fortnights ago").is_err());
+        assert!(parse("3 days").is_err());
+        assert!(parse("3 days ago extra").is_err());
+    }
+
+    #[test]
+    fn test_date_pattern_matches() {
+        let timestamp = |s| Timestamp::from_datetime(DateTime::parse_from_rfc3339(s).unwrap());
+        let after = DatePattern::from_str_kind("2024-01-01T00:00:00Z", "after", now()).unwrap();
+        let before = DatePattern::from_str_kind("2024-01-01T00:00:00Z", "before", now()).unwrap();
+        assert!(after.matches(&timestamp("2024-01-01T00:00:00Z")));
+        assert!(after.matches(&timestamp("2024-01-01T09:00:00+09:00")));
+        assert!(!after.matches(&timestamp("2023-12-31T23:59:59Z")));
+        assert!(!before.matches(&timestamp("2024-01-01T00:00:00Z")));
+        assert!(before.matches(&timestamp("2023-12-31T23:59:59Z")));
+        assert!(matches!(
+            DatePattern::from_str_kind("now", "around", now()),
+            Err(DatePatternParseError::InvalidKind(_))
+        ));
+    }
+}
@@ -23,7 +23,7 @@ use crate::index::ReadonlyIndex;
 use crate::op_heads_store::OpHeadsStore;
 use crate::op_store::OperationMetadata;
 use crate::operation::Operation;
-use crate::repo::{MutableRepo, ReadonlyRepo, Repo, RepoLoader, RepoLoaderError};
+use crate::repo::{MutableRepo, ReadonlyRepo, Repo, RepoLoader, RepoLoaderError, ViewConflict};
 use crate::settings::UserSettings;
 use crate::view::View;
 use crate::{dag_walk, op_store};
@@ -49,6 +49,7 @@ pub struct Transaction {
 impl Transaction {
     pub fn new(mut_repo: MutableRepo, user_settings: &UserSettings) -> Transaction {
         let parent_ops = vec![mut_repo.base_repo().operation().clone()];
+        user_settings.reseed_rng_for_operation(parent_ops[0].id());
         let op_metadata = create_op_metadata(user_settings, "".to_string(), false);
         let end_time = user_settings.operation_timestamp();
         Transaction {
@@ -75,7 +76,12 @@ impl Transaction {
         &mut self.mut_repo
     }
 
-    pub fn merge_operation(&mut self, other_op: Operation) -> Result<(), RepoLoaderError> {
+    /// Merges the `other_op` into this transaction, and returns the conflicts
+    /// left in the view.
+    pub fn merge_operation(
+        &mut self,
+        other_op: Operation,
+    ) -> Result<Vec<ViewConflict>, RepoLoaderError> {
         let ancestor_op = dag_walk::closest_common_node_ok(
             self.parent_ops.iter().cloned().map(Ok),
             [Ok(other_op.clone())],
@@ -88,8 +94,7 @@ impl Transaction {
         let other_repo = repo_loader.load_at(&other_op)?;
         self.parent_ops.push(other_op);
         let merged_repo = self.mut_repo();
-        merged_repo.merge(&base_repo, &other_repo);
-        Ok(())
+        Ok(merged_repo.merge(&base_repo, &other_repo)?)
     }
 
     pub fn set_is_snapshot(&mut self, is_snapshot: bool) {
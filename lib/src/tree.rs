@@ -363,7 +363,7 @@ fn merge_tree_value(
                 Some(TreeValue::Conflict(id)) => store.read_conflict(&filename, id),
                 _ => Ok(Merge::resolved(term.clone())),
             })?;
-            let merge = expanded.flatten().simplify();
+            let merge = expanded.flatten().simplify().dedup_changes();
             match merge.into_resolved() {
                 Ok(value) => value,
                 Err(conflict) => {
@@ -441,12 +441,26 @@ pub fn try_resolve_file_conflict(
             Ok(content)
         })?;
     let slices = contents.map(|content| content.as_slice());
-    let merge_result = files::merge(&slices);
+    let ignore_whitespace = store.merge_drivers().ignore_whitespace(filename);
+    let merge_result = if ignore_whitespace {
+        files::merge_ignoring_whitespace(&slices)
+    } else {
+        files::merge(&slices)
+    };
     match merge_result {
         MergeResult::Resolved(merged_content) => {
             let id = store.write_file(filename, &mut merged_content.0.as_slice())?;
             Ok(Some(TreeValue::File { id, executable }))
         }
-        MergeResult::Conflict(_) => Ok(None),
+        MergeResult::Conflict(_) => {
+            let Some(driver) = store.merge_drivers().find(filename) else {
+                return Ok(None);
+            };
+            let Some(merged_content) = driver.merge(&slices, ignore_whitespace) else {
+                return Ok(None);
+            };
+            let id = store.write_file(filename, &mut merged_content.as_slice())?;
+            Ok(Some(TreeValue::File { id, executable }))
+        }
     }
 }
@@ -24,11 +24,13 @@ use thiserror::Error;
 
 use crate::backend::{BackendError, MergedTreeId};
 use crate::commit::Commit;
+use crate::conflicts::ConflictMarkerOptions;
 use crate::fsmonitor::FsmonitorKind;
 use crate::gitignore::{GitIgnoreError, GitIgnoreFile};
 use crate::op_store::{OperationId, WorkspaceId};
 use crate::repo_path::{RepoPath, RepoPathBuf};
 use crate::settings::HumanByteSize;
+use crate::sparse::SparsePattern;
 use crate::store::Store;
 
 /// The trait all working-copy implementations must implement.
@@ -54,9 +56,13 @@ pub trait WorkingCopy: Send {
 
     /// Patterns that decide which paths from the current tree should be checked
     /// out in the working copy. An empty list means that no paths should be
-    /// checked out in the working copy. A single `RepoPath::root()` entry means
-    /// that all files should be checked out.
-    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError>;
+    /// checked out in the working copy. A single `SparsePattern::Prefix` entry
+    /// with `RepoPath::root()` means that all files should be checked out.
+    fn sparse_patterns(&self) -> Result<&[SparsePattern], WorkingCopyStateError>;
+
+    /// The sparse profile the patterns are read from, if any. The patterns are
+    /// updated from the profile in the new tree on checkout.
+    fn sparse_profile(&self) -> Result<Option<&str>, WorkingCopyStateError>;
 
     /// Locks the working copy and returns an instance with methods for updating
     /// the working copy files and state.
@@ -102,7 +108,11 @@ pub trait LockedWorkingCopy {
     fn snapshot(&mut self, options: SnapshotOptions) -> Result<MergedTreeId, SnapshotError>;
 
     /// Check out the specified commit in the working copy.
-    fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError>;
+    fn check_out(
+        &mut self,
+        commit: &Commit,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError>;
 
     /// Update to another commit without touching the files in the working copy.
     fn reset(&mut self, commit: &Commit) -> Result<(), ResetError>;
@@ -112,7 +122,10 @@ pub trait LockedWorkingCopy {
     fn recover(&mut self, commit: &Commit) -> Result<(), ResetError>;
 
     /// See `WorkingCopy::sparse_patterns()`
-    fn sparse_patterns(&self) -> Result<&[RepoPathBuf], WorkingCopyStateError>;
+    fn sparse_patterns(&self) -> Result<&[SparsePattern], WorkingCopyStateError>;
+
+    /// See `WorkingCopy::sparse_profile()`
+    fn sparse_profile(&self) -> Result<Option<&str>, WorkingCopyStateError>;
 
     /// Updates the patterns that decide which paths from the current tree
     /// should be checked out in the working copy.
@@ -122,7 +135,17 @@ pub trait LockedWorkingCopy {
     // to use sparse).
     fn set_sparse_patterns(
         &mut self,
-        new_sparse_patterns: Vec<RepoPathBuf>,
+        new_sparse_patterns: Vec<SparsePattern>,
+        options: &CheckoutOptions,
+    ) -> Result<CheckoutStats, CheckoutError>;
+
+    /// Updates the patterns from the sparse profile `name` in the current tree,
+    /// and keeps them in sync with the profile on later checkouts. Setting
+    /// patterns with `set_sparse_patterns()` stops using the profile.
+    fn set_sparse_profile(
+        &mut self,
+        name: &str,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError>;
 
     /// Finish the modifications to the working copy by writing the updated
@@ -198,6 +221,13 @@ pub struct SnapshotOptions<'a> {
     /// (depending on implementation)
     /// return `SnapshotError::NewFileTooLarge`.
     pub max_new_file_size: u64,
+    /// Paths (files or directories) which should never be snapshotted. They're
+    /// skipped before walking the file system, and the files already tracked
+    /// in them are left unchanged.
+    pub exclude_paths: Vec<RepoPathBuf>,
+    /// The conflict markers to parse from files which were conflicted. Should
+    /// match the ones the files were checked out with.
+    pub conflict_marker_options: ConflictMarkerOptions,
 }
 
 impl SnapshotOptions<'_> {
@@ -208,6 +238,42 @@ impl SnapshotOptions<'_> {
             fsmonitor_kind: FsmonitorKind::None,
             progress: None,
             max_new_file_size: u64::MAX,
+            exclude_paths: vec![],
+            conflict_marker_options: ConflictMarkerOptions::default(),
+        }
+    }
+}
+
+/// Options used when checking out a tree in the working copy.
+#[derive(Clone)]
+pub struct CheckoutOptions<'a> {
+    /// How to write conflicts to files.
+    pub conflict_marker_options: ConflictMarkerOptions,
+    /// A callback for the UI to display progress.
+    pub progress: Option<&'a CheckoutProgress<'a>>,
+    /// Whether to clone the files from a cache of the previously checked out
+    /// files instead of writing them, on filesystems which support
+    /// copy-on-write clones.
+    pub use_reflinks: bool,
+    /// The maximum size in bytes of the cache used with `use_reflinks`. The
+    /// least recently used files are evicted when it's exceeded.
+    pub reflink_cache_size: u64,
+    /// If set, the untracked files and the files modified since the last
+    /// snapshot which are in the way of the checkout are moved to this
+    /// directory, under their path in the repo. Otherwise, the checkout of
+    /// these paths is skipped.
+    pub backup_dir: Option<PathBuf>,
+}
+
+impl CheckoutOptions<'_> {
+    /// Create an instance for use in tests.
+    pub fn empty_for_test() -> Self {
+        CheckoutOptions {
+            conflict_marker_options: ConflictMarkerOptions::default(),
+            progress: None,
+            use_reflinks: false,
+            reflink_cache_size: u64::MAX,
+            backup_dir: None,
         }
     }
 }
@@ -215,6 +281,11 @@ impl SnapshotOptions<'_> {
 /// A callback for getting progress updates.
 pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 
+/// A callback for getting checkout progress updates. It's called before each
+/// path is updated, with the stats so far, which count the path as updated,
+/// added, or removed.
+pub type CheckoutProgress<'a> = dyn Fn(&RepoPath, &CheckoutStats) + 'a + Sync;
+
 /// Stats about a checkout operation on a working copy. All "files" mentioned
 /// below may also be symlinks or materialized conflicts.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -230,6 +301,11 @@ pub struct CheckoutStats {
     /// working copy but were skipped because there was an untracked (probably
     /// ignored) file in its place.
     pub skipped_files: u32,
+    /// The paths of the skipped files.
+    pub skipped_paths: Vec<RepoPathBuf>,
+    /// The paths of the untracked or modified files which were moved to
+    /// `CheckoutOptions::backup_dir` before being replaced.
+    pub backed_up_paths: Vec<RepoPathBuf>,
 }
 
 /// The working-copy checkout failed.
@@ -39,8 +39,8 @@ use crate::settings::UserSettings;
 use crate::signing::{SignInitError, Signer};
 use crate::store::Store;
 use crate::working_copy::{
-    CheckoutError, CheckoutStats, LockedWorkingCopy, WorkingCopy, WorkingCopyFactory,
-    WorkingCopyStateError,
+    CheckoutError, CheckoutOptions, CheckoutStats, LockedWorkingCopy, WorkingCopy,
+    WorkingCopyFactory, WorkingCopyStateError,
 };
 
 #[derive(Error, Debug)]
@@ -59,6 +59,8 @@ pub enum WorkspaceInitError {
     Backend(#[from] BackendInitError),
     #[error(transparent)]
     SignInit(#[from] SignInitError),
+    #[error(transparent)]
+    Config(#[from] config::ConfigError),
 }
 
 #[derive(Error, Debug)]
@@ -126,9 +128,89 @@ fn init_working_copy(
     )?;
     let working_copy_type_path = working_copy_state_path.join("type");
     fs::write(&working_copy_type_path, working_copy.name()).context(&working_copy_type_path)?;
+    record_workspace_root(
+        repo.repo_path(),
+        working_copy.workspace_id(),
+        workspace_root,
+    )?;
     Ok((working_copy, repo))
 }
 
+fn workspace_root_record_path(repo_path: &Path, workspace_id: &WorkspaceId) -> PathBuf {
+    repo_path
+        .join("workspace_roots")
+        .join(hex::encode(workspace_id.as_str()))
+}
+
+fn record_workspace_root(
+    repo_path: &Path,
+    workspace_id: &WorkspaceId,
+    workspace_root: &Path,
+) -> Result<(), WorkspaceInitError> {
+    let workspace_root = workspace_root.canonicalize().context(workspace_root)?;
+    // A root which can't be recorded on this platform is simply never reported
+    // as stale.
+    let Some(content) = path_to_record(&workspace_root) else {
+        return Ok(());
+    };
+    let record_path = workspace_root_record_path(repo_path, workspace_id);
+    file_util::create_or_reuse_dir(record_path.parent().unwrap())
+        .context(record_path.parent().unwrap())?;
+    fs::write(&record_path, content).context(&record_path)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn path_to_record(path: &Path) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt as _;
+    Some(path.as_os_str().as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn path_to_record(path: &Path) -> Option<Vec<u8>> {
+    path.to_str().map(|path| path.as_bytes().to_vec())
+}
+
+#[cfg(unix)]
+fn path_from_record(content: Vec<u8>) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt as _;
+    Some(OsString::from_vec(content).into())
+}
+
+#[cfg(not(unix))]
+fn path_from_record(content: Vec<u8>) -> Option<PathBuf> {
+    String::from_utf8(content).ok().map(PathBuf::from)
+}
+
+/// Returns the root directory the workspace was created in, or `None` if it
+/// wasn't recorded (e.g. because the workspace was created by an older
+/// version).
+pub fn recorded_workspace_root(
+    repo_path: &Path,
+    workspace_id: &WorkspaceId,
+) -> Result<Option<PathBuf>, PathError> {
+    let record_path = workspace_root_record_path(repo_path, workspace_id);
+    match fs::read(&record_path) {
+        Ok(content) => Ok(path_from_record(content)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).context(&record_path),
+    }
+}
+
+/// Removes the recorded root directory of a forgotten workspace.
+pub fn forget_workspace_root(
+    repo_path: &Path,
+    workspace_id: &WorkspaceId,
+) -> Result<(), PathError> {
+    let record_path = workspace_root_record_path(repo_path, workspace_id);
+    match fs::remove_file(&record_path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).context(&record_path),
+    }
+}
+
 impl Workspace {
     fn new(
         workspace_root: &Path,
@@ -147,8 +229,14 @@ impl Workspace {
         user_settings: &UserSettings,
         workspace_root: &Path,
     ) -> Result<(Self, Arc<ReadonlyRepo>), WorkspaceInitError> {
-        let backend_initializer: &BackendInitializer =
-            &|_settings, store_path| Ok(Box::new(LocalBackend::init(store_path)));
+        let backend_initializer: &BackendInitializer = &|settings, store_path| {
+            let threshold = settings
+                .large_file_threshold()
+                .map_err(|err| BackendInitError(err.into()))?;
+            Ok(Box::new(
+                LocalBackend::init(store_path).with_large_file_threshold(threshold),
+            ))
+        };
         let signer = Signer::from_settings(user_settings)?;
         Self::init_with_backend(user_settings, workspace_root, backend_initializer, signer)
     }
@@ -256,6 +344,7 @@ impl Workspace {
             .map_err(|repo_init_err| match repo_init_err {
                 RepoInitError::Backend(err) => WorkspaceInitError::Backend(err),
                 RepoInitError::Path(err) => WorkspaceInitError::Path(err),
+                RepoInitError::Config(err) => WorkspaceInitError::Config(err),
             })?;
             let (working_copy, repo) = init_working_copy(
                 user_settings,
@@ -374,6 +463,7 @@ impl Workspace {
         operation_id: OperationId,
         old_tree_id: Option<&MergedTreeId>,
         commit: &Commit,
+        options: &CheckoutOptions,
     ) -> Result<CheckoutStats, CheckoutError> {
         let mut locked_ws =
             self.start_working_copy_mutation()
@@ -390,7 +480,7 @@ impl Workspace {
                 return Err(CheckoutError::ConcurrentCheckout);
             }
         }
-        let stats = locked_ws.locked_wc().check_out(commit)?;
+        let stats = locked_ws.locked_wc().check_out(commit, options)?;
         locked_ws
             .finish(operation_id)
             .map_err(|err| CheckoutError::Other {
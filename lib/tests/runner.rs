@@ -22,13 +22,17 @@ mod test_load_repo;
 mod test_local_working_copy;
 mod test_local_working_copy_concurrent;
 mod test_local_working_copy_sparse;
+mod test_merge_drivers;
 mod test_merge_trees;
 mod test_merged_tree;
 mod test_mut_repo;
 mod test_operations;
 mod test_refs;
+mod test_rename_detection;
 mod test_revset;
 mod test_rewrite;
+mod test_scratch;
+mod test_session;
 mod test_signing;
 mod test_ssh_signing;
 mod test_view;
@@ -12,14 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use jj_lib::backend::{ChangeId, MillisSinceEpoch, Signature, Timestamp};
+use std::sync::Arc;
+
+use jj_lib::backend::{ChangeId, CopyRecord, MillisSinceEpoch, Signature, Timestamp, TreeValue};
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::merged_tree::DiffSummary;
-use jj_lib::repo::Repo;
+use jj_lib::repo::{ReadonlyRepo, Repo};
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
 use jj_lib::settings::UserSettings;
 use test_case::test_case;
-use testutils::{assert_rebased_onto, create_tree, CommitGraphBuilder, TestRepo, TestRepoBackend};
+use testutils::{
+    assert_rebased_onto, create_tree, load_repo_at_head, CommitGraphBuilder, TestRepo,
+    TestRepoBackend,
+};
 
 fn to_owned_path_vec(paths: &[&RepoPath]) -> Vec<RepoPathBuf> {
     paths.iter().map(|&path| path.to_owned()).collect()
@@ -244,6 +249,90 @@ fn test_rewrite_update_missing_user(backend: TestRepoBackend) {
     );
 }
 
+#[test_case(TestRepoBackend::Local ; "local backend")]
+#[test_case(TestRepoBackend::Git ; "git backend")]
+fn test_copies(backend: TestRepoBackend) {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init_with_backend(backend);
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    let source_path = RepoPath::from_internal_string("source");
+    let target_path = RepoPath::from_internal_string("dir/target");
+    let parent_tree = create_tree(repo, &[(source_path, "contents")]);
+    let tree = create_tree(repo, &[(target_path, "contents")]);
+    let Ok(Some(TreeValue::File { id, .. })) = parent_tree.path_value(source_path).into_resolved()
+    else {
+        panic!("source should be a file");
+    };
+    let copies = vec![CopyRecord {
+        target: target_path.to_owned(),
+        source: source_path.to_owned(),
+        source_file: id,
+    }];
+
+    let mut tx = repo.start_transaction(&settings);
+    let parent = tx
+        .mut_repo()
+        .new_commit(
+            &settings,
+            vec![store.root_commit_id().clone()],
+            parent_tree.id(),
+        )
+        .write()
+        .unwrap();
+    let builder = tx
+        .mut_repo()
+        .new_commit(&settings, vec![parent.id().clone()], tree.id())
+        .set_copies(copies.clone());
+    assert_eq!(builder.copies(), copies);
+    let commit = builder.write().unwrap();
+    assert_eq!(commit.copies(), copies);
+
+    // The copies are stored in the backend
+    let repo = tx.commit("test");
+    let reloaded_repo = load_repo_at_head(&settings, repo.repo_path());
+    let stored_commit = reloaded_repo.store().get_commit(commit.id()).unwrap();
+    assert_eq!(stored_commit.copies(), copies);
+
+    // The copies are preserved when the commit is rewritten without changing
+    // the parents or the tree, or if they still hold
+    let mut tx = repo.start_transaction(&settings);
+    let rewritten_commit = tx
+        .mut_repo()
+        .rewrite_commit(&settings, &commit)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    assert_eq!(rewritten_commit.copies(), copies);
+    let modified_tree = create_tree(&repo, &[(target_path, "modified contents")]);
+    let rewritten_commit = tx
+        .mut_repo()
+        .rewrite_commit(&settings, &commit)
+        .set_tree_id(modified_tree.id())
+        .write()
+        .unwrap();
+    assert_eq!(rewritten_commit.copies(), copies);
+
+    // They're dropped if the target was removed
+    let rewritten_commit = tx
+        .mut_repo()
+        .rewrite_commit(&settings, &commit)
+        .set_tree_id(parent_tree.id())
+        .write()
+        .unwrap();
+    assert!(rewritten_commit.copies().is_empty());
+
+    // Or if the source doesn't have the recorded contents in the new parents
+    let rewritten_commit = tx
+        .mut_repo()
+        .rewrite_commit(&settings, &commit)
+        .set_parents(vec![store.root_commit_id().clone()])
+        .write()
+        .unwrap();
+    assert!(rewritten_commit.copies().is_empty());
+}
+
 #[test_case(TestRepoBackend::Local ; "local backend")]
 // #[test_case(TestRepoBackend::Git ; "git backend")]
 fn test_commit_builder_descendants(backend: TestRepoBackend) {
@@ -302,3 +391,49 @@ fn test_commit_builder_descendants(backend: TestRepoBackend) {
         .unwrap();
     assert!(rebase_map.is_empty());
 }
+
+#[test]
+fn test_deterministic_ids() {
+    let config = testutils::base_config()
+        .add_source(config::File::from_str(
+            r#"
+            debug.commit-timestamp = "2001-02-03T04:05:06+07:00"
+            debug.operation-timestamp = "2001-02-03T04:05:06+07:00"
+            debug.deterministic-ids = true
+            "#,
+            config::FileFormat::Toml,
+        ))
+        .build()
+        .unwrap();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let store = repo.store();
+
+    let write_commit = |repo: &Arc<ReadonlyRepo>| {
+        // Each session has its own settings and RNG
+        let settings = UserSettings::from_config(config.clone());
+        let mut tx = repo.start_transaction(&settings);
+        let commit = tx
+            .mut_repo()
+            .new_commit(
+                &settings,
+                vec![store.root_commit_id().clone()],
+                store.empty_merged_tree_id(),
+            )
+            .write()
+            .unwrap();
+        (tx, commit)
+    };
+
+    // The same commit is created on the same operation
+    let (tx1, commit1) = write_commit(repo);
+    let (_tx2, commit2) = write_commit(repo);
+    assert_eq!(commit1.change_id(), commit2.change_id());
+    assert_eq!(commit1.id(), commit2.id());
+
+    // A different commit is created on a different operation
+    let repo = tx1.commit("test");
+    let (_tx3, commit3) = write_commit(&repo);
+    assert_ne!(commit1.change_id(), commit3.change_id());
+    assert_ne!(commit1.id(), commit3.id());
+}
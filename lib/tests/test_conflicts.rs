@@ -16,6 +16,7 @@ use indoc::indoc;
 use jj_lib::backend::FileId;
 use jj_lib::conflicts::{
     extract_as_single_hunk, materialize_merge_result, parse_conflict, update_from_content,
+    ConflictMarkerOptions, ConflictMarkerStyle, DEFAULT_CONFLICT_MARKER_LEN,
 };
 use jj_lib::merge::Merge;
 use jj_lib::repo::Repo;
@@ -311,7 +312,7 @@ fn test_materialize_parse_roundtrip() {
 
     // The first add should always be from the left side
     insta::assert_debug_snapshot!(
-        parse_conflict(materialized.as_bytes(), conflict.num_sides()),
+        parse_conflict(materialized.as_bytes(), conflict.num_sides(), DEFAULT_CONFLICT_MARKER_LEN),
         @r###"
     Some(
         [
@@ -337,6 +338,270 @@ fn test_materialize_parse_roundtrip() {
     "###);
 }
 
+#[test]
+fn test_materialize_parse_roundtrip_git_styles() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("file");
+    let base_id = testutils::write_file(
+        store,
+        path,
+        indoc! {"
+            line 1
+            line 2
+            line 3
+        "},
+    );
+    let left_id = testutils::write_file(
+        store,
+        path,
+        indoc! {"
+            line 1
+            shared 1
+            left
+            shared 2
+            line 3
+        "},
+    );
+    let right_id = testutils::write_file(
+        store,
+        path,
+        indoc! {"
+            line 1
+            shared 1
+            right
+            shared 2
+            line 3
+        "},
+    );
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_id.clone())],
+        vec![Some(left_id.clone()), Some(right_id.clone())],
+    );
+
+    let diff3_options = ConflictMarkerOptions {
+        style: ConflictMarkerStyle::Diff3,
+        marker_len: DEFAULT_CONFLICT_MARKER_LEN,
+    };
+    let materialized =
+        materialize_conflict_string_with_options(store, path, &conflict, diff3_options);
+    insta::assert_snapshot!(materialized, @r###"
+    line 1
+    <<<<<<<
+    shared 1
+    left
+    shared 2
+    |||||||
+    line 2
+    =======
+    shared 1
+    right
+    shared 2
+    >>>>>>>
+    line 3
+    "###);
+    insta::assert_debug_snapshot!(
+        parse_conflict(materialized.as_bytes(), 2, DEFAULT_CONFLICT_MARKER_LEN),
+        @r###"
+    Some(
+        [
+            Resolved(
+                "line 1\n",
+            ),
+            Conflicted(
+                [
+                    "shared 1\nleft\nshared 2\n",
+                    "line 2\n",
+                    "shared 1\nright\nshared 2\n",
+                ],
+            ),
+            Resolved(
+                "line 3\n",
+            ),
+        ],
+    )
+    "###);
+
+    // Lines added by both sides are moved out of the conflict
+    let zdiff3_options = ConflictMarkerOptions {
+        style: ConflictMarkerStyle::Zdiff3,
+        marker_len: DEFAULT_CONFLICT_MARKER_LEN,
+    };
+    let materialized =
+        materialize_conflict_string_with_options(store, path, &conflict, zdiff3_options);
+    insta::assert_snapshot!(materialized, @r###"
+    line 1
+    shared 1
+    <<<<<<<
+    left
+    |||||||
+    line 2
+    =======
+    right
+    >>>>>>>
+    shared 2
+    line 3
+    "###);
+    insta::assert_debug_snapshot!(
+        parse_conflict(materialized.as_bytes(), 2, DEFAULT_CONFLICT_MARKER_LEN),
+        @r###"
+    Some(
+        [
+            Resolved(
+                "line 1\nshared 1\n",
+            ),
+            Conflicted(
+                [
+                    "left\n",
+                    "line 2\n",
+                    "right\n",
+                ],
+            ),
+            Resolved(
+                "shared 2\nline 3\n",
+            ),
+        ],
+    )
+    "###);
+
+    // Conflicts with more than two sides fall back to the diff style
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_id.clone()), Some(base_id.clone())],
+        vec![
+            Some(left_id.clone()),
+            Some(right_id.clone()),
+            Some(left_id.clone()),
+        ],
+    );
+    insta::assert_snapshot!(
+        materialize_conflict_string_with_options(store, path, &conflict, diff3_options),
+        @r###"
+    line 1
+    <<<<<<<
+    %%%%%%%
+    -line 2
+    +shared 1
+    +left
+    +shared 2
+    +++++++
+    shared 1
+    right
+    shared 2
+    %%%%%%%
+    -line 2
+    +shared 1
+    +left
+    +shared 2
+    >>>>>>>
+    line 3
+    "###);
+}
+
+#[test]
+fn test_materialize_parse_marker_len() {
+    let test_repo = TestRepo::init();
+    let store = test_repo.repo.store();
+
+    let path = RepoPath::from_internal_string("file");
+    let base_id = testutils::write_file(store, path, "<<<<<<<\nbase\n");
+    let left_id = testutils::write_file(store, path, "<<<<<<<\nleft\n");
+    let right_id = testutils::write_file(store, path, "<<<<<<<\nright\n");
+    let conflict = Merge::from_removes_adds(
+        vec![Some(base_id.clone())],
+        vec![Some(left_id.clone()), Some(right_id.clone())],
+    );
+    let options = ConflictMarkerOptions {
+        style: ConflictMarkerStyle::Diff,
+        marker_len: 9,
+    };
+    let materialized = materialize_conflict_string_with_options(store, path, &conflict, options);
+    insta::assert_snapshot!(materialized, @r###"
+    <<<<<<<
+    <<<<<<<<<
+    %%%%%%%%%
+    -base
+    +left
+    +++++++++
+    right
+    >>>>>>>>>
+    "###);
+    // Markers of other lengths are treated as content
+    assert_eq!(
+        parse_conflict(materialized.as_bytes(), 2, DEFAULT_CONFLICT_MARKER_LEN),
+        None
+    );
+    insta::assert_debug_snapshot!(parse_conflict(materialized.as_bytes(), 2, 9), @r###"
+    Some(
+        [
+            Resolved(
+                "<<<<<<<\n",
+            ),
+            Conflicted(
+                [
+                    "left\n",
+                    "base\n",
+                    "right\n",
+                ],
+            ),
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn test_parse_conflict_git_style_with_labels() {
+    insta::assert_debug_snapshot!(
+        parse_conflict(indoc! {b"
+            line 1
+            <<<<<<< ours
+            left
+            ||||||| base
+            base
+            =======
+            right
+            >>>>>>> theirs
+            line 3
+            "},
+            2,
+            DEFAULT_CONFLICT_MARKER_LEN
+        ),
+        @r###"
+    Some(
+        [
+            Resolved(
+                "line 1\n",
+            ),
+            Conflicted(
+                [
+                    "left\n",
+                    "base\n",
+                    "right\n",
+                ],
+            ),
+            Resolved(
+                "line 3\n",
+            ),
+        ],
+    )
+    "###);
+    // Without the base, the conflict can't be reconstructed
+    assert_eq!(
+        parse_conflict(
+            indoc! {b"
+            <<<<<<< ours
+            left
+            =======
+            right
+            >>>>>>> theirs
+            "},
+            2,
+            DEFAULT_CONFLICT_MARKER_LEN
+        ),
+        None
+    );
+}
+
 #[test]
 fn test_materialize_conflict_modify_delete() {
     let test_repo = TestRepo::init();
@@ -500,7 +765,8 @@ line 3
 line 4
 line 5
 "},
-            2
+            2,
+            DEFAULT_CONFLICT_MARKER_LEN
         ),
         None
     )
@@ -509,7 +775,7 @@ line 5
 #[test]
 fn test_parse_conflict_simple() {
     insta::assert_debug_snapshot!(
-        parse_conflict(indoc! {b"
+            parse_conflict(indoc! {b"
             line 1
             <<<<<<<
             %%%%%%%
@@ -522,9 +788,10 @@ fn test_parse_conflict_simple() {
             >>>>>>>
             line 5
             "},
-            2
-        ),
-        @r###"
+                2,
+                DEFAULT_CONFLICT_MARKER_LEN
+    ),
+            @r###"
     Some(
         [
             Resolved(
@@ -543,14 +810,14 @@ fn test_parse_conflict_simple() {
         ],
     )
     "###
-    )
+        )
 }
 
 #[test]
 fn test_parse_conflict_multi_way() {
     insta::assert_debug_snapshot!(
-        parse_conflict(
-            indoc! {b"
+            parse_conflict(
+                indoc! {b"
                 line 1
                 <<<<<<<
                 %%%%%%%
@@ -568,9 +835,10 @@ fn test_parse_conflict_multi_way() {
                 >>>>>>>
                 line 5
                 "},
-            3
-        ),
-        @r###"
+                3,
+                DEFAULT_CONFLICT_MARKER_LEN
+    ),
+            @r###"
     Some(
         [
             Resolved(
@@ -591,7 +859,7 @@ fn test_parse_conflict_multi_way() {
         ],
     )
     "###
-    )
+        )
 }
 
 #[test]
@@ -611,7 +879,8 @@ fn test_parse_conflict_different_wrong_arity() {
             >>>>>>>
             line 5
             "},
-            3
+            3,
+            DEFAULT_CONFLICT_MARKER_LEN
         ),
         None
     )
@@ -634,7 +903,8 @@ fn test_parse_conflict_malformed_marker() {
             >>>>>>>
             line 5
             "},
-            2
+            2,
+            DEFAULT_CONFLICT_MARKER_LEN
         ),
         None
     )
@@ -658,7 +928,8 @@ fn test_parse_conflict_malformed_diff() {
             >>>>>>>
             line 5
             "},
-            2
+            2,
+            DEFAULT_CONFLICT_MARKER_LEN
         ),
         None
     )
@@ -682,9 +953,15 @@ fn test_update_conflict_from_content() {
     // old conflict id back.
     let materialized = materialize_conflict_string(store, path, &conflict);
     let parse = |content| {
-        update_from_content(&conflict, store, path, content)
-            .block_on()
-            .unwrap()
+        update_from_content(
+            &conflict,
+            store,
+            path,
+            content,
+            ConflictMarkerOptions::default(),
+        )
+        .block_on()
+        .unwrap()
     };
     assert_eq!(parse(materialized.as_bytes()), conflict);
 
@@ -731,9 +1008,15 @@ fn test_update_conflict_from_content_modify_delete() {
     // old conflict id back.
     let materialized = materialize_conflict_string(store, path, &conflict);
     let parse = |content| {
-        update_from_content(&conflict, store, path, content)
-            .block_on()
-            .unwrap()
+        update_from_content(
+            &conflict,
+            store,
+            path,
+            content,
+            ConflictMarkerOptions::default(),
+        )
+        .block_on()
+        .unwrap()
     };
     assert_eq!(parse(materialized.as_bytes()), conflict);
 
@@ -763,9 +1046,23 @@ fn materialize_conflict_string(
     store: &Store,
     path: &RepoPath,
     conflict: &Merge<Option<FileId>>,
+) -> String {
+    materialize_conflict_string_with_options(
+        store,
+        path,
+        conflict,
+        ConflictMarkerOptions::default(),
+    )
+}
+
+fn materialize_conflict_string_with_options(
+    store: &Store,
+    path: &RepoPath,
+    conflict: &Merge<Option<FileId>>,
+    options: ConflictMarkerOptions,
 ) -> String {
     let mut result: Vec<u8> = vec![];
     let contents = extract_as_single_hunk(conflict, store, path).block_on();
-    materialize_merge_result(&contents, &mut result).unwrap();
+    materialize_merge_result(&contents, options, &mut result).unwrap();
     String::from_utf8(result).unwrap()
 }
@@ -17,8 +17,8 @@ use jj_lib::commit::Commit;
 use jj_lib::default_index::revset_engine::{evaluate, RevsetImpl};
 use jj_lib::default_index::DefaultReadonlyIndex;
 use jj_lib::repo::{ReadonlyRepo, Repo as _};
-use jj_lib::revset::ResolvedExpression;
-use jj_lib::revset_graph::RevsetGraphEdge;
+use jj_lib::revset::{ResolvedExpression, GENERATION_RANGE_FULL};
+use jj_lib::revset_graph::{RevsetGraphEdge, TopoGroupedRevsetGraphIterator};
 use test_case::test_case;
 use testutils::{CommitGraphBuilder, TestRepo};
 
@@ -314,3 +314,52 @@ fn test_graph_iterator_edge_escapes_from_(skip_transitive_edges: bool) {
     assert_eq!(commits[3].1, vec![indirect(&commit_a)]);
     assert_eq!(commits[4].1, vec![missing(&root_commit)]);
 }
+
+#[test]
+fn test_graph_iterator_stops_early() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // Tests that the graph iterator doesn't walk the whole history when only the
+    // first few nodes are consumed, which is how `jj log --limit` is evaluated.
+    let mut tx = repo.start_transaction(&settings);
+    let mut graph_builder = CommitGraphBuilder::new(&settings, tx.mut_repo());
+    let mut commits = vec![graph_builder.initial_commit()];
+    for _ in 0..50 {
+        let commit = graph_builder.commit_with_parents(&[commits.last().unwrap()]);
+        commits.push(commit);
+    }
+    let repo = tx.commit("test");
+    let index = repo
+        .readonly_index()
+        .as_any()
+        .downcast_ref::<DefaultReadonlyIndex>()
+        .unwrap();
+    let expression = ResolvedExpression::Ancestors {
+        heads: Box::new(ResolvedExpression::Commits(vec![commits
+            .last()
+            .unwrap()
+            .id()
+            .clone()])),
+        generation: GENERATION_RANGE_FULL,
+    };
+    let revset = evaluate(&expression, repo.store(), index.clone()).unwrap();
+
+    let mut num_consumed = 0;
+    let input_iter = revset.iter_graph_impl(true).inspect(|_| num_consumed += 1);
+    let emitted = TopoGroupedRevsetGraphIterator::new(input_iter)
+        .take(3)
+        .map(|(commit_id, _)| commit_id)
+        .collect_vec();
+    assert_eq!(
+        emitted,
+        commits
+            .iter()
+            .rev()
+            .take(3)
+            .map(|commit| commit.id().clone())
+            .collect_vec()
+    );
+    assert_eq!(num_consumed, 3);
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{mpsc, Arc, Barrier};
 use std::{fs, iter, thread};
@@ -25,8 +25,8 @@ use jj_lib::commit::Commit;
 use jj_lib::commit_builder::CommitBuilder;
 use jj_lib::git;
 use jj_lib::git::{
-    FailedRefExportReason, GitBranchPushTargets, GitFetchError, GitImportError, GitPushError,
-    GitRefUpdate, RefName, SubmoduleConfig,
+    FailedRefExportReason, GitBranchPushTargets, GitFetchError, GitImportError, GitNoteError,
+    GitPushError, GitRefUpdate, NotesFetchOutcome, RefName, SubmoduleConfig,
 };
 use jj_lib::git_backend::GitBackend;
 use jj_lib::object_id::ObjectId;
@@ -1733,6 +1733,82 @@ fn test_export_partial_failure() {
     );
 }
 
+#[test]
+fn test_export_many_packed_refs() {
+    // Check that the refs are exported in a single transaction, while the ones
+    // which changed in Git are still reported individually
+    let test_data = GitRepoData::create();
+    let git_repo = test_data.git_repo;
+    let mut tx = test_data.repo.start_transaction(&test_data.settings);
+    let mut_repo = tx.mut_repo();
+    let commit_a = write_random_commit(mut_repo, &test_data.settings);
+    let commit_b = write_random_commit(mut_repo, &test_data.settings);
+    let commit_c = write_random_commit(mut_repo, &test_data.settings);
+    let branch_names = (0..100).map(|i| format!("branch{i:03}")).collect_vec();
+    for name in &branch_names {
+        mut_repo.set_local_branch_target(name, RefTarget::normal(commit_a.id().clone()));
+    }
+    assert!(git::export_refs(mut_repo).unwrap().is_empty());
+
+    // Move the loose refs to the packed-refs file
+    let git_dir = git_repo.path();
+    let mut packed_refs = "# pack-refs with: peeled fully-peeled sorted \n".to_owned();
+    for name in &branch_names {
+        packed_refs.push_str(&format!("{} refs/heads/{name}\n", git_id(&commit_a)));
+        fs::remove_file(git_dir.join("refs/heads").join(name)).unwrap();
+    }
+    fs::write(git_dir.join("packed-refs"), packed_refs).unwrap();
+
+    // Modify one of the branches in Git, and all of them in jj
+    git_repo
+        .reference("refs/heads/branch000", git_id(&commit_c), true, "test")
+        .unwrap();
+    for name in &branch_names[..50] {
+        mut_repo.set_local_branch_target(name, RefTarget::normal(commit_b.id().clone()));
+    }
+    for name in &branch_names[50..] {
+        mut_repo.set_local_branch_target(name, RefTarget::absent());
+    }
+    let failed = git::export_refs(mut_repo).unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(
+        failed[0].name,
+        RefName::LocalBranch("branch000".to_string())
+    );
+    assert_matches!(failed[0].reason, FailedRefExportReason::FailedToSet(_));
+
+    assert_eq!(
+        git_repo
+            .find_reference("refs/heads/branch000")
+            .unwrap()
+            .target()
+            .unwrap(),
+        git_id(&commit_c)
+    );
+    for name in &branch_names[1..50] {
+        assert_eq!(
+            git_repo
+                .find_reference(&format!("refs/heads/{name}"))
+                .unwrap()
+                .target()
+                .unwrap(),
+            git_id(&commit_b)
+        );
+        assert_eq!(
+            mut_repo.get_git_ref(&format!("refs/heads/{name}")),
+            RefTarget::normal(commit_b.id().clone())
+        );
+    }
+    for name in &branch_names[50..] {
+        assert!(git_repo
+            .find_reference(&format!("refs/heads/{name}"))
+            .is_err());
+        assert!(mut_repo
+            .get_git_ref(&format!("refs/heads/{name}"))
+            .is_absent());
+    }
+}
+
 #[test]
 fn test_export_reexport_transitions() {
     // Test exporting after making changes on the jj side, or the git side, or both
@@ -2016,6 +2092,7 @@ fn test_fetch_empty_repo() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2042,6 +2119,7 @@ fn test_fetch_initial_commit() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2092,6 +2170,7 @@ fn test_fetch_success() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2115,6 +2194,7 @@ fn test_fetch_success() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2157,6 +2237,38 @@ fn test_fetch_success() {
     );
 }
 
+#[test]
+fn test_fetch_excluded_branches() {
+    let test_data = GitRepoData::create();
+    let git_settings = GitSettings::default();
+    let main_git_commit = empty_git_commit(&test_data.origin_repo, "refs/heads/main", &[]);
+    empty_git_commit(&test_data.origin_repo, "refs/heads/excluded", &[]);
+
+    let mut tx = test_data.repo.start_transaction(&test_data.settings);
+    git::fetch(
+        tx.mut_repo(),
+        &test_data.git_repo,
+        "origin",
+        &[StringPattern::everything()],
+        &["excluded".to_string()],
+        git::RemoteCallbacks::default(),
+        &git_settings,
+    )
+    .unwrap();
+    // The excluded branch matches the pattern, but isn't fetched
+    assert_eq!(
+        *tx.mut_repo().view().git_refs(),
+        btreemap! {
+            "refs/remotes/origin/main".to_string() =>
+                RefTarget::normal(jj_id(&main_git_commit)),
+        }
+    );
+    assert!(test_data
+        .git_repo
+        .find_reference("refs/remotes/origin/excluded")
+        .is_err());
+}
+
 #[test]
 fn test_fetch_prune_deleted_ref() {
     let test_data = GitRepoData::create();
@@ -2172,6 +2284,7 @@ fn test_fetch_prune_deleted_ref() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2191,6 +2304,7 @@ fn test_fetch_prune_deleted_ref() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2214,6 +2328,7 @@ fn test_fetch_no_default_branch() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2237,6 +2352,7 @@ fn test_fetch_no_default_branch() {
         &test_data.git_repo,
         "origin",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2258,6 +2374,7 @@ fn test_fetch_empty_refspecs() {
         &test_data.git_repo,
         "origin",
         &[],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     )
@@ -2284,6 +2401,7 @@ fn test_fetch_no_such_remote() {
         &test_data.git_repo,
         "invalid-remote",
         &[StringPattern::everything()],
+        &[],
         git::RemoteCallbacks::default(),
         &git_settings,
     );
@@ -2424,6 +2542,73 @@ fn test_push_branches_success() {
     assert!(!tx.mut_repo().has_changes());
 }
 
+#[test]
+fn test_push_branches_progress() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let setup = set_up_push_repos(&settings, &temp_dir);
+    let clone_repo = get_git_repo(&setup.jj_repo);
+    let mut tx = setup.jj_repo.start_transaction(&settings);
+
+    let targets = GitBranchPushTargets {
+        branch_updates: vec![(
+            "main".to_owned(),
+            BranchPushUpdate {
+                old_target: Some(setup.initial_commit.id().clone()),
+                new_target: Some(setup.new_commit.id().clone()),
+            },
+        )],
+        force_pushed_branches: hashset! {},
+    };
+    let mut progress = vec![];
+    let mut push_progress = |p: &git::PushProgress| {
+        progress.push((p.objects_sent, p.total_objects));
+    };
+    let mut callbacks = git::RemoteCallbacks::default();
+    callbacks.push_progress = Some(&mut push_progress);
+    let result = git::push_branches(tx.mut_repo(), &clone_repo, "origin", &targets, callbacks);
+    assert_eq!(result, Ok(()));
+    let (objects_sent, total_objects) = *progress.last().unwrap();
+    assert!(total_objects > 0);
+    assert_eq!(objects_sent, total_objects);
+}
+
+#[test]
+fn test_list_remote_branches() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let setup = set_up_push_repos(&settings, &temp_dir);
+    let clone_repo = get_git_repo(&setup.jj_repo);
+
+    let source_repo = git2::Repository::open(&setup.source_repo_dir).unwrap();
+    let feature_commit = empty_git_commit(&source_repo, "refs/heads/feature", &[]);
+    source_repo
+        .reference("refs/tags/v1", feature_commit.id(), false, "")
+        .unwrap();
+    let branches =
+        git::list_remote_branches(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap();
+    assert_eq!(
+        branches,
+        btreemap! {
+            "feature".to_owned() => jj_id(&feature_commit),
+            "main".to_owned() => setup.initial_commit.id().clone(),
+        }
+    );
+    // The remote-tracking branches aren't updated
+    assert!(clone_repo
+        .find_reference("refs/remotes/origin/feature")
+        .is_err());
+
+    assert_eq!(
+        git::list_remote_branches(
+            &clone_repo,
+            "invalid-remote",
+            git::RemoteCallbacks::default()
+        ),
+        Err(GitPushError::NoSuchRemote("invalid-remote".to_owned()))
+    );
+}
+
 #[test]
 fn test_push_branches_deletion() {
     let settings = testutils::user_settings();
@@ -2690,6 +2875,101 @@ fn test_push_updates_invalid_remote() {
     assert!(matches!(result, Err(GitPushError::NoSuchRemote(_))));
 }
 
+#[test]
+fn test_notes_read_write() {
+    let git_repo_dir = testutils::new_temp_dir();
+    let git_repo = git2::Repository::init_bare(git_repo_dir.path()).unwrap();
+    let commit1 = jj_id(&empty_git_commit(&git_repo, "refs/heads/main", &[]));
+    let commit2 = jj_id(&empty_git_commit(&git_repo, "refs/heads/other", &[]));
+    let signature = git2::Signature::now("Someone", "someone@example.com").unwrap();
+    assert_eq!(git::read_note(&git_repo, &commit1).unwrap(), None);
+    assert!(git::read_all_notes(&git_repo).unwrap().is_empty());
+
+    git::write_note(&git_repo, &commit1, "note 1", &signature, false).unwrap();
+    assert_eq!(
+        git::read_note(&git_repo, &commit1).unwrap().as_deref(),
+        Some("note 1")
+    );
+    assert_matches!(
+        git::write_note(&git_repo, &commit1, "note 1 again", &signature, false),
+        Err(GitNoteError::AlreadyExists(id)) if id == commit1
+    );
+    git::write_note(&git_repo, &commit1, "new note 1", &signature, true).unwrap();
+    git::write_note(&git_repo, &commit2, "note 2", &signature, false).unwrap();
+    assert_eq!(
+        git::read_all_notes(&git_repo).unwrap(),
+        HashMap::from([
+            (commit1.clone(), "new note 1".to_owned()),
+            (commit2.clone(), "note 2".to_owned()),
+        ])
+    );
+
+    git::remove_note(&git_repo, &commit1, &signature).unwrap();
+    assert_eq!(git::read_note(&git_repo, &commit1).unwrap(), None);
+    assert_matches!(
+        git::remove_note(&git_repo, &commit1, &signature),
+        Err(GitNoteError::NotFound(id)) if id == commit1
+    );
+}
+
+#[test]
+fn test_push_and_fetch_notes() {
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let setup = set_up_push_repos(&settings, &temp_dir);
+    let clone_repo = get_git_repo(&setup.jj_repo);
+    let source_repo = git2::Repository::open(&setup.source_repo_dir).unwrap();
+    let commit_id = setup.initial_commit.id();
+    let signature = git2::Signature::now("Someone", "someone@example.com").unwrap();
+
+    // Nothing to push or fetch yet
+    assert!(!git::push_notes(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap());
+    assert_eq!(
+        git::fetch_notes(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap(),
+        NotesFetchOutcome::Unchanged
+    );
+
+    git::write_note(&clone_repo, commit_id, "local", &signature, false).unwrap();
+    assert!(git::push_notes(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap());
+    assert_eq!(
+        git::read_note(&source_repo, commit_id).unwrap().as_deref(),
+        Some("local")
+    );
+
+    // The local notes fast-forward to the remote notes
+    git::write_note(&source_repo, commit_id, "remote", &signature, true).unwrap();
+    assert_eq!(
+        git::fetch_notes(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap(),
+        NotesFetchOutcome::Updated
+    );
+    assert_eq!(
+        git::read_note(&clone_repo, commit_id).unwrap().as_deref(),
+        Some("remote")
+    );
+
+    // The local notes are ahead of the remote notes
+    git::write_note(&clone_repo, commit_id, "local 2", &signature, true).unwrap();
+    assert_eq!(
+        git::fetch_notes(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap(),
+        NotesFetchOutcome::Unchanged
+    );
+
+    // Both changed
+    git::write_note(&source_repo, commit_id, "remote 2", &signature, true).unwrap();
+    assert_matches!(
+        git::push_notes(&clone_repo, "origin", git::RemoteCallbacks::default()),
+        Err(GitPushError::NotFastForward)
+    );
+    assert_eq!(
+        git::fetch_notes(&clone_repo, "origin", git::RemoteCallbacks::default()).unwrap(),
+        NotesFetchOutcome::Diverged("refs/notes/remotes/origin/commits".to_owned())
+    );
+    assert_eq!(
+        git::read_note(&clone_repo, commit_id).unwrap().as_deref(),
+        Some("local 2")
+    );
+}
+
 #[test]
 fn test_bulk_update_extra_on_import_refs() {
     let settings = testutils::user_settings();
@@ -17,7 +17,7 @@ use std::os::unix::fs::PermissionsExt;
 #[cfg(unix)]
 use std::os::unix::net::UnixListener;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use itertools::Itertools;
 use jj_lib::backend::{MergedTreeId, TreeId, TreeValue};
@@ -30,7 +30,8 @@ use jj_lib::op_store::{OperationId, WorkspaceId};
 use jj_lib::repo::{ReadonlyRepo, Repo};
 use jj_lib::repo_path::{RepoPath, RepoPathBuf, RepoPathComponent};
 use jj_lib::settings::UserSettings;
-use jj_lib::working_copy::{CheckoutStats, SnapshotError, SnapshotOptions};
+use jj_lib::sparse::SparsePattern;
+use jj_lib::working_copy::{CheckoutOptions, CheckoutStats, SnapshotError, SnapshotOptions};
 use jj_lib::workspace::LockedWorkspace;
 use test_case::test_case;
 use testutils::{
@@ -48,7 +49,10 @@ fn test_root() {
     let mut test_workspace = TestWorkspace::init(&settings);
 
     let wc = test_workspace.workspace.working_copy();
-    assert_eq!(wc.sparse_patterns().unwrap(), vec![RepoPathBuf::root()]);
+    assert_eq!(
+        wc.sparse_patterns().unwrap(),
+        vec![SparsePattern::Prefix(RepoPathBuf::root())]
+    );
     let new_tree = test_workspace.snapshot().unwrap();
     let repo = &test_workspace.repo;
     let wc_commit_id = repo
@@ -191,10 +195,20 @@ fn test_checkout_file_transitions(backend: TestRepoBackend) {
     let right_commit = commit_with_tree(&store, right_tree_id.clone());
 
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &left_commit)
-        .unwrap();
-    ws.check_out(repo.op_id().clone(), None, &right_commit)
-        .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &left_commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &right_commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     // Check that the working copy is clean.
     let new_tree = test_workspace.snapshot().unwrap();
@@ -279,9 +293,20 @@ fn test_conflict_subdirectory() {
     let merged_commit = commit_with_tree(repo.store(), merged_tree.id());
     let repo = &test_workspace.repo;
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit1).unwrap();
-    ws.check_out(repo.op_id().clone(), None, &merged_commit)
-        .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &merged_commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 }
 
 #[test]
@@ -295,7 +320,13 @@ fn test_tree_builder_file_directory_transition() {
     let mut check_out_tree = |tree_id: &TreeId| {
         let tree = repo.store().get_tree(RepoPath::root(), tree_id).unwrap();
         let commit = commit_with_tree(repo.store(), MergedTreeId::Legacy(tree.id().clone()));
-        ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+        ws.check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
     };
 
     let parent_path = RepoPath::from_internal_string("foo/bar");
@@ -372,7 +403,14 @@ fn test_conflicting_changes_on_disk() {
     )
     .unwrap();
 
-    let stats = ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    let stats = ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
     assert_eq!(
         stats,
         CheckoutStats {
@@ -380,6 +418,12 @@ fn test_conflicting_changes_on_disk() {
             added_files: 3,
             removed_files: 0,
             skipped_files: 3,
+            skipped_paths: vec![
+                dir_file_path.to_owned(),
+                file_dir_path.join(RepoPathComponent::new("file")),
+                file_file_path.to_owned(),
+            ],
+            backed_up_paths: vec![],
         }
     );
 
@@ -397,6 +441,83 @@ fn test_conflicting_changes_on_disk() {
     );
 }
 
+#[test]
+fn test_checkout_backup_changes_on_disk() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+    let mut ws = test_workspace.workspace;
+    let workspace_root = ws.workspace_root().clone();
+    let backup_dir = workspace_root.join(".jj").join("backup");
+
+    let modified_path = RepoPath::from_internal_string("modified");
+    let untracked_path = RepoPath::from_internal_string("dir/untracked");
+    let tree1 = create_tree(repo, &[(modified_path, "1")]);
+    let tree2 = create_tree(repo, &[(modified_path, "2"), (untracked_path, "2")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    // Modify a tracked file and add an untracked file without snapshotting them
+    std::fs::write(modified_path.to_fs_path(&workspace_root), "modified").unwrap();
+    testutils::write_working_copy_file(&workspace_root, untracked_path, "untracked");
+
+    // Without a backup directory, the changes on disk are kept
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let stats = locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(
+        stats.skipped_paths,
+        vec![untracked_path.to_owned(), modified_path.to_owned()]
+    );
+    assert_eq!(
+        std::fs::read_to_string(modified_path.to_fs_path(&workspace_root)).unwrap(),
+        "modified"
+    );
+    assert_eq!(
+        std::fs::read_to_string(untracked_path.to_fs_path(&workspace_root)).unwrap(),
+        "untracked"
+    );
+    drop(locked_ws);
+
+    // With a backup directory, they're moved there and replaced
+    let options = CheckoutOptions {
+        backup_dir: Some(backup_dir.clone()),
+        ..CheckoutOptions::empty_for_test()
+    };
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let stats = locked_ws.locked_wc().check_out(&commit2, &options).unwrap();
+    assert_eq!(stats.skipped_files, 0);
+    assert_eq!(
+        stats.backed_up_paths,
+        vec![untracked_path.to_owned(), modified_path.to_owned()]
+    );
+    assert_eq!(
+        std::fs::read_to_string(modified_path.to_fs_path(&workspace_root)).unwrap(),
+        "2"
+    );
+    assert_eq!(
+        std::fs::read_to_string(untracked_path.to_fs_path(&workspace_root)).unwrap(),
+        "2"
+    );
+    assert_eq!(
+        std::fs::read_to_string(modified_path.to_fs_path(&backup_dir)).unwrap(),
+        "modified"
+    );
+    assert_eq!(
+        std::fs::read_to_string(untracked_path.to_fs_path(&backup_dir)).unwrap(),
+        "untracked"
+    );
+}
+
 #[test]
 fn test_reset() {
     let settings = testutils::user_settings();
@@ -418,7 +539,13 @@ fn test_reset() {
 
     let ws = &mut test_workspace.workspace;
     let commit = commit_with_tree(repo.store(), tree_with_file.id());
-    ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     // Test the setup: the file should exist on disk and in the tree state.
     assert!(ignored_path.to_fs_path(&workspace_root).is_file());
@@ -470,7 +597,13 @@ fn test_checkout_discard() {
     let commit2 = commit_with_tree(repo.store(), tree2.id());
 
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit1).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
     let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
     let state_path = wc.state_path().to_path_buf();
 
@@ -481,7 +614,10 @@ fn test_checkout_discard() {
 
     // Start a checkout
     let mut locked_ws = ws.start_working_copy_mutation().unwrap();
-    locked_ws.locked_wc().check_out(&commit2).unwrap();
+    locked_ws
+        .locked_wc()
+        .check_out(&commit2, &CheckoutOptions::empty_for_test())
+        .unwrap();
     // The change should be reflected in the working copy but not saved
     assert!(!file1_path.to_fs_path(&workspace_root).is_file());
     assert!(file2_path.to_fs_path(&workspace_root).is_file());
@@ -502,6 +638,110 @@ fn test_checkout_discard() {
     assert!(!reloaded_wc.file_states().unwrap().contains_path(file2_path));
 }
 
+#[test]
+fn test_checkout_progress() {
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+
+    let file1_path = RepoPath::from_internal_string("file1");
+    let file2_path = RepoPath::from_internal_string("file2");
+
+    let tree1 = create_tree(repo, &[(file1_path, "contents")]);
+    let tree2 = create_tree(repo, &[(file2_path, "contents")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
+
+    // The callback is called for each path with the stats so far
+    let progress = Mutex::new(vec![]);
+    let callback = |path: &RepoPath, stats: &CheckoutStats| {
+        let stats = (stats.added_files, stats.removed_files);
+        progress.lock().unwrap().push((path.to_owned(), stats));
+    };
+    let options = CheckoutOptions {
+        progress: Some(&callback),
+        ..CheckoutOptions::empty_for_test()
+    };
+    ws.check_out(repo.op_id().clone(), None, &commit2, &options)
+        .unwrap();
+    assert_eq!(
+        progress.into_inner().unwrap(),
+        vec![
+            (file1_path.to_owned(), (0, 1)),
+            (file2_path.to_owned(), (1, 1)),
+        ]
+    );
+}
+
+#[test]
+fn test_checkout_with_reflinks() {
+    // Checking out files from the cache gives the same results whether or not
+    // the filesystem supports clones
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let repo = test_workspace.repo.clone();
+    let workspace_root = test_workspace.workspace.workspace_root().clone();
+
+    let file1_path = RepoPath::from_internal_string("file1");
+    let file2_path = RepoPath::from_internal_string("dir/file2");
+
+    let tree1 = create_tree(
+        &repo,
+        &[(file1_path, "contents1"), (file2_path, "contents2")],
+    );
+    let tree2 = create_tree(&repo, &[(file1_path, "modified")]);
+    let commit1 = commit_with_tree(repo.store(), tree1.id());
+    let commit2 = commit_with_tree(repo.store(), tree2.id());
+
+    let options = CheckoutOptions {
+        use_reflinks: true,
+        ..CheckoutOptions::empty_for_test()
+    };
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(repo.op_id().clone(), None, &commit1, &options)
+        .unwrap();
+    ws.check_out(repo.op_id().clone(), None, &commit2, &options)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(file1_path.to_fs_path(&workspace_root)).unwrap(),
+        "modified"
+    );
+    assert!(!file2_path.to_fs_path(&workspace_root).exists());
+
+    ws.check_out(repo.op_id().clone(), None, &commit1, &options)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(file1_path.to_fs_path(&workspace_root)).unwrap(),
+        "contents1"
+    );
+    assert_eq!(
+        std::fs::read_to_string(file2_path.to_fs_path(&workspace_root)).unwrap(),
+        "contents2"
+    );
+
+    // Modifying a checked-out file doesn't modify the cached copy
+    std::fs::write(file1_path.to_fs_path(&workspace_root), "changed on disk").unwrap();
+    test_workspace.snapshot().unwrap();
+    let ws = &mut test_workspace.workspace;
+    ws.check_out(repo.op_id().clone(), None, &commit2, &options)
+        .unwrap();
+    ws.check_out(repo.op_id().clone(), None, &commit1, &options)
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(file1_path.to_fs_path(&workspace_root)).unwrap(),
+        "contents1"
+    );
+}
+
 #[test]
 fn test_snapshot_racy_timestamps() {
     // Tests that file modifications are detected even if they happen the same
@@ -663,7 +903,13 @@ fn test_gitignores_in_ignored_dir() {
     let tree1 = create_tree(&test_workspace.repo, &[(gitignore_path, "ignored\n")]);
     let commit1 = commit_with_tree(test_workspace.repo.store(), tree1.id());
     let ws = &mut test_workspace.workspace;
-    ws.check_out(op_id.clone(), None, &commit1).unwrap();
+    ws.check_out(
+        op_id.clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     testutils::write_working_copy_file(&workspace_root, nested_gitignore_path, "!file\n");
     testutils::write_working_copy_file(&workspace_root, ignored_path, "contents");
@@ -721,7 +967,14 @@ fn test_gitignores_checkout_never_overwrites_ignored() {
     // "contents". The exiting contents ("garbage") shouldn't be replaced in the
     // working copy.
     let ws = &mut test_workspace.workspace;
-    assert!(ws.check_out(repo.op_id().clone(), None, &commit).is_ok());
+    assert!(ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test()
+        )
+        .is_ok());
 
     // Check that the old contents are in the working copy
     let path = workspace_root.join("modified");
@@ -791,7 +1044,13 @@ fn test_gitignores_ignored_directory_already_tracked() {
 
     // Check out the tree with the files in `ignored/`
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     // Make some changes inside the ignored directory and check that they are
     // detected when we snapshot. The files that are still there should not be
@@ -899,7 +1158,13 @@ fn test_gitsubmodule() {
     let tree = store.get_root_tree(&tree_id).unwrap();
     let commit = commit_with_tree(repo.store(), tree.id());
     let ws = &mut test_workspace.workspace;
-    ws.check_out(repo.op_id().clone(), None, &commit).unwrap();
+    ws.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     std::fs::create_dir(submodule_path.to_fs_path(&workspace_root)).unwrap();
 
@@ -943,7 +1208,14 @@ fn test_existing_directory_symlink() {
 
     // Checkout should fail because "parent" already exists and is a symlink.
     let ws = &mut test_workspace.workspace;
-    assert!(ws.check_out(repo.op_id().clone(), None, &commit).is_err());
+    assert!(ws
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test()
+        )
+        .is_err());
 
     // Therefore, "../escaped" shouldn't be created.
     assert!(!workspace_root.parent().unwrap().join("escaped").exists());
@@ -959,7 +1231,7 @@ fn test_fsmonitor() {
     let ws = &mut test_workspace.workspace;
     assert_eq!(
         ws.working_copy().sparse_patterns().unwrap(),
-        vec![RepoPathBuf::root()]
+        vec![SparsePattern::Prefix(RepoPathBuf::root())]
     );
 
     let foo_path = RepoPath::from_internal_string("foo");
@@ -1043,6 +1315,58 @@ fn test_fsmonitor() {
     }
 }
 
+#[test]
+fn test_snapshot_exclude_paths() {
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+    let workspace_root = test_workspace.workspace.workspace_root().clone();
+    let ws = &mut test_workspace.workspace;
+
+    let file_path = RepoPath::from_internal_string("file");
+    let excluded_path = RepoPath::from_internal_string("out");
+    let tracked_path = RepoPath::from_internal_string("out/tracked");
+    let generated_path = RepoPath::from_internal_string("out/generated");
+    testutils::write_working_copy_file(&workspace_root, file_path, "file\n");
+    testutils::write_working_copy_file(&workspace_root, tracked_path, "tracked\n");
+
+    let snapshot = |locked_ws: &mut LockedWorkspace, exclude_paths: &[&RepoPath]| {
+        let tree_id = locked_ws
+            .locked_wc()
+            .snapshot(SnapshotOptions {
+                exclude_paths: exclude_paths.iter().map(|&path| path.to_owned()).collect(),
+                ..SnapshotOptions::empty_for_test()
+            })
+            .unwrap();
+        repo.store().get_root_tree(&tree_id).unwrap()
+    };
+
+    // Track a file before its directory gets excluded
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let old_tree = snapshot(&mut locked_ws, &[]);
+    assert!(old_tree.path_value(tracked_path).is_present());
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+
+    // Changes in the excluded directory are ignored, including the deletion of
+    // tracked files
+    testutils::write_working_copy_file(&workspace_root, file_path, "modified\n");
+    testutils::write_working_copy_file(&workspace_root, generated_path, "generated\n");
+    std::fs::remove_file(tracked_path.to_fs_path(&workspace_root)).unwrap();
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let tree = snapshot(&mut locked_ws, &[excluded_path]);
+    assert_ne!(tree.path_value(file_path), old_tree.path_value(file_path));
+    assert_eq!(
+        tree.path_value(tracked_path),
+        old_tree.path_value(tracked_path)
+    );
+    assert!(tree.path_value(generated_path).is_absent());
+
+    // Once the directory isn't excluded anymore, the changes are snapshotted
+    let tree = snapshot(&mut locked_ws, &[]);
+    assert!(tree.path_value(tracked_path).is_absent());
+    assert!(tree.path_value(generated_path).is_present());
+}
+
 #[test]
 fn test_snapshot_max_new_file_size() {
     let settings = UserSettings::from_config(
@@ -18,7 +18,7 @@ use std::thread;
 use assert_matches::assert_matches;
 use jj_lib::repo::Repo;
 use jj_lib::repo_path::{RepoPath, RepoPathBuf};
-use jj_lib::working_copy::{CheckoutError, SnapshotOptions};
+use jj_lib::working_copy::{CheckoutError, CheckoutOptions, SnapshotOptions};
 use jj_lib::workspace::{default_working_copy_factories, Workspace};
 use testutils::{commit_with_tree, create_tree, write_working_copy_file, TestRepo, TestWorkspace};
 
@@ -44,7 +44,13 @@ fn test_concurrent_checkout() {
     // Check out tree1
     let ws1 = &mut test_workspace1.workspace;
     // The operation ID is not correct, but that doesn't matter for this test
-    ws1.check_out(repo.op_id().clone(), None, &commit1).unwrap();
+    ws1.check_out(
+        repo.op_id().clone(),
+        None,
+        &commit1,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     // Check out tree2 from another process (simulated by another workspace
     // instance)
@@ -55,12 +61,22 @@ fn test_concurrent_checkout() {
         &default_working_copy_factories(),
     )
     .unwrap();
-    ws2.check_out(repo.op_id().clone(), Some(&tree_id1), &commit2)
-        .unwrap();
+    ws2.check_out(
+        repo.op_id().clone(),
+        Some(&tree_id1),
+        &commit2,
+        &CheckoutOptions::empty_for_test(),
+    )
+    .unwrap();
 
     // Checking out another tree (via the first workspace instance) should now fail.
     assert_matches!(
-        ws1.check_out(repo.op_id().clone(), Some(&tree_id1), &commit3),
+        ws1.check_out(
+            repo.op_id().clone(),
+            Some(&tree_id1),
+            &commit3,
+            &CheckoutOptions::empty_for_test()
+        ),
         Err(CheckoutError::ConcurrentCheckout)
     );
 
@@ -101,7 +117,12 @@ fn test_checkout_parallel() {
     let commit = commit_with_tree(repo.store(), tree.id());
     test_workspace
         .workspace
-        .check_out(repo.op_id().clone(), None, &commit)
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
         .unwrap();
 
     thread::scope(|s| {
@@ -120,7 +141,9 @@ fn test_checkout_parallel() {
                 )
                 .unwrap();
                 // The operation ID is not correct, but that doesn't matter for this test
-                let stats = workspace.check_out(op_id, None, &commit).unwrap();
+                let stats = workspace
+                    .check_out(op_id, None, &commit, &CheckoutOptions::empty_for_test())
+                    .unwrap();
                 assert_eq!(stats.updated_files, 0);
                 assert_eq!(stats.added_files, 1);
                 assert_eq!(stats.removed_files, 1);
@@ -154,7 +177,13 @@ fn test_racy_checkout() {
     let mut num_matches = 0;
     for _ in 0..100 {
         let ws = &mut test_workspace.workspace;
-        ws.check_out(op_id.clone(), None, &commit).unwrap();
+        ws.check_out(
+            op_id.clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
         assert_eq!(
             std::fs::read(path.to_fs_path(&workspace_root)).unwrap(),
             b"1".to_vec()
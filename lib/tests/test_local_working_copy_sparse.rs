@@ -16,12 +16,16 @@ use itertools::Itertools;
 use jj_lib::local_working_copy::LocalWorkingCopy;
 use jj_lib::matchers::EverythingMatcher;
 use jj_lib::repo::Repo;
-use jj_lib::repo_path::{RepoPath, RepoPathBuf};
-use jj_lib::working_copy::{CheckoutStats, WorkingCopy};
+use jj_lib::repo_path::RepoPath;
+use jj_lib::sparse::SparsePattern;
+use jj_lib::working_copy::{CheckoutOptions, CheckoutStats, WorkingCopy};
 use testutils::{commit_with_tree, create_tree, TestWorkspace};
 
-fn to_owned_path_vec(paths: &[&RepoPath]) -> Vec<RepoPathBuf> {
-    paths.iter().map(|&path| path.to_owned()).collect()
+fn to_prefix_patterns(paths: &[&RepoPath]) -> Vec<SparsePattern> {
+    paths
+        .iter()
+        .map(|&path| SparsePattern::Prefix(path.to_owned()))
+        .collect()
 }
 
 #[test]
@@ -56,16 +60,21 @@ fn test_sparse_checkout() {
 
     test_workspace
         .workspace
-        .check_out(repo.op_id().clone(), None, &commit)
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
         .unwrap();
     let ws = &mut test_workspace.workspace;
 
     // Set sparse patterns to only dir1/
     let mut locked_ws = ws.start_working_copy_mutation().unwrap();
-    let sparse_patterns = to_owned_path_vec(&[dir1_path]);
+    let sparse_patterns = to_prefix_patterns(&[dir1_path]);
     let stats = locked_ws
         .locked_wc()
-        .set_sparse_patterns(sparse_patterns.clone())
+        .set_sparse_patterns(sparse_patterns.clone(), &CheckoutOptions::empty_for_test())
         .unwrap();
     assert_eq!(
         stats,
@@ -74,6 +83,8 @@ fn test_sparse_checkout() {
             added_files: 0,
             removed_files: 3,
             skipped_files: 0,
+            skipped_paths: vec![],
+            backed_up_paths: vec![],
         }
     );
     assert_eq!(
@@ -112,9 +123,9 @@ fn test_sparse_checkout() {
 
     // Set sparse patterns to file2, dir1/subdir1/ and dir2/
     let mut locked_wc = wc.start_mutation().unwrap();
-    let sparse_patterns = to_owned_path_vec(&[root_file1_path, dir1_subdir1_path, dir2_path]);
+    let sparse_patterns = to_prefix_patterns(&[root_file1_path, dir1_subdir1_path, dir2_path]);
     let stats = locked_wc
-        .set_sparse_patterns(sparse_patterns.clone())
+        .set_sparse_patterns(sparse_patterns.clone(), &CheckoutOptions::empty_for_test())
         .unwrap();
     assert_eq!(
         stats,
@@ -123,6 +134,8 @@ fn test_sparse_checkout() {
             added_files: 2,
             removed_files: 2,
             skipped_files: 0,
+            skipped_paths: vec![],
+            backed_up_paths: vec![],
         }
     );
     assert_eq!(locked_wc.sparse_patterns().unwrap(), sparse_patterns);
@@ -142,6 +155,85 @@ fn test_sparse_checkout() {
     );
 }
 
+#[test]
+fn test_sparse_checkout_globs_and_exclusions() {
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+    let working_copy_path = test_workspace.workspace.workspace_root().clone();
+
+    let root_readme_path = RepoPath::from_internal_string("README.md");
+    let root_file1_path = RepoPath::from_internal_string("file1");
+    let dir1_readme_path = RepoPath::from_internal_string("dir1/README.md");
+    let dir1_file1_path = RepoPath::from_internal_string("dir1/file1");
+    let dir1_subdir1_file1_path = RepoPath::from_internal_string("dir1/subdir1/file1");
+
+    let tree = create_tree(
+        repo,
+        &[
+            (root_readme_path, "contents"),
+            (root_file1_path, "contents"),
+            (dir1_readme_path, "contents"),
+            (dir1_file1_path, "contents"),
+            (dir1_subdir1_file1_path, "contents"),
+        ],
+    );
+    let commit = commit_with_tree(repo.store(), tree.id());
+    test_workspace
+        .workspace
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
+        .unwrap();
+    let ws = &mut test_workspace.workspace;
+
+    // Include dir1/ and the Markdown files, except for dir1/subdir1/
+    let mut locked_ws = ws.start_working_copy_mutation().unwrap();
+    let sparse_patterns = ["dir1", "glob:**/*.md", "!dir1/subdir1"]
+        .map(|pattern| SparsePattern::parse(pattern).unwrap())
+        .to_vec();
+    let stats = locked_ws
+        .locked_wc()
+        .set_sparse_patterns(sparse_patterns.clone(), &CheckoutOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(
+        stats,
+        CheckoutStats {
+            updated_files: 0,
+            added_files: 0,
+            removed_files: 2,
+            skipped_files: 0,
+            skipped_paths: vec![],
+            backed_up_paths: vec![],
+        }
+    );
+    assert!(root_readme_path.to_fs_path(&working_copy_path).exists());
+    assert!(!root_file1_path.to_fs_path(&working_copy_path).exists());
+    assert!(dir1_readme_path.to_fs_path(&working_copy_path).exists());
+    assert!(dir1_file1_path.to_fs_path(&working_copy_path).exists());
+    assert!(!dir1_subdir1_file1_path
+        .to_fs_path(&working_copy_path)
+        .exists());
+    locked_ws.finish(repo.op_id().clone()).unwrap();
+
+    // The patterns are preserved when the working copy is reloaded
+    let wc: &LocalWorkingCopy = ws.working_copy().as_any().downcast_ref().unwrap();
+    assert_eq!(wc.sparse_patterns().unwrap(), sparse_patterns);
+    let wc = LocalWorkingCopy::load(
+        repo.store().clone(),
+        wc.path().to_path_buf(),
+        wc.state_path().to_path_buf(),
+    );
+    assert_eq!(wc.sparse_patterns().unwrap(), sparse_patterns);
+    assert_eq!(
+        wc.file_states().unwrap().paths().collect_vec(),
+        vec![root_readme_path, dir1_readme_path, dir1_file1_path]
+    );
+}
+
 /// Test that sparse patterns are respected on commit
 #[test]
 fn test_sparse_commit() {
@@ -169,7 +261,12 @@ fn test_sparse_commit() {
     let commit = commit_with_tree(repo.store(), tree.id());
     test_workspace
         .workspace
-        .check_out(repo.op_id().clone(), None, &commit)
+        .check_out(
+            repo.op_id().clone(),
+            None,
+            &commit,
+            &CheckoutOptions::empty_for_test(),
+        )
         .unwrap();
 
     // Set sparse patterns to only dir1/
@@ -177,10 +274,10 @@ fn test_sparse_commit() {
         .workspace
         .start_working_copy_mutation()
         .unwrap();
-    let sparse_patterns = to_owned_path_vec(&[dir1_path]);
+    let sparse_patterns = to_prefix_patterns(&[dir1_path]);
     locked_ws
         .locked_wc()
-        .set_sparse_patterns(sparse_patterns)
+        .set_sparse_patterns(sparse_patterns, &CheckoutOptions::empty_for_test())
         .unwrap();
     locked_ws.finish(repo.op_id().clone()).unwrap();
 
@@ -203,10 +300,10 @@ fn test_sparse_commit() {
         .workspace
         .start_working_copy_mutation()
         .unwrap();
-    let sparse_patterns = to_owned_path_vec(&[dir1_path, dir2_path]);
+    let sparse_patterns = to_prefix_patterns(&[dir1_path, dir2_path]);
     locked_ws
         .locked_wc()
-        .set_sparse_patterns(sparse_patterns)
+        .set_sparse_patterns(sparse_patterns, &CheckoutOptions::empty_for_test())
         .unwrap();
     locked_ws.finish(op_id).unwrap();
 
@@ -236,10 +333,10 @@ fn test_sparse_commit_gitignore() {
         .workspace
         .start_working_copy_mutation()
         .unwrap();
-    let sparse_patterns = to_owned_path_vec(&[dir1_path]);
+    let sparse_patterns = to_prefix_patterns(&[dir1_path]);
     locked_ws
         .locked_wc()
-        .set_sparse_patterns(sparse_patterns)
+        .set_sparse_patterns(sparse_patterns, &CheckoutOptions::empty_for_test())
         .unwrap();
     locked_ws.finish(repo.op_id().clone()).unwrap();
 
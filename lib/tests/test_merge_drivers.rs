@@ -0,0 +1,123 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::backend::TreeValue;
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::settings::UserSettings;
+use testutils::{create_tree, read_file, TestRepo};
+
+fn settings_with_config(text: &str) -> UserSettings {
+    let config = testutils::base_config()
+        .add_source(config::File::from_str(text, config::FileFormat::Toml))
+        .build()
+        .unwrap();
+    UserSettings::from_config(config)
+}
+
+/// Returns the contents of the file at `path` in the merged tree, or `None` if
+/// it's conflicted.
+fn merge_file(test_repo: &TestRepo, path: &RepoPath, contents: [&str; 3]) -> Option<String> {
+    let repo = &test_repo.repo;
+    let [base, side1, side2] = contents.map(|content| create_tree(repo, &[(path, content)]));
+    let merged = side1.merge(&base, &side2).unwrap();
+    match merged.path_value(path).into_resolved() {
+        Ok(Some(TreeValue::File { id, .. })) => {
+            let content = read_file(repo.store(), path, &id);
+            Some(String::from_utf8(content).unwrap())
+        }
+        Ok(value) => panic!("unexpected value: {value:?}"),
+        Err(_) => None,
+    }
+}
+
+#[test]
+fn test_union_merge_driver() {
+    let settings = settings_with_config(
+        r#"
+        merge-drivers.changelog.paths = ["CHANGELOG.md"]
+        merge-drivers.changelog.program = ":union"
+        "#,
+    );
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let changelog_path = RepoPath::from_internal_string("CHANGELOG.md");
+    let other_path = RepoPath::from_internal_string("other");
+
+    assert_eq!(
+        merge_file(
+            &test_repo,
+            changelog_path,
+            ["* a\n", "* a\n* b\n", "* a\n* c\n"]
+        ),
+        Some("* a\n* b\n* c\n".to_owned())
+    );
+    // Other paths are merged as usual
+    assert_eq!(
+        merge_file(&test_repo, other_path, ["base\n", "left\n", "right\n"]),
+        None
+    );
+}
+
+#[test]
+fn test_external_merge_driver_not_run() {
+    // External drivers are run by the commands, not when merging trees, so the
+    // program doesn't even have to exist
+    let settings = settings_with_config(
+        r#"
+        merge-drivers.lock.paths = ["**/*.lock"]
+        merge-drivers.lock.program = "non-existent-merge-driver"
+        "#,
+    );
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let lock_path = RepoPath::from_internal_string("dir/Cargo.lock");
+
+    assert!(test_repo.repo.store().merge_drivers().has_external());
+    assert_eq!(
+        merge_file(&test_repo, lock_path, ["base\n", "left\n", "right\n"]),
+        None
+    );
+}
+
+#[test]
+fn test_ignore_whitespace() {
+    let settings = settings_with_config(
+        r#"
+        merge.ignore-whitespace = true
+        merge-drivers.python.paths = ["*.py"]
+        merge-drivers.python.ignore-whitespace = false
+        "#,
+    );
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let rust_path = RepoPath::from_internal_string("file.rs");
+    let python_path = RepoPath::from_internal_string("file.py");
+
+    // The whitespace change is lost, but the other change is kept
+    assert_eq!(
+        merge_file(
+            &test_repo,
+            rust_path,
+            ["f(1,2);\n", "f(1, 2);\n", "f(1,3);\n"]
+        ),
+        Some("f(1,3);\n".to_owned())
+    );
+    // The driver overrides the setting for this file
+    assert_eq!(
+        merge_file(
+            &test_repo,
+            python_path,
+            ["f(1,2)\n", "f(1, 2)\n", "f(1,3)\n"]
+        ),
+        None
+    );
+}
@@ -1326,6 +1326,33 @@ fn test_merge_simplify_result() {
     assert_eq!(merged, expected_merged);
 }
 
+/// Test that merging the same change into a conflict again doesn't add more
+/// sides to it.
+#[test]
+fn test_merge_dedup_changes() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let path1 = RepoPath::from_internal_string("dir1/file");
+    let path2 = RepoPath::from_internal_string("dir2/file");
+    let base = create_single_tree(repo, &[(path1, "base"), (path2, "base")]);
+    let side1 = create_single_tree(repo, &[(path1, "1"), (path2, "base")]);
+    let side2 = create_single_tree(repo, &[(path1, "2"), (path2, "2")]);
+    let conflict = MergedTree::new(Merge::from_removes_adds(
+        vec![base.clone()],
+        vec![side1.clone(), side2.clone()],
+    ));
+    let base_merged = MergedTree::resolved(base);
+    let side2_merged = MergedTree::resolved(side2);
+
+    let merged = conflict.merge(&base_merged, &side2_merged).unwrap();
+    assert_eq!(merged.path_value(path1), conflict.path_value(path1));
+    assert_eq!(merged.path_value(path2), side2_merged.path_value(path2));
+    let merged = side2_merged.merge(&base_merged, &conflict).unwrap();
+    assert_eq!(merged.path_value(path1).num_sides(), 2);
+    assert_eq!(merged.path_value(path2), side2_merged.path_value(path2));
+}
+
 /// Test that we simplify content-level conflicts before passing them to
 /// files::merge().
 ///
@@ -0,0 +1,279 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use itertools::Itertools as _;
+use jj_lib::backend::{CopyRecord, FileId, TreeValue};
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::rename_detection::{
+    detect_renames, merge_following_renames, DetectedRename, RenameDetectionOptions, RenameKind,
+};
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
+use testutils::{create_single_tree, create_tree, TestRepo};
+
+fn renames_between(
+    repo: &dyn Repo,
+    tree1: &MergedTree,
+    tree2: &MergedTree,
+    options: &RenameDetectionOptions,
+) -> Vec<DetectedRename> {
+    let diff = tree1
+        .diff(tree2, &EverythingMatcher)
+        .map(|(path, diff)| {
+            let (before, after) = diff.unwrap();
+            (path, before, after)
+        })
+        .collect_vec();
+    detect_renames(repo.store(), &diff, options).unwrap()
+}
+
+fn rename(
+    source: &RepoPath,
+    target: &RepoPath,
+    kind: RenameKind,
+    similarity: u32,
+) -> DetectedRename {
+    DetectedRename {
+        source: source.to_owned(),
+        target: target.to_owned(),
+        kind,
+        similarity,
+    }
+}
+
+#[test]
+fn test_detect_renames() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let exact_old = RepoPath::from_internal_string("exact_old");
+    let exact_new = RepoPath::from_internal_string("dir/exact_new");
+    let similar_old = RepoPath::from_internal_string("similar_old");
+    let similar_new = RepoPath::from_internal_string("similar_new");
+    let removed = RepoPath::from_internal_string("removed");
+    let added = RepoPath::from_internal_string("added");
+    let modified = RepoPath::from_internal_string("modified");
+    let copied = RepoPath::from_internal_string("copied");
+    let empty_old = RepoPath::from_internal_string("empty_old");
+    let empty_new = RepoPath::from_internal_string("empty_new");
+
+    let tree1 = create_tree(
+        repo,
+        &[
+            (exact_old, "exact\n"),
+            (similar_old, "a\nb\nc\nd\n"),
+            (removed, "removed\n"),
+            (modified, "1\n2\n3\n"),
+            (empty_old, ""),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (exact_new, "exact\n"),
+            (similar_new, "a\nb\nc\nx\n"),
+            (added, "added\n"),
+            (modified, "1\n2\n3\n4\n"),
+            (copied, "1\n2\n3\n"),
+            (empty_new, ""),
+        ],
+    );
+
+    let options = RenameDetectionOptions::default();
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![
+            rename(modified, copied, RenameKind::Copy, 100),
+            rename(exact_old, exact_new, RenameKind::Rename, 100),
+            rename(similar_old, similar_new, RenameKind::Rename, 75),
+        ]
+    );
+
+    let options = RenameDetectionOptions {
+        find_copies: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![
+            rename(exact_old, exact_new, RenameKind::Rename, 100),
+            rename(similar_old, similar_new, RenameKind::Rename, 75),
+        ]
+    );
+
+    let options = RenameDetectionOptions {
+        threshold: 80,
+        find_copies: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![rename(exact_old, exact_new, RenameKind::Rename, 100)]
+    );
+
+    let options = RenameDetectionOptions {
+        detect_similar: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![]
+    );
+}
+
+#[test]
+fn test_detect_renames_prefers_most_similar() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old1 = RepoPath::from_internal_string("old1");
+    let old2 = RepoPath::from_internal_string("old2");
+    let new1 = RepoPath::from_internal_string("new1");
+    let new2 = RepoPath::from_internal_string("new2");
+
+    let tree1 = create_tree(repo, &[(old1, "a\nb\nc\nd\n"), (old2, "a\nb\nx\ny\n")]);
+    let tree2 = create_tree(repo, &[(new1, "a\nb\nx\nz\n"), (new2, "a\nb\nc\nz\n")]);
+
+    let options = RenameDetectionOptions::default();
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![
+            rename(old2, new1, RenameKind::Rename, 75),
+            rename(old1, new2, RenameKind::Rename, 75),
+        ]
+    );
+}
+
+#[test]
+fn test_detect_renames_recorded() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old = RepoPath::from_internal_string("old");
+    let new = RepoPath::from_internal_string("new");
+    let unchanged = RepoPath::from_internal_string("unchanged");
+    let copied = RepoPath::from_internal_string("copied");
+    let stale = RepoPath::from_internal_string("stale");
+
+    let tree1 = create_tree(repo, &[(old, "a\nb\n"), (unchanged, "c\nd\n")]);
+    let tree2 = create_tree(
+        repo,
+        &[
+            (new, "x\ny\n"),
+            (unchanged, "c\nd\n"),
+            (copied, "c\nz\n"),
+            (stale, "c\nd\n"),
+        ],
+    );
+    let file_id = |path: &RepoPath| match tree1.path_value(path).into_resolved() {
+        Ok(Some(TreeValue::File { id, .. })) => id,
+        value => panic!("unexpected value at {path:?}: {value:?}"),
+    };
+    let copy = |source: &RepoPath, target: &RepoPath, source_file: FileId| CopyRecord {
+        target: target.to_owned(),
+        source: source.to_owned(),
+        source_file,
+    };
+
+    let options = RenameDetectionOptions {
+        copies: vec![
+            copy(old, new, file_id(old)),
+            copy(unchanged, copied, file_id(unchanged)),
+            // The source had different contents
+            copy(old, stale, file_id(unchanged)),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![
+            rename(unchanged, copied, RenameKind::Copy, 50),
+            rename(old, new, RenameKind::Rename, 0),
+        ]
+    );
+
+    // The recorded copies are paired even if similarity detection is disabled
+    let no_similar_options = RenameDetectionOptions {
+        detect_similar: false,
+        ..options.clone()
+    };
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &no_similar_options),
+        vec![
+            rename(unchanged, copied, RenameKind::Copy, 50),
+            rename(old, new, RenameKind::Rename, 0),
+        ]
+    );
+
+    // Copies aren't followed when only renames are detected
+    let options = RenameDetectionOptions {
+        find_copies: false,
+        ..options
+    };
+    assert_eq!(
+        renames_between(repo.as_ref(), &tree1, &tree2, &options),
+        vec![rename(old, new, RenameKind::Rename, 0)]
+    );
+}
+
+/// Merge a file modified on one side into the file it was renamed to on the
+/// other side.
+#[test]
+fn test_merge_renamed_file() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old_path = RepoPath::from_internal_string("old/file");
+    let new_path = RepoPath::from_internal_string("new/file");
+    let other_path = RepoPath::from_internal_string("other");
+    let base = create_single_tree(repo, &[(old_path, "a\nb\nc\nd\n"), (other_path, "x\n")]);
+    let side1 = create_single_tree(repo, &[(old_path, "a\nb\nc\nd\ne\n"), (other_path, "x\n")]);
+    let side2 = create_single_tree(repo, &[(new_path, "A\nb\nc\nd\n"), (other_path, "y\n")]);
+    let expected = create_single_tree(repo, &[(new_path, "A\nb\nc\nd\ne\n"), (other_path, "y\n")]);
+    let base_merged = MergedTree::resolved(base);
+    let side1_merged = MergedTree::resolved(side1);
+    let side2_merged = MergedTree::resolved(side2);
+    let expected_merged = MergedTree::resolved(expected);
+
+    // The rename is followed regardless of which side renamed the file
+    let merged = merge_following_renames(&side1_merged, &base_merged, &side2_merged, &[]).unwrap();
+    assert_eq!(merged, expected_merged);
+    let merged = merge_following_renames(&side2_merged, &base_merged, &side1_merged, &[]).unwrap();
+    assert_eq!(merged, expected_merged);
+
+    // The plain merge doesn't look for renames
+    let merged = side1_merged.merge(&base_merged, &side2_merged).unwrap();
+    assert!(merged.has_conflict());
+}
+
+/// A modify/delete conflict is kept if the deleted file wasn't renamed.
+#[test]
+fn test_merge_deleted_file_not_renamed() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("new");
+    let base = create_single_tree(repo, &[(old_path, "a\nb\n")]);
+    let side1 = create_single_tree(repo, &[(old_path, "a\nb\nc\n")]);
+    let side2 = create_single_tree(repo, &[(new_path, "x\ny\n")]);
+    let base_merged = MergedTree::resolved(base);
+    let side1_merged = MergedTree::resolved(side1);
+    let side2_merged = MergedTree::resolved(side2);
+
+    let merged = merge_following_renames(&side1_merged, &base_merged, &side2_merged, &[]).unwrap();
+    assert!(merged.path_value(old_path).as_resolved().is_none());
+    assert!(merged.path_value(new_path).is_present());
+}
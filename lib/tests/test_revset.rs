@@ -1698,6 +1698,44 @@ fn test_evaluate_expression_visible_heads() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_hidden() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+    let mut graph_builder = CommitGraphBuilder::new(&settings, mut_repo);
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
+    let commit4 = graph_builder.commit_with_parents(&[&commit1]);
+    assert_eq!(resolve_commit_ids(mut_repo, "hidden()"), vec![]);
+
+    // The rewritten commit and its rebased descendant are hidden, as is the
+    // abandoned commit
+    mut_repo
+        .rewrite_commit(&settings, &commit2)
+        .set_description("rewritten")
+        .write()
+        .unwrap();
+    mut_repo.record_abandoned_commit(commit4.id().clone());
+    mut_repo.rebase_descendants(&settings).unwrap();
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "hidden()"),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone()
+        ]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "hidden() & description(rewritten)"),
+        vec![]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_git_refs() {
     let settings = testutils::user_settings();
@@ -2353,6 +2391,124 @@ fn test_evaluate_expression_committer() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_author_date() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+
+    let timestamp1 = Timestamp::from_datetime(
+        chrono::DateTime::parse_from_rfc3339("2023-03-25T11:30:00Z").unwrap(),
+    );
+    let timestamp2 = Timestamp::from_datetime(
+        chrono::DateTime::parse_from_rfc3339("2023-03-25T12:30:00Z").unwrap(),
+    );
+    let timestamp3 = Timestamp::from_datetime(
+        chrono::DateTime::parse_from_rfc3339("2023-03-25T13:30:00Z").unwrap(),
+    );
+    let root_commit = repo.store().root_commit();
+    let mut create_commit = |author_timestamp: &Timestamp| {
+        let committer_timestamp = timestamp3.clone();
+        create_random_commit(mut_repo, &settings)
+            .set_parents(vec![root_commit.id().clone()])
+            .set_author(Signature {
+                name: "name1".to_string(),
+                email: "email1".to_string(),
+                timestamp: author_timestamp.clone(),
+            })
+            .set_committer(Signature {
+                name: "name1".to_string(),
+                email: "email1".to_string(),
+                timestamp: committer_timestamp,
+            })
+            .write()
+            .unwrap()
+    };
+    let commit1 = create_commit(&timestamp1);
+    let commit2 = create_commit(&timestamp2);
+    let commit3 = create_commit(&timestamp3);
+
+    // Can find multiple matches
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(after:\"2023-03-25T12:00:00Z\")"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    // The lower bound is inclusive, the upper bound is exclusive
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(after:\"2023-03-25T12:30:00Z\")"),
+        vec![commit3.id().clone(), commit2.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "author_date(before:\"2023-03-25T12:30:00Z\")"),
+        vec![commit1.id().clone(), root_commit.id().clone()]
+    );
+    // Can be combined to select a range
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "author_date(after:\"2023-03-25T12:00:00Z\") & \
+             author_date(before:\"2023-03-25T13:00:00Z\")"
+        ),
+        vec![commit2.id().clone()]
+    );
+    // Only the author date is matched
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(before:\"2023-03-25T13:00:00Z\")"),
+        vec![root_commit.id().clone()]
+    );
+}
+
+#[test]
+fn test_evaluate_expression_committer_date() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+
+    let timestamp1 = Timestamp::from_datetime(
+        chrono::DateTime::parse_from_rfc3339("2023-03-25T11:30:00Z").unwrap(),
+    );
+    let timestamp2 = Timestamp::from_datetime(
+        chrono::DateTime::parse_from_rfc3339("2023-03-25T12:30:00+01:00").unwrap(),
+    );
+    let root_commit = repo.store().root_commit();
+    let mut create_commit = |committer_timestamp: &Timestamp| {
+        create_random_commit(mut_repo, &settings)
+            .set_parents(vec![root_commit.id().clone()])
+            .set_committer(Signature {
+                name: "name1".to_string(),
+                email: "email1".to_string(),
+                timestamp: committer_timestamp.clone(),
+            })
+            .write()
+            .unwrap()
+    };
+    let commit1 = create_commit(&timestamp1);
+    let commit2 = create_commit(&timestamp2);
+
+    // Time zones are taken into account
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(after:\"2023-03-25T11:30:00Z\")"),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        resolve_commit_ids(mut_repo, "committer_date(after:\"2023-03-25T11:30:01Z\")"),
+        vec![]
+    );
+    assert_eq!(
+        resolve_commit_ids(
+            mut_repo,
+            "committer_date(before:\"2023-03-25T12:00:00+01:00\")"
+        ),
+        vec![root_commit.id().clone()]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_union() {
     let settings = testutils::user_settings();
@@ -2720,6 +2876,152 @@ fn test_evaluate_expression_file() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_diff_contains() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.mut_repo();
+
+    let empty_clean_inserted_deleted =
+        RepoPath::from_internal_string("empty_clean_inserted_deleted");
+    let blank_clean_inserted_clean = RepoPath::from_internal_string("blank_clean_inserted_clean");
+    let noeol_modified_modified_clean =
+        RepoPath::from_internal_string("noeol_modified_modified_clean");
+    let normal_inserted_modified_removed =
+        RepoPath::from_internal_string("normal_inserted_modified_removed");
+    let tree1 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, ""),
+            (blank_clean_inserted_clean, "\n"),
+            (noeol_modified_modified_clean, "1"),
+            (normal_inserted_modified_removed, "1\n"),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, ""),
+            (blank_clean_inserted_clean, "\n"),
+            (noeol_modified_modified_clean, "2"),
+            (normal_inserted_modified_removed, "1\n2\n"),
+        ],
+    );
+    let tree3 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, "3"),
+            (blank_clean_inserted_clean, "\n3\n"),
+            (noeol_modified_modified_clean, "2 3"),
+            (normal_inserted_modified_removed, "1 3\n2\n"),
+        ],
+    );
+    let tree4 = create_tree(
+        repo,
+        &[
+            (empty_clean_inserted_deleted, ""),
+            (blank_clean_inserted_clean, "\n3\n"),
+            (noeol_modified_modified_clean, "2 3"),
+            // normal_inserted_modified_removed
+        ],
+    );
+    let commit1 = mut_repo
+        .new_commit(
+            &settings,
+            vec![repo.store().root_commit_id().clone()],
+            tree1.id(),
+        )
+        .write()
+        .unwrap();
+    let commit2 = mut_repo
+        .new_commit(&settings, vec![commit1.id().clone()], tree2.id())
+        .write()
+        .unwrap();
+    let commit3 = mut_repo
+        .new_commit(&settings, vec![commit2.id().clone()], tree3.id())
+        .write()
+        .unwrap();
+    let commit4 = mut_repo
+        .new_commit(&settings, vec![commit3.id().clone()], tree4.id())
+        .write()
+        .unwrap();
+
+    let query = |revset_str: &str| {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            revset_str,
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    // should match both inserted and deleted lines
+    assert_eq!(
+        query(r#"diff_contains("2")"#),
+        vec![
+            commit4.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+        ]
+    );
+    assert_eq!(
+        query(r#"diff_contains("3")"#),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+    assert_eq!(query(r#"diff_contains("2 3")"#), vec![commit3.id().clone()]);
+    assert_eq!(
+        query(r#"diff_contains("1 3")"#),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+
+    // should match line with eol
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"1", {normal_inserted_modified_removed:?})"#,
+            normal_inserted_modified_removed =
+                normal_inserted_modified_removed.as_internal_file_string(),
+        )),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+
+    // should match line without eol
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"1", {noeol_modified_modified_clean:?})"#,
+            noeol_modified_modified_clean = noeol_modified_modified_clean.as_internal_file_string(),
+        )),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+
+    // exact:'' should match blank line
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"", {empty_clean_inserted_deleted:?})"#,
+            empty_clean_inserted_deleted = empty_clean_inserted_deleted.as_internal_file_string(),
+        )),
+        vec![]
+    );
+    assert_eq!(
+        query(&format!(
+            r#"diff_contains(exact:"", {blank_clean_inserted_clean:?})"#,
+            blank_clean_inserted_clean = blank_clean_inserted_clean.as_internal_file_string(),
+        )),
+        vec![commit1.id().clone()]
+    );
+
+    // regex pattern
+    assert_eq!(
+        query(r#"diff_contains(regex:"^[0-9] [0-9]$")"#),
+        vec![commit4.id().clone(), commit3.id().clone()]
+    );
+
+    // the files argument is optional outside of a workspace
+    assert!(resolve_commit_ids(mut_repo, r#"diff_contains("1")"#).contains(commit1.id()));
+}
+
 #[test]
 fn test_evaluate_expression_conflict() {
     let settings = testutils::user_settings();
@@ -1591,6 +1591,7 @@ fn test_empty_commit_option(empty_behavior: EmptyBehaviour) {
             RebaseOptions {
                 empty: empty_behavior.clone(),
                 simplify_ancestor_merge: true,
+                follow_renames: false,
             },
         )
         .unwrap();
@@ -1725,6 +1726,7 @@ fn test_rebase_abandoning_empty() {
     let rebase_options = RebaseOptions {
         empty: EmptyBehaviour::AbandonAllEmpty,
         simplify_ancestor_merge: true,
+        follow_renames: false,
     };
     rebase_commit_with_options(
         &settings,
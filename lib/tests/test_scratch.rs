@@ -0,0 +1,153 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::scratch::ScratchPool;
+use jj_lib::working_copy::CheckoutOptions;
+use testutils::{create_tree, TestRepo};
+
+#[test]
+fn test_scratch_pool_reuse() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let temp_dir = testutils::new_temp_dir();
+    let pool = ScratchPool::new(repo.store().clone(), temp_dir.path().join("scratch"), 1);
+    let options = CheckoutOptions::empty_for_test();
+
+    let file1_path = RepoPath::from_internal_string("file1");
+    let file2_path = RepoPath::from_internal_string("dir/file2");
+    let gitignore_path = RepoPath::from_internal_string(".gitignore");
+    let tree1 = create_tree(
+        repo,
+        &[
+            (file1_path, "1"),
+            (file2_path, "2"),
+            (gitignore_path, "/target\n"),
+        ],
+    );
+    let tree2 = create_tree(
+        repo,
+        &[
+            (file1_path, "1"),
+            (file2_path, "modified"),
+            (gitignore_path, "/target\n"),
+        ],
+    );
+    let tree3 = create_tree(repo, &[(file1_path, "other")]);
+
+    let scratch = pool.check_out(&tree1, &options).unwrap();
+    let scratch_path = scratch.working_copy_path().to_owned();
+    assert_eq!(fs::read_to_string(scratch_path.join("file1")).unwrap(), "1");
+    assert_eq!(
+        fs::read_to_string(scratch_path.join("dir/file2")).unwrap(),
+        "2"
+    );
+    // Changes made by a previous user to the tracked files are discarded, but
+    // untracked files are kept
+    fs::write(scratch_path.join("file1"), "changed").unwrap();
+    fs::write(scratch_path.join("untracked"), "output").unwrap();
+    fs::write(scratch_path.join("target"), "output").unwrap();
+    drop(scratch);
+
+    let scratch = pool.check_out(&tree2, &options).unwrap();
+    assert_eq!(scratch.working_copy_path(), scratch_path);
+    assert_eq!(fs::read_to_string(scratch_path.join("file1")).unwrap(), "1");
+    assert_eq!(
+        fs::read_to_string(scratch_path.join("dir/file2")).unwrap(),
+        "modified"
+    );
+    assert!(scratch_path.join("untracked").exists());
+    assert!(scratch_path.join("target").exists());
+
+    // Another scratch working copy is created while the first one is in use
+    let other_scratch = pool.check_out(&tree3, &options).unwrap();
+    let other_scratch_path = other_scratch.working_copy_path().to_owned();
+    assert_ne!(other_scratch_path, scratch_path);
+    assert_eq!(
+        fs::read_to_string(other_scratch_path.join("file1")).unwrap(),
+        "other"
+    );
+    assert!(!other_scratch_path.join("dir").exists());
+
+    // The least recently used one is evicted when both are free
+    drop(scratch);
+    drop(other_scratch);
+    assert!(!scratch_path.exists());
+    assert!(other_scratch_path.exists());
+
+    assert_eq!(pool.clean().unwrap(), 1);
+    assert!(!other_scratch_path.exists());
+}
+
+#[test]
+fn test_scratch_pool_most_similar() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let temp_dir = testutils::new_temp_dir();
+    let pool = ScratchPool::new(repo.store().clone(), temp_dir.path().join("scratch"), 2);
+    let options = CheckoutOptions::empty_for_test();
+
+    let path_a = RepoPath::from_internal_string("a");
+    let path_b = RepoPath::from_internal_string("b");
+    let path_c = RepoPath::from_internal_string("c");
+    let tree1 = create_tree(repo, &[(path_a, "a"), (path_b, "b"), (path_c, "c")]);
+    let tree2 = create_tree(repo, &[(path_a, "2")]);
+    let tree3 = create_tree(repo, &[(path_a, "a"), (path_b, "b"), (path_c, "3")]);
+
+    let scratch1 = pool.check_out(&tree1, &options).unwrap();
+    let scratch2 = pool.check_out(&tree2, &options).unwrap();
+    let path1 = scratch1.working_copy_path().to_owned();
+    drop(scratch1);
+    drop(scratch2);
+
+    // The working copy of tree1 is reused, although it was used less recently
+    let scratch3 = pool.check_out(&tree3, &options).unwrap();
+    assert_eq!(scratch3.working_copy_path(), path1);
+    assert_eq!(fs::read_to_string(path1.join("c")).unwrap(), "3");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_scratch_pool_killed_user() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let temp_dir = testutils::new_temp_dir();
+    let pool = ScratchPool::new(repo.store().clone(), temp_dir.path().join("scratch"), 1);
+    let options = CheckoutOptions::empty_for_test();
+
+    let path = RepoPath::from_internal_string("file");
+    let tree1 = create_tree(repo, &[(path, "1")]);
+    let tree2 = create_tree(repo, &[(path, "2")]);
+
+    let scratch = pool.check_out(&tree1, &options).unwrap();
+    let scratch_path = scratch.working_copy_path().to_owned();
+    fs::remove_file(scratch_path.join("file")).unwrap();
+    drop(scratch);
+    // A user which was killed leaves the lock file behind, but its lock isn't
+    // held anymore
+    let lock_path = scratch_path.parent().unwrap().join("lock");
+    fs::write(&lock_path, "").unwrap();
+
+    let scratch = pool.check_out(&tree2, &options).unwrap();
+    assert_eq!(scratch.working_copy_path(), scratch_path);
+    assert_eq!(fs::read_to_string(scratch_path.join("file")).unwrap(), "2");
+    drop(scratch);
+
+    fs::write(&lock_path, "").unwrap();
+    assert_eq!(pool.clean().unwrap(), 1);
+    assert!(!scratch_path.exists());
+}
@@ -0,0 +1,119 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, slice};
+
+use assert_matches::assert_matches;
+use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::session::{SessionError, WorkspaceSession};
+use testutils::{TestRepoBackend, TestWorkspace};
+
+#[test]
+fn test_session_load_and_query() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init_with_backend(&settings, TestRepoBackend::Git);
+    let workspace_root = test_workspace.workspace.workspace_root().clone();
+
+    let session = WorkspaceSession::load(&settings, &workspace_root).unwrap();
+    assert_eq!(session.repo().op_id(), test_workspace.repo.op_id());
+    let wc_commit = session.working_copy_commit().unwrap().unwrap();
+    assert_eq!(session.resolve_single_commit("@").unwrap(), wc_commit);
+    assert_eq!(
+        session.evaluate_revset("all()").unwrap(),
+        vec![wc_commit.clone(), session.repo().store().root_commit()]
+    );
+    assert_eq!(session.commit(wc_commit.id()).unwrap(), wc_commit);
+
+    assert_matches!(
+        session.evaluate_revset("all(("),
+        Err(SessionError::RevsetParse(_))
+    );
+    assert_matches!(
+        session.evaluate_revset("nonexistent"),
+        Err(SessionError::RevsetResolution(_))
+    );
+    assert_matches!(
+        session.resolve_single_commit("none()"),
+        Err(SessionError::NotSingleRevision { count: 0, .. })
+    );
+}
+
+#[test]
+fn test_session_mutations() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init(&settings);
+    let workspace_root = test_workspace.workspace.workspace_root().clone();
+    let mut session =
+        WorkspaceSession::from_workspace(&settings, test_workspace.workspace).unwrap();
+    let path = RepoPath::from_internal_string("file");
+
+    // Changes in the working copy are recorded by the snapshot
+    fs::write(workspace_root.join("file"), "contents").unwrap();
+    session.snapshot().unwrap();
+    let first = session.working_copy_commit().unwrap().unwrap();
+    assert_eq!(
+        session.read_file(&first, path).unwrap(),
+        Some(b"contents".to_vec())
+    );
+    assert_eq!(
+        session
+            .read_file(&first, RepoPath::from_internal_string("missing"))
+            .unwrap(),
+        None
+    );
+    assert_matches!(
+        session.read_file(&first, RepoPath::root()),
+        Err(SessionError::NotAFile { .. })
+    );
+
+    // Describing the working-copy commit keeps it checked out
+    let first = session.describe(&first, "first").unwrap();
+    assert_eq!(first.description(), "first");
+    assert_eq!(session.working_copy_commit().unwrap().unwrap(), first);
+
+    // A new change becomes the working-copy commit
+    let second = session
+        .new_change(slice::from_ref(&first), "second")
+        .unwrap();
+    assert_eq!(second.parent_ids(), [first.id().clone()]);
+    assert_eq!(session.resolve_single_commit("@").unwrap(), second);
+
+    // Editing another commit updates the files on disk
+    fs::write(workspace_root.join("file2"), "contents2").unwrap();
+    session.snapshot().unwrap();
+    session.edit(&first).unwrap();
+    assert!(!workspace_root.join("file2").exists());
+
+    // Abandoning a commit rebases its descendants
+    session.abandon(&first).unwrap();
+    let second = session
+        .resolve_single_commit("description(second)")
+        .unwrap();
+    assert_eq!(
+        second.parent_ids(),
+        [session.repo().store().root_commit_id().clone()]
+    );
+    assert_eq!(session.read_file(&second, path).unwrap(), None);
+    assert_eq!(
+        session
+            .read_file(&second, RepoPath::from_internal_string("file2"))
+            .unwrap(),
+        Some(b"contents2".to_vec())
+    );
+    assert_eq!(
+        session.repo().op_id(),
+        session.workspace().working_copy().operation_id()
+    );
+}
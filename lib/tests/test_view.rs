@@ -15,7 +15,7 @@
 use std::collections::BTreeMap;
 
 use jj_lib::op_store::{BranchTarget, RefTarget, RemoteRef, RemoteRefState, WorkspaceId};
-use jj_lib::repo::Repo;
+use jj_lib::repo::{Repo, ViewConflict};
 use maplit::{btreemap, hashset};
 use test_case::test_case;
 use testutils::{
@@ -194,6 +194,88 @@ fn test_merge_views_checkout() {
     assert_eq!(repo.view().get_wc_commit_id(&ws7_id), Some(commit3.id()));
 }
 
+#[test]
+fn test_merge_views_checkout_discardable() {
+    // Tests that the empty working-copy commit of the losing side is dropped,
+    // and that the other conflicts are reported.
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // Workspace 1 gets updated in both transactions, to an empty commit in tx2.
+    // Workspace 2 gets updated in both transactions.
+    let mut initial_tx = repo.start_transaction(&settings);
+    let commit1 = write_random_commit(initial_tx.mut_repo(), &settings);
+    let ws1_id = WorkspaceId::new("ws1".to_string());
+    let ws2_id = WorkspaceId::new("ws2".to_string());
+    initial_tx
+        .mut_repo()
+        .set_wc_commit(ws1_id.clone(), commit1.id().clone())
+        .unwrap();
+    initial_tx
+        .mut_repo()
+        .set_wc_commit(ws2_id.clone(), commit1.id().clone())
+        .unwrap();
+    let repo = initial_tx.commit("test");
+
+    let mut tx1 = repo.start_transaction(&settings);
+    let commit2 = write_random_commit(tx1.mut_repo(), &settings);
+    let commit3 = write_random_commit(tx1.mut_repo(), &settings);
+    tx1.mut_repo()
+        .set_wc_commit(ws1_id.clone(), commit2.id().clone())
+        .unwrap();
+    tx1.mut_repo()
+        .set_wc_commit(ws2_id.clone(), commit3.id().clone())
+        .unwrap();
+
+    let mut tx2 = repo.start_transaction(&settings);
+    let empty_commit = tx2
+        .mut_repo()
+        .new_commit(
+            &settings,
+            vec![commit1.id().clone()],
+            commit1.tree_id().clone(),
+        )
+        .write()
+        .unwrap();
+    let commit4 = write_random_commit(tx2.mut_repo(), &settings);
+    tx2.mut_repo()
+        .set_wc_commit(ws1_id.clone(), empty_commit.id().clone())
+        .unwrap();
+    tx2.mut_repo()
+        .set_wc_commit(ws2_id.clone(), commit4.id().clone())
+        .unwrap();
+
+    let op2 = tx2.commit("tx2").operation().clone();
+    let mut tx = tx1.commit("tx1").start_transaction(&settings);
+    let conflicts = tx.merge_operation(op2).unwrap();
+    assert_eq!(
+        conflicts,
+        vec![ViewConflict::WorkingCopy {
+            workspace_id: ws2_id.clone(),
+            kept_id: commit3.id().clone(),
+            other_id: commit4.id().clone(),
+        }]
+    );
+    assert_eq!(
+        tx.repo().view().get_wc_commit_id(&ws1_id),
+        Some(commit2.id())
+    );
+    assert_eq!(
+        tx.repo().view().get_wc_commit_id(&ws2_id),
+        Some(commit3.id())
+    );
+    assert_eq!(
+        *tx.repo().view().heads(),
+        hashset! {
+            commit1.id().clone(),
+            commit2.id().clone(),
+            commit3.id().clone(),
+            commit4.id().clone(),
+        }
+    );
+}
+
 #[test]
 fn test_merge_views_branches() {
     // Tests merging of branches (by performing concurrent operations). See
@@ -18,7 +18,8 @@ use assert_matches::assert_matches;
 use jj_lib::op_store::WorkspaceId;
 use jj_lib::repo::Repo;
 use jj_lib::workspace::{
-    default_working_copy_factories, default_working_copy_factory, Workspace, WorkspaceLoadError,
+    default_working_copy_factories, default_working_copy_factory, recorded_workspace_root,
+    Workspace, WorkspaceLoadError,
 };
 use testutils::{TestRepo, TestWorkspace};
 
@@ -87,6 +88,24 @@ fn test_init_additional_workspace() {
     assert_eq!(same_workspace.workspace_root(), ws2.workspace_root());
 }
 
+// macOS file systems don't accept non-UTF-8 file names
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn test_init_non_utf8_workspace_root() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let settings = testutils::user_settings();
+    let temp_dir = testutils::new_temp_dir();
+    let workspace_root = temp_dir.path().join(OsStr::from_bytes(b"repo-\xff"));
+    std::fs::create_dir(&workspace_root).unwrap();
+    let (workspace, _repo) = Workspace::init_local(&settings, &workspace_root).unwrap();
+    assert_eq!(
+        recorded_workspace_root(workspace.repo_path(), workspace.workspace_id()).unwrap(),
+        Some(workspace_root.canonicalize().unwrap())
+    );
+}
+
 /// Test cross-thread access to a workspace, which requires it to be Send
 #[test]
 fn test_sendable() {
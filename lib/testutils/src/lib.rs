@@ -233,6 +233,7 @@ impl TestWorkspace {
         let mut locked_ws = self.workspace.start_working_copy_mutation().unwrap();
         let tree_id = locked_ws.locked_wc().snapshot(SnapshotOptions {
             max_new_file_size: self.settings.max_new_file_size().unwrap(),
+            exclude_paths: self.settings.snapshot_exclude_paths().unwrap(),
             ..SnapshotOptions::empty_for_test()
         })?;
         // arbitrary operation id
@@ -362,6 +363,7 @@ pub fn commit_with_tree(store: &Arc<Store>, tree_id: MergedTreeId) -> Commit {
         author: signature.clone(),
         committer: signature,
         secure_sig: None,
+        copies: vec![],
     };
     store.write_commit(commit, None).unwrap()
 }